@@ -1,3 +1,30 @@
+use std::fs;
+use std::path::Path;
+
+/// Write the ids of the icons bundled in public/icons/classic, so user
+/// payloads can say which ones need a fallback avatar
+fn bundled_icons() {
+    let dir = Path::new("../public/icons/classic");
+    println!("cargo:rerun-if-changed={}", dir.display());
+
+    let mut ids: Vec<u16> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter_map(|entry| {
+                    let name = entry.file_name().into_string().ok()?;
+                    name.strip_suffix(".png")?.parse().ok()
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    ids.sort_unstable();
+
+    let out = Path::new(&std::env::var("OUT_DIR").unwrap()).join("bundled_icons.rs");
+    fs::write(out, format!("const BUNDLED_ICONS: &[u16] = &{:?};\n", ids)).unwrap();
+}
+
 fn main() {
+    bundled_icons();
     tauri_build::build()
 }
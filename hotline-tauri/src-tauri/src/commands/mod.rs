@@ -1,7 +1,27 @@
 // Tauri commands - these are callable from the frontend
 
-use crate::protocol::types::Bookmark;
-use crate::protocol::tracker::TrackerClient;
+use crate::protocol::client::bandwidth::TransferLimits;
+use crate::protocol::client::board::{BoardContinuation, BoardPage};
+use crate::protocol::client::file_batch::{BatchResult, FileRef};
+use crate::protocol::client::mac_forks::{ForkMode, UploadFile};
+use crate::protocol::client::raw::{RawField, RawReply};
+use crate::protocol::client::request_policy::RequestPolicy;
+use crate::protocol::client::upload_conflict::{ConflictResolution, UploadConflict};
+use crate::protocol::client::{Account, ConnectionStats, LoginRefusal};
+use crate::protocol::tracker_registration::{TrackerAddress, TrackerRegistration};
+use crate::protocol::types::{ArticleFlavor, Bookmark, UserAccess};
+use crate::state::auto_away::AutoAwayPolicy;
+use crate::state::board_fetch::BoardFetchPolicy;
+use crate::state::bookmark_groups::BookmarkGroup;
+use crate::state::recent_servers::RecentServer;
+use crate::protocol::client::instant_message::MessagingOptions;
+use crate::state::reconnect::ReconnectPolicy;
+use crate::state::server_full::ServerFullRetryPolicy;
+use crate::state::server_refresh::{banner_data_url, RefreshOutcome, ServerRefreshPolicy};
+use crate::state::tracker_browse::TrackerBrowse;
+use crate::state::tracker_cache::{TrackerDiff, TrackerListing, TrackerSearchResult, TrackerSort};
+use crate::state::partial_downloads::DownloadOptions;
+use crate::state::upload_staging::StagingManifest;
 use crate::state::AppState;
 use tauri::State;
 use base64::engine::general_purpose::STANDARD;
@@ -9,6 +29,7 @@ use base64::Engine;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct UpdateRelease {
     pub tag_name: String,
     pub display_version: String,
@@ -34,24 +55,108 @@ struct GitHubAsset {
     name: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ConnectResult {
     pub server_id: String,
     pub tls: bool,
     pub port: u16,
+    /// Who was online at login; user events only report changes after this
+    pub users: Vec<crate::protocol::client::RosterUser>,
+}
+
+/// Why connect_to_server failed. `refusal` is set when the server turned the
+/// login down for a reason the UI can offer to resolve, like a takeover.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectError {
+    pub message: String,
+    pub refusal: Option<LoginRefusal>,
+}
+
+impl From<String> for ConnectError {
+    fn from(message: String) -> Self {
+        Self { message, refusal: None }
+    }
 }
 
+/// `username` and `user_icon_id` default to the ones in the preferences.
+/// `takeover` asks the server to disconnect another session of the same
+/// account first, when the login is refused because one is already active.
+/// `wait_if_full` keeps retrying while the server refuses logins because it
+/// is full, until a slot opens or the wait runs out.
 #[tauri::command]
 pub async fn connect_to_server(
     bookmark: Bookmark,
-    username: String,
-    user_icon_id: u16,
+    username: Option<String>,
+    user_icon_id: Option<u16>,
     auto_detect_tls: Option<bool>,
+    takeover: Option<bool>,
+    wait_if_full: Option<bool>,
     state: State<'_, AppState>,
-) -> Result<ConnectResult, String> {
+) -> Result<ConnectResult, ConnectError> {
+    let preferences = state.get_preferences().await;
+    let username = username.unwrap_or(preferences.nickname);
+    let user_icon_id = user_icon_id.unwrap_or(preferences.icon);
     println!("Command: connect_to_server to {}:{} as {}", bookmark.address, bookmark.port, username);
-    state.connect_server(bookmark, username, user_icon_id, auto_detect_tls.unwrap_or(false)).await
+    state
+        .connect_server(
+            bookmark,
+            username,
+            user_icon_id,
+            auto_detect_tls.unwrap_or(false),
+            takeover.unwrap_or(false),
+            wait_if_full.unwrap_or(false),
+        )
+        .await
+}
+
+/// A connection made from a typed address, with the bookmark made up for it
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddressConnectResult {
+    #[serde(flatten)]
+    pub connected: ConnectResult,
+    #[serde(flatten)]
+    pub address: crate::protocol::address::ServerAddress,
+}
+
+/// Parse a quick connect address like `user:pass@host:5500` without connecting
+#[tauri::command]
+pub async fn parse_server_address(address: String) -> Result<crate::protocol::address::ServerAddress, String> {
+    crate::protocol::address::parse(&address)
+}
+
+/// Parse a quick connect address and connect to it with a bookmark that
+/// isn't saved
+#[tauri::command]
+pub async fn connect_to_address(
+    address: String,
+    username: Option<String>,
+    user_icon_id: Option<u16>,
+    auto_detect_tls: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<AddressConnectResult, ConnectError> {
+    let parsed = crate::protocol::address::parse(&address)?;
+    let preferences = state.get_preferences().await;
+    let username = username.unwrap_or(preferences.nickname);
+    let user_icon_id = user_icon_id.unwrap_or(preferences.icon);
+    println!("Command: connect_to_address {}:{} as {}", parsed.bookmark.address, parsed.bookmark.port, username);
+    let auto_detect_tls = auto_detect_tls.unwrap_or(false) && !parsed.bookmark.tls;
+    let connected = state
+        .connect_server(parsed.bookmark.clone(), username, user_icon_id, auto_detect_tls, false, false)
+        .await?;
+    Ok(AddressConnectResult { connected, address: parsed })
+}
+
+/// Stop waiting for a slot on a full server
+#[tauri::command]
+pub async fn cancel_server_full_wait(
+    bookmark_id: String,
+    state: State<'_, AppState>,
+) -> Result<bool, String> {
+    println!("Command: cancel_server_full_wait {}", bookmark_id);
+    Ok(state.cancel_server_full_wait(&bookmark_id).await)
 }
 
 #[tauri::command]
@@ -63,13 +168,40 @@ pub async fn disconnect_from_server(
     state.disconnect_server(&server_id).await
 }
 
+/// Change nickname and icon live, on `server_id` or on every connected server
+#[tauri::command]
+pub async fn quit_now(state: State<'_, AppState>) -> Result<(), String> {
+    println!("Command: quit_now");
+    state.quit_now();
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn open_server_window(server_id: String, title: String, state: State<'_, AppState>) -> Result<(), String> {
+    println!("Command: open_server_window {}", server_id);
+    state.open_server_window(&server_id, &title).await
+}
+
+#[tauri::command]
+pub async fn close_server_window(server_id: String, state: State<'_, AppState>) -> Result<(), String> {
+    println!("Command: close_server_window {}", server_id);
+    state.close_server_window(&server_id)
+}
+
 #[tauri::command]
 pub async fn update_user_info(
+    server_id: Option<String>,
     username: String,
     icon_id: u16,
     state: State<'_, AppState>,
 ) -> Result<(), String> {
-    state.update_user_info_all_servers(&username, icon_id).await
+    match server_id {
+        Some(server_id) => {
+            println!("Command: update_user_info on {}: {} (icon {})", server_id, username, icon_id);
+            state.update_user_info(&server_id, &username, icon_id).await
+        }
+        None => state.update_user_info_all_servers(&username, icon_id).await,
+    }
 }
 
 #[tauri::command]
@@ -82,24 +214,295 @@ pub async fn send_chat_message(
     state.send_chat(&server_id, message).await
 }
 
+/// `quote_reply` (on by default) quotes the message being answered
 #[tauri::command]
 pub async fn send_private_message(
     server_id: String,
     user_id: u16,
     message: String,
+    quote_reply: Option<bool>,
     state: State<'_, AppState>,
 ) -> Result<(), String> {
     println!("Command: send_private_message to user {} on {}: {}", user_id, server_id, message);
-    state.send_private_message(&server_id, user_id, message).await
+    state.send_private_message(&server_id, user_id, message, quote_reply.unwrap_or(true)).await
+}
+
+#[tauri::command]
+pub async fn get_messaging_options(
+    state: State<'_, AppState>,
+) -> Result<MessagingOptions, String> {
+    Ok(state.get_messaging_options().await)
+}
+
+#[tauri::command]
+pub async fn set_messaging_options(
+    options: MessagingOptions,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    println!("Command: set_messaging_options {:?}", options);
+    state.set_messaging_options(options).await
+}
+
+#[tauri::command]
+pub async fn create_private_chat(
+    server_id: String,
+    user_ids: Vec<u16>,
+    state: State<'_, AppState>,
+) -> Result<u32, String> {
+    println!("Command: create_private_chat on {} with {:?}", server_id, user_ids);
+    state.create_private_chat(&server_id, user_ids).await
+}
+
+#[tauri::command]
+pub async fn invite_to_private_chat(
+    server_id: String,
+    chat_id: u32,
+    user_id: u16,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    println!("Command: invite_to_private_chat {} user {}", chat_id, user_id);
+    state.invite_to_private_chat(&server_id, chat_id, user_id).await
+}
+
+#[tauri::command]
+pub async fn join_private_chat(
+    server_id: String,
+    chat_id: u32,
+    state: State<'_, AppState>,
+) -> Result<crate::protocol::client::private_chat::PrivateChatInfo, String> {
+    println!("Command: join_private_chat {}", chat_id);
+    state.join_private_chat(&server_id, chat_id).await
+}
+
+#[tauri::command]
+pub async fn leave_private_chat(
+    server_id: String,
+    chat_id: u32,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    println!("Command: leave_private_chat {}", chat_id);
+    state.leave_private_chat(&server_id, chat_id).await
+}
+
+#[tauri::command]
+pub async fn reject_private_chat_invite(
+    server_id: String,
+    chat_id: u32,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    println!("Command: reject_private_chat_invite {}", chat_id);
+    state.reject_private_chat_invite(&server_id, chat_id).await
+}
+
+#[tauri::command]
+pub async fn send_private_chat(
+    server_id: String,
+    chat_id: u32,
+    message: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    println!("Command: send_private_chat {}", chat_id);
+    state.send_private_chat(&server_id, chat_id, message).await
+}
+
+#[tauri::command]
+pub async fn set_private_chat_subject(
+    server_id: String,
+    chat_id: u32,
+    subject: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    println!("Command: set_private_chat_subject {}", chat_id);
+    state.set_private_chat_subject(&server_id, chat_id, subject).await
+}
+
+/// The original name of get_pm_conversations, still registered for callers
+/// that use it
+#[tauri::command]
+pub async fn list_conversations(
+    server_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::state::conversations::ConversationSummary>, String> {
+    println!("Command: list_conversations for {:?}", server_id);
+    Ok(state.get_pm_conversations(server_id.as_deref()).await)
+}
+
+#[tauri::command]
+pub async fn get_pm_conversations(
+    server_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::state::conversations::ConversationSummary>, String> {
+    println!("Command: get_pm_conversations for {:?}", server_id);
+    Ok(state.get_pm_conversations(server_id.as_deref()).await)
+}
+
+#[tauri::command]
+pub async fn get_pm_thread(
+    server_id: String,
+    user_id: u16,
+    state: State<'_, AppState>,
+) -> Result<crate::state::conversations::Conversation, String> {
+    println!("Command: get_pm_thread for user {} on {}", user_id, server_id);
+    state.get_pm_thread(&server_id, user_id).await
+}
+
+#[tauri::command]
+pub async fn mark_conversation_read(
+    server_id: String,
+    user_id: u16,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    println!("Command: mark_conversation_read for user {} on {}", user_id, server_id);
+    state.mark_conversation_read(&server_id, user_id).await
+}
+
+#[tauri::command]
+pub async fn get_highlight_rules(
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::state::highlights::HighlightRule>, String> {
+    println!("Command: get_highlight_rules");
+    Ok(state.get_highlight_rules().await)
+}
+
+#[tauri::command]
+pub async fn set_highlight_rules(
+    rules: Vec<crate::state::highlights::HighlightRule>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    println!("Command: set_highlight_rules ({} rules)", rules.len());
+    state.set_highlight_rules(rules).await
+}
+
+#[tauri::command]
+pub async fn get_transfer_limits(
+    server_id: String,
+    state: State<'_, AppState>,
+) -> Result<TransferLimits, String> {
+    Ok(state.get_transfer_limits(&server_id).await)
+}
+
+#[tauri::command]
+pub async fn set_transfer_limits(
+    server_id: String,
+    limits: TransferLimits,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    println!("Command: set_transfer_limits for {} {:?}", server_id, limits);
+    state.set_transfer_limits(&server_id, limits).await
+}
+
+#[tauri::command]
+pub async fn get_request_policy(
+    bookmark_id: String,
+    state: State<'_, AppState>,
+) -> Result<RequestPolicy, String> {
+    Ok(state.get_request_policy(&bookmark_id).await)
+}
+
+#[tauri::command]
+pub async fn set_request_policy(
+    bookmark_id: String,
+    policy: RequestPolicy,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    println!("Command: set_request_policy for {} {:?}", bookmark_id, policy);
+    state.set_request_policy(&bookmark_id, policy).await
+}
+
+#[tauri::command]
+pub async fn get_watch_list(
+    server_id: String,
+    state: State<'_, AppState>,
+) -> Result<crate::state::watchlist::WatchPreferences, String> {
+    println!("Command: get_watch_list for {}", server_id);
+    Ok(state.get_watch_list(&server_id).await)
+}
+
+#[tauri::command]
+pub async fn set_watch_list(
+    server_id: String,
+    preferences: crate::state::watchlist::WatchPreferences,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    println!("Command: set_watch_list for {} ({} nicknames)", server_id, preferences.nicknames.len());
+    state.set_watch_list(&server_id, preferences).await
+}
+
+#[tauri::command]
+pub async fn get_preferences(state: State<'_, AppState>) -> Result<crate::state::preferences::Preferences, String> {
+    println!("Command: get_preferences");
+    Ok(state.get_preferences().await)
+}
+
+#[tauri::command]
+pub async fn set_preferences(
+    preferences: crate::state::preferences::Preferences,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    println!("Command: set_preferences {:?}", preferences);
+    state.set_preferences(preferences).await
+}
+
+#[tauri::command]
+pub async fn get_notification_prefs(
+    server_id: String,
+    state: State<'_, AppState>,
+) -> Result<crate::state::notifications::NotificationPrefs, String> {
+    println!("Command: get_notification_prefs for {}", server_id);
+    Ok(state.get_notification_prefs(&server_id).await)
+}
+
+#[tauri::command]
+pub async fn set_notification_prefs(
+    server_id: String,
+    prefs: crate::state::notifications::NotificationPrefs,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    println!("Command: set_notification_prefs for {} {:?}", server_id, prefs);
+    state.set_notification_prefs(&server_id, prefs).await
+}
+
+#[tauri::command]
+pub async fn get_mention_keywords(state: State<'_, AppState>) -> Result<Vec<String>, String> {
+    println!("Command: get_mention_keywords");
+    Ok(state.get_mention_keywords().await)
+}
+
+#[tauri::command]
+pub async fn set_mention_keywords(keywords: Vec<String>, state: State<'_, AppState>) -> Result<(), String> {
+    println!("Command: set_mention_keywords ({} keywords)", keywords.len());
+    state.set_mention_keywords(keywords).await
+}
+
+#[tauri::command]
+pub async fn set_locale(locale: String) -> Result<(), String> {
+    use crate::protocol::i18n::{self, Locale};
+
+    println!("Command: set_locale {}", locale);
+    // Unsupported languages fall back to English rather than failing
+    i18n::set_locale(Locale::from_tag(&locale).unwrap_or(Locale::En));
+    Ok(())
 }
 
 #[tauri::command]
 pub async fn get_message_board(
     server_id: String,
+    operation_id: Option<String>,
     state: State<'_, AppState>,
-) -> Result<Vec<String>, String> {
+) -> Result<BoardPage, String> {
     println!("Command: get_message_board for {}", server_id);
-    state.get_message_board(&server_id).await
+    state.get_message_board(&server_id, operation_id).await
+}
+
+/// Next page of the board, from the continuation of the previous page
+#[tauri::command]
+pub async fn get_more_message_board(
+    server_id: String,
+    continuation: BoardContinuation,
+    state: State<'_, AppState>,
+) -> Result<BoardPage, String> {
+    println!("Command: get_more_message_board for {} from {}", server_id, continuation.offset);
+    state.get_more_message_board(&server_id, continuation).await
 }
 
 #[tauri::command]
@@ -117,6 +520,22 @@ pub async fn get_bookmarks(state: State<'_, AppState>) -> Result<Vec<Bookmark>,
     state.get_bookmarks().await
 }
 
+#[tauri::command]
+pub async fn get_recent_servers(state: State<'_, AppState>) -> Result<Vec<RecentServer>, String> {
+    Ok(state.get_recent_servers().await)
+}
+
+#[tauri::command]
+pub async fn clear_recent_servers(state: State<'_, AppState>) -> Result<(), String> {
+    println!("Command: clear_recent_servers");
+    state.clear_recent_servers().await
+}
+
+#[tauri::command]
+pub async fn search_bookmarks(query: String, state: State<'_, AppState>) -> Result<Vec<Bookmark>, String> {
+    Ok(state.search_bookmarks(&query).await)
+}
+
 #[tauri::command]
 pub async fn save_bookmark(
     bookmark: Bookmark,
@@ -140,6 +559,55 @@ pub async fn reorder_bookmarks(
     state.reorder_bookmarks(bookmarks).await
 }
 
+#[tauri::command]
+pub async fn get_bookmark_groups(state: State<'_, AppState>) -> Result<Vec<BookmarkGroup>, String> {
+    Ok(state.get_bookmark_groups().await)
+}
+
+#[tauri::command]
+pub async fn create_bookmark_group(
+    name: String,
+    parent: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<BookmarkGroup, String> {
+    println!("Command: create_bookmark_group {}", name);
+    state.create_bookmark_group(&name, parent).await
+}
+
+#[tauri::command]
+pub async fn rename_bookmark_group(id: String, name: String, state: State<'_, AppState>) -> Result<(), String> {
+    println!("Command: rename_bookmark_group {} to {}", id, name);
+    state.rename_bookmark_group(&id, &name).await
+}
+
+#[tauri::command]
+pub async fn move_bookmark_group(
+    id: String,
+    parent: Option<String>,
+    index: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    println!("Command: move_bookmark_group {} into {:?}", id, parent);
+    state.move_bookmark_group(&id, parent, index).await
+}
+
+#[tauri::command]
+pub async fn delete_bookmark_group(id: String, state: State<'_, AppState>) -> Result<(), String> {
+    println!("Command: delete_bookmark_group {}", id);
+    state.delete_bookmark_group(&id).await
+}
+
+#[tauri::command]
+pub async fn move_bookmark_to_group(
+    bookmark_id: String,
+    group: Option<String>,
+    index: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    println!("Command: move_bookmark_to_group {} into {:?}", bookmark_id, group);
+    state.move_bookmark_to_group(&bookmark_id, group, index).await
+}
+
 #[tauri::command]
 pub async fn add_default_bookmarks(
     state: State<'_, AppState>,
@@ -148,90 +616,641 @@ pub async fn add_default_bookmarks(
 }
 
 #[tauri::command]
-pub async fn get_file_list(
+pub async fn get_file_list(
+    server_id: String,
+    path: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    println!("Command: get_file_list for server {} path {:?}", server_id, path);
+    state.get_file_list(&server_id, path).await
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn download_file(
+    server_id: String,
+    path: Vec<String>,
+    file_name: String,
+    file_size: u64,
+    download_folder: Option<String>,
+    fork_mode: Option<ForkMode>,
+    operation_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    println!("Command: download_file {} (size: {} bytes)", file_name, file_size);
+    let options = DownloadOptions {
+        download_folder,
+        fork_mode: fork_mode.unwrap_or_default(),
+    };
+    state.download_file(&server_id, path, file_name, file_size, options, operation_id).await
+}
+
+#[tauri::command]
+pub async fn download_folder(
+    server_id: String,
+    path: Vec<String>,
+    folder_name: String,
+    download_folder: Option<String>,
+    operation_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    println!("Command: download_folder {}", folder_name);
+    state.download_folder(&server_id, path, folder_name, download_folder, operation_id).await
+}
+
+#[tauri::command]
+pub async fn pick_download_folder() -> Result<Option<String>, String> {
+    #[cfg(any(target_os = "android", target_os = "ios"))]
+    {
+        Err("Download folder selection is not available on mobile".to_string())
+    }
+
+    #[cfg(not(any(target_os = "android", target_os = "ios")))]
+    {
+        let result = tokio::task::spawn_blocking(|| {
+            rfd::FileDialog::new()
+                .set_title("Choose Download Folder")
+                .pick_folder()
+                .map(|p| p.to_string_lossy().to_string())
+        })
+        .await
+        .map_err(|e| format!("Dialog error: {}", e))?;
+        Ok(result)
+    }
+}
+
+#[tauri::command]
+pub async fn resume_download(
+    transfer_id: String,
+    operation_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    println!("Command: resume_download {}", transfer_id);
+    state.resume_download(&transfer_id, operation_id).await
+}
+
+#[tauri::command]
+pub async fn get_partial_downloads(
+    server_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::state::partial_downloads::PartialDownload>, String> {
+    println!("Command: get_partial_downloads for {:?}", server_id);
+    Ok(state.get_partial_downloads(server_id.as_deref()).await)
+}
+
+#[tauri::command]
+pub async fn verify_download(
+    path: String,
+    expected_sha256: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<crate::state::partial_downloads::DownloadVerification, String> {
+    println!("Command: verify_download {}", path);
+    state.verify_download(&path, expected_sha256.as_deref()).await
+}
+
+#[tauri::command]
+pub async fn get_transfer_history(
+    filter: Option<crate::state::transfer_history::TransferHistoryFilter>,
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::state::transfer_history::TransferRecord>, String> {
+    println!("Command: get_transfer_history");
+    Ok(state.get_transfer_history(&filter.unwrap_or_default()).await)
+}
+
+#[tauri::command]
+pub async fn clear_transfer_history(state: State<'_, AppState>) -> Result<(), String> {
+    println!("Command: clear_transfer_history");
+    state.clear_transfer_history().await
+}
+
+#[tauri::command]
+pub async fn get_hosting_config(state: State<'_, AppState>) -> Result<crate::protocol::server::ServerConfig, String> {
+    println!("Command: get_hosting_config");
+    Ok(state.get_hosting_config().await)
+}
+
+#[tauri::command]
+pub async fn set_hosting_config(
+    config: crate::protocol::server::ServerConfig,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    // The config carries account passwords, so only the name and port are logged
+    println!("Command: set_hosting_config {} on port {}", config.name, config.port);
+    state.set_hosting_config(config).await
+}
+
+#[tauri::command]
+pub async fn start_hosting(state: State<'_, AppState>) -> Result<crate::protocol::server::HostingStatus, String> {
+    println!("Command: start_hosting");
+    state.start_hosting().await
+}
+
+#[tauri::command]
+pub async fn stop_hosting(state: State<'_, AppState>) -> Result<(), String> {
+    println!("Command: stop_hosting");
+    state.stop_hosting().await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_hosting_status(
+    state: State<'_, AppState>,
+) -> Result<Option<crate::protocol::server::HostingStatus>, String> {
+    println!("Command: get_hosting_status");
+    Ok(state.get_hosting_status().await)
+}
+
+#[tauri::command]
+pub async fn discard_partial_download(
+    transfer_id: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    println!("Command: discard_partial_download {}", transfer_id);
+    state.discard_partial_download(&transfer_id).await
+}
+
+#[tauri::command]
+pub async fn queue_download(
+    server_id: String,
+    path: Vec<String>,
+    file_name: String,
+    file_size: u64,
+    download_folder: Option<String>,
+    fork_mode: Option<ForkMode>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    println!("Command: queue_download {} ({} bytes)", file_name, file_size);
+    let options = DownloadOptions {
+        download_folder,
+        fork_mode: fork_mode.unwrap_or_default(),
+    };
+    Ok(state.queue_download(&server_id, path, file_name, file_size, options))
+}
+
+#[tauri::command]
+pub async fn queue_upload(
+    server_id: String,
+    path: Vec<String>,
+    file_name: String,
+    file_data: Vec<u8>,
+    mac_binary: Option<bool>,
+    apple_double: Option<Vec<u8>>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    println!("Command: queue_upload {} ({} bytes)", file_name, file_data.len());
+    let upload = UploadFile::from_local(file_name, file_data, mac_binary.unwrap_or(false), apple_double.as_deref())?;
+    Ok(state.queue_upload(&server_id, path, upload))
+}
+
+#[tauri::command]
+pub async fn stage_uploads(
+    paths: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<StagingManifest, String> {
+    println!("Command: stage_uploads ({} paths)", paths.len());
+    Ok(state.stage_uploads(paths).await)
+}
+
+#[tauri::command]
+pub async fn commit_staged_uploads(
+    staging_id: String,
+    server_id: String,
+    path: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<String>, String> {
+    println!("Command: commit_staged_uploads {} to {} path {:?}", staging_id, server_id, path);
+    state.commit_staged_uploads(&staging_id, &server_id, path).await
+}
+
+#[tauri::command]
+pub async fn discard_staged_uploads(
+    staging_id: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    println!("Command: discard_staged_uploads {}", staging_id);
+    state.discard_staged_uploads(&staging_id).await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn list_transfers(
+    server_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::protocol::client::transfer_queue::QueuedTransfer>, String> {
+    println!("Command: list_transfers for {:?}", server_id);
+    Ok(state.list_transfers(server_id.as_deref()))
+}
+
+#[tauri::command]
+pub async fn pause_transfer(
+    transfer_id: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    println!("Command: pause_transfer {}", transfer_id);
+    state.pause_transfer(&transfer_id).await
+}
+
+#[tauri::command]
+pub async fn resume_transfer(
+    transfer_id: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    println!("Command: resume_transfer {}", transfer_id);
+    state.resume_transfer(&transfer_id)
+}
+
+#[tauri::command]
+pub async fn cancel_transfer(
+    transfer_id: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    println!("Command: cancel_transfer {}", transfer_id);
+    state.cancel_transfer(&transfer_id).await
+}
+
+#[tauri::command]
+pub async fn reorder_transfers(
+    server_id: String,
+    transfer_ids: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    println!("Command: reorder_transfers for {}", server_id);
+    state.reorder_transfers(&server_id, transfer_ids)
+}
+
+#[tauri::command]
+pub async fn get_download_slots(
+    server_id: String,
+    state: State<'_, AppState>,
+) -> Result<crate::protocol::client::transfer_queue::DownloadSlots, String> {
+    println!("Command: get_download_slots for {}", server_id);
+    Ok(state.get_download_slots(&server_id))
+}
+
+#[tauri::command]
+pub async fn set_max_concurrent_transfers(
+    server_id: String,
+    max_concurrent: usize,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    println!("Command: set_max_concurrent_transfers {} for {}", max_concurrent, server_id);
+    state.set_max_concurrent_transfers(&server_id, max_concurrent);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn set_max_transfers_per_host(
+    limit: usize,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    println!("Command: set_max_transfers_per_host {}", limit);
+    state.set_max_transfers_per_host(limit);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn clear_finished_transfers(
+    server_id: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    println!("Command: clear_finished_transfers for {}", server_id);
+    state.clear_finished_transfers(&server_id);
+    Ok(())
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn upload_file(
+    server_id: String,
+    path: Vec<String>,
+    file_name: String,
+    file_data: Vec<u8>,
+    mac_binary: Option<bool>,
+    apple_double: Option<Vec<u8>>,
+    conflict: Option<ConflictResolution>,
+    operation_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    println!("Command: upload_file {} ({} bytes)", file_name, file_data.len());
+    let upload = UploadFile::from_local(file_name, file_data, mac_binary.unwrap_or(false), apple_double.as_deref())?;
+    state.upload_file(&server_id, path, upload, conflict, operation_id).await
+}
+
+#[tauri::command]
+pub async fn check_upload_conflict(
+    server_id: String,
+    path: Vec<String>,
+    file_name: String,
+    file_size: u64,
+    state: State<'_, AppState>,
+) -> Result<Option<UploadConflict>, String> {
+    println!("Command: check_upload_conflict {} in {:?}", file_name, path);
+    state.check_upload_conflict(&server_id, path, &file_name, file_size).await
+}
+
+#[tauri::command]
+pub async fn get_news_categories(
+    server_id: String,
+    path: Vec<String>,
+    operation_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::protocol::types::NewsCategory>, String> {
+    println!("Command: get_news_categories for {} path {:?}", server_id, path);
+    state.get_news_categories(&server_id, path, operation_id).await
+}
+
+#[tauri::command]
+pub async fn get_news_articles(
+    server_id: String,
+    path: Vec<String>,
+    operation_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::protocol::types::NewsArticle>, String> {
+    println!("Command: get_news_articles for {} path {:?}", server_id, path);
+    state.get_news_articles(&server_id, path, operation_id).await
+}
+
+#[tauri::command]
+pub async fn get_news_thread_tree(
+    server_id: String,
+    path: Vec<String>,
+    operation_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::state::news_threads::NewsThread>, String> {
+    println!("Command: get_news_thread_tree for {} path {:?}", server_id, path);
+    state.get_news_thread_tree(&server_id, path, operation_id).await
+}
+
+#[tauri::command]
+pub async fn get_news_article_data(
+    server_id: String,
+    article_id: u32,
+    path: Vec<String>,
+    operation_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    println!("Command: get_news_article_data for {} article {} path {:?}", server_id, article_id, path);
+    state.get_news_article_data(&server_id, article_id, path, operation_id).await
+}
+
+#[tauri::command]
+pub async fn get_cached_file_list(
+    server_id: String,
+    path: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<crate::state::offline::CachedView<Vec<crate::protocol::client::FileInfo>>, String> {
+    state.get_cached_file_list(&server_id, &path).await
+}
+
+#[tauri::command]
+pub async fn get_cached_news(
+    server_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::state::offline::CachedView<crate::state::news_cache::CachedContent>>, String> {
+    Ok(state.get_cached_news(&server_id).await)
+}
+
+#[tauri::command]
+pub async fn get_chat_history(
+    server_id: String,
+    limit: Option<usize>,
+    before: Option<u64>,
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::state::chat_log::ChatLogEntry>, String> {
+    Ok(state.get_chat_history(&server_id, limit, before).await)
+}
+
+#[tauri::command]
+pub async fn clear_chat_history(
+    server_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    println!("Command: clear_chat_history {:?}", server_id);
+    state.clear_chat_history(server_id.as_deref()).await
+}
+
+#[tauri::command]
+pub async fn export_chat_transcript(
+    server_name: String,
+    transcript: String,
+    destination: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    println!("Command: export_chat_transcript for {}", server_name);
+    state.export_chat_transcript(&server_name, &transcript, destination).await
+}
+
+#[tauri::command]
+pub async fn export_news(
+    server_id: String,
+    path: Vec<String>,
+    format: crate::state::news_export::NewsExportFormat,
+    destination: Option<String>,
+    operation_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    println!("Command: export_news {:?} from {} as {:?}", path, server_id, format);
+    state.export_news(&server_id, path, format, destination, operation_id).await
+}
+
+#[tauri::command]
+pub async fn get_server_full_retry_policy(
+    state: State<'_, AppState>,
+) -> Result<ServerFullRetryPolicy, String> {
+    Ok(state.get_server_full_retry_policy().await)
+}
+
+#[tauri::command]
+pub async fn set_server_full_retry_policy(
+    policy: ServerFullRetryPolicy,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    println!("Command: set_server_full_retry_policy {:?}", policy);
+    state.set_server_full_retry_policy(policy).await
+}
+
+#[tauri::command]
+pub async fn get_reconnect_policy(
+    state: State<'_, AppState>,
+) -> Result<ReconnectPolicy, String> {
+    Ok(state.get_reconnect_policy().await)
+}
+
+#[tauri::command]
+pub async fn set_reconnect_policy(
+    policy: ReconnectPolicy,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    println!("Command: set_reconnect_policy {:?}", policy);
+    state.set_reconnect_policy(policy).await
+}
+
+#[tauri::command]
+pub async fn get_server_refresh_policy(
+    state: State<'_, AppState>,
+) -> Result<ServerRefreshPolicy, String> {
+    Ok(state.get_server_refresh_policy().await)
+}
+
+#[tauri::command]
+pub async fn set_server_refresh_policy(
+    policy: ServerRefreshPolicy,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    println!("Command: set_server_refresh_policy {:?}", policy);
+    state.set_server_refresh_policy(policy).await
+}
+
+#[tauri::command]
+pub async fn get_auto_away_policy(
+    state: State<'_, AppState>,
+) -> Result<AutoAwayPolicy, String> {
+    Ok(state.get_auto_away_policy().await)
+}
+
+#[tauri::command]
+pub async fn set_auto_away_policy(
+    policy: AutoAwayPolicy,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    println!("Command: set_auto_away_policy {:?}", policy);
+    state.set_auto_away_policy(policy).await
+}
+
+#[tauri::command]
+pub async fn get_board_fetch_policy(
+    state: State<'_, AppState>,
+) -> Result<BoardFetchPolicy, String> {
+    Ok(state.get_board_fetch_policy().await)
+}
+
+#[tauri::command]
+pub async fn set_board_fetch_policy(
+    policy: BoardFetchPolicy,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    println!("Command: set_board_fetch_policy {:?}", policy);
+    state.set_board_fetch_policy(policy).await
+}
+
+/// `message` is sent back to users who message us while away
+#[tauri::command]
+pub async fn set_away(
+    server_id: String,
+    away: bool,
+    message: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    println!("Command: set_away {} on {}", away, server_id);
+    state.set_away(&server_id, away, message).await
+}
+
+#[tauri::command]
+pub async fn get_history_encryption(
+    state: State<'_, AppState>,
+) -> Result<bool, String> {
+    Ok(state.get_history_encryption().await)
+}
+
+#[tauri::command]
+pub async fn set_history_encryption(
+    enabled: bool,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    println!("Command: set_history_encryption {}", enabled);
+    state.set_history_encryption(enabled).await
+}
+
+#[tauri::command]
+pub async fn get_client_identity(
+    state: State<'_, AppState>,
+) -> Result<crate::protocol::types::ClientIdentity, String> {
+    Ok(state.get_client_identity().await)
+}
+
+#[tauri::command]
+pub async fn set_client_identity(
+    identity: crate::protocol::types::ClientIdentity,
+    state: State<'_, AppState>,
+) -> Result<crate::protocol::types::ClientIdentity, String> {
+    println!("Command: set_client_identity {:?}", identity);
+    state.set_client_identity(identity).await
+}
+
+#[tauri::command]
+pub async fn set_metrics_enabled(
+    enabled: bool,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    println!("Command: set_metrics_enabled {}", enabled);
+    state.set_metrics_enabled(enabled);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn send_raw_transaction(
     server_id: String,
-    path: Vec<String>,
+    transaction_type: u16,
+    fields: Vec<RawField>,
     state: State<'_, AppState>,
-) -> Result<(), String> {
-    println!("Command: get_file_list for server {} path {:?}", server_id, path);
-    state.get_file_list(&server_id, path).await
+) -> Result<RawReply, String> {
+    println!("Command: send_raw_transaction {} to {}", transaction_type, server_id);
+    state.send_raw_transaction(&server_id, transaction_type, fields).await
 }
 
 #[tauri::command]
-pub async fn download_file(
-    server_id: String,
-    path: Vec<String>,
-    file_name: String,
-    file_size: u32,
-    download_folder: Option<String>,
+pub async fn dump_metrics(
     state: State<'_, AppState>,
-) -> Result<String, String> {
-    println!("Command: download_file {} (size: {} bytes)", file_name, file_size);
-    state.download_file(&server_id, path, file_name, file_size, download_folder).await
+) -> Result<crate::state::metrics::MetricsSnapshot, String> {
+    Ok(state.dump_metrics().await)
 }
 
 #[tauri::command]
-pub async fn pick_download_folder() -> Result<Option<String>, String> {
-    #[cfg(any(target_os = "android", target_os = "ios"))]
-    {
-        Err("Download folder selection is not available on mobile".to_string())
-    }
-
-    #[cfg(not(any(target_os = "android", target_os = "ios")))]
-    {
-        let result = tokio::task::spawn_blocking(|| {
-            rfd::FileDialog::new()
-                .set_title("Choose Download Folder")
-                .pick_folder()
-                .map(|p| p.to_string_lossy().to_string())
-        })
-        .await
-        .map_err(|e| format!("Dialog error: {}", e))?;
-        Ok(result)
-    }
+pub async fn get_session_summary(
+    state: State<'_, AppState>,
+) -> Result<crate::state::session_stats::SessionSummary, String> {
+    Ok(state.get_session_summary())
 }
 
+/// Newest first; `server_id` narrows it to one server or tracker
 #[tauri::command]
-pub async fn upload_file(
-    server_id: String,
-    path: Vec<String>,
-    file_name: String,
-    file_data: Vec<u8>,
+pub async fn get_recent_failures(
+    server_id: Option<String>,
+    limit: Option<usize>,
     state: State<'_, AppState>,
-) -> Result<(), String> {
-    println!("Command: upload_file {} ({} bytes)", file_name, file_data.len());
-    state.upload_file(&server_id, path, file_name, file_data).await
+) -> Result<Vec<crate::state::failures::Failure>, String> {
+    Ok(state.get_recent_failures(server_id.as_deref(), limit))
 }
 
 #[tauri::command]
-pub async fn get_news_categories(
-    server_id: String,
-    path: Vec<String>,
+pub async fn generate_diagnostics(
+    consent: bool,
+    settings: Option<serde_json::Value>,
+    destination: Option<String>,
     state: State<'_, AppState>,
-) -> Result<Vec<crate::protocol::types::NewsCategory>, String> {
-    println!("Command: get_news_categories for {} path {:?}", server_id, path);
-    state.get_news_categories(&server_id, path).await
+) -> Result<String, String> {
+    println!("Command: generate_diagnostics");
+    if !consent {
+        return Err("Diagnostics require your consent".to_string());
+    }
+    state.generate_diagnostics(settings, destination).await
 }
 
 #[tauri::command]
-pub async fn get_news_articles(
-    server_id: String,
-    path: Vec<String>,
+pub async fn clear_cache(
+    server_id: Option<String>,
     state: State<'_, AppState>,
-) -> Result<Vec<crate::protocol::types::NewsArticle>, String> {
-    println!("Command: get_news_articles for {} path {:?}", server_id, path);
-    state.get_news_articles(&server_id, path).await
+) -> Result<(), String> {
+    println!("Command: clear_cache {:?}", server_id);
+    state.clear_news_cache(server_id.as_deref()).await
 }
 
 #[tauri::command]
-pub async fn get_news_article_data(
-    server_id: String,
-    article_id: u32,
-    path: Vec<String>,
+pub async fn get_cache_stats(
+    server_id: Option<String>,
     state: State<'_, AppState>,
-) -> Result<String, String> {
-    println!("Command: get_news_article_data for {} article {} path {:?}", server_id, article_id, path);
-    state.get_news_article_data(&server_id, article_id, path).await
+) -> Result<Vec<crate::state::news_cache::NewsCacheStats>, String> {
+    Ok(state.get_news_cache_stats(server_id.as_deref()).await)
 }
 
 #[tauri::command]
@@ -241,30 +1260,45 @@ pub async fn post_news_article(
     text: String,
     path: Vec<String>,
     parent_id: u32,
+    flavor: Option<ArticleFlavor>,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<Option<u32>, String> {
     println!("Command: post_news_article to {} path {:?}", server_id, path);
-    state.post_news_article(&server_id, title, text, path, parent_id).await
+    state.post_news_article(&server_id, title, text, path, parent_id, flavor.unwrap_or_default()).await
+}
+
+#[tauri::command]
+pub async fn reply_to_news_article(
+    server_id: String,
+    path: Vec<String>,
+    parent_id: u32,
+    title: Option<String>,
+    text: String,
+    flavor: Option<ArticleFlavor>,
+    state: State<'_, AppState>,
+) -> Result<Option<u32>, String> {
+    println!("Command: reply_to_news_article {} at path {:?} on {}", parent_id, path, server_id);
+    state.reply_to_news_article(&server_id, path, parent_id, title, text, flavor.unwrap_or_default()).await
 }
 
 #[tauri::command]
-pub async fn send_broadcast(
+pub async fn broadcast_message(
     server_id: String,
     message: String,
     state: State<'_, AppState>,
 ) -> Result<(), String> {
-    println!("Command: send_broadcast to {}: {}", server_id, message);
-    state.send_broadcast(&server_id, message).await
+    println!("Command: broadcast_message to {}: {}", server_id, message);
+    state.broadcast_message(&server_id, message).await
 }
 
 #[tauri::command]
-pub async fn create_folder(
+pub async fn create_remote_folder(
     server_id: String,
     path: Vec<String>,
     name: String,
     state: State<'_, AppState>,
 ) -> Result<(), String> {
-    println!("Command: create_folder '{}' at path {:?} on {}", name, path, server_id);
+    println!("Command: create_remote_folder '{}' at path {:?} on {}", name, path, server_id);
     state.create_folder(&server_id, path, name).await
 }
 
@@ -321,6 +1355,24 @@ pub async fn get_pending_agreement(
     Ok(state.get_pending_agreement(&server_id).await)
 }
 
+#[tauri::command]
+pub async fn take_connect_outcome(
+    server_id: String,
+    state: State<'_, AppState>,
+) -> Result<Option<crate::state::connect_actions::ConnectOutcome>, String> {
+    println!("Command: take_connect_outcome for {}", server_id);
+    Ok(state.take_connect_outcome(&server_id).await)
+}
+
+#[tauri::command]
+pub async fn get_agreement(
+    server_id: String,
+    state: State<'_, AppState>,
+) -> Result<Option<String>, String> {
+    println!("Command: get_agreement for {}", server_id);
+    state.get_agreement(&server_id).await
+}
+
 #[tauri::command]
 pub async fn accept_agreement(
     server_id: String,
@@ -337,42 +1389,26 @@ pub async fn download_banner(
 ) -> Result<String, String> {
     println!("Command: download_banner for {}", server_id);
     let banner_path = state.download_banner(&server_id).await?;
-    
-    // Read the file and convert to base64 data URL
+
+    // Read the file and convert to a data URL
     let file_data = std::fs::read(&banner_path)
         .map_err(|e| format!("Failed to read banner file: {}", e))?;
-    
     println!("Banner file read, {} bytes", file_data.len());
-    
-    // Detect image format from file signature
-    let mime_type = if file_data.len() >= 4 && &file_data[0..4] == [0xFF, 0xD8, 0xFF, 0xE0] {
-        "image/jpeg"
-    } else if file_data.len() >= 8 && &file_data[0..8] == [0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A] {
-        "image/png"
-    } else if file_data.len() >= 6 && &file_data[0..6] == [0x47, 0x49, 0x46, 0x38, 0x39, 0x61] {
-        "image/gif"
-    } else {
-        // Check for JPEG with different header
-        if file_data.len() >= 3 && &file_data[0..3] == [0xFF, 0xD8, 0xFF] {
-            "image/jpeg"
-        } else {
-            "image/png" // Default to PNG
-        }
-    };
-    
-    println!("Detected image format: {}", mime_type);
-    
-    // Convert to base64 data URL
-    use base64::{Engine as _, engine::general_purpose};
-    let base64 = general_purpose::STANDARD.encode(&file_data);
-    let data_url = format!("data:{};base64,{}", mime_type, base64);
-    
-    println!("Banner converted to data URL, length: {} bytes", data_url.len());
-    
-    Ok(data_url)
+
+    Ok(banner_data_url(&file_data))
+}
+
+#[tauri::command]
+pub async fn refresh_server_info(
+    server_id: String,
+    state: State<'_, AppState>,
+) -> Result<RefreshOutcome, String> {
+    println!("Command: refresh_server_info for {}", server_id);
+    state.refresh_server_info(&server_id).await
 }
 
 #[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct PreviewData {
     pub mime: String,
     pub data: String,
@@ -508,13 +1544,16 @@ fn guess_mime(path: &str, data: Option<&[u8]>) -> &'static str {
     guess_mime_from_extension(path)
 }
 
-/// Read a downloaded file into a data payload for safe previewing (avoids asset:// CORS issues)
+/// Read a downloaded file into a data payload for safe previewing (avoids asset:// CORS issues).
+/// Only files under the downloads directory, the app cache or an allowed folder can be read.
 #[tauri::command]
-pub async fn read_preview_file(path: String) -> Result<PreviewData, String> {
+pub async fn read_preview_file(path: String, state: State<'_, AppState>) -> Result<PreviewData, String> {
     use std::fs;
 
+    let resolved = state.resolve_preview_path(&path).await?;
+
     // Read file bytes first for content-based MIME detection
-    let bytes = fs::read(&path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let bytes = fs::read(&resolved).map_err(|e| format!("Failed to read file: {}", e))?;
     
     // Detect MIME type from content (magic bytes) first, then fall back to extension
     let mime = guess_mime(&path, Some(&bytes)).to_string();
@@ -544,13 +1583,136 @@ pub async fn read_preview_file(path: String) -> Result<PreviewData, String> {
     Ok(PreviewData { mime, data: encoded, is_text: false })
 }
 
+#[tauri::command]
+pub async fn get_preview_allow_list(
+    state: State<'_, AppState>,
+) -> Result<Vec<std::path::PathBuf>, String> {
+    Ok(state.get_preview_allow_list().await)
+}
+
+/// Let the user pick a folder to allow previews from. The folder comes from
+/// the native picker, never from the webview, so a compromised page can't
+/// widen the list. Returns the updated list, or None when the picker was
+/// dismissed.
+#[tauri::command]
+pub async fn add_preview_folder(
+    state: State<'_, AppState>,
+) -> Result<Option<Vec<std::path::PathBuf>>, String> {
+    println!("Command: add_preview_folder");
+    #[cfg(any(target_os = "android", target_os = "ios"))]
+    {
+        let _ = state;
+        Err("Folder selection is not available on mobile".to_string())
+    }
+
+    #[cfg(not(any(target_os = "android", target_os = "ios")))]
+    {
+        let picked = tokio::task::spawn_blocking(|| {
+            rfd::FileDialog::new()
+                .set_title("Allow Previews From Folder")
+                .pick_folder()
+        })
+        .await
+        .map_err(|e| format!("Dialog error: {}", e))?;
+        match picked {
+            Some(directory) => state.add_preview_folder(&directory).await.map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn remove_preview_folder(
+    directory: std::path::PathBuf,
+    state: State<'_, AppState>,
+) -> Result<Vec<std::path::PathBuf>, String> {
+    println!("Command: remove_preview_folder {:?}", directory);
+    state.remove_preview_folder(&directory).await
+}
+
 #[tauri::command]
 pub async fn fetch_tracker_servers(
     address: String,
     port: Option<u16>,
+    operation_id: Option<String>,
+    state: State<'_, AppState>,
 ) -> Result<Vec<crate::protocol::types::TrackerServer>, String> {
     println!("Command: fetch_tracker_servers from {}:{}", address, port.unwrap_or(5498));
-    TrackerClient::fetch_servers(&address, port).await
+    state.fetch_tracker_servers(&address, port, operation_id).await
+}
+
+#[tauri::command]
+pub async fn get_cached_tracker_servers(
+    address: String,
+    port: Option<u16>,
+    state: State<'_, AppState>,
+) -> Result<Option<TrackerListing>, String> {
+    Ok(state.get_cached_tracker_servers(&address, port).await)
+}
+
+#[tauri::command]
+pub async fn search_tracker_servers(
+    query: String,
+    min_users: Option<u16>,
+    sort: Option<TrackerSort>,
+    state: State<'_, AppState>,
+) -> Result<Vec<TrackerSearchResult>, String> {
+    Ok(state.search_tracker_servers(&query, min_users.unwrap_or(0), sort.unwrap_or_default()).await)
+}
+
+#[tauri::command]
+pub async fn refresh_tracker(
+    address: String,
+    port: Option<u16>,
+    operation_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<TrackerDiff, String> {
+    println!("Command: refresh_tracker {}:{}", address, port.unwrap_or(5498));
+    state.refresh_tracker(&address, port, operation_id).await
+}
+
+#[tauri::command]
+pub async fn fetch_all_trackers(
+    operation_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<TrackerBrowse, String> {
+    println!("Command: fetch_all_trackers");
+    Ok(state.fetch_all_trackers(operation_id).await)
+}
+
+#[tauri::command]
+pub async fn start_tracker_registration(
+    registration: TrackerRegistration,
+    trackers: Vec<TrackerAddress>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    println!("Command: start_tracker_registration for {} with {} trackers", registration.name, trackers.len());
+    state.start_tracker_registration(registration, trackers).await
+}
+
+#[tauri::command]
+pub async fn stop_tracker_registration(
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    println!("Command: stop_tracker_registration");
+    state.stop_tracker_registration().await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn cancel_operation(
+    operation_id: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    println!("Command: cancel_operation {}", operation_id);
+    state.cancel_operation(&operation_id).await
+}
+
+#[tauri::command]
+pub async fn list_operations(
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::state::operations::OperationSummary>, String> {
+    Ok(state.list_operations().await)
 }
 
 #[tauri::command]
@@ -566,10 +1728,128 @@ pub async fn get_server_info(
 pub async fn get_user_access(
     server_id: String,
     state: State<'_, AppState>,
-) -> Result<u64, String> {
+) -> Result<UserAccess, String> {
     state.get_user_access(&server_id).await
 }
 
+#[tauri::command]
+pub async fn get_connection_stats(
+    server_id: String,
+    state: State<'_, AppState>,
+) -> Result<ConnectionStats, String> {
+    state.get_connection_stats(&server_id).await
+}
+
+#[tauri::command]
+pub async fn get_file_info(
+    server_id: String,
+    path: Vec<String>,
+    file_name: String,
+    state: State<'_, AppState>,
+) -> Result<crate::protocol::client::FileDetails, String> {
+    println!("Command: get_file_info {:?} / {}", path, file_name);
+    state.get_file_info(&server_id, path, file_name).await
+}
+
+#[tauri::command]
+pub async fn set_file_info(
+    server_id: String,
+    path: Vec<String>,
+    file_name: String,
+    new_name: Option<String>,
+    comment: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    println!("Command: set_file_info {:?} / {}", path, file_name);
+    state.set_file_info(&server_id, path, file_name, new_name, comment).await
+}
+
+#[tauri::command]
+pub async fn rename_file(
+    server_id: String,
+    path: Vec<String>,
+    file_name: String,
+    new_name: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    println!("Command: rename_file {:?} / {} -> {}", path, file_name, new_name);
+    state.rename_file(&server_id, path, file_name, new_name).await
+}
+
+#[tauri::command]
+pub async fn move_file(
+    server_id: String,
+    path: Vec<String>,
+    file_name: String,
+    new_path: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    println!("Command: move_file {:?} / {} -> {:?}", path, file_name, new_path);
+    state.move_file(&server_id, path, file_name, new_path).await
+}
+
+#[tauri::command]
+pub async fn make_file_alias(
+    server_id: String,
+    path: Vec<String>,
+    file_name: String,
+    alias_path: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    println!("Command: make_file_alias {:?} / {} -> {:?}", path, file_name, alias_path);
+    state.make_file_alias(&server_id, path, file_name, alias_path).await
+}
+
+#[tauri::command]
+pub async fn delete_file(
+    server_id: String,
+    path: Vec<String>,
+    file_name: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    println!("Command: delete_file {:?} / {}", path, file_name);
+    state.delete_file(&server_id, path, file_name).await
+}
+
+#[tauri::command]
+pub async fn delete_files(
+    server_id: String,
+    files: Vec<FileRef>,
+    state: State<'_, AppState>,
+) -> Result<BatchResult, String> {
+    println!("Command: delete_files ({} items)", files.len());
+    state.delete_files(&server_id, files).await
+}
+
+#[tauri::command]
+pub async fn move_files(
+    server_id: String,
+    files: Vec<FileRef>,
+    new_path: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<BatchResult, String> {
+    println!("Command: move_files ({} items) -> {:?}", files.len(), new_path);
+    state.move_files(&server_id, files, new_path).await
+}
+
+#[tauri::command]
+pub async fn get_users(
+    server_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::protocol::User>, String> {
+    state.get_users(&server_id).await
+}
+
+#[tauri::command]
+pub async fn get_user_info(
+    server_id: String,
+    user_id: u16,
+    state: State<'_, AppState>,
+) -> Result<crate::protocol::client::UserInfo, String> {
+    println!("Command: get_user_info {} on {}", user_id, server_id);
+    state.get_user_info(&server_id, user_id).await
+}
+
 #[tauri::command]
 pub async fn disconnect_user(
     server_id: String,
@@ -580,6 +1860,46 @@ pub async fn disconnect_user(
     state.disconnect_user(&server_id, user_id, options).await
 }
 
+#[tauri::command]
+pub async fn list_accounts(
+    server_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<Account>, String> {
+    println!("Command: list_accounts on {}", server_id);
+    state.list_accounts(&server_id).await
+}
+
+#[tauri::command]
+pub async fn get_account(
+    server_id: String,
+    login: String,
+    state: State<'_, AppState>,
+) -> Result<Account, String> {
+    println!("Command: get_account {} on {}", login, server_id);
+    state.get_account(&server_id, &login).await
+}
+
+#[tauri::command]
+pub async fn save_account(
+    server_id: String,
+    account: Account,
+    is_new: bool,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    println!("Command: save_account {} on {} (new: {})", account.login, server_id, is_new);
+    state.save_account(&server_id, account, is_new).await
+}
+
+#[tauri::command]
+pub async fn delete_account(
+    server_id: String,
+    login: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    println!("Command: delete_account {} on {}", login, server_id);
+    state.delete_account(&server_id, &login).await
+}
+
 #[tauri::command]
 pub async fn test_connection(address: String, port: u16) -> Result<String, String> {
     println!("Command: test_connection to {}:{}", address, port);
@@ -592,10 +1912,15 @@ pub async fn test_connection(address: String, port: u16) -> Result<String, Strin
         port,
         login: "guest".to_string(),
         password: Some("".to_string()),
-        icon: Some(414),
+        icon: Some(crate::protocol::constants::FALLBACK_USER_ICON),
         auto_connect: false,
         tls: false,
         bookmark_type: None,
+        notes: None,
+        tags: Vec::new(),
+        idle_disconnect_mins: None,
+        group: None,
+        on_connect: Vec::new(),
     };
 
     // Create client and connect
@@ -1,7 +1,8 @@
 // Hotline Tauri App
 
 mod commands;
-mod protocol;
+/// Public so the CLI and scripts can drive a HotlineClient without Tauri
+pub mod protocol;
 mod state;
 
 use state::AppState;
@@ -11,6 +12,7 @@ use tauri::Manager;
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_notification::init())
         .setup(|app| {
             // Get app data directory
             let app_data_dir = app
@@ -25,44 +27,203 @@ pub fn run() {
 
             Ok(())
         })
+        .on_window_event(|window, event| {
+            // A server's own window takes its connection with it
+            if let tauri::WindowEvent::Destroyed = event {
+                if let Some(server_id) = state::server_windows::server_id_for_label(window.label()) {
+                    let app_handle = window.app_handle().clone();
+                    tauri::async_runtime::spawn(async move {
+                        app_handle.state::<AppState>().server_window_closed(&server_id).await;
+                    });
+                }
+            }
+        })
         .invoke_handler(tauri::generate_handler![
             commands::connect_to_server,
+            commands::parse_server_address,
+            commands::connect_to_address,
+            commands::cancel_server_full_wait,
             commands::disconnect_from_server,
+            commands::quit_now,
+            commands::open_server_window,
+            commands::close_server_window,
             commands::update_user_info,
             commands::send_chat_message,
             commands::send_private_message,
+            commands::get_messaging_options,
+            commands::set_messaging_options,
+            commands::list_conversations,
+            commands::get_pm_conversations,
+            commands::get_pm_thread,
+            commands::mark_conversation_read,
+            commands::get_highlight_rules,
+            commands::set_highlight_rules,
+            commands::get_transfer_limits,
+            commands::set_transfer_limits,
+            commands::get_request_policy,
+            commands::set_request_policy,
+            commands::get_watch_list,
+            commands::set_watch_list,
+            commands::get_preferences,
+            commands::set_preferences,
+            commands::get_notification_prefs,
+            commands::set_notification_prefs,
+            commands::get_mention_keywords,
+            commands::set_mention_keywords,
+            commands::set_locale,
             commands::get_message_board,
+            commands::get_more_message_board,
             commands::post_message_board,
             commands::get_file_list,
             commands::download_file,
+            commands::download_folder,
+            commands::resume_download,
+            commands::get_partial_downloads,
+            commands::discard_partial_download,
+            commands::verify_download,
+            commands::get_transfer_history,
+            commands::clear_transfer_history,
+            commands::get_hosting_config,
+            commands::set_hosting_config,
+            commands::start_hosting,
+            commands::stop_hosting,
+            commands::get_hosting_status,
+            commands::queue_download,
+            commands::queue_upload,
+            commands::stage_uploads,
+            commands::commit_staged_uploads,
+            commands::discard_staged_uploads,
+            commands::list_transfers,
+            commands::pause_transfer,
+            commands::resume_transfer,
+            commands::cancel_transfer,
+            commands::reorder_transfers,
+            commands::get_download_slots,
+            commands::set_max_concurrent_transfers,
+            commands::set_max_transfers_per_host,
+            commands::clear_finished_transfers,
+            commands::cancel_operation,
+            commands::list_operations,
+            commands::create_private_chat,
+            commands::invite_to_private_chat,
+            commands::join_private_chat,
+            commands::leave_private_chat,
+            commands::reject_private_chat_invite,
+            commands::send_private_chat,
+            commands::set_private_chat_subject,
             commands::upload_file,
+            commands::check_upload_conflict,
             commands::get_news_categories,
             commands::get_news_articles,
+            commands::get_news_thread_tree,
             commands::get_news_article_data,
+            commands::get_cached_file_list,
+            commands::get_cached_news,
+            commands::get_chat_history,
+            commands::clear_chat_history,
+            commands::export_chat_transcript,
+            commands::export_news,
+            commands::clear_cache,
+            commands::get_client_identity,
+            commands::get_server_full_retry_policy,
+            commands::set_server_full_retry_policy,
+            commands::get_reconnect_policy,
+            commands::set_reconnect_policy,
+            commands::get_server_refresh_policy,
+            commands::set_server_refresh_policy,
+            commands::get_auto_away_policy,
+            commands::set_auto_away_policy,
+            commands::get_board_fetch_policy,
+            commands::set_board_fetch_policy,
+            commands::set_away,
+            commands::get_history_encryption,
+            commands::set_history_encryption,
+            commands::set_client_identity,
+            commands::set_metrics_enabled,
+            commands::send_raw_transaction,
+            commands::dump_metrics,
+            commands::get_session_summary,
+            commands::get_recent_failures,
+            commands::generate_diagnostics,
+            commands::get_cache_stats,
             commands::post_news_article,
+            commands::reply_to_news_article,
             commands::get_bookmarks,
+            commands::search_bookmarks,
+            commands::get_recent_servers,
+            commands::clear_recent_servers,
             commands::save_bookmark,
             commands::delete_bookmark,
             commands::reorder_bookmarks,
+            commands::get_bookmark_groups,
+            commands::create_bookmark_group,
+            commands::rename_bookmark_group,
+            commands::move_bookmark_group,
+            commands::delete_bookmark_group,
+            commands::move_bookmark_to_group,
             commands::add_default_bookmarks,
             commands::get_pending_agreement,
+            commands::take_connect_outcome,
+            commands::get_agreement,
             commands::accept_agreement,
             commands::download_banner,
+            commands::refresh_server_info,
             commands::read_preview_file,
+            commands::get_preview_allow_list,
+            commands::add_preview_folder,
+            commands::remove_preview_folder,
             commands::fetch_tracker_servers,
+            commands::get_cached_tracker_servers,
+            commands::search_tracker_servers,
+            commands::refresh_tracker,
+            commands::fetch_all_trackers,
+            commands::start_tracker_registration,
+            commands::stop_tracker_registration,
             commands::get_server_info,
             commands::get_user_access,
+            commands::get_connection_stats,
             commands::disconnect_user,
+            commands::get_file_info,
+            commands::set_file_info,
+            commands::rename_file,
+            commands::move_file,
+            commands::make_file_alias,
+            commands::delete_file,
+            commands::delete_files,
+            commands::move_files,
+            commands::get_users,
+            commands::get_user_info,
+            commands::list_accounts,
+            commands::get_account,
+            commands::save_account,
+            commands::delete_account,
             commands::test_connection,
             commands::check_for_updates,
             commands::pick_download_folder,
-            commands::send_broadcast,
-            commands::create_folder,
+            commands::broadcast_message,
+            commands::create_remote_folder,
             commands::create_news_category,
             commands::create_news_folder,
             commands::delete_news_item,
             commands::delete_news_article,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app_handle, event| {
+            // Hold the exit until every server was disconnected cleanly
+            if let tauri::RunEvent::ExitRequested { api, .. } = event {
+                let state = app_handle.state::<AppState>();
+                if state.is_shut_down() {
+                    return;
+                }
+                api.prevent_exit();
+                if state.begin_shutdown() {
+                    let app_handle = app_handle.clone();
+                    tauri::async_runtime::spawn(async move {
+                        app_handle.state::<AppState>().shutdown().await;
+                        app_handle.exit(0);
+                    });
+                }
+            }
+        });
 }
@@ -0,0 +1,106 @@
+// Fallback avatars for user icons without bundled artwork
+//
+// Servers hand out icon ids from a range far larger than the set shipped in
+// public/icons/classic. For the rest, user payloads carry a color and initials
+// so every client view draws the same placeholder. The color comes from a
+// fixed hash of the nickname, so a user keeps it across sessions and servers.
+
+use serde::Serialize;
+
+include!(concat!(env!("OUT_DIR"), "/bundled_icons.rs"));
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AvatarFallback {
+    /// CSS color, `#rrggbb`
+    pub color: String,
+    pub initials: String,
+}
+
+pub fn has_artwork(icon_id: u16) -> bool {
+    BUNDLED_ICONS.binary_search(&icon_id).is_ok()
+}
+
+/// Placeholder for `user_name`, or None when the icon has artwork
+pub fn fallback(icon_id: u16, user_name: &str) -> Option<AvatarFallback> {
+    if has_artwork(icon_id) {
+        return None;
+    }
+    Some(AvatarFallback {
+        color: color(user_name),
+        initials: initials(user_name),
+    })
+}
+
+/// First letter of up to two words, or "?" for a name without any
+fn initials(user_name: &str) -> String {
+    let initials: String = user_name
+        .split(|c: char| !c.is_alphanumeric())
+        .filter_map(|word| word.chars().next())
+        .take(2)
+        .flat_map(char::to_uppercase)
+        .collect();
+    if initials.is_empty() {
+        "?".to_string()
+    } else {
+        initials
+    }
+}
+
+/// FNV-1a of the lowercased name, which unlike the std hasher never changes
+/// between builds, picking a hue at fixed saturation and lightness
+fn color(user_name: &str) -> String {
+    let hash = user_name
+        .to_lowercase()
+        .bytes()
+        .fold(0x811c_9dc5u32, |hash, byte| (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193));
+    let (r, g, b) = hsl_to_rgb((hash % 360) as f32, 0.55, 0.45);
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+fn hsl_to_rgb(hue: f32, saturation: f32, lightness: f32) -> (u8, u8, u8) {
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    let x = chroma * (1.0 - ((hue / 60.0) % 2.0 - 1.0).abs());
+    let (r, g, b) = match hue as u32 / 60 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = lightness - chroma / 2.0;
+    let channel = |v: f32| ((v + m) * 255.0).round() as u8;
+    (channel(r), channel(g), channel(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn initials_from_up_to_two_words() {
+        assert_eq!(initials("alice"), "A");
+        assert_eq!(initials("Mac user_42"), "MU");
+        assert_eq!(initials("the quick brown fox"), "TQ");
+        assert_eq!(initials("***"), "?");
+        assert_eq!(initials("élodie"), "É");
+    }
+
+    #[test]
+    fn color_is_stable_and_ignores_case() {
+        assert_eq!(color("Alice"), color("alice"));
+        assert_ne!(color("alice"), color("bob"));
+        assert_eq!(color("alice").len(), 7);
+        assert_eq!(hsl_to_rgb(0.0, 1.0, 0.5), (255, 0, 0));
+        assert_eq!(hsl_to_rgb(240.0, 1.0, 0.5), (0, 0, 255));
+    }
+
+    #[test]
+    fn bundled_icons_need_no_fallback() {
+        assert!(has_artwork(128));
+        assert!(fallback(128, "alice").is_none());
+        assert!(!has_artwork(1));
+        assert_eq!(fallback(1, "alice").unwrap().initials, "A");
+    }
+}
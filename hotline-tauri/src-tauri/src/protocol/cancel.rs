@@ -0,0 +1,52 @@
+// Cancellation for long-running requests
+//
+// Operations that can take a while (transfers, tracker fetches, news and
+// board requests) take a CancellationToken. Transfer loops check it between
+// chunks; everything else is wrapped in `cancellable`, which drops the
+// request future as soon as the token fires.
+
+use super::i18n::{tr, MessageId};
+use std::future::Future;
+
+pub use tokio_util::sync::CancellationToken;
+
+/// Run `future` until it completes or `token` is cancelled
+pub async fn cancellable<T, F>(token: &CancellationToken, future: F) -> Result<T, String>
+where
+    F: Future<Output = Result<T, String>>,
+{
+    tokio::select! {
+        biased;
+        _ = token.cancelled() => Err(tr(MessageId::Cancelled)),
+        result = future => result,
+    }
+}
+
+/// Error to return from a loop that noticed the token was cancelled
+pub fn check_cancelled(token: &CancellationToken) -> Result<(), String> {
+    if token.is_cancelled() {
+        Err(tr(MessageId::Cancelled))
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn cancelled_token_stops_pending_future() {
+        let token = CancellationToken::new();
+        token.cancel();
+        let result: Result<(), String> = cancellable(&token, std::future::pending()).await;
+        assert_eq!(result, Err(tr(MessageId::Cancelled)));
+        assert!(check_cancelled(&token).is_err());
+    }
+
+    #[tokio::test]
+    async fn completed_future_wins_when_not_cancelled() {
+        let token = CancellationToken::new();
+        assert_eq!(cancellable(&token, async { Ok::<_, String>(7) }).await, Ok(7));
+    }
+}
@@ -0,0 +1,167 @@
+// Server account administration (ListUsers/GetUser/NewUser/SetUser/DeleteUser)
+//
+// Logins and passwords sent to these transactions use Hotline's byte-inverted
+// encoding. The server never returns a usable password, so accounts come back
+// without one and `password: None` on save means "leave it unchanged".
+
+use super::HotlineClient;
+use crate::protocol::constants::{FieldType, TransactionType};
+use crate::protocol::transaction::{decode_fields, Transaction, TransactionField};
+use crate::protocol::types::UserAccess;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Account {
+    pub login: String,
+    pub name: String,
+    /// New password; `None` keeps the current one, an empty string clears it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    pub access: UserAccess,
+}
+
+fn decode_inverted(data: &[u8]) -> String {
+    let bytes: Vec<u8> = data.iter().map(|b| b ^ 0xFF).collect();
+    TransactionField::new(FieldType::UserLogin, bytes)
+        .to_string()
+        .unwrap_or_default()
+}
+
+/// Build an account from the Name/Login/Access fields of a GetUser reply or
+/// of one nested ListUsers record. Both carry the login inverted.
+fn account_from_fields(fields: &[TransactionField]) -> Account {
+    let field = |kind: FieldType| fields.iter().find(|f| f.field_type == kind);
+
+    let login = field(FieldType::UserLogin)
+        .map(|f| decode_inverted(&f.data))
+        .unwrap_or_default();
+    let name = field(FieldType::UserName)
+        .and_then(|f| f.to_string().ok())
+        .unwrap_or_default();
+    let access = field(FieldType::UserAccess)
+        .and_then(|f| f.to_u64().ok())
+        .map(UserAccess::from_bits)
+        .unwrap_or_default();
+
+    Account {
+        login,
+        name,
+        password: None,
+        access,
+    }
+}
+
+fn validate_login(login: &str) -> Result<&str, String> {
+    let login = login.trim();
+    if login.is_empty() {
+        return Err("Account login cannot be empty".to_string());
+    }
+    Ok(login)
+}
+
+impl HotlineClient {
+    /// List every account on the server (requires the open user privilege)
+    pub async fn list_accounts(&self) -> Result<Vec<Account>, String> {
+        println!("Requesting account list...");
+
+        let transaction = Transaction::new(self.next_transaction_id(), TransactionType::ListUsers);
+        let reply = self.send_and_wait(transaction, "list accounts").await?;
+
+        let mut accounts: Vec<Account> = reply
+            .fields
+            .iter()
+            .filter(|f| f.field_type == FieldType::Data)
+            .map(|f| account_from_fields(&decode_fields(&f.data)))
+            .filter(|a| !a.login.is_empty())
+            .collect();
+        accounts.sort_by_key(|a| a.login.to_lowercase());
+
+        println!("Received {} accounts", accounts.len());
+        Ok(accounts)
+    }
+
+    pub async fn get_account(&self, login: &str) -> Result<Account, String> {
+        let login = validate_login(login)?;
+        println!("Requesting account '{}'", login);
+
+        let mut transaction = Transaction::new(self.next_transaction_id(), TransactionType::GetUser);
+        transaction.add_field(TransactionField::from_string(FieldType::UserLogin, login));
+
+        let reply = self.send_and_wait(transaction, "get account").await?;
+        let mut account = account_from_fields(&reply.fields);
+        if account.login.is_empty() {
+            account.login = login.to_string();
+        }
+        Ok(account)
+    }
+
+    /// Create (`is_new`) or update an account
+    pub async fn save_account(&self, account: &Account, is_new: bool) -> Result<(), String> {
+        let login = validate_login(&account.login)?;
+        println!("{} account '{}'", if is_new { "Creating" } else { "Updating" }, login);
+
+        let kind = if is_new { TransactionType::NewUser } else { TransactionType::SetUser };
+        let mut transaction = Transaction::new(self.next_transaction_id(), kind);
+        transaction.add_field(TransactionField::from_encoded_string(FieldType::UserLogin, login));
+        transaction.add_field(TransactionField::from_string(FieldType::UserName, account.name.trim()));
+        transaction.add_field(TransactionField::from_u64(FieldType::UserAccess, account.access.to_bits()));
+
+        match account.password.as_deref() {
+            // A single zero byte tells the server to keep the existing password
+            None if !is_new => {
+                transaction.add_field(TransactionField::new(FieldType::UserPassword, vec![0]));
+            }
+            // Omitting the field clears the password
+            None | Some("") => {}
+            Some(password) => {
+                transaction.add_field(TransactionField::from_encoded_string(FieldType::UserPassword, password));
+            }
+        }
+
+        self.send_and_wait(transaction, "save account").await?;
+        println!("Account '{}' saved", login);
+        Ok(())
+    }
+
+    pub async fn delete_account(&self, login: &str) -> Result<(), String> {
+        let login = validate_login(login)?;
+        println!("Deleting account '{}'", login);
+
+        let mut transaction = Transaction::new(self.next_transaction_id(), TransactionType::DeleteUser);
+        transaction.add_field(TransactionField::from_encoded_string(FieldType::UserLogin, login));
+
+        self.send_and_wait(transaction, "delete account").await?;
+        println!("Account '{}' deleted", login);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_nested_account_record() {
+        let access = UserAccess {
+            download_file: true,
+            send_chat: true,
+            ..Default::default()
+        };
+        let fields = [
+            TransactionField::from_string(FieldType::UserName, "Guest"),
+            TransactionField::from_encoded_string(FieldType::UserLogin, "guest"),
+            TransactionField::from_u64(FieldType::UserAccess, access.to_bits()),
+        ];
+        let mut record = (fields.len() as u16).to_be_bytes().to_vec();
+        for field in &fields {
+            record.extend_from_slice(&field.encode());
+        }
+
+        let account = account_from_fields(&decode_fields(&record));
+        assert_eq!(account.login, "guest");
+        assert_eq!(account.name, "Guest");
+        assert_eq!(account.access, access);
+        assert!(account.password.is_none());
+    }
+}
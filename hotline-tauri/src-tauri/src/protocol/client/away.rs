@@ -0,0 +1,186 @@
+// Away status
+//
+// Hotline has no transaction for being away; clients announce it through
+// SetClientUserInfo. While away we send the idle bit in the user flags, can
+// append a suffix to the nickname, and the away message goes out as the
+// automatic response so users who message us get it back. Away set by the
+// idle timer is cleared again by our next chat or private message; away set
+// by the user stays until they clear it.
+
+use super::{HotlineClient, HotlineEvent};
+use crate::protocol::client::instant_message::MessagingOptions;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// Idle bit of the user flags
+const USER_FLAG_AWAY: u16 = 0x0001;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AwayStatus {
+    /// Sent back as the automatic response while away
+    pub message: Option<String>,
+    /// Appended to the nickname while away, e.g. " [away]"
+    pub nickname_suffix: Option<String>,
+}
+
+pub(crate) struct Away {
+    status: Option<AwayStatus>,
+    /// Set by the idle timer rather than the user
+    automatic: bool,
+    /// Last chat or private message we sent
+    last_activity: Instant,
+}
+
+impl Default for Away {
+    fn default() -> Self {
+        Self {
+            status: None,
+            automatic: false,
+            last_activity: Instant::now(),
+        }
+    }
+}
+
+impl Away {
+    /// Nickname as other users see it
+    pub(crate) fn nickname(&self, username: &str) -> String {
+        match self.status.as_ref().and_then(|s| s.nickname_suffix.as_deref()) {
+            Some(suffix) if !suffix.is_empty() => format!("{}{}", username, suffix),
+            _ => username.to_string(),
+        }
+    }
+
+    pub(crate) fn user_flags(&self) -> u16 {
+        if self.status.is_some() {
+            USER_FLAG_AWAY
+        } else {
+            0
+        }
+    }
+
+    /// The away message while away, otherwise the configured automatic response
+    pub(crate) fn automatic_response<'a>(&'a self, messaging: &'a MessagingOptions) -> Option<&'a str> {
+        self.status
+            .as_ref()
+            .and_then(|s| s.message.as_deref())
+            .filter(|text| !text.trim().is_empty())
+            .or_else(|| messaging.automatic_response())
+    }
+
+    /// User option bits, with the automatic response bit set for an away message
+    pub(crate) fn user_options(&self, messaging: &MessagingOptions) -> u16 {
+        let options = messaging.user_options();
+        if self.automatic_response(messaging).is_some() {
+            options | 4
+        } else {
+            options
+        }
+    }
+}
+
+impl HotlineClient {
+    /// Set or clear away. `automatic` marks away set by the idle timer, which
+    /// our next chat or private message clears.
+    pub async fn set_away(&self, status: Option<AwayStatus>, automatic: bool) -> Result<(), String> {
+        {
+            let mut away = self.away.lock().await;
+            if status.is_none() && away.status.is_none() {
+                return Ok(());
+            }
+            away.automatic = automatic && status.is_some();
+            away.status = status;
+        }
+        self.announce_away().await
+    }
+
+    pub async fn is_away(&self) -> bool {
+        self.away.lock().await.status.is_some()
+    }
+
+    /// Time since our last chat or private message
+    pub async fn idle_for(&self) -> Duration {
+        self.away.lock().await.last_activity.elapsed()
+    }
+
+    /// The message to answer private messages with, if any
+    pub async fn automatic_response(&self) -> Option<String> {
+        let messaging = self.messaging.lock().await.clone();
+        self.away.lock().await.automatic_response(&messaging).map(str::to_string)
+    }
+
+    /// Note outgoing chat, coming back from automatic away
+    pub(crate) async fn record_activity(&self) {
+        let came_back = {
+            let mut away = self.away.lock().await;
+            away.last_activity = Instant::now();
+            let came_back = away.automatic && away.status.is_some();
+            if came_back {
+                away.status = None;
+                away.automatic = false;
+            }
+            came_back
+        };
+        if came_back {
+            if let Err(e) = self.announce_away().await {
+                eprintln!("Failed to clear away status: {}", e);
+            }
+        }
+    }
+
+    /// Resend our user info and tell listeners the away status changed
+    async fn announce_away(&self) -> Result<(), String> {
+        let username = self.get_username().await;
+        let icon_id = self.get_user_icon_id().await;
+        self.send_set_client_user_info(&username, icon_id).await?;
+
+        let away = self.away.lock().await;
+        let _ = self.event_tx.send(HotlineEvent::AwayChanged {
+            away: away.status.is_some(),
+            automatic: away.automatic,
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn away(message: Option<&str>, suffix: Option<&str>) -> Away {
+        Away {
+            status: Some(AwayStatus {
+                message: message.map(str::to_string),
+                nickname_suffix: suffix.map(str::to_string),
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn away_changes_nickname_and_flags() {
+        let present = Away::default();
+        assert_eq!(present.nickname("alice"), "alice");
+        assert_eq!(present.user_flags(), 0);
+
+        let away = away(None, Some(" [away]"));
+        assert_eq!(away.nickname("alice"), "alice [away]");
+        assert_eq!(away.user_flags(), USER_FLAG_AWAY);
+    }
+
+    #[test]
+    fn away_message_replaces_the_automatic_response() {
+        let messaging = MessagingOptions {
+            refuse_chat: true,
+            automatic_response: Some("Busy".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(Away::default().automatic_response(&messaging), Some("Busy"));
+        assert_eq!(away(Some("At lunch"), None).automatic_response(&messaging), Some("At lunch"));
+        assert_eq!(away(Some(" "), None).automatic_response(&messaging), Some("Busy"));
+
+        let quiet = MessagingOptions::default();
+        assert_eq!(away(None, None).user_options(&quiet), 0);
+        assert_eq!(away(Some("At lunch"), None).user_options(&quiet), 4);
+    }
+}
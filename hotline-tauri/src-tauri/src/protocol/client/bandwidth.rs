@@ -0,0 +1,150 @@
+// Transfer rate limiting and speed measurement
+//
+// Each server connection has its own upload and download limits. Transfer
+// loops ask a RateLimiter for permission before moving each chunk; the
+// limiter is a token bucket refilled at the configured rate with one second
+// of burst. TransferMeter turns progress callbacks into bytes/sec and ETA.
+
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// Bytes per second; `None` or 0 means unlimited
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TransferLimits {
+    pub download_bytes_per_sec: Option<u64>,
+    pub upload_bytes_per_sec: Option<u64>,
+}
+
+impl TransferLimits {
+    pub fn validate(&self) -> Result<(), String> {
+        for limit in [self.download_bytes_per_sec, self.upload_bytes_per_sec].into_iter().flatten() {
+            if limit > 0 && limit < 1024 {
+                return Err("Transfer limits must be at least 1 KB/s".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+pub struct RateLimiter {
+    rate: Option<f64>,
+    /// Available bytes; negative while paying off a chunk larger than the bucket
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(bytes_per_sec: Option<u64>) -> Self {
+        let rate = bytes_per_sec.filter(|&r| r > 0).map(|r| r as f64);
+        Self {
+            rate,
+            tokens: rate.unwrap_or(0.0),
+            last_refill: Instant::now(),
+        }
+    }
+
+    /// Take `bytes` from the bucket and return how long to wait before sending them
+    fn reserve(&mut self, bytes: usize, now: Instant) -> Duration {
+        let Some(rate) = self.rate else {
+            return Duration::ZERO;
+        };
+        let elapsed = now.saturating_duration_since(self.last_refill).as_secs_f64();
+        self.last_refill = now;
+        self.tokens = (self.tokens + elapsed * rate).min(rate) - bytes as f64;
+
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / rate)
+        }
+    }
+
+    /// Wait until `bytes` may be sent or received
+    pub async fn acquire(&mut self, bytes: usize) {
+        let wait = self.reserve(bytes, Instant::now());
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TransferSpeed {
+    pub bytes_per_sec: u64,
+    pub eta_secs: Option<u64>,
+}
+
+/// Average speed since the first progress report
+pub struct TransferMeter {
+    start: Option<(Instant, u64)>,
+}
+
+impl Default for TransferMeter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TransferMeter {
+    pub fn new() -> Self {
+        Self { start: None }
+    }
+
+    pub fn update(&mut self, done: u64, total: u64) -> TransferSpeed {
+        self.update_at(done, total, Instant::now())
+    }
+
+    fn update_at(&mut self, done: u64, total: u64, now: Instant) -> TransferSpeed {
+        // Measure from the first report so a resumed transfer's offset doesn't count
+        let (started, base) = *self.start.get_or_insert((now, done));
+        let elapsed = now.saturating_duration_since(started).as_secs_f64();
+        let bytes_per_sec = if elapsed > 0.0 {
+            (done.saturating_sub(base) as f64 / elapsed) as u64
+        } else {
+            0
+        };
+        let eta_secs = (bytes_per_sec > 0).then(|| total.saturating_sub(done) / bytes_per_sec);
+        TransferSpeed { bytes_per_sec, eta_secs }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limiter_spaces_chunks_to_the_configured_rate() {
+        let start = Instant::now();
+        let mut limiter = RateLimiter::new(Some(100_000));
+        limiter.last_refill = start;
+
+        // One second of burst goes through immediately
+        assert_eq!(limiter.reserve(100_000, start), Duration::ZERO);
+        // The next 50 KB has to wait half a second
+        assert_eq!(limiter.reserve(50_000, start), Duration::from_millis(500));
+        // ...and after that wait the bucket is square again
+        assert_eq!(limiter.reserve(0, start + Duration::from_millis(500)), Duration::ZERO);
+
+        let mut unlimited = RateLimiter::new(Some(0));
+        assert_eq!(unlimited.reserve(10_000_000, start), Duration::ZERO);
+    }
+
+    #[test]
+    fn meter_reports_speed_and_eta_from_first_sample() {
+        let start = Instant::now();
+        let mut meter = TransferMeter::new();
+        // Resumed at 1 MB of 3 MB
+        assert_eq!(meter.update_at(1_000_000, 3_000_000, start).eta_secs, None);
+        let speed = meter.update_at(1_500_000, 3_000_000, start + Duration::from_secs(2));
+        assert_eq!(speed.bytes_per_sec, 250_000);
+        assert_eq!(speed.eta_secs, Some(6));
+    }
+
+    #[test]
+    fn tiny_limits_are_rejected() {
+        assert!(TransferLimits::default().validate().is_ok());
+        assert!(TransferLimits { download_bytes_per_sec: Some(512), upload_bytes_per_sec: None }.validate().is_err());
+        assert!(TransferLimits { download_bytes_per_sec: Some(0), upload_bytes_per_sec: Some(4096) }.validate().is_ok());
+    }
+}
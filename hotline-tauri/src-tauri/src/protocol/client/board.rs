@@ -0,0 +1,306 @@
+// Message board
+//
+// The server sends the whole board as one Data field, which on some servers
+// runs to megabytes. We keep the raw bytes of the last fetch and hand posts
+// out a page at a time: each page stops once it would pass the size limit,
+// and carries a continuation for fetching the next one from the same copy.
+// The copy is dropped once its last page has been handed out.
+// Posts are decoded in chunks, each announced as a MessageBoardChunk event,
+// so the UI can show them while the rest of the page is still being decoded.
+
+use super::{HotlineClient, HotlineEvent, Lane};
+use crate::protocol::constants::{FieldType, TransactionType};
+use crate::protocol::quirks::Feature;
+use crate::protocol::transaction::{Transaction, TransactionField};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// Posts decoded per chunk event
+const BOARD_CHUNK_POSTS: usize = 50;
+
+/// Where the next page of a fetched board starts. Fetching the board again
+/// makes earlier continuations stale.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BoardContinuation {
+    pub fetch: u32,
+    pub offset: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BoardPage {
+    pub posts: Vec<String>,
+    /// Size of the whole board as the server sent it
+    pub total_bytes: u64,
+    /// Set when there are posts past this page
+    pub continuation: Option<BoardContinuation>,
+}
+
+/// Raw board from the last fetch, with the byte range of each post
+#[derive(Default)]
+pub(crate) struct BoardSnapshot {
+    fetch: u32,
+    data: Vec<u8>,
+    posts: Vec<Range<usize>>,
+}
+
+impl HotlineClient {
+    /// Fetch the message board and return its first page of up to
+    /// `max_bytes` (always at least one post)
+    pub async fn get_message_board(&self, max_bytes: u64) -> Result<BoardPage, String> {
+        println!("Requesting message board");
+        self.require(Feature::MessageBoard).await?;
+
+        let transaction = Transaction::new(self.next_transaction_id(), TransactionType::GetMessageBoard);
+        let reply = self.send_feature_request(Feature::MessageBoard, transaction, "get message board").await?;
+
+        // Get raw bytes and split by divider lines before decoding.
+        // Boards can mix UTF-8 posts (modern clients) and Mac Roman posts (old clients).
+        // Decoding the whole blob at once causes mojibake when UTF-8 multi-byte sequences
+        // get misinterpreted as Mac Roman. Split first, decode each post individually.
+        let data = reply
+            .get_field(FieldType::Data)
+            .map(|f| f.data.clone())
+            .unwrap_or_default();
+
+        let fetch = {
+            let mut board = self.board.lock().await;
+            board.fetch = board.fetch.wrapping_add(1);
+            board.posts = board_post_ranges(&data);
+            board.data = data;
+            println!("Received message board: {} posts, {} bytes", board.posts.len(), board.data.len());
+            board.fetch
+        };
+
+        self.get_more_message_board(BoardContinuation { fetch, offset: 0 }, max_bytes).await
+    }
+
+    /// Next page of the board fetched last, starting at `from`
+    pub async fn get_more_message_board(&self, from: BoardContinuation, max_bytes: u64) -> Result<BoardPage, String> {
+        let mut board = self.board.lock().await;
+        if board.fetch != from.fetch {
+            return Err("The message board changed since it was loaded; reload it".to_string());
+        }
+
+        let offset = usize::try_from(from.offset).unwrap_or(usize::MAX);
+        let max_bytes = usize::try_from(max_bytes).unwrap_or(usize::MAX);
+        let (page, next) = page_posts(&board.posts, offset, max_bytes);
+
+        let mut posts = Vec::with_capacity(page.len());
+        for chunk in page.chunks(BOARD_CHUNK_POSTS) {
+            let decoded: Vec<String> = chunk
+                .iter()
+                .filter_map(|range| decode_post_bytes(&board.data[range.clone()]))
+                .collect();
+            let _ = self.event_tx.send(HotlineEvent::MessageBoardChunk(decoded.clone()));
+            posts.extend(decoded);
+            // Let the rest of the client run between chunks of a big board
+            tokio::task::yield_now().await;
+        }
+
+        println!("Message board page: {} posts from offset {}, more: {}", posts.len(), offset, next.is_some());
+
+        let page = BoardPage {
+            posts,
+            total_bytes: board.data.len() as u64,
+            continuation: next.map(|offset| BoardContinuation {
+                fetch: board.fetch,
+                offset: offset as u64,
+            }),
+        };
+        if next.is_none() {
+            board.data = Vec::new();
+            board.posts = Vec::new();
+        }
+        Ok(page)
+    }
+
+    pub async fn post_message_board(&self, text: String) -> Result<(), String> {
+        println!("Posting to message board: {} chars", text.len());
+        self.require(Feature::MessageBoard).await?;
+
+        let mut transaction = Transaction::new(self.next_transaction_id(), TransactionType::OldPostNews);
+        transaction.add_field(TransactionField::from_string(FieldType::Data, &text));
+
+        let encoded = transaction.encode();
+
+        self.send_frame(Lane::Bulk, encoded)
+            .await
+            .map_err(|e| format!("Failed to post message: {}", e))?;
+
+        println!("Message board post sent successfully");
+
+        Ok(())
+    }
+}
+
+/// Posts starting at or after `offset` that fit in `max_bytes`, but at least
+/// one, and where the page after them starts
+fn page_posts(posts: &[Range<usize>], offset: usize, max_bytes: usize) -> (&[Range<usize>], Option<usize>) {
+    let rest = &posts[posts.partition_point(|post| post.start < offset)..];
+    let start = match rest.first() {
+        Some(first) => first.start,
+        None => return (rest, None),
+    };
+    let count = 1 + rest[1..].iter().take_while(|post| post.end - start <= max_bytes).count();
+    (&rest[..count], rest.get(count).map(|post| post.start))
+}
+
+// --- Message board parsing helpers ---
+// Boards mix UTF-8 (modern clients) and Mac Roman (old clients) posts.
+// We split on divider lines in raw bytes before decoding so each post
+// gets its own UTF-8 → Mac Roman fallback pass.
+
+/// Byte ranges of the lines in `data`, without their line endings
+fn split_raw_lines(data: &[u8]) -> Vec<Range<usize>> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+    while i < data.len() {
+        if data[i] == 0x0D {
+            lines.push(start..i);
+            i += 1;
+            if i < data.len() && data[i] == 0x0A {
+                i += 1;
+            }
+            start = i;
+        } else if data[i] == 0x0A {
+            lines.push(start..i);
+            i += 1;
+            start = i;
+        } else {
+            i += 1;
+        }
+    }
+    if start < data.len() {
+        lines.push(start..data.len());
+    }
+    lines
+}
+
+fn classify_divider_lead(line: &[u8]) -> Option<u8> {
+    const SEPS: &[u8] = &[b'_', b'-', b'=', b'~', b'*'];
+    const WS: &[u8] = &[b' ', b'\t'];
+    let s = line.iter().position(|b| !WS.contains(b))?;
+    let e = line.iter().rposition(|b| !WS.contains(b))? + 1;
+    let trimmed = &line[s..e];
+    let lead = *trimmed.first()?;
+    if !SEPS.contains(&lead) {
+        return None;
+    }
+    if trimmed.len() >= 15 && trimmed.iter().all(|b| SEPS.contains(b)) {
+        return Some(lead);
+    }
+    let lc = trimmed.iter().take_while(|b| SEPS.contains(b)).count();
+    let tc = trimmed.iter().rev().take_while(|b| SEPS.contains(b)).count();
+    if lc >= 5 && tc >= 5 {
+        return Some(lead);
+    }
+    None
+}
+
+fn find_canonical_divider(data: &[u8], lines: &[Range<usize>]) -> Option<u8> {
+    let mut counts: HashMap<u8, usize> = HashMap::new();
+    let mut order: HashMap<u8, usize> = HashMap::new();
+    for line in lines {
+        if let Some(ch) = classify_divider_lead(&data[line.clone()]) {
+            let n = order.len();
+            order.entry(ch).or_insert(n);
+            *counts.entry(ch).or_insert(0) += 1;
+        }
+    }
+    counts
+        .iter()
+        .max_by(|a, b| {
+            a.1.cmp(b.1).then_with(|| {
+                // Earlier first-seen wins on tie
+                order.get(b.0).unwrap_or(&usize::MAX).cmp(order.get(a.0).unwrap_or(&usize::MAX))
+            })
+        })
+        .map(|(c, _)| *c)
+}
+
+fn decode_post_bytes(data: &[u8]) -> Option<String> {
+    if data.is_empty() {
+        return None;
+    }
+    let s = if let Ok(s) = std::str::from_utf8(data) {
+        s.to_owned()
+    } else {
+        let (decoded, _, _) = encoding_rs::MACINTOSH.decode(data);
+        decoded.into_owned()
+    };
+    let s = s.replace("\r\n", "\n").replace('\r', "\n");
+    let trimmed = s.trim().to_string();
+    if trimmed.is_empty() { None } else { Some(trimmed) }
+}
+
+/// Byte ranges of the posts on a board, split on its most common divider.
+/// Posts keep their inner line endings; decode_post_bytes normalizes them.
+fn board_post_ranges(data: &[u8]) -> Vec<Range<usize>> {
+    let lines = split_raw_lines(data);
+    let canonical = find_canonical_divider(data, &lines);
+    let mut posts = Vec::new();
+    let mut current: Option<Range<usize>> = None;
+
+    for line in lines {
+        if canonical.is_some() && classify_divider_lead(&data[line.clone()]) == canonical {
+            posts.extend(current.take());
+            continue;
+        }
+        match &mut current {
+            Some(post) => post.end = line.end,
+            None => current = Some(line),
+        }
+    }
+
+    posts.extend(current);
+    posts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIVIDER: &str = "__________________________________________________";
+
+    fn board(posts: &[&str]) -> Vec<u8> {
+        posts.join(&format!("\r{}\r", DIVIDER)).into_bytes()
+    }
+
+    fn decode(data: &[u8], ranges: &[Range<usize>]) -> Vec<String> {
+        ranges.iter().filter_map(|r| decode_post_bytes(&data[r.clone()])).collect()
+    }
+
+    #[test]
+    fn splits_posts_on_the_divider() {
+        let data = board(&["First post\rsecond line", "Second\r\n  post  "]);
+        let posts = board_post_ranges(&data);
+        assert_eq!(decode(&data, &posts), ["First post\nsecond line", "Second\n  post"]);
+
+        // No divider: the whole board is one post
+        assert_eq!(board_post_ranges(b"just text").len(), 1);
+        assert!(board_post_ranges(b"").is_empty());
+    }
+
+    #[test]
+    fn pages_stop_at_the_size_limit() {
+        let data = board(&["aaaaaaaaaa", "bbbbbbbbbb", "cccccccccc"]);
+        let posts = board_post_ranges(&data);
+
+        let (first, next) = page_posts(&posts, 0, 10);
+        assert_eq!(decode(&data, first), ["aaaaaaaaaa"]);
+        let next = next.unwrap();
+
+        let (rest, end) = page_posts(&posts, next, usize::MAX);
+        assert_eq!(decode(&data, rest), ["bbbbbbbbbb", "cccccccccc"]);
+        assert_eq!(end, None);
+
+        // A post bigger than the limit still makes a page of its own
+        let (single, _) = page_posts(&posts, 0, 1);
+        assert_eq!(single.len(), 1);
+        assert!(page_posts(&posts, data.len(), 10).0.is_empty());
+    }
+}
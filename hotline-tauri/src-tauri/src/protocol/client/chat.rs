@@ -1,100 +1,57 @@
 // Chat functionality for Hotline client
 
-use super::HotlineClient;
+use super::instant_message::InstantMessageKind;
+use super::requests::{AgreedRequest, SendChatRequest, SetClientUserInfoRequest, UserInfoFields};
+use super::{HotlineClient, Lane};
 use crate::protocol::constants::{FieldType, TransactionType};
+use crate::protocol::i18n::{tr, MessageId};
 use crate::protocol::transaction::{Transaction, TransactionField};
-use tokio::io::AsyncWriteExt;
 
 impl HotlineClient {
     pub async fn send_chat(&self, message: String) -> Result<(), String> {
         println!("Sending chat: {}", message);
+        self.record_activity().await;
 
-        let mut transaction = Transaction::new(self.next_transaction_id(), TransactionType::SendChat);
-        transaction.add_field(TransactionField::from_string(FieldType::Data, &message));
-        transaction.add_field(TransactionField::from_u16(FieldType::ChatOptions, 0)); // 0 = normal chat, 1 = announce
+        let transaction = self.request(&SendChatRequest { message: &message, chat_id: None });
 
         let encoded = transaction.encode();
         println!("Chat transaction: {} bytes", encoded.len());
 
         println!("Writing chat to stream...");
-        let mut write_guard = self.write_half.lock().await;
-        let write_stream = write_guard
-            .as_mut()
-            .ok_or("Not connected".to_string())?;
-
-        write_stream
-            .write_all(&encoded)
-            .await
-            .map_err(|e| {
-                let err = format!("Failed to send chat: {}", e);
-                eprintln!("{}", err);
-                err
-            })?;
-
-        println!("Flushing stream...");
-        // Flush the stream to ensure the message is sent immediately
-        write_stream
-            .flush()
+        self.send_frame(Lane::Interactive, encoded)
             .await
-            .map_err(|e| {
-                let err = format!("Failed to flush stream: {}", e);
-                eprintln!("{}", err);
-                err
-            })?;
+            .map_err(|e| format!("Failed to send chat: {}", e))?;
 
         println!("Chat sent successfully");
 
         Ok(())
     }
 
-    pub async fn send_broadcast(&self, message: String) -> Result<(), String> {
+    /// Send a server-wide broadcast (requires the broadcast privilege)
+    pub async fn broadcast_message(&self, message: String) -> Result<(), String> {
+        if message.trim().is_empty() {
+            return Err("Broadcast message cannot be empty".to_string());
+        }
+        if !self.user_access.lock().await.broadcast {
+            return Err(tr(MessageId::NotPermitted));
+        }
+        println!("Broadcasting: {}", message);
+
         let mut transaction = Transaction::new(self.next_transaction_id(), TransactionType::UserBroadcast);
         transaction.add_field(TransactionField::from_string(FieldType::Data, &message));
 
-        let encoded = transaction.encode();
-
-        let mut write_guard = self.write_half.lock().await;
-        let write_stream = write_guard
-            .as_mut()
-            .ok_or("Not connected".to_string())?;
-
-        write_stream
-            .write_all(&encoded)
-            .await
-            .map_err(|e| format!("Failed to send broadcast: {}", e))?;
-
-        write_stream
-            .flush()
-            .await
-            .map_err(|e| format!("Failed to flush: {}", e))?;
+        self.send_and_wait(transaction, "send broadcast").await?;
 
+        println!("Broadcast sent");
         Ok(())
     }
 
-    pub async fn send_private_message(&self, user_id: u16, message: String) -> Result<(), String> {
+    /// Send a private message; `quoting` is the message being replied to
+    pub async fn send_private_message(&self, user_id: u16, message: String, quoting: Option<&str>) -> Result<(), String> {
         println!("Sending private message to user {}: {}", user_id, message);
+        self.record_activity().await;
 
-        let mut transaction = Transaction::new(self.next_transaction_id(), TransactionType::SendInstantMessage);
-        transaction.add_field(TransactionField::from_u16(FieldType::UserId, user_id));
-        transaction.add_field(TransactionField::from_u32(FieldType::Options, 1)); // Options = 1 for instant messages
-        transaction.add_field(TransactionField::from_string(FieldType::Data, &message));
-
-        let encoded = transaction.encode();
-
-        let mut write_guard = self.write_half.lock().await;
-        let write_stream = write_guard
-            .as_mut()
-            .ok_or("Not connected".to_string())?;
-
-        write_stream
-            .write_all(&encoded)
-            .await
-            .map_err(|e| format!("Failed to send private message: {}", e))?;
-
-        write_stream
-            .flush()
-            .await
-            .map_err(|e| format!("Failed to flush: {}", e))?;
+        self.send_instant_message(user_id, InstantMessageKind::Message, &message, quoting).await?;
 
         println!("Private message sent successfully");
 
@@ -102,27 +59,23 @@ impl HotlineClient {
     }
 
     pub async fn send_set_client_user_info(&self, username: &str, icon_id: u16) -> Result<(), String> {
-        let mut transaction = Transaction::new(self.next_transaction_id(), TransactionType::SetClientUserInfo);
-        transaction.add_field(TransactionField::from_string(FieldType::UserName, username));
-        transaction.add_field(TransactionField::from_u16(FieldType::UserIconId, icon_id));
-        transaction.add_field(TransactionField::from_u16(FieldType::Options, 0));
-
-        let encoded = transaction.encode();
-
-        let mut write_guard = self.write_half.lock().await;
-        let write_stream = write_guard
-            .as_mut()
-            .ok_or("Not connected".to_string())?;
-
-        write_stream
-            .write_all(&encoded)
-            .await
-            .map_err(|e| format!("Failed to send user info update: {}", e))?;
-
-        write_stream
-            .flush()
-            .await
-            .map_err(|e| format!("Failed to flush: {}", e))?;
+        let messaging = self.messaging.lock().await.clone();
+        let away = self.away.lock().await;
+        let nickname = away.nickname(username);
+        let transaction = self.request(&SetClientUserInfoRequest {
+            user_info: UserInfoFields {
+                user_name: &nickname,
+                icon_id,
+                options: away.user_options(&messaging),
+                automatic_response: away.automatic_response(&messaging),
+            },
+            flags: away.user_flags(),
+        });
+        drop(away);
+
+        // Noted first, as the echo can beat send_transaction back
+        self.own_user.lock().await.expect(&nickname, username, icon_id);
+        self.send_transaction(transaction, "send user info update").await?;
 
         // Update local state
         *self.username.lock().await = username.to_string();
@@ -134,7 +87,6 @@ impl HotlineClient {
     pub async fn accept_agreement(&self) -> Result<(), String> {
         use std::time::Duration;
         use tokio::sync::mpsc;
-        use crate::protocol::constants::TransactionType;
 
         println!("Sending agreement acceptance...");
 
@@ -149,24 +101,17 @@ impl HotlineClient {
             *icon_guard
         };
 
-        // Create Agreed transaction with REQUIRED fields
-        let mut transaction = Transaction::new(self.next_transaction_id(), TransactionType::Agreed);
-        
-        // REQUIRED fields for Agreed transaction (some servers like Mobius require these)
-        transaction.add_field(TransactionField::from_string(
-            FieldType::UserName,
-            &username,
-        ));
-        transaction.add_field(TransactionField::from_u16(
-            FieldType::UserIconId,
-            user_icon_id,
-        ));
-        transaction.add_field(TransactionField::from_u16(
-            FieldType::Options,
-            0, // User options (typically 0)
-        ));
-        
-        let encoded = transaction.encode();
+        // Some servers like Mobius require our user info with Agreed
+        let quirks = self.quirks().await;
+        let messaging = self.messaging.lock().await.clone();
+        let transaction = self.request(&AgreedRequest {
+            user_info: quirks.agreed_requires_user_info.then(|| UserInfoFields {
+                user_name: &username,
+                icon_id: user_icon_id,
+                options: messaging.user_options(),
+                automatic_response: messaging.automatic_response(),
+            }),
+        });
         let transaction_id = transaction.id;
 
         // Create channel to receive reply (if any)
@@ -176,24 +121,13 @@ impl HotlineClient {
             pending.insert(transaction_id, tx);
         }
 
-        // Send transaction with combined write+flush
-        {
-        let mut write_guard = self.write_half.lock().await;
-        let write_stream = write_guard
-            .as_mut()
-            .ok_or("Not connected".to_string())?;
-
-        write_stream
-            .write_all(&encoded)
-            .await
-            .map_err(|e| format!("Failed to send agreement: {}", e))?;
-
-        write_stream
-            .flush()
-            .await
-            .map_err(|e| format!("Failed to flush: {}", e))?;
+        if quirks.agreed_requires_user_info {
+            self.own_user.lock().await.expect(&username, &username, user_icon_id);
         }
 
+        // Send transaction
+        self.send_transaction(transaction, "send agreement").await?;
+
         // Wait for reply (but handle empty replies gracefully)
         // Some servers send empty replies, which is fine
         println!("Waiting for Agreed reply...");
@@ -218,11 +152,22 @@ impl HotlineClient {
 
         println!("Agreement accepted successfully");
 
+        if let Some(info) = self.server_info.lock().await.as_mut() {
+            info.agreement_accepted = true;
+            *self.accepted_agreement.lock().await = info.agreement.clone();
+        }
+
         // CRITICAL: Call GetUserNameList immediately after Agreed
         // This must happen in the same function, not separately
-        // Some servers (like Mobius) require this to complete the sign-in process
-        println!("Requesting user list after agreement acceptance...");
-        self.get_user_list().await?;
+        // Some servers (like Mobius) require this to complete the sign-in process.
+        // Anyone missing from the list loaded at login reaches the frontend as
+        // a roster sync.
+        if quirks.user_list_after_agreement {
+            println!("Requesting user list after agreement acceptance...");
+            if let Err(e) = self.refresh_user_list().await {
+                eprintln!("User list after agreement failed: {}", e);
+            }
+        }
 
         Ok(())
     }
@@ -0,0 +1,122 @@
+// Pulling events without the Tauri event loop
+//
+// The app takes the client's event receiver and forwards every event to the
+// webview. Code that embeds the protocol without Tauri (the CLI, scripts)
+// pulls events from the client instead: next_event waits for the next one of
+// the wanted kinds, poll_events returns what is already queued. Kinds are the
+// names from HotlineEvent::name, and an empty filter matches everything.
+// Events of other kinds are dropped, so callers that care about several kinds
+// should ask for all of them at once.
+
+use super::{HotlineClient, HotlineEvent};
+use std::time::Duration;
+use tokio::sync::mpsc::error::TryRecvError;
+
+fn wanted(event: &HotlineEvent, kinds: &[&str]) -> bool {
+    kinds.is_empty() || kinds.contains(&event.name())
+}
+
+impl HotlineClient {
+    /// Wait for the next event of one of `kinds`. Returns None when `timeout`
+    /// passes first.
+    pub async fn next_event(&self, kinds: &[&str], timeout: Option<Duration>) -> Result<Option<HotlineEvent>, String> {
+        let mut guard = self.event_rx.lock().await;
+        let rx = guard
+            .as_mut()
+            .ok_or_else(|| "Events are already being delivered to the app".to_string())?;
+
+        let next = async {
+            while let Some(event) = rx.recv().await {
+                if wanted(&event, kinds) {
+                    return Ok(event);
+                }
+            }
+            Err("Event stream closed".to_string())
+        };
+        match timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, next).await {
+                Ok(event) => event.map(Some),
+                Err(_) => Ok(None),
+            },
+            None => next.await.map(Some),
+        }
+    }
+
+    /// Take up to `max` queued events of `kinds` without waiting
+    pub async fn poll_events(&self, kinds: &[&str], max: usize) -> Result<Vec<HotlineEvent>, String> {
+        let mut guard = self.event_rx.lock().await;
+        let rx = guard
+            .as_mut()
+            .ok_or_else(|| "Events are already being delivered to the app".to_string())?;
+
+        let mut events = Vec::new();
+        while events.len() < max {
+            match rx.try_recv() {
+                Ok(event) if wanted(&event, kinds) => events.push(event),
+                Ok(_) => {}
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) if events.is_empty() => {
+                    return Err("Event stream closed".to_string());
+                }
+                Err(TryRecvError::Disconnected) => break,
+            }
+        }
+        Ok(events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::types::Bookmark;
+
+    fn client() -> HotlineClient {
+        HotlineClient::new(Bookmark {
+            id: "test".to_string(),
+            name: "Test".to_string(),
+            address: "127.0.0.1".to_string(),
+            port: 5500,
+            login: "guest".to_string(),
+            password: None,
+            icon: None,
+            auto_connect: false,
+            tls: false,
+            bookmark_type: None,
+            notes: None,
+            tags: Vec::new(),
+            idle_disconnect_mins: None,
+            group: None,
+            on_connect: Vec::new(),
+        })
+    }
+
+    #[tokio::test]
+    async fn poll_filters_by_kind_and_respects_max() {
+        let client = client();
+        for i in 0..3 {
+            let _ = client.event_tx.send(HotlineEvent::ServerMessage(format!("notice {}", i)));
+            let _ = client.event_tx.send(HotlineEvent::UserLeft { user_id: i });
+        }
+
+        let left = client.poll_events(&["userLeft"], 2).await.unwrap();
+        assert!(matches!(left[..], [HotlineEvent::UserLeft { user_id: 0 }, HotlineEvent::UserLeft { user_id: 1 }]));
+        // The notices before the second userLeft were skipped; the rest remain
+        let rest = client.poll_events(&[], 10).await.unwrap();
+        assert_eq!(rest.len(), 2);
+        assert!(client.poll_events(&[], 10).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn next_event_waits_and_times_out() {
+        let client = client();
+        assert!(client.next_event(&[], Some(Duration::from_millis(10))).await.unwrap().is_none());
+
+        let _ = client.event_tx.send(HotlineEvent::ConnectionLost);
+        let _ = client.event_tx.send(HotlineEvent::UserLeft { user_id: 4 });
+        let event = client.next_event(&["userLeft"], None).await.unwrap();
+        assert!(matches!(event, Some(HotlineEvent::UserLeft { user_id: 4 })));
+
+        client.event_rx.lock().await.take();
+        assert!(client.poll_events(&[], 1).await.is_err());
+    }
+}
@@ -0,0 +1,115 @@
+// Deleting and moving several files at once
+//
+// Hotline has no batch transactions, so a multi-selection is worked through
+// one item at a time, in order. A failure doesn't stop the rest: every item
+// gets its own result, and the batch says which folders' listings changed so
+// the caller can refresh them once instead of after every item.
+
+use super::HotlineClient;
+use serde::{Deserialize, Serialize};
+
+/// A file or folder on the server, by the folder it's in and its name
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileRef {
+    pub path: Vec<String>,
+    pub file_name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchItemResult {
+    pub path: Vec<String>,
+    pub file_name: String,
+    /// The server's error, None when the item was done
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchResult {
+    /// One per item, in the order they were given
+    pub items: Vec<BatchItemResult>,
+    pub succeeded: usize,
+    pub failed: usize,
+    /// Folders whose listings changed, each once
+    pub changed_folders: Vec<Vec<String>>,
+}
+
+impl BatchResult {
+    /// `destination` is where moved items went, if anywhere
+    fn collect(items: Vec<BatchItemResult>, destination: Option<&[String]>) -> Self {
+        let mut folders: Vec<&[String]> = items.iter().filter(|item| item.error.is_none()).map(|item| item.path.as_slice()).collect();
+        let succeeded = folders.len();
+        if succeeded > 0 {
+            folders.extend(destination);
+        }
+
+        let mut changed_folders: Vec<Vec<String>> = Vec::new();
+        for folder in folders {
+            if !changed_folders.iter().any(|changed| changed == folder) {
+                changed_folders.push(folder.to_vec());
+            }
+        }
+        Self {
+            failed: items.len() - succeeded,
+            succeeded,
+            items,
+            changed_folders,
+        }
+    }
+}
+
+impl HotlineClient {
+    pub async fn delete_files(&self, files: Vec<FileRef>) -> BatchResult {
+        let mut items = Vec::with_capacity(files.len());
+        for file in files {
+            let error = self.delete_file(file.path.clone(), file.file_name.clone()).await.err();
+            items.push(BatchItemResult { path: file.path, file_name: file.file_name, error });
+        }
+        BatchResult::collect(items, None)
+    }
+
+    /// Move every file into `new_path` (an empty path is the root)
+    pub async fn move_files(&self, files: Vec<FileRef>, new_path: Vec<String>) -> BatchResult {
+        let mut items = Vec::with_capacity(files.len());
+        for file in files {
+            let error = self.move_file(file.path.clone(), file.file_name.clone(), new_path.clone()).await.err();
+            items.push(BatchItemResult { path: file.path, file_name: file.file_name, error });
+        }
+        BatchResult::collect(items, Some(&new_path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(path: &[&str], file_name: &str, error: Option<&str>) -> BatchItemResult {
+        BatchItemResult {
+            path: path.iter().map(|part| part.to_string()).collect(),
+            file_name: file_name.to_string(),
+            error: error.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn partial_failures_are_counted_and_changed_folders_listed_once() {
+        let destination = vec!["Archive".to_string()];
+        let result = BatchResult::collect(
+            vec![
+                item(&["Uploads"], "a.txt", None),
+                item(&["Uploads"], "b.txt", None),
+                item(&["Drop Box"], "c.txt", Some("You are not allowed to move files.")),
+            ],
+            Some(&destination),
+        );
+
+        assert_eq!((result.succeeded, result.failed), (2, 1));
+        assert_eq!(result.changed_folders, vec![vec!["Uploads".to_string()], destination.clone()]);
+
+        // Nothing moved, so nothing changed
+        let failed = BatchResult::collect(vec![item(&["Uploads"], "a.txt", Some("Missing"))], Some(&destination));
+        assert!(failed.changed_folders.is_empty());
+    }
+}
@@ -0,0 +1,191 @@
+// What kind of file a listing entry is, for the file browser's icons
+//
+// Classic Mac files say what they are through their four-character type and
+// creator codes. Files that came from other systems usually carry a generic
+// type the server made up, so the name extension is the fallback. These
+// tables are the only copy; the file browser just draws the kind it's given.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FileKind {
+    Folder,
+    Archive,
+    Image,
+    Audio,
+    Video,
+    Text,
+    Document,
+    Application,
+    /// Nothing we recognize
+    #[default]
+    File,
+}
+
+const TYPE_CODES: &[(&[u8; 4], FileKind)] = &[
+    (b"fldr", FileKind::Folder),
+    (b"APPL", FileKind::Application),
+    (b"appe", FileKind::Application),
+    (b"APPC", FileKind::Application),
+    (b"APPD", FileKind::Application),
+    (b"cdev", FileKind::Application),
+    (b"INIT", FileKind::Application),
+    (b"SIT!", FileKind::Archive),
+    (b"SITD", FileKind::Archive),
+    (b"SIT5", FileKind::Archive),
+    (b"SITX", FileKind::Archive),
+    (b"ZIP ", FileKind::Archive),
+    (b"Gzip", FileKind::Archive),
+    (b"TARF", FileKind::Archive),
+    (b"PACT", FileKind::Archive),
+    (b"dImg", FileKind::Archive),
+    (b"rohd", FileKind::Archive),
+    (b"GIFf", FileKind::Image),
+    (b"JPEG", FileKind::Image),
+    (b"PICT", FileKind::Image),
+    (b"PNGf", FileKind::Image),
+    (b"TIFF", FileKind::Image),
+    (b"BMP ", FileKind::Image),
+    (b"BMPp", FileKind::Image),
+    (b"8BPS", FileKind::Image),
+    (b"MPG3", FileKind::Audio),
+    (b"Mp3 ", FileKind::Audio),
+    (b"MP3 ", FileKind::Audio),
+    (b"AIFF", FileKind::Audio),
+    (b"AIFC", FileKind::Audio),
+    (b"WAVE", FileKind::Audio),
+    (b"sfil", FileKind::Audio),
+    (b"MooV", FileKind::Video),
+    (b"MPEG", FileKind::Video),
+    (b"VfW ", FileKind::Video),
+    (b"TEXT", FileKind::Text),
+    (b"ttro", FileKind::Text),
+    (b"utxt", FileKind::Text),
+    (b"PDF ", FileKind::Document),
+    (b"RTF ", FileKind::Document),
+    (b"W8BN", FileKind::Document),
+    (b"WDBN", FileKind::Document),
+    (b"XLS8", FileKind::Document),
+];
+
+/// Creators whose files are one kind whatever type they carry
+const CREATOR_CODES: &[(&[u8; 4], FileKind)] = &[
+    (b"SIT!", FileKind::Archive),
+    (b"aust", FileKind::Archive),
+    (b"TVOD", FileKind::Video),
+    (b"ogle", FileKind::Image),
+    (b"CARO", FileKind::Document),
+    (b"MSWD", FileKind::Document),
+];
+
+const EXTENSIONS: &[(&str, FileKind)] = &[
+    ("zip", FileKind::Archive),
+    ("sit", FileKind::Archive),
+    ("sitx", FileKind::Archive),
+    ("sea", FileKind::Archive),
+    ("hqx", FileKind::Archive),
+    ("bin", FileKind::Archive),
+    ("cpt", FileKind::Archive),
+    ("gz", FileKind::Archive),
+    ("tgz", FileKind::Archive),
+    ("tar", FileKind::Archive),
+    ("bz2", FileKind::Archive),
+    ("7z", FileKind::Archive),
+    ("rar", FileKind::Archive),
+    ("dmg", FileKind::Archive),
+    ("img", FileKind::Archive),
+    ("iso", FileKind::Archive),
+    ("png", FileKind::Image),
+    ("jpg", FileKind::Image),
+    ("jpeg", FileKind::Image),
+    ("gif", FileKind::Image),
+    ("bmp", FileKind::Image),
+    ("tif", FileKind::Image),
+    ("tiff", FileKind::Image),
+    ("webp", FileKind::Image),
+    ("svg", FileKind::Image),
+    ("pict", FileKind::Image),
+    ("pct", FileKind::Image),
+    ("mp3", FileKind::Audio),
+    ("wav", FileKind::Audio),
+    ("ogg", FileKind::Audio),
+    ("oga", FileKind::Audio),
+    ("flac", FileKind::Audio),
+    ("m4a", FileKind::Audio),
+    ("aac", FileKind::Audio),
+    ("aif", FileKind::Audio),
+    ("aiff", FileKind::Audio),
+    ("mod", FileKind::Audio),
+    ("mp4", FileKind::Video),
+    ("m4v", FileKind::Video),
+    ("webm", FileKind::Video),
+    ("ogv", FileKind::Video),
+    ("mov", FileKind::Video),
+    ("avi", FileKind::Video),
+    ("mpg", FileKind::Video),
+    ("mpeg", FileKind::Video),
+    ("mkv", FileKind::Video),
+    ("txt", FileKind::Text),
+    ("md", FileKind::Text),
+    ("nfo", FileKind::Text),
+    ("log", FileKind::Text),
+    ("json", FileKind::Text),
+    ("xml", FileKind::Text),
+    ("html", FileKind::Text),
+    ("htm", FileKind::Text),
+    ("css", FileKind::Text),
+    ("js", FileKind::Text),
+    ("pdf", FileKind::Document),
+    ("rtf", FileKind::Document),
+    ("doc", FileKind::Document),
+    ("docx", FileKind::Document),
+    ("xls", FileKind::Document),
+    ("xlsx", FileKind::Document),
+    ("ppt", FileKind::Document),
+    ("pptx", FileKind::Document),
+    ("odt", FileKind::Document),
+    ("exe", FileKind::Application),
+    ("msi", FileKind::Application),
+    ("app", FileKind::Application),
+];
+
+fn lookup(table: &[(&[u8; 4], FileKind)], code: &[u8]) -> Option<FileKind> {
+    table.iter().find(|(known, _)| known.as_slice() == code).map(|(_, kind)| *kind)
+}
+
+impl FileKind {
+    /// Resolve from the type code, then the creator, then the name extension
+    pub fn classify(file_type: &[u8], creator: &[u8], name: &str) -> Self {
+        lookup(TYPE_CODES, file_type)
+            .or_else(|| lookup(CREATOR_CODES, creator))
+            .or_else(|| {
+                let (_, extension) = name.rsplit_once('.')?;
+                let extension = extension.to_ascii_lowercase();
+                EXTENSIONS.iter().find(|(known, _)| *known == extension).map(|(_, kind)| *kind)
+            })
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn type_code_wins_over_creator_and_extension() {
+        assert_eq!(FileKind::classify(b"fldr", b"n/a ", "Uploads"), FileKind::Folder);
+        assert_eq!(FileKind::classify(b"SITD", b"SIT!", "Game.sit"), FileKind::Archive);
+        assert_eq!(FileKind::classify(b"APPL", b"WILD", "Marathon"), FileKind::Application);
+        assert_eq!(FileKind::classify(b"TEXT", b"ttxt", "notes.png"), FileKind::Text);
+        assert_eq!(FileKind::classify(b"????", b"MSWD", "Letter"), FileKind::Document);
+    }
+
+    #[test]
+    fn generic_types_fall_back_to_the_extension() {
+        assert_eq!(FileKind::classify(b"BINA", b"dosa", "photo.JPG"), FileKind::Image);
+        assert_eq!(FileKind::classify(b"\0\0\0\0", b"\0\0\0\0", "song.flac"), FileKind::Audio);
+        assert_eq!(FileKind::classify(b"BINA", b"dosa", "README"), FileKind::File);
+        assert_eq!(FileKind::classify(b"BINA", b"dosa", "archive."), FileKind::File);
+    }
+}
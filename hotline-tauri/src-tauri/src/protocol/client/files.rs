@@ -1,12 +1,27 @@
 // File management functionality for Hotline client
 
+use super::file_kind::FileKind;
+use super::mac_forks::{MacForks, UploadFile};
+use super::replies::{error_text, DownloadFileReply};
+use super::requests::{
+    DeleteFileRequest, DownloadFileRequest, FileTarget, GetFileInfoRequest, MakeFileAliasRequest,
+    MoveFileRequest, NewFolderRequest, SetFileInfoRequest, UploadFileRequest,
+};
+use super::transfer_queue::TransferSlot;
 use super::{BoxedRead, BoxedWrite, FileInfo, HotlineClient};
+use crate::protocol::cancel::{check_cancelled, CancellationToken};
 use crate::protocol::constants::{FieldType, TransactionType, FILE_TRANSFER_ID};
 use crate::protocol::transaction::{Transaction, TransactionField};
+use serde::Serialize;
 use std::time::Duration;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::io::{AsyncReadExt, AsyncWrite, AsyncWriteExt};
 use tokio::net::TcpStream;
-use tokio::sync::mpsc;
+
+/// Largest kept resource fork; the classic Resource Manager can't address more
+const MAX_RESOURCE_FORK_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Largest kept INFO fork, which only holds a name, comment and dates
+const MAX_INFO_FORK_SIZE: u64 = 64 * 1024;
 
 /// Encode a UTF-8 folder name to bytes suitable for the Hotline FilePath field.
 /// Tries MacRoman encoding first (which is what the protocol uses natively).
@@ -24,7 +39,7 @@ fn encode_path_component(name: &str) -> Vec<u8> {
 
 /// Build the binary FilePath field data from a path component list.
 /// Returns None if path is empty (no field needed).
-fn encode_file_path(path: &[String]) -> Option<Vec<u8>> {
+pub(super) fn encode_file_path(path: &[String]) -> Option<Vec<u8>> {
     if path.is_empty() {
         return None;
     }
@@ -51,10 +66,156 @@ fn encode_file_path(path: &[String]) -> Option<Vec<u8>> {
     Some(path_data)
 }
 
+/// Build FileResumeData (RFLT) asking the server to resume the DATA fork at `data_offset`.
+/// Layout: "RFLT" + version 1 + 34 reserved bytes + fork count, then per fork:
+/// fork type (4) + bytes already received (4) + 8 reserved bytes.
+pub(crate) fn encode_resume_data(data_offset: u32) -> Vec<u8> {
+    let mut data = Vec::with_capacity(40 + 2 * 16);
+    data.extend_from_slice(b"RFLT");
+    data.extend_from_slice(&1u16.to_be_bytes());
+    data.extend_from_slice(&[0u8; 34]);
+    data.extend_from_slice(&2u16.to_be_bytes());
+    for (fork, size) in [(b"DATA", data_offset), (b"MACR", 0)] {
+        data.extend_from_slice(fork);
+        data.extend_from_slice(&size.to_be_bytes());
+        data.extend_from_slice(&[0u8; 8]);
+    }
+    data
+}
+
+/// FileTransferOptions value asking the server to resume a transfer
+pub(super) const TRANSFER_OPTION_RESUME: u16 = 2;
+
+/// How much of each fork the server already has, from the FileResumeData
+/// (RFLT) it sends back when an upload continues an earlier attempt
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct ResumeOffsets {
+    pub data: u64,
+    pub resource: u64,
+}
+
+/// Read the per-fork offsets out of FileResumeData. Forks the server doesn't
+/// list start from the beginning.
+pub(crate) fn decode_resume_data(data: &[u8]) -> Option<ResumeOffsets> {
+    if data.len() < 42 || &data[0..4] != b"RFLT" {
+        return None;
+    }
+    let fork_count = u16::from_be_bytes([data[40], data[41]]) as usize;
+    let mut offsets = ResumeOffsets::default();
+    for entry in data[42..].chunks_exact(16).take(fork_count) {
+        let size = u64::from(u32::from_be_bytes([entry[4], entry[5], entry[6], entry[7]]));
+        match &entry[0..4] {
+            b"DATA" => offsets.data = size,
+            b"MACR" => offsets.resource = size,
+            _ => {}
+        }
+    }
+    Some(offsets)
+}
+
+/// Build a 16-byte fork header: fork type, compression (none), then the size
+/// as 8 bytes. Below 4GB the high word is the zero "reserved" field older
+/// peers expect, so only larger forks use the extended size.
+pub(crate) fn fork_header(fork_type: &[u8; 4], size: u64) -> [u8; 16] {
+    let mut header = [0u8; 16];
+    header[0..4].copy_from_slice(fork_type);
+    header[8..16].copy_from_slice(&size.to_be_bytes());
+    header
+}
+
+/// Size of a fork from its 16-byte header, including the extended high word
+pub(crate) fn fork_size(header: &[u8; 16]) -> u64 {
+    u64::from_be_bytes([
+        header[8], header[9], header[10], header[11],
+        header[12], header[13], header[14], header[15],
+    ])
+}
+
+/// Bytes to read for a DATA fork, given its header size and the size the
+/// server reported for the file. Some servers leave the header at 0, and
+/// servers without extended sizes only send the low 32 bits of a fork over
+/// 4GB; the reported size fills in both cases.
+pub(crate) fn data_fork_size(header_size: u64, expected_size: u64) -> u64 {
+    if header_size == 0 && expected_size > 0 {
+        println!("Fork header shows 0 size, using expected size: {} bytes", expected_size);
+        return expected_size;
+    }
+    if expected_size > u64::from(u32::MAX) && header_size == expected_size & u64::from(u32::MAX) {
+        return expected_size;
+    }
+    if expected_size > 0 && header_size != expected_size {
+        println!("Note: DATA fork header size ({}) differs from expected size ({})", header_size, expected_size);
+    }
+    header_size
+}
+
+/// Details from GetFileInfo. Dates are Unix timestamps in seconds.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileDetails {
+    pub name: String,
+    /// Human-readable kind, e.g. "Text File"
+    pub type_name: String,
+    pub creator_name: String,
+    /// Four-character type code, e.g. "TEXT"
+    pub type_code: String,
+    pub comment: String,
+    pub size: Option<u64>,
+    pub created: Option<i64>,
+    pub modified: Option<i64>,
+}
+
+/// Convert a Hotline date (year (2), milliseconds (2), seconds since the
+/// start of that year (4)) to a Unix timestamp
+pub(crate) fn hotline_date_to_unix(data: &[u8]) -> Option<i64> {
+    if data.len() != 8 {
+        return None;
+    }
+    let year = u16::from_be_bytes([data[0], data[1]]) as i64;
+    let seconds = u32::from_be_bytes([data[4], data[5], data[6], data[7]]) as i64;
+    if year == 0 {
+        return None;
+    }
+
+    // Days from 1970-01-01 to January 1st of `year`
+    let y = year - 1;
+    let days = 365 * (year - 1970) + (y / 4 - y / 100 + y / 400) - (1969 / 4 - 1969 / 100 + 1969 / 400);
+    Some(days * 86_400 + seconds)
+}
+
+/// Convert a Unix timestamp to a Hotline date
+pub(crate) fn unix_to_hotline_date(unix: i64) -> [u8; 8] {
+    let is_leap = |y: i64| (y % 4 == 0 && y % 100 != 0) || y % 400 == 0;
+    let year_secs = |y: i64| if is_leap(y) { 366 * 86_400 } else { 365 * 86_400 };
+
+    let mut year = 1970;
+    let mut seconds = unix;
+    while seconds < 0 {
+        year -= 1;
+        seconds += year_secs(year);
+    }
+    while seconds >= year_secs(year) {
+        seconds -= year_secs(year);
+        year += 1;
+    }
+
+    let mut date = [0u8; 8];
+    date[0..2].copy_from_slice(&(year as u16).to_be_bytes());
+    date[4..8].copy_from_slice(&(seconds as u32).to_be_bytes());
+    date
+}
+
 impl HotlineClient {
     /// Create a transfer connection (plain TCP or TLS) to the file transfer port.
-    /// File transfers use main port + 1.
-    async fn create_transfer_stream(&self) -> Result<(BoxedRead, BoxedWrite), String> {
+    /// File transfers use main port + 1. The returned slot counts against the
+    /// per-host connection cap and must be kept until the transfer is done.
+    pub(super) async fn create_transfer_stream(&self) -> Result<(BoxedRead, BoxedWrite, Option<TransferSlot>), String> {
+        let slots = self.transfer_slots.lock().await.clone();
+        let slot = match slots {
+            Some(slots) => Some(slots.acquire(&self.bookmark.address).await),
+            None => None,
+        };
+
         let transfer_port = self.bookmark.port + 1;
         let addr = crate::protocol::socket_addr_string(&self.bookmark.address, transfer_port);
         println!("Connecting to file transfer port: {}", transfer_port);
@@ -66,10 +227,10 @@ impl HotlineClient {
         if self.bookmark.tls {
             let tls_stream = Self::wrap_tls(tcp_stream, &self.bookmark.address).await?;
             let (read_half, write_half) = tokio::io::split(tls_stream);
-            Ok((Box::new(read_half), Box::new(write_half)))
+            Ok((Box::new(read_half), Box::new(write_half), slot))
         } else {
             let (read_half, write_half) = tcp_stream.into_split();
-            Ok((Box::new(read_half), Box::new(write_half)))
+            Ok((Box::new(read_half), Box::new(write_half), slot))
         }
     }
 
@@ -94,150 +255,75 @@ impl HotlineClient {
             });
         }
 
-        let encoded = transaction.encode();
-
         println!("Sending GetFileNameList transaction...");
-        let mut write_guard = self.write_half.lock().await;
-        let write_stream = write_guard
-            .as_mut()
-            .ok_or("Not connected".to_string())?;
-
-        write_stream
-            .write_all(&encoded)
-            .await
-            .map_err(|e| format!("Failed to send GetFileNameList: {}", e))?;
-
-        write_stream
-            .flush()
-            .await
-            .map_err(|e| format!("Failed to flush stream: {}", e))?;
+        self.send_transaction(transaction, "send GetFileNameList").await?;
 
         println!("GetFileNameList request sent");
 
         Ok(())
     }
 
-    pub async fn download_file(&self, path: Vec<String>, file_name: String) -> Result<(u32, Option<u32>), String> {
-        println!("Requesting download for file: {:?} / {}", path, file_name);
-
-        let mut transaction = Transaction::new(self.next_transaction_id(), TransactionType::DownloadFile);
-
-        // Add FileName field
-        transaction.add_field(TransactionField::from_string(FieldType::FileName, &file_name));
+    /// Request a download. A non-zero `resume_offset` asks the server to skip
+    /// that many bytes of the data fork when resuming a partial download.
+    pub async fn download_file(&self, path: Vec<String>, file_name: String, resume_offset: u32) -> Result<DownloadFileReply, String> {
+        println!("Requesting download for file: {:?} / {} (offset {})", path, file_name, resume_offset);
 
-        // Add FilePath field if not at root
-        if let Some(path_data) = encode_file_path(&path) {
-            transaction.add_field(TransactionField {
-                field_type: FieldType::FilePath,
-                data: path_data,
-            });
-        }
-
-        let encoded = transaction.encode();
-        let transaction_id = transaction.id;
-
-        // Create channel to receive reply
-        let (tx, mut rx) = mpsc::channel(1);
-        {
-            let mut pending = self.pending_transactions.write().await;
-            pending.insert(transaction_id, tx);
-        }
+        let transaction = self.request(&DownloadFileRequest {
+            file: FileTarget { path: &path, file_name: &file_name },
+            resume_offset,
+        });
 
-        // Send transaction
+        // Send transaction and wait for reply
         println!("Sending DownloadFile transaction...");
-        let mut write_guard = self.write_half.lock().await;
-        let write_stream = write_guard
-            .as_mut()
-            .ok_or("Not connected".to_string())?;
-
-        write_stream
-            .write_all(&encoded)
-            .await
-            .map_err(|e| format!("Failed to send DownloadFile: {}", e))?;
-
-        write_stream
-            .flush()
-            .await
-            .map_err(|e| format!("Failed to flush stream: {}", e))?;
-
-        drop(write_guard);
-
-        // Wait for reply
-        println!("Waiting for DownloadFile reply...");
-        let reply = match tokio::time::timeout(Duration::from_secs(10), rx.recv()).await {
-            Ok(Some(reply)) => reply,
-            Ok(None) => {
-                // Channel closed, remove from pending
-                let mut pending = self.pending_transactions.write().await;
-                pending.remove(&transaction_id);
-                return Err("Channel closed".to_string());
-            }
-            Err(_) => {
-                // Timeout, remove from pending
-                let mut pending = self.pending_transactions.write().await;
-                pending.remove(&transaction_id);
-                return Err("Timeout waiting for download reply".to_string());
-            }
-        };
+        let reply = self.send_and_wait_reply(transaction, "send DownloadFile").await?;
 
         println!("DownloadFile reply received: error_code={}, {} fields", reply.error_code, reply.fields.len());
 
         // Print all fields for debugging
         for (i, field) in reply.fields.iter().enumerate() {
+            let data = field.loggable_data();
             println!("  Field {}: type={:?}, size={} bytes, data={:02X?}",
                 i, field.field_type, field.data.len(),
-                &field.data[..std::cmp::min(20, field.data.len())]);
+                &data[..std::cmp::min(20, data.len())]);
         }
 
         if reply.error_code != 0 {
-            let error_msg = reply
-                .get_field(FieldType::ErrorText)
-                .and_then(|f| f.to_string().ok())
-                .unwrap_or_else(|| format!("Error code: {}", reply.error_code));
-            return Err(format!("Download failed: {}", error_msg));
+            return Err(format!("Download failed: {}", error_text(&reply)));
         }
 
-        // Get reference number from reply
-        let reference_number = reply
-            .get_field(FieldType::ReferenceNumber)
-            .and_then(|f| f.to_u32().ok())
-            .ok_or("No reference number in reply".to_string())?;
-
-        println!("Download reference number: {}", reference_number);
-
-        // Get transfer size if available
-        let transfer_size = reply.get_field(FieldType::TransferSize)
-            .and_then(|f| f.to_u32().ok());
-
-        if let Some(size) = transfer_size {
+        let download = DownloadFileReply::from_reply(&reply)?;
+        println!("Download reference number: {}", download.reference_number);
+        if let Some(size) = download.transfer_size {
             println!("Transfer size from server: {} bytes", size);
         }
-
-        // Get file size if available
-        let file_size = reply.get_field(FieldType::FileSize)
-            .and_then(|f| f.to_u32().ok());
-
-        if let Some(size) = file_size {
+        if let Some(size) = download.file_size {
             println!("File size from server: {} bytes", size);
         }
+        if download.waiting_count > 0 {
+            println!("Download queued behind {} others", download.waiting_count);
+        }
 
         // Check for file transfer options
         if let Some(options_field) = reply.get_field(FieldType::FileTransferOptions) {
             println!("File transfer options: {:02X?}", options_field.data);
         }
 
-        // Return both reference number and server-reported file size
-        Ok((reference_number, file_size))
+        Ok(download)
     }
 
-    pub async fn perform_file_transfer<F>(&self, reference_number: u32, expected_size: u32, mut progress_callback: F) -> Result<Vec<u8>, String>
+    /// Receive a file over the transfer port, writing the DATA fork into `sink`.
+    /// INFO and MACR forks are kept in `forks` when given, otherwise dropped.
+    /// Returns the number of DATA fork bytes written, which can be less than
+    /// `expected_size` if the server closed the connection early.
+    pub async fn perform_file_transfer<W, F>(&self, reference_number: u32, expected_size: u64, sink: &mut W, mut forks: Option<&mut MacForks>, cancel: &CancellationToken, mut progress_callback: F) -> Result<u64, String>
     where
-        F: FnMut(u32, u32) + Send,
+        W: AsyncWrite + Unpin + Send,
+        F: FnMut(u64, u64) + Send,
     {
         println!("Starting file transfer with reference number: {}", reference_number);
 
         // Open a new connection (TCP or TLS) to the server for file transfer
-        let (mut transfer_read, mut transfer_write) = self.create_transfer_stream().await?;
+        let (mut transfer_read, mut transfer_write, _slot) = self.create_transfer_stream().await?;
 
         println!("File transfer connection established");
 
@@ -315,14 +401,13 @@ impl HotlineClient {
         println!("File has {} fork(s)", fork_count);
 
         // Read each fork header and data
-        let mut file_data = Vec::new();
+        let mut data_bytes_written = 0u64;
 
         for fork_idx in 0..fork_count {
             // Fork header format:
             // Fork type (4 bytes) - "DATA" or "MACR" (resource fork) or "INFO"
             // Compression type (4 bytes)
-            // Reserved (4 bytes)
-            // Data size (4 bytes)
+            // Data size (8 bytes) - the high word is "reserved" (zero) below 4GB
             let mut fork_header = [0u8; 16];
             transfer_read
                 .read_exact(&mut fork_header)
@@ -333,161 +418,111 @@ impl HotlineClient {
 
             let fork_type = String::from_utf8_lossy(&fork_header[0..4]).to_string();
             let compression = u32::from_be_bytes([fork_header[4], fork_header[5], fork_header[6], fork_header[7]]);
-            let data_size = u32::from_be_bytes([fork_header[12], fork_header[13], fork_header[14], fork_header[15]]);
+            let data_size = fork_size(&fork_header);
 
             println!("Fork {}: type='{}', compression={}, size={} bytes", fork_idx, fork_type.trim(), compression, data_size);
 
-            // Determine actual size to read
-            // If fork header shows 0 size but this is a DATA fork, use expected_size
-            // Note: The Hotline protocol uses u32 for file sizes, which limits files to ~4.3GB (u32::MAX)
-            // We allow files up to this limit. To support larger files (like 25GB), the protocol would need
-            // to be extended to use u64, which is a significant change.
-            let (actual_size, read_until_eof) = if data_size == 0 && fork_type.trim() == "DATA" && expected_size > 0 {
-                // Check for suspicious round numbers that might indicate corruption (like exactly 2GB)
-                // These specific values often indicate encoding/parsing issues with unicode filenames
-                if expected_size == 2_147_483_648 || expected_size == 2_161_946_800 {
-                    return Err(format!(
-                        "File size from file list ({}) appears to be corrupted (suspicious round number). Fork header shows size=0. This may be due to a unicode encoding issue in the filename. Please try refreshing the file list or contact the server administrator.",
-                        expected_size
-                    ));
-                }
-                
-                // Check for suspiciously large file sizes (> 2GB) when fork header shows 0
-                // This often indicates file list corruption, especially with unicode filenames
-                // Instead of rejecting, we'll try to read until EOF as a workaround
-                const SUSPICIOUS_FILE_SIZE_THRESHOLD: u32 = 2_000_000_000; // 2GB
-                let is_suspicious = expected_size > SUSPICIOUS_FILE_SIZE_THRESHOLD;
-                
-                if is_suspicious {
-                    println!("WARNING: File size from file list ({:.2} GB) is suspiciously large and fork header shows size=0. This likely indicates file list corruption, possibly due to unicode encoding issues in the filename. Attempting to read until EOF as a workaround...", expected_size as f64 / 1_000_000_000.0);
-                } else {
-                    println!("Fork header shows 0 size, using expected size from file list: {} bytes ({:.2} MB)", expected_size, expected_size as f64 / 1_000_000.0);
-                }
-                
-                // If suspicious, we'll read until EOF instead of expecting the full size
-                (expected_size, is_suspicious)
+            let actual_size = if fork_type.trim() == "DATA" {
+                data_fork_size(data_size, expected_size)
             } else {
-                if fork_type.trim() == "DATA" && data_size != expected_size && expected_size > 0 {
-                    println!("Note: DATA fork header size ({}) differs from file list size ({})", data_size, expected_size);
-                }
-                (data_size, false)
+                data_size
             };
 
             // Read fork data
-            if actual_size > 0 || read_until_eof {
+            if actual_size > 0 {
                 let is_data_fork = fork_type.trim() == "DATA";
 
                 if is_data_fork {
-                    // For DATA fork, read in chunks and report progress
-                    // For very large files, we need to be careful about memory
+                    // For DATA fork, read in chunks, write them straight to the sink and report progress
                     let chunk_size = 65536; // 64KB chunks
-                    // Don't pre-allocate the entire vector for huge files - let it grow naturally
-                    // but reserve a reasonable amount to avoid too many reallocations
-                    // For files > 100MB, use a smaller initial capacity to avoid memory issues
-                    let initial_capacity = if read_until_eof {
-                        1024 * 1024 // 1MB default for read-until-EOF mode
-                    } else if actual_size > 100_000_000 {
-                        std::cmp::min(actual_size as usize / 100, 10 * 1024 * 1024) // Max 10MB initial for huge files
-                    } else {
-                        std::cmp::min(actual_size as usize, 10 * 1024 * 1024) // Max 10MB initial
-                    };
-                    let mut fork_data = Vec::with_capacity(initial_capacity);
-                    let mut bytes_read = 0u32;
+                    let mut chunk = vec![0u8; chunk_size];
+                    let mut bytes_read = 0u64;
                     let mut last_reported_progress = 0u32;
-
-                    if read_until_eof {
-                        // Read until EOF as a workaround for corrupted file sizes
-                        println!("Reading file until EOF (file list size may be corrupted)...");
-                        loop {
-                            let mut chunk = vec![0u8; chunk_size];
-                            
-                            match transfer_read.read(&mut chunk).await {
-                                Ok(0) => {
-                                    // EOF reached
-                                    println!("EOF reached after reading {} bytes", bytes_read);
-                                    break;
-                                }
-                                Ok(n) => {
-                                    chunk.truncate(n);
-                                    bytes_read += n as u32;
-                                    fork_data.extend_from_slice(&chunk);
-                                    
-                                    // Report progress using bytes_read as both current and total (since we don't know the total)
-                                    // This will show progress but percentage will be approximate
-                                    if bytes_read % (1024 * 1024) == 0 || bytes_read < 1024 * 1024 {
-                                        // Report every MB or for small files
-                                        progress_callback(bytes_read, bytes_read.max(1));
-                                    }
-                                }
-                                Err(e) => {
-                                    // If we've read some data, treat EOF as success
-                                    if bytes_read > 0 && e.kind() == std::io::ErrorKind::UnexpectedEof {
-                                        println!("EOF reached after reading {} bytes (unexpected EOF)", bytes_read);
-                                        break;
-                                    }
-                                    return Err(format!("Failed to read fork {} data: {}", fork_idx, e));
+                    let mut limiter = self.download_limiter().await;
+
+                    while bytes_read < actual_size {
+                        check_cancelled(cancel)?;
+                        let remaining = actual_size - bytes_read;
+                        let to_read = std::cmp::min(remaining, chunk_size as u64) as usize;
+                        limiter.acquire(to_read).await;
+
+                        // Use read_exact with better error handling for large files
+                        match transfer_read.read_exact(&mut chunk[..to_read]).await {
+                            Ok(_) => {
+                                sink.write_all(&chunk[..to_read])
+                                    .await
+                                    .map_err(|e| format!("Failed to write downloaded data: {}", e))?;
+                                bytes_read += to_read as u64;
+
+                                // Only emit progress every 2% or on completion to avoid UI stuttering
+                                let current_progress = (bytes_read as f64 / actual_size as f64 * 100.0) as u32;
+                                if current_progress >= last_reported_progress + 2 || bytes_read == actual_size {
+                                    progress_callback(bytes_read, actual_size);
+                                    last_reported_progress = current_progress;
                                 }
                             }
-                        }
-                        println!("Received DATA fork: {} bytes (read until EOF)", fork_data.len());
-                    } else {
-                        // Normal read with known size
-                        while bytes_read < actual_size {
-                            let remaining = actual_size - bytes_read;
-                            let to_read = std::cmp::min(remaining, chunk_size as u32) as usize;
-                            let mut chunk = vec![0u8; to_read];
-
-                            // Use read_exact with better error handling for large files
-                            match transfer_read.read_exact(&mut chunk).await {
-                                Ok(_) => {
-                                    bytes_read += to_read as u32;
-                                    fork_data.extend_from_slice(&chunk);
-
-                                    // Only emit progress every 2% or on completion to avoid UI stuttering
-                                    let current_progress = (bytes_read as f64 / actual_size as f64 * 100.0) as u32;
-                                    if current_progress >= last_reported_progress + 2 || bytes_read == actual_size {
-                                        progress_callback(bytes_read, actual_size);
-                                        last_reported_progress = current_progress;
-                                    }
-                                }
-                                Err(e) => {
-                                    // If we get an error, check if it's EOF and we've read some data
-                                    if bytes_read > 0 && e.kind() == std::io::ErrorKind::UnexpectedEof {
-                                        println!("Warning: Early EOF after reading {} of {} bytes. File may be incomplete.", bytes_read, actual_size);
-                                        // Continue with what we have
-                                        break;
-                                    }
-                                    return Err(format!("Failed to read fork {} data at offset {}: {}", fork_idx, bytes_read, e));
+                            Err(e) => {
+                                // If we get an error, check if it's EOF and we've read some data
+                                if bytes_read > 0 && e.kind() == std::io::ErrorKind::UnexpectedEof {
+                                    println!("Warning: Early EOF after reading {} of {} bytes. File may be incomplete.", bytes_read, actual_size);
+                                    // Continue with what we have
+                                    break;
                                 }
+                                return Err(format!("Failed to read fork {} data at offset {}: {}", fork_idx, bytes_read, e));
                             }
                         }
-                        println!("Received DATA fork: {} bytes (expected: {} bytes)", fork_data.len(), actual_size);
-                        if fork_data.len() as u32 != actual_size {
-                            println!("Warning: Received {} bytes but expected {} bytes. File may be incomplete.", fork_data.len(), actual_size);
-                        }
                     }
-                    
-                    file_data = fork_data;
-                } else {
-                    // For INFO/MACR forks, read all at once
-                    let mut fork_data = vec![0u8; actual_size as usize];
-                    transfer_read
-                        .read_exact(&mut fork_data)
+                    println!("Received DATA fork: {} bytes (expected: {} bytes)", bytes_read, actual_size);
+                    if bytes_read != actual_size {
+                        println!("Warning: Received {} bytes but expected {} bytes. File may be incomplete.", bytes_read, actual_size);
+                    }
+
+                    sink.flush()
                         .await
-                        .map_err(|e| format!("Failed to read fork {} data: {}", fork_idx, e))?;
+                        .map_err(|e| format!("Failed to flush downloaded data: {}", e))?;
+                    data_bytes_written = bytes_read;
+                } else {
+                    let kind = fork_type.trim();
+                    let slot = match (kind, forks.as_deref_mut()) {
+                        ("INFO", Some(forks)) => Some(&mut forks.info),
+                        ("MACR", Some(forks)) => Some(&mut forks.resource),
+                        _ => None,
+                    };
 
-                    if fork_type.trim() == "INFO" {
-                        println!("Skipped INFO fork: {} bytes", fork_data.len());
-                    } else if fork_type.trim() == "MACR" {
-                        println!("Skipped MACR (resource) fork: {} bytes", fork_data.len());
+                    match slot {
+                        Some(slot) => {
+                            // Kept INFO/MACR forks are read all at once, so a
+                            // server can't make us allocate more than a Mac file holds
+                            let max_size = if kind == "INFO" { MAX_INFO_FORK_SIZE } else { MAX_RESOURCE_FORK_SIZE };
+                            if actual_size > max_size {
+                                return Err(format!("{} fork is {} bytes, more than the {} bytes allowed", kind, actual_size, max_size));
+                            }
+                            let mut fork_data = vec![0u8; actual_size as usize];
+                            transfer_read
+                                .read_exact(&mut fork_data)
+                                .await
+                                .map_err(|e| format!("Failed to read fork {} data: {}", fork_idx, e))?;
+                            println!("Kept {} fork: {} bytes", kind, fork_data.len());
+                            *slot = Some(fork_data);
+                        }
+                        None => {
+                            // Anything else is drained without buffering it
+                            let skipped = tokio::io::copy(&mut (&mut transfer_read).take(actual_size), &mut tokio::io::sink())
+                                .await
+                                .map_err(|e| format!("Failed to read fork {} data: {}", fork_idx, e))?;
+                            if skipped < actual_size {
+                                return Err(format!("Failed to read fork {} data: connection closed early", fork_idx));
+                            }
+                            println!("Skipped {} fork: {} bytes", kind, skipped);
+                        }
                     }
                 }
             }
         }
 
-        println!("File transfer complete: {} bytes received", file_data.len());
+        println!("File transfer complete: {} bytes received", data_bytes_written);
 
-        Ok(file_data)
+        Ok(data_bytes_written)
     }
 
     pub(crate) fn parse_file_info(data: &[u8]) -> Result<FileInfo, String> {
@@ -506,7 +541,7 @@ impl HotlineClient {
 
         let file_type = String::from_utf8_lossy(&data[0..4]).to_string();
         let creator = String::from_utf8_lossy(&data[4..8]).to_string();
-        let size = u32::from_be_bytes([data[8], data[9], data[10], data[11]]);
+        let size = u64::from(u32::from_be_bytes([data[8], data[9], data[10], data[11]]));
         // Skip bytes 12-15 (unknown/reserved)
         // Skip bytes 16-17 (unknown/flags)
         let name_len = u16::from_be_bytes([data[18], data[19]]) as usize;
@@ -519,6 +554,7 @@ impl HotlineClient {
 
         // Folders have file type "fldr"
         let is_folder = file_type.trim() == "fldr";
+        let kind = FileKind::classify(&data[0..4], &data[4..8], &name);
 
         Ok(FileInfo {
             name,
@@ -526,6 +562,7 @@ impl HotlineClient {
             is_folder,
             file_type,
             creator,
+            kind,
         })
     }
 
@@ -533,50 +570,15 @@ impl HotlineClient {
         println!("Requesting banner download...");
 
         let transaction = Transaction::new(self.next_transaction_id(), TransactionType::DownloadBanner);
-        let encoded = transaction.encode();
-        let transaction_id = transaction.id;
-
-        // Create channel to receive reply
-        let (tx, mut rx) = mpsc::channel(1);
-        {
-            let mut pending = self.pending_transactions.write().await;
-            pending.insert(transaction_id, tx);
-        }
 
-        // Send transaction
+        // Send transaction and wait for reply
         println!("Sending DownloadBanner transaction...");
-        let mut write_guard = self.write_half.lock().await;
-        let write_stream = write_guard
-            .as_mut()
-            .ok_or("Not connected".to_string())?;
-
-        write_stream
-            .write_all(&encoded)
-            .await
-            .map_err(|e| format!("Failed to send DownloadBanner: {}", e))?;
-
-        write_stream
-            .flush()
-            .await
-            .map_err(|e| format!("Failed to flush: {}", e))?;
-
-        drop(write_guard);
-
-        // Wait for reply
-        println!("Waiting for DownloadBanner reply...");
-        let reply = tokio::time::timeout(Duration::from_secs(10), rx.recv())
-            .await
-            .map_err(|_| "Timeout waiting for banner reply".to_string())?
-            .ok_or("Channel closed".to_string())?;
+        let reply = self.send_and_wait_reply(transaction, "send DownloadBanner").await?;
 
         println!("DownloadBanner reply received: error_code={}", reply.error_code);
 
         if reply.error_code != 0 {
-            let error_msg = reply
-                .get_field(FieldType::ErrorText)
-                .and_then(|f| f.to_string().ok())
-                .unwrap_or_else(|| format!("Error code: {}", reply.error_code));
-            return Err(format!("Banner download failed: {}", error_msg));
+            return Err(format!("Banner download failed: {}", error_text(&reply)));
         }
 
         // Get reference number and transfer size from reply
@@ -601,7 +603,7 @@ impl HotlineClient {
         println!("Starting banner download (raw data) with reference: {}, size: {} bytes", reference_number, transfer_size);
 
         // Open a new connection (TCP or TLS) for file transfer
-        let (mut transfer_read, mut transfer_write) = self.create_transfer_stream().await?;
+        let (mut transfer_read, mut transfer_write, _slot) = self.create_transfer_stream().await?;
 
         println!("Banner transfer connection established");
 
@@ -652,81 +654,37 @@ impl HotlineClient {
 
     /// Upload a file to the server
     /// - path: Directory path where the file should be uploaded
-    /// - file_name: Name of the file to upload
-    /// - file_data: The file contents to upload
+    /// - upload: Name, data and any Mac forks of the file to upload
+    /// - resume: Ask the server to continue a partial upload of the same file
+    ///   from where it stopped; it starts over when the server has none
     /// - progress_callback: Callback for progress updates (bytes_sent, total_bytes)
     pub async fn upload_file<F>(
         &self,
         path: Vec<String>,
-        file_name: String,
-        file_data: Vec<u8>,
+        upload: UploadFile,
+        resume: bool,
+        cancel: &CancellationToken,
         mut progress_callback: F,
     ) -> Result<(), String>
     where
-        F: FnMut(u32, u32),
+        F: FnMut(u64, u64),
     {
+        let file_name = &upload.file_name;
         println!("Requesting file upload: {} to path {:?}", file_name, path);
 
-        let transaction_id = self.next_transaction_id();
-        let mut transaction = Transaction::new(transaction_id, TransactionType::UploadFile);
-
-        // Add file name field
-        transaction.add_field(TransactionField {
-            field_type: FieldType::FileName,
-            data: file_name.as_bytes().to_vec(),
+        let transaction = self.request(&UploadFileRequest {
+            file: FileTarget { path: &path, file_name },
+            resume,
         });
 
-        // Add file path field if not root
-        if let Some(path_data) = encode_file_path(&path) {
-            transaction.add_field(TransactionField {
-                field_type: FieldType::FilePath,
-                data: path_data,
-            });
-        }
-
-        let encoded = transaction.encode();
-
-        // Create channel to receive reply
-        let (tx, mut rx) = mpsc::channel(1);
-        {
-            let mut pending = self.pending_transactions.write().await;
-            pending.insert(transaction_id, tx);
-        }
-
-        // Send transaction
+        // Send transaction and wait for reply
         println!("Sending UploadFile transaction...");
-        let mut write_guard = self.write_half.lock().await;
-        let write_stream = write_guard
-            .as_mut()
-            .ok_or("Not connected".to_string())?;
-
-        write_stream
-            .write_all(&encoded)
-            .await
-            .map_err(|e| format!("Failed to send UploadFile: {}", e))?;
-
-        write_stream
-            .flush()
-            .await
-            .map_err(|e| format!("Failed to flush: {}", e))?;
-
-        drop(write_guard);
-
-        // Wait for reply
-        println!("Waiting for UploadFile reply...");
-        let reply = tokio::time::timeout(Duration::from_secs(10), rx.recv())
-            .await
-            .map_err(|_| "Timeout waiting for upload reply".to_string())?
-            .ok_or("Channel closed".to_string())?;
+        let reply = self.send_and_wait_reply(transaction, "send UploadFile").await?;
 
         println!("UploadFile reply received: error_code={}", reply.error_code);
 
         if reply.error_code != 0 {
-            let error_msg = reply
-                .get_field(FieldType::ErrorText)
-                .and_then(|f| f.to_string().ok())
-                .unwrap_or_else(|| format!("Error code: {}", reply.error_code));
-            return Err(format!("Upload failed: {}", error_msg));
+            return Err(format!("Upload failed: {}", error_text(&reply)));
         }
 
         // Get reference number from reply
@@ -737,99 +695,158 @@ impl HotlineClient {
 
         println!("Upload reference number: {}", reference_number);
 
+        // A server with part of the file already reports how much it has
+        let offsets = reply
+            .get_field(FieldType::FileResumeData)
+            .and_then(|f| decode_resume_data(&f.data))
+            .unwrap_or_default();
+        if offsets.data > upload.data.len() as u64 || offsets.resource > upload.forks.resource().len() as u64 {
+            return Err(format!("Cannot resume upload: the server has more of {} than there is to send", file_name));
+        }
+        if offsets != ResumeOffsets::default() {
+            println!("Resuming upload at data offset {}, resource offset {}", offsets.data, offsets.resource);
+        }
+
         // Perform the actual file transfer
-        self.perform_file_upload(reference_number, &file_name, &file_data, &mut progress_callback)
+        self.perform_file_upload(reference_number, &upload, offsets, cancel, &mut progress_callback)
             .await?;
 
         Ok(())
     }
 
+    /// Create a folder named `name` inside `path` (NewFolder transaction)
     pub async fn create_folder(&self, path: Vec<String>, name: String) -> Result<(), String> {
+        let name = name.trim();
+        if name.is_empty() || name.contains(['/', ':']) {
+            return Err(format!("Invalid folder name: '{}'", name));
+        }
         println!("Creating folder '{}' at path: {:?}", name, path);
 
-        let transaction_id = self.next_transaction_id();
-        let mut transaction = Transaction::new(transaction_id, TransactionType::NewFolder);
+        let transaction = self.request(&NewFolderRequest {
+            folder: FileTarget { path: &path, file_name: name },
+        });
+
+        self.send_and_wait(transaction, "create folder").await?;
+        println!("Folder '{}' created successfully", name);
+        Ok(())
+    }
 
-        // Add folder name
-        transaction.add_field(TransactionField {
-            field_type: FieldType::FileName,
-            data: name.as_bytes().to_vec(),
+    pub async fn get_file_info(&self, path: Vec<String>, file_name: String) -> Result<FileDetails, String> {
+        let transaction = self.request(&GetFileInfoRequest {
+            file: FileTarget { path: &path, file_name: &file_name },
         });
 
-        // Add path field if not at root
-        if let Some(path_data) = encode_file_path(&path) {
-            transaction.add_field(TransactionField {
-                field_type: FieldType::FilePath,
-                data: path_data,
-            });
-        }
+        let reply = self.send_and_wait(transaction, "get file info").await?;
 
-        let encoded = transaction.encode();
+        let string_field = |field_type| {
+            reply
+                .get_field(field_type)
+                .and_then(|f| f.to_string().ok())
+                .unwrap_or_default()
+        };
+        let date_field = |field_type| {
+            reply
+                .get_field(field_type)
+                .and_then(|f| hotline_date_to_unix(&f.data))
+        };
 
-        let (tx, mut rx) = mpsc::channel(1);
-        {
-            let mut pending = self.pending_transactions.write().await;
-            pending.insert(transaction_id, tx);
-        }
+        let name = string_field(FieldType::FileName);
+        Ok(FileDetails {
+            name: if name.is_empty() { file_name } else { name },
+            type_name: string_field(FieldType::FileTypeString),
+            creator_name: string_field(FieldType::FileCreatorString),
+            type_code: reply
+                .get_field(FieldType::FileType)
+                .map(|f| String::from_utf8_lossy(&f.data).to_string())
+                .unwrap_or_default(),
+            comment: string_field(FieldType::FileComment),
+            size: reply.get_field(FieldType::FileSize).and_then(|f| f.to_size().ok()),
+            created: date_field(FieldType::FileCreateDate),
+            modified: date_field(FieldType::FileModifyDate),
+        })
+    }
 
-        let mut write_guard = self.write_half.lock().await;
-        let write_stream = write_guard
-            .as_mut()
-            .ok_or("Not connected".to_string())?;
+    /// Rename a file and/or change its comment. Fields left as None are not changed.
+    pub async fn set_file_info(&self, path: Vec<String>, file_name: String, new_name: Option<String>, comment: Option<String>) -> Result<(), String> {
+        let transaction = self.request(&SetFileInfoRequest {
+            file: FileTarget { path: &path, file_name: &file_name },
+            new_name: new_name.as_deref(),
+            comment: comment.as_deref(),
+        });
 
-        write_stream
-            .write_all(&encoded)
-            .await
-            .map_err(|e| format!("Failed to send NewFolder: {}", e))?;
+        self.send_and_wait(transaction, "set file info").await.map(|_| ())
+    }
 
-        write_stream
-            .flush()
-            .await
-            .map_err(|e| format!("Failed to flush: {}", e))?;
+    /// Rename a file or folder
+    pub async fn rename_file(&self, path: Vec<String>, file_name: String, new_name: String) -> Result<(), String> {
+        self.set_file_info(path, file_name, Some(new_name), None).await
+    }
 
-        drop(write_guard);
+    /// Move a file or folder from `path` into `new_path` (an empty path is the root)
+    pub async fn move_file(&self, path: Vec<String>, file_name: String, new_path: Vec<String>) -> Result<(), String> {
+        let transaction = self.request(&MoveFileRequest {
+            file: FileTarget { path: &path, file_name: &file_name },
+            new_path: &new_path,
+        });
 
-        let reply = tokio::time::timeout(Duration::from_secs(10), rx.recv())
-            .await
-            .map_err(|_| "Timeout waiting for create folder reply".to_string())?
-            .ok_or("Channel closed".to_string())?;
+        self.send_and_wait(transaction, "move file").await.map(|_| ())
+    }
 
-        if reply.error_code != 0 {
-            let error_msg = reply
-                .get_field(FieldType::ErrorText)
-                .and_then(|f| f.to_string().ok())
-                .unwrap_or_else(|| format!("Error code: {}", reply.error_code));
-            return Err(format!("Create folder failed: {}", error_msg));
-        }
+    /// Create an alias of `file_name` (in `path`) inside the folder `alias_path`
+    pub async fn make_file_alias(&self, path: Vec<String>, file_name: String, alias_path: Vec<String>) -> Result<(), String> {
+        let transaction = self.request(&MakeFileAliasRequest {
+            file: FileTarget { path: &path, file_name: &file_name },
+            alias_path: &alias_path,
+        });
 
-        println!("Folder '{}' created successfully", name);
+        self.send_and_wait(transaction, "make file alias").await.map(|_| ())
+    }
 
-        Ok(())
+    /// Delete a file or folder (folders are removed with their contents)
+    pub async fn delete_file(&self, path: Vec<String>, file_name: String) -> Result<(), String> {
+        let transaction = self.request(&DeleteFileRequest {
+            file: FileTarget { path: &path, file_name: &file_name },
+        });
+
+        self.send_and_wait(transaction, "delete file").await.map(|_| ())
     }
 
-    /// Perform the actual file upload transfer
+    /// Perform the actual file upload transfer, sending each fork from `offsets` on
     async fn perform_file_upload<F>(
         &self,
         reference_number: u32,
-        file_name: &str,
-        file_data: &[u8],
+        upload: &UploadFile,
+        offsets: ResumeOffsets,
+        cancel: &CancellationToken,
         progress_callback: &mut F,
     ) -> Result<(), String>
     where
-        F: FnMut(u32, u32),
+        F: FnMut(u64, u64),
     {
-        println!("Starting file upload transfer: {} ({} bytes)", file_name, file_data.len());
+        let file_data = upload.data.as_slice();
+        println!("Starting file upload transfer: {} ({} bytes)", upload.file_name, file_data.len());
 
         // Open a new connection (TCP or TLS) for file transfer
-        let (_transfer_read, mut transfer_write) = self.create_transfer_stream().await?;
+        let (_transfer_read, mut transfer_write, _slot) = self.create_transfer_stream().await?;
 
         println!("Upload transfer connection established");
 
         // Calculate total transfer size
-        // FILP header (24) + INFO fork header (16) + INFO fork data (minimal) + DATA fork header (16) + DATA fork data
-        let info_fork_size = 0u32; // Minimal INFO fork for now
-        let data_fork_size = file_data.len() as u32;
-        let total_size = 24 + 16 + info_fork_size + 16 + data_fork_size;
+        // FILP header (24) + INFO fork header (16) + INFO fork data + DATA fork header (16) + DATA fork data,
+        // plus MACR fork header (16) and data when there is a resource fork
+        let info_fork = upload.forks.info.as_deref().unwrap_or_default();
+        let resource_fork = &upload.forks.resource()[offsets.resource as usize..];
+        let info_fork_size = info_fork.len() as u64;
+        let data_fork_size = file_data.len() as u64;
+        let resource_fork_size = resource_fork.len() as u64;
+        let fork_count: u16 = if resource_fork.is_empty() { 2 } else { 3 };
+        let mut total_size = 24 + 16 + info_fork_size + 16 + (data_fork_size - offsets.data);
+        if !resource_fork.is_empty() {
+            total_size += 16 + resource_fork_size;
+        }
+        // Fork headers carry 64-bit sizes, but the handshake only has room for 32 bits
+        let total_size = u32::try_from(total_size)
+            .map_err(|_| format!("{} is too large to upload ({} bytes)", upload.file_name, total_size))?;
 
         // Send file transfer handshake
         // Format: HTXF (4) + reference_number (4) + total_size (4) + 0 (4) = 16 bytes
@@ -858,7 +875,7 @@ impl HotlineClient {
         filp_header.extend_from_slice(b"FILP"); // Format
         filp_header.extend_from_slice(&1u16.to_be_bytes()); // Version
         filp_header.extend_from_slice(&[0u8; 16]); // Reserved
-        filp_header.extend_from_slice(&2u16.to_be_bytes()); // Fork count (INFO + DATA)
+        filp_header.extend_from_slice(&fork_count.to_be_bytes()); // Fork count (INFO + DATA, + MACR)
 
         transfer_write
             .write_all(&filp_header)
@@ -866,27 +883,23 @@ impl HotlineClient {
             .map_err(|e| format!("Failed to send FILP header: {}", e))?;
 
         // Send INFO fork header
-        // Format: Fork type (4) + compression (4) + reserved (4) + data size (4) = 16 bytes
-        let mut info_fork_header = Vec::with_capacity(16);
-        info_fork_header.extend_from_slice(b"INFO"); // Fork type
-        info_fork_header.extend_from_slice(&0u32.to_be_bytes()); // Compression
-        info_fork_header.extend_from_slice(&0u32.to_be_bytes()); // Reserved
-        info_fork_header.extend_from_slice(&info_fork_size.to_be_bytes()); // Data size
+        // Format: Fork type (4) + compression (4) + data size (8) = 16 bytes
+        let info_fork_header = fork_header(b"INFO", info_fork_size);
 
         transfer_write
             .write_all(&info_fork_header)
             .await
             .map_err(|e| format!("Failed to send INFO fork header: {}", e))?;
 
-        // INFO fork data is empty for now
-        // (In a full implementation, this would contain file metadata)
+        // INFO fork data: type, creator, dates and comment when the file came
+        // from a Mac format, otherwise empty
+        transfer_write
+            .write_all(info_fork)
+            .await
+            .map_err(|e| format!("Failed to send INFO fork: {}", e))?;
 
         // Send DATA fork header
-        let mut data_fork_header = Vec::with_capacity(16);
-        data_fork_header.extend_from_slice(b"DATA"); // Fork type
-        data_fork_header.extend_from_slice(&0u32.to_be_bytes()); // Compression
-        data_fork_header.extend_from_slice(&0u32.to_be_bytes()); // Reserved
-        data_fork_header.extend_from_slice(&data_fork_size.to_be_bytes()); // Data size
+        let data_fork_header = fork_header(b"DATA", data_fork_size - offsets.data);
 
         transfer_write
             .write_all(&data_fork_header)
@@ -895,20 +908,26 @@ impl HotlineClient {
 
         // Send DATA fork (the actual file data) in chunks with progress tracking
         let chunk_size = 65536; // 64KB chunks
-        let mut bytes_sent = 0u32;
+        let mut bytes_sent = offsets.data;
         let mut last_reported_progress = 0u32;
+        let mut limiter = self.upload_limiter().await;
+        if bytes_sent > 0 {
+            progress_callback(bytes_sent, data_fork_size);
+        }
 
         while bytes_sent < data_fork_size {
+            check_cancelled(cancel)?;
             let remaining = data_fork_size - bytes_sent;
             let to_send = std::cmp::min(remaining, chunk_size) as usize;
             let chunk = &file_data[bytes_sent as usize..(bytes_sent as usize + to_send)];
+            limiter.acquire(to_send).await;
 
             transfer_write
                 .write_all(chunk)
                 .await
                 .map_err(|e| format!("Failed to send file data: {}", e))?;
 
-            bytes_sent += to_send as u32;
+            bytes_sent += to_send as u64;
 
             // Report progress every 2% or on completion
             let current_progress = (bytes_sent as f64 / data_fork_size as f64 * 100.0) as u32;
@@ -918,6 +937,21 @@ impl HotlineClient {
             }
         }
 
+        if !resource_fork.is_empty() {
+            let resource_fork_header = fork_header(b"MACR", resource_fork_size);
+
+            check_cancelled(cancel)?;
+            limiter.acquire(resource_fork.len()).await;
+            transfer_write
+                .write_all(&resource_fork_header)
+                .await
+                .map_err(|e| format!("Failed to send MACR fork header: {}", e))?;
+            transfer_write
+                .write_all(resource_fork)
+                .await
+                .map_err(|e| format!("Failed to send resource fork: {}", e))?;
+        }
+
         transfer_write
             .flush()
             .await
@@ -928,3 +962,76 @@ impl HotlineClient {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_hotline_dates() {
+        // 2024-03-01 00:00:10 UTC is 60 days and 10 seconds into 2024
+        let mut data = Vec::new();
+        data.extend_from_slice(&2024u16.to_be_bytes());
+        data.extend_from_slice(&0u16.to_be_bytes());
+        data.extend_from_slice(&(60 * 86_400 + 10u32).to_be_bytes());
+        assert_eq!(hotline_date_to_unix(&data), Some(1_709_251_210));
+
+        let epoch = [0x07, 0xB2, 0, 0, 0, 0, 0, 0];
+        assert_eq!(hotline_date_to_unix(&epoch), Some(0));
+        assert_eq!(hotline_date_to_unix(&[0; 8]), None);
+
+        assert_eq!(unix_to_hotline_date(1_709_251_210).as_slice(), data.as_slice());
+        assert_eq!(unix_to_hotline_date(0), epoch);
+        // Before 1970, as classic Mac dates can be
+        assert_eq!(hotline_date_to_unix(&unix_to_hotline_date(-86_400)), Some(-86_400));
+    }
+
+    #[test]
+    fn resume_data_layout() {
+        let data = encode_resume_data(0x0001_0000);
+        assert_eq!(&data[0..4], b"RFLT");
+        assert_eq!(u16::from_be_bytes([data[4], data[5]]), 1);
+        assert_eq!(u16::from_be_bytes([data[40], data[41]]), 2);
+        assert_eq!(&data[42..46], b"DATA");
+        assert_eq!(u32::from_be_bytes([data[46], data[47], data[48], data[49]]), 0x0001_0000);
+        assert_eq!(&data[58..62], b"MACR");
+        assert_eq!(data.len(), 42 + 2 * 16);
+    }
+
+    #[test]
+    fn reads_resume_offsets_back() {
+        let offsets = decode_resume_data(&encode_resume_data(0x0001_0000)).unwrap();
+        assert_eq!(offsets, ResumeOffsets { data: 0x0001_0000, resource: 0 });
+
+        let mut data = encode_resume_data(10);
+        data[62..66].copy_from_slice(&7u32.to_be_bytes());
+        assert_eq!(decode_resume_data(&data).unwrap().resource, 7);
+
+        assert_eq!(decode_resume_data(b"RFLT"), None);
+        assert_eq!(decode_resume_data(&[0u8; 74]), None);
+    }
+
+    #[test]
+    fn fork_headers_carry_extended_sizes() {
+        let small = fork_header(b"DATA", 1234);
+        assert_eq!(&small[0..4], b"DATA");
+        // The high word stays zero, as older peers expect in the reserved field
+        assert_eq!(&small[4..12], &[0u8; 8]);
+        assert_eq!(fork_size(&small), 1234);
+
+        let large = fork_header(b"DATA", 6_000_000_000);
+        assert_eq!(u32::from_be_bytes([large[8], large[9], large[10], large[11]]), 1);
+        assert_eq!(fork_size(&large), 6_000_000_000);
+    }
+
+    #[test]
+    fn data_fork_size_falls_back_to_reported_size() {
+        assert_eq!(data_fork_size(500, 500), 500);
+        assert_eq!(data_fork_size(0, 500), 500);
+        // A server without extended sizes only sends the low 32 bits
+        assert_eq!(data_fork_size(6_000_000_000 & 0xFFFF_FFFF, 6_000_000_000), 6_000_000_000);
+        // The header wins when it disagrees otherwise
+        assert_eq!(data_fork_size(400, 500), 400);
+        assert_eq!(data_fork_size(400, 0), 400);
+    }
+}
@@ -0,0 +1,393 @@
+// Folder download functionality for Hotline client
+//
+// DownloadFolder replies with a reference number, the total transfer size and
+// the number of items in the tree. After the usual HTXF handshake on the
+// transfer port the server walks the folder and, for every item, sends a
+// header naming it by its path relative to the downloaded folder. The client
+// answers each header with an action: "send file" makes the server follow up
+// with a 4-byte size and the flattened file object, "next file" skips to the
+// next header. Folders are always answered with "next file", and every
+// received file is acknowledged with "next file" too.
+//
+// Each file is streamed into `name.part` beside its target, checked against
+// the size the server announced for it and renamed once complete, so a
+// cancelled or short transfer never leaves a truncated file under the real
+// name.
+
+use super::HotlineClient;
+use super::bandwidth::RateLimiter;
+use super::files::{encode_file_path, fork_size};
+use crate::protocol::cancel::{check_cancelled, CancellationToken};
+use crate::protocol::constants::{FieldType, TransactionType, FILE_TRANSFER_ID};
+use crate::protocol::sanitize_path::local_path;
+use crate::protocol::transaction::{Transaction, TransactionField};
+use crate::state::partial_downloads::{part_path, verify_size};
+use std::path::Path;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+const ACTION_SEND_FILE: u16 = 1;
+const ACTION_NEXT_FILE: u16 = 3;
+
+/// Server reply to a DownloadFolder request
+#[derive(Debug, Clone, Copy)]
+pub struct FolderDownload {
+    pub reference_number: u32,
+    pub total_size: u64,
+    pub item_count: u32,
+}
+
+/// One entry of a folder transfer, named relative to the downloaded folder
+#[derive(Debug, Clone, PartialEq)]
+pub struct FolderItem {
+    pub path: Vec<String>,
+    pub is_folder: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct FolderProgress {
+    pub bytes_received: u64,
+    pub total_bytes: u64,
+    pub items_completed: u32,
+    pub item_count: u32,
+}
+
+fn decode_component(data: &[u8]) -> String {
+    match std::str::from_utf8(data) {
+        Ok(s) => s.to_string(),
+        Err(_) => encoding_rs::MACINTOSH.decode(data).0.into_owned(),
+    }
+}
+
+/// Parse an item header: type (2, 1 for folders) followed by an encoded file
+/// path (count (2), then per component 2 reserved bytes, length (1), name).
+pub(crate) fn parse_folder_item(data: &[u8]) -> Result<FolderItem, String> {
+    if data.len() < 4 {
+        return Err(format!("Folder item header too short: {} bytes", data.len()));
+    }
+
+    let is_folder = u16::from_be_bytes([data[0], data[1]]) == 1;
+    let count = u16::from_be_bytes([data[2], data[3]]) as usize;
+
+    let mut path = Vec::with_capacity(count);
+    let mut offset = 4;
+    for _ in 0..count {
+        if data.len() < offset + 3 {
+            return Err("Folder item path truncated".to_string());
+        }
+        let len = data[offset + 2] as usize;
+        offset += 3;
+        if data.len() < offset + len {
+            return Err("Folder item name truncated".to_string());
+        }
+        path.push(decode_component(&data[offset..offset + len]));
+        offset += len;
+    }
+
+    if path.is_empty() {
+        return Err("Folder item has an empty path".to_string());
+    }
+
+    Ok(FolderItem { path, is_folder })
+}
+
+async fn send_action<W: AsyncWrite + Unpin>(writer: &mut W, action: u16) -> Result<(), String> {
+    writer
+        .write_all(&action.to_be_bytes())
+        .await
+        .map_err(|e| format!("Failed to send folder transfer action: {}", e))?;
+    writer
+        .flush()
+        .await
+        .map_err(|e| format!("Failed to flush folder transfer action: {}", e))
+}
+
+/// Read one flattened file object (FILP header and forks), writing the DATA
+/// fork into `sink` and discarding the rest. `on_data` gets the DATA bytes
+/// received so far. Returns the size of the DATA fork and of the whole object.
+async fn receive_flat_file<R, W, F>(
+    reader: &mut R,
+    sink: &mut W,
+    cancel: &CancellationToken,
+    limiter: &mut RateLimiter,
+    mut on_data: F,
+) -> Result<(u64, u64), String>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+    F: FnMut(u64),
+{
+    let mut header = [0u8; 24];
+    reader
+        .read_exact(&mut header)
+        .await
+        .map_err(|e| format!("Failed to read file header: {}", e))?;
+    if &header[0..4] != b"FILP" {
+        return Err(format!(
+            "Invalid file header: expected FILP, got {:?}",
+            String::from_utf8_lossy(&header[0..4])
+        ));
+    }
+    let fork_count = u16::from_be_bytes([header[22], header[23]]);
+    let mut data_received = 0u64;
+    let mut object_size = header.len() as u64;
+
+    for fork_idx in 0..fork_count {
+        let mut fork_header = [0u8; 16];
+        reader
+            .read_exact(&mut fork_header)
+            .await
+            .map_err(|e| format!("Failed to read fork {} header: {}", fork_idx, e))?;
+        let is_data_fork = &fork_header[0..4] == b"DATA";
+        let size = fork_size(&fork_header);
+        object_size += fork_header.len() as u64 + size;
+
+        let mut received = 0u64;
+        let mut chunk = vec![0u8; 65536];
+        while received < size {
+            check_cancelled(cancel)?;
+            let to_read = std::cmp::min(size - received, chunk.len() as u64) as usize;
+            limiter.acquire(to_read).await;
+            reader
+                .read_exact(&mut chunk[..to_read])
+                .await
+                .map_err(|e| format!("Failed to read fork {} data: {}", fork_idx, e))?;
+            received += to_read as u64;
+
+            if is_data_fork {
+                sink.write_all(&chunk[..to_read])
+                    .await
+                    .map_err(|e| format!("Failed to write downloaded data: {}", e))?;
+                on_data(received);
+            }
+        }
+        if is_data_fork {
+            data_received = received;
+        }
+    }
+
+    sink.flush()
+        .await
+        .map_err(|e| format!("Failed to flush downloaded data: {}", e))?;
+    Ok((data_received, object_size))
+}
+
+impl HotlineClient {
+    /// Request a folder download. The returned reference is used for the transfer connection.
+    pub async fn download_folder(&self, path: Vec<String>, folder_name: String) -> Result<FolderDownload, String> {
+        println!("Requesting folder download: {:?} / {}", path, folder_name);
+
+        let mut transaction = Transaction::new(self.next_transaction_id(), TransactionType::DownloadFolder);
+        transaction.add_field(TransactionField::from_string(FieldType::FileName, &folder_name));
+        if let Some(path_data) = encode_file_path(&path) {
+            transaction.add_field(TransactionField::new(FieldType::FilePath, path_data));
+        }
+
+        let reply = self.send_and_wait(transaction, "download folder").await?;
+
+        let reference_number = reply
+            .get_field(FieldType::ReferenceNumber)
+            .and_then(|f| f.to_u32().ok())
+            .ok_or("No reference number in reply".to_string())?;
+        let total_size = reply
+            .get_field(FieldType::TransferSize)
+            .and_then(|f| f.to_size().ok())
+            .unwrap_or(0);
+        let item_count = reply
+            .get_field(FieldType::FolderItemCount)
+            .and_then(|f| f.to_u32().ok().or_else(|| f.to_u16().ok().map(u32::from)))
+            .unwrap_or(0);
+
+        println!(
+            "Folder download reference {}: {} items, {} bytes",
+            reference_number, item_count, total_size
+        );
+
+        Ok(FolderDownload {
+            reference_number,
+            total_size,
+            item_count,
+        })
+    }
+
+    /// Receive a folder tree into `destination`. Returns the number of files written.
+    pub async fn perform_folder_download<F>(
+        &self,
+        download: FolderDownload,
+        destination: &Path,
+        cancel: &CancellationToken,
+        mut progress_callback: F,
+    ) -> Result<u32, String>
+    where
+        F: FnMut(&FolderItem, FolderProgress) + Send,
+    {
+        tokio::fs::create_dir_all(destination)
+            .await
+            .map_err(|e| format!("Failed to create download folder: {}", e))?;
+
+        let (mut transfer_read, mut transfer_write, _slot) = self.create_transfer_stream().await?;
+
+        let mut handshake = Vec::with_capacity(16);
+        handshake.extend_from_slice(FILE_TRANSFER_ID); // "HTXF"
+        handshake.extend_from_slice(&download.reference_number.to_be_bytes());
+        handshake.extend_from_slice(&0u32.to_be_bytes());
+        handshake.extend_from_slice(&0u32.to_be_bytes());
+        transfer_write
+            .write_all(&handshake)
+            .await
+            .map_err(|e| format!("Failed to send file transfer handshake: {}", e))?;
+
+        // Ask for the first item
+        send_action(&mut transfer_write, ACTION_NEXT_FILE).await?;
+
+        let mut progress = FolderProgress {
+            bytes_received: 0,
+            total_bytes: download.total_size,
+            items_completed: 0,
+            item_count: download.item_count,
+        };
+        let mut files_written = 0u32;
+        let mut limiter = self.download_limiter().await;
+
+        loop {
+            check_cancelled(cancel)?;
+
+            // The server closes the connection after the last item
+            let mut size_bytes = [0u8; 2];
+            match transfer_read.read_exact(&mut size_bytes).await {
+                Ok(_) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(format!("Failed to read folder item header: {}", e)),
+            }
+            let mut header = vec![0u8; u16::from_be_bytes(size_bytes) as usize];
+            transfer_read
+                .read_exact(&mut header)
+                .await
+                .map_err(|e| format!("Failed to read folder item header: {}", e))?;
+            let item = parse_folder_item(&header)?;
+            let target = local_path(destination, &item.path);
+
+            if item.is_folder {
+                tokio::fs::create_dir_all(&target)
+                    .await
+                    .map_err(|e| format!("Failed to create folder {}: {}", target.display(), e))?;
+                send_action(&mut transfer_write, ACTION_NEXT_FILE).await?;
+            } else {
+                send_action(&mut transfer_write, ACTION_SEND_FILE).await?;
+
+                // The item's size is only 32 bits, so it is checked modulo
+                // 2^32; the fork headers that follow carry the real sizes
+                let mut announced_size = [0u8; 4];
+                transfer_read
+                    .read_exact(&mut announced_size)
+                    .await
+                    .map_err(|e| format!("Failed to read file size: {}", e))?;
+
+                if let Some(parent) = target.parent() {
+                    tokio::fs::create_dir_all(parent)
+                        .await
+                        .map_err(|e| format!("Failed to create folder {}: {}", parent.display(), e))?;
+                }
+                let partial = part_path(&target, &download.reference_number.to_string(), |_| false);
+                let mut file = tokio::fs::File::create(&partial)
+                    .await
+                    .map_err(|e| format!("Failed to create {}: {}", target.display(), e))?;
+
+                let base = progress.bytes_received;
+                let received = receive_flat_file(&mut transfer_read, &mut file, cancel, &mut limiter, |received| {
+                    let current = FolderProgress {
+                        bytes_received: base + received,
+                        ..progress
+                    };
+                    progress_callback(&item, current);
+                })
+                .await;
+                drop(file);
+                let announced_size = u64::from(u32::from_be_bytes(announced_size));
+                let received = received.and_then(|(received, object_size)| {
+                    verify_size(object_size & 0xFFFF_FFFF, announced_size)
+                        .map_err(|e| format!("{}: {}", target.display(), e))?;
+                    Ok(received)
+                });
+                let received = match received {
+                    Ok(received) => received,
+                    Err(e) => {
+                        let _ = tokio::fs::remove_file(&partial).await;
+                        return Err(e);
+                    }
+                };
+                tokio::fs::rename(&partial, &target)
+                    .await
+                    .map_err(|e| format!("Failed to write {}: {}", target.display(), e))?;
+
+                progress.bytes_received = base + received;
+                files_written += 1;
+                send_action(&mut transfer_write, ACTION_NEXT_FILE).await?;
+            }
+
+            progress.items_completed += 1;
+            progress_callback(&item, progress);
+        }
+
+        println!(
+            "Folder download complete: {} files, {} of {} items",
+            files_written, progress.items_completed, download.item_count
+        );
+
+        Ok(files_written)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item_header(is_folder: bool, path: &[&str]) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&(is_folder as u16).to_be_bytes());
+        data.extend_from_slice(&(path.len() as u16).to_be_bytes());
+        for component in path {
+            data.extend_from_slice(&[0, 0, component.len() as u8]);
+            data.extend_from_slice(component.as_bytes());
+        }
+        data
+    }
+
+    #[test]
+    fn parses_nested_item_headers() {
+        let item = parse_folder_item(&item_header(false, &["Docs", "readme.txt"])).unwrap();
+        assert_eq!(item.path, vec!["Docs", "readme.txt"]);
+        assert!(!item.is_folder);
+
+        assert!(parse_folder_item(&item_header(true, &["Docs"])).unwrap().is_folder);
+        assert!(parse_folder_item(&item_header(false, &[])).is_err());
+
+        let mut truncated = item_header(false, &["readme.txt"]);
+        truncated.truncate(truncated.len() - 2);
+        assert!(parse_folder_item(&truncated).is_err());
+    }
+
+    #[tokio::test]
+    async fn receives_data_fork_and_skips_others() {
+        let mut stream = Vec::new();
+        stream.extend_from_slice(b"FILP");
+        stream.extend_from_slice(&1u16.to_be_bytes());
+        stream.extend_from_slice(&[0u8; 16]);
+        stream.extend_from_slice(&2u16.to_be_bytes());
+        for (fork, body) in [(b"INFO", &b"info"[..]), (b"DATA", &b"hello"[..])] {
+            stream.extend_from_slice(fork);
+            stream.extend_from_slice(&[0u8; 8]);
+            stream.extend_from_slice(&(body.len() as u32).to_be_bytes());
+            stream.extend_from_slice(body);
+        }
+
+        let mut sink = Vec::new();
+        let mut last = 0;
+        let (received, object_size) = receive_flat_file(&mut stream.as_slice(), &mut sink, &CancellationToken::new(), &mut RateLimiter::new(None), |n| last = n)
+            .await
+            .unwrap();
+        assert_eq!(sink, b"hello");
+        assert_eq!(last, 5);
+        assert_eq!(received, 5);
+        assert_eq!(object_size, stream.len() as u64);
+    }
+}
@@ -0,0 +1,128 @@
+// Connection health: keep-alive round trips and the no-traffic watchdog
+//
+// The receive loop stamps every incoming transaction and the keep-alive task
+// times each keep-alive reply. Both feed ConnectionHealth, which the
+// get_connection_stats command reports to the frontend.
+
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Round trips kept for the rolling average
+const LATENCY_SAMPLES: usize = 10;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionStats {
+    /// Transaction used as keep-alive: "connectionKeepAlive" or "getUserNameList"
+    pub keepalive: String,
+    pub keepalives_sent: u32,
+    /// Keep-alives that got no reply in time
+    pub keepalives_missed: u32,
+    pub last_latency_ms: Option<u64>,
+    pub average_latency_ms: Option<u64>,
+    /// Seconds since anything arrived from the server
+    pub idle_secs: Option<u64>,
+    /// Silence after which the connection is treated as lost
+    pub watchdog_timeout_secs: Option<u64>,
+    /// Users online, yourself included
+    pub users: usize,
+    /// The server's stated user limit, for showing occupancy as users/limit
+    pub user_limit: Option<u16>,
+}
+
+#[derive(Debug, Default)]
+pub struct ConnectionHealth {
+    connection_keepalive: bool,
+    keepalives_sent: u32,
+    keepalives_missed: u32,
+    latencies: VecDeque<Duration>,
+    last_traffic: Option<Instant>,
+    watchdog_timeout: Option<Duration>,
+}
+
+impl ConnectionHealth {
+    /// Start over for a fresh session
+    pub fn reset(&mut self, connection_keepalive: bool, watchdog_timeout: Option<Duration>) {
+        *self = Self {
+            connection_keepalive,
+            watchdog_timeout,
+            last_traffic: Some(Instant::now()),
+            ..Self::default()
+        };
+    }
+
+    pub fn record_traffic(&mut self) {
+        self.last_traffic = Some(Instant::now());
+    }
+
+    pub fn record_keepalive_sent(&mut self) {
+        self.keepalives_sent += 1;
+    }
+
+    pub fn record_keepalive_missed(&mut self) {
+        self.keepalives_missed += 1;
+    }
+
+    pub fn record_latency(&mut self, latency: Duration) {
+        if self.latencies.len() == LATENCY_SAMPLES {
+            self.latencies.pop_front();
+        }
+        self.latencies.push_back(latency);
+    }
+
+    pub fn stats(&self) -> ConnectionStats {
+        let average = if self.latencies.is_empty() {
+            None
+        } else {
+            let total: Duration = self.latencies.iter().sum();
+            Some((total / self.latencies.len() as u32).as_millis() as u64)
+        };
+
+        ConnectionStats {
+            keepalive: if self.connection_keepalive { "connectionKeepAlive" } else { "getUserNameList" }.to_string(),
+            keepalives_sent: self.keepalives_sent,
+            keepalives_missed: self.keepalives_missed,
+            last_latency_ms: self.latencies.back().map(|d| d.as_millis() as u64),
+            average_latency_ms: average,
+            idle_secs: self.last_traffic.map(|t| t.elapsed().as_secs()),
+            watchdog_timeout_secs: self.watchdog_timeout.map(|d| d.as_secs()),
+            users: 0,
+            user_limit: None,
+        }
+    }
+}
+
+/// Silence allowed before the watchdog fires. A quiet server only sends
+/// keep-alive replies, so the window never drops below one keep-alive interval
+/// plus time for the reply.
+pub fn watchdog_window(configured: Option<Duration>, keepalive_interval: Duration) -> Option<Duration> {
+    configured.map(|window| window.max(keepalive_interval + Duration::from_secs(30)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn averages_the_most_recent_round_trips() {
+        let mut health = ConnectionHealth::default();
+        assert_eq!(health.stats().average_latency_ms, None);
+
+        for ms in [1000, 10, 20, 30, 40, 50, 60, 70, 80, 90, 100] {
+            health.record_latency(Duration::from_millis(ms));
+        }
+        let stats = health.stats();
+        assert_eq!(stats.last_latency_ms, Some(100));
+        // The 1000ms sample has rolled out of the window
+        assert_eq!(stats.average_latency_ms, Some(55));
+    }
+
+    #[test]
+    fn watchdog_outlasts_the_keepalive_interval() {
+        let interval = Duration::from_secs(180);
+        assert_eq!(watchdog_window(None, interval), None);
+        assert_eq!(watchdog_window(Some(Duration::from_secs(60)), interval), Some(Duration::from_secs(210)));
+        assert_eq!(watchdog_window(Some(Duration::from_secs(600)), interval), Some(Duration::from_secs(600)));
+    }
+}
@@ -0,0 +1,237 @@
+// Instant message options
+//
+// SendInstantMessage carries an Options field saying what kind of message it
+// is: an ordinary message, a refusal sent back because the recipient doesn't
+// accept messages or chat invitations, or an automatic response. A reply can
+// quote the message it answers in the QuotingMessage field. The server relays
+// them as ServerMessage transactions with the same fields, which are turned
+// into distinct events here so refusals don't look like ordinary messages.
+//
+// Our own preferences go to the server as user option bits, so servers that
+// enforce them can answer for us. Servers that don't simply forward messages,
+// and the event loop sends the refusal or automatic response itself.
+//
+// Hotline 1.2 servers and the clients on them predate Options and quotes:
+// messages sent there carry only UserId and Data, with any quote folded into
+// the text and classic Mac line breaks. Messages from those clients arrive
+// without Options, which reads as an ordinary message.
+
+use super::requests::SendInstantMessageRequest;
+use super::{HotlineClient, HotlineEvent};
+use crate::protocol::constants::FieldType;
+use crate::protocol::transaction::Transaction;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstantMessageKind {
+    Message = 1,
+    RefuseMessage = 2,
+    RefuseChat = 3,
+    AutomaticResponse = 4,
+}
+
+impl InstantMessageKind {
+    /// Unknown values are treated as ordinary messages
+    fn from_options(options: u32) -> Self {
+        match options {
+            2 => Self::RefuseMessage,
+            3 => Self::RefuseChat,
+            4 => Self::AutomaticResponse,
+            _ => Self::Message,
+        }
+    }
+}
+
+/// How we answer other users' private messages and chat invitations
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MessagingOptions {
+    pub refuse_messages: bool,
+    pub refuse_chat: bool,
+    /// Sent back to everyone who messages us, e.g. while away
+    pub automatic_response: Option<String>,
+}
+
+impl MessagingOptions {
+    /// User option bits for SetClientUserInfo and Agreed
+    pub fn user_options(&self) -> u16 {
+        let mut options = 0;
+        if self.refuse_messages {
+            options |= 1;
+        }
+        if self.refuse_chat {
+            options |= 2;
+        }
+        if self.automatic_response().is_some() {
+            options |= 4;
+        }
+        options
+    }
+
+    pub fn automatic_response(&self) -> Option<&str> {
+        self.automatic_response.as_deref().filter(|text| !text.trim().is_empty())
+    }
+}
+
+/// Text of a message for a server without Options: the quote goes above the
+/// reply, and lines end in carriage returns. Refusals with nothing to say
+/// aren't sent, since they would arrive as empty messages.
+pub fn legacy_text(kind: InstantMessageKind, message: &str, quoting: Option<&str>) -> Option<String> {
+    let refusal = matches!(kind, InstantMessageKind::RefuseMessage | InstantMessageKind::RefuseChat);
+    if refusal && message.trim().is_empty() {
+        return None;
+    }
+    let mut text = String::new();
+    if let Some(quote) = quoting.filter(|quote| !quote.is_empty()) {
+        for line in quote.lines() {
+            text.push_str("> ");
+            text.push_str(line);
+            text.push('\n');
+        }
+        text.push('\n');
+    }
+    text.push_str(message);
+    Some(text.replace("\r\n", "\n").replace('\n', "\r"))
+}
+
+/// Turn a ServerMessage from another user into an event. Returns None for
+/// server broadcasts, which carry no UserId.
+pub(crate) fn instant_message_event(transaction: &Transaction) -> Option<HotlineEvent> {
+    let user_id = transaction.get_field(FieldType::UserId)?.to_u16().ok()?;
+    let user_name = transaction
+        .get_field(FieldType::UserName)
+        .and_then(|f| f.to_string().ok())
+        .unwrap_or_default();
+    let message = transaction
+        .get_field(FieldType::Data)
+        .and_then(|f| f.to_string().ok())
+        .unwrap_or_default();
+    let options = transaction
+        .get_field(FieldType::Options)
+        .and_then(|f| f.to_u32().ok().or_else(|| f.to_u16().ok().map(u32::from)))
+        .unwrap_or(1);
+
+    Some(match InstantMessageKind::from_options(options) {
+        InstantMessageKind::Message => HotlineEvent::PrivateMessage {
+            user_id,
+            user_name,
+            message,
+            quoting: transaction
+                .get_field(FieldType::QuotingMessage)
+                .and_then(|f| f.to_string().ok())
+                .filter(|quote| !quote.is_empty()),
+        },
+        InstantMessageKind::RefuseMessage => HotlineEvent::MessageRefused { user_id, user_name, message },
+        InstantMessageKind::RefuseChat => HotlineEvent::ChatRefused { user_id, user_name, message },
+        InstantMessageKind::AutomaticResponse => HotlineEvent::AutomaticResponse { user_id, user_name, message },
+    })
+}
+
+impl HotlineClient {
+    /// Send an instant message of any kind, optionally quoting the message it answers
+    pub async fn send_instant_message(
+        &self,
+        user_id: u16,
+        kind: InstantMessageKind,
+        message: &str,
+        quoting: Option<&str>,
+    ) -> Result<(), String> {
+        let quirks = self.quirks().await;
+        let legacy;
+        let request = if quirks.instant_message_options {
+            SendInstantMessageRequest { user_id, kind: Some(kind), message, quoting }
+        } else {
+            let Some(text) = legacy_text(kind, message, quoting) else {
+                return Ok(());
+            };
+            legacy = text;
+            SendInstantMessageRequest { user_id, kind: None, message: &legacy, quoting: None }
+        };
+        let transaction = self.request(&request);
+        if quirks.acknowledges_instant_messages {
+            self.send_and_wait(transaction, "send instant message").await.map(|_| ())
+        } else {
+            self.send_transaction(transaction, "send instant message").await
+        }
+    }
+
+    pub async fn set_messaging_options(&self, options: MessagingOptions) {
+        *self.messaging.lock().await = options;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::constants::TransactionType;
+    use crate::protocol::transaction::TransactionField;
+
+    fn server_message(options: Option<u32>) -> Transaction {
+        let mut transaction = Transaction::new(1, TransactionType::ServerMessage);
+        transaction.add_field(TransactionField::from_u16(FieldType::UserId, 12));
+        transaction.add_field(TransactionField::from_string(FieldType::UserName, "alice"));
+        transaction.add_field(TransactionField::from_string(FieldType::Data, "not now"));
+        if let Some(options) = options {
+            transaction.add_field(TransactionField::from_u32(FieldType::Options, options));
+        }
+        transaction
+    }
+
+    #[test]
+    fn refusals_and_automatic_responses_are_distinct_events() {
+        assert!(matches!(
+            instant_message_event(&server_message(Some(2))),
+            Some(HotlineEvent::MessageRefused { user_id: 12, .. })
+        ));
+        assert!(matches!(
+            instant_message_event(&server_message(Some(3))),
+            Some(HotlineEvent::ChatRefused { user_id: 12, .. })
+        ));
+        assert!(matches!(
+            instant_message_event(&server_message(Some(4))),
+            Some(HotlineEvent::AutomaticResponse { user_id: 12, .. })
+        ));
+    }
+
+    #[test]
+    fn ordinary_messages_carry_the_quote() {
+        let mut transaction = server_message(None);
+        transaction.add_field(TransactionField::from_string(FieldType::QuotingMessage, "lunch?"));
+        match instant_message_event(&transaction) {
+            Some(HotlineEvent::PrivateMessage { user_name, message, quoting, .. }) => {
+                assert_eq!(user_name, "alice");
+                assert_eq!(message, "not now");
+                assert_eq!(quoting.as_deref(), Some("lunch?"));
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+
+        let mut broadcast = Transaction::new(2, TransactionType::ServerMessage);
+        broadcast.add_field(TransactionField::from_string(FieldType::Data, "Server going down"));
+        assert!(instant_message_event(&broadcast).is_none());
+    }
+
+    #[test]
+    fn legacy_messages_fold_the_quote_into_the_text() {
+        let text = legacy_text(InstantMessageKind::Message, "sure\nsee you", Some("lunch?\nat noon"));
+        assert_eq!(text.as_deref(), Some("> lunch?\r> at noon\r\rsure\rsee you"));
+        assert_eq!(legacy_text(InstantMessageKind::AutomaticResponse, "Away", None).as_deref(), Some("Away"));
+        assert_eq!(legacy_text(InstantMessageKind::RefuseMessage, "", None), None);
+    }
+
+    #[test]
+    fn user_option_bits() {
+        assert_eq!(MessagingOptions::default().user_options(), 0);
+        let options = MessagingOptions {
+            refuse_messages: true,
+            refuse_chat: true,
+            automatic_response: Some("  ".to_string()),
+        };
+        assert_eq!(options.user_options(), 3);
+        let away = MessagingOptions {
+            automatic_response: Some("Away".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(away.user_options(), 4);
+    }
+}
@@ -0,0 +1,97 @@
+// Background task lifecycle
+//
+// Each connection runs three background tasks: the receive loop, the
+// keep-alive timer and the writer. The receive loop and keep-alive report
+// when they start and stop, and why; the writer reports a frame that sits on
+// the socket far longer than it should. When a user says the app "just hung",
+// these notices show which of the three stopped making progress. They go
+// through the event channel like everything else and always reach the
+// diagnostic log; the verbosity setting decides which reach the frontend.
+
+use super::Lane;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// How long one frame may take to write before the writer counts as stalled
+pub const WRITER_STALL_WARNING: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DiagnosticVerbosity {
+    #[default]
+    Off,
+    /// Stalls and tasks that stopped on their own
+    Problems,
+    /// Every start and stop as well
+    All,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LifecycleNotice {
+    ReceiveLoopStarted,
+    /// `expected` when we stopped it, rather than the socket or the watchdog
+    ReceiveLoopStopped { reason: String, expected: bool },
+    KeepaliveStarted { interval_secs: u64 },
+    KeepaliveStopped { reason: String, expected: bool },
+    WriterStalled { lane: Lane, waited_secs: u64 },
+}
+
+impl LifecycleNotice {
+    pub fn name(&self) -> &'static str {
+        match self {
+            LifecycleNotice::ReceiveLoopStarted => "receive-loop-started",
+            LifecycleNotice::ReceiveLoopStopped { .. } => "receive-loop-stopped",
+            LifecycleNotice::KeepaliveStarted { .. } => "keepalive-started",
+            LifecycleNotice::KeepaliveStopped { .. } => "keepalive-stopped",
+            LifecycleNotice::WriterStalled { .. } => "writer-stalled",
+        }
+    }
+
+    pub fn detail(&self) -> String {
+        match self {
+            LifecycleNotice::ReceiveLoopStarted => String::new(),
+            LifecycleNotice::ReceiveLoopStopped { reason, .. } | LifecycleNotice::KeepaliveStopped { reason, .. } => reason.clone(),
+            LifecycleNotice::KeepaliveStarted { interval_secs } => format!("every {}s", interval_secs),
+            LifecycleNotice::WriterStalled { lane, waited_secs } => {
+                format!("{:?} frame still writing after {}s", lane, waited_secs)
+            }
+        }
+    }
+
+    pub fn is_problem(&self) -> bool {
+        match self {
+            LifecycleNotice::ReceiveLoopStopped { expected, .. } | LifecycleNotice::KeepaliveStopped { expected, .. } => !expected,
+            LifecycleNotice::WriterStalled { .. } => true,
+            LifecycleNotice::ReceiveLoopStarted | LifecycleNotice::KeepaliveStarted { .. } => false,
+        }
+    }
+
+    /// Whether the frontend should hear about this notice at `verbosity`
+    pub fn shown_at(&self, verbosity: DiagnosticVerbosity) -> bool {
+        match verbosity {
+            DiagnosticVerbosity::Off => false,
+            DiagnosticVerbosity::Problems => self.is_problem(),
+            DiagnosticVerbosity::All => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verbosity_filters_routine_notices() {
+        let started = LifecycleNotice::KeepaliveStarted { interval_secs: 180 };
+        let dropped = LifecycleNotice::ReceiveLoopStopped { reason: "connection closed".to_string(), expected: false };
+        let stopped = LifecycleNotice::ReceiveLoopStopped { reason: "disconnect".to_string(), expected: true };
+        let stalled = LifecycleNotice::WriterStalled { lane: Lane::Bulk, waited_secs: 10 };
+
+        assert!([&started, &dropped, &stopped, &stalled].iter().all(|notice| !notice.shown_at(DiagnosticVerbosity::Off)));
+        assert!(!started.shown_at(DiagnosticVerbosity::Problems) && !stopped.shown_at(DiagnosticVerbosity::Problems));
+        assert!(dropped.shown_at(DiagnosticVerbosity::Problems) && stalled.shown_at(DiagnosticVerbosity::Problems));
+        assert!(started.shown_at(DiagnosticVerbosity::All));
+        assert_eq!(stalled.name(), "writer-stalled");
+        assert_eq!(stalled.detail(), "Bulk frame still writing after 10s");
+    }
+}
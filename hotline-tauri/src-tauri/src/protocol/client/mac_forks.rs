@@ -0,0 +1,554 @@
+// Preserving classic Mac metadata from downloads
+//
+// A flattened Hotline file carries an INFO fork (type, creator, Finder flags,
+// dates, comment) and often a MACR resource fork next to the DATA fork. Most
+// filesystems have nowhere to put them, so by default they are dropped. A
+// download can instead keep them as an AppleDouble `._name` companion file
+// (what macOS itself writes on foreign volumes) or wrap everything into a
+// single MacBinary II `.bin` file.
+//
+// Uploads go the other way: a MacBinary file or an AppleDouble companion is
+// unpacked so the server receives the real INFO and MACR forks.
+
+use super::files::{hotline_date_to_unix, unix_to_hotline_date};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ForkMode {
+    /// Keep only the data fork
+    #[default]
+    Discard,
+    /// Data fork as-is plus a `._name` AppleDouble file
+    AppleDouble,
+    /// One `.bin` MacBinary II file
+    MacBinary,
+}
+
+/// INFO and MACR forks collected during a transfer
+#[derive(Debug, Clone, Default)]
+pub struct MacForks {
+    pub info: Option<Vec<u8>>,
+    pub resource: Option<Vec<u8>>,
+}
+
+impl MacForks {
+    pub fn is_empty(&self) -> bool {
+        self.info.is_none() && self.resource.as_ref().is_none_or(|r| r.is_empty())
+    }
+
+    pub fn resource(&self) -> &[u8] {
+        self.resource.as_deref().unwrap_or_default()
+    }
+
+    fn metadata(&self) -> MacMetadata {
+        self.info
+            .as_deref()
+            .map(MacMetadata::from_info_fork)
+            .transpose()
+            .unwrap_or_else(|e| {
+                eprintln!("Ignoring unreadable INFO fork: {}", e);
+                None
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// The parts of an INFO fork that Mac metadata formats care about
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MacMetadata {
+    pub file_type: [u8; 4],
+    pub creator: [u8; 4],
+    pub finder_flags: u16,
+    /// Unix timestamps
+    pub created: Option<i64>,
+    pub modified: Option<i64>,
+    pub comment: Vec<u8>,
+}
+
+impl MacMetadata {
+    /// Parse an INFO fork: platform (4), type (4), creator (4), flags (4),
+    /// platform flags (4), reserved (32), created (8), modified (8), name
+    /// script (2), name length (2), name, comment length (2), comment
+    pub fn from_info_fork(info: &[u8]) -> Result<Self, String> {
+        if info.len() < 72 {
+            return Err(format!("INFO fork too short: {} bytes", info.len()));
+        }
+        let name_len = u16::from_be_bytes([info[70], info[71]]) as usize;
+        let comment_at = 72 + name_len;
+        let comment = info
+            .get(comment_at..comment_at + 2)
+            .map(|len| u16::from_be_bytes([len[0], len[1]]) as usize)
+            .and_then(|len| info.get(comment_at + 2..comment_at + 2 + len))
+            .map(|c| c.to_vec())
+            .unwrap_or_default();
+
+        Ok(Self {
+            file_type: info[4..8].try_into().unwrap_or_default(),
+            creator: info[8..12].try_into().unwrap_or_default(),
+            finder_flags: u16::from_be_bytes([info[14], info[15]]),
+            created: hotline_date_to_unix(&info[52..60]),
+            modified: hotline_date_to_unix(&info[60..68]),
+            comment,
+        })
+    }
+
+    /// Build the INFO fork sent ahead of the data in an upload
+    pub fn to_info_fork(&self, file_name: &str) -> Vec<u8> {
+        let (name, _, _) = encoding_rs::MACINTOSH.encode(file_name);
+        let hotline_date = |unix: Option<i64>| unix.map(unix_to_hotline_date).unwrap_or_default();
+
+        let mut info = Vec::with_capacity(74 + name.len() + self.comment.len());
+        info.extend_from_slice(b"AMAC");
+        info.extend_from_slice(&self.file_type);
+        info.extend_from_slice(&self.creator);
+        info.extend_from_slice(&(self.finder_flags as u32).to_be_bytes());
+        info.extend_from_slice(&[0u8; 4 + 32]); // platform flags, reserved
+        info.extend_from_slice(&hotline_date(self.created));
+        info.extend_from_slice(&hotline_date(self.modified));
+        info.extend_from_slice(&0u16.to_be_bytes()); // name script
+        info.extend_from_slice(&(name.len() as u16).to_be_bytes());
+        info.extend_from_slice(&name);
+        info.extend_from_slice(&(self.comment.len() as u16).to_be_bytes());
+        info.extend_from_slice(&self.comment);
+        info
+    }
+
+    /// Type, creator and Finder flags, padded to the 32 byte FinderInfo record
+    fn finder_info(&self) -> [u8; 32] {
+        let mut info = [0u8; 32];
+        info[0..4].copy_from_slice(&self.file_type);
+        info[4..8].copy_from_slice(&self.creator);
+        info[8..10].copy_from_slice(&self.finder_flags.to_be_bytes());
+        info
+    }
+}
+
+const APPLE_DOUBLE_MAGIC: u32 = 0x0005_1607;
+const APPLE_DOUBLE_VERSION: u32 = 0x0002_0000;
+const ENTRY_RESOURCE_FORK: u32 = 2;
+const ENTRY_COMMENT: u32 = 4;
+const ENTRY_DATES: u32 = 8;
+const ENTRY_FINDER_INFO: u32 = 9;
+
+/// Seconds between the Unix epoch and 2000-01-01, AppleDouble's date origin
+const APPLE_DOUBLE_EPOCH: i64 = 946_684_800;
+/// Seconds between 1904-01-01, the classic Mac epoch, and the Unix epoch
+const MAC_EPOCH_OFFSET: i64 = 2_082_844_800;
+
+/// Name of the AppleDouble companion for `file_name`
+pub fn apple_double_name(file_name: &str) -> String {
+    format!("._{}", file_name)
+}
+
+/// Build an AppleDouble v2 file holding everything but the data fork
+pub fn apple_double(meta: &MacMetadata, resource: &[u8]) -> Vec<u8> {
+    let apple_date = |unix: Option<i64>| {
+        unix.map(|t| (t - APPLE_DOUBLE_EPOCH) as i32)
+            .unwrap_or(i32::MIN) // "unknown" per the spec
+            .to_be_bytes()
+    };
+    let mut dates = Vec::with_capacity(16);
+    dates.extend_from_slice(&apple_date(meta.created));
+    dates.extend_from_slice(&apple_date(meta.modified));
+    dates.extend_from_slice(&i32::MIN.to_be_bytes()); // backup
+    dates.extend_from_slice(&apple_date(meta.modified)); // access
+
+    let mut entries: Vec<(u32, Vec<u8>)> = vec![
+        (ENTRY_FINDER_INFO, meta.finder_info().to_vec()),
+        (ENTRY_DATES, dates),
+    ];
+    if !meta.comment.is_empty() {
+        entries.push((ENTRY_COMMENT, meta.comment.clone()));
+    }
+    // Resource fork goes last so tools can grow it in place
+    entries.push((ENTRY_RESOURCE_FORK, resource.to_vec()));
+
+    let header_len = 26 + entries.len() * 12;
+    let mut out = Vec::with_capacity(header_len + entries.iter().map(|(_, d)| d.len()).sum::<usize>());
+    out.extend_from_slice(&APPLE_DOUBLE_MAGIC.to_be_bytes());
+    out.extend_from_slice(&APPLE_DOUBLE_VERSION.to_be_bytes());
+    out.extend_from_slice(&[0u8; 16]);
+    out.extend_from_slice(&(entries.len() as u16).to_be_bytes());
+
+    let mut offset = header_len as u32;
+    for (id, data) in &entries {
+        out.extend_from_slice(&id.to_be_bytes());
+        out.extend_from_slice(&offset.to_be_bytes());
+        out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        offset += data.len() as u32;
+    }
+    for (_, data) in entries {
+        out.extend_from_slice(&data);
+    }
+    out
+}
+
+/// Read the metadata and resource fork back out of an AppleDouble file
+pub fn parse_apple_double(file: &[u8]) -> Result<(MacMetadata, Vec<u8>), String> {
+    let be32 = |at: usize| file.get(at..at + 4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]));
+
+    if be32(0) != Some(APPLE_DOUBLE_MAGIC) {
+        return Err("Not an AppleDouble file".to_string());
+    }
+    let count = file
+        .get(24..26)
+        .map(|b| u16::from_be_bytes([b[0], b[1]]) as usize)
+        .ok_or_else(|| "AppleDouble header is truncated".to_string())?;
+
+    let mut meta = MacMetadata::default();
+    let mut resource = Vec::new();
+    for i in 0..count {
+        let at = 26 + i * 12;
+        let (Some(id), Some(offset), Some(len)) = (be32(at), be32(at + 4), be32(at + 8)) else {
+            return Err("AppleDouble entry table is truncated".to_string());
+        };
+        let data = file
+            .get(offset as usize..offset as usize + len as usize)
+            .ok_or_else(|| format!("AppleDouble entry {} runs past the end of the file", id))?;
+
+        match id {
+            ENTRY_FINDER_INFO if data.len() >= 10 => {
+                meta.file_type.copy_from_slice(&data[0..4]);
+                meta.creator.copy_from_slice(&data[4..8]);
+                meta.finder_flags = u16::from_be_bytes([data[8], data[9]]);
+            }
+            ENTRY_DATES if data.len() >= 8 => {
+                let date = |b: &[u8]| {
+                    let t = i32::from_be_bytes([b[0], b[1], b[2], b[3]]);
+                    (t != i32::MIN).then_some(t as i64 + APPLE_DOUBLE_EPOCH)
+                };
+                meta.created = date(&data[0..4]);
+                meta.modified = date(&data[4..8]);
+            }
+            ENTRY_COMMENT => meta.comment = data.to_vec(),
+            ENTRY_RESOURCE_FORK => resource = data.to_vec(),
+            _ => {}
+        }
+    }
+    Ok((meta, resource))
+}
+
+/// Name of the MacBinary file for `file_name`
+pub fn mac_binary_name(file_name: &str) -> String {
+    format!("{}.bin", file_name)
+}
+
+/// Bytes of zero padding after a fork of `len` bytes in a MacBinary file
+pub fn mac_binary_padding(len: u64) -> usize {
+    ((128 - len % 128) % 128) as usize
+}
+
+/// CRC-16/XMODEM, which MacBinary II uses for its header checksum
+fn crc16_xmodem(data: &[u8]) -> u16 {
+    data.iter().fold(0u16, |crc, &byte| {
+        (0..8).fold(crc ^ ((byte as u16) << 8), |crc, _| {
+            if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            }
+        })
+    })
+}
+
+/// The 128 byte MacBinary II header. The data fork, padded to 128 bytes,
+/// follows it, then the resource fork, also padded.
+pub fn mac_binary_header(file_name: &str, meta: &MacMetadata, data_len: u32, resource_len: u32) -> [u8; 128] {
+    let mac_date = |unix: Option<i64>| unix.map(|t| (t + MAC_EPOCH_OFFSET) as u32).unwrap_or(0).to_be_bytes();
+
+    // Names are MacRoman and at most 63 bytes
+    let (encoded, _, _) = encoding_rs::MACINTOSH.encode(file_name);
+    let name = &encoded[..encoded.len().min(63)];
+
+    let mut header = [0u8; 128];
+    header[1] = name.len() as u8;
+    header[2..2 + name.len()].copy_from_slice(name);
+    header[65..69].copy_from_slice(&meta.file_type);
+    header[69..73].copy_from_slice(&meta.creator);
+    header[73] = (meta.finder_flags >> 8) as u8;
+    header[83..87].copy_from_slice(&data_len.to_be_bytes());
+    header[87..91].copy_from_slice(&resource_len.to_be_bytes());
+    header[91..95].copy_from_slice(&mac_date(meta.created));
+    header[95..99].copy_from_slice(&mac_date(meta.modified));
+    header[101] = meta.finder_flags as u8;
+    header[122] = 129; // written by MacBinary II
+    header[123] = 129; // readable by MacBinary II and later
+    let crc = crc16_xmodem(&header[..124]);
+    header[124..126].copy_from_slice(&crc.to_be_bytes());
+    header
+}
+
+/// A MacBinary file split back into its parts
+#[derive(Debug, Clone, PartialEq)]
+pub struct MacBinaryFile {
+    pub name: String,
+    pub meta: MacMetadata,
+    pub data: Vec<u8>,
+    pub resource: Vec<u8>,
+}
+
+/// Where the forks of a MacBinary file sit, from its 128-byte header
+#[derive(Debug, Clone, PartialEq)]
+pub struct MacBinaryLayout {
+    pub name: String,
+    pub data_at: u64,
+    pub data_len: u64,
+    pub resource_at: u64,
+    pub resource_len: u64,
+}
+
+/// Read a MacBinary header without the rest of the file. `file_len` is the
+/// size of the whole file, which must hold every fork the header claims.
+/// Returns None when the header doesn't look like MacBinary.
+pub fn mac_binary_layout(header: &[u8], file_len: u64) -> Option<MacBinaryLayout> {
+    let header = header.get(..128)?;
+    let name_len = header[1] as usize;
+    if header[0] != 0 || header[74] != 0 || name_len == 0 || name_len > 63 {
+        return None;
+    }
+    // MacBinary II and later carry a CRC; MacBinary I has a zero byte 82 instead
+    let crc = u16::from_be_bytes([header[124], header[125]]);
+    if crc != crc16_xmodem(&header[..124]) && (header[82] != 0 || crc != 0) {
+        return None;
+    }
+
+    let be32 = |at: usize| u32::from_be_bytes([header[at], header[at + 1], header[at + 2], header[at + 3]]) as u64;
+    let data_len = be32(83);
+    let resource_len = be32(87);
+    let secondary_len = u16::from_be_bytes([header[120], header[121]]) as u64;
+
+    let data_at = 128 + secondary_len + mac_binary_padding(secondary_len) as u64;
+    let resource_at = data_at + data_len + mac_binary_padding(data_len) as u64;
+    if resource_at + resource_len > file_len {
+        return None;
+    }
+
+    let (name, _, _) = encoding_rs::MACINTOSH.decode(&header[2..2 + name_len]);
+    Some(MacBinaryLayout {
+        name: name.into_owned(),
+        data_at,
+        data_len,
+        resource_at,
+        resource_len,
+    })
+}
+
+/// Unpack a MacBinary I, II or III file. Returns None when `file` doesn't
+/// look like MacBinary, so ordinary files that happen to end in `.bin` pass
+/// through untouched.
+pub fn parse_mac_binary(file: &[u8]) -> Option<MacBinaryFile> {
+    let layout = mac_binary_layout(file, file.len() as u64)?;
+    let header = &file[..128];
+    let be32 = |at: usize| u32::from_be_bytes([header[at], header[at + 1], header[at + 2], header[at + 3]]) as u64;
+
+    let mac_date = |at: usize| {
+        let t = be32(at) as i64;
+        (t != 0).then_some(t - MAC_EPOCH_OFFSET)
+    };
+    Some(MacBinaryFile {
+        meta: MacMetadata {
+            file_type: header[65..69].try_into().ok()?,
+            creator: header[69..73].try_into().ok()?,
+            finder_flags: u16::from_be_bytes([header[73], header[101]]),
+            created: mac_date(91),
+            modified: mac_date(95),
+            comment: Vec::new(),
+        },
+        data: file[layout.data_at as usize..(layout.data_at + layout.data_len) as usize].to_vec(),
+        resource: file[layout.resource_at as usize..(layout.resource_at + layout.resource_len) as usize].to_vec(),
+        name: layout.name,
+    })
+}
+
+/// A file ready to upload: its name on the server, the DATA fork, and any
+/// INFO and MACR forks to send with it
+#[derive(Debug, Clone, Default)]
+pub struct UploadFile {
+    pub file_name: String,
+    pub data: Vec<u8>,
+    pub forks: MacForks,
+}
+
+impl UploadFile {
+    pub fn new(file_name: String, data: Vec<u8>) -> Self {
+        Self { file_name, data, forks: MacForks::default() }
+    }
+
+    /// Build an upload from local input. With `mac_binary` set, data that
+    /// parses as MacBinary is unpacked and uploaded under its stored name;
+    /// otherwise `apple_double`, the contents of the `._name` companion, supplies
+    /// the metadata and resource fork for the plain data.
+    pub fn from_local(file_name: String, data: Vec<u8>, mac_binary: bool, apple_double: Option<&[u8]>) -> Result<Self, String> {
+        if mac_binary {
+            if let Some(file) = parse_mac_binary(&data) {
+                println!("Unpacked MacBinary upload: {} ({} data, {} resource bytes)", file.name, file.data.len(), file.resource.len());
+                return Ok(Self {
+                    forks: MacForks {
+                        info: Some(file.meta.to_info_fork(&file.name)),
+                        resource: Some(file.resource),
+                    },
+                    file_name: file.name,
+                    data: file.data,
+                });
+            }
+        }
+
+        match apple_double {
+            Some(companion) => {
+                let (meta, resource) = parse_apple_double(companion)?;
+                Ok(Self {
+                    forks: MacForks {
+                        info: Some(meta.to_info_fork(&file_name)),
+                        resource: Some(resource),
+                    },
+                    file_name,
+                    data,
+                })
+            }
+            None => Ok(Self::new(file_name, data)),
+        }
+    }
+}
+
+/// Write the `._name` companion for the data file at `data_path`
+pub fn write_apple_double(data_path: &Path, forks: &MacForks) -> Result<(), String> {
+    let file_name = data_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .ok_or_else(|| format!("No file name in {}", data_path.display()))?;
+    let target = data_path.with_file_name(apple_double_name(&file_name));
+    fs::write(&target, apple_double(&forks.metadata(), forks.resource()))
+        .map_err(|e| format!("Failed to write {}: {}", target.display(), e))
+}
+
+/// Assemble a MacBinary II file at `target` from the data fork stored at
+/// `data_path` and the collected forks
+pub fn write_mac_binary(data_path: &Path, target: &Path, file_name: &str, forks: &MacForks) -> Result<(), String> {
+    let write_error = |e: io::Error| format!("Failed to write {}: {}", target.display(), e);
+
+    let mut data = File::open(data_path).map_err(|e| format!("Failed to read downloaded data: {}", e))?;
+    let data_len = data.metadata().map_err(|e| e.to_string())?.len();
+    let data_len = u32::try_from(data_len).map_err(|_| "File is too large for MacBinary".to_string())?;
+    let resource = forks.resource();
+
+    let mut out = io::BufWriter::new(File::create(target).map_err(write_error)?);
+    out.write_all(&mac_binary_header(file_name, &forks.metadata(), data_len, resource.len() as u32))
+        .map_err(write_error)?;
+    io::copy(&mut data, &mut out).map_err(write_error)?;
+    out.write_all(&vec![0u8; mac_binary_padding(data_len as u64)]).map_err(write_error)?;
+    out.write_all(resource).map_err(write_error)?;
+    out.write_all(&vec![0u8; mac_binary_padding(resource.len() as u64)]).map_err(write_error)?;
+    out.flush().map_err(write_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info_fork() -> Vec<u8> {
+        let mut info = Vec::new();
+        info.extend_from_slice(b"AMAC");
+        info.extend_from_slice(b"TEXT");
+        info.extend_from_slice(b"ttxt");
+        info.extend_from_slice(&0x0100u32.to_be_bytes());
+        info.extend_from_slice(&[0u8; 4 + 32]);
+        // 1970-01-01 plus one day, created and modified
+        for _ in 0..2 {
+            info.extend_from_slice(&1970u16.to_be_bytes());
+            info.extend_from_slice(&0u16.to_be_bytes());
+            info.extend_from_slice(&86_400u32.to_be_bytes());
+        }
+        info.extend_from_slice(&0u16.to_be_bytes());
+        info.extend_from_slice(&(b"Read Me".len() as u16).to_be_bytes());
+        info.extend_from_slice(b"Read Me");
+        info.extend_from_slice(&(b"hello".len() as u16).to_be_bytes());
+        info.extend_from_slice(b"hello");
+        info
+    }
+
+    #[test]
+    fn parses_info_fork() {
+        let meta = MacMetadata::from_info_fork(&info_fork()).unwrap();
+        assert_eq!(&meta.file_type, b"TEXT");
+        assert_eq!(&meta.creator, b"ttxt");
+        assert_eq!(meta.finder_flags, 0x0100);
+        assert_eq!(meta.created, Some(86_400));
+        assert_eq!(meta.comment, b"hello");
+        assert!(MacMetadata::from_info_fork(&[0u8; 10]).is_err());
+    }
+
+    #[test]
+    fn apple_double_layout() {
+        let meta = MacMetadata::from_info_fork(&info_fork()).unwrap();
+        let file = apple_double(&meta, b"RSRC");
+        assert_eq!(&file[0..4], &APPLE_DOUBLE_MAGIC.to_be_bytes());
+        assert_eq!(u16::from_be_bytes([file[24], file[25]]), 4);
+
+        // Last entry is the resource fork and points at the tail of the file
+        let last = 26 + 3 * 12;
+        assert_eq!(&file[last..last + 4], &ENTRY_RESOURCE_FORK.to_be_bytes());
+        let offset = u32::from_be_bytes(file[last + 4..last + 8].try_into().unwrap()) as usize;
+        assert_eq!(&file[offset..], b"RSRC");
+        // FinderInfo starts right after the entry table
+        assert_eq!(&file[26 + 4 * 12..26 + 4 * 12 + 8], b"TEXTttxt");
+    }
+
+    #[test]
+    fn mac_binary_header_fields_and_checksum() {
+        assert_eq!(crc16_xmodem(b"123456789"), 0x31C3);
+
+        let meta = MacMetadata::from_info_fork(&info_fork()).unwrap();
+        let header = mac_binary_header("Read Me", &meta, 300, 4);
+        assert_eq!(header[1], 7);
+        assert_eq!(&header[2..9], b"Read Me");
+        assert_eq!(&header[65..73], b"TEXTttxt");
+        assert_eq!(u32::from_be_bytes(header[83..87].try_into().unwrap()), 300);
+        assert_eq!(u32::from_be_bytes(header[91..95].try_into().unwrap()), (86_400 + MAC_EPOCH_OFFSET) as u32);
+        assert_eq!(u16::from_be_bytes([header[124], header[125]]), crc16_xmodem(&header[..124]));
+
+        assert_eq!(mac_binary_padding(300), 84);
+        assert_eq!(mac_binary_padding(256), 0);
+    }
+
+    #[test]
+    fn info_fork_round_trips() {
+        let meta = MacMetadata::from_info_fork(&info_fork()).unwrap();
+        let encoded = meta.to_info_fork("Read Me");
+        assert_eq!(encoded, info_fork());
+        assert_eq!(MacMetadata::from_info_fork(&encoded).unwrap(), meta);
+    }
+
+    #[test]
+    fn unpacks_mac_binary_and_apple_double_uploads() {
+        let meta = MacMetadata::from_info_fork(&info_fork()).unwrap();
+        let data = vec![b'd'; 300];
+
+        let mut file = mac_binary_header("Read Me", &meta, data.len() as u32, 4).to_vec();
+        file.extend_from_slice(&data);
+        file.extend(vec![0u8; mac_binary_padding(300)]);
+        file.extend_from_slice(b"RSRC");
+        file.extend(vec![0u8; mac_binary_padding(4)]);
+
+        let upload = UploadFile::from_local("Read Me.bin".to_string(), file.clone(), true, None).unwrap();
+        assert_eq!(upload.file_name, "Read Me");
+        assert_eq!(upload.data, data);
+        assert_eq!(upload.forks.resource(), b"RSRC");
+        let sent = MacMetadata::from_info_fork(upload.forks.info.as_deref().unwrap()).unwrap();
+        assert_eq!((sent.file_type, sent.creator, sent.created), (meta.file_type, meta.creator, meta.created));
+
+        // Not asked to decode, or not MacBinary at all: uploaded as-is
+        let upload = UploadFile::from_local("x.bin".to_string(), file, false, None).unwrap();
+        assert!(upload.forks.is_empty());
+        assert!(parse_mac_binary(&[1u8; 200]).is_none());
+
+        let companion = apple_double(&meta, b"RSRC");
+        let (parsed, resource) = parse_apple_double(&companion).unwrap();
+        assert_eq!(parsed, meta);
+        assert_eq!(resource, b"RSRC");
+        assert!(parse_apple_double(b"nope").is_err());
+    }
+}
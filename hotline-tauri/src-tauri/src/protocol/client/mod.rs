@@ -1,16 +1,60 @@
 // Hotline client implementation
 
+mod accounts;
+pub mod away;
+pub mod bandwidth;
+pub mod board;
 mod chat;
+mod events;
+pub mod file_batch;
+pub mod file_kind;
 mod files;
+pub mod folder_transfer;
+mod health;
+pub mod instant_message;
+pub mod lifecycle;
+pub mod mac_forks;
 mod news;
+pub mod occupancy;
+mod own_user;
+pub mod private_chat;
+pub mod raw;
+pub mod request_policy;
+pub mod replies;
+pub mod requests;
 mod users;
+pub mod transfer_queue;
+pub mod upload_conflict;
+mod writer;
+
+pub use accounts::Account;
+pub use files::FileDetails;
+pub(crate) use files::{fork_header, fork_size};
+pub use health::ConnectionStats;
+pub use users::{account_from_info, RosterDiff, RosterUser, UserInfo};
+pub(crate) use writer::Lane;
 
 use super::constants::{
-    FieldType, TransactionType, PROTOCOL_ID, PROTOCOL_SUBVERSION,
-    PROTOCOL_VERSION, SUBPROTOCOL_ID, TRANSACTION_HEADER_SIZE,
+    FieldType, TransactionType, DEFAULT_NICKNAME, DEFAULT_USER_ICON, FALLBACK_USER_ICON, PROTOCOL_ID,
+    PROTOCOL_SUBVERSION, PROTOCOL_VERSION, SUBPROTOCOL_ID, TRANSACTION_HEADER_SIZE,
 };
-use super::transaction::{Transaction, TransactionField};
-use super::types::{Bookmark, ConnectionStatus, ServerInfo};
+use super::i18n::{tr, tr_args, MessageId};
+use super::quirks::{Feature, ServerCapabilities, ServerQuirks};
+use super::transaction::Transaction;
+use away::Away;
+use bandwidth::{RateLimiter, TransferLimits};
+use file_kind::FileKind;
+use board::BoardSnapshot;
+use instant_message::MessagingOptions;
+use lifecycle::LifecycleNotice;
+use own_user::OwnUser;
+use request_policy::RequestPolicy;
+use replies::{FileListReply, LoginReply};
+use requests::{ClientVersion, LoginRequest};
+use transfer_queue::HostSlots;
+use health::ConnectionHealth;
+use writer::OutboundQueue;
+use super::types::{Bookmark, ClientIdentity, ConnectionStatus, ServerInfo, User, UserAccess};
 use std::collections::HashMap;
 use std::net::IpAddr;
 use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
@@ -31,6 +75,24 @@ use tokio_rustls::TlsConnector;
 pub(crate) type BoxedRead = Box<dyn AsyncRead + Unpin + Send>;
 pub(crate) type BoxedWrite = Box<dyn AsyncWrite + Unpin + Send>;
 
+/// Large transaction bodies (a big message board, say) are read this much at
+/// a time, yielding in between so they don't hold up other tasks
+const RECEIVE_SLICE: usize = 64 * 1024;
+
+/// Largest transaction body we accept. The message board is the biggest thing
+/// a server sends; without a limit a bogus header could have us allocate 4 GiB.
+const MAX_TRANSACTION_DATA: u32 = 32 * 1024 * 1024;
+
+async fn read_in_slices(stream: &mut BoxedRead, buf: &mut [u8]) -> std::io::Result<()> {
+    for slice in buf.chunks_mut(RECEIVE_SLICE) {
+        stream.read_exact(slice).await?;
+        if slice.len() == RECEIVE_SLICE {
+            tokio::task::yield_now().await;
+        }
+    }
+    Ok(())
+}
+
 /// Certificate verifier that accepts any certificate.
 /// Hotline servers typically use self-signed certificates.
 #[derive(Debug)]
@@ -78,34 +140,139 @@ impl ServerCertVerifier for NoVerifier {
 pub enum HotlineEvent {
     ChatMessage { user_id: u16, user_name: String, message: String },
     ServerMessage(String),
-    PrivateMessage { user_id: u16, message: String },
+    PrivateMessage { user_id: u16, user_name: String, message: String, quoting: Option<String> },
+    /// The user doesn't accept private messages
+    MessageRefused { user_id: u16, user_name: String, message: String },
+    /// The user doesn't accept private chat invitations
+    ChatRefused { user_id: u16, user_name: String, message: String },
+    AutomaticResponse { user_id: u16, user_name: String, message: String },
     UserJoined { user_id: u16, user_name: String, icon: u16, flags: u16 },
     UserLeft { user_id: u16 },
-    UserChanged { user_id: u16, user_name: String, icon: u16, flags: u16 },
+    /// `is_self` marks the echo of our own user info; `previous_name` is the
+    /// user's name before the change, None when they weren't online
+    UserChanged { user_id: u16, user_name: String, icon: u16, flags: u16, is_self: bool, previous_name: Option<String> },
+    /// What a re-requested user list changed in the roster
+    UserListSynced(RosterDiff),
     AgreementRequired(String),
     FileList { files: Vec<FileInfo>, path: Vec<String> },
     NewMessageBoardPost(String),
+    /// Posts decoded so far while loading a page of the message board
+    MessageBoardChunk(Vec<String>),
     StatusChanged(ConnectionStatus),
+    /// The receive loop lost the socket without us asking to disconnect
+    ConnectionLost,
+    ChatInvite { chat_id: u32, user_id: u16, user_name: String },
+    ChatUserJoined { chat_id: u32, user_id: u16, user_name: String, icon: u16, flags: u16 },
+    ChatUserLeft { chat_id: u32, user_id: u16 },
+    ChatSubjectChanged { chat_id: u32, subject: String },
+    PrivateChatMessage { chat_id: u32, user_id: u16, user_name: String, message: String },
+    /// We went away or came back; `automatic` when the idle timer did it
+    AwayChanged { away: bool, automatic: bool },
+    /// A request got an error reply, or no reply before its retries ran out
+    RequestFailed { action: String, error: String, timed_out: bool },
+    /// A background task started, stopped or stalled
+    Lifecycle(LifecycleNotice),
 }
 
-#[derive(Debug, Clone)]
+impl HotlineEvent {
+    /// Short camelCase name of the event kind, used for metrics
+    pub fn name(&self) -> &'static str {
+        match self {
+            HotlineEvent::ChatMessage { .. } => "chatMessage",
+            HotlineEvent::ServerMessage(_) => "serverMessage",
+            HotlineEvent::PrivateMessage { .. } => "privateMessage",
+            HotlineEvent::MessageRefused { .. } => "messageRefused",
+            HotlineEvent::ChatRefused { .. } => "chatRefused",
+            HotlineEvent::AutomaticResponse { .. } => "automaticResponse",
+            HotlineEvent::UserJoined { .. } => "userJoined",
+            HotlineEvent::UserLeft { .. } => "userLeft",
+            HotlineEvent::UserChanged { .. } => "userChanged",
+            HotlineEvent::UserListSynced(_) => "userListSynced",
+            HotlineEvent::AgreementRequired(_) => "agreementRequired",
+            HotlineEvent::FileList { .. } => "fileList",
+            HotlineEvent::NewMessageBoardPost(_) => "newMessageBoardPost",
+            HotlineEvent::MessageBoardChunk(_) => "messageBoardChunk",
+            HotlineEvent::StatusChanged(_) => "statusChanged",
+            HotlineEvent::ConnectionLost => "connectionLost",
+            HotlineEvent::ChatInvite { .. } => "chatInvite",
+            HotlineEvent::ChatUserJoined { .. } => "chatUserJoined",
+            HotlineEvent::ChatUserLeft { .. } => "chatUserLeft",
+            HotlineEvent::ChatSubjectChanged { .. } => "chatSubjectChanged",
+            HotlineEvent::PrivateChatMessage { .. } => "privateChatMessage",
+            HotlineEvent::AwayChanged { .. } => "awayChanged",
+            HotlineEvent::RequestFailed { .. } => "requestFailed",
+            HotlineEvent::Lifecycle(_) => "lifecycle",
+        }
+    }
+}
+
+/// Login refusals the UI can do something about, beyond showing the message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LoginRefusal {
+    /// The account is already connected from another session
+    AlreadyLoggedIn,
+    /// The server has reached its user limit
+    ServerFull,
+}
+
+impl LoginRefusal {
+    /// Recognize a refusal from the error text of a failed login reply.
+    /// Servers don't use a dedicated error code for these, only the wording.
+    pub fn from_error_text(text: &str) -> Option<Self> {
+        let text = text.to_lowercase();
+        const ALREADY_LOGGED_IN: [&str; 3] = ["already logged in", "already connected", "logged in from another"];
+        const SERVER_FULL: [&str; 4] = ["server is full", "server full", "too many users", "maximum number of users"];
+        if ALREADY_LOGGED_IN.iter().any(|phrase| text.contains(phrase)) {
+            Some(LoginRefusal::AlreadyLoggedIn)
+        } else if SERVER_FULL.iter().any(|phrase| text.contains(phrase)) {
+            Some(LoginRefusal::ServerFull)
+        } else {
+            None
+        }
+    }
+}
+
+/// Why one attempt at a request got no usable reply
+enum RequestFailure {
+    /// No reply within the timeout; read-only requests may be retried
+    TimedOut,
+    Failed(String),
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct FileInfo {
     pub name: String,
-    pub size: u32,
+    pub size: u64,
     pub is_folder: bool,
     pub file_type: String,
     pub creator: String,
+    /// Icon the file browser shows; cached listings from before it existed lack it
+    #[serde(default)]
+    pub kind: FileKind,
 }
 
 pub struct HotlineClient {
     bookmark: Bookmark,
     username: Arc<Mutex<String>>,
     user_icon_id: Arc<Mutex<u16>>,
+    identity: Arc<Mutex<ClientIdentity>>,
     status: Arc<Mutex<ConnectionStatus>>,
     read_half: Arc<Mutex<Option<BoxedRead>>>,
     write_half: Arc<Mutex<Option<BoxedWrite>>>,
+    /// Prioritized lanes into the writer task, set once logged in
+    outbound: Arc<Mutex<Option<OutboundQueue>>>,
     transaction_counter: Arc<AtomicU32>,
     running: Arc<AtomicBool>,
+    /// Agreement text the user last accepted, so a reconnect can accept it again
+    accepted_agreement: Arc<Mutex<Option<String>>>,
+    /// Treat the connection as lost after this long without traffic
+    watchdog_timeout: Arc<Mutex<Option<Duration>>>,
+    health: Arc<Mutex<ConnectionHealth>>,
+    transfer_limits: Arc<Mutex<TransferLimits>>,
+    transfer_slots: Arc<Mutex<Option<HostSlots>>>,
+    request_policy: Arc<Mutex<RequestPolicy>>,
 
     // Event channel
     event_tx: mpsc::UnboundedSender<HotlineEvent>,
@@ -114,18 +281,40 @@ pub struct HotlineClient {
     // Pending transactions (for request/reply pattern)
     pending_transactions: Arc<RwLock<HashMap<u32, mpsc::Sender<Transaction>>>>,
 
+    // Raw transactions waiting for the undecoded reply frame
+    raw_replies: Arc<RwLock<HashMap<u32, mpsc::Sender<Vec<u8>>>>>,
+
     // Track file list paths by transaction ID
     file_list_paths: Arc<RwLock<HashMap<u32, Vec<String>>>>,
 
+    /// Users online, kept current by the receive loop
+    users: Arc<RwLock<HashMap<u16, User>>>,
+
+    /// Which user is us, learned from the echo of our user info
+    own_user: Arc<Mutex<OwnUser>>,
+
     // Server info (extracted from login reply)
     server_info: Arc<Mutex<Option<ServerInfo>>>,
     
     // User access permissions (from login reply)
-    user_access: Arc<Mutex<u64>>,
+    user_access: Arc<Mutex<UserAccess>>,
+
+    /// Why the last login was refused, when it was one the UI can act on
+    login_refusal: Arc<Mutex<Option<LoginRefusal>>>,
+
+    /// Refusals and automatic response, sent with our user info
+    messaging: Arc<Mutex<MessagingOptions>>,
+
+    /// Away status and when we last chatted
+    away: Arc<Mutex<Away>>,
+
+    /// Message board from the last fetch, paged out on request
+    board: Arc<Mutex<BoardSnapshot>>,
 
     // Background tasks
     receive_task: Arc<Mutex<Option<JoinHandle<()>>>>,
     keepalive_task: Arc<Mutex<Option<JoinHandle<()>>>>,
+    writer_task: Arc<Mutex<Option<JoinHandle<()>>>>,
 }
 
 impl HotlineClient {
@@ -134,21 +323,37 @@ impl HotlineClient {
 
         Self {
             bookmark,
-            username: Arc::new(Mutex::new("guest".to_string())),
-            user_icon_id: Arc::new(Mutex::new(191)),
+            username: Arc::new(Mutex::new(DEFAULT_NICKNAME.to_string())),
+            user_icon_id: Arc::new(Mutex::new(DEFAULT_USER_ICON)),
+            identity: Arc::new(Mutex::new(ClientIdentity::default())),
             status: Arc::new(Mutex::new(ConnectionStatus::Disconnected)),
             read_half: Arc::new(Mutex::new(None)),
             write_half: Arc::new(Mutex::new(None)),
+            outbound: Arc::new(Mutex::new(None)),
             transaction_counter: Arc::new(AtomicU32::new(1)),
             file_list_paths: Arc::new(RwLock::new(HashMap::new())),
+            users: Arc::new(RwLock::new(HashMap::new())),
+            own_user: Arc::new(Mutex::new(OwnUser::default())),
             server_info: Arc::new(Mutex::new(None)),
-            user_access: Arc::new(Mutex::new(0)), // Default to no permissions
+            user_access: Arc::new(Mutex::new(UserAccess::default())), // Default to no permissions
+            login_refusal: Arc::new(Mutex::new(None)),
+            messaging: Arc::new(Mutex::new(MessagingOptions::default())),
+            away: Arc::new(Mutex::new(Away::default())),
+            board: Arc::new(Mutex::new(BoardSnapshot::default())),
             running: Arc::new(AtomicBool::new(false)),
+            accepted_agreement: Arc::new(Mutex::new(None)),
+            watchdog_timeout: Arc::new(Mutex::new(None)),
+            health: Arc::new(Mutex::new(ConnectionHealth::default())),
+            transfer_limits: Arc::new(Mutex::new(TransferLimits::default())),
+            transfer_slots: Arc::new(Mutex::new(None)),
+            request_policy: Arc::new(Mutex::new(RequestPolicy::default())),
             event_tx,
             event_rx: Arc::new(Mutex::new(Some(event_rx))),
             pending_transactions: Arc::new(RwLock::new(HashMap::new())),
+            raw_replies: Arc::new(RwLock::new(HashMap::new())),
             receive_task: Arc::new(Mutex::new(None)),
             keepalive_task: Arc::new(Mutex::new(None)),
+            writer_task: Arc::new(Mutex::new(None)),
         }
     }
 
@@ -157,10 +362,180 @@ impl HotlineClient {
         *self.user_icon_id.lock().await = user_icon_id;
     }
 
+    pub async fn set_identity(&self, identity: ClientIdentity) {
+        *self.identity.lock().await = identity;
+    }
+
+    /// `None` turns the no-traffic watchdog off. Takes effect on the next connect.
+    pub async fn set_watchdog_timeout(&self, timeout: Option<Duration>) {
+        *self.watchdog_timeout.lock().await = timeout;
+    }
+
+    /// Rate limits for transfers started from now on
+    pub async fn set_transfer_limits(&self, limits: TransferLimits) {
+        *self.transfer_limits.lock().await = limits;
+    }
+
+    /// Share the app-wide per-host transfer connection cap with this client
+    pub async fn set_transfer_slots(&self, slots: HostSlots) {
+        *self.transfer_slots.lock().await = Some(slots);
+    }
+
+    pub async fn set_request_policy(&self, policy: RequestPolicy) {
+        *self.request_policy.lock().await = policy;
+    }
+
+    pub(crate) async fn download_limiter(&self) -> RateLimiter {
+        RateLimiter::new(self.transfer_limits.lock().await.download_bytes_per_sec)
+    }
+
+    pub(crate) async fn upload_limiter(&self) -> RateLimiter {
+        RateLimiter::new(self.transfer_limits.lock().await.upload_bytes_per_sec)
+    }
+
+    pub async fn login_refusal(&self) -> Option<LoginRefusal> {
+        *self.login_refusal.lock().await
+    }
+
+    /// Server address and port this client connects to
+    pub fn address(&self) -> (&str, u16) {
+        (&self.bookmark.address, self.bookmark.port)
+    }
+
+    pub async fn connection_stats(&self) -> ConnectionStats {
+        let mut stats = self.health.lock().await.stats();
+        stats.users = self.users.read().await.len();
+        stats.user_limit = self.server_info.lock().await.as_ref().and_then(|info| info.user_limit);
+        stats
+    }
+
+    pub async fn get_username(&self) -> String {
+        self.username.lock().await.clone()
+    }
+
+    pub async fn get_user_icon_id(&self) -> u16 {
+        *self.user_icon_id.lock().await
+    }
+
     pub(crate) fn next_transaction_id(&self) -> u32 {
         self.transaction_counter.fetch_add(1, Ordering::SeqCst)
     }
 
+    /// Send a transaction that expects no reply
+    pub(crate) async fn send_transaction(&self, transaction: Transaction, action: &str) -> Result<(), String> {
+        let lane = Lane::for_transaction(transaction.transaction_type);
+        self.send_frame(lane, transaction.encode())
+            .await
+            .map_err(|e| format!("Failed to {}: {}", action, e))
+    }
+
+    /// Queue an encoded frame on `lane` and wait until it has been written
+    pub(crate) async fn send_frame(&self, lane: Lane, data: Vec<u8>) -> Result<(), String> {
+        let outbound = self
+            .outbound
+            .lock()
+            .await
+            .clone()
+            .ok_or_else(|| tr(MessageId::NotConnected))?;
+        outbound.send(lane, data).await
+    }
+
+    /// Send a transaction and wait for its reply. `action` describes the request in error messages.
+    pub(crate) async fn send_and_wait(&self, transaction: Transaction, action: &str) -> Result<Transaction, String> {
+        let reply = self.send_and_wait_reply(transaction, action).await?;
+        self.check_reply(reply, action)
+    }
+
+    /// Send a request that reads from `feature`, noting a refusal
+    pub(crate) async fn send_feature_request(&self, feature: Feature, transaction: Transaction, action: &str) -> Result<Transaction, String> {
+        let reply = self.send_and_wait_reply(transaction, action).await?;
+        self.note_refusal(feature, &reply, action).await;
+        self.check_reply(reply, action)
+    }
+
+    /// A server that refuses to let us read from `feature` although our
+    /// access allows it has switched the feature off, so later requests fail
+    /// fast instead of asking again
+    pub(crate) async fn note_refusal(&self, feature: Feature, reply: &Transaction, action: &str) {
+        if reply.error_code == 0 || !self.user_access.lock().await.news_read_article {
+            return;
+        }
+        println!("Server refused to {} although our access allows it; treating {:?} as off", action, feature);
+        if let Some(info) = self.server_info.lock().await.as_mut() {
+            info.capabilities.disable(feature);
+        }
+    }
+
+    /// Turn an error reply into an error, announcing it as a failed request
+    fn check_reply(&self, reply: Transaction, action: &str) -> Result<Transaction, String> {
+        if reply.error_code != 0 {
+            let error = replies::error_text(&reply);
+            let _ = self.event_tx.send(HotlineEvent::RequestFailed {
+                action: action.to_string(),
+                error: error.clone(),
+                timed_out: false,
+            });
+            return Err(format!("Failed to {}: {}", action, error));
+        }
+
+        Ok(reply)
+    }
+
+    /// Send a transaction and wait for its reply under the connection's
+    /// request policy, leaving the reply's error code to the caller.
+    /// Read-only requests that time out are sent again with a fresh
+    /// transaction ID.
+    pub(crate) async fn send_and_wait_reply(&self, mut transaction: Transaction, action: &str) -> Result<Transaction, String> {
+        let policy = *self.request_policy.lock().await;
+        let retries = policy.retries_for(transaction.transaction_type);
+        let mut retry = 0;
+        loop {
+            match self.try_send_and_wait(transaction.clone(), action, policy.timeout()).await {
+                Err(RequestFailure::TimedOut) if retry < retries => {
+                    retry += 1;
+                    println!("No reply to {} after {:?}, retrying ({}/{})", action, policy.timeout(), retry, retries);
+                    tokio::time::sleep(policy.backoff(retry)).await;
+                    transaction.id = self.next_transaction_id();
+                }
+                Err(RequestFailure::TimedOut) => {
+                    let error = tr(MessageId::Timeout);
+                    let _ = self.event_tx.send(HotlineEvent::RequestFailed {
+                        action: action.to_string(),
+                        error: error.clone(),
+                        timed_out: true,
+                    });
+                    return Err(error);
+                }
+                Err(RequestFailure::Failed(e)) => return Err(e),
+                Ok(reply) => return Ok(reply),
+            }
+        }
+    }
+
+    /// One attempt at a request
+    async fn try_send_and_wait(&self, transaction: Transaction, action: &str, timeout: Duration) -> Result<Transaction, RequestFailure> {
+        let transaction_id = transaction.id;
+        let (tx, mut rx) = mpsc::channel(1);
+        self.pending_transactions.write().await.insert(transaction_id, tx);
+
+        if let Err(e) = self.send_transaction(transaction, action).await {
+            self.pending_transactions.write().await.remove(&transaction_id);
+            return Err(RequestFailure::Failed(e));
+        }
+
+        match tokio::time::timeout(timeout, rx.recv()).await {
+            Ok(Some(reply)) => Ok(reply),
+            Ok(None) => {
+                self.pending_transactions.write().await.remove(&transaction_id);
+                Err(RequestFailure::Failed(tr(MessageId::ChannelClosed)))
+            }
+            Err(_) => {
+                self.pending_transactions.write().await.remove(&transaction_id);
+                Err(RequestFailure::TimedOut)
+            }
+        }
+    }
+
     pub async fn connect(&self) -> Result<(), String> {
         let tls_label = if self.bookmark.tls { " (TLS)" } else { "" };
         println!("Connecting to {}:{}{tls_label}...", self.bookmark.address, self.bookmark.port);
@@ -204,11 +579,12 @@ impl HotlineClient {
         self.login().await?;
 
         // Start background tasks
+        self.start_writer().await;
         self.start_receive_loop().await;
         self.start_keepalive().await;
 
-        // Request initial user list
-        self.get_user_list().await?;
+        // The caller loads the initial user list with load_user_list; it's
+        // left out here so a slow list doesn't count against connect timeouts
 
         println!("Successfully connected and logged in!");
 
@@ -262,7 +638,7 @@ impl HotlineClient {
             let mut write_guard = self.write_half.lock().await;
             let write_stream = write_guard
                 .as_mut()
-                .ok_or("Not connected".to_string())?;
+                .ok_or_else(|| tr(MessageId::NotConnected))?;
             write_stream
                 .write_all(&handshake)
                 .await
@@ -275,7 +651,7 @@ impl HotlineClient {
             let mut read_guard = self.read_half.lock().await;
             let read_stream = read_guard
                 .as_mut()
-                .ok_or("Not connected".to_string())?;
+                .ok_or_else(|| tr(MessageId::NotConnected))?;
             read_stream
                 .read_exact(&mut response)
                 .await
@@ -299,6 +675,7 @@ impl HotlineClient {
 
     async fn login(&self) -> Result<(), String> {
         println!("Logging in as {}...", self.bookmark.login);
+        *self.login_refusal.lock().await = None;
 
         // Update status
         {
@@ -308,40 +685,37 @@ impl HotlineClient {
         }
 
         // Build login transaction
-        let mut transaction = Transaction::new(self.next_transaction_id(), TransactionType::Login);
-
-        // Add fields
-        transaction.add_field(TransactionField::from_encoded_string(
-            FieldType::UserLogin,
-            &self.bookmark.login,
-        ));
-        transaction.add_field(TransactionField::from_encoded_string(
-            FieldType::UserPassword,
-            self.bookmark.password.as_deref().unwrap_or(""),
-        ));
         let user_icon_id = *self.user_icon_id.lock().await;
         let username = self.username.lock().await.clone();
-        
-        transaction.add_field(TransactionField::from_u16(
-            FieldType::UserIconId,
-            user_icon_id,
-        ));
-        transaction.add_field(TransactionField::from_string(
-            FieldType::UserName,
-            &username,
-        ));
-        transaction.add_field(TransactionField::from_u32(FieldType::VersionNumber, 255));
+        let identity = self.identity.lock().await.clone();
+        {
+            let mut own_user = self.own_user.lock().await;
+            own_user.reset();
+            own_user.expect(&username, &username, user_icon_id);
+        }
+        let transaction = self.request(&LoginRequest {
+            login: &self.bookmark.login,
+            password: self.bookmark.password.as_deref().unwrap_or(""),
+            user_name: &username,
+            icon_id: user_icon_id,
+            version_number: identity.version_number,
+            client: identity.send_identification.then(|| ClientVersion {
+                name: &identity.name,
+                version: &identity.version,
+            }),
+        });
 
         // Send transaction
         let encoded = transaction.encode();
         println!("Login transaction: {} bytes, fields={}", encoded.len(), transaction.fields.len());
-        println!("Transaction data: {:02X?}", &encoded[..std::cmp::min(40, encoded.len())]);
+        let dump = transaction.redacted().encode();
+        println!("Transaction data: {:02X?}", &dump[..std::cmp::min(40, dump.len())]);
 
         {
             let mut write_guard = self.write_half.lock().await;
             let write_stream = write_guard
                 .as_mut()
-                .ok_or("Not connected".to_string())?;
+                .ok_or_else(|| tr(MessageId::NotConnected))?;
             write_stream
                 .write_all(&encoded)
                 .await
@@ -357,7 +731,7 @@ impl HotlineClient {
             let mut read_guard = self.read_half.lock().await;
             let read_stream = read_guard
                 .as_mut()
-                .ok_or("Not connected".to_string())?;
+                .ok_or_else(|| tr(MessageId::NotConnected))?;
             read_stream
                 .read_exact(&mut header)
                 .await
@@ -371,12 +745,15 @@ impl HotlineClient {
         let mut full_data = header.to_vec();
 
         // Read additional data if present
+        if data_size > MAX_TRANSACTION_DATA {
+            return Err(format!("Login reply is too large: {} bytes", data_size));
+        }
         if data_size > 0 {
             let mut additional_data = vec![0u8; data_size as usize];
             let mut read_guard = self.read_half.lock().await;
             let read_stream = read_guard
                 .as_mut()
-                .ok_or("Not connected".to_string())?;
+                .ok_or_else(|| tr(MessageId::NotConnected))?;
             read_stream
                 .read_exact(&mut additional_data)
                 .await
@@ -389,74 +766,53 @@ impl HotlineClient {
 
         println!("Login reply: error_code={}, fields={}", reply.error_code, reply.fields.len());
 
-        // Check for error
-        if reply.error_code != 0 {
-            // Try to get error text from various possible fields
-            let error_msg = reply
-                .get_field(FieldType::ErrorText)
-                .and_then(|f| f.to_string().ok())
-                .or_else(|| {
-                    // Some servers put error text in Data field
-                    reply.get_field(FieldType::Data)
-                        .and_then(|f| f.to_string().ok())
-                })
-                .unwrap_or_else(|| {
-                    // Map common error codes to messages
-                    match reply.error_code {
-                        1 => "Invalid login credentials or server rejected login".to_string(),
-                        2 => "Server is full".to_string(),
-                        3 => "Banned from server".to_string(),
-                        _ => format!("Error code: {}", reply.error_code),
+        let login = match LoginReply::from_reply(&reply) {
+            Ok(login) => login,
+            Err(error_msg) => {
+                // Log all fields for debugging
+                println!("Login failed with error_code={}, fields={}", reply.error_code, reply.fields.len());
+                for (i, field) in reply.fields.iter().enumerate() {
+                    println!("  Field {}: type={:?} ({}), size={} bytes", 
+                        i, field.field_type, field.field_type as u16, field.data.len());
+                    if field.is_credential() {
+                        continue;
                     }
-                });
-
-            // Log all fields for debugging
-            println!("Login failed with error_code={}, fields={}", reply.error_code, reply.fields.len());
-            for (i, field) in reply.fields.iter().enumerate() {
-                println!("  Field {}: type={:?} ({}), size={} bytes", 
-                    i, field.field_type, field.field_type as u16, field.data.len());
-                if let Ok(text) = field.to_string() {
-                    if text.len() < 200 {
-                        println!("    Text: {}", text);
+                    if let Ok(text) = field.to_string() {
+                        if text.len() < 200 {
+                            println!("    Text: {}", text);
+                        }
                     }
                 }
-            }
 
-            return Err(format!("Login failed: {}", error_msg));
-        }
+                let refusal = LoginRefusal::from_error_text(&error_msg);
+                *self.login_refusal.lock().await = refusal;
+                if refusal == Some(LoginRefusal::AlreadyLoggedIn) {
+                    return Err(tr(MessageId::AlreadyLoggedIn));
+                }
+
+                return Err(tr_args(MessageId::LoginFailed, &[("reason", &error_msg)]));
+            }
+        };
 
         // Extract server info from login reply
-        let server_name = reply
-            .get_field(FieldType::ServerName)
-            .and_then(|f| f.to_string().ok())
-            .unwrap_or_else(|| self.bookmark.name.clone());
-        
-        let server_version = reply
-            .get_field(FieldType::VersionNumber)
-            .and_then(|f| f.to_u16().ok())
+        let server_name = login.server_name.unwrap_or_else(|| self.bookmark.name.clone());
+        let server_version = login
+            .version
             .map(|v| v.to_string())
             .unwrap_or_else(|| "Unknown".to_string());
-        
+        let server_software = login.software;
+        let quirks = ServerQuirks::for_software(server_software).with_version(login.version);
+        println!("Detected server software: {:?}, quirks: {:?}", server_software, quirks);
+
         // Server description may be in Data field or not present
-        let server_description = reply
-            .get_field(FieldType::Data)
-            .and_then(|f| f.to_string().ok())
-            .filter(|s| !s.is_empty() && s != &server_name)
-            .unwrap_or_else(|| String::new());
-
-        // Parse and store user access permissions (if present)
-        // This is optional - some servers may not send it, which is fine
-        let user_access = reply
-            .get_field(FieldType::UserAccess)
-            .and_then(|f| f.to_u64().ok())
-            .unwrap_or(0);
-        
-        {
-            let mut access_guard = self.user_access.lock().await;
-            *access_guard = user_access;
-        }
-        
-        println!("User access permissions: 0x{:016X}", user_access);
+        let server_description = login
+            .description
+            .filter(|s| s != &server_name)
+            .unwrap_or_default();
+
+        // User access permissions are optional - some servers don't send them
+        println!("User access permissions: 0x{:016X}", login.user_access.to_bits());
+        *self.user_access.lock().await = login.user_access;
 
         // Store server info
         {
@@ -466,6 +822,11 @@ impl HotlineClient {
                 description: server_description,
                 version: server_version,
                 agreement: None, // Agreement is handled separately
+                agreement_accepted: false,
+                server_software,
+                quirks,
+                capabilities: ServerCapabilities::default(),
+                user_limit: login.user_limit,
             });
         }
 
@@ -484,54 +845,81 @@ impl HotlineClient {
     pub async fn disconnect(&self) -> Result<(), String> {
         println!("Disconnecting...");
 
-        // Stop background tasks
+        self.teardown().await;
+
+        let mut status = self.status.lock().await;
+        *status = ConnectionStatus::Disconnected;
+        let _ = self.event_tx.send(HotlineEvent::StatusChanged(ConnectionStatus::Disconnected));
+
+        println!("Disconnected");
+
+        Ok(())
+    }
+
+    /// Drop the current socket and connect again with the same bookmark and
+    /// user info. The event channel survives, so listeners keep working.
+    pub async fn reconnect(&self) -> Result<(), String> {
+        println!("Reconnecting to {}:{}...", self.bookmark.address, self.bookmark.port);
+        // The roster is kept, so the user list fetched afterwards reports who
+        // came and went while we were away
+        self.teardown().await;
+        self.connect().await
+    }
+
+    /// Whether `agreement` is the exact text the user already accepted
+    pub async fn has_accepted_agreement(&self, agreement: &str) -> bool {
+        self.accepted_agreement.lock().await.as_deref() == Some(agreement)
+    }
+
+    // Stop background tasks, close the socket and forget pending requests
+    async fn teardown(&self) {
         self.running.store(false, Ordering::SeqCst);
 
-        // Wait for tasks to finish
         if let Some(task) = self.receive_task.lock().await.take() {
+            if !task.is_finished() {
+                self.notify_stopped(LifecycleNotice::ReceiveLoopStopped { reason: "disconnect".to_string(), expected: true });
+            }
             task.abort();
         }
         if let Some(task) = self.keepalive_task.lock().await.take() {
-            task.abort();
-        }
-
-        // Close both halves of the stream
-        {
-            let mut read_guard = self.read_half.lock().await;
-            if let Some(read_half) = read_guard.take() {
-                drop(read_half);
+            if !task.is_finished() {
+                self.notify_stopped(LifecycleNotice::KeepaliveStopped { reason: "disconnect".to_string(), expected: true });
             }
+            task.abort();
         }
-        {
-            let mut write_guard = self.write_half.lock().await;
-            if let Some(write_half) = write_guard.take() {
-                drop(write_half);
-            }
+        self.outbound.lock().await.take();
+        if let Some(task) = self.writer_task.lock().await.take() {
+            task.abort();
         }
 
-        // Clean up pending state
-        {
-            let mut paths = self.file_list_paths.write().await;
-            paths.clear();
-        }
-        {
-            let mut pending = self.pending_transactions.write().await;
-            pending.clear();
+        // Close both halves of the stream. Shutting down the write half ends
+        // a TLS session properly and sends FIN, so the server drops us from
+        // its user list right away instead of at its idle timeout.
+        self.read_half.lock().await.take();
+        if let Some(mut write_half) = self.write_half.lock().await.take() {
+            let _ = tokio::time::timeout(Duration::from_secs(2), write_half.shutdown()).await;
         }
 
-        let mut status = self.status.lock().await;
-        *status = ConnectionStatus::Disconnected;
-        let _ = self.event_tx.send(HotlineEvent::StatusChanged(ConnectionStatus::Disconnected));
-
-        println!("Disconnected");
+        self.file_list_paths.write().await.clear();
+        self.pending_transactions.write().await.clear();
+        self.raw_replies.write().await.clear();
+    }
 
-        Ok(())
+    fn notify_stopped(&self, notice: LifecycleNotice) {
+        let _ = self.event_tx.send(HotlineEvent::Lifecycle(notice));
     }
 
     pub async fn get_status(&self) -> ConnectionStatus {
         self.status.lock().await.clone()
     }
 
+    // Start the task that owns writes to the server after login
+    async fn start_writer(&self) {
+        let (queue, task) = writer::spawn_writer(self.write_half.clone(), self.event_tx.clone());
+        *self.outbound.lock().await = Some(queue);
+        *self.writer_task.lock().await = Some(task);
+    }
+
     // Start background task to receive messages from server
     async fn start_receive_loop(&self) {
         println!("Starting receive loop...");
@@ -544,9 +932,25 @@ impl HotlineClient {
         let status = self.status.clone();
         let event_tx = self.event_tx.clone();
         let pending_transactions = self.pending_transactions.clone();
+        let raw_replies = self.raw_replies.clone();
         let file_list_paths = self.file_list_paths.clone();
+        let users = self.users.clone();
+        let own_user = self.own_user.clone();
+        let username = self.username.clone();
+        let user_icon_id = self.user_icon_id.clone();
+        let health = self.health.clone();
+
+        let quirks = self.quirks().await;
+        let watchdog = health::watchdog_window(
+            *self.watchdog_timeout.lock().await,
+            Duration::from_secs(quirks.keepalive_interval_secs),
+        );
+        health.lock().await.reset(quirks.connection_keepalive, watchdog);
 
         let task = tokio::spawn(async move {
+            let _ = event_tx.send(HotlineEvent::Lifecycle(LifecycleNotice::ReceiveLoopStarted));
+            // Set on every way out except `running` being cleared
+            let mut stop_reason = None;
             while running.load(Ordering::SeqCst) {
                 // Read transaction header
                 let mut header = [0u8; TRANSACTION_HEADER_SIZE];
@@ -554,14 +958,30 @@ impl HotlineClient {
                 let mut read_guard = read_half.lock().await;
                 let read_stream = match read_guard.as_mut() {
                     Some(s) => s,
-                    None => break,
+                    None => {
+                        stop_reason = Some("socket already closed");
+                        break;
+                    }
                 };
 
-                let read_result = read_stream.read_exact(&mut header).await;
+                let read_result = match watchdog {
+                    Some(window) => tokio::time::timeout(window, read_stream.read_exact(&mut header))
+                        .await
+                        .unwrap_or_else(|_| {
+                            println!("Watchdog: no traffic for {}s, treating connection as lost", window.as_secs());
+                            Err(std::io::ErrorKind::TimedOut.into())
+                        }),
+                    None => read_stream.read_exact(&mut header).await,
+                };
                 drop(read_guard);
 
-                if read_result.is_err() {
+                if let Err(e) = read_result {
                     println!("Receive loop: connection closed");
+                    stop_reason = Some(if e.kind() == std::io::ErrorKind::TimedOut {
+                        "no traffic before the watchdog ran out"
+                    } else {
+                        "connection closed"
+                    });
                     // Clear both halves to prevent further writes
                     {
                         let mut read_guard = read_half.lock().await;
@@ -571,12 +991,12 @@ impl HotlineClient {
                         let mut write_guard = write_half.lock().await;
                         write_guard.take();
                     }
-                    // Update status
+                    // Update status; the event loop decides whether to reconnect
                     {
                         let mut status_guard = status.lock().await;
                         *status_guard = ConnectionStatus::Disconnected;
                     }
-                    let _ = event_tx.send(HotlineEvent::StatusChanged(ConnectionStatus::Disconnected));
+                    let _ = event_tx.send(HotlineEvent::ConnectionLost);
                     break;
                 }
 
@@ -594,39 +1014,55 @@ impl HotlineClient {
                 let mut full_data = header.to_vec();
 
                 if data_size > 0 {
-                    let mut additional_data = vec![0u8; data_size as usize];
                     let mut read_guard = read_half.lock().await;
                     let read_stream = match read_guard.as_mut() {
                         Some(s) => s,
-                        None => break,
+                        None => {
+                            stop_reason = Some("socket already closed");
+                            break;
+                        }
                     };
 
-                    let read_result = read_stream.read_exact(&mut additional_data).await;
+                    let read_result = if data_size > MAX_TRANSACTION_DATA {
+                        Err("transaction too large")
+                    } else {
+                        let mut additional_data = vec![0u8; data_size as usize];
+                        read_in_slices(read_stream, &mut additional_data)
+                            .await
+                            .map(|_| additional_data)
+                            .map_err(|_| "connection closed while reading data")
+                    };
                     drop(read_guard);
-                    
-                    if read_result.is_err() {
-                        println!("Receive loop: connection closed while reading data");
-                        // Clear both halves to prevent further writes
-                        {
-                            let mut read_guard = read_half.lock().await;
-                            read_guard.take();
-                        }
-                        {
-                            let mut write_guard = write_half.lock().await;
-                            write_guard.take();
-                        }
-                        // Update status
-                        {
-                            let mut status_guard = status.lock().await;
-                            *status_guard = ConnectionStatus::Disconnected;
+
+                    let additional_data = match read_result {
+                        Ok(data) => data,
+                        Err(reason) => {
+                            println!("Receive loop: {}", reason);
+                            stop_reason = Some(reason);
+                            // Clear both halves to prevent further writes
+                            {
+                                let mut read_guard = read_half.lock().await;
+                                read_guard.take();
+                            }
+                            {
+                                let mut write_guard = write_half.lock().await;
+                                write_guard.take();
+                            }
+                            // Update status; the event loop decides whether to reconnect
+                            {
+                                let mut status_guard = status.lock().await;
+                                *status_guard = ConnectionStatus::Disconnected;
+                            }
+                            let _ = event_tx.send(HotlineEvent::ConnectionLost);
+                            break;
                         }
-                        let _ = event_tx.send(HotlineEvent::StatusChanged(ConnectionStatus::Disconnected));
-                        break;
-                    }
+                    };
 
                     full_data.extend(additional_data);
                 }
 
+                health.lock().await.record_traffic();
+
                 // Re-decode with full data
                 let transaction = match Transaction::decode(&full_data) {
                     Ok(t) => t,
@@ -640,19 +1076,33 @@ impl HotlineClient {
                     transaction.transaction_type, transaction.id, transaction.is_reply,
                     transaction.error_code, transaction.fields.len());
 
+                // Replies to raw transactions go back as sent, undecoded
+                if transaction.is_reply == 1 {
+                    if let Some(tx) = raw_replies.write().await.remove(&transaction.id) {
+                        let _ = tx.try_send(full_data);
+                        continue;
+                    }
+                }
+
                 // Handle transaction
                 if transaction.is_reply == 1 {
-                    // This is a reply to one of our requests
+                    // This is a reply to one of our requests. Replies someone is
+                    // waiting on (e.g. JoinChat, which also carries UserNameWithInfo)
+                    // go straight to the waiter instead of the roster.
+                    let is_pending = pending_transactions.read().await.contains_key(&transaction.id);
+
                     // Check for UserNameWithInfo fields (from GetUserNameList reply)
                     let mut has_user_info = false;
-                    let mut has_file_info = false;
-                    let mut files = Vec::new();
+                    let mut listed_users = HashMap::new();
+                    let has_file_info = !is_pending
+                        && transaction.fields.iter().any(|f| f.field_type == FieldType::FileNameWithInfo);
 
-                    for field in &transaction.fields {
+                    for field in transaction.fields.iter().filter(|_| !is_pending) {
                         if field.field_type == FieldType::UserNameWithInfo {
                             has_user_info = true;
                             if let Ok(user_info) = HotlineClient::parse_user_info(&field.data) {
                                 println!("Parsed user: {} (ID: {}, Icon: {}, Flags: 0x{:04x})", user_info.1, user_info.0, user_info.2, user_info.3);
+                                listed_users.insert(user_info.0, User::new(user_info.0, user_info.1.clone(), user_info.2, user_info.3));
                                 let _ = event_tx.send(HotlineEvent::UserJoined {
                                     user_id: user_info.0,
                                     user_name: user_info.1,
@@ -660,15 +1110,11 @@ impl HotlineClient {
                                     flags: user_info.3,
                                 });
                             }
-                        } else if field.field_type == FieldType::FileNameWithInfo {
-                            has_file_info = true;
-                            if let Ok(file_info) = HotlineClient::parse_file_info(&field.data) {
-                                println!("Parsed file: {} ({} bytes, folder: {})",
-                                    file_info.name, file_info.size, file_info.is_folder);
-                                files.push(file_info);
-                            }
                         }
                     }
+                    if has_user_info {
+                        *users.write().await = listed_users;
+                    }
 
                     // Check if this reply corresponds to a file list request
                     // (even if empty — an empty folder has zero FileNameWithInfo fields)
@@ -677,7 +1123,13 @@ impl HotlineClient {
                         paths.contains_key(&transaction.id)
                     };
 
+                    let files = if is_file_list_reply || has_file_info {
+                        FileListReply::from_reply(&transaction).files
+                    } else {
+                        Vec::new()
+                    };
                     if is_file_list_reply {
+                        println!("Parsed {} files", files.len());
                         let path = {
                             let mut paths = file_list_paths.write().await;
                             paths.remove(&transaction.id).unwrap_or_default()
@@ -689,7 +1141,7 @@ impl HotlineClient {
                     }
 
                     // If it's not a user/file list reply, forward to pending transaction handlers
-                    if !has_user_info && !is_file_list_reply && !has_file_info {
+                    if is_pending || (!has_user_info && !is_file_list_reply && !has_file_info) {
                         // Remove transaction from pending and get the sender
                         // Do this quickly to minimize lock time
                         let tx_opt = {
@@ -723,18 +1175,35 @@ impl HotlineClient {
                     }
                 } else {
                     // This is an unsolicited server message
-                    Self::handle_server_event(&transaction, &event_tx);
+                    Self::handle_server_event(&transaction, &event_tx, &users, &own_user, &username, &user_icon_id).await;
                 }
             }
 
             println!("Receive loop exited");
+            let _ = event_tx.send(HotlineEvent::Lifecycle(LifecycleNotice::ReceiveLoopStopped {
+                reason: stop_reason.unwrap_or("stopped").to_string(),
+                expected: stop_reason.is_none(),
+            }));
         });
 
         let mut receive_task = self.receive_task.lock().await;
         *receive_task = Some(task);
     }
 
-    fn handle_server_event(transaction: &Transaction, event_tx: &mpsc::UnboundedSender<HotlineEvent>) {
+    async fn handle_server_event(
+        transaction: &Transaction,
+        event_tx: &mpsc::UnboundedSender<HotlineEvent>,
+        users: &RwLock<HashMap<u16, User>>,
+        own_user: &Mutex<OwnUser>,
+        username: &Mutex<String>,
+        user_icon_id: &Mutex<u16>,
+    ) {
+        // Anything carrying a ChatId belongs to a private chat
+        if let Some(event) = private_chat::chat_event(transaction) {
+            let _ = event_tx.send(event);
+            return;
+        }
+
         match transaction.transaction_type {
             TransactionType::ChatMessage => {
                 // Extract chat message fields
@@ -758,19 +1227,14 @@ impl HotlineClient {
                 });
             }
             TransactionType::ServerMessage => {
-                let message = transaction
-                    .get_field(FieldType::Data)
-                    .and_then(|f| f.to_string().ok())
-                    .unwrap_or_default();
-
-                // Check if this is a private message (has UserId field) or server broadcast
-                if let Some(user_id_field) = transaction.get_field(FieldType::UserId) {
-                    if let Ok(user_id) = user_id_field.to_u16() {
-                        // Private message from a specific user
-                        let _ = event_tx.send(HotlineEvent::PrivateMessage { user_id, message });
-                    }
-                } else {
-                    // Server broadcast message
+                // Messages from a user carry a UserId; without one it's a server broadcast
+                if let Some(event) = instant_message::instant_message_event(transaction) {
+                    let _ = event_tx.send(event);
+                } else if transaction.get_field(FieldType::UserId).is_none() {
+                    let message = transaction
+                        .get_field(FieldType::Data)
+                        .and_then(|f| f.to_string().ok())
+                        .unwrap_or_default();
                     let _ = event_tx.send(HotlineEvent::ServerMessage(message));
                 }
             }
@@ -791,6 +1255,9 @@ impl HotlineClient {
                 for (i, field) in transaction.fields.iter().enumerate() {
                     println!("  Field {}: type={:?} ({}), size={} bytes", 
                         i, field.field_type, field.field_type as u16, field.data.len());
+                    if field.is_credential() {
+                        continue;
+                    }
                     if field.data.len() > 0 && field.data.len() <= 200 {
                         println!("    Data (hex): {:02X?}", &field.data);
                         if let Ok(s) = field.to_string() {
@@ -837,17 +1304,29 @@ impl HotlineClient {
                 let icon = transaction
                     .get_field(FieldType::UserIconId)
                     .and_then(|f| f.to_u16().ok())
-                    .unwrap_or(414);
+                    .unwrap_or(FALLBACK_USER_ICON);
                 let flags = transaction
                     .get_field(FieldType::UserFlags)
                     .and_then(|f| f.to_u16().ok())
                     .unwrap_or(0);
 
+                let previous_name = users
+                    .write()
+                    .await
+                    .insert(user_id, User::new(user_id, user_name.clone(), icon, flags))
+                    .map(|previous| previous.user_name);
+                let own = own_user.lock().await.echo(user_id, &user_name, icon);
+                if let Some((own_name, own_icon)) = &own {
+                    *username.lock().await = own_name.clone();
+                    *user_icon_id.lock().await = *own_icon;
+                }
                 let _ = event_tx.send(HotlineEvent::UserChanged {
                     user_id,
                     user_name,
                     icon,
                     flags,
+                    is_self: own.is_some(),
+                    previous_name,
                 });
             }
             TransactionType::NotifyUserDelete => {
@@ -856,6 +1335,7 @@ impl HotlineClient {
                     .and_then(|f| f.to_u16().ok())
                     .unwrap_or(0);
 
+                users.write().await.remove(&user_id);
                 let _ = event_tx.send(HotlineEvent::UserLeft { user_id });
             }
             _ => {
@@ -864,54 +1344,182 @@ impl HotlineClient {
         }
     }
 
+    /// Fail fast with a clear error once the server has shown a feature is off,
+    /// instead of asking again
+    pub(crate) async fn require(&self, feature: Feature) -> Result<(), String> {
+        let capabilities = self
+            .server_info
+            .lock()
+            .await
+            .as_ref()
+            .map(|info| info.capabilities)
+            .unwrap_or_default();
+        if capabilities.supports(feature) {
+            return Ok(());
+        }
+        Err(tr(match feature {
+            Feature::MessageBoard => MessageId::MessageBoardDisabled,
+            Feature::News => MessageId::NewsDisabled,
+        }))
+    }
+
+    /// Compatibility flags for the connected server (defaults before login)
+    pub(crate) async fn quirks(&self) -> ServerQuirks {
+        self.server_info
+            .lock()
+            .await
+            .as_ref()
+            .map(|info| info.quirks.clone())
+            .unwrap_or_default()
+    }
+
     // Start background task to send keep-alive messages
     async fn start_keepalive(&self) {
-        println!("Starting keep-alive...");
+        let quirks = self.quirks().await;
+        let interval = Duration::from_secs(quirks.keepalive_interval_secs);
+        // Servers before 1.8.5 don't know ConnectionKeepAlive; a user list
+        // request keeps them from idling us out just as well
+        let keepalive_type = if quirks.connection_keepalive {
+            TransactionType::ConnectionKeepAlive
+        } else {
+            TransactionType::GetUserNameList
+        };
+        println!("Starting keep-alive ({:?} every {}s)...", keepalive_type, interval.as_secs());
 
-        let write_half = self.write_half.clone();
+        let outbound = self.outbound.clone();
         let running = self.running.clone();
         let transaction_counter = self.transaction_counter.clone();
+        let pending_transactions = self.pending_transactions.clone();
+        let health = self.health.clone();
+        let event_tx = self.event_tx.clone();
 
         let task = tokio::spawn(async move {
+            let _ = event_tx.send(HotlineEvent::Lifecycle(LifecycleNotice::KeepaliveStarted { interval_secs: interval.as_secs() }));
+            // Set on every way out except `running` being cleared
+            let mut stop_reason = None;
             while running.load(Ordering::SeqCst) {
-                tokio::time::sleep(Duration::from_secs(180)).await; // 3 minutes like Swift client
+                tokio::time::sleep(interval).await; // 3 minutes like Swift client unless the server needs more
 
                 if !running.load(Ordering::SeqCst) {
                     break;
                 }
 
-                // Send GetUserNameList as keep-alive (works for all server versions)
-                // Swift client uses ConnectionKeepAlive for servers >= 185, but falls back to GetUserNameList
-                // Since we don't have ConnectionKeepAlive in our protocol, we'll use GetUserNameList
                 let transaction = Transaction::new(
                     transaction_counter.fetch_add(1, Ordering::SeqCst),
-                    TransactionType::GetUserNameList,
+                    keepalive_type,
                 );
-                let encoded = transaction.encode();
+                let transaction_id = transaction.id;
+                let Some(queue) = outbound.lock().await.clone() else {
+                    stop_reason = Some("writer is gone");
+                    break;
+                };
+
+                // Register as a pending request so the reply comes back here
+                // (and a user list reply doesn't replay the roster)
+                let (tx, mut rx) = mpsc::channel(1);
+                pending_transactions.write().await.insert(transaction_id, tx);
+
+                // Keep-alives jump ahead of queued bulk requests
+                let sent_at = std::time::Instant::now();
+                if queue.send(Lane::Control, transaction.encode()).await.is_err() {
+                    pending_transactions.write().await.remove(&transaction_id);
+                    println!("Keep-alive failed, connection lost");
+                    stop_reason = Some("send failed");
+                    break;
+                }
+                health.lock().await.record_keepalive_sent();
 
-                let mut write_guard = write_half.lock().await;
-                if let Some(write_stream) = write_guard.as_mut() {
-                    if write_stream.write_all(&encoded).await.is_err() {
-                        println!("Keep-alive failed, connection lost");
+                match tokio::time::timeout(Duration::from_secs(30), rx.recv()).await {
+                    Ok(Some(_)) => {
+                        let latency = sent_at.elapsed();
+                        health.lock().await.record_latency(latency);
+                        println!("Keep-alive reply after {}ms", latency.as_millis());
+                    }
+                    Ok(None) => {
+                        stop_reason = Some("reply channel closed");
                         break;
                     }
-                    println!("Keep-alive sent (GetUserNameList)");
-                } else {
-                    break;
+                    Err(_) => {
+                        pending_transactions.write().await.remove(&transaction_id);
+                        health.lock().await.record_keepalive_missed();
+                        println!("Keep-alive got no reply within 30s");
+                    }
                 }
             }
 
             println!("Keep-alive exited");
+            let _ = event_tx.send(HotlineEvent::Lifecycle(LifecycleNotice::KeepaliveStopped {
+                reason: stop_reason.unwrap_or("stopped").to_string(),
+                expected: stop_reason.is_none(),
+            }));
         });
 
         let mut keepalive_task = self.keepalive_task.lock().await;
         *keepalive_task = Some(task);
     }
 
+    /// Remember an agreement pushed by the server. Returns true when it replaces
+    /// an agreement that was already accepted this session with different text.
+    pub async fn record_agreement(&self, agreement: &str) -> bool {
+        let mut server_info = self.server_info.lock().await;
+        let Some(info) = server_info.as_mut() else {
+            return false;
+        };
+
+        let updated = info.agreement_accepted && info.agreement.as_deref() != Some(agreement);
+        if info.agreement.as_deref() != Some(agreement) {
+            info.agreement = Some(agreement.to_string());
+            info.agreement_accepted = false;
+        }
+        updated
+    }
+
+    /// Record the download cap the server revealed by refusing a download
+    pub async fn set_download_limit(&self, limit: usize) {
+        if let Some(info) = self.server_info.lock().await.as_mut() {
+            info.capabilities.max_downloads = Some(limit);
+        }
+    }
+
+    pub async fn get_agreement(&self) -> Option<String> {
+        let server_info = self.server_info.lock().await;
+        server_info.as_ref().and_then(|info| info.agreement.clone())
+    }
+
     pub async fn get_server_info(&self) -> Result<ServerInfo, String> {
         let server_info = self.server_info.lock().await;
         server_info
             .clone()
-            .ok_or_else(|| "Server info not available".to_string())
+            .ok_or_else(|| tr(MessageId::ServerInfoUnavailable))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognizes_duplicate_login_refusals() {
+        assert_eq!(
+            LoginRefusal::from_error_text("You are already logged in."),
+            Some(LoginRefusal::AlreadyLoggedIn)
+        );
+        assert_eq!(
+            LoginRefusal::from_error_text("This account is ALREADY CONNECTED"),
+            Some(LoginRefusal::AlreadyLoggedIn)
+        );
+        assert_eq!(LoginRefusal::from_error_text("Incorrect login."), None);
+    }
+
+    #[test]
+    fn recognizes_server_full_refusals() {
+        assert_eq!(
+            LoginRefusal::from_error_text("Sorry, the server is full. Try again later."),
+            Some(LoginRefusal::ServerFull)
+        );
+        assert_eq!(
+            LoginRefusal::from_error_text("Too many users are connected"),
+            Some(LoginRefusal::ServerFull)
+        );
     }
 }
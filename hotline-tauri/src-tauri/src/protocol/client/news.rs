@@ -1,237 +1,61 @@
-// News and message board functionality for Hotline client
+// Threaded news functionality for Hotline client
 
+use super::files::hotline_date_to_unix;
+use super::replies::{error_text, NewsListReply};
 use super::HotlineClient;
 use crate::protocol::constants::{FieldType, TransactionType};
+use crate::protocol::i18n::{tr, MessageId};
+use crate::protocol::quirks::Feature;
 use crate::protocol::transaction::{Transaction, TransactionField};
-use crate::protocol::types::{NewsArticle, NewsCategory};
-use std::io::ErrorKind;
-use std::time::Duration;
-use tokio::io::AsyncWriteExt;
-use tokio::sync::mpsc;
+use crate::protocol::types::{ArticleFlavor, NewsArticle, NewsCategory};
 
-impl HotlineClient {
-    pub async fn get_message_board(&self) -> Result<Vec<String>, String> {
-        println!("Requesting message board");
-
-        let transaction = Transaction::new(self.next_transaction_id(), TransactionType::GetMessageBoard);
-        let transaction_id = transaction.id;
-        let (tx, mut rx) = mpsc::channel(1);
-
-        // Register pending transaction
-        {
-            let mut pending = self.pending_transactions.write().await;
-            pending.insert(transaction_id, tx);
-        }
-
-        // Send transaction
-        let encoded = transaction.encode();
-        let write_result = {
-            let mut write_guard = self.write_half.lock().await;
-            let write_stream = write_guard
-                .as_mut()
-                .ok_or("Not connected".to_string())?;
-            write_stream.write_all(&encoded).await
-        };
-        if let Err(e) = &write_result {
-            if e.kind() == ErrorKind::BrokenPipe || e.to_string().contains("Broken pipe") {
-                let mut write_guard = self.write_half.lock().await;
-                write_guard.take();
-            }
-        }
-        write_result.map_err(|e| format!("Failed to send get message board request: {}", e))?;
-
-        let flush_result = {
-            let mut write_guard = self.write_half.lock().await;
-            let write_stream = write_guard
-                .as_mut()
-                .ok_or("Not connected".to_string())?;
-            write_stream.flush().await
-        };
-        if let Err(e) = &flush_result {
-            if e.kind() == ErrorKind::BrokenPipe || e.to_string().contains("Broken pipe") {
-                let mut write_guard = self.write_half.lock().await;
-                write_guard.take();
-            }
-        }
-        flush_result.map_err(|e| format!("Failed to flush: {}", e))?;
-
-        // Wait for reply
-        let reply = tokio::time::timeout(Duration::from_secs(10), rx.recv())
-            .await
-            .map_err(|_| "Timeout waiting for message board reply".to_string())?
-            .ok_or("Channel closed".to_string())?;
-
-        if reply.error_code != 0 {
-            let error_msg = reply
-                .get_field(FieldType::ErrorText)
-                .and_then(|f| f.to_string().ok())
-                .unwrap_or_else(|| format!("Error code: {}", reply.error_code));
-            return Err(format!("Get message board failed: {}", error_msg));
-        }
-
-        // Get raw bytes and split by divider lines before decoding.
-        // Boards can mix UTF-8 posts (modern clients) and Mac Roman posts (old clients).
-        // Decoding the whole blob at once causes mojibake when UTF-8 multi-byte sequences
-        // get misinterpreted as Mac Roman. Split first, decode each post individually.
-        let raw_data = reply
-            .get_field(FieldType::Data)
-            .map(|f| f.data.clone())
-            .unwrap_or_default();
-
-        let posts = parse_message_board_data(&raw_data);
-
-        println!("Received message board: {} posts", posts.len());
-
-        Ok(posts)
+/// Title for a reply to `title`, without stacking "Re:" prefixes
+fn reply_title(title: &str) -> String {
+    if title.get(..3).is_some_and(|prefix| prefix.eq_ignore_ascii_case("re:")) {
+        title.to_string()
+    } else {
+        format!("Re: {}", title)
     }
+}
 
-    pub async fn post_message_board(&self, text: String) -> Result<(), String> {
-        println!("Posting to message board: {} chars", text.len());
-
-        let mut transaction = Transaction::new(self.next_transaction_id(), TransactionType::OldPostNews);
-        transaction.add_field(TransactionField::from_string(FieldType::Data, &text));
-
-        let encoded = transaction.encode();
-
-        let write_result = {
-            let mut write_guard = self.write_half.lock().await;
-            let write_stream = write_guard
-                .as_mut()
-                .ok_or("Not connected".to_string())?;
-
-            let write_result = write_stream.write_all(&encoded).await;
-            if let Err(e) = &write_result {
-                if e.kind() == ErrorKind::BrokenPipe || e.to_string().contains("Broken pipe") {
-                    write_guard.take();
-                }
-            }
-            write_result
-        };
-        write_result.map_err(|e| format!("Failed to post message: {}", e))?;
-
-        let flush_result = {
-            let mut write_guard = self.write_half.lock().await;
-            let write_stream = write_guard
-                .as_mut()
-                .ok_or("Not connected".to_string())?;
-            let flush_result = write_stream.flush().await;
-            if let Err(e) = &flush_result {
-                if e.kind() == ErrorKind::BrokenPipe || e.to_string().contains("Broken pipe") {
-                    write_guard.take();
-                }
-            }
-            flush_result
-        };
-        flush_result.map_err(|e| format!("Failed to flush: {}", e))?;
-
-        println!("Message board post sent successfully");
-
-        Ok(())
-    }
+/// The article we most likely just posted, for servers whose reply doesn't
+/// carry its id: the newest one with our title under the same parent
+fn find_posted(articles: &[NewsArticle], title: &str, parent_id: u32) -> Option<u32> {
+    articles
+        .iter()
+        .filter(|article| article.parent_id == parent_id && article.title == title)
+        .map(|article| article.id)
+        .max()
+}
 
+impl HotlineClient {
     pub async fn get_news_categories(&self, path: Vec<String>) -> Result<Vec<NewsCategory>, String> {
         println!("Requesting news categories for path: {:?}", path);
+        self.require(Feature::News).await?;
+
+        if !self.quirks().await.threaded_news {
+            return Err(tr(MessageId::NewsNotSupported));
+        }
 
         let mut transaction = Transaction::new(self.next_transaction_id(), TransactionType::GetNewsCategoryList);
         if !path.is_empty() {
             transaction.add_field(TransactionField::from_path(FieldType::NewsPath, &path));
         }
 
-        let transaction_id = transaction.id;
-        let (tx, mut rx) = mpsc::channel(1);
-
-        // Register pending transaction
-        {
-            let mut pending = self.pending_transactions.write().await;
-            pending.insert(transaction_id, tx);
-        }
-
-        // Send transaction
-        let encoded = transaction.encode();
-
-        let write_result = {
-            let mut write_guard = self.write_half.lock().await;
-            let write_stream = write_guard
-                .as_mut()
-                .ok_or("Not connected".to_string())?;
-
-            let write_result = write_stream.write_all(&encoded).await;
-            if let Err(e) = &write_result {
-                if e.kind() == ErrorKind::BrokenPipe || e.to_string().contains("Broken pipe") {
-                    write_guard.take();
-                }
-            }
-            write_result
-        };
-        if let Err(e) = write_result {
-            // Clean up pending transaction on send error
-            let mut pending = self.pending_transactions.write().await;
-            pending.remove(&transaction_id);
-            return Err(format!("Failed to send request: {}", e));
-        }
-
-        let flush_result = {
-            let mut write_guard = self.write_half.lock().await;
-            let write_stream = write_guard
-                .as_mut()
-                .ok_or("Not connected".to_string())?;
-            let flush_result = write_stream.flush().await;
-            if let Err(e) = &flush_result {
-                if e.kind() == ErrorKind::BrokenPipe || e.to_string().contains("Broken pipe") {
-                    write_guard.take();
-                }
-            }
-            flush_result
-        };
-        if let Err(e) = flush_result {
-            // Clean up pending transaction on flush error
-            let mut pending = self.pending_transactions.write().await;
-            pending.remove(&transaction_id);
-            return Err(format!("Failed to flush: {}", e));
-        }
+        let reply = self.send_and_wait_reply(transaction, "get news categories").await?;
 
-        // Wait for reply (shorter timeout for unsupported feature)
-        let reply = match tokio::time::timeout(Duration::from_secs(5), rx.recv()).await {
-            Ok(Some(reply)) => reply,
-            Ok(None) => {
-                // Channel closed - clean up pending transaction
-                let mut pending = self.pending_transactions.write().await;
-                pending.remove(&transaction_id);
-                return Err("Channel closed while waiting for news categories reply".to_string());
-            }
-            Err(_) => {
-                // Timeout - clean up pending transaction
-                let mut pending = self.pending_transactions.write().await;
-                pending.remove(&transaction_id);
-                return Err("Timeout waiting for news categories reply (server may not support news)".to_string());
-            }
-        };
-
-        // Note: Transaction was already removed from pending by receive loop
-        // Empty reply (0 fields, error_code=0) is valid - means "no news"
-        
         if reply.error_code != 0 {
-            let error_msg = reply
-                .get_field(FieldType::ErrorText)
-                .and_then(|f| f.to_string().ok())
-                .unwrap_or_else(|| format!("Error code: {}", reply.error_code));
+            self.note_refusal(Feature::News, &reply, "get news categories").await;
+            let error_msg = error_text(&reply);
             // Return a more user-friendly error for unsupported features
             if reply.error_code == 1 || error_msg.to_lowercase().contains("not supported") {
-                return Err("News is not supported on this server".to_string());
+                return Err(tr(MessageId::NewsNotSupported));
             }
             return Err(format!("Get news categories failed: {}", error_msg));
         }
 
-        // Parse categories from NewsCategoryListData15 fields
         // Empty reply (0 fields) is valid - just means no categories
-        let mut categories = Vec::new();
-        for field in &reply.fields {
-            if field.field_type == FieldType::NewsCategoryListData15 {
-                if let Ok(category) = self.parse_news_category(&field.data, &path) {
-                    categories.push(category);
-                }
-            }
-        }
+        let categories = NewsListReply::from_reply(&reply, &path)?.categories;
 
         println!("Received {} news categories (reply had {} fields)", categories.len(), reply.fields.len());
 
@@ -240,104 +64,31 @@ impl HotlineClient {
 
     pub async fn get_news_articles(&self, path: Vec<String>) -> Result<Vec<NewsArticle>, String> {
         println!("Requesting news articles for path: {:?}", path);
+        self.require(Feature::News).await?;
+
+        if !self.quirks().await.threaded_news {
+            return Err(tr(MessageId::NewsNotSupported));
+        }
 
         let mut transaction = Transaction::new(self.next_transaction_id(), TransactionType::GetNewsArticleList);
         if !path.is_empty() {
             transaction.add_field(TransactionField::from_path(FieldType::NewsPath, &path));
         }
 
-        let transaction_id = transaction.id;
-        let (tx, mut rx) = mpsc::channel(1);
+        let reply = self.send_and_wait_reply(transaction, "get news articles").await?;
 
-        // Register pending transaction
-        {
-            let mut pending = self.pending_transactions.write().await;
-            pending.insert(transaction_id, tx);
-        }
-
-        // Send transaction
-        let encoded = transaction.encode();
-
-        let write_result = {
-            let mut write_guard = self.write_half.lock().await;
-            let write_stream = write_guard
-                .as_mut()
-                .ok_or("Not connected".to_string())?;
-
-            let write_result = write_stream.write_all(&encoded).await;
-            if let Err(e) = &write_result {
-                if e.kind() == ErrorKind::BrokenPipe || e.to_string().contains("Broken pipe") {
-                    write_guard.take();
-                }
-            }
-            write_result
-        };
-        if let Err(e) = write_result {
-            // Clean up pending transaction on send error
-            let mut pending = self.pending_transactions.write().await;
-            pending.remove(&transaction_id);
-            return Err(format!("Failed to send request: {}", e));
-        }
-
-        let flush_result = {
-            let mut write_guard = self.write_half.lock().await;
-            let write_stream = write_guard
-                .as_mut()
-                .ok_or("Not connected".to_string())?;
-            let flush_result = write_stream.flush().await;
-            if let Err(e) = &flush_result {
-                if e.kind() == ErrorKind::BrokenPipe || e.to_string().contains("Broken pipe") {
-                    write_guard.take();
-                }
-            }
-            flush_result
-        };
-        if let Err(e) = flush_result {
-            // Clean up pending transaction on flush error
-            let mut pending = self.pending_transactions.write().await;
-            pending.remove(&transaction_id);
-            return Err(format!("Failed to flush: {}", e));
-        }
-
-        // Wait for reply (shorter timeout for unsupported feature)
-        let reply = match tokio::time::timeout(Duration::from_secs(5), rx.recv()).await {
-            Ok(Some(reply)) => reply,
-            Ok(None) => {
-                // Channel closed - clean up pending transaction
-                let mut pending = self.pending_transactions.write().await;
-                pending.remove(&transaction_id);
-                return Err("Channel closed while waiting for news articles reply".to_string());
-            }
-            Err(_) => {
-                // Timeout - clean up pending transaction
-                let mut pending = self.pending_transactions.write().await;
-                pending.remove(&transaction_id);
-                return Err("Timeout waiting for news articles reply (server may not support news)".to_string());
-            }
-        };
-
-        // Note: Transaction was already removed from pending by receive loop
-        // Empty reply (0 fields, error_code=0) is valid - means "no news"
-        
         if reply.error_code != 0 {
-            let error_msg = reply
-                .get_field(FieldType::ErrorText)
-                .and_then(|f| f.to_string().ok())
-                .unwrap_or_else(|| format!("Error code: {}", reply.error_code));
+            self.note_refusal(Feature::News, &reply, "get news articles").await;
+            let error_msg = error_text(&reply);
             // Return a more user-friendly error for unsupported features
             if reply.error_code == 1 || error_msg.to_lowercase().contains("not supported") {
-                return Err("News is not supported on this server".to_string());
+                return Err(tr(MessageId::NewsNotSupported));
             }
             return Err(format!("Get news articles failed: {}", error_msg));
         }
 
-        // Parse articles from NewsArticleListData field
         // Empty reply (0 fields) is valid - just means no articles
-        let articles = if let Some(field) = reply.get_field(FieldType::NewsArticleListData) {
-            self.parse_news_article_list(&field.data, &path)?
-        } else {
-            Vec::new()
-        };
+        let articles = NewsListReply::from_reply(&reply, &path)?.articles;
 
         println!("Received {} news articles (reply had {} fields)", articles.len(), reply.fields.len());
 
@@ -346,91 +97,14 @@ impl HotlineClient {
 
     pub async fn get_news_article_data(&self, article_id: u32, path: Vec<String>) -> Result<String, String> {
         println!("Requesting news article data for ID {} at path: {:?}", article_id, path);
+        self.require(Feature::News).await?;
 
         let mut transaction = Transaction::new(self.next_transaction_id(), TransactionType::GetNewsArticleData);
         transaction.add_field(TransactionField::from_path(FieldType::NewsPath, &path));
         transaction.add_field(TransactionField::from_u32(FieldType::NewsArticleId, article_id));
         transaction.add_field(TransactionField::from_string(FieldType::NewsArticleDataFlavor, "text/plain"));
 
-        let transaction_id = transaction.id;
-        let (tx, mut rx) = mpsc::channel(1);
-
-        // Register pending transaction
-        {
-            let mut pending = self.pending_transactions.write().await;
-            pending.insert(transaction_id, tx);
-        }
-
-        // Send transaction
-        let encoded = transaction.encode();
-
-        let write_result = {
-            let mut write_guard = self.write_half.lock().await;
-            let write_stream = write_guard
-                .as_mut()
-                .ok_or("Not connected".to_string())?;
-
-            let write_result = write_stream.write_all(&encoded).await;
-            if let Err(e) = &write_result {
-                if e.kind() == ErrorKind::BrokenPipe || e.to_string().contains("Broken pipe") {
-                    write_guard.take();
-                }
-            }
-            write_result
-        };
-        if let Err(e) = write_result {
-            // Clean up pending transaction on send error
-            let mut pending = self.pending_transactions.write().await;
-            pending.remove(&transaction_id);
-            return Err(format!("Failed to send request: {}", e));
-        }
-
-        let flush_result = {
-            let mut write_guard = self.write_half.lock().await;
-            let write_stream = write_guard
-                .as_mut()
-                .ok_or("Not connected".to_string())?;
-            let flush_result = write_stream.flush().await;
-            if let Err(e) = &flush_result {
-                if e.kind() == ErrorKind::BrokenPipe || e.to_string().contains("Broken pipe") {
-                    write_guard.take();
-                }
-            }
-            flush_result
-        };
-        if let Err(e) = flush_result {
-            // Clean up pending transaction on flush error
-            let mut pending = self.pending_transactions.write().await;
-            pending.remove(&transaction_id);
-            return Err(format!("Failed to flush: {}", e));
-        }
-
-        // Wait for reply
-        let reply = match tokio::time::timeout(Duration::from_secs(10), rx.recv()).await {
-            Ok(Some(reply)) => reply,
-            Ok(None) => {
-                // Channel closed - clean up pending transaction
-                let mut pending = self.pending_transactions.write().await;
-                pending.remove(&transaction_id);
-                return Err("Channel closed while waiting for news article data reply".to_string());
-            }
-            Err(_) => {
-                // Timeout - clean up pending transaction
-                let mut pending = self.pending_transactions.write().await;
-                pending.remove(&transaction_id);
-                return Err("Timeout waiting for news article data reply".to_string());
-            }
-        };
-
-        // Note: Transaction was already removed from pending by receive loop
-        
-        if reply.error_code != 0 {
-            let error_msg = reply
-                .get_field(FieldType::ErrorText)
-                .and_then(|f| f.to_string().ok())
-                .unwrap_or_else(|| format!("Error code: {}", reply.error_code));
-            return Err(format!("Get news article data failed: {}", error_msg));
-        }
+        let reply = self.send_feature_request(Feature::News, transaction, "get news article data").await?;
 
         // Get article content from NewsArticleData field
         let content = reply
@@ -443,101 +117,42 @@ impl HotlineClient {
         Ok(content)
     }
 
-    pub async fn post_news_article(&self, title: String, text: String, path: Vec<String>, parent_id: u32) -> Result<(), String> {
+    /// Post an article, as a reply when `parent_id` isn't 0. Returns the new
+    /// article's id when the server says or it can be found in the listing.
+    pub async fn post_news_article(&self, title: String, text: String, path: Vec<String>, parent_id: u32, flavor: ArticleFlavor) -> Result<Option<u32>, String> {
         println!("Posting news article '{}' to path: {:?}", title, path);
+        self.require(Feature::News).await?;
 
         let mut transaction = Transaction::new(self.next_transaction_id(), TransactionType::PostNewsArticle);
         transaction.add_field(TransactionField::from_path(FieldType::NewsPath, &path));
         transaction.add_field(TransactionField::from_u32(FieldType::NewsArticleId, parent_id));
         transaction.add_field(TransactionField::from_string(FieldType::NewsArticleTitle, &title));
-        transaction.add_field(TransactionField::from_string(FieldType::NewsArticleDataFlavor, "text/plain"));
+        transaction.add_field(TransactionField::from_string(FieldType::NewsArticleDataFlavor, flavor.mime_type()));
         transaction.add_field(TransactionField::from_u32(FieldType::NewsArticleFlags, 0));
         transaction.add_field(TransactionField::from_string(FieldType::NewsArticleData, &text));
 
-        let transaction_id = transaction.id;
-        let (tx, mut rx) = mpsc::channel(1);
-
-        // Register pending transaction
-        {
-            let mut pending = self.pending_transactions.write().await;
-            pending.insert(transaction_id, tx);
-        }
-
-        // Send transaction
-        let encoded = transaction.encode();
-
-        let write_result = {
-            let mut write_guard = self.write_half.lock().await;
-            let write_stream = write_guard
-                .as_mut()
-                .ok_or("Not connected".to_string())?;
-
-            let write_result = write_stream.write_all(&encoded).await;
-            if let Err(e) = &write_result {
-                if e.kind() == ErrorKind::BrokenPipe || e.to_string().contains("Broken pipe") {
-                    write_guard.take();
-                }
-            }
-            write_result
-        };
-        if let Err(e) = write_result {
-            // Clean up pending transaction on send error
-            let mut pending = self.pending_transactions.write().await;
-            pending.remove(&transaction_id);
-            return Err(format!("Failed to send request: {}", e));
-        }
-
-        let flush_result = {
-            let mut write_guard = self.write_half.lock().await;
-            let write_stream = write_guard
-                .as_mut()
-                .ok_or("Not connected".to_string())?;
-            let flush_result = write_stream.flush().await;
-            if let Err(e) = &flush_result {
-                if e.kind() == ErrorKind::BrokenPipe || e.to_string().contains("Broken pipe") {
-                    write_guard.take();
-                }
-            }
-            flush_result
-        };
-        if let Err(e) = flush_result {
-            // Clean up pending transaction on flush error
-            let mut pending = self.pending_transactions.write().await;
-            pending.remove(&transaction_id);
-            return Err(format!("Failed to flush: {}", e));
-        }
+        let reply = self.send_and_wait(transaction, "post news article").await?;
 
-        // Wait for reply
-        let reply = match tokio::time::timeout(Duration::from_secs(10), rx.recv()).await {
-            Ok(Some(reply)) => reply,
-            Ok(None) => {
-                // Channel closed - clean up pending transaction
-                let mut pending = self.pending_transactions.write().await;
-                pending.remove(&transaction_id);
-                return Err("Channel closed while waiting for post news article reply".to_string());
-            }
-            Err(_) => {
-                // Timeout - clean up pending transaction
-                let mut pending = self.pending_transactions.write().await;
-                pending.remove(&transaction_id);
-                return Err("Timeout waiting for post news article reply".to_string());
-            }
-        };
+        println!("News article posted successfully");
 
-        // Note: Transaction was already removed from pending by receive loop
-        
-        if reply.error_code != 0 {
-            let error_msg = reply
-                .get_field(FieldType::ErrorText)
-                .and_then(|f| f.to_string().ok())
-                .unwrap_or_else(|| format!("Error code: {}", reply.error_code));
-            println!("Post news article error: code={}, message={}", reply.error_code, error_msg);
-            return Err(format!("Post news article failed: {}", error_msg));
+        if let Some(id) = reply.get_field(FieldType::NewsArticleId).and_then(|f| f.to_u32().ok()) {
+            return Ok(Some(id));
         }
+        // Most servers reply without it; the article is there now, so look
+        let articles = self.get_news_articles(path).await.unwrap_or_default();
+        Ok(find_posted(&articles, &title, parent_id))
+    }
 
-        println!("News article posted successfully");
-
-        Ok(())
+    /// Reply to an article in `path`. The parent must still be listed there;
+    /// without a title the reply gets "Re: " and the parent's.
+    pub async fn reply_to_news_article(&self, path: Vec<String>, parent_id: u32, title: Option<String>, text: String, flavor: ArticleFlavor) -> Result<Option<u32>, String> {
+        let articles = self.get_news_articles(path.clone()).await?;
+        let parent = articles
+            .iter()
+            .find(|article| article.id == parent_id)
+            .ok_or_else(|| "The article you are replying to is no longer there".to_string())?;
+        let title = title.filter(|title| !title.trim().is_empty()).unwrap_or_else(|| reply_title(&parent.title));
+        self.post_news_article(title, text, path, parent_id, flavor).await
     }
 
     pub async fn create_news_category(&self, path: Vec<String>, name: String) -> Result<(), String> {
@@ -549,37 +164,7 @@ impl HotlineClient {
         }
         transaction.add_field(TransactionField::from_string(FieldType::NewsCategoryName, &name));
 
-        let transaction_id = transaction.id;
-        let (tx, mut rx) = mpsc::channel(1);
-        {
-            let mut pending = self.pending_transactions.write().await;
-            pending.insert(transaction_id, tx);
-        }
-
-        let encoded = transaction.encode();
-        let write_result = {
-            let mut write_guard = self.write_half.lock().await;
-            let write_stream = write_guard.as_mut().ok_or("Not connected".to_string())?;
-            let r = write_stream.write_all(&encoded).await;
-            write_stream.flush().await.ok();
-            r
-        };
-        if let Err(e) = write_result {
-            let mut pending = self.pending_transactions.write().await;
-            pending.remove(&transaction_id);
-            return Err(format!("Failed to send request: {}", e));
-        }
-
-        let reply = match tokio::time::timeout(Duration::from_secs(10), rx.recv()).await {
-            Ok(Some(r)) => r,
-            Ok(None) => { let mut p = self.pending_transactions.write().await; p.remove(&transaction_id); return Err("Channel closed".to_string()); }
-            Err(_) => { let mut p = self.pending_transactions.write().await; p.remove(&transaction_id); return Err("Timeout".to_string()); }
-        };
-
-        if reply.error_code != 0 {
-            let msg = reply.get_field(FieldType::ErrorText).and_then(|f| f.to_string().ok()).unwrap_or_else(|| format!("Error code: {}", reply.error_code));
-            return Err(format!("Create news category failed: {}", msg));
-        }
+        self.send_and_wait(transaction, "create news category").await?;
         println!("News category '{}' created", name);
         Ok(())
     }
@@ -593,37 +178,7 @@ impl HotlineClient {
         }
         transaction.add_field(TransactionField::from_string(FieldType::FileName, &name));
 
-        let transaction_id = transaction.id;
-        let (tx, mut rx) = mpsc::channel(1);
-        {
-            let mut pending = self.pending_transactions.write().await;
-            pending.insert(transaction_id, tx);
-        }
-
-        let encoded = transaction.encode();
-        let write_result = {
-            let mut write_guard = self.write_half.lock().await;
-            let write_stream = write_guard.as_mut().ok_or("Not connected".to_string())?;
-            let r = write_stream.write_all(&encoded).await;
-            write_stream.flush().await.ok();
-            r
-        };
-        if let Err(e) = write_result {
-            let mut pending = self.pending_transactions.write().await;
-            pending.remove(&transaction_id);
-            return Err(format!("Failed to send request: {}", e));
-        }
-
-        let reply = match tokio::time::timeout(Duration::from_secs(10), rx.recv()).await {
-            Ok(Some(r)) => r,
-            Ok(None) => { let mut p = self.pending_transactions.write().await; p.remove(&transaction_id); return Err("Channel closed".to_string()); }
-            Err(_) => { let mut p = self.pending_transactions.write().await; p.remove(&transaction_id); return Err("Timeout".to_string()); }
-        };
-
-        if reply.error_code != 0 {
-            let msg = reply.get_field(FieldType::ErrorText).and_then(|f| f.to_string().ok()).unwrap_or_else(|| format!("Error code: {}", reply.error_code));
-            return Err(format!("Create news folder failed: {}", msg));
-        }
+        self.send_and_wait(transaction, "create news folder").await?;
         println!("News folder '{}' created", name);
         Ok(())
     }
@@ -634,37 +189,7 @@ impl HotlineClient {
         let mut transaction = Transaction::new(self.next_transaction_id(), TransactionType::DeleteNewsItem);
         transaction.add_field(TransactionField::from_path(FieldType::NewsPath, &path));
 
-        let transaction_id = transaction.id;
-        let (tx, mut rx) = mpsc::channel(1);
-        {
-            let mut pending = self.pending_transactions.write().await;
-            pending.insert(transaction_id, tx);
-        }
-
-        let encoded = transaction.encode();
-        let write_result = {
-            let mut write_guard = self.write_half.lock().await;
-            let write_stream = write_guard.as_mut().ok_or("Not connected".to_string())?;
-            let r = write_stream.write_all(&encoded).await;
-            write_stream.flush().await.ok();
-            r
-        };
-        if let Err(e) = write_result {
-            let mut pending = self.pending_transactions.write().await;
-            pending.remove(&transaction_id);
-            return Err(format!("Failed to send request: {}", e));
-        }
-
-        let reply = match tokio::time::timeout(Duration::from_secs(10), rx.recv()).await {
-            Ok(Some(r)) => r,
-            Ok(None) => { let mut p = self.pending_transactions.write().await; p.remove(&transaction_id); return Err("Channel closed".to_string()); }
-            Err(_) => { let mut p = self.pending_transactions.write().await; p.remove(&transaction_id); return Err("Timeout".to_string()); }
-        };
-
-        if reply.error_code != 0 {
-            let msg = reply.get_field(FieldType::ErrorText).and_then(|f| f.to_string().ok()).unwrap_or_else(|| format!("Error code: {}", reply.error_code));
-            return Err(format!("Delete news item failed: {}", msg));
-        }
+        self.send_and_wait(transaction, "delete news item").await?;
         println!("News item deleted at path: {:?}", path);
         Ok(())
     }
@@ -677,73 +202,55 @@ impl HotlineClient {
         transaction.add_field(TransactionField::from_u32(FieldType::NewsArticleId, article_id));
         transaction.add_field(TransactionField::from_u16(FieldType::NewsArticleRecursiveDelete, if recursive { 1 } else { 0 }));
 
-        let transaction_id = transaction.id;
-        let (tx, mut rx) = mpsc::channel(1);
-        {
-            let mut pending = self.pending_transactions.write().await;
-            pending.insert(transaction_id, tx);
-        }
-
-        let encoded = transaction.encode();
-        let write_result = {
-            let mut write_guard = self.write_half.lock().await;
-            let write_stream = write_guard.as_mut().ok_or("Not connected".to_string())?;
-            let r = write_stream.write_all(&encoded).await;
-            write_stream.flush().await.ok();
-            r
-        };
-        if let Err(e) = write_result {
-            let mut pending = self.pending_transactions.write().await;
-            pending.remove(&transaction_id);
-            return Err(format!("Failed to send request: {}", e));
-        }
-
-        let reply = match tokio::time::timeout(Duration::from_secs(10), rx.recv()).await {
-            Ok(Some(r)) => r,
-            Ok(None) => { let mut p = self.pending_transactions.write().await; p.remove(&transaction_id); return Err("Channel closed".to_string()); }
-            Err(_) => { let mut p = self.pending_transactions.write().await; p.remove(&transaction_id); return Err("Timeout".to_string()); }
-        };
-
-        if reply.error_code != 0 {
-            let msg = reply.get_field(FieldType::ErrorText).and_then(|f| f.to_string().ok()).unwrap_or_else(|| format!("Error code: {}", reply.error_code));
-            return Err(format!("Delete news article failed: {}", msg));
-        }
+        self.send_and_wait(transaction, "delete news article").await?;
         println!("News article {} deleted", article_id);
         Ok(())
     }
 
     // Helper method to parse a single news category from binary data
-    fn parse_news_category(&self, data: &[u8], parent_path: &[String]) -> Result<NewsCategory, String> {
-        if data.len() < 4 {
+    //
+    // The full record is type, count, a 16-byte GUID, add and delete serial
+    // numbers, then the name as a PString. Servers send that for categories
+    // and some for bundles too; others leave the GUID and serials out, so the
+    // name follows the count directly. Whichever layout ends exactly at the
+    // end of the data wins, otherwise the usual one for the type.
+    pub(crate) fn parse_news_category(data: &[u8], parent_path: &[String]) -> Result<NewsCategory, String> {
+        const FULL_NAME_OFFSET: usize = 28;
+        const SHORT_NAME_OFFSET: usize = 4;
+
+        if data.len() < 5 {
             return Err("Category data too short".to_string());
         }
 
         let category_type = u16::from_be_bytes([data[0], data[1]]);
         let count = u16::from_be_bytes([data[2], data[3]]);
+        if category_type != 2 && category_type != 3 {
+            return Err(format!("Unknown category type: {}", category_type));
+        }
 
-        let name = if category_type == 2 {
-            // Bundle: PString at offset 4
-            if data.len() < 5 {
-                return Err("Bundle data too short".to_string());
-            }
-            let name_len = data[4] as usize;
-            if data.len() < 5 + name_len {
-                return Err("Bundle name too short".to_string());
-            }
-            String::from_utf8_lossy(&data[5..5 + name_len]).to_string()
-        } else if category_type == 3 {
-            // Category: PString at offset 28
-            if data.len() < 29 {
-                return Err("Category data too short".to_string());
-            }
-            let name_len = data[28] as usize;
-            if data.len() < 29 + name_len {
-                return Err("Category name too short".to_string());
-            }
-            let (decoded, _, _) = encoding_rs::MACINTOSH.decode(&data[29..29 + name_len]);
-            decoded.to_string()
+        // Where the name would end if it started at `offset`
+        let name_end = |offset: usize| data.get(offset).map(|len| offset + 1 + *len as usize).filter(|end| *end <= data.len());
+        let full = match (name_end(FULL_NAME_OFFSET), name_end(SHORT_NAME_OFFSET)) {
+            (Some(end), _) if end == data.len() => true,
+            (_, Some(end)) if end == data.len() => false,
+            (Some(_), _) if category_type == 3 => true,
+            (_, Some(_)) => false,
+            (Some(_), None) => true,
+            (None, None) => return Err("Category name too short".to_string()),
+        };
+
+        let name_offset = if full { FULL_NAME_OFFSET } else { SHORT_NAME_OFFSET };
+        let name_len = data[name_offset] as usize;
+        let (decoded, _, _) = encoding_rs::MACINTOSH.decode(&data[name_offset + 1..name_offset + 1 + name_len]);
+        let name = decoded.to_string();
+
+        let (guid, add_sn, delete_sn) = if full {
+            let guid = data[4..20].iter().map(|byte| format!("{:02x}", byte)).collect();
+            let add_sn = u32::from_be_bytes([data[20], data[21], data[22], data[23]]);
+            let delete_sn = u32::from_be_bytes([data[24], data[25], data[26], data[27]]);
+            (Some(guid), Some(add_sn), Some(delete_sn))
         } else {
-            return Err(format!("Unknown category type: {}", category_type));
+            (None, None, None)
         };
 
         let mut path = parent_path.to_vec();
@@ -754,11 +261,14 @@ impl HotlineClient {
             count,
             name,
             path,
+            guid,
+            add_sn,
+            delete_sn,
         })
     }
 
     // Helper method to parse news article list from binary data
-    fn parse_news_article_list(&self, data: &[u8], parent_path: &[String]) -> Result<Vec<NewsArticle>, String> {
+    pub(crate) fn parse_news_article_list(data: &[u8], parent_path: &[String]) -> Result<Vec<NewsArticle>, String> {
         if data.len() < 8 {
             return Err("Article list data too short".to_string());
         }
@@ -793,7 +303,7 @@ impl HotlineClient {
             let article_id = u32::from_be_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]]);
             offset += 4;
 
-            // Skip date (8 bytes)
+            let date = hotline_date_to_unix(&data[offset..offset + 8]);
             offset += 8;
 
             let parent_id = u32::from_be_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]]);
@@ -855,7 +365,7 @@ impl HotlineClient {
                 flags,
                 title,
                 poster,
-                date: None,
+                date,
                 path: parent_path.to_vec(),
             });
         }
@@ -864,126 +374,58 @@ impl HotlineClient {
     }
 }
 
-// --- Message board parsing helpers ---
-// Boards mix UTF-8 (modern clients) and Mac Roman (old clients) posts.
-// We split on divider lines in raw bytes before decoding so each post
-// gets its own UTF-8 → Mac Roman fallback pass.
-
-fn split_raw_lines(data: &[u8]) -> Vec<Vec<u8>> {
-    let mut lines: Vec<Vec<u8>> = Vec::new();
-    let mut start = 0;
-    let mut i = 0;
-    while i < data.len() {
-        if data[i] == 0x0D {
-            lines.push(data[start..i].to_vec());
-            i += 1;
-            if i < data.len() && data[i] == 0x0A {
-                i += 1;
-            }
-            start = i;
-        } else if data[i] == 0x0A {
-            lines.push(data[start..i].to_vec());
-            i += 1;
-            start = i;
-        } else {
-            i += 1;
-        }
-    }
-    if start < data.len() {
-        lines.push(data[start..].to_vec());
-    }
-    lines
-}
-
-fn classify_divider_lead(line: &[u8]) -> Option<u8> {
-    const SEPS: &[u8] = &[b'_', b'-', b'=', b'~', b'*'];
-    const WS: &[u8] = &[b' ', b'\t'];
-    let s = line.iter().position(|b| !WS.contains(b))?;
-    let e = line.iter().rposition(|b| !WS.contains(b))? + 1;
-    let trimmed = &line[s..e];
-    let lead = *trimmed.first()?;
-    if !SEPS.contains(&lead) {
-        return None;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(category_type: u16, count: u16, extra: &[u8], name: &str) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&category_type.to_be_bytes());
+        data.extend_from_slice(&count.to_be_bytes());
+        data.extend_from_slice(extra);
+        data.push(name.len() as u8);
+        data.extend_from_slice(name.as_bytes());
+        data
     }
-    if trimmed.len() >= 15 && trimmed.iter().all(|b| SEPS.contains(b)) {
-        return Some(lead);
-    }
-    let lc = trimmed.iter().take_while(|b| SEPS.contains(b)).count();
-    let tc = trimmed.iter().rev().take_while(|b| SEPS.contains(b)).count();
-    if lc >= 5 && tc >= 5 {
-        return Some(lead);
-    }
-    None
-}
-
-fn find_canonical_divider(lines: &[Vec<u8>]) -> Option<u8> {
-    let mut counts: std::collections::HashMap<u8, usize> = std::collections::HashMap::new();
-    let mut order: std::collections::HashMap<u8, usize> = std::collections::HashMap::new();
-    for line in lines {
-        if let Some(ch) = classify_divider_lead(line) {
-            let n = order.len();
-            order.entry(ch).or_insert(n);
-            *counts.entry(ch).or_insert(0) += 1;
-        }
-    }
-    counts
-        .iter()
-        .max_by(|a, b| {
-            a.1.cmp(b.1).then_with(|| {
-                // Earlier first-seen wins on tie
-                order.get(b.0).unwrap_or(&usize::MAX).cmp(order.get(a.0).unwrap_or(&usize::MAX))
-            })
-        })
-        .map(|(c, _)| *c)
-}
 
-fn decode_post_bytes(data: &[u8]) -> Option<String> {
-    if data.is_empty() {
-        return None;
-    }
-    let s = if let Ok(s) = std::str::from_utf8(data) {
-        s.to_owned()
-    } else {
-        let (decoded, _, _) = encoding_rs::MACINTOSH.decode(data);
-        decoded.into_owned()
-    };
-    let s = s.replace('\r', "\n");
-    let trimmed = s.trim().to_string();
-    if trimmed.is_empty() { None } else { Some(trimmed) }
-}
-
-fn parse_message_board_data(data: &[u8]) -> Vec<String> {
-    if data.is_empty() {
-        return Vec::new();
-    }
-    let lines = split_raw_lines(data);
-    let canonical = find_canonical_divider(&lines);
-    let mut posts: Vec<String> = Vec::new();
-    let mut current: Vec<u8> = Vec::new();
-
-    for line in &lines {
-        if let (Some(lead), Some(canon)) = (classify_divider_lead(line), canonical) {
-            if lead == canon {
-                if !current.is_empty() {
-                    if let Some(post) = decode_post_bytes(&current) {
-                        posts.push(post);
-                    }
-                    current.clear();
-                }
-                continue;
-            }
-        }
-        if !current.is_empty() {
-            current.push(b'\n');
-        }
-        current.extend_from_slice(line);
+    #[test]
+    fn replies_get_one_prefix_and_are_found_by_title() {
+        assert_eq!(reply_title("Welcome"), "Re: Welcome");
+        assert_eq!(reply_title("RE: Welcome"), "RE: Welcome");
+
+        let article = |id: u32, parent_id: u32, title: &str| NewsArticle {
+            id,
+            parent_id,
+            flags: 0,
+            title: title.to_string(),
+            poster: "alice".to_string(),
+            date: None,
+            path: Vec::new(),
+        };
+        let articles = vec![article(1, 0, "Welcome"), article(2, 1, "Re: Welcome"), article(5, 1, "Re: Welcome"), article(6, 0, "Re: Welcome")];
+        assert_eq!(find_posted(&articles, "Re: Welcome", 1), Some(5));
+        assert_eq!(find_posted(&articles, "Re: Welcome", 3), None);
     }
 
-    if !current.is_empty() {
-        if let Some(post) = decode_post_bytes(&current) {
-            posts.push(post);
-        }
+    #[test]
+    fn categories_parse_with_or_without_guid_and_serials() {
+        let mut extra = vec![0xab; 16];
+        extra.extend_from_slice(&7u32.to_be_bytes());
+        extra.extend_from_slice(&2u32.to_be_bytes());
+        let parent = vec!["News".to_string()];
+
+        // A bundle sent in the full layout keeps its GUID and serials
+        let bundle = HotlineClient::parse_news_category(&record(2, 1, &extra, "Archive"), &parent).unwrap();
+        assert_eq!(bundle.name, "Archive");
+        assert_eq!(bundle.guid.as_deref(), Some("abababababababababababababababab"));
+        assert_eq!((bundle.add_sn, bundle.delete_sn), (Some(7), Some(2)));
+
+        // An older server's category without them
+        let category = HotlineClient::parse_news_category(&record(3, 5, &[], "General"), &parent).unwrap();
+        assert_eq!((category.name.as_str(), category.count), ("General", 5));
+        assert_eq!(category.path, vec!["News", "General"]);
+        assert!(category.guid.is_none() && category.add_sn.is_none());
+
+        assert!(HotlineClient::parse_news_category(&record(3, 0, &[], "General")[..8], &parent).is_err());
     }
-
-    posts
 }
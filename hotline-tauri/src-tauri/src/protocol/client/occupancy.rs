@@ -0,0 +1,91 @@
+// Server user limits
+//
+// Hotline has no field for a server's user limit, but servers often state it
+// in words: in the refusal when they're full ("Server is full (50/50)"), or in
+// the description sent with the login reply ("Max 30 users"). The limit is
+// picked out of that text so the app can show how full a server is next to
+// its user count.
+
+/// Words that mark a nearby number as the limit
+const LIMIT_WORDS: [&str; 6] = ["max", "maximum", "limit", "limited", "capacity", "most"];
+
+/// How many words apart a limit word and its number may be
+const LIMIT_WORD_DISTANCE: usize = 4;
+
+#[derive(Debug, PartialEq)]
+enum Token<'a> {
+    Word(&'a str),
+    Number(u32),
+    Slash,
+}
+
+fn tokenize(text: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if c == '/' {
+            tokens.push(Token::Slash);
+            rest = &rest[1..];
+        } else if c.is_alphanumeric() {
+            let end = rest.find(|c: char| !c.is_alphanumeric()).unwrap_or(rest.len());
+            let word = &rest[..end];
+            tokens.push(match word.parse() {
+                Ok(number) => Token::Number(number),
+                Err(_) => Token::Word(word),
+            });
+            rest = &rest[end..];
+        } else {
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    tokens
+}
+
+/// The user limit a server states in `text`, if it states one
+pub fn user_limit_from_text(text: &str) -> Option<u16> {
+    let lower = text.to_lowercase();
+    let tokens = tokenize(&lower);
+    let word_at = |at: usize| match tokens.get(at) {
+        Some(Token::Word(word)) => Some(*word),
+        _ => None,
+    };
+    let valid = |number: u32| u16::try_from(number).ok().filter(|limit| *limit > 0);
+
+    // "14/50 users", or "(50/50)" in a refusal that says the server is full
+    let full = tokens.contains(&Token::Word("full"));
+    for (at, window) in tokens.windows(3).enumerate() {
+        if let [Token::Number(_), Token::Slash, Token::Number(limit)] = window {
+            if full || word_at(at + 3).is_some_and(|word| word.starts_with("user")) {
+                return valid(*limit);
+            }
+        }
+    }
+
+    // "Max 30 users", "limit: 100", "maximum number of users (25)"
+    for (at, token) in tokens.iter().enumerate() {
+        let Token::Number(number) = token else { continue };
+        let mut near = at.saturating_sub(LIMIT_WORD_DISTANCE)..at + LIMIT_WORD_DISTANCE + 1;
+        if near.any(|other| word_at(other).is_some_and(|word| LIMIT_WORDS.contains(&word))) {
+            return valid(*number);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_limits_stated_in_words() {
+        assert_eq!(user_limit_from_text("Sorry, the server is full (50/50)."), Some(50));
+        assert_eq!(user_limit_from_text("14/40 users online"), Some(40));
+        assert_eq!(user_limit_from_text("Maximum number of users (25) reached"), Some(25));
+        assert_eq!(user_limit_from_text("Welcome! Max 30 users, be nice."), Some(30));
+        assert_eq!(user_limit_from_text("Server limit: 100"), Some(100));
+
+        assert_eq!(user_limit_from_text("Too many users, try again later"), None);
+        assert_eq!(user_limit_from_text("Since 1997. Files updated 10/17"), None);
+        assert_eq!(user_limit_from_text("Max 0 users"), None);
+    }
+}
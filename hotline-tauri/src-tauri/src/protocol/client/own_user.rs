@@ -0,0 +1,92 @@
+// Our own roster entry
+//
+// Servers never say which user id is ours, but they echo our user info back
+// as NotifyUserChange after login and after each SetClientUserInfo. Every
+// name and icon we send is remembered until its echo arrives; the first echo
+// matching one gives away our id, and from then on every change to that id
+// is ours. The name and icon in an echo are what the server actually applied
+// (some trim or truncate names), so they become the ones the next Agreed,
+// user info update or reconnect sends.
+
+/// Sent updates kept waiting for their echo; older ones are forgotten
+const MAX_PENDING: usize = 8;
+
+struct SentInfo {
+    /// Nickname as sent, including any away suffix
+    sent_name: String,
+    /// Our nickname without the suffix
+    username: String,
+    icon: u16,
+}
+
+#[derive(Default)]
+pub(crate) struct OwnUser {
+    user_id: Option<u16>,
+    pending: Vec<SentInfo>,
+}
+
+impl OwnUser {
+    /// Forget our id, which the server hands out again at each login
+    pub(crate) fn reset(&mut self) {
+        self.user_id = None;
+        self.pending.clear();
+    }
+
+    /// Note user info we just sent as `sent_name`, for nickname `username`
+    pub(crate) fn expect(&mut self, sent_name: &str, username: &str, icon: u16) {
+        if self.pending.len() == MAX_PENDING {
+            self.pending.remove(0);
+        }
+        self.pending.push(SentInfo {
+            sent_name: sent_name.to_string(),
+            username: username.to_string(),
+            icon,
+        });
+    }
+
+    /// Our nickname and icon when a user change describes us, else None
+    pub(crate) fn echo(&mut self, user_id: u16, user_name: &str, icon: u16) -> Option<(String, u16)> {
+        let matched = self
+            .pending
+            .iter()
+            .position(|sent| sent.sent_name == user_name && sent.icon == icon);
+        match self.user_id {
+            Some(own) if own != user_id => return None,
+            Some(_) => {}
+            None if matched.is_some() => self.user_id = Some(user_id),
+            None => return None,
+        }
+
+        // Earlier updates were overtaken by this one
+        let username = match matched {
+            Some(at) => self.pending.drain(..=at).next_back().map(|sent| sent.username),
+            None => None,
+        };
+        Some((username.unwrap_or_else(|| user_name.to_string()), icon))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn learns_our_id_from_the_echo_and_follows_server_changes() {
+        let mut own = OwnUser::default();
+        own.expect("alice", "alice", 128);
+        assert_eq!(own.echo(7, "bob", 128), None);
+        assert_eq!(own.echo(9, "alice", 128), Some(("alice".to_string(), 128)));
+
+        // The away suffix is left off the nickname we keep
+        own.expect("alice [away]", "alice", 128);
+        assert_eq!(own.echo(9, "alice [away]", 128), Some(("alice".to_string(), 128)));
+
+        // A name the server shortened is taken as ours
+        own.expect("a very long nickname", "a very long nickname", 200);
+        assert_eq!(own.echo(9, "a very long", 200), Some(("a very long".to_string(), 200)));
+        assert_eq!(own.echo(7, "alice", 128), None);
+
+        own.reset();
+        assert_eq!(own.echo(9, "alice", 128), None);
+    }
+}
@@ -0,0 +1,210 @@
+// Private chat (chat room) functionality for Hotline client
+//
+// Private chats are server-side rooms identified by a ChatId. Creating one
+// (InviteToNewChat) returns its id; other users are pulled in with
+// InviteToChat and answer with JoinChat or RejectChatInvite. Messages use the
+// regular SendChat/ChatMessage transactions with a ChatId field attached.
+
+use super::requests::SendChatRequest;
+use super::{HotlineClient, HotlineEvent};
+use crate::protocol::avatar::{self, AvatarFallback};
+use crate::protocol::constants::{FieldType, TransactionType, FALLBACK_USER_ICON};
+use crate::protocol::transaction::{Transaction, TransactionField};
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrivateChatMember {
+    pub user_id: u16,
+    pub user_name: String,
+    pub icon: u16,
+    pub flags: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avatar: Option<AvatarFallback>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrivateChatInfo {
+    pub chat_id: u32,
+    pub subject: String,
+    pub members: Vec<PrivateChatMember>,
+}
+
+/// ChatId is a u32 on the wire, but some servers send it as two bytes
+pub(crate) fn chat_id(transaction: &Transaction) -> Option<u32> {
+    let field = transaction.get_field(FieldType::ChatId)?;
+    field
+        .to_u32()
+        .ok()
+        .or_else(|| field.to_u16().ok().map(u32::from))
+}
+
+/// Turn a server-initiated private chat transaction into an event
+pub(crate) fn chat_event(transaction: &Transaction) -> Option<HotlineEvent> {
+    let chat_id = chat_id(transaction)?;
+    let user_id = || {
+        transaction
+            .get_field(FieldType::UserId)
+            .and_then(|f| f.to_u16().ok())
+            .unwrap_or(0)
+    };
+    let user_name = || {
+        transaction
+            .get_field(FieldType::UserName)
+            .and_then(|f| f.to_string().ok())
+            .unwrap_or_default()
+    };
+
+    match transaction.transaction_type {
+        TransactionType::InviteToChat => Some(HotlineEvent::ChatInvite {
+            chat_id,
+            user_id: user_id(),
+            user_name: user_name(),
+        }),
+        TransactionType::NotifyChatOfUserChange => Some(HotlineEvent::ChatUserJoined {
+            chat_id,
+            user_id: user_id(),
+            user_name: user_name(),
+            icon: transaction
+                .get_field(FieldType::UserIconId)
+                .and_then(|f| f.to_u16().ok())
+                .unwrap_or(FALLBACK_USER_ICON),
+            flags: transaction
+                .get_field(FieldType::UserFlags)
+                .and_then(|f| f.to_u16().ok())
+                .unwrap_or(0),
+        }),
+        TransactionType::NotifyChatOfUserDelete => Some(HotlineEvent::ChatUserLeft {
+            chat_id,
+            user_id: user_id(),
+        }),
+        TransactionType::NotifyChatSubject => Some(HotlineEvent::ChatSubjectChanged {
+            chat_id,
+            subject: transaction
+                .get_field(FieldType::ChatSubject)
+                .and_then(|f| f.to_string().ok())
+                .unwrap_or_default(),
+        }),
+        TransactionType::ChatMessage => Some(HotlineEvent::PrivateChatMessage {
+            chat_id,
+            user_id: user_id(),
+            user_name: user_name(),
+            message: transaction
+                .get_field(FieldType::Data)
+                .and_then(|f| f.to_string().ok())
+                .unwrap_or_default(),
+        }),
+        _ => None,
+    }
+}
+
+impl HotlineClient {
+    /// Create a private chat and invite the given users. Returns the new chat's id.
+    pub async fn create_private_chat(&self, user_ids: &[u16]) -> Result<u32, String> {
+        let mut transaction = Transaction::new(self.next_transaction_id(), TransactionType::InviteToNewChat);
+        for user_id in user_ids {
+            transaction.add_field(TransactionField::from_u16(FieldType::UserId, *user_id));
+        }
+
+        let reply = self.send_and_wait(transaction, "create private chat").await?;
+        chat_id(&reply).ok_or_else(|| "Server did not return a chat id".to_string())
+    }
+
+    pub async fn invite_to_private_chat(&self, chat_id: u32, user_id: u16) -> Result<(), String> {
+        let mut transaction = Transaction::new(self.next_transaction_id(), TransactionType::InviteToChat);
+        transaction.add_field(TransactionField::from_u32(FieldType::ChatId, chat_id));
+        transaction.add_field(TransactionField::from_u16(FieldType::UserId, user_id));
+        self.send_transaction(transaction, "invite to private chat").await
+    }
+
+    /// Join a private chat we were invited to. The reply lists the subject and current members.
+    pub async fn join_private_chat(&self, chat_id: u32) -> Result<PrivateChatInfo, String> {
+        let mut transaction = Transaction::new(self.next_transaction_id(), TransactionType::JoinChat);
+        transaction.add_field(TransactionField::from_u32(FieldType::ChatId, chat_id));
+
+        let reply = self.send_and_wait(transaction, "join private chat").await?;
+
+        let subject = reply
+            .get_field(FieldType::ChatSubject)
+            .and_then(|f| f.to_string().ok())
+            .unwrap_or_default();
+        let members = reply
+            .fields
+            .iter()
+            .filter(|f| f.field_type == FieldType::UserNameWithInfo)
+            .filter_map(|f| HotlineClient::parse_user_info(&f.data).ok())
+            .map(|(user_id, user_name, icon, flags)| PrivateChatMember {
+                avatar: avatar::fallback(icon, &user_name),
+                user_id,
+                user_name,
+                icon,
+                flags,
+            })
+            .collect();
+
+        Ok(PrivateChatInfo {
+            chat_id,
+            subject,
+            members,
+        })
+    }
+
+    pub async fn leave_private_chat(&self, chat_id: u32) -> Result<(), String> {
+        let mut transaction = Transaction::new(self.next_transaction_id(), TransactionType::LeaveChat);
+        transaction.add_field(TransactionField::from_u32(FieldType::ChatId, chat_id));
+        self.send_transaction(transaction, "leave private chat").await
+    }
+
+    pub async fn reject_private_chat_invite(&self, chat_id: u32) -> Result<(), String> {
+        let mut transaction = Transaction::new(self.next_transaction_id(), TransactionType::RejectChatInvite);
+        transaction.add_field(TransactionField::from_u32(FieldType::ChatId, chat_id));
+        self.send_transaction(transaction, "reject private chat invite").await
+    }
+
+    pub async fn send_private_chat(&self, chat_id: u32, message: String) -> Result<(), String> {
+        let transaction = self.request(&SendChatRequest { message: &message, chat_id: Some(chat_id) });
+        self.send_transaction(transaction, "send private chat").await
+    }
+
+    pub async fn set_private_chat_subject(&self, chat_id: u32, subject: String) -> Result<(), String> {
+        let mut transaction = Transaction::new(self.next_transaction_id(), TransactionType::SetChatSubject);
+        transaction.add_field(TransactionField::from_u32(FieldType::ChatId, chat_id));
+        transaction.add_field(TransactionField::from_string(FieldType::ChatSubject, &subject));
+        self.send_transaction(transaction, "set chat subject").await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_two_and_four_byte_chat_ids() {
+        let mut transaction = Transaction::new(1, TransactionType::NotifyChatSubject);
+        transaction.add_field(TransactionField::from_u16(FieldType::ChatId, 9));
+        transaction.add_field(TransactionField::from_string(FieldType::ChatSubject, "Retro Macs"));
+        match chat_event(&transaction) {
+            Some(HotlineEvent::ChatSubjectChanged { chat_id, subject }) => {
+                assert_eq!(chat_id, 9);
+                assert_eq!(subject, "Retro Macs");
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+
+        let mut transaction = Transaction::new(2, TransactionType::NotifyChatOfUserDelete);
+        transaction.add_field(TransactionField::from_u32(FieldType::ChatId, 70000));
+        transaction.add_field(TransactionField::from_u16(FieldType::UserId, 4));
+        assert!(matches!(
+            chat_event(&transaction),
+            Some(HotlineEvent::ChatUserLeft { chat_id: 70000, user_id: 4 })
+        ));
+    }
+
+    #[test]
+    fn public_chat_is_not_a_private_chat_event() {
+        let mut transaction = Transaction::new(3, TransactionType::ChatMessage);
+        transaction.add_field(TransactionField::from_string(FieldType::Data, "hi"));
+        assert!(chat_event(&transaction).is_none());
+    }
+}
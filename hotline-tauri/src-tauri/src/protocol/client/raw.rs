@@ -0,0 +1,183 @@
+// Raw transactions
+//
+// A debugging aid for trying undocumented server features without
+// recompiling: any transaction type with any fields, numbered by hand. The
+// reply is kept as the bytes the server sent, before decoding folds unknown
+// field numbers into known ones, and returned as a dump of every field with
+// its number, size and the readings that make sense for its length.
+
+use super::{HotlineClient, Lane};
+use crate::protocol::constants::{FieldType, TransactionType, TRANSACTION_HEADER_SIZE};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "kind", content = "value")]
+pub enum RawValue {
+    Text(String),
+    U16(u16),
+    U32(u32),
+    /// Bytes written as hex digits, spaces allowed
+    Hex(String),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RawField {
+    pub id: u16,
+    pub value: RawValue,
+}
+
+impl RawField {
+    fn data(&self) -> Result<Vec<u8>, String> {
+        match &self.value {
+            RawValue::Text(text) => Ok(text.as_bytes().to_vec()),
+            RawValue::U16(value) => Ok(value.to_be_bytes().to_vec()),
+            RawValue::U32(value) => Ok(value.to_be_bytes().to_vec()),
+            RawValue::Hex(hex) => parse_hex(hex).ok_or_else(|| format!("Field {} is not valid hex", self.id)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RawFieldDump {
+    pub id: u16,
+    /// The field's name when this client knows the number
+    pub name: Option<String>,
+    pub size: usize,
+    pub hex: String,
+    /// The data as text, when it's valid UTF-8
+    pub text: Option<String>,
+    /// The data as a big-endian number, when it's 2, 4 or 8 bytes long
+    pub number: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RawReply {
+    pub transaction_type: u16,
+    pub error_code: u32,
+    pub fields: Vec<RawFieldDump>,
+}
+
+fn parse_hex(hex: &str) -> Option<Vec<u8>> {
+    let digits: Vec<char> = hex.chars().filter(|c| !c.is_whitespace()).collect();
+    if !digits.len().is_multiple_of(2) {
+        return None;
+    }
+    digits
+        .chunks(2)
+        .map(|pair| u8::from_str_radix(&pair.iter().collect::<String>(), 16).ok())
+        .collect()
+}
+
+fn field_name(id: u16) -> Option<String> {
+    let known = FieldType::from(id);
+    (known as u16 == id).then(|| format!("{:?}", known))
+}
+
+/// Encode a transaction from numbers rather than the typed constants
+pub fn encode_raw(id: u32, transaction_type: u16, fields: &[RawField]) -> Result<Vec<u8>, String> {
+    let mut body = (fields.len() as u16).to_be_bytes().to_vec();
+    for field in fields {
+        let data = field.data()?;
+        let size = u16::try_from(data.len()).map_err(|_| format!("Field {} is too long", field.id))?;
+        body.extend_from_slice(&field.id.to_be_bytes());
+        body.extend_from_slice(&size.to_be_bytes());
+        body.extend_from_slice(&data);
+    }
+
+    let mut frame = Vec::with_capacity(TRANSACTION_HEADER_SIZE + body.len());
+    frame.extend_from_slice(&[0, 0]);
+    frame.extend_from_slice(&transaction_type.to_be_bytes());
+    frame.extend_from_slice(&id.to_be_bytes());
+    frame.extend_from_slice(&0u32.to_be_bytes());
+    frame.extend_from_slice(&(body.len() as u32).to_be_bytes());
+    frame.extend_from_slice(&(body.len() as u32).to_be_bytes());
+    frame.extend_from_slice(&body);
+    Ok(frame)
+}
+
+/// Dump a whole frame as received, keeping every field's own number
+pub fn dump_reply(frame: &[u8]) -> Result<RawReply, String> {
+    if frame.len() < TRANSACTION_HEADER_SIZE {
+        return Err("Reply too short".to_string());
+    }
+    let read_u16 = |at: usize| frame.get(at..at + 2).map(|b| u16::from_be_bytes([b[0], b[1]]));
+    let mut reply = RawReply {
+        transaction_type: u16::from_be_bytes([frame[2], frame[3]]),
+        error_code: u32::from_be_bytes([frame[8], frame[9], frame[10], frame[11]]),
+        fields: Vec::new(),
+    };
+
+    let count = read_u16(TRANSACTION_HEADER_SIZE).unwrap_or(0);
+    let mut offset = TRANSACTION_HEADER_SIZE + 2;
+    for _ in 0..count {
+        let (Some(id), Some(size)) = (read_u16(offset), read_u16(offset + 2)) else {
+            break;
+        };
+        let Some(data) = frame.get(offset + 4..offset + 4 + size as usize) else {
+            break;
+        };
+        offset += 4 + data.len();
+        reply.fields.push(RawFieldDump {
+            id,
+            name: field_name(id),
+            size: data.len(),
+            hex: data.iter().map(|b| format!("{:02x}", b)).collect(),
+            text: std::str::from_utf8(data).ok().map(str::to_string),
+            number: matches!(data.len(), 2 | 4 | 8).then(|| data.iter().fold(0u64, |n, b| (n << 8) | u64::from(*b))),
+        });
+    }
+    Ok(reply)
+}
+
+impl HotlineClient {
+    /// Send a hand-built transaction and wait for the server's reply
+    pub async fn send_raw_transaction(&self, transaction_type: u16, fields: &[RawField]) -> Result<RawReply, String> {
+        let id = self.next_transaction_id();
+        let frame = encode_raw(id, transaction_type, fields)?;
+        let timeout = self.request_policy.lock().await.timeout();
+
+        let (tx, mut rx) = mpsc::channel(1);
+        self.raw_replies.write().await.insert(id, tx);
+        let lane = Lane::for_transaction(TransactionType::from(transaction_type));
+        if let Err(e) = self.send_frame(lane, frame).await {
+            self.raw_replies.write().await.remove(&id);
+            return Err(format!("Failed to send transaction {}: {}", transaction_type, e));
+        }
+
+        let reply = tokio::time::timeout(timeout, rx.recv()).await;
+        self.raw_replies.write().await.remove(&id);
+        match reply {
+            Ok(Some(frame)) => dump_reply(&frame),
+            Ok(None) => Err("Disconnected before the reply arrived".to_string()),
+            Err(_) => Err(format!("No reply to transaction {} after {:?}", transaction_type, timeout)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn raw_frames_keep_unknown_field_numbers() {
+        let fields = vec![
+            RawField { id: 101, value: RawValue::Text("hi".to_string()) },
+            RawField { id: 9999, value: RawValue::Hex("00 2a".to_string()) },
+        ];
+        let mut frame = encode_raw(7, 12345, &fields).unwrap();
+        assert_eq!(&frame[2..4], &12345u16.to_be_bytes());
+        assert!(encode_raw(7, 1, &[RawField { id: 1, value: RawValue::Hex("abc".to_string()) }]).is_err());
+
+        // Answer it as a server would, with an error code
+        frame[8..12].copy_from_slice(&3u32.to_be_bytes());
+        let reply = dump_reply(&frame).unwrap();
+        assert_eq!((reply.transaction_type, reply.error_code), (12345, 3));
+        assert_eq!(reply.fields[0].name.as_deref(), Some("Data"));
+        assert_eq!(reply.fields[0].text.as_deref(), Some("hi"));
+        assert_eq!((reply.fields[1].id, reply.fields[1].name.clone()), (9999, None));
+        assert_eq!((reply.fields[1].hex.as_str(), reply.fields[1].number), ("002a", Some(42)));
+    }
+}
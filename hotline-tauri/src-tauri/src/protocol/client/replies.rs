@@ -0,0 +1,226 @@
+// Typed replies
+//
+// Each struct is parsed from one kind of reply in a single place, instead of
+// the code that sent the request fishing for fields inline. Fields servers
+// may leave out are Options (or empty lists); a reply missing what makes it
+// usable, like a download's reference number, fails to parse. Parsers take
+// the reply only after its error code was checked, except LoginReply, which
+// turns a failed login into its error text.
+
+use super::occupancy::user_limit_from_text;
+use super::{FileInfo, HotlineClient};
+use crate::protocol::constants::FieldType;
+use crate::protocol::fingerprint::LoginFingerprint;
+use crate::protocol::i18n::{tr, tr_args, MessageId};
+use crate::protocol::quirks::ServerSoftware;
+use crate::protocol::transaction::Transaction;
+use crate::protocol::types::{NewsArticle, NewsCategory, UserAccess};
+
+/// ErrorText of a failed reply, or the error code when there is none
+pub fn error_text(reply: &Transaction) -> String {
+    reply
+        .get_field(FieldType::ErrorText)
+        .and_then(|f| f.to_string().ok())
+        .unwrap_or_else(|| tr_args(MessageId::ServerErrorCode, &[("code", &reply.error_code)]))
+}
+
+fn string_field(reply: &Transaction, field_type: FieldType) -> Option<String> {
+    reply.get_field(field_type).and_then(|f| f.to_string().ok())
+}
+
+#[derive(Debug, Clone)]
+pub struct LoginReply {
+    pub server_name: Option<String>,
+    pub version: Option<u16>,
+    /// Only sent by some servers, in the Data field
+    pub description: Option<String>,
+    /// Stated in the description or server name, when the server states one
+    pub user_limit: Option<u16>,
+    pub software: ServerSoftware,
+    pub user_access: UserAccess,
+}
+
+impl LoginReply {
+    /// Parse a login reply; a refused login gives the server's reason
+    pub fn from_reply(reply: &Transaction) -> Result<Self, String> {
+        if reply.error_code != 0 {
+            // Some servers put the error text in the Data field
+            return Err(string_field(reply, FieldType::ErrorText)
+                .or_else(|| string_field(reply, FieldType::Data))
+                .unwrap_or_else(|| match reply.error_code {
+                    1 => tr(MessageId::LoginRejected),
+                    2 => tr(MessageId::ServerFull),
+                    3 => tr(MessageId::Banned),
+                    _ => tr_args(MessageId::ServerErrorCode, &[("code", &reply.error_code)]),
+                }));
+        }
+
+        let server_name = string_field(reply, FieldType::ServerName);
+        let description = string_field(reply, FieldType::Data)
+            .filter(|text| !text.is_empty() && Some(text) != server_name.as_ref());
+        let user_access = reply
+            .get_field(FieldType::UserAccess)
+            .and_then(|f| f.to_u64().ok())
+            .unwrap_or(0);
+        let user_limit = description
+            .as_deref()
+            .and_then(user_limit_from_text)
+            .or_else(|| server_name.as_deref().and_then(user_limit_from_text));
+
+        Ok(Self {
+            server_name,
+            version: reply.get_field(FieldType::VersionNumber).and_then(|f| f.to_u16().ok()),
+            description,
+            user_limit,
+            software: LoginFingerprint::from_reply(reply).classify(),
+            user_access: UserAccess::from_bits(user_access),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DownloadFileReply {
+    pub reference_number: u32,
+    /// Bytes the transfer connection will carry, all forks included
+    pub transfer_size: Option<u64>,
+    /// Size of the data fork
+    pub file_size: Option<u64>,
+    /// Downloads queued ahead of this one; 0 when it can start right away
+    pub waiting_count: u16,
+}
+
+impl DownloadFileReply {
+    pub fn from_reply(reply: &Transaction) -> Result<Self, String> {
+        let reference_number = reply
+            .get_field(FieldType::ReferenceNumber)
+            .and_then(|f| f.to_u32().ok())
+            .ok_or_else(|| "No reference number in reply".to_string())?;
+
+        Ok(Self {
+            reference_number,
+            transfer_size: reply.get_field(FieldType::TransferSize).and_then(|f| f.to_size().ok()),
+            file_size: reply.get_field(FieldType::FileSize).and_then(|f| f.to_size().ok()),
+            waiting_count: reply
+                .get_field(FieldType::WaitingCount)
+                .and_then(|f| f.to_u16().ok())
+                .unwrap_or(0),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FileListReply {
+    pub files: Vec<FileInfo>,
+}
+
+impl FileListReply {
+    /// Entries that fail to parse are skipped. An empty folder has none.
+    pub fn from_reply(reply: &Transaction) -> Self {
+        let files = reply
+            .fields
+            .iter()
+            .filter(|f| f.field_type == FieldType::FileNameWithInfo)
+            .filter_map(|f| HotlineClient::parse_file_info(&f.data).ok())
+            .collect();
+        Self { files }
+    }
+}
+
+/// Reply to GetNewsCategoryList or GetNewsArticleList for the folder at `path`
+#[derive(Debug, Clone, Default)]
+pub struct NewsListReply {
+    pub categories: Vec<NewsCategory>,
+    pub articles: Vec<NewsArticle>,
+}
+
+impl NewsListReply {
+    /// Categories that fail to parse are skipped; a malformed article list
+    /// fails the whole reply
+    pub fn from_reply(reply: &Transaction, path: &[String]) -> Result<Self, String> {
+        let categories = reply
+            .fields
+            .iter()
+            .filter(|f| f.field_type == FieldType::NewsCategoryListData15)
+            .filter_map(|f| HotlineClient::parse_news_category(&f.data, path).ok())
+            .collect();
+        let articles = match reply.get_field(FieldType::NewsArticleListData) {
+            Some(field) => HotlineClient::parse_news_article_list(&field.data, path)?,
+            None => Vec::new(),
+        };
+        Ok(Self { categories, articles })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::constants::TransactionType;
+    use crate::protocol::transaction::TransactionField;
+
+    fn reply(fields: Vec<TransactionField>) -> Transaction {
+        let mut reply = Transaction::new(1, TransactionType::Reply);
+        reply.is_reply = 1;
+        for field in fields {
+            reply.add_field(field);
+        }
+        reply
+    }
+
+    #[test]
+    fn login_reply_fields_and_refusals() {
+        let ok = LoginReply::from_reply(&reply(vec![
+            TransactionField::from_u16(FieldType::VersionNumber, 190),
+            TransactionField::from_string(FieldType::ServerName, "Home"),
+            TransactionField::from_string(FieldType::Data, "Home"),
+            TransactionField::from_u64(FieldType::UserAccess, 0),
+        ]))
+        .unwrap();
+        assert_eq!(ok.server_name.as_deref(), Some("Home"));
+        assert_eq!(ok.version, Some(190));
+        // A description that only repeats the name is dropped
+        assert_eq!(ok.description, None);
+        assert_eq!(ok.user_limit, None);
+
+        let limited = LoginReply::from_reply(&reply(vec![
+            TransactionField::from_string(FieldType::ServerName, "Home"),
+            TransactionField::from_string(FieldType::Data, "Welcome! Max 30 users."),
+        ]))
+        .unwrap();
+        assert_eq!(limited.user_limit, Some(30));
+
+        let mut refused = reply(vec![TransactionField::from_string(FieldType::Data, "Server is full")]);
+        refused.error_code = 1;
+        assert_eq!(LoginReply::from_reply(&refused).unwrap_err(), "Server is full");
+    }
+
+    #[test]
+    fn download_reply_needs_a_reference_number() {
+        let download = DownloadFileReply::from_reply(&reply(vec![
+            TransactionField::from_u32(FieldType::ReferenceNumber, 42),
+            TransactionField::from_u32(FieldType::TransferSize, 1200),
+            TransactionField::from_u32(FieldType::FileSize, 1000),
+            TransactionField::from_u16(FieldType::WaitingCount, 3),
+        ]))
+        .unwrap();
+        assert_eq!(
+            download,
+            DownloadFileReply {
+                reference_number: 42,
+                transfer_size: Some(1200),
+                file_size: Some(1000),
+                waiting_count: 3,
+            }
+        );
+
+        assert!(DownloadFileReply::from_reply(&reply(vec![])).is_err());
+        let bare = DownloadFileReply::from_reply(&reply(vec![TransactionField::from_u32(FieldType::ReferenceNumber, 1)])).unwrap();
+        assert_eq!((bare.file_size, bare.waiting_count), (None, 0));
+    }
+
+    #[test]
+    fn empty_lists_parse_as_empty() {
+        assert!(FileListReply::from_reply(&reply(vec![])).files.is_empty());
+        let news = NewsListReply::from_reply(&reply(vec![]), &[]).unwrap();
+        assert!(news.categories.is_empty() && news.articles.is_empty());
+    }
+}
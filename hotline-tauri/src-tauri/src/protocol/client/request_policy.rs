@@ -0,0 +1,114 @@
+// Timeouts and retries for request/reply transactions
+//
+// Each connection waits `timeout_secs` for a reply before giving up; old
+// servers on slow links can need far longer than the default. Requests that
+// only read state are sent again after a timeout, up to `retries` more times,
+// waiting `backoff_ms` before the first retry and twice as long before each
+// one after that. Requests that change something are never repeated, since
+// the server may have acted on the first copy and only the reply was slow.
+
+use crate::protocol::constants::TransactionType;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Longest wait between retries, however many there are
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RequestPolicy {
+    pub timeout_secs: u64,
+    pub retries: u32,
+    pub backoff_ms: u64,
+}
+
+impl Default for RequestPolicy {
+    fn default() -> Self {
+        Self {
+            timeout_secs: 10,
+            retries: 0,
+            backoff_ms: 1000,
+        }
+    }
+}
+
+impl RequestPolicy {
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs)
+    }
+
+    /// Pause before retry number `retry`, counting from 1
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = 1u64 << retry.saturating_sub(1).min(16);
+        Duration::from_millis(self.backoff_ms.saturating_mul(factor)).min(MAX_BACKOFF)
+    }
+
+    /// Retries allowed for a request of this type
+    pub fn retries_for(&self, transaction_type: TransactionType) -> u32 {
+        if is_read_only(transaction_type) {
+            self.retries
+        } else {
+            0
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1..=300).contains(&self.timeout_secs) {
+            return Err("Request timeout must be between 1 and 300 seconds".to_string());
+        }
+        if self.retries > 5 {
+            return Err("Requests can be retried at most 5 times".to_string());
+        }
+        if self.backoff_ms > 60_000 {
+            return Err("Retry backoff must be at most 60 seconds".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Requests that are safe to send twice
+fn is_read_only(transaction_type: TransactionType) -> bool {
+    matches!(
+        transaction_type,
+        TransactionType::GetMessageBoard
+            | TransactionType::GetFileNameList
+            | TransactionType::GetFileInfo
+            | TransactionType::GetUserNameList
+            | TransactionType::GetClientInfoText
+            | TransactionType::ListUsers
+            | TransactionType::GetUser
+            | TransactionType::GetNewsCategoryList
+            | TransactionType::GetNewsArticleList
+            | TransactionType::GetNewsArticleData
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_a_cap() {
+        let policy = RequestPolicy { retries: 3, backoff_ms: 500, ..Default::default() };
+        assert_eq!(policy.backoff(1), Duration::from_millis(500));
+        assert_eq!(policy.backoff(2), Duration::from_millis(1000));
+        assert_eq!(policy.backoff(3), Duration::from_millis(2000));
+        assert_eq!(policy.backoff(40), MAX_BACKOFF);
+    }
+
+    #[test]
+    fn only_read_only_requests_are_retried() {
+        let policy = RequestPolicy { retries: 2, ..Default::default() };
+        assert_eq!(policy.retries_for(TransactionType::GetFileInfo), 2);
+        assert_eq!(policy.retries_for(TransactionType::DeleteFile), 0);
+        assert_eq!(policy.retries_for(TransactionType::SendChat), 0);
+    }
+
+    #[test]
+    fn rejects_out_of_range_values() {
+        assert!(RequestPolicy::default().validate().is_ok());
+        assert!(RequestPolicy { timeout_secs: 0, ..Default::default() }.validate().is_err());
+        assert!(RequestPolicy { retries: 9, ..Default::default() }.validate().is_err());
+        assert!(RequestPolicy { backoff_ms: 120_000, ..Default::default() }.validate().is_err());
+    }
+}
@@ -0,0 +1,385 @@
+// Typed request builders
+//
+// Each request is a struct holding the fields its transaction carries. The
+// fields a server insists on are plain struct fields, so leaving one out is a
+// compile error rather than a refused request; optional ones are Options or
+// have a neutral value that isn't sent. `transaction(id)` encodes the request,
+// and HotlineClient::request numbers it.
+
+use super::files::{encode_file_path, encode_resume_data, TRANSFER_OPTION_RESUME};
+use super::instant_message::InstantMessageKind;
+use super::HotlineClient;
+use crate::protocol::constants::{FieldType, TransactionType};
+use crate::protocol::transaction::{Transaction, TransactionField};
+
+pub trait Request {
+    const KIND: TransactionType;
+
+    fn fields(&self) -> Vec<TransactionField>;
+
+    fn transaction(&self, id: u32) -> Transaction {
+        let mut transaction = Transaction::new(id, Self::KIND);
+        for field in self.fields() {
+            transaction.add_field(field);
+        }
+        transaction
+    }
+}
+
+/// Client name and version sent with the login when identification is on
+pub struct ClientVersion<'a> {
+    pub name: &'a str,
+    pub version: &'a str,
+}
+
+pub struct LoginRequest<'a> {
+    pub login: &'a str,
+    pub password: &'a str,
+    pub user_name: &'a str,
+    pub icon_id: u16,
+    pub version_number: u16,
+    pub client: Option<ClientVersion<'a>>,
+}
+
+impl Request for LoginRequest<'_> {
+    const KIND: TransactionType = TransactionType::Login;
+
+    fn fields(&self) -> Vec<TransactionField> {
+        let mut fields = vec![
+            TransactionField::from_encoded_string(FieldType::UserLogin, self.login),
+            TransactionField::from_encoded_string(FieldType::UserPassword, self.password),
+            TransactionField::from_u16(FieldType::UserIconId, self.icon_id),
+            TransactionField::from_string(FieldType::UserName, self.user_name),
+            TransactionField::from_u16(FieldType::VersionNumber, self.version_number),
+        ];
+        if let Some(client) = &self.client {
+            fields.push(TransactionField::from_string(FieldType::ClientName, client.name));
+            fields.push(TransactionField::from_string(FieldType::ClientVersion, client.version));
+        }
+        fields
+    }
+}
+
+/// Who we are, as sent with Agreed and SetClientUserInfo
+pub struct UserInfoFields<'a> {
+    pub user_name: &'a str,
+    pub icon_id: u16,
+    /// User option bits: refuse messages, refuse chat, automatic response
+    pub options: u16,
+    pub automatic_response: Option<&'a str>,
+}
+
+impl UserInfoFields<'_> {
+    fn fields(&self) -> Vec<TransactionField> {
+        let mut fields = vec![
+            TransactionField::from_string(FieldType::UserName, self.user_name),
+            TransactionField::from_u16(FieldType::UserIconId, self.icon_id),
+            TransactionField::from_u16(FieldType::Options, self.options),
+        ];
+        if let Some(response) = self.automatic_response {
+            fields.push(TransactionField::from_string(FieldType::AutomaticResponse, response));
+        }
+        fields
+    }
+}
+
+/// Accepting the agreement. Some servers (Mobius) refuse it without our user
+/// info, others take a bare Agreed.
+pub struct AgreedRequest<'a> {
+    pub user_info: Option<UserInfoFields<'a>>,
+}
+
+impl Request for AgreedRequest<'_> {
+    const KIND: TransactionType = TransactionType::Agreed;
+
+    fn fields(&self) -> Vec<TransactionField> {
+        self.user_info.as_ref().map(UserInfoFields::fields).unwrap_or_default()
+    }
+}
+
+pub struct SetClientUserInfoRequest<'a> {
+    pub user_info: UserInfoFields<'a>,
+    pub flags: u16,
+}
+
+impl Request for SetClientUserInfoRequest<'_> {
+    const KIND: TransactionType = TransactionType::SetClientUserInfo;
+
+    fn fields(&self) -> Vec<TransactionField> {
+        let mut fields = self.user_info.fields();
+        fields.insert(2, TransactionField::from_u16(FieldType::UserFlags, self.flags));
+        fields
+    }
+}
+
+/// Chat to the public room, or to a private chat with `chat_id`
+pub struct SendChatRequest<'a> {
+    pub message: &'a str,
+    pub chat_id: Option<u32>,
+}
+
+impl Request for SendChatRequest<'_> {
+    const KIND: TransactionType = TransactionType::SendChat;
+
+    fn fields(&self) -> Vec<TransactionField> {
+        let mut fields = vec![
+            TransactionField::from_string(FieldType::Data, self.message),
+            TransactionField::from_u16(FieldType::ChatOptions, 0), // 0 = normal chat, 1 = announce
+        ];
+        if let Some(chat_id) = self.chat_id {
+            fields.push(TransactionField::from_u32(FieldType::ChatId, chat_id));
+        }
+        fields
+    }
+}
+
+pub struct SendInstantMessageRequest<'a> {
+    pub user_id: u16,
+    /// None for servers that predate the Options field
+    pub kind: Option<InstantMessageKind>,
+    pub message: &'a str,
+    /// The message being answered; empty quotes aren't sent
+    pub quoting: Option<&'a str>,
+}
+
+impl Request for SendInstantMessageRequest<'_> {
+    const KIND: TransactionType = TransactionType::SendInstantMessage;
+
+    fn fields(&self) -> Vec<TransactionField> {
+        let mut fields = vec![TransactionField::from_u16(FieldType::UserId, self.user_id)];
+        if let Some(kind) = self.kind {
+            fields.push(TransactionField::from_u32(FieldType::Options, kind as u32));
+        }
+        fields.push(TransactionField::from_string(FieldType::Data, self.message));
+        if let Some(quote) = self.quoting.filter(|q| !q.is_empty()) {
+            fields.push(TransactionField::from_string(FieldType::QuotingMessage, quote));
+        }
+        fields
+    }
+}
+
+/// A file or folder on the server; an empty path is the root
+pub struct FileTarget<'a> {
+    pub path: &'a [String],
+    pub file_name: &'a str,
+}
+
+impl FileTarget<'_> {
+    fn fields(&self) -> Vec<TransactionField> {
+        let mut fields = vec![TransactionField::from_string(FieldType::FileName, self.file_name)];
+        if let Some(path_data) = encode_file_path(self.path) {
+            fields.push(TransactionField::new(FieldType::FilePath, path_data));
+        }
+        fields
+    }
+}
+
+/// Destination folder for a move or alias. The root has no encoded form, so
+/// an empty path is sent to say where to go.
+fn new_path_field(path: &[String]) -> TransactionField {
+    let data = encode_file_path(path).unwrap_or_else(|| 0u16.to_be_bytes().to_vec());
+    TransactionField::new(FieldType::FileNewPath, data)
+}
+
+pub struct DownloadFileRequest<'a> {
+    pub file: FileTarget<'a>,
+    /// Bytes of the data fork already on disk; 0 downloads from the start
+    pub resume_offset: u32,
+}
+
+impl Request for DownloadFileRequest<'_> {
+    const KIND: TransactionType = TransactionType::DownloadFile;
+
+    fn fields(&self) -> Vec<TransactionField> {
+        let mut fields = self.file.fields();
+        if self.resume_offset > 0 {
+            fields.push(TransactionField::new(FieldType::FileResumeData, encode_resume_data(self.resume_offset)));
+        }
+        fields
+    }
+}
+
+pub struct UploadFileRequest<'a> {
+    pub file: FileTarget<'a>,
+    /// Ask the server to continue an earlier upload of the same file
+    pub resume: bool,
+}
+
+impl Request for UploadFileRequest<'_> {
+    const KIND: TransactionType = TransactionType::UploadFile;
+
+    fn fields(&self) -> Vec<TransactionField> {
+        let mut fields = self.file.fields();
+        if self.resume {
+            fields.push(TransactionField::from_u16(FieldType::FileTransferOptions, TRANSFER_OPTION_RESUME));
+        }
+        fields
+    }
+}
+
+pub struct GetFileInfoRequest<'a> {
+    pub file: FileTarget<'a>,
+}
+
+impl Request for GetFileInfoRequest<'_> {
+    const KIND: TransactionType = TransactionType::GetFileInfo;
+
+    fn fields(&self) -> Vec<TransactionField> {
+        self.file.fields()
+    }
+}
+
+pub struct SetFileInfoRequest<'a> {
+    pub file: FileTarget<'a>,
+    pub new_name: Option<&'a str>,
+    pub comment: Option<&'a str>,
+}
+
+impl Request for SetFileInfoRequest<'_> {
+    const KIND: TransactionType = TransactionType::SetFileInfo;
+
+    fn fields(&self) -> Vec<TransactionField> {
+        let mut fields = self.file.fields();
+        if let Some(new_name) = self.new_name {
+            fields.push(TransactionField::from_string(FieldType::FileNewName, new_name));
+        }
+        if let Some(comment) = self.comment {
+            fields.push(TransactionField::from_string(FieldType::FileComment, comment));
+        }
+        fields
+    }
+}
+
+pub struct MoveFileRequest<'a> {
+    pub file: FileTarget<'a>,
+    pub new_path: &'a [String],
+}
+
+impl Request for MoveFileRequest<'_> {
+    const KIND: TransactionType = TransactionType::MoveFile;
+
+    fn fields(&self) -> Vec<TransactionField> {
+        let mut fields = self.file.fields();
+        fields.push(new_path_field(self.new_path));
+        fields
+    }
+}
+
+pub struct MakeFileAliasRequest<'a> {
+    pub file: FileTarget<'a>,
+    /// Folder the alias is created in
+    pub alias_path: &'a [String],
+}
+
+impl Request for MakeFileAliasRequest<'_> {
+    const KIND: TransactionType = TransactionType::MakeFileAlias;
+
+    fn fields(&self) -> Vec<TransactionField> {
+        let mut fields = self.file.fields();
+        fields.push(new_path_field(self.alias_path));
+        fields
+    }
+}
+
+pub struct DeleteFileRequest<'a> {
+    pub file: FileTarget<'a>,
+}
+
+impl Request for DeleteFileRequest<'_> {
+    const KIND: TransactionType = TransactionType::DeleteFile;
+
+    fn fields(&self) -> Vec<TransactionField> {
+        self.file.fields()
+    }
+}
+
+/// Create a folder named `folder.file_name` inside `folder.path`
+pub struct NewFolderRequest<'a> {
+    pub folder: FileTarget<'a>,
+}
+
+impl Request for NewFolderRequest<'_> {
+    const KIND: TransactionType = TransactionType::NewFolder;
+
+    fn fields(&self) -> Vec<TransactionField> {
+        self.folder.fields()
+    }
+}
+
+impl HotlineClient {
+    /// Encode `request` with the next transaction id
+    pub(crate) fn request(&self, request: &impl Request) -> Transaction {
+        request.transaction(self.next_transaction_id())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field_types(transaction: &Transaction) -> Vec<FieldType> {
+        transaction.fields.iter().map(|f| f.field_type).collect()
+    }
+
+    #[test]
+    fn agreed_carries_user_info_only_when_given() {
+        let bare = AgreedRequest { user_info: None }.transaction(7);
+        assert_eq!(bare.id, 7);
+        assert!(bare.fields.is_empty());
+
+        let full = AgreedRequest {
+            user_info: Some(UserInfoFields {
+                user_name: "alice",
+                icon_id: 128,
+                options: 4,
+                automatic_response: Some("Away"),
+            }),
+        }
+        .transaction(8);
+        assert_eq!(
+            field_types(&full),
+            [FieldType::UserName, FieldType::UserIconId, FieldType::Options, FieldType::AutomaticResponse]
+        );
+        assert_eq!(full.get_field(FieldType::Options).unwrap().to_u16().unwrap(), 4);
+    }
+
+    #[test]
+    fn login_obfuscates_credentials() {
+        let login = LoginRequest {
+            login: "guest",
+            password: "",
+            user_name: "alice",
+            icon_id: 128,
+            version_number: 190,
+            client: None,
+        }
+        .transaction(1);
+        assert_eq!(login.get_field(FieldType::UserLogin).unwrap().data, b"guest".map(|b| b ^ 0xFF));
+        assert!(login.get_field(FieldType::ClientName).is_none());
+    }
+
+    #[test]
+    fn file_requests_encode_path_and_options() {
+        let path = vec!["Uploads".to_string()];
+        let download = DownloadFileRequest {
+            file: FileTarget { path: &path, file_name: "a.txt" },
+            resume_offset: 0,
+        }
+        .transaction(1);
+        assert_eq!(field_types(&download), [FieldType::FileName, FieldType::FilePath]);
+
+        let resumed = DownloadFileRequest {
+            file: FileTarget { path: &[], file_name: "a.txt" },
+            resume_offset: 10,
+        }
+        .transaction(2);
+        assert_eq!(field_types(&resumed), [FieldType::FileName, FieldType::FileResumeData]);
+
+        let to_root = MoveFileRequest {
+            file: FileTarget { path: &path, file_name: "a.txt" },
+            new_path: &[],
+        }
+        .transaction(3);
+        assert_eq!(to_root.get_field(FieldType::FileNewPath).unwrap().data, [0u8, 0]);
+    }
+}
@@ -0,0 +1,596 @@
+// Transfer queue scheduling
+//
+// Downloads and uploads are queued per server and started in order, at most
+// `max_concurrent` at a time. This module only tracks ordering and state; the
+// app layer performs the actual transfers and reports back through
+// `start_next` / `finish`. Running transfers are stopped through the abort
+// handle recorded when they start.
+//
+// Servers may also cap how many downloads one user runs at once. They don't
+// announce the cap, only refuse the download that goes over it, so the
+// queue learns it from the refusal and holds further downloads back until
+// one of the running ones finishes.
+//
+// Independently of the queues, every HTXF connection takes a slot from
+// HostSlots, which caps how many transfer sockets are open to one host at a
+// time across all bookmarks that point at it.
+
+use super::mac_forks::{ForkMode, UploadFile};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+use tokio::task::AbortHandle;
+
+pub const DEFAULT_MAX_CONCURRENT_TRANSFERS: usize = 2;
+/// Classic clients never opened more than a few transfer connections per server
+pub const DEFAULT_MAX_TRANSFERS_PER_HOST: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TransferKind {
+    Download,
+    Upload,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TransferState {
+    Queued,
+    Running,
+    Paused,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl TransferState {
+    pub fn is_finished(self) -> bool {
+        matches!(self, TransferState::Completed | TransferState::Failed | TransferState::Cancelled)
+    }
+}
+
+/// What to transfer. Kept out of the serialized listing since uploads carry their data.
+#[derive(Debug, Clone)]
+pub enum TransferRequest {
+    Download {
+        path: Vec<String>,
+        file_name: String,
+        file_size: u64,
+        download_folder: Option<String>,
+        fork_mode: ForkMode,
+    },
+    Upload {
+        path: Vec<String>,
+        upload: UploadFile,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueuedTransfer {
+    pub id: String,
+    pub server_id: String,
+    pub kind: TransferKind,
+    pub file_name: String,
+    pub path: Vec<String>,
+    pub state: TransferState,
+    pub total_bytes: u64,
+    pub error: Option<String>,
+    /// True once the transfer has run at least once (paused downloads resume from their
+    /// partial data, uploads ask the server to continue its partial copy)
+    pub started: bool,
+    #[serde(skip)]
+    pub request: TransferRequest,
+}
+
+impl QueuedTransfer {
+    pub fn new(id: String, server_id: String, request: TransferRequest) -> Self {
+        let (kind, file_name, path, total_bytes) = match &request {
+            TransferRequest::Download { path, file_name, file_size, .. } => {
+                (TransferKind::Download, file_name.clone(), path.clone(), *file_size)
+            }
+            TransferRequest::Upload { path, upload } => {
+                (TransferKind::Upload, upload.file_name.clone(), path.clone(), upload.data.len() as u64)
+            }
+        };
+
+        Self {
+            id,
+            server_id,
+            kind,
+            file_name,
+            path,
+            state: TransferState::Queued,
+            total_bytes,
+            error: None,
+            started: false,
+            request,
+        }
+    }
+}
+
+/// Download slots in use against the server's cap, once one was observed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadSlots {
+    pub used: usize,
+    pub limit: Option<usize>,
+}
+
+/// Wording servers use when refusing a download over the per-user cap
+const DOWNLOAD_LIMIT_PHRASES: [&str; 4] = [
+    "too many downloads",
+    "simultaneous downloads",
+    "download limit",
+    "maximum number of downloads",
+];
+
+/// The cap a download refusal reveals, or None when it's another error.
+/// A number in the text is taken as the cap; otherwise it's the downloads
+/// that were already running, `others`.
+pub fn download_limit_from_error(text: &str, others: usize) -> Option<usize> {
+    let lower = text.to_lowercase();
+    if !DOWNLOAD_LIMIT_PHRASES.iter().any(|phrase| lower.contains(phrase)) {
+        return None;
+    }
+    let stated = lower
+        .split(|c: char| !c.is_ascii_digit())
+        .find_map(|digits| digits.parse::<usize>().ok());
+    Some(stated.unwrap_or(others).max(1))
+}
+
+pub struct TransferQueue {
+    items: Vec<QueuedTransfer>,
+    max_concurrent: usize,
+    /// The server's cap on simultaneous downloads, once observed
+    download_limit: Option<usize>,
+}
+
+impl Default for TransferQueue {
+    fn default() -> Self {
+        Self {
+            items: Vec::new(),
+            max_concurrent: DEFAULT_MAX_CONCURRENT_TRANSFERS,
+            download_limit: None,
+        }
+    }
+}
+
+impl TransferQueue {
+    pub fn enqueue(&mut self, item: QueuedTransfer) {
+        self.items.push(item);
+    }
+
+    pub fn list(&self) -> Vec<QueuedTransfer> {
+        self.items.clone()
+    }
+
+    pub fn get(&self, id: &str) -> Option<&QueuedTransfer> {
+        self.items.iter().find(|item| item.id == id)
+    }
+
+    fn get_mut(&mut self, id: &str) -> Result<&mut QueuedTransfer, String> {
+        self.items
+            .iter_mut()
+            .find(|item| item.id == id)
+            .ok_or_else(|| format!("No queued transfer with id {}", id))
+    }
+
+    pub fn set_max_concurrent(&mut self, max_concurrent: usize) {
+        self.max_concurrent = max_concurrent.max(1);
+    }
+
+    pub fn set_download_limit(&mut self, limit: usize) {
+        self.download_limit = Some(limit.max(1));
+    }
+
+    pub fn download_slots(&self) -> DownloadSlots {
+        DownloadSlots {
+            used: self.running_downloads(),
+            limit: self.download_limit,
+        }
+    }
+
+    fn running_count(&self) -> usize {
+        self.items.iter().filter(|item| item.state == TransferState::Running).count()
+    }
+
+    pub fn running_downloads(&self) -> usize {
+        self.items
+            .iter()
+            .filter(|item| item.state == TransferState::Running && item.kind == TransferKind::Download)
+            .count()
+    }
+
+    /// Mark as many queued transfers as running as free slots allow, in queue order,
+    /// and return them so the caller can start them. Downloads past the server's
+    /// download cap wait without holding up the uploads behind them.
+    pub fn start_next(&mut self) -> Vec<QueuedTransfer> {
+        let mut free = self.max_concurrent.saturating_sub(self.running_count());
+        let mut free_downloads = self
+            .download_limit
+            .map_or(usize::MAX, |limit| limit.saturating_sub(self.running_downloads()));
+        let mut started = Vec::new();
+
+        for item in self.items.iter_mut().filter(|item| item.state == TransferState::Queued) {
+            if free == 0 {
+                break;
+            }
+            if item.kind == TransferKind::Download {
+                if free_downloads == 0 {
+                    continue;
+                }
+                free_downloads -= 1;
+            }
+            free -= 1;
+            item.state = TransferState::Running;
+            started.push(item.clone());
+            item.started = true;
+        }
+
+        started
+    }
+
+    /// Put a running transfer back in the queue, for a download the server
+    /// refused only because too many were running
+    pub fn requeue(&mut self, id: &str) {
+        if let Ok(item) = self.get_mut(id) {
+            if item.state == TransferState::Running {
+                item.state = TransferState::Queued;
+            }
+        }
+    }
+
+    /// Record the outcome of a running transfer. Ignored if it was paused or
+    /// cancelled in the meantime.
+    pub fn finish(&mut self, id: &str, result: Result<(), String>) {
+        if let Ok(item) = self.get_mut(id) {
+            if item.state != TransferState::Running {
+                return;
+            }
+            match result {
+                Ok(()) => item.state = TransferState::Completed,
+                Err(e) => {
+                    item.state = TransferState::Failed;
+                    item.error = Some(e);
+                }
+            }
+        }
+    }
+
+    /// Pause a queued or running transfer. Returns true if it was running and must be stopped.
+    pub fn pause(&mut self, id: &str) -> Result<bool, String> {
+        let item = self.get_mut(id)?;
+        match item.state {
+            TransferState::Queued => {
+                item.state = TransferState::Paused;
+                Ok(false)
+            }
+            TransferState::Running => {
+                item.state = TransferState::Paused;
+                Ok(true)
+            }
+            state => Err(format!("Cannot pause a transfer that is {:?}", state)),
+        }
+    }
+
+    /// Put a paused or failed transfer back in the queue
+    pub fn resume(&mut self, id: &str) -> Result<(), String> {
+        let item = self.get_mut(id)?;
+        match item.state {
+            TransferState::Paused | TransferState::Failed => {
+                item.state = TransferState::Queued;
+                item.error = None;
+                Ok(())
+            }
+            state => Err(format!("Cannot resume a transfer that is {:?}", state)),
+        }
+    }
+
+    /// Cancel a transfer. Returns true if it was running and must be stopped.
+    pub fn cancel(&mut self, id: &str) -> Result<bool, String> {
+        let item = self.get_mut(id)?;
+        if item.state.is_finished() {
+            return Err(format!("Cannot cancel a transfer that is {:?}", item.state));
+        }
+        let was_running = item.state == TransferState::Running;
+        item.state = TransferState::Cancelled;
+        Ok(was_running)
+    }
+
+    /// Reorder the queue. `ids` lists transfers in their new order; transfers not
+    /// mentioned keep their relative order after the listed ones.
+    pub fn reorder(&mut self, ids: &[String]) -> Result<(), String> {
+        for id in ids {
+            if self.get(id).is_none() {
+                return Err(format!("No queued transfer with id {}", id));
+            }
+        }
+
+        let mut reordered: Vec<QueuedTransfer> = Vec::with_capacity(self.items.len());
+        for id in ids {
+            if let Some(pos) = self.items.iter().position(|item| &item.id == id) {
+                reordered.push(self.items.remove(pos));
+            }
+        }
+        reordered.append(&mut self.items);
+        self.items = reordered;
+        Ok(())
+    }
+
+    /// Drop completed, failed and cancelled transfers from the list
+    pub fn clear_finished(&mut self) {
+        self.items.retain(|item| !item.state.is_finished());
+    }
+}
+
+#[derive(Default)]
+struct HostSlotsInner {
+    limit: AtomicUsize,
+    open: Mutex<HashMap<String, usize>>,
+    released: Notify,
+}
+
+/// Counts open transfer connections per host and makes new ones wait for a free slot
+#[derive(Clone)]
+pub struct HostSlots {
+    inner: Arc<HostSlotsInner>,
+}
+
+impl Default for HostSlots {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_TRANSFERS_PER_HOST)
+    }
+}
+
+impl HostSlots {
+    pub fn new(limit: usize) -> Self {
+        let slots = Self { inner: Arc::new(HostSlotsInner::default()) };
+        slots.set_limit(limit);
+        slots
+    }
+
+    pub fn set_limit(&self, limit: usize) {
+        self.inner.limit.store(limit.max(1), Ordering::SeqCst);
+        // A raised limit may free up waiters
+        self.inner.released.notify_waiters();
+    }
+
+    #[cfg(test)]
+    fn open_count(&self, host: &str) -> usize {
+        self.inner.open.lock().unwrap().get(&host.to_lowercase()).copied().unwrap_or(0)
+    }
+
+    fn try_acquire(&self, host: &str) -> Option<TransferSlot> {
+        let mut open = self.inner.open.lock().unwrap();
+        let count = open.entry(host.to_string()).or_default();
+        if *count >= self.inner.limit.load(Ordering::SeqCst) {
+            return None;
+        }
+        *count += 1;
+        Some(TransferSlot { slots: self.clone(), host: host.to_string() })
+    }
+
+    /// Wait until a transfer connection to `host` may be opened
+    pub async fn acquire(&self, host: &str) -> TransferSlot {
+        let host = host.to_lowercase();
+        let mut logged = false;
+        loop {
+            let released = self.inner.released.notified();
+            tokio::pin!(released);
+            released.as_mut().enable();
+
+            if let Some(slot) = self.try_acquire(&host) {
+                return slot;
+            }
+            if !logged {
+                println!("Waiting for a free transfer slot on {}", host);
+                logged = true;
+            }
+            released.await;
+        }
+    }
+}
+
+/// An open transfer connection; frees its host slot when dropped
+pub struct TransferSlot {
+    slots: HostSlots,
+    host: String,
+}
+
+impl Drop for TransferSlot {
+    fn drop(&mut self) {
+        let mut open = self.slots.inner.open.lock().unwrap();
+        if let Some(count) = open.get_mut(&self.host) {
+            *count -= 1;
+            if *count == 0 {
+                open.remove(&self.host);
+            }
+        }
+        drop(open);
+        self.slots.inner.released.notify_waiters();
+    }
+}
+
+/// Per-server transfer queues, the abort handles of running transfers, and
+/// the per-host connection cap shared by every client
+#[derive(Default)]
+pub struct TransferManager {
+    queues: HashMap<String, TransferQueue>,
+    running: HashMap<String, AbortHandle>,
+    host_slots: HostSlots,
+}
+
+impl TransferManager {
+    pub fn host_slots(&self) -> HostSlots {
+        self.host_slots.clone()
+    }
+
+    pub fn set_max_per_host(&mut self, limit: usize) {
+        self.host_slots.set_limit(limit);
+    }
+
+    pub fn queue(&mut self, server_id: &str) -> &mut TransferQueue {
+        self.queues.entry(server_id.to_string()).or_default()
+    }
+
+    /// Transfers for one server, or every server when `server_id` is None
+    pub fn list(&self, server_id: Option<&str>) -> Vec<QueuedTransfer> {
+        self.queues
+            .iter()
+            .filter(|(id, _)| server_id.is_none_or(|server_id| server_id == id.as_str()))
+            .flat_map(|(_, queue)| queue.list())
+            .collect()
+    }
+
+    /// Find which server a transfer id belongs to
+    pub fn server_for(&self, transfer_id: &str) -> Option<String> {
+        self.queues
+            .iter()
+            .find(|(_, queue)| queue.get(transfer_id).is_some())
+            .map(|(server_id, _)| server_id.clone())
+    }
+
+    pub fn set_running(&mut self, transfer_id: &str, handle: AbortHandle) {
+        self.running.insert(transfer_id.to_string(), handle);
+    }
+
+    pub fn clear_running(&mut self, transfer_id: &str) {
+        self.running.remove(transfer_id);
+    }
+
+    /// Abort a running transfer task, if any
+    pub fn abort(&mut self, transfer_id: &str) {
+        if let Some(handle) = self.running.remove(transfer_id) {
+            handle.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn download(id: &str) -> QueuedTransfer {
+        QueuedTransfer::new(
+            id.to_string(),
+            "srv".to_string(),
+            TransferRequest::Download {
+                path: vec![],
+                file_name: format!("{}.bin", id),
+                file_size: 10,
+                download_folder: None,
+                fork_mode: ForkMode::Discard,
+            },
+        )
+    }
+
+    fn queue_with(ids: &[&str]) -> TransferQueue {
+        let mut queue = TransferQueue::default();
+        for id in ids {
+            queue.enqueue(download(id));
+        }
+        queue
+    }
+
+    fn ids(items: &[QueuedTransfer]) -> Vec<&str> {
+        items.iter().map(|item| item.id.as_str()).collect()
+    }
+
+    #[test]
+    fn starts_up_to_max_concurrent() {
+        let mut queue = queue_with(&["a", "b", "c"]);
+        assert_eq!(ids(&queue.start_next()), vec!["a", "b"]);
+        assert!(queue.start_next().is_empty());
+
+        queue.finish("a", Ok(()));
+        assert_eq!(ids(&queue.start_next()), vec!["c"]);
+        assert_eq!(queue.get("a").unwrap().state, TransferState::Completed);
+    }
+
+    #[test]
+    fn pause_and_resume() {
+        let mut queue = queue_with(&["a", "b"]);
+        queue.start_next();
+
+        assert!(queue.pause("a").unwrap());
+        // A late completion report for a paused transfer is ignored
+        queue.finish("a", Err("aborted".to_string()));
+        assert_eq!(queue.get("a").unwrap().state, TransferState::Paused);
+
+        queue.resume("a").unwrap();
+        assert_eq!(ids(&queue.start_next()), vec!["a"]);
+        assert!(queue.get("a").unwrap().started);
+    }
+
+    #[test]
+    fn cancel_reports_whether_running() {
+        let mut queue = queue_with(&["a", "b", "c"]);
+        queue.start_next();
+
+        assert!(queue.cancel("a").unwrap());
+        assert!(!queue.cancel("c").unwrap());
+        assert!(queue.cancel("c").is_err());
+    }
+
+    #[test]
+    fn reorder_moves_listed_items_first() {
+        let mut queue = queue_with(&["a", "b", "c", "d"]);
+        queue.reorder(&["c".to_string(), "a".to_string()]).unwrap();
+        assert_eq!(ids(&queue.list()), vec!["c", "a", "b", "d"]);
+        assert!(queue.reorder(&["missing".to_string()]).is_err());
+
+        queue.set_max_concurrent(1);
+        assert_eq!(ids(&queue.start_next()), vec!["c"]);
+    }
+
+    #[test]
+    fn download_cap_holds_back_downloads_only() {
+        assert_eq!(download_limit_from_error("You are already running too many downloads.", 2), Some(2));
+        assert_eq!(download_limit_from_error("Maximum number of downloads (3) reached", 1), Some(3));
+        assert_eq!(download_limit_from_error("File not found", 2), None);
+
+        let mut queue = queue_with(&["a", "b", "c"]);
+        queue.enqueue(QueuedTransfer::new(
+            "up".to_string(),
+            "srv".to_string(),
+            TransferRequest::Upload {
+                path: vec![],
+                upload: UploadFile::new("up.txt".to_string(), Vec::new()),
+            },
+        ));
+        queue.set_max_concurrent(3);
+        queue.set_download_limit(1);
+        assert_eq!(ids(&queue.start_next()), vec!["a", "up"]);
+        assert_eq!(queue.download_slots(), DownloadSlots { used: 1, limit: Some(1) });
+
+        queue.requeue("a");
+        assert_eq!(queue.get("a").unwrap().state, TransferState::Queued);
+        assert_eq!(ids(&queue.start_next()), vec!["a"]);
+    }
+
+    #[tokio::test]
+    async fn host_slots_cap_connections_per_host() {
+        let slots = HostSlots::new(2);
+        let a = slots.acquire("Example.com").await;
+        let _b = slots.acquire("example.com").await;
+        assert_eq!(slots.open_count("example.com"), 2);
+
+        // Other hosts are unaffected
+        let _c = slots.acquire("other.net").await;
+
+        // A third connection waits until one closes
+        let waiter = tokio::spawn({
+            let slots = slots.clone();
+            async move { slots.acquire("example.com").await }
+        });
+        tokio::task::yield_now().await;
+        assert!(!waiter.is_finished());
+
+        drop(a);
+        let _d = waiter.await.unwrap();
+        assert_eq!(slots.open_count("example.com"), 2);
+    }
+}
@@ -0,0 +1,89 @@
+// Upload name conflicts
+//
+// A server refuses an upload whose name is already taken in the destination
+// folder, and only says so once the transfer has been requested. Asking for
+// the file's info first finds the clash before any data is read or sent, so
+// the user can decide instead: upload under a free name, replace the existing
+// file (when the account may delete it), continue a partial copy, or cancel.
+
+use super::HotlineClient;
+use serde::{Deserialize, Serialize};
+
+/// How many numbered names to try before giving up on finding a free one
+const MAX_RENAME_ATTEMPTS: u32 = 100;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadConflict {
+    pub file_name: String,
+    /// Size of the file already on the server, when it reports one
+    pub existing_size: Option<u64>,
+    /// A free name in the same folder, e.g. "notes 2.txt"
+    pub suggested_name: Option<String>,
+    /// The account may delete the existing file to replace it
+    pub can_overwrite: bool,
+    /// The existing file is smaller than ours, so it may be an interrupted copy
+    pub can_resume: bool,
+}
+
+/// What to do about a conflict; cancelling is just not uploading
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConflictResolution {
+    Rename,
+    Overwrite,
+    Resume,
+}
+
+/// The `attempt`th alternative to `file_name`, numbered before the extension
+pub fn numbered_name(file_name: &str, attempt: u32) -> String {
+    match file_name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => format!("{} {}.{}", stem, attempt, extension),
+        _ => format!("{} {}", file_name, attempt),
+    }
+}
+
+impl HotlineClient {
+    async fn file_exists(&self, path: &[String], file_name: &str) -> bool {
+        // Servers answer GetFileInfo with an error for missing files
+        self.get_file_info(path.to_vec(), file_name.to_string()).await.is_ok()
+    }
+
+    /// Look for a file named `file_name` in `path`; None when the name is free
+    pub async fn check_upload_conflict(&self, path: &[String], file_name: &str, upload_size: u64) -> Option<UploadConflict> {
+        let existing = self.get_file_info(path.to_vec(), file_name.to_string()).await.ok()?;
+        let access = self.get_user_access().await;
+
+        Some(UploadConflict {
+            file_name: file_name.to_string(),
+            existing_size: existing.size,
+            suggested_name: self.free_upload_name(path, file_name).await,
+            can_overwrite: access.delete_file,
+            can_resume: existing.size.is_some_and(|size| size < upload_size),
+        })
+    }
+
+    /// The first numbered variant of `file_name` that isn't taken in `path`
+    pub async fn free_upload_name(&self, path: &[String], file_name: &str) -> Option<String> {
+        for attempt in 2..2 + MAX_RENAME_ATTEMPTS {
+            let candidate = numbered_name(file_name, attempt);
+            if !self.file_exists(path, &candidate).await {
+                return Some(candidate);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_go_before_the_extension() {
+        assert_eq!(numbered_name("notes.txt", 2), "notes 2.txt");
+        assert_eq!(numbered_name("archive.tar.gz", 3), "archive.tar 3.gz");
+        assert_eq!(numbered_name("README", 2), "README 2");
+        assert_eq!(numbered_name(".profile", 2), ".profile 2");
+    }
+}
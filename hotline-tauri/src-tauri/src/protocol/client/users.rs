@@ -1,36 +1,183 @@
 // User management functionality for Hotline client
 
-use super::HotlineClient;
+use super::{HotlineClient, HotlineEvent, RequestFailure};
+use crate::protocol::avatar::{self, AvatarFallback};
 use crate::protocol::constants::{FieldType, TransactionType};
 use crate::protocol::transaction::{Transaction, TransactionField};
-use tokio::io::AsyncWriteExt;
+use crate::protocol::types::{User, UserAccess};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// How long the first user list is waited for before it's left to join events
+const USER_LIST_WAIT: Duration = Duration::from_secs(5);
+
+/// One entry of the online user list
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RosterUser {
+    pub user_id: u16,
+    pub user_name: String,
+    pub icon_id: u16,
+    pub flags: u16,
+    /// Set when the icon has no bundled artwork
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avatar: Option<AvatarFallback>,
+}
+
+impl RosterUser {
+    pub fn new(user_id: u16, user_name: String, icon_id: u16, flags: u16) -> Self {
+        let avatar = avatar::fallback(icon_id, &user_name);
+        Self {
+            user_id,
+            user_name,
+            icon_id,
+            flags,
+            avatar,
+        }
+    }
+}
+
+/// How a fresh user list differs from the roster it replaces
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RosterDiff {
+    pub added: Vec<RosterUser>,
+    pub removed: Vec<u16>,
+    pub changed: Vec<RosterUser>,
+}
+
+impl RosterDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+fn diff_users(current: &HashMap<u16, User>, fresh: &[RosterUser]) -> RosterDiff {
+    let same = |existing: &User, user: &RosterUser| {
+        existing.user_name == user.user_name && existing.icon_id == user.icon_id && existing.flags == user.flags
+    };
+    let mut diff = RosterDiff::default();
+    for user in fresh {
+        match current.get(&user.user_id) {
+            None => diff.added.push(user.clone()),
+            Some(existing) if !same(existing, user) => diff.changed.push(user.clone()),
+            Some(_) => {}
+        }
+    }
+    diff.removed = current
+        .keys()
+        .filter(|id| !fresh.iter().any(|user| user.user_id == **id))
+        .copied()
+        .collect();
+    diff.removed.sort_unstable();
+    diff.added.sort_by_key(|u| u.user_id);
+    diff.changed.sort_by_key(|u| u.user_id);
+    diff
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserInfo {
+    pub user_id: u16,
+    pub user_name: String,
+    /// Formatted info text as provided by the server
+    pub info: String,
+}
+
+/// Account login from a user's info text. Servers list it on a line such as
+/// "Account: admin" or "Login: admin", lines separated by CR or LF.
+pub fn account_from_info(info: &str) -> Option<&str> {
+    info.split(['\r', '\n']).find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        let key = key.trim();
+        (key.eq_ignore_ascii_case("account") || key.eq_ignore_ascii_case("login")).then(|| value.trim())
+    })
+}
 
 impl HotlineClient {
-    pub async fn get_user_list(&self) -> Result<(), String> {
+    /// Users currently online, in user id order
+    pub async fn get_users(&self) -> Vec<User> {
+        let mut users: Vec<User> = self.users.read().await.values().cloned().collect();
+        users.sort_by_key(|u| u.user_id);
+        users
+    }
+
+    /// Nickname of an online user
+    pub async fn user_name(&self, user_id: u16) -> Option<String> {
+        self.users.read().await.get(&user_id).map(|u| u.user_name.clone())
+    }
+
+    /// Request the user list right after login and return it as the starting
+    /// roster, which goes back with the connect result rather than as join
+    /// events. Servers that hold the list back until the agreement is
+    /// accepted don't answer in time; the roster is then empty and the list
+    /// arrives once the agreement is accepted.
+    pub async fn load_user_list(&self) -> Result<Vec<RosterUser>, String> {
         println!("Requesting user list...");
 
         let transaction = Transaction::new(self.next_transaction_id(), TransactionType::GetUserNameList);
-        let encoded = transaction.encode();
+        let reply = match self.try_send_and_wait(transaction, "get user list", USER_LIST_WAIT).await {
+            Ok(reply) if reply.error_code == 0 => reply,
+            Ok(_) | Err(RequestFailure::TimedOut) => {
+                println!("No user list yet, leaving it to join events");
+                return Ok(Vec::new());
+            }
+            Err(RequestFailure::Failed(e)) => return Err(e),
+        };
+
+        let users = Self::roster_from_reply(&reply);
+        println!("User list loaded: {} users", users.len());
+        // Join events may have got in first; only what the list changes in
+        // that roster is news, the rest goes back with the connect result
+        let had_users = !self.users.read().await.is_empty();
+        let diff = self.replace_users(&users).await;
+        if had_users && !diff.is_empty() {
+            let _ = self.event_tx.send(HotlineEvent::UserListSynced(diff));
+        }
+        Ok(users)
+    }
 
-        println!("Sending GetUserNameList transaction...");
-        let mut write_guard = self.write_half.lock().await;
-        let write_stream = write_guard
-            .as_mut()
-            .ok_or("Not connected".to_string())?;
+    /// Re-request the full user list and hand what changed to the event loop
+    /// as one diff, so users that left without a notification get dropped too
+    pub async fn refresh_user_list(&self) -> Result<(), String> {
+        println!("Refreshing user list...");
 
-        write_stream
-            .write_all(&encoded)
-            .await
-            .map_err(|e| format!("Failed to send GetUserNameList: {}", e))?;
+        let users = self.fetch_user_list().await?;
+        println!("User list refreshed: {} users", users.len());
+        let diff = self.replace_users(&users).await;
+        if !diff.is_empty() {
+            let _ = self.event_tx.send(HotlineEvent::UserListSynced(diff));
+        }
+        Ok(())
+    }
 
-        write_stream
-            .flush()
-            .await
-            .map_err(|e| format!("Failed to flush stream: {}", e))?;
+    /// Request the user list and return it instead of going through events
+    pub async fn fetch_user_list(&self) -> Result<Vec<RosterUser>, String> {
+        let transaction = Transaction::new(self.next_transaction_id(), TransactionType::GetUserNameList);
+        let reply = self.send_and_wait(transaction, "refresh user list").await?;
+        Ok(Self::roster_from_reply(&reply))
+    }
 
-        println!("GetUserNameList request sent");
+    fn roster_from_reply(reply: &Transaction) -> Vec<RosterUser> {
+        reply
+            .fields
+            .iter()
+            .filter(|f| f.field_type == FieldType::UserNameWithInfo)
+            .filter_map(|f| Self::parse_user_info(&f.data).ok())
+            .map(|(user_id, user_name, icon_id, flags)| RosterUser::new(user_id, user_name, icon_id, flags))
+            .collect()
+    }
 
-        Ok(())
+    /// Make `users` the roster and return how it differs from the old one
+    async fn replace_users(&self, users: &[RosterUser]) -> RosterDiff {
+        let mut roster = self.users.write().await;
+        let diff = diff_users(&roster, users);
+        *roster = users
+            .iter()
+            .map(|u| (u.user_id, User::new(u.user_id, u.user_name.clone(), u.icon_id, u.flags)))
+            .collect();
+        diff
     }
 
     pub(crate) fn parse_user_info(data: &[u8]) -> Result<(u16, String, u16, u16), String> {
@@ -73,31 +220,71 @@ impl HotlineClient {
             transaction.add_field(TransactionField::from_u16(FieldType::Options, opts));
         }
 
-        let encoded = transaction.encode();
+        self.send_transaction(transaction, "send DisconnectUser").await?;
 
-        let mut write_guard = self.write_half.lock().await;
-        let write_stream = write_guard
-            .as_mut()
-            .ok_or("Not connected".to_string())?;
+        println!("DisconnectUser transaction sent successfully");
 
-        write_stream
-            .write_all(&encoded)
-            .await
-            .map_err(|e| format!("Failed to send DisconnectUser: {}", e))?;
+        Ok(())
+    }
 
-        write_stream
-            .flush()
-            .await
-            .map_err(|e| format!("Failed to flush: {}", e))?;
+    /// Ask the server for the info text it keeps about a connected user
+    /// (address, login time, active transfers). Usually requires admin access.
+    pub async fn get_user_info(&self, user_id: u16) -> Result<UserInfo, String> {
+        println!("Requesting user info for {}", user_id);
 
-        println!("DisconnectUser transaction sent successfully");
+        let mut transaction = Transaction::new(self.next_transaction_id(), TransactionType::GetClientInfoText);
+        transaction.add_field(TransactionField::from_u16(FieldType::UserId, user_id));
 
-        Ok(())
+        let reply = self.send_and_wait(transaction, "get user info").await?;
+
+        let user_name = reply
+            .get_field(FieldType::UserName)
+            .and_then(|f| f.to_string().ok())
+            .unwrap_or_default();
+        let info = reply
+            .get_field(FieldType::Data)
+            .and_then(|f| f.to_string().ok())
+            .unwrap_or_default();
+
+        Ok(UserInfo {
+            user_id,
+            user_name,
+            info,
+        })
     }
 
     /// Get current user access permissions
-    pub async fn get_user_access(&self) -> u64 {
+    pub async fn get_user_access(&self) -> UserAccess {
         let access_guard = self.user_access.lock().await;
         *access_guard
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_account_in_info_text() {
+        let info = "Nickname:  Ghost\rName:      Ghost\rAccount:   ghost\rAddress:   10.0.0.2\r";
+        assert_eq!(account_from_info(info), Some("ghost"));
+        assert_eq!(account_from_info("login: admin\n"), Some("admin"));
+        assert_eq!(account_from_info("Name: x"), None);
+    }
+
+    #[test]
+    fn diff_reports_added_removed_and_changed() {
+        let user = |user_id: u16, user_name: &str, flags: u16| RosterUser::new(user_id, user_name.to_string(), 414, flags);
+        let current: HashMap<u16, User> = [(1, "alice"), (2, "bob"), (3, "carol")]
+            .into_iter()
+            .map(|(user_id, user_name)| (user_id, User::new(user_id, user_name.to_string(), 414, 0)))
+            .collect();
+
+        let diff = diff_users(&current, &[user(1, "alice", 0), user(2, "bob", 2), user(4, "dave", 0)]);
+        assert_eq!(diff.added, vec![user(4, "dave", 0)]);
+        assert_eq!(diff.removed, vec![3]);
+        assert_eq!(diff.changed, vec![user(2, "bob", 2)]);
+
+        assert!(diff_users(&current, &[user(1, "alice", 0), user(2, "bob", 0), user(3, "carol", 0)]).is_empty());
+    }
+}
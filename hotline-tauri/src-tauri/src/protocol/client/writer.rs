@@ -0,0 +1,183 @@
+// Outbound transaction writer
+//
+// After login every transaction goes through a single writer task instead of
+// each caller locking the socket. Frames are queued on one of three lanes and
+// the writer always drains the higher lanes first, so a keep-alive or a chat
+// line never waits behind a burst of file list or news requests. A frame
+// still writing after WRITER_STALL_WARNING is reported as a stall.
+
+use super::lifecycle::{LifecycleNotice, WRITER_STALL_WARNING};
+use super::{BoxedWrite, HotlineEvent};
+use crate::protocol::constants::TransactionType;
+use crate::protocol::i18n::{tr, MessageId};
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::{mpsc, oneshot, Mutex};
+use tokio::task::JoinHandle;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lane {
+    /// Keep-alives, agreement and session housekeeping
+    Control,
+    /// Chat, private messages and broadcasts
+    Interactive,
+    /// Listings, news, file and account requests
+    Bulk,
+}
+
+impl Lane {
+    pub fn for_transaction(kind: TransactionType) -> Self {
+        use TransactionType::*;
+        match kind {
+            Agreed | SetClientUserInfo | ConnectionKeepAlive | Reply | Error => Lane::Control,
+            SendChat | SendInstantMessage | UserBroadcast | DisconnectUser | InviteToNewChat | InviteToChat
+            | RejectChatInvite | JoinChat | LeaveChat | SetChatSubject => Lane::Interactive,
+            _ => Lane::Bulk,
+        }
+    }
+}
+
+struct Frame {
+    data: Vec<u8>,
+    done: oneshot::Sender<Result<(), String>>,
+}
+
+struct LaneReceivers {
+    control: mpsc::UnboundedReceiver<Frame>,
+    interactive: mpsc::UnboundedReceiver<Frame>,
+    bulk: mpsc::UnboundedReceiver<Frame>,
+}
+
+/// Sending side of the lanes; cloned into anything that needs to write
+#[derive(Clone)]
+pub struct OutboundQueue {
+    control: mpsc::UnboundedSender<Frame>,
+    interactive: mpsc::UnboundedSender<Frame>,
+    bulk: mpsc::UnboundedSender<Frame>,
+}
+
+impl OutboundQueue {
+    /// Queue a frame and wait until the writer has flushed it
+    pub async fn send(&self, lane: Lane, data: Vec<u8>) -> Result<(), String> {
+        let (done, result) = oneshot::channel();
+        let sender = match lane {
+            Lane::Control => &self.control,
+            Lane::Interactive => &self.interactive,
+            Lane::Bulk => &self.bulk,
+        };
+        sender
+            .send(Frame { data, done })
+            .map_err(|_| tr(MessageId::NotConnected))?;
+        result.await.map_err(|_| tr(MessageId::NotConnected))?
+    }
+}
+
+/// Start the writer task for `write_half`. The task exits when every queue
+/// handle is dropped or a write fails; a failed write also clears the half
+/// so nothing else tries to use a dead socket.
+pub fn spawn_writer(
+    write_half: Arc<Mutex<Option<BoxedWrite>>>,
+    event_tx: mpsc::UnboundedSender<HotlineEvent>,
+) -> (OutboundQueue, JoinHandle<()>) {
+    let (control_tx, control) = mpsc::unbounded_channel();
+    let (interactive_tx, interactive) = mpsc::unbounded_channel();
+    let (bulk_tx, bulk) = mpsc::unbounded_channel();
+    let queue = OutboundQueue {
+        control: control_tx,
+        interactive: interactive_tx,
+        bulk: bulk_tx,
+    };
+    let task = tokio::spawn(run_writer(write_half, LaneReceivers { control, interactive, bulk }, event_tx));
+    (queue, task)
+}
+
+async fn run_writer(
+    write_half: Arc<Mutex<Option<BoxedWrite>>>,
+    mut lanes: LaneReceivers,
+    event_tx: mpsc::UnboundedSender<HotlineEvent>,
+) {
+    loop {
+        let (lane, frame) = tokio::select! {
+            biased;
+            Some(frame) = lanes.control.recv() => (Lane::Control, frame),
+            Some(frame) = lanes.interactive.recv() => (Lane::Interactive, frame),
+            Some(frame) = lanes.bulk.recv() => (Lane::Bulk, frame),
+            else => break,
+        };
+
+        let write = write_frame(&write_half, &frame.data);
+        tokio::pin!(write);
+        let result = match tokio::time::timeout(WRITER_STALL_WARNING, &mut write).await {
+            Ok(result) => result,
+            Err(_) => {
+                let _ = event_tx.send(HotlineEvent::Lifecycle(LifecycleNotice::WriterStalled {
+                    lane,
+                    waited_secs: WRITER_STALL_WARNING.as_secs(),
+                }));
+                write.await
+            }
+        };
+        let failed = result.is_err();
+        let _ = frame.done.send(result);
+        if failed {
+            break;
+        }
+    }
+    println!("Writer exited");
+}
+
+async fn write_frame(write_half: &Mutex<Option<BoxedWrite>>, data: &[u8]) -> Result<(), String> {
+    let mut write_guard = write_half.lock().await;
+    let write_stream = write_guard
+        .as_mut()
+        .ok_or_else(|| tr(MessageId::NotConnected))?;
+
+    let result = match write_stream.write_all(data).await {
+        Ok(()) => write_stream.flush().await,
+        Err(e) => Err(e),
+    };
+    result.map_err(|e| {
+        write_guard.take();
+        format!("Failed to write to server: {}", e)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncReadExt;
+
+    #[tokio::test]
+    async fn higher_lanes_are_written_first() {
+        let (control_tx, control) = mpsc::unbounded_channel();
+        let (interactive_tx, interactive) = mpsc::unbounded_channel();
+        let (bulk_tx, bulk) = mpsc::unbounded_channel();
+
+        // Queue everything before the writer starts, lowest lane first
+        let mut results = Vec::new();
+        for (sender, data) in [(&bulk_tx, b"B"), (&interactive_tx, b"I"), (&control_tx, b"C")] {
+            let (done, result) = oneshot::channel();
+            sender.send(Frame { data: data.to_vec(), done }).unwrap();
+            results.push(result);
+        }
+        drop((control_tx, interactive_tx, bulk_tx));
+
+        let (client, mut server) = tokio::io::duplex(64);
+        let write_half: Arc<Mutex<Option<BoxedWrite>>> = Arc::new(Mutex::new(Some(Box::new(client))));
+        run_writer(write_half, LaneReceivers { control, interactive, bulk }, mpsc::unbounded_channel().0).await;
+
+        for result in results {
+            assert!(result.await.unwrap().is_ok());
+        }
+        let mut written = [0u8; 3];
+        server.read_exact(&mut written).await.unwrap();
+        assert_eq!(&written, b"CIB");
+    }
+
+    #[test]
+    fn lanes_follow_transaction_type() {
+        assert_eq!(Lane::for_transaction(TransactionType::Agreed), Lane::Control);
+        assert_eq!(Lane::for_transaction(TransactionType::SendChat), Lane::Interactive);
+        assert_eq!(Lane::for_transaction(TransactionType::GetNewsArticleList), Lane::Bulk);
+    }
+}
@@ -15,6 +15,12 @@ pub const DEFAULT_SERVER_PORT: u16 = 5500;
 pub const DEFAULT_TLS_PORT: u16 = 5600;
 pub const DEFAULT_TRACKER_PORT: u16 = 5498;
 
+// Default user info
+pub const DEFAULT_NICKNAME: &str = "guest";
+pub const DEFAULT_USER_ICON: u16 = 191;
+/// Icon assumed for users whose info leaves it out
+pub const FALLBACK_USER_ICON: u16 = 414;
+
 // Transaction types
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u16)]
@@ -61,6 +67,7 @@ pub enum TransactionType {
     NotifyUserDelete = 302,
     GetClientInfoText = 303,
     SetClientUserInfo = 304,
+    ListUsers = 348,
     NewUser = 350,
     DeleteUser = 351,
     GetUser = 352,
@@ -75,6 +82,7 @@ pub enum TransactionType {
     GetNewsArticleData = 400,
     PostNewsArticle = 410,
     DeleteNewsArticle = 411,
+    ConnectionKeepAlive = 500,
     Unknown = 0xFFFF,
 }
 
@@ -123,6 +131,7 @@ impl From<u16> for TransactionType {
             302 => Self::NotifyUserDelete,
             303 => Self::GetClientInfoText,
             304 => Self::SetClientUserInfo,
+            348 => Self::ListUsers,
             350 => Self::NewUser,
             351 => Self::DeleteUser,
             352 => Self::GetUser,
@@ -137,6 +146,7 @@ impl From<u16> for TransactionType {
             400 => Self::GetNewsArticleData,
             410 => Self::PostNewsArticle,
             411 => Self::DeleteNewsArticle,
+            500 => Self::ConnectionKeepAlive,
             _ => Self::Unknown,
         }
     }
@@ -171,9 +181,13 @@ pub enum FieldType {
     VersionNumber = 160,
     CommunityBannerId = 161,
     ServerName = 162,
+    // Client identification sent at login; servers that don't know them ignore them
+    ClientName = 163,
+    ClientVersion = 164,
     FileNameWithInfo = 200,
     FileName = 201,
     FilePath = 202,
+    FileResumeData = 203,
     FileTransferOptions = 204,
     FileTypeString = 205,
     FileCreatorString = 206,
@@ -182,6 +196,7 @@ pub enum FieldType {
     FileModifyDate = 209,
     FileComment = 210,
     FileNewName = 211,
+    FileNewPath = 212,
     FileType = 213,
     QuotingMessage = 214,
     AutomaticResponse = 215,
@@ -235,9 +250,12 @@ impl From<u16> for FieldType {
             160 => Self::VersionNumber,
             161 => Self::CommunityBannerId,
             162 => Self::ServerName,
+            163 => Self::ClientName,
+            164 => Self::ClientVersion,
             200 => Self::FileNameWithInfo,
             201 => Self::FileName,
             202 => Self::FilePath,
+            203 => Self::FileResumeData,
             204 => Self::FileTransferOptions,
             205 => Self::FileTypeString,
             206 => Self::FileCreatorString,
@@ -246,6 +264,7 @@ impl From<u16> for FieldType {
             209 => Self::FileModifyDate,
             210 => Self::FileComment,
             211 => Self::FileNewName,
+            212 => Self::FileNewPath,
             213 => Self::FileType,
             214 => Self::QuotingMessage,
             215 => Self::AutomaticResponse,
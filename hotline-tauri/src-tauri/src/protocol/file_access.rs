@@ -0,0 +1,114 @@
+// Per-path file capabilities
+//
+// The access bitmap says what an account may do in general, but for uploads
+// the folder matters too: without "upload anywhere" a server only accepts
+// files inside upload folders and drop boxes, which it recognizes by name
+// ("Uploads", "Drop Box") anywhere along the path. Combining the two gives the
+// actions the server will actually accept for a listed folder and its items,
+// so the UI can offer exactly those.
+
+use super::types::UserAccess;
+use serde::Serialize;
+
+/// What the account may do inside one folder of the file tree
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FolderCapabilities {
+    pub can_upload: bool,
+    pub can_create_folder: bool,
+    /// Some item in the folder, file or folder, may be deleted
+    pub can_delete: bool,
+    /// Some item in the folder, file or folder, may be renamed
+    pub can_rename: bool,
+}
+
+/// What the account may do with one listed file or folder
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemCapabilities {
+    pub can_delete: bool,
+    pub can_rename: bool,
+}
+
+/// Upload folders and drop boxes, by the names servers look for
+pub fn is_upload_folder(name: &str) -> bool {
+    let name = name.to_lowercase();
+    name.contains("upload") || name.contains("drop box")
+}
+
+impl FolderCapabilities {
+    pub fn for_path(access: &UserAccess, path: &[String]) -> Self {
+        let in_upload_folder = path.iter().any(|name| is_upload_folder(name));
+        Self {
+            can_upload: access.upload_file && (access.upload_anywhere || in_upload_folder),
+            can_create_folder: access.create_folder,
+            can_delete: access.delete_file || access.delete_folder,
+            can_rename: access.rename_file || access.rename_folder,
+        }
+    }
+}
+
+impl ItemCapabilities {
+    pub fn for_item(access: &UserAccess, is_folder: bool) -> Self {
+        if is_folder {
+            Self {
+                can_delete: access.delete_folder,
+                can_rename: access.rename_folder,
+            }
+        } else {
+            Self {
+                can_delete: access.delete_file,
+                can_rename: access.rename_file,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn uploads_need_an_upload_folder_without_upload_anywhere() {
+        let access = UserAccess {
+            upload_file: true,
+            ..Default::default()
+        };
+        assert!(!FolderCapabilities::for_path(&access, &path(&["Files"])).can_upload);
+        assert!(FolderCapabilities::for_path(&access, &path(&["Uploads"])).can_upload);
+        assert!(FolderCapabilities::for_path(&access, &path(&["Drop Box", "Mine"])).can_upload);
+
+        let anywhere = UserAccess {
+            upload_anywhere: true,
+            ..access
+        };
+        assert!(FolderCapabilities::for_path(&anywhere, &[]).can_upload);
+
+        // Upload folders don't help without the upload bit itself
+        assert!(!FolderCapabilities::for_path(&UserAccess::default(), &path(&["Uploads"])).can_upload);
+    }
+
+    #[test]
+    fn item_capabilities_follow_file_or_folder_bits() {
+        let access = UserAccess {
+            delete_file: true,
+            rename_folder: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            ItemCapabilities::for_item(&access, false),
+            ItemCapabilities { can_delete: true, can_rename: false }
+        );
+        assert_eq!(
+            ItemCapabilities::for_item(&access, true),
+            ItemCapabilities { can_delete: false, can_rename: true }
+        );
+
+        let folder = FolderCapabilities::for_path(&access, &[]);
+        assert!(folder.can_delete && folder.can_rename && !folder.can_create_folder);
+    }
+}
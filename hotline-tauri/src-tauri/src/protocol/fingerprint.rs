@@ -0,0 +1,123 @@
+// Server software fingerprinting
+//
+// Servers don't announce their implementation, so we infer it from what the
+// login reply looks like: the VersionNumber value, which optional fields are
+// present, and how banner information is advertised. The result selects the
+// quirks set (see quirks.rs) and is shown to the user as serverSoftware.
+//
+// These are heuristics; anything that doesn't match a known shape falls back
+// to the version-number classification.
+
+use super::constants::FieldType;
+use super::quirks::ServerSoftware;
+use super::transaction::Transaction;
+
+/// The parts of a login reply that differ between server implementations
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LoginFingerprint {
+    pub version: Option<u16>,
+    pub has_server_name: bool,
+    /// Raw CommunityBannerId field, if present
+    pub community_banner_id: Option<Vec<u8>>,
+    /// Login reply advertises a banner type or URL up front
+    pub advertises_banner: bool,
+    pub field_count: usize,
+}
+
+impl LoginFingerprint {
+    pub fn from_reply(reply: &Transaction) -> Self {
+        Self {
+            version: reply
+                .get_field(FieldType::VersionNumber)
+                .and_then(|f| f.to_u16().ok()),
+            has_server_name: reply.get_field(FieldType::ServerName).is_some(),
+            community_banner_id: reply
+                .get_field(FieldType::CommunityBannerId)
+                .map(|f| f.data.clone()),
+            advertises_banner: reply.get_field(FieldType::ServerBannerType).is_some()
+                || reply.get_field(FieldType::ServerBannerUrl).is_some(),
+            field_count: reply.fields.len(),
+        }
+    }
+
+    pub fn classify(&self) -> ServerSoftware {
+        // Hotline 1.2.x servers reply to Login with no fields at all
+        if self.version.is_none() && self.field_count == 0 {
+            return ServerSoftware::Hotline12;
+        }
+
+        // mobius always replies with exactly Version(190), a two-byte zero
+        // CommunityBannerId and ServerName
+        if self.version == Some(190)
+            && self.has_server_name
+            && self.community_banner_id.as_deref() == Some(&[0, 0])
+            && self.field_count == 3
+        {
+            return ServerSoftware::Mobius;
+        }
+
+        // synHL announces its banner (type/URL) in the login reply
+        if self.advertises_banner {
+            return ServerSoftware::SynHl;
+        }
+
+        match self.version {
+            // Version numbers past the 1.9 range come from third-party servers
+            Some(v) if v >= 200 => ServerSoftware::HlServerClone,
+            version => ServerSoftware::from_version(version),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::constants::TransactionType;
+    use crate::protocol::transaction::TransactionField;
+
+    fn reply(fields: Vec<TransactionField>) -> Transaction {
+        let mut transaction = Transaction::new(1, TransactionType::Reply);
+        transaction.is_reply = 1;
+        for field in fields {
+            transaction.add_field(field);
+        }
+        transaction
+    }
+
+    #[test]
+    fn identifies_mobius() {
+        let reply = reply(vec![
+            TransactionField::from_u16(FieldType::VersionNumber, 190),
+            TransactionField::new(FieldType::CommunityBannerId, vec![0, 0]),
+            TransactionField::from_string(FieldType::ServerName, "Mobius Server"),
+        ]);
+        assert_eq!(LoginFingerprint::from_reply(&reply).classify(), ServerSoftware::Mobius);
+    }
+
+    #[test]
+    fn identifies_classic_servers() {
+        assert_eq!(LoginFingerprint::from_reply(&reply(vec![])).classify(), ServerSoftware::Hotline12);
+
+        let classic = reply(vec![
+            TransactionField::from_u16(FieldType::VersionNumber, 197),
+            TransactionField::new(FieldType::CommunityBannerId, vec![0, 0, 0, 1]),
+            TransactionField::from_string(FieldType::ServerName, "Classic"),
+        ]);
+        assert_eq!(LoginFingerprint::from_reply(&classic).classify(), ServerSoftware::Hotline19);
+
+        let v185 = reply(vec![TransactionField::from_u16(FieldType::VersionNumber, 185)]);
+        assert_eq!(LoginFingerprint::from_reply(&v185).classify(), ServerSoftware::Hotline18);
+    }
+
+    #[test]
+    fn identifies_banner_advertising_and_clone_servers() {
+        let synhl = reply(vec![
+            TransactionField::from_u16(FieldType::VersionNumber, 190),
+            TransactionField::from_string(FieldType::ServerBannerUrl, "http://example.com/banner.gif"),
+        ]);
+        assert_eq!(LoginFingerprint::from_reply(&synhl).classify(), ServerSoftware::SynHl);
+
+        let clone = reply(vec![TransactionField::from_u16(FieldType::VersionNumber, 255)]);
+        assert_eq!(LoginFingerprint::from_reply(&clone).classify(), ServerSoftware::HlServerClone);
+    }
+}
@@ -0,0 +1,132 @@
+// Golden tests: feed captured wire data from tests/fixtures through the
+// parsers and compare against the expected structured output.
+
+use super::client::instant_message::{instant_message_event, legacy_text, InstantMessageKind};
+use super::client::requests::{Request, SendInstantMessageRequest};
+use super::client::{HotlineClient, HotlineEvent};
+use super::constants::FieldType;
+use super::fingerprint::LoginFingerprint;
+use super::tracker::TrackerClient;
+use super::transaction::Transaction;
+use serde_json::{json, Value};
+
+macro_rules! fixture {
+    ($name:literal) => {
+        include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/", $name))
+    };
+}
+
+macro_rules! expected {
+    ($name:literal) => {
+        serde_json::from_str::<Value>(include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/",
+            $name
+        )))
+        .expect("expected output is valid JSON")
+    };
+}
+
+fn fields(transaction: &Transaction, field_type: FieldType) -> impl Iterator<Item = &[u8]> {
+    transaction
+        .fields
+        .iter()
+        .filter(move |f| f.field_type == field_type)
+        .map(|f| f.data.as_slice())
+}
+
+#[test]
+fn login_reply() {
+    let reply = Transaction::decode(fixture!("login_reply.bin")).unwrap();
+    assert_eq!(reply.is_reply, 1);
+    assert_eq!(reply.error_code, 0);
+
+    let fingerprint = LoginFingerprint::from_reply(&reply);
+    let actual = json!({
+        "version": fingerprint.version,
+        "serverName": reply.get_field(FieldType::ServerName).unwrap().to_string().unwrap(),
+        "serverSoftware": fingerprint.classify(),
+    });
+    assert_eq!(actual, expected!("login_reply.json"));
+}
+
+#[test]
+fn user_list_reply() {
+    let reply = Transaction::decode(fixture!("user_list_reply.bin")).unwrap();
+    let users: Vec<Value> = fields(&reply, FieldType::UserNameWithInfo)
+        .map(|data| {
+            let (id, name, icon, flags) = HotlineClient::parse_user_info(data).unwrap();
+            json!({ "id": id, "name": name, "icon": icon, "flags": flags })
+        })
+        .collect();
+    assert_eq!(Value::Array(users), expected!("user_list_reply.json"));
+}
+
+#[test]
+fn file_list_reply() {
+    let reply = Transaction::decode(fixture!("file_list_reply.bin")).unwrap();
+    let files: Vec<Value> = fields(&reply, FieldType::FileNameWithInfo)
+        .map(|data| {
+            let file = HotlineClient::parse_file_info(data).unwrap();
+            json!({
+                "name": file.name,
+                "size": file.size,
+                "isFolder": file.is_folder,
+                "fileType": file.file_type,
+                "creator": file.creator,
+            })
+        })
+        .collect();
+    assert_eq!(Value::Array(files), expected!("file_list_reply.json"));
+}
+
+#[test]
+fn news_categories_reply() {
+    let reply = Transaction::decode(fixture!("news_categories_reply.bin")).unwrap();
+    let parent = vec!["News".to_string()];
+    let categories: Vec<_> = fields(&reply, FieldType::NewsCategoryListData15)
+        .map(|data| HotlineClient::parse_news_category(data, &parent).unwrap())
+        .collect();
+    assert_eq!(serde_json::to_value(categories).unwrap(), expected!("news_categories_reply.json"));
+}
+
+#[test]
+fn news_articles_reply() {
+    let reply = Transaction::decode(fixture!("news_articles_reply.bin")).unwrap();
+    let data = reply.get_field(FieldType::NewsArticleListData).unwrap();
+    let parent = vec!["News".to_string(), "General".to_string()];
+    let articles = HotlineClient::parse_news_article_list(&data.data, &parent).unwrap();
+    assert_eq!(serde_json::to_value(articles).unwrap(), expected!("news_articles_reply.json"));
+}
+
+#[tokio::test]
+async fn tracker_listing() {
+    let mut stream: &[u8] = fixture!("tracker_listing.bin");
+    let servers = TrackerClient::read_server_list(&mut stream).await.unwrap();
+    assert_eq!(serde_json::to_value(servers).unwrap(), expected!("tracker_listing.json"));
+}
+
+#[test]
+fn instant_message_from_a_vintage_client() {
+    let message = Transaction::decode(fixture!("instant_message_legacy.bin")).unwrap();
+    assert!(message.get_field(FieldType::Options).is_none());
+
+    let actual = match instant_message_event(&message) {
+        Some(HotlineEvent::PrivateMessage { user_id, user_name, message, quoting }) => json!({
+            "event": "privateMessage",
+            "userId": user_id,
+            "userName": user_name,
+            "message": message,
+            "quoting": quoting,
+        }),
+        other => panic!("unexpected event: {:?}", other),
+    };
+    assert_eq!(actual, expected!("instant_message_legacy.json"));
+}
+
+#[test]
+fn instant_message_to_a_vintage_server() {
+    let text = legacy_text(InstantMessageKind::Message, "Yes, one moment\nbrb", Some("Still there?")).unwrap();
+    let request = SendInstantMessageRequest { user_id: 7, kind: None, message: &text, quoting: None };
+    assert_eq!(request.transaction(1).encode(), fixture!("instant_message_legacy_sent.bin"));
+}
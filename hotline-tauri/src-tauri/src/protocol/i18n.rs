@@ -0,0 +1,227 @@
+// Localized user-facing error messages
+//
+// Errors that reach the UI are looked up here by MessageId instead of being
+// hardcoded English at each call site. The active locale is process-wide and
+// set from the frontend's language setting via the set_locale command.
+// Templates use {name} placeholders filled by tr_args.
+
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU8, Ordering};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[repr(u8)]
+pub enum Locale {
+    En = 0,
+    De = 1,
+    Fr = 2,
+    Es = 3,
+    Ja = 4,
+}
+
+impl Locale {
+    /// Parse a BCP 47 style tag ("de", "fr-CA", "ja_JP"). Unknown languages return None.
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let language = tag
+            .split(['-', '_'])
+            .next()
+            .unwrap_or("")
+            .to_ascii_lowercase();
+        match language.as_str() {
+            "en" => Some(Locale::En),
+            "de" => Some(Locale::De),
+            "fr" => Some(Locale::Fr),
+            "es" => Some(Locale::Es),
+            "ja" => Some(Locale::Ja),
+            _ => None,
+        }
+    }
+
+    fn from_u8(value: u8) -> Self {
+        match value {
+            1 => Locale::De,
+            2 => Locale::Fr,
+            3 => Locale::Es,
+            4 => Locale::Ja,
+            _ => Locale::En,
+        }
+    }
+}
+
+static CURRENT_LOCALE: AtomicU8 = AtomicU8::new(Locale::En as u8);
+
+pub fn set_locale(locale: Locale) {
+    CURRENT_LOCALE.store(locale as u8, Ordering::Relaxed);
+}
+
+pub fn current_locale() -> Locale {
+    Locale::from_u8(CURRENT_LOCALE.load(Ordering::Relaxed))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageId {
+    NotConnected,
+    ServerNotConnected,
+    ChannelClosed,
+    Timeout,
+    /// {code}
+    ServerErrorCode,
+    /// {reason}
+    LoginFailed,
+    LoginRejected,
+    ServerFull,
+    Banned,
+    NewsNotSupported,
+    MessageBoardDisabled,
+    NewsDisabled,
+    /// Missing access privilege for the requested action
+    NotPermitted,
+    ServerInfoUnavailable,
+    CannotConnectToTracker,
+    Cancelled,
+    AlreadyLoggedIn,
+    /// No connection to the server may disconnect the other session
+    TakeoverNotPermitted,
+    TakeoverSessionNotFound,
+}
+
+fn template(locale: Locale, id: MessageId) -> &'static str {
+    use Locale::*;
+    use MessageId::*;
+
+    match (locale, id) {
+        (En, NotConnected) => "Not connected",
+        (En, ServerNotConnected) => "Server not connected",
+        (En, ChannelClosed) => "Connection closed while waiting for a reply",
+        (En, Timeout) => "Timed out waiting for the server",
+        (En, ServerErrorCode) => "Server returned error code {code}",
+        (En, LoginFailed) => "Login failed: {reason}",
+        (En, LoginRejected) => "Invalid login credentials or server rejected login",
+        (En, ServerFull) => "Server is full",
+        (En, Banned) => "Banned from server",
+        (En, NewsNotSupported) => "News is not supported on this server",
+        (En, MessageBoardDisabled) => "The message board is disabled on this server",
+        (En, NewsDisabled) => "News is disabled on this server",
+        (En, NotPermitted) => "You don't have permission to do that on this server",
+        (En, ServerInfoUnavailable) => "Server info not available",
+        (En, CannotConnectToTracker) => "Cannot connect to tracker. Trackers are used to browse servers, not to connect directly.",
+        (En, Cancelled) => "Operation cancelled",
+        (En, AlreadyLoggedIn) => "This account is already logged in from another session",
+        (En, TakeoverNotPermitted) => "Can't take over the other session: no connection to this server has permission to disconnect users",
+        (En, TakeoverSessionNotFound) => "Can't take over the other session: it was not found in the user list",
+
+        (De, NotConnected) => "Nicht verbunden",
+        (De, ServerNotConnected) => "Server nicht verbunden",
+        (De, ChannelClosed) => "Verbindung wurde beim Warten auf eine Antwort geschlossen",
+        (De, Timeout) => "Zeitüberschreitung beim Warten auf den Server",
+        (De, ServerErrorCode) => "Der Server hat Fehlercode {code} zurückgegeben",
+        (De, LoginFailed) => "Anmeldung fehlgeschlagen: {reason}",
+        (De, LoginRejected) => "Ungültige Anmeldedaten oder Anmeldung vom Server abgelehnt",
+        (De, ServerFull) => "Der Server ist voll",
+        (De, Banned) => "Vom Server gesperrt",
+        (De, NewsNotSupported) => "Dieser Server unterstützt keine News",
+        (De, MessageBoardDisabled) => "Das Message Board ist auf diesem Server deaktiviert",
+        (De, NewsDisabled) => "News sind auf diesem Server deaktiviert",
+        (De, NotPermitted) => "Sie haben auf diesem Server keine Berechtigung dafür",
+        (De, ServerInfoUnavailable) => "Serverinformationen nicht verfügbar",
+        (De, CannotConnectToTracker) => "Verbindung zu einem Tracker nicht möglich. Tracker dienen zum Durchsuchen von Servern, nicht zur direkten Verbindung.",
+        (De, Cancelled) => "Vorgang abgebrochen",
+        (De, AlreadyLoggedIn) => "Dieses Konto ist bereits in einer anderen Sitzung angemeldet",
+        (De, TakeoverNotPermitted) => "Die andere Sitzung kann nicht übernommen werden: Keine Verbindung zu diesem Server darf Benutzer trennen",
+        (De, TakeoverSessionNotFound) => "Die andere Sitzung kann nicht übernommen werden: Sie wurde in der Benutzerliste nicht gefunden",
+
+        (Fr, NotConnected) => "Non connecté",
+        (Fr, ServerNotConnected) => "Serveur non connecté",
+        (Fr, ChannelClosed) => "Connexion fermée en attendant une réponse",
+        (Fr, Timeout) => "Délai d'attente du serveur dépassé",
+        (Fr, ServerErrorCode) => "Le serveur a renvoyé le code d'erreur {code}",
+        (Fr, LoginFailed) => "Échec de la connexion : {reason}",
+        (Fr, LoginRejected) => "Identifiants invalides ou connexion refusée par le serveur",
+        (Fr, ServerFull) => "Le serveur est plein",
+        (Fr, Banned) => "Banni du serveur",
+        (Fr, NewsNotSupported) => "Les news ne sont pas prises en charge par ce serveur",
+        (Fr, MessageBoardDisabled) => "Le tableau de messages est désactivé sur ce serveur",
+        (Fr, NewsDisabled) => "Les news sont désactivées sur ce serveur",
+        (Fr, NotPermitted) => "Vous n'avez pas l'autorisation de faire cela sur ce serveur",
+        (Fr, ServerInfoUnavailable) => "Informations du serveur indisponibles",
+        (Fr, CannotConnectToTracker) => "Impossible de se connecter à un tracker. Les trackers servent à parcourir les serveurs, pas à s'y connecter directement.",
+        (Fr, Cancelled) => "Opération annulée",
+        (Fr, AlreadyLoggedIn) => "Ce compte est déjà connecté dans une autre session",
+        (Fr, TakeoverNotPermitted) => "Impossible de reprendre l'autre session : aucune connexion à ce serveur n'a le droit de déconnecter des utilisateurs",
+        (Fr, TakeoverSessionNotFound) => "Impossible de reprendre l'autre session : elle est introuvable dans la liste des utilisateurs",
+
+        (Es, NotConnected) => "No conectado",
+        (Es, ServerNotConnected) => "Servidor no conectado",
+        (Es, ChannelClosed) => "La conexión se cerró mientras se esperaba una respuesta",
+        (Es, Timeout) => "Tiempo de espera agotado esperando al servidor",
+        (Es, ServerErrorCode) => "El servidor devolvió el código de error {code}",
+        (Es, LoginFailed) => "Error al iniciar sesión: {reason}",
+        (Es, LoginRejected) => "Credenciales no válidas o el servidor rechazó el inicio de sesión",
+        (Es, ServerFull) => "El servidor está lleno",
+        (Es, Banned) => "Expulsado del servidor",
+        (Es, NewsNotSupported) => "Este servidor no admite noticias",
+        (Es, MessageBoardDisabled) => "El tablón de mensajes está desactivado en este servidor",
+        (Es, NewsDisabled) => "Las noticias están desactivadas en este servidor",
+        (Es, NotPermitted) => "No tienes permiso para hacer eso en este servidor",
+        (Es, ServerInfoUnavailable) => "Información del servidor no disponible",
+        (Es, CannotConnectToTracker) => "No se puede conectar a un tracker. Los trackers sirven para explorar servidores, no para conectarse directamente.",
+        (Es, Cancelled) => "Operación cancelada",
+        (Es, AlreadyLoggedIn) => "Esta cuenta ya tiene una sesión iniciada en otro lugar",
+        (Es, TakeoverNotPermitted) => "No se puede tomar el control de la otra sesión: ninguna conexión a este servidor tiene permiso para desconectar usuarios",
+        (Es, TakeoverSessionNotFound) => "No se puede tomar el control de la otra sesión: no aparece en la lista de usuarios",
+
+        (Ja, NotConnected) => "接続されていません",
+        (Ja, ServerNotConnected) => "サーバーに接続されていません",
+        (Ja, ChannelClosed) => "応答待ちの間に接続が閉じられました",
+        (Ja, Timeout) => "サーバーの応答がタイムアウトしました",
+        (Ja, ServerErrorCode) => "サーバーがエラーコード {code} を返しました",
+        (Ja, LoginFailed) => "ログインに失敗しました: {reason}",
+        (Ja, LoginRejected) => "ログイン情報が無効か、サーバーがログインを拒否しました",
+        (Ja, ServerFull) => "サーバーが満員です",
+        (Ja, Banned) => "サーバーから追放されています",
+        (Ja, NewsNotSupported) => "このサーバーはニュースに対応していません",
+        (Ja, MessageBoardDisabled) => "このサーバーでは掲示板が無効になっています",
+        (Ja, NewsDisabled) => "このサーバーではニュースが無効になっています",
+        (Ja, NotPermitted) => "このサーバーではその操作を行う権限がありません",
+        (Ja, ServerInfoUnavailable) => "サーバー情報を取得できません",
+        (Ja, CannotConnectToTracker) => "トラッカーには接続できません。トラッカーはサーバーを探すためのもので、直接接続するものではありません。",
+        (Ja, Cancelled) => "操作はキャンセルされました",
+        (Ja, AlreadyLoggedIn) => "このアカウントは別のセッションで既にログインしています",
+        (Ja, TakeoverNotPermitted) => "別のセッションを引き継げません: このサーバーへのどの接続にもユーザーを切断する権限がありません",
+        (Ja, TakeoverSessionNotFound) => "別のセッションを引き継げません: ユーザー一覧に見つかりません",
+    }
+}
+
+/// Message in the current locale
+pub fn tr(id: MessageId) -> String {
+    template(current_locale(), id).to_string()
+}
+
+/// Message in the current locale with {name} placeholders substituted
+pub fn tr_args(id: MessageId, args: &[(&str, &dyn std::fmt::Display)]) -> String {
+    let mut message = template(current_locale(), id).to_string();
+    for (name, value) in args {
+        message = message.replace(&format!("{{{}}}", name), &value.to_string());
+    }
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_locale_tags() {
+        assert_eq!(Locale::from_tag("de-DE"), Some(Locale::De));
+        assert_eq!(Locale::from_tag("FR"), Some(Locale::Fr));
+        assert_eq!(Locale::from_tag("ja_JP"), Some(Locale::Ja));
+        assert_eq!(Locale::from_tag("xx"), None);
+    }
+
+    #[test]
+    fn substitutes_placeholders() {
+        let message = template(Locale::En, MessageId::ServerErrorCode).replace("{code}", "7");
+        assert_eq!(message, "Server returned error code 7");
+        assert!(template(Locale::De, MessageId::LoginFailed).contains("{reason}"));
+    }
+}
@@ -1,10 +1,22 @@
 // Hotline protocol implementation
 
+pub mod address;
+pub mod avatar;
+pub mod cancel;
 pub mod client;
 pub mod constants;
+pub mod file_access;
+pub mod fingerprint;
+#[cfg(test)]
+mod golden_tests;
+pub mod i18n;
+pub mod quirks;
+pub mod sanitize_path;
+pub mod server;
 pub mod transaction;
 pub mod types;
 pub mod tracker;
+pub mod tracker_registration;
 
 /// Format `address:port` for use with `TcpStream::connect`.
 /// IPv6 literals must be wrapped in brackets (e.g. `[::1]:5493`) so the parser can distinguish
@@ -0,0 +1,203 @@
+// Server compatibility quirks
+//
+// Server implementations disagree on a handful of protocol details. Instead of
+// special-casing them inline, the client looks up a ServerQuirks set for the
+// detected server software once at login and consults its flags.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ServerSoftware {
+    #[default]
+    Unknown,
+    /// Hotline 1.2.x and earlier: no threaded news, no agreement fields
+    Hotline12,
+    /// Hotline 1.5 - 1.8.x
+    Hotline18,
+    /// Hotline 1.9.x
+    Hotline19,
+    Mobius,
+    SynHl,
+    /// Third-party servers modelled on the classic HLServer codebase
+    HlServerClone,
+}
+
+impl ServerSoftware {
+    /// Coarse classification from the VersionNumber field of the login reply
+    pub fn from_version(version: Option<u16>) -> Self {
+        match version {
+            None => ServerSoftware::Unknown,
+            Some(v) if v < 150 => ServerSoftware::Hotline12,
+            Some(v) if v < 190 => ServerSoftware::Hotline18,
+            Some(v) if v < 200 => ServerSoftware::Hotline19,
+            Some(_) => ServerSoftware::Unknown,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerQuirks {
+    /// Agreed (121) carries UserName, UserIconId and Options. Servers that
+    /// predate the field-bearing Agreed ignore it; mobius won't finish login without them.
+    pub agreed_requires_user_info: bool,
+    /// The server only finishes sign-in after an explicit GetUserNameList
+    /// following Agreed
+    pub user_list_after_agreement: bool,
+    /// Threaded news (GetNewsCategoryList and friends) is available
+    pub threaded_news: bool,
+    /// Seconds between keep-alive requests
+    pub keepalive_interval_secs: u64,
+    /// ConnectionKeepAlive (500) is understood; otherwise GetUserNameList
+    /// doubles as the keep-alive
+    pub connection_keepalive: bool,
+    /// SendInstantMessage takes Options and QuotingMessage. Hotline 1.2
+    /// servers relay only UserId and Data, to clients that know nothing else.
+    pub instant_message_options: bool,
+    /// SendInstantMessage gets a reply, an error when the recipient has left
+    pub acknowledges_instant_messages: bool,
+}
+
+impl Default for ServerQuirks {
+    /// Conservative behavior for servers we couldn't identify
+    fn default() -> Self {
+        Self {
+            agreed_requires_user_info: true,
+            user_list_after_agreement: true,
+            threaded_news: true,
+            keepalive_interval_secs: 180,
+            connection_keepalive: false,
+            instant_message_options: true,
+            acknowledges_instant_messages: false,
+        }
+    }
+}
+
+/// First server version that answers ConnectionKeepAlive
+const CONNECTION_KEEPALIVE_MIN_VERSION: u16 = 185;
+
+impl ServerQuirks {
+    /// Adjust for the VersionNumber the server reported at login
+    pub fn with_version(mut self, version: Option<u16>) -> Self {
+        self.connection_keepalive = version.is_some_and(|v| v >= CONNECTION_KEEPALIVE_MIN_VERSION);
+        self
+    }
+
+    pub fn for_software(software: ServerSoftware) -> Self {
+        let defaults = Self::default();
+        match software {
+            ServerSoftware::Hotline12 => Self {
+                agreed_requires_user_info: false,
+                threaded_news: false,
+                instant_message_options: false,
+                ..defaults
+            },
+            ServerSoftware::Mobius => Self {
+                // mobius disconnects clients idle longer than its configured timeout
+                keepalive_interval_secs: 120,
+                acknowledges_instant_messages: true,
+                ..defaults
+            },
+            ServerSoftware::Hotline18 | ServerSoftware::Hotline19 => Self {
+                acknowledges_instant_messages: true,
+                ..defaults
+            },
+            ServerSoftware::SynHl | ServerSoftware::HlServerClone | ServerSoftware::Unknown => defaults,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Feature {
+    MessageBoard,
+    News,
+}
+
+/// Subsystems still available this session. Login replies don't say which
+/// subsystems a server has switched off (field 113 there carries instant
+/// message options), so everything starts on, and a feature is only marked
+/// off once the server refuses a request for it that our access allows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerCapabilities {
+    pub message_board: bool,
+    pub news: bool,
+    /// Downloads one user may run at once, once the server refused one over it
+    #[serde(default)]
+    pub max_downloads: Option<usize>,
+}
+
+impl Default for ServerCapabilities {
+    fn default() -> Self {
+        Self {
+            message_board: true,
+            news: true,
+            max_downloads: None,
+        }
+    }
+}
+
+impl ServerCapabilities {
+    pub fn supports(&self, feature: Feature) -> bool {
+        match feature {
+            Feature::MessageBoard => self.message_board,
+            Feature::News => self.news,
+        }
+    }
+
+    pub fn disable(&mut self, feature: Feature) {
+        match feature {
+            Feature::MessageBoard => self.message_board = false,
+            Feature::News => self.news = false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_by_version_number() {
+        assert_eq!(ServerSoftware::from_version(Some(123)), ServerSoftware::Hotline12);
+        assert_eq!(ServerSoftware::from_version(Some(185)), ServerSoftware::Hotline18);
+        assert_eq!(ServerSoftware::from_version(Some(197)), ServerSoftware::Hotline19);
+        assert_eq!(ServerSoftware::from_version(None), ServerSoftware::Unknown);
+    }
+
+    #[test]
+    fn old_servers_lack_threaded_news() {
+        assert!(!ServerQuirks::for_software(ServerSoftware::Hotline12).threaded_news);
+        assert!(ServerQuirks::for_software(ServerSoftware::Unknown).threaded_news);
+        assert!(ServerQuirks::for_software(ServerSoftware::Mobius).agreed_requires_user_info);
+    }
+
+    #[test]
+    fn only_old_servers_send_plain_instant_messages() {
+        let old = ServerQuirks::for_software(ServerSoftware::Hotline12);
+        assert!(!old.instant_message_options && !old.acknowledges_instant_messages);
+        assert!(ServerQuirks::for_software(ServerSoftware::Hotline19).acknowledges_instant_messages);
+        // Unidentified servers aren't waited on, in case they never answer
+        assert!(!ServerQuirks::default().acknowledges_instant_messages);
+    }
+
+    #[test]
+    fn keepalive_transaction_follows_version() {
+        let quirks = ServerQuirks::for_software(ServerSoftware::Hotline18);
+        assert!(!quirks.clone().with_version(Some(151)).connection_keepalive);
+        assert!(quirks.clone().with_version(Some(185)).connection_keepalive);
+        assert!(quirks.clone().with_version(Some(197)).connection_keepalive);
+        assert!(!quirks.with_version(None).connection_keepalive);
+    }
+
+    #[test]
+    fn features_stay_on_until_switched_off() {
+        let mut capabilities = ServerCapabilities::default();
+        assert!(capabilities.supports(Feature::MessageBoard) && capabilities.supports(Feature::News));
+
+        capabilities.disable(Feature::News);
+        assert!(capabilities.supports(Feature::MessageBoard));
+        assert!(!capabilities.supports(Feature::News));
+    }
+}
@@ -0,0 +1,145 @@
+// Local filesystem names for server-supplied files
+//
+// Names come from the server and have to be safe on every platform we ship:
+// no separators or reserved characters, no `.`/`..` escapes, no Windows device
+// names (CON, NUL, COM1...), and no trailing dots or spaces, which Windows
+// silently strips and which can make two different names collide.
+
+use std::path::{Component, Path, PathBuf};
+
+/// Longest component most filesystems accept, in bytes
+const MAX_COMPONENT_BYTES: usize = 255;
+
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Windows treats `NUL`, `nul.txt` and `Nul.tar.gz` all as the device
+fn is_reserved_name(name: &str) -> bool {
+    let stem = name.split('.').next().unwrap_or(name).trim_end();
+    RESERVED_NAMES.iter().any(|reserved| stem.eq_ignore_ascii_case(reserved))
+}
+
+fn truncate_to_bytes(name: &mut String, max: usize) {
+    if name.len() <= max {
+        return;
+    }
+    let mut end = max;
+    while !name.is_char_boundary(end) {
+        end -= 1;
+    }
+    name.truncate(end);
+}
+
+/// Map one server-supplied name to a single safe path component
+pub fn sanitize_component(name: &str) -> String {
+    let mut cleaned: String = name
+        .chars()
+        .map(|c| {
+            if c.is_control() || matches!(c, '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*') {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect();
+
+    truncate_to_bytes(&mut cleaned, MAX_COMPONENT_BYTES);
+    let trimmed = cleaned.trim_start().trim_end_matches(['.', ' ']);
+
+    if trimmed.is_empty() {
+        return "_".to_string();
+    }
+    if is_reserved_name(trimmed) {
+        let mut prefixed = format!("_{}", trimmed);
+        truncate_to_bytes(&mut prefixed, MAX_COMPONENT_BYTES);
+        return prefixed;
+    }
+    trimmed.to_string()
+}
+
+/// Join server-supplied components under `destination`; the result can never
+/// leave `destination`
+pub fn local_path(destination: &Path, components: &[String]) -> PathBuf {
+    components
+        .iter()
+        .fold(destination.to_path_buf(), |path, component| path.join(sanitize_component(component)))
+}
+
+/// Reject local paths that climb out of where they point with `..`
+pub fn check_no_traversal(path: &Path) -> Result<(), String> {
+    if path.components().any(|c| matches!(c, Component::ParentDir)) {
+        return Err(format!("Refusing path with parent directory references: {}", path.display()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replaces_separators_and_reserved_characters() {
+        assert_eq!(sanitize_component("a/b\\c"), "a_b_c");
+        assert_eq!(sanitize_component("what?<*>|\":"), "what_______");
+        assert_eq!(sanitize_component("tab\there\u{7}"), "tab_here_");
+        assert_eq!(sanitize_component("Résumé ✓.txt"), "Résumé ✓.txt");
+    }
+
+    #[test]
+    fn dot_names_and_empty_names_cannot_escape() {
+        assert_eq!(sanitize_component(""), "_");
+        assert_eq!(sanitize_component("   "), "_");
+        assert_eq!(sanitize_component("."), "_");
+        assert_eq!(sanitize_component(".."), "_");
+        assert_eq!(sanitize_component("..."), "_");
+        assert_eq!(sanitize_component(".hidden"), ".hidden");
+    }
+
+    #[test]
+    fn strips_trailing_dots_and_spaces() {
+        assert_eq!(sanitize_component("report. . "), "report");
+        assert_eq!(sanitize_component("  leading"), "leading");
+        assert_eq!(sanitize_component("file.txt."), "file.txt");
+    }
+
+    #[test]
+    fn prefixes_windows_device_names() {
+        for name in ["CON", "con", "Nul", "AUX", "PRN", "COM1", "lpt9"] {
+            assert_eq!(sanitize_component(name), format!("_{}", name));
+        }
+        assert_eq!(sanitize_component("nul.txt"), "_nul.txt");
+        assert_eq!(sanitize_component("CON.tar.gz"), "_CON.tar.gz");
+        assert_eq!(sanitize_component("CON ."), "_CON");
+        // Only exact device stems are reserved
+        assert_eq!(sanitize_component("CONSOLE"), "CONSOLE");
+        assert_eq!(sanitize_component("COM10"), "COM10");
+        assert_eq!(sanitize_component("my nul"), "my nul");
+    }
+
+    #[test]
+    fn long_names_are_truncated_on_char_boundaries() {
+        let long = "é".repeat(200);
+        let cleaned = sanitize_component(&long);
+        assert!(cleaned.len() <= MAX_COMPONENT_BYTES);
+        assert_eq!(cleaned, "é".repeat(127));
+    }
+
+    #[test]
+    fn local_paths_stay_inside_destination() {
+        let destination = Path::new("/downloads/Stuff");
+        let path = local_path(destination, &["..".to_string(), "a/b".to_string(), "c:d".to_string()]);
+        assert_eq!(path, Path::new("/downloads/Stuff/_/a_b/c_d"));
+
+        let path = local_path(destination, &["NUL".to_string(), "x. ".to_string()]);
+        assert_eq!(path, Path::new("/downloads/Stuff/_NUL/x"));
+    }
+
+    #[test]
+    fn detects_parent_directory_traversal() {
+        assert!(check_no_traversal(Path::new("/downloads/file.txt")).is_ok());
+        assert!(check_no_traversal(Path::new("/downloads/../etc/passwd")).is_err());
+        assert!(check_no_traversal(Path::new("../x")).is_err());
+    }
+}
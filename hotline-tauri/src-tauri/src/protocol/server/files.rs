@@ -0,0 +1,356 @@
+// The hosted server's file area
+//
+// Each shared folder appears as a top-level folder; below that, paths map
+// onto the local folder one component at a time. A component has to be a
+// plain name (no separators, no `..`), and the resolved path has to stay
+// inside the share after symlinks are followed. Downloads send an INFO fork
+// and the DATA fork; uploads keep only the DATA fork, never replace an
+// existing file, and are refused past the configured size limit. A download or upload is registered under a reference number
+// and served when the client connects to the transfer port with it; one that
+// nobody connects for within PENDING_TRANSFER_TIMEOUT is dropped.
+
+use super::Server;
+use crate::protocol::client::mac_forks::MacMetadata;
+use crate::protocol::client::{fork_header, fork_size};
+use crate::protocol::constants::{FieldType, FILE_TRANSFER_ID};
+use crate::protocol::sanitize_path::sanitize_component;
+use crate::protocol::transaction::{Transaction, TransactionField};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant, UNIX_EPOCH};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+const TRANSFER_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(30);
+const PENDING_TRANSFER_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SharedFolder {
+    /// Name the folder has on the server
+    pub name: String,
+    pub path: PathBuf,
+    #[serde(default)]
+    pub allow_uploads: bool,
+}
+
+pub(super) enum PendingTransfer {
+    Download { path: PathBuf, info: Vec<u8>, size: u64 },
+    Upload { path: PathBuf },
+}
+
+/// Read a FilePath field: a component count, then each component as two
+/// reserved bytes, a length byte and the name
+fn decode_path(data: &[u8]) -> Result<Vec<String>, String> {
+    if data.len() < 2 {
+        return Ok(Vec::new());
+    }
+    let count = u16::from_be_bytes([data[0], data[1]]) as usize;
+    let mut components = Vec::with_capacity(count);
+    let mut offset = 2;
+    for _ in 0..count {
+        let len = *data.get(offset + 2).ok_or("Malformed file path")? as usize;
+        let bytes = data.get(offset + 3..offset + 3 + len).ok_or("Malformed file path")?;
+        let component = match std::str::from_utf8(bytes) {
+            Ok(component) => component.to_string(),
+            Err(_) => encoding_rs::MACINTOSH.decode(bytes).0.into_owned(),
+        };
+        components.push(component);
+        offset += 3 + len;
+    }
+    Ok(components)
+}
+
+fn request_path(request: &Transaction) -> Result<Vec<String>, String> {
+    request.get_field(FieldType::FilePath).map(|f| decode_path(&f.data)).unwrap_or(Ok(Vec::new()))
+}
+
+fn is_plain_name(name: &str) -> bool {
+    !name.is_empty() && sanitize_component(name) == name
+}
+
+/// The share and local folder `path` names; None for the top level
+fn resolve<'a>(shares: &'a [SharedFolder], path: &[String]) -> Result<Option<(&'a SharedFolder, PathBuf)>, String> {
+    let Some((first, rest)) = path.split_first() else {
+        return Ok(None);
+    };
+    let share = shares.iter().find(|share| share.name == *first).ok_or("No such folder")?;
+    let mut local = share.path.clone();
+    for component in rest {
+        if !is_plain_name(component) {
+            return Err(format!("Invalid name '{}'", component));
+        }
+        local.push(component);
+    }
+    Ok(Some((share, local)))
+}
+
+/// Whether `local` exists and, symlinks followed, is still inside the share
+fn inside_share(share: &SharedFolder, local: &Path) -> bool {
+    match (fs::canonicalize(&share.path), fs::canonicalize(local)) {
+        (Ok(root), Ok(local)) => local.starts_with(root),
+        _ => false,
+    }
+}
+
+fn visible_entries(folder: &Path) -> Result<Vec<fs::DirEntry>, String> {
+    let mut entries: Vec<_> = fs::read_dir(folder)
+        .map_err(|e| format!("Failed to read folder: {}", e))?
+        .filter_map(Result::ok)
+        .filter(|entry| !entry.file_name().to_string_lossy().starts_with('.'))
+        .collect();
+    entries.sort_by_key(|entry| entry.file_name().to_string_lossy().to_lowercase());
+    Ok(entries)
+}
+
+/// One FileNameWithInfo record: type, creator, size (a folder's item count),
+/// reserved bytes, name script and the name
+fn file_name_with_info(file_type: &[u8; 4], creator: &[u8; 4], size: u64, name: &str) -> TransactionField {
+    let name = name.as_bytes();
+    let mut data = Vec::with_capacity(20 + name.len());
+    data.extend_from_slice(file_type);
+    data.extend_from_slice(creator);
+    data.extend_from_slice(&(size.min(u32::MAX as u64) as u32).to_be_bytes());
+    data.extend_from_slice(&[0u8; 6]);
+    data.extend_from_slice(&(name.len() as u16).to_be_bytes());
+    data.extend_from_slice(name);
+    TransactionField::new(FieldType::FileNameWithInfo, data)
+}
+
+fn folder_info(name: &str, folder: &Path) -> TransactionField {
+    let items = visible_entries(folder).map(|entries| entries.len()).unwrap_or(0);
+    file_name_with_info(b"fldr", &[0; 4], items as u64, name)
+}
+
+fn size_field(field_type: FieldType, size: u64) -> TransactionField {
+    match u32::try_from(size) {
+        Ok(size) => TransactionField::from_u32(field_type, size),
+        Err(_) => TransactionField::from_u64(field_type, size),
+    }
+}
+
+pub(super) fn list(shares: &[SharedFolder], request: &Transaction) -> Result<Vec<TransactionField>, String> {
+    let Some((share, folder)) = resolve(shares, &request_path(request)?)? else {
+        return Ok(shares.iter().map(|share| folder_info(&share.name, &share.path)).collect());
+    };
+    if !folder.is_dir() || !inside_share(share, &folder) {
+        return Err("No such folder".to_string());
+    }
+
+    Ok(visible_entries(&folder)?
+        .into_iter()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let path = entry.path();
+            if path.is_dir() {
+                Some(folder_info(&name, &path))
+            } else {
+                let size = path.metadata().ok()?.len();
+                Some(file_name_with_info(b"????", b"????", size, &name))
+            }
+        })
+        .collect())
+}
+
+async fn register(server: &Server, transfer: PendingTransfer) -> u32 {
+    let reference = server.next_reference.fetch_add(1, Ordering::Relaxed);
+    let mut transfers = server.transfers.lock().await;
+    transfers.retain(|_, (registered, _)| registered.elapsed() < PENDING_TRANSFER_TIMEOUT);
+    transfers.insert(reference, (Instant::now(), transfer));
+    reference
+}
+
+fn file_name(request: &Transaction) -> Result<String, String> {
+    request
+        .get_field(FieldType::FileName)
+        .and_then(|f| f.to_string().ok())
+        .filter(|name| is_plain_name(name))
+        .ok_or_else(|| "Invalid file name".to_string())
+}
+
+pub(super) async fn download(server: &Server, request: &Transaction) -> Result<Vec<TransactionField>, String> {
+    let name = file_name(request)?;
+    let mut path = request_path(request)?;
+    path.push(name.clone());
+    let (share, local) = resolve(&server.config.shared_folders, &path)?.ok_or("No such file")?;
+    if !local.is_file() || !inside_share(share, &local) {
+        return Err("No such file".to_string());
+    }
+
+    let metadata = local.metadata().map_err(|e| format!("Failed to read {}: {}", name, e))?;
+    let unix = |time: std::io::Result<std::time::SystemTime>| {
+        time.ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|since| since.as_secs() as i64)
+    };
+    let info = MacMetadata {
+        file_type: *b"????",
+        creator: *b"????",
+        created: unix(metadata.created()),
+        modified: unix(metadata.modified()),
+        ..MacMetadata::default()
+    }
+    .to_info_fork(&name);
+
+    let size = metadata.len();
+    let transfer_size = 24 + 16 + info.len() as u64 + 16 + size;
+    let reference = register(server, PendingTransfer::Download { path: local, info, size }).await;
+    Ok(vec![
+        TransactionField::from_u32(FieldType::ReferenceNumber, reference),
+        size_field(FieldType::TransferSize, transfer_size),
+        size_field(FieldType::FileSize, size),
+        TransactionField::from_u16(FieldType::WaitingCount, 0),
+    ])
+}
+
+pub(super) async fn upload(server: &Server, request: &Transaction) -> Result<Vec<TransactionField>, String> {
+    let name = file_name(request)?;
+    let (share, folder) = resolve(&server.config.shared_folders, &request_path(request)?)?
+        .ok_or("Upload into one of the shared folders")?;
+    if !share.allow_uploads {
+        return Err(format!("{} doesn't take uploads", share.name));
+    }
+    if !folder.is_dir() || !inside_share(share, &folder) {
+        return Err("No such folder".to_string());
+    }
+    let local = folder.join(&name);
+    if local.exists() {
+        return Err(format!("There is already a file named {}", name));
+    }
+
+    let reference = register(server, PendingTransfer::Upload { path: local }).await;
+    Ok(vec![TransactionField::from_u32(FieldType::ReferenceNumber, reference)])
+}
+
+/// Serve one connection to the transfer port
+pub(super) async fn serve_transfer(server: &Server, mut stream: TcpStream) -> Result<(), String> {
+    let mut handshake = [0u8; 16];
+    tokio::time::timeout(TRANSFER_HANDSHAKE_TIMEOUT, stream.read_exact(&mut handshake))
+        .await
+        .map_err(|_| "Timed out waiting for the transfer handshake".to_string())?
+        .map_err(|e| format!("Failed to read transfer handshake: {}", e))?;
+    if &handshake[0..4] != FILE_TRANSFER_ID {
+        return Err("Not a Hotline transfer".to_string());
+    }
+    let reference = u32::from_be_bytes([handshake[4], handshake[5], handshake[6], handshake[7]]);
+    let transfer = server
+        .transfers
+        .lock()
+        .await
+        .remove(&reference)
+        .filter(|(registered, _)| registered.elapsed() < PENDING_TRANSFER_TIMEOUT)
+        .map(|(_, transfer)| transfer)
+        .ok_or_else(|| format!("No transfer {}", reference))?;
+
+    match transfer {
+        PendingTransfer::Download { path, info, size } => send_file(&mut stream, &path, &info, size).await,
+        PendingTransfer::Upload { path } => receive_file(&mut stream, &path, server.config.max_upload_size).await,
+    }
+}
+
+async fn send_file(stream: &mut TcpStream, path: &Path, info: &[u8], size: u64) -> Result<(), String> {
+    let mut header = Vec::with_capacity(24 + 16 + info.len() + 16);
+    header.extend_from_slice(b"FILP");
+    header.extend_from_slice(&1u16.to_be_bytes());
+    header.extend_from_slice(&[0u8; 16]);
+    header.extend_from_slice(&2u16.to_be_bytes());
+    header.extend_from_slice(&fork_header(b"INFO", info.len() as u64));
+    header.extend_from_slice(info);
+    header.extend_from_slice(&fork_header(b"DATA", size));
+    stream.write_all(&header).await.map_err(|e| format!("Failed to send file header: {}", e))?;
+
+    let file = tokio::fs::File::open(path)
+        .await
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let sent = tokio::io::copy(&mut file.take(size), stream)
+        .await
+        .map_err(|e| format!("Failed to send {}: {}", path.display(), e))?;
+    if sent < size {
+        return Err(format!("{} shrank while it was being sent", path.display()));
+    }
+    stream.flush().await.map_err(|e| format!("Failed to send {}: {}", path.display(), e))
+}
+
+async fn receive_file(stream: &mut TcpStream, path: &Path, max_size: u64) -> Result<(), String> {
+    let mut header = [0u8; 24];
+    stream
+        .read_exact(&mut header)
+        .await
+        .map_err(|e| format!("Failed to read file header: {}", e))?;
+    if &header[0..4] != b"FILP" {
+        return Err("Invalid file header".to_string());
+    }
+    let fork_count = u16::from_be_bytes([header[22], header[23]]);
+
+    // Only a file created here may be removed again; whatever else is at the
+    // path, such as another upload of the same name, is left alone
+    let mut file = tokio::fs::File::create_new(path)
+        .await
+        .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let result = receive_forks(stream, &mut file, fork_count, path, max_size).await;
+    drop(file);
+    if result.is_err() {
+        let _ = fs::remove_file(path);
+    }
+    result
+}
+
+async fn receive_forks(stream: &mut TcpStream, file: &mut tokio::fs::File, fork_count: u16, path: &Path, max_size: u64) -> Result<(), String> {
+    for _ in 0..fork_count {
+        let mut fork = [0u8; 16];
+        stream
+            .read_exact(&mut fork)
+            .await
+            .map_err(|e| format!("Failed to read fork header: {}", e))?;
+        let size = fork_size(&fork);
+        if &fork[0..4] == b"DATA" && size > max_size {
+            return Err(format!("Uploads are limited to {} bytes", max_size));
+        }
+        let mut data = (&mut *stream).take(size);
+        let received = if &fork[0..4] == b"DATA" {
+            tokio::io::copy(&mut data, file).await
+        } else {
+            tokio::io::copy(&mut data, &mut tokio::io::sink()).await
+        }
+        .map_err(|e| format!("Failed to receive {}: {}", path.display(), e))?;
+        if received < size {
+            return Err(format!("Connection closed before {} arrived", path.display()));
+        }
+    }
+    file.flush().await.map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::client::HotlineClient;
+
+    #[test]
+    fn listings_read_back_and_paths_stay_inside_the_share() {
+        let root = std::env::temp_dir().join(format!("hosted-share-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("Docs")).unwrap();
+        fs::write(root.join("Read Me.txt"), b"hello").unwrap();
+        fs::write(root.join(".hidden"), b"").unwrap();
+        let shares = vec![SharedFolder { name: "Public".to_string(), path: root.clone(), allow_uploads: false }];
+
+        let mut request = Transaction::new(1, crate::protocol::constants::TransactionType::GetFileNameList);
+        request.add_field(TransactionField::from_path(FieldType::FilePath, &["Public".to_string()]));
+        let files: Vec<_> = list(&shares, &request)
+            .unwrap()
+            .iter()
+            .map(|field| HotlineClient::parse_file_info(&field.data).unwrap())
+            .collect();
+        assert_eq!(files.len(), 2);
+        assert!(files[0].is_folder && files[0].name == "Docs");
+        assert_eq!((files[1].name.as_str(), files[1].size), ("Read Me.txt", 5));
+
+        let escape = ["Public".to_string(), "..".to_string()];
+        assert!(resolve(&shares, &escape).is_err());
+        assert!(resolve(&shares, &["Private".to_string()]).is_err());
+        assert!(resolve(&shares, &[]).unwrap().is_none());
+        let _ = fs::remove_dir_all(&root);
+    }
+}
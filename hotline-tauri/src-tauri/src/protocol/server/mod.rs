@@ -0,0 +1,363 @@
+// Hosting a Hotline server
+//
+// A small server run from inside the app, for a group of friends or a LAN
+// party rather than a public community. It speaks enough of the protocol for
+// any Hotline client to join: the handshake, login against the configured
+// accounts (or as a guest), the agreement, the user list, public chat,
+// private messages, the message board, and a file area made of local
+// folders the host chose to share. Transfers use the next port up, as on
+// every Hotline server. The board lives in memory and starts empty each time
+// hosting starts.
+//
+// "News" here means only that 1.2-style message board. Threaded news (the
+// categories and articles of 1.5 and later) isn't served: those requests get
+// an error reply saying the server doesn't support them.
+
+mod files;
+mod session;
+
+pub use files::SharedFolder;
+
+use crate::protocol::cancel::CancellationToken;
+use crate::protocol::constants::{FieldType, TransactionType};
+use crate::protocol::sanitize_path::sanitize_component;
+use crate::protocol::transaction::{Transaction, TransactionField};
+use crate::protocol::types::UserAccess;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::atomic::{AtomicU16, AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::net::TcpListener;
+use tokio::sync::{mpsc, Mutex, RwLock};
+
+/// Version reported in the login reply
+const SERVER_VERSION: u16 = 151;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerAccount {
+    pub login: String,
+    pub password: String,
+    /// Nickname for clients that log in without one
+    pub name: String,
+    pub access: UserAccess,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ServerConfig {
+    pub name: String,
+    pub description: String,
+    /// Address to listen on; 0.0.0.0 for every interface, 127.0.0.1 for
+    /// this machine only
+    pub bind_address: IpAddr,
+    /// Transfers use the port after this one
+    pub port: u16,
+    /// Shown after login to users whose access doesn't skip it
+    pub agreement: Option<String>,
+    /// Top-level folders of the file area
+    pub shared_folders: Vec<SharedFolder>,
+    /// What guests may do; None refuses guests
+    pub guest_access: Option<UserAccess>,
+    pub accounts: Vec<ServerAccount>,
+    /// Largest file that may be uploaded, in bytes
+    pub max_upload_size: u64,
+}
+
+/// Upload limit for configs that don't set one
+const DEFAULT_MAX_UPLOAD_SIZE: u64 = 100 * 1024 * 1024;
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            name: "Hotline Navigator".to_string(),
+            description: String::new(),
+            bind_address: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            port: crate::protocol::constants::DEFAULT_SERVER_PORT,
+            agreement: None,
+            shared_folders: Vec::new(),
+            guest_access: Some(default_guest_access()),
+            accounts: Vec::new(),
+            max_upload_size: DEFAULT_MAX_UPLOAD_SIZE,
+        }
+    }
+}
+
+/// Chat, messages, the board and downloads
+pub fn default_guest_access() -> UserAccess {
+    UserAccess {
+        download_file: true,
+        read_chat: true,
+        send_chat: true,
+        show_in_list: true,
+        send_private_message: true,
+        news_read_article: true,
+        news_post_article: true,
+        send_message: true,
+        ..UserAccess::default()
+    }
+}
+
+impl ServerConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Server name cannot be empty".to_string());
+        }
+        if self.port == 0 || self.port == u16::MAX {
+            return Err(format!("Port {} cannot be used; transfers need the port after it", self.port));
+        }
+        if self.max_upload_size == 0 {
+            return Err("Upload size limit must be greater than zero".to_string());
+        }
+
+        let mut share_names = HashSet::new();
+        for share in &self.shared_folders {
+            if share.name.is_empty() || sanitize_component(&share.name) != share.name {
+                return Err(format!("'{}' cannot be used as a shared folder name", share.name));
+            }
+            if !share_names.insert(share.name.to_lowercase()) {
+                return Err(format!("More than one shared folder is named '{}'", share.name));
+            }
+        }
+
+        let mut logins = HashSet::new();
+        for account in &self.accounts {
+            let login = account.login.trim();
+            if login.is_empty() || login.eq_ignore_ascii_case("guest") {
+                return Err(format!("'{}' cannot be used as an account login", account.login));
+            }
+            if !logins.insert(login.to_lowercase()) {
+                return Err(format!("More than one account logs in as '{}'", login));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HostingStatus {
+    pub port: u16,
+    /// Nicknames of the users online
+    pub users: Vec<String>,
+}
+
+/// A user who has joined, as the others see them
+#[derive(Clone)]
+struct Connected {
+    name: String,
+    icon: u16,
+    flags: u16,
+    access: UserAccess,
+    outbox: Outbox,
+}
+
+impl Connected {
+    fn user_name_with_info(&self, user_id: u16) -> Vec<u8> {
+        let name = self.name.as_bytes();
+        let mut data = Vec::with_capacity(8 + name.len());
+        data.extend_from_slice(&user_id.to_be_bytes());
+        data.extend_from_slice(&self.icon.to_be_bytes());
+        data.extend_from_slice(&self.flags.to_be_bytes());
+        data.extend_from_slice(&(name.len() as u16).to_be_bytes());
+        data.extend_from_slice(name);
+        data
+    }
+}
+
+/// Transactions a client's writer hasn't sent yet before it is dropped
+const OUTBOX_SIZE: usize = 256;
+
+/// The queue to a client's writer task. A client that stops reading is
+/// dropped once the queue fills rather than buffered for without limit.
+#[derive(Clone)]
+struct Outbox {
+    tx: mpsc::Sender<Transaction>,
+    /// Cancelled when the queue overflows
+    overflowed: CancellationToken,
+}
+
+impl Outbox {
+    fn send(&self, transaction: Transaction) {
+        if self.tx.try_send(transaction).is_err() {
+            self.overflowed.cancel();
+        }
+    }
+}
+
+struct Server {
+    config: ServerConfig,
+    users: RwLock<HashMap<u16, Connected>>,
+    next_user_id: AtomicU16,
+    next_transaction_id: AtomicU32,
+    /// Newest post first
+    board: RwLock<Vec<String>>,
+    /// Transfers waiting for their connection, with when they were registered
+    transfers: Mutex<HashMap<u32, (Instant, files::PendingTransfer)>>,
+    next_reference: AtomicU32,
+}
+
+impl Server {
+    fn new(config: ServerConfig) -> Self {
+        Self {
+            config,
+            users: RwLock::new(HashMap::new()),
+            next_user_id: AtomicU16::new(1),
+            next_transaction_id: AtomicU32::new(1),
+            board: RwLock::new(Vec::new()),
+            transfers: Mutex::new(HashMap::new()),
+            next_reference: AtomicU32::new(1),
+        }
+    }
+
+    /// A transaction the server sends on its own, rather than as a reply
+    fn transaction(&self, transaction_type: TransactionType, fields: Vec<TransactionField>) -> Transaction {
+        let mut transaction = Transaction::new(self.next_transaction_id.fetch_add(1, Ordering::Relaxed), transaction_type);
+        for field in fields {
+            transaction.add_field(field);
+        }
+        transaction
+    }
+
+    /// Send to every joined user `to` picks
+    async fn broadcast(&self, transaction: Transaction, to: impl Fn(&Connected) -> bool) {
+        for user in self.users.read().await.values().filter(|user| to(user)) {
+            user.outbox.send(transaction.clone());
+        }
+    }
+}
+
+/// A running hosted server; stopping it closes every connection
+pub struct HotlineServer {
+    server: Arc<Server>,
+    cancel: CancellationToken,
+}
+
+impl HotlineServer {
+    /// Listen on the configured address and port, and the transfer port after it
+    pub async fn start(config: ServerConfig) -> Result<Self, String> {
+        config.validate()?;
+        if let Some(share) = config.shared_folders.iter().find(|share| !share.path.is_dir()) {
+            return Err(format!("Shared folder {} is not a folder", share.path.display()));
+        }
+
+        let listener = TcpListener::bind((config.bind_address, config.port))
+            .await
+            .map_err(|e| format!("Failed to listen on {}:{}: {}", config.bind_address, config.port, e))?;
+        let transfer_listener = TcpListener::bind((config.bind_address, config.port + 1))
+            .await
+            .map_err(|e| format!("Failed to listen on {}:{}: {}", config.bind_address, config.port + 1, e))?;
+        println!("Hosting '{}' on {}:{}", config.name, config.bind_address, config.port);
+
+        let server = Arc::new(Server::new(config));
+        let cancel = CancellationToken::new();
+
+        let accept_server = Arc::clone(&server);
+        let accept_cancel = cancel.clone();
+        tokio::spawn(async move {
+            loop {
+                let (stream, peer) = tokio::select! {
+                    _ = accept_cancel.cancelled() => break,
+                    accepted = listener.accept() => match accepted {
+                        Ok(accepted) => accepted,
+                        Err(e) => {
+                            eprintln!("Failed to accept connection: {}", e);
+                            continue;
+                        }
+                    },
+                };
+                let server = Arc::clone(&accept_server);
+                let cancel = accept_cancel.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = session::run(server, stream, cancel).await {
+                        println!("Hosted session from {} ended: {}", peer, e);
+                    }
+                });
+            }
+        });
+
+        let transfer_server = Arc::clone(&server);
+        let transfer_cancel = cancel.clone();
+        tokio::spawn(async move {
+            loop {
+                let (stream, peer) = tokio::select! {
+                    _ = transfer_cancel.cancelled() => break,
+                    accepted = transfer_listener.accept() => match accepted {
+                        Ok(accepted) => accepted,
+                        Err(e) => {
+                            eprintln!("Failed to accept transfer connection: {}", e);
+                            continue;
+                        }
+                    },
+                };
+                let server = Arc::clone(&transfer_server);
+                let cancel = transfer_cancel.clone();
+                tokio::spawn(async move {
+                    let result = tokio::select! {
+                        _ = cancel.cancelled() => Ok(()),
+                        result = files::serve_transfer(&server, stream) => result,
+                    };
+                    if let Err(e) = result {
+                        println!("Hosted transfer from {} failed: {}", peer, e);
+                    }
+                });
+            }
+        });
+
+        Ok(Self { server, cancel })
+    }
+
+    pub fn stop(&self) {
+        println!("Stopping hosted server on port {}", self.server.config.port);
+        self.cancel.cancel();
+    }
+
+    pub async fn status(&self) -> HostingStatus {
+        let users = self.server.users.read().await;
+        let mut ids: Vec<_> = users.keys().copied().collect();
+        ids.sort_unstable();
+        HostingStatus {
+            port: self.server.config.port,
+            users: ids.iter().map(|id| users[id].name.clone()).collect(),
+        }
+    }
+}
+
+/// A reply to `request` carrying `fields`
+fn reply(request: &Transaction, fields: Vec<TransactionField>) -> Transaction {
+    let mut reply = Transaction::new(request.id, TransactionType::Reply);
+    reply.is_reply = 1;
+    for field in fields {
+        reply.add_field(field);
+    }
+    reply
+}
+
+/// A refused request, with the reason for the user
+fn error_reply(request: &Transaction, message: &str) -> Transaction {
+    let mut reply = reply(request, vec![TransactionField::from_string(FieldType::ErrorText, message)]);
+    reply.error_code = 1;
+    reply
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_rejects_clashing_names() {
+        assert!(ServerConfig::default().validate().is_ok());
+
+        let share = |name: &str| SharedFolder { name: name.to_string(), path: std::env::temp_dir(), allow_uploads: false };
+        let shares = ServerConfig { shared_folders: vec![share("Public"), share("public")], ..Default::default() };
+        assert!(shares.validate().is_err());
+        let escaping = ServerConfig { shared_folders: vec![share("..")], ..Default::default() };
+        assert!(escaping.validate().is_err());
+
+        let account = ServerAccount { login: "guest".to_string(), password: String::new(), name: String::new(), access: UserAccess::default() };
+        let guest = ServerConfig { accounts: vec![account], ..Default::default() };
+        assert!(guest.validate().is_err());
+    }
+}
@@ -0,0 +1,363 @@
+// One client connection to the hosted server
+//
+// After the handshake the client has to log in before anything else. A user
+// joins (appears in everyone's list and starts hearing chat) once logged in,
+// or once they accept the agreement when there is one. Replies and
+// everything broadcast to the user go through one bounded channel to a writer
+// task, so a slow client never holds up the others and is dropped once it
+// falls too far behind.
+
+use super::{error_reply, files, reply, Connected, Outbox, Server, OUTBOX_SIZE, SERVER_VERSION};
+use crate::protocol::cancel::CancellationToken;
+use crate::protocol::constants::{
+    FieldType, TransactionType, DEFAULT_NICKNAME, DEFAULT_USER_ICON, PROTOCOL_ID, SUBPROTOCOL_ID, TRANSACTION_HEADER_SIZE,
+};
+use crate::protocol::transaction::{Transaction, TransactionField};
+use crate::protocol::types::UserAccess;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::tcp::OwnedReadHalf;
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Largest transaction a client may send; files go over the transfer port
+const MAX_TRANSACTION_SIZE: usize = 256 * 1024;
+
+/// Separates posts in the board as sent to clients
+const POST_DIVIDER: &str = "\r__________________________________________________\r";
+
+pub(super) async fn run(server: Arc<Server>, stream: TcpStream, cancel: CancellationToken) -> Result<(), String> {
+    let (mut reader, mut writer) = stream.into_split();
+
+    let mut handshake = [0u8; 12];
+    tokio::time::timeout(HANDSHAKE_TIMEOUT, reader.read_exact(&mut handshake))
+        .await
+        .map_err(|_| "Timed out waiting for the handshake".to_string())?
+        .map_err(|e| format!("Failed to read handshake: {}", e))?;
+    if &handshake[0..4] != PROTOCOL_ID || &handshake[4..8] != SUBPROTOCOL_ID {
+        return Err("Not a Hotline client".to_string());
+    }
+    let mut handshake_reply = Vec::with_capacity(8);
+    handshake_reply.extend_from_slice(PROTOCOL_ID);
+    handshake_reply.extend_from_slice(&0u32.to_be_bytes());
+    writer
+        .write_all(&handshake_reply)
+        .await
+        .map_err(|e| format!("Failed to send handshake reply: {}", e))?;
+
+    let (tx, mut rx) = mpsc::channel::<Transaction>(OUTBOX_SIZE);
+    let outbox = Outbox { tx, overflowed: cancel.child_token() };
+    tokio::spawn(async move {
+        while let Some(transaction) = rx.recv().await {
+            if writer.write_all(&transaction.encode()).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let user_id = server.next_user_id.fetch_add(1, Ordering::Relaxed);
+    let mut session = Session { server, outbox: outbox.clone(), user_id, user: None, joined: false };
+    let result = tokio::select! {
+        biased;
+        _ = cancel.cancelled() => Ok(()),
+        _ = outbox.overflowed.cancelled() => Err("Client stopped reading".to_string()),
+        result = session.serve(&mut reader) => result,
+    };
+    session.leave().await;
+    result
+}
+
+async fn read_transaction(reader: &mut OwnedReadHalf) -> Result<Option<Transaction>, String> {
+    let mut header = [0u8; TRANSACTION_HEADER_SIZE];
+    match reader.read_exact(&mut header).await {
+        Ok(_) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(format!("Failed to read transaction: {}", e)),
+    }
+    let data_size = u32::from_be_bytes([header[16], header[17], header[18], header[19]]) as usize;
+    if data_size > MAX_TRANSACTION_SIZE {
+        return Err(format!("Transaction of {} bytes is too large", data_size));
+    }
+
+    let mut data = header.to_vec();
+    data.resize(TRANSACTION_HEADER_SIZE + data_size, 0);
+    reader
+        .read_exact(&mut data[TRANSACTION_HEADER_SIZE..])
+        .await
+        .map_err(|e| format!("Failed to read transaction: {}", e))?;
+    Transaction::decode(&data).map(Some)
+}
+
+fn string_field(request: &Transaction, field_type: FieldType) -> Option<String> {
+    request.get_field(field_type).and_then(|f| f.to_string().ok())
+}
+
+/// Login and password are sent with every byte inverted
+fn decoded_field(request: &Transaction, field_type: FieldType) -> String {
+    request
+        .get_field(field_type)
+        .map(|f| String::from_utf8_lossy(&f.data.iter().map(|b| !b).collect::<Vec<_>>()).into_owned())
+        .unwrap_or_default()
+}
+
+struct Session {
+    server: Arc<Server>,
+    outbox: Outbox,
+    user_id: u16,
+    /// Set once logged in
+    user: Option<Connected>,
+    joined: bool,
+}
+
+impl Session {
+    async fn serve(&mut self, reader: &mut OwnedReadHalf) -> Result<(), String> {
+        while let Some(request) = read_transaction(reader).await? {
+            let response = match self.handle(&request).await {
+                Ok(fields) => reply(&request, fields),
+                Err(e) => error_reply(&request, &e),
+            };
+            let logged_in = request.transaction_type == TransactionType::Login && response.error_code == 0;
+            self.outbox.send(response);
+            if logged_in {
+                self.welcome().await;
+            }
+        }
+        Ok(())
+    }
+
+    async fn handle(&mut self, request: &Transaction) -> Result<Vec<TransactionField>, String> {
+        if self.user.is_none() && request.transaction_type != TransactionType::Login {
+            return Err("Log in first".to_string());
+        }
+
+        match request.transaction_type {
+            TransactionType::Login => self.login(request),
+            TransactionType::Agreed => {
+                self.update_user_info(request);
+                self.join().await;
+                Ok(Vec::new())
+            }
+            TransactionType::SetClientUserInfo => {
+                self.update_user_info(request);
+                self.announce().await;
+                Ok(Vec::new())
+            }
+            TransactionType::GetUserNameList => Ok(self.user_list().await),
+            TransactionType::SendChat => self.send_chat(request).await,
+            TransactionType::SendInstantMessage => self.send_instant_message(request).await,
+            TransactionType::GetMessageBoard => {
+                self.require(|access| access.news_read_article, "read the board")?;
+                let board = self.server.board.read().await.join(POST_DIVIDER);
+                Ok(vec![TransactionField::from_string(FieldType::Data, &board)])
+            }
+            TransactionType::OldPostNews => self.post(request).await,
+            TransactionType::GetFileNameList => {
+                // Hotline has no browse privilege; listing is for those who can transfer
+                self.require(|access| access.download_file || access.upload_file, "browse files")?;
+                files::list(&self.server.config.shared_folders, request)
+            }
+            TransactionType::DownloadFile => {
+                self.require(|access| access.download_file, "download files")?;
+                files::download(&self.server, request).await
+            }
+            TransactionType::UploadFile => {
+                self.require(|access| access.upload_file, "upload files")?;
+                files::upload(&self.server, request).await
+            }
+            TransactionType::ConnectionKeepAlive => Ok(Vec::new()),
+            other => Err(format!("This server doesn't support {:?}", other)),
+        }
+    }
+
+    fn require(&self, allowed: impl Fn(&UserAccess) -> bool, action: &str) -> Result<(), String> {
+        match &self.user {
+            Some(user) if allowed(&user.access) => Ok(()),
+            _ => Err(format!("You are not allowed to {}", action)),
+        }
+    }
+
+    fn login(&mut self, request: &Transaction) -> Result<Vec<TransactionField>, String> {
+        if self.user.is_some() {
+            return Err("Already logged in".to_string());
+        }
+
+        let config = &self.server.config;
+        let login = decoded_field(request, FieldType::UserLogin);
+        let password = decoded_field(request, FieldType::UserPassword);
+        let (access, account_name) = if login.is_empty() || login.eq_ignore_ascii_case("guest") {
+            let access = config.guest_access.ok_or("This server doesn't accept guests")?;
+            (access, DEFAULT_NICKNAME.to_string())
+        } else {
+            let account = config
+                .accounts
+                .iter()
+                .find(|account| account.login.trim().eq_ignore_ascii_case(&login) && account.password == password)
+                .ok_or("Incorrect login or password")?;
+            (account.access, account.name.clone())
+        };
+
+        let name = string_field(request, FieldType::UserName)
+            .filter(|name| !name.trim().is_empty())
+            .unwrap_or(account_name);
+        let icon = request
+            .get_field(FieldType::UserIconId)
+            .and_then(|f| f.to_u16().ok())
+            .unwrap_or(DEFAULT_USER_ICON);
+        println!("Hosted server: {} logged in as {}", name, if login.is_empty() { "guest" } else { &login });
+        self.user = Some(Connected { name, icon, flags: 0, access, outbox: self.outbox.clone() });
+
+        Ok(vec![
+            TransactionField::from_u16(FieldType::VersionNumber, SERVER_VERSION),
+            TransactionField::from_string(FieldType::ServerName, &config.name),
+            TransactionField::from_string(FieldType::Data, &config.description),
+            TransactionField::from_u64(FieldType::UserAccess, access.to_bits()),
+        ])
+    }
+
+    /// Tell a user who just logged in what they may do, then show the
+    /// agreement or let them straight in
+    async fn welcome(&mut self) {
+        let Some(user) = &self.user else { return };
+        let access = self.server.transaction(
+            TransactionType::UserAccess,
+            vec![TransactionField::from_u64(FieldType::UserAccess, user.access.to_bits())],
+        );
+        self.outbox.send(access);
+
+        match &self.server.config.agreement {
+            Some(agreement) if !agreement.trim().is_empty() && !user.access.no_agreement => {
+                let show = self.server.transaction(
+                    TransactionType::ShowAgreement,
+                    vec![TransactionField::from_string(FieldType::ServerAgreement, agreement)],
+                );
+                self.outbox.send(show);
+            }
+            _ => self.join().await,
+        }
+    }
+
+    /// Name, icon and flags, as sent with Agreed and SetClientUserInfo
+    fn update_user_info(&mut self, request: &Transaction) {
+        let Some(user) = &mut self.user else { return };
+        if let Some(name) = string_field(request, FieldType::UserName).filter(|name| !name.trim().is_empty()) {
+            user.name = name;
+        }
+        if let Some(icon) = request.get_field(FieldType::UserIconId).and_then(|f| f.to_u16().ok()) {
+            user.icon = icon;
+        }
+        if let Some(flags) = request.get_field(FieldType::UserFlags).and_then(|f| f.to_u16().ok()) {
+            user.flags = flags;
+        }
+    }
+
+    async fn join(&mut self) {
+        if self.joined {
+            return;
+        }
+        self.joined = true;
+        self.announce().await;
+    }
+
+    /// Put the user's current info in the list and tell everyone
+    async fn announce(&self) {
+        let Some(user) = self.user.clone().filter(|_| self.joined) else { return };
+        let change = self.server.transaction(
+            TransactionType::NotifyUserChange,
+            vec![
+                TransactionField::from_u16(FieldType::UserId, self.user_id),
+                TransactionField::from_u16(FieldType::UserIconId, user.icon),
+                TransactionField::from_u16(FieldType::UserFlags, user.flags),
+                TransactionField::from_string(FieldType::UserName, &user.name),
+            ],
+        );
+        self.server.users.write().await.insert(self.user_id, user);
+        self.server.broadcast(change, |_| true).await;
+    }
+
+    async fn leave(&mut self) {
+        if self.server.users.write().await.remove(&self.user_id).is_none() {
+            return;
+        }
+        let delete = self.server.transaction(
+            TransactionType::NotifyUserDelete,
+            vec![TransactionField::from_u16(FieldType::UserId, self.user_id)],
+        );
+        self.server.broadcast(delete, |_| true).await;
+    }
+
+    async fn user_list(&self) -> Vec<TransactionField> {
+        let users = self.server.users.read().await;
+        let mut ids: Vec<_> = users.keys().copied().collect();
+        ids.sort_unstable();
+        ids.iter()
+            .map(|id| TransactionField::new(FieldType::UserNameWithInfo, users[id].user_name_with_info(*id)))
+            .collect()
+    }
+
+    async fn send_chat(&self, request: &Transaction) -> Result<Vec<TransactionField>, String> {
+        self.require(|access| access.send_chat, "chat")?;
+        if request.get_field(FieldType::ChatId).is_some() {
+            return Err("This server doesn't support private chats".to_string());
+        }
+        let Some(user) = self.user.as_ref().filter(|_| self.joined) else {
+            return Err("Accept the agreement first".to_string());
+        };
+
+        let text = string_field(request, FieldType::Data).unwrap_or_default();
+        let mut fields = vec![
+            TransactionField::from_u16(FieldType::UserId, self.user_id),
+            TransactionField::from_string(FieldType::UserName, &user.name),
+            TransactionField::from_string(FieldType::Data, &text),
+        ];
+        if let Some(options) = request.get_field(FieldType::ChatOptions) {
+            fields.push(options.clone());
+        }
+        let message = self.server.transaction(TransactionType::ChatMessage, fields);
+        self.server.broadcast(message, |user| user.access.read_chat).await;
+        Ok(Vec::new())
+    }
+
+    async fn send_instant_message(&self, request: &Transaction) -> Result<Vec<TransactionField>, String> {
+        self.require(|access| access.send_private_message, "send private messages")?;
+        let Some(user) = &self.user else { return Ok(Vec::new()) };
+        let to = request
+            .get_field(FieldType::UserId)
+            .and_then(|f| f.to_u16().ok())
+            .ok_or("No user to send the message to")?;
+
+        let mut fields = vec![
+            TransactionField::from_u16(FieldType::UserId, self.user_id),
+            TransactionField::from_string(FieldType::UserName, &user.name),
+            TransactionField::from_string(FieldType::Data, &string_field(request, FieldType::Data).unwrap_or_default()),
+            TransactionField::from_u32(FieldType::Options, 1),
+        ];
+        if let Some(quote) = request.get_field(FieldType::QuotingMessage) {
+            fields.push(quote.clone());
+        }
+        let message = self.server.transaction(TransactionType::ServerMessage, fields);
+
+        let users = self.server.users.read().await;
+        let recipient = users.get(&to).ok_or("That user is no longer on the server")?;
+        recipient.outbox.send(message);
+        Ok(Vec::new())
+    }
+
+    async fn post(&self, request: &Transaction) -> Result<Vec<TransactionField>, String> {
+        self.require(|access| access.news_post_article, "post to the board")?;
+        let Some(user) = &self.user else { return Ok(Vec::new()) };
+        let text = string_field(request, FieldType::Data).unwrap_or_default();
+        if text.trim().is_empty() {
+            return Err("Nothing to post".to_string());
+        }
+
+        let post = format!("From {}:\r\r{}", user.name, text.replace('\n', "\r"));
+        self.server.board.write().await.insert(0, post.clone());
+        let notice = self.server.transaction(TransactionType::NewMessage, vec![TransactionField::from_string(FieldType::Data, &post)]);
+        self.server.broadcast(notice, |user| user.access.news_read_article).await;
+        Ok(Vec::new())
+    }
+}
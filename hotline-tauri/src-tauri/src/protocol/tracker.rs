@@ -1,15 +1,108 @@
 // Hotline Tracker Client
 // Protocol: Connect to tracker, send HTRK magic packet, receive server listings
 
-use std::net::SocketAddr;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use std::fmt;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpStream;
+use crate::protocol::cancel::{cancellable, CancellationToken};
 use crate::protocol::types::TrackerServer;
 
 const TRACKER_MAGIC: &[u8] = b"HTRK";
 const TRACKER_VERSION: u16 = 0x0001;
 const DEFAULT_TRACKER_PORT: u16 = 5498;
 
+// Limits on what a tracker may send. Real trackers list a few hundred servers
+// with short names; anything far beyond that is broken or hostile.
+const MAX_SERVERS_PER_BATCH: u16 = 2000;
+const MAX_TOTAL_SERVERS: usize = 5000;
+const MAX_BATCHES: usize = 100;
+const MAX_NAME_LEN: usize = 128;
+const MAX_DESCRIPTION_LEN: usize = 255;
+
+/// A tracker listing that broke one of the limits above
+#[derive(Debug, Clone, PartialEq)]
+pub enum TrackerLimitError {
+    BatchTooLarge { batch: usize, count: u16 },
+    TooManyServers { batch: usize, total: usize },
+    NameTooLong { batch: usize, len: usize },
+    DescriptionTooLong { batch: usize, len: usize },
+}
+
+impl fmt::Display for TrackerLimitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BatchTooLarge { batch, count } => write!(
+                f,
+                "Tracker batch #{} announces {} servers (limit {} per batch)",
+                batch, count, MAX_SERVERS_PER_BATCH
+            ),
+            Self::TooManyServers { batch, total } => write!(
+                f,
+                "Tracker batch #{} brings the listing to {} servers (limit {})",
+                batch, total, MAX_TOTAL_SERVERS
+            ),
+            Self::NameTooLong { batch, len } => write!(
+                f,
+                "Tracker batch #{} has a {}-byte server name (limit {})",
+                batch, len, MAX_NAME_LEN
+            ),
+            Self::DescriptionTooLong { batch, len } => write!(
+                f,
+                "Tracker batch #{} has a {}-byte server description (limit {})",
+                batch, len, MAX_DESCRIPTION_LEN
+            ),
+        }
+    }
+}
+
+/// Check a batch header before reading any of its entries
+fn check_batch(batch: usize, count: u16, total_so_far: usize) -> Result<(), TrackerLimitError> {
+    if count > MAX_SERVERS_PER_BATCH {
+        return Err(TrackerLimitError::BatchTooLarge { batch, count });
+    }
+    let total = total_so_far + count as usize;
+    if total > MAX_TOTAL_SERVERS {
+        return Err(TrackerLimitError::TooManyServers { batch, total });
+    }
+    Ok(())
+}
+
+/// Read a MacRoman Pascal string, refusing lengths over `max` before allocating
+async fn read_pascal_string<R: AsyncRead + Unpin>(
+    stream: &mut R,
+    max: usize,
+    too_long: impl FnOnce(usize) -> TrackerLimitError,
+    what: &str,
+) -> Result<String, String> {
+    let mut len = [0u8; 1];
+    stream
+        .read_exact(&mut len)
+        .await
+        .map_err(|e| format!("Failed to read server {} length: {}", what, e))?;
+
+    let len = len[0] as usize;
+    if len > max {
+        return Err(too_long(len).to_string());
+    }
+    if len == 0 {
+        return Ok(String::new());
+    }
+
+    let mut data = vec![0u8; len];
+    stream
+        .read_exact(&mut data)
+        .await
+        .map_err(|e| format!("Failed to read server {}: {}", what, e))?;
+
+    // Decode MacOS Roman to UTF-8
+    let (decoded, _encoding, had_errors) = encoding_rs::MACINTOSH.decode(&data);
+    Ok(if had_errors {
+        String::from_utf8_lossy(&data).to_string()
+    } else {
+        decoded.into_owned()
+    })
+}
+
 pub struct TrackerClient;
 
 impl TrackerClient {
@@ -28,7 +121,11 @@ impl TrackerClient {
     ///      - Unused: 2 bytes
     ///      - Server name: Pascal string (1-byte length + data, MacOS Roman encoding)
     ///      - Server description: Pascal string (1-byte length + data, MacOS Roman encoding)
-    pub async fn fetch_servers(address: &str, port: Option<u16>) -> Result<Vec<TrackerServer>, String> {
+    pub async fn fetch_servers(address: &str, port: Option<u16>, cancel: &CancellationToken) -> Result<Vec<TrackerServer>, String> {
+        cancellable(cancel, Self::fetch_servers_inner(address, port)).await
+    }
+
+    async fn fetch_servers_inner(address: &str, port: Option<u16>) -> Result<Vec<TrackerServer>, String> {
         let tracker_port = port.unwrap_or(DEFAULT_TRACKER_PORT);
         let addr = crate::protocol::socket_addr_string(address, tracker_port);
         
@@ -57,6 +154,12 @@ impl TrackerClient {
         
         println!("TrackerClient: Sent magic packet");
         
+        Self::read_server_list(&mut stream).await
+    }
+
+    /// Read the tracker's reply to the handshake: the magic response followed
+    /// by one or more batches of server listings
+    pub(crate) async fn read_server_list<R: AsyncRead + Unpin>(stream: &mut R) -> Result<Vec<TrackerServer>, String> {
         // Receive magic response (6 bytes: "HTRK" + version)
         let mut magic_response = [0u8; 6];
         stream
@@ -102,6 +205,8 @@ impl TrackerClient {
             
             println!("TrackerClient: Batch #{} - type: {}, count1: {}, count2: {}", 
                 batch_count, message_type, server_count, server_count2);
+
+            check_batch(batch_count, server_count2, total_entries_parsed).map_err(|e| e.to_string())?;
             
             // Parse servers in this batch
             for _ in 0..server_count2 {
@@ -137,55 +242,21 @@ impl TrackerClient {
                     .await
                     .map_err(|e| format!("Failed to skip unused bytes: {}", e))?;
                 
-                // Read server name (Pascal string: 1 byte length + data)
-                let mut name_len = [0u8; 1];
-                stream
-                    .read_exact(&mut name_len)
-                    .await
-                    .map_err(|e| format!("Failed to read server name length: {}", e))?;
-                
-                let name = if name_len[0] > 0 {
-                    let mut name_data = vec![0u8; name_len[0] as usize];
-                    stream
-                        .read_exact(&mut name_data)
-                        .await
-                        .map_err(|e| format!("Failed to read server name: {}", e))?;
-                    
-                    // Decode MacOS Roman to UTF-8
-                    let (decoded, _encoding, had_errors) = encoding_rs::MACINTOSH.decode(&name_data);
-                    if had_errors {
-                        String::from_utf8_lossy(&name_data).to_string()
-                    } else {
-                        decoded.into_owned()
-                    }
-                } else {
-                    String::new()
-                };
-                
-                // Read server description (Pascal string: 1 byte length + data)
-                let mut desc_len = [0u8; 1];
-                stream
-                    .read_exact(&mut desc_len)
-                    .await
-                    .map_err(|e| format!("Failed to read server description length: {}", e))?;
-                
-                let description = if desc_len[0] > 0 {
-                    let mut desc_data = vec![0u8; desc_len[0] as usize];
-                    stream
-                        .read_exact(&mut desc_data)
-                        .await
-                        .map_err(|e| format!("Failed to read server description: {}", e))?;
-                    
-                    // Decode MacOS Roman to UTF-8
-                    let (decoded, _encoding, had_errors) = encoding_rs::MACINTOSH.decode(&desc_data);
-                    if had_errors {
-                        String::from_utf8_lossy(&desc_data).to_string()
-                    } else {
-                        decoded.into_owned()
-                    }
-                } else {
-                    String::new()
-                };
+                // Server name and description (Pascal strings: 1 byte length + data)
+                let name = read_pascal_string(
+                    stream,
+                    MAX_NAME_LEN,
+                    |len| TrackerLimitError::NameTooLong { batch: batch_count, len },
+                    "name",
+                )
+                .await?;
+                let description = read_pascal_string(
+                    stream,
+                    MAX_DESCRIPTION_LEN,
+                    |len| TrackerLimitError::DescriptionTooLong { batch: batch_count, len },
+                    "description",
+                )
+                .await?;
                 
                 total_entries_parsed += 1;
                 
@@ -199,6 +270,7 @@ impl TrackerClient {
                         users,
                         name: if name.is_empty() { None } else { Some(name) },
                         description: if description.is_empty() { None } else { Some(description) },
+                        user_limit: None,
                     });
                 }
             }
@@ -212,8 +284,8 @@ impl TrackerClient {
             }
             
             // Safety: don't loop forever
-            if batch_count >= 100 {
-                println!("TrackerClient: WARNING - Stopped after 100 batches");
+            if batch_count >= MAX_BATCHES {
+                println!("TrackerClient: WARNING - Stopped after {} batches", MAX_BATCHES);
                 break;
             }
         }
@@ -225,3 +297,55 @@ impl TrackerClient {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn listing(count: u16, name: &[u8]) -> Vec<u8> {
+        let mut data = b"HTRK\x00\x01".to_vec();
+        data.extend_from_slice(&1u16.to_be_bytes());
+        data.extend_from_slice(&0u16.to_be_bytes());
+        data.extend_from_slice(&count.to_be_bytes());
+        data.extend_from_slice(&count.to_be_bytes());
+        for _ in 0..count {
+            data.extend_from_slice(&[10, 0, 0, 1, 0x15, 0x7c, 0, 3, 0, 0]);
+            data.push(name.len() as u8);
+            data.extend_from_slice(name);
+            data.push(0);
+        }
+        data
+    }
+
+    #[test]
+    fn batch_counts_are_capped() {
+        assert!(check_batch(1, 10, 0).is_ok());
+        assert_eq!(
+            check_batch(2, MAX_SERVERS_PER_BATCH + 1, 0),
+            Err(TrackerLimitError::BatchTooLarge { batch: 2, count: MAX_SERVERS_PER_BATCH + 1 })
+        );
+        assert_eq!(
+            check_batch(3, 100, MAX_TOTAL_SERVERS - 50),
+            Err(TrackerLimitError::TooManyServers { batch: 3, total: MAX_TOTAL_SERVERS + 50 })
+        );
+    }
+
+    #[tokio::test]
+    async fn oversized_batches_and_names_are_rejected() {
+        let data = listing(2, b"Server");
+        let servers = TrackerClient::read_server_list(&mut data.as_slice()).await.unwrap();
+        assert_eq!(servers.len(), 2);
+
+        // Only the header is sent; the count alone must be refused
+        let data = listing(0, b"")
+            .into_iter()
+            .take(6)
+            .chain(vec![0, 1, 0, 0, 0xff, 0xff, 0xff, 0xff])
+            .collect::<Vec<_>>();
+        let err = TrackerClient::read_server_list(&mut data.as_slice()).await.unwrap_err();
+        assert!(err.contains("batch #1"), "{}", err);
+
+        let data = listing(1, &[b'x'; 200]);
+        let err = TrackerClient::read_server_list(&mut data.as_slice()).await.unwrap_err();
+        assert!(err.contains("200-byte server name"), "{}", err);
+    }
+}
@@ -0,0 +1,182 @@
+// Hotline Tracker Registration
+// Protocol: servers announce themselves to trackers with a UDP packet (port
+// 5499), repeated every few minutes; trackers drop servers that stop sending.
+// This lets a server hosted from this app appear in public tracker listings.
+//
+// Packet layout (big-endian):
+//   version (u16, always 1), port (u16), user count (u16), reserved (u16, 0),
+//   pass ID (u32, stays the same for the whole session so the tracker can tell
+//   a refresh from a new server), then name, description and password as
+//   MacRoman Pascal strings.
+
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::net::{lookup_host, UdpSocket};
+use tokio::task::JoinHandle;
+
+const REGISTRATION_VERSION: u16 = 0x0001;
+const DEFAULT_REGISTRATION_PORT: u16 = 5499;
+/// How often trackers expect to hear from a listed server
+const REGISTRATION_INTERVAL: Duration = Duration::from_secs(300);
+const MAX_PASCAL_LEN: usize = 255;
+
+/// What we tell trackers about our server
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrackerRegistration {
+    pub name: String,
+    pub description: String,
+    /// Port our server accepts clients on
+    pub port: u16,
+    pub user_count: u16,
+    /// Only for trackers that require one to list a server
+    #[serde(default)]
+    pub password: Option<String>,
+}
+
+/// A tracker to register with; `port` defaults to the registration port
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrackerAddress {
+    pub address: String,
+    #[serde(default)]
+    pub port: Option<u16>,
+}
+
+fn push_pascal_string(packet: &mut Vec<u8>, text: &str, what: &str) -> Result<(), String> {
+    let (encoded, _, _) = encoding_rs::MACINTOSH.encode(text);
+    if encoded.len() > MAX_PASCAL_LEN {
+        return Err(format!("Server {} is too long for trackers ({} bytes, limit {})", what, encoded.len(), MAX_PASCAL_LEN));
+    }
+    packet.push(encoded.len() as u8);
+    packet.extend_from_slice(&encoded);
+    Ok(())
+}
+
+impl TrackerRegistration {
+    /// Encode the registration packet
+    pub fn encode(&self, pass_id: u32) -> Result<Vec<u8>, String> {
+        if self.name.trim().is_empty() {
+            return Err("Server name is required to register with trackers".to_string());
+        }
+
+        let mut packet = Vec::with_capacity(12 + self.name.len() + self.description.len() + 3);
+        packet.extend_from_slice(&REGISTRATION_VERSION.to_be_bytes());
+        packet.extend_from_slice(&self.port.to_be_bytes());
+        packet.extend_from_slice(&self.user_count.to_be_bytes());
+        packet.extend_from_slice(&0u16.to_be_bytes());
+        packet.extend_from_slice(&pass_id.to_be_bytes());
+        push_pascal_string(&mut packet, &self.name, "name")?;
+        push_pascal_string(&mut packet, &self.description, "description")?;
+        push_pascal_string(&mut packet, self.password.as_deref().unwrap_or(""), "tracker password")?;
+        Ok(packet)
+    }
+}
+
+/// Send one registration packet to a tracker
+async fn send_registration(tracker: &TrackerAddress, packet: &[u8]) -> Result<(), String> {
+    let port = tracker.port.unwrap_or(DEFAULT_REGISTRATION_PORT);
+    let addr = crate::protocol::socket_addr_string(&tracker.address, port);
+    let target = lookup_host(&addr)
+        .await
+        .map_err(|e| format!("Failed to resolve tracker {}: {}", addr, e))?
+        .next()
+        .ok_or_else(|| format!("Tracker {} has no address", addr))?;
+
+    let local = if target.is_ipv6() { "[::]:0" } else { "0.0.0.0:0" };
+    let socket = UdpSocket::bind(local)
+        .await
+        .map_err(|e| format!("Failed to open socket for tracker registration: {}", e))?;
+    socket
+        .send_to(packet, target)
+        .await
+        .map_err(|e| format!("Failed to send registration to tracker {}: {}", addr, e))?;
+    Ok(())
+}
+
+/// Keeps our server registered with a set of trackers until stopped
+pub struct TrackerRegistrar {
+    pass_id: u32,
+    task: Option<JoinHandle<()>>,
+}
+
+impl Default for TrackerRegistrar {
+    fn default() -> Self {
+        let mut bytes = [0u8; 4];
+        if SystemRandom::new().fill(&mut bytes).is_err() {
+            eprintln!("Failed to pick a tracker pass ID, using the process ID");
+            bytes = std::process::id().to_be_bytes();
+        }
+        Self {
+            pass_id: u32::from_be_bytes(bytes),
+            task: None,
+        }
+    }
+}
+
+impl TrackerRegistrar {
+    /// Register with `trackers` now and every few minutes after, replacing any
+    /// registration already running. Trackers that can't be reached are
+    /// logged and tried again next round.
+    pub fn start(&mut self, registration: TrackerRegistration, trackers: Vec<TrackerAddress>) -> Result<(), String> {
+        if trackers.is_empty() {
+            return Err("No trackers to register with".to_string());
+        }
+        let packet = registration.encode(self.pass_id)?;
+        self.stop();
+
+        println!("TrackerRegistrar: Registering {} with {} trackers", registration.name, trackers.len());
+        self.task = Some(tokio::spawn(async move {
+            let mut interval = tokio::time::interval(REGISTRATION_INTERVAL);
+            loop {
+                interval.tick().await;
+                for tracker in &trackers {
+                    if let Err(e) = send_registration(tracker, &packet).await {
+                        eprintln!("TrackerRegistrar: {}", e);
+                    }
+                }
+            }
+        }));
+        Ok(())
+    }
+
+    pub fn stop(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registration(name: &str) -> TrackerRegistration {
+        TrackerRegistration {
+            name: name.to_string(),
+            description: "Café".to_string(),
+            port: 5500,
+            user_count: 3,
+            password: None,
+        }
+    }
+
+    #[test]
+    fn encodes_header_and_pascal_strings() {
+        let packet = registration("Home").encode(0x01020304).unwrap();
+        assert_eq!(&packet[..12], &[0, 1, 0x15, 0x7C, 0, 3, 0, 0, 1, 2, 3, 4]);
+        assert_eq!(&packet[12..17], &[4, b'H', b'o', b'm', b'e']);
+        // MacRoman: é is a single byte
+        assert_eq!(&packet[17..22], &[4, b'C', b'a', b'f', 0x8E]);
+        // Empty password
+        assert_eq!(&packet[22..], &[0]);
+    }
+
+    #[test]
+    fn rejects_missing_or_oversized_names() {
+        assert!(registration(" ").encode(1).is_err());
+        assert!(registration(&"x".repeat(256)).encode(1).is_err());
+        assert!(registration(&"x".repeat(255)).encode(1).is_ok());
+    }
+}
@@ -1,14 +1,49 @@
 // Hotline transaction structures
 
 use super::constants::{FieldType, TransactionType, TRANSACTION_HEADER_SIZE};
+use std::borrow::Cow;
+use std::fmt;
 
-#[derive(Debug, Clone)]
+/// Stand-in for credential bytes in logs. Fixed length so dumps don't reveal
+/// how long a password is.
+const REDACTED_BYTES: &[u8] = b"********";
+
+/// Logins and passwords only get Hotline's reversible XOR encoding, so a hex
+/// dump of them is as good as plaintext
+fn is_sensitive(field_type: FieldType) -> bool {
+    matches!(field_type, FieldType::UserLogin | FieldType::UserPassword)
+}
+
+#[derive(Clone)]
 pub struct TransactionField {
     pub field_type: FieldType,
     pub data: Vec<u8>,
 }
 
+impl fmt::Debug for TransactionField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TransactionField")
+            .field("field_type", &self.field_type)
+            .field("data", &self.loggable_data())
+            .finish()
+    }
+}
+
 impl TransactionField {
+    /// Login or password field, which must not be logged
+    pub fn is_credential(&self) -> bool {
+        is_sensitive(self.field_type)
+    }
+
+    /// Field data safe to print: credential fields are masked
+    pub fn loggable_data(&self) -> Cow<'_, [u8]> {
+        if self.is_credential() {
+            Cow::Borrowed(REDACTED_BYTES)
+        } else {
+            Cow::Borrowed(&self.data)
+        }
+    }
+
     pub fn new(field_type: FieldType, data: Vec<u8>) -> Self {
         Self { field_type, data }
     }
@@ -134,6 +169,17 @@ impl TransactionField {
         ]))
     }
 
+    /// Read a size or offset field. Servers send these as 4 bytes, or as 8
+    /// when the value doesn't fit (files over 4GB).
+    pub fn to_size(&self) -> Result<u64, String> {
+        match self.data.len() {
+            2 => self.to_u16().map(u64::from),
+            4 => self.to_u32().map(u64::from),
+            8 => self.to_u64(),
+            len => Err(format!("Invalid size field length: {}", len)),
+        }
+    }
+
     // Encode field for transmission
     pub fn encode(&self) -> Vec<u8> {
         let mut buf = Vec::new();
@@ -170,6 +216,15 @@ impl Transaction {
         self.fields.push(field);
     }
 
+    /// Copy with credential fields masked, for hex dumps of whole frames
+    pub fn redacted(&self) -> Self {
+        let mut copy = self.clone();
+        for field in &mut copy.fields {
+            field.data = field.loggable_data().into_owned();
+        }
+        copy
+    }
+
     pub fn get_field(&self, field_type: FieldType) -> Option<&TransactionField> {
         self.fields
             .iter()
@@ -238,39 +293,48 @@ impl Transaction {
 
         // Decode fields
         if data_size > 0 && data.len() >= TRANSACTION_HEADER_SIZE + 2 {
-            let field_data = &data[TRANSACTION_HEADER_SIZE..];
-            if field_data.len() < 2 {
-                return Ok(transaction);
-            }
+            transaction.fields = decode_fields(&data[TRANSACTION_HEADER_SIZE..]);
+        }
 
-            let field_count = u16::from_be_bytes([field_data[0], field_data[1]]) as usize;
-            let mut offset = 2;
+        Ok(transaction)
+    }
+}
 
-            for _ in 0..field_count {
-                if offset + 4 > field_data.len() {
-                    break;
-                }
+/// Decode a field list (u16 count followed by type/size/data records). Also
+/// used for the nested field lists some replies pack into a single field.
+/// Truncated trailing records are dropped.
+pub fn decode_fields(field_data: &[u8]) -> Vec<TransactionField> {
+    let mut fields = Vec::new();
+    if field_data.len() < 2 {
+        return fields;
+    }
 
-                let field_type_raw = u16::from_be_bytes([field_data[offset], field_data[offset + 1]]);
-                let field_size = u16::from_be_bytes([field_data[offset + 2], field_data[offset + 3]]) as usize;
-                offset += 4;
+    let field_count = u16::from_be_bytes([field_data[0], field_data[1]]) as usize;
+    let mut offset = 2;
 
-                if offset + field_size > field_data.len() {
-                    break;
-                }
+    for _ in 0..field_count {
+        if offset + 4 > field_data.len() {
+            break;
+        }
 
-                let field_data_bytes = field_data[offset..offset + field_size].to_vec();
-                offset += field_size;
+        let field_type_raw = u16::from_be_bytes([field_data[offset], field_data[offset + 1]]);
+        let field_size = u16::from_be_bytes([field_data[offset + 2], field_data[offset + 3]]) as usize;
+        offset += 4;
 
-                transaction.fields.push(TransactionField {
-                    field_type: FieldType::from(field_type_raw),
-                    data: field_data_bytes,
-                });
-            }
+        if offset + field_size > field_data.len() {
+            break;
         }
 
-        Ok(transaction)
+        let field_data_bytes = field_data[offset..offset + field_size].to_vec();
+        offset += field_size;
+
+        fields.push(TransactionField {
+            field_type: FieldType::from(field_type_raw),
+            data: field_data_bytes,
+        });
     }
+
+    fields
 }
 
 #[cfg(test)]
@@ -293,6 +357,24 @@ mod tests {
         assert_eq!(field.data, vec![0x61 ^ 0xFF, 0x62 ^ 0xFF, 0x63 ^ 0xFF]);
     }
 
+    #[test]
+    fn credentials_never_reach_debug_output_or_dumps() {
+        let mut transaction = Transaction::new(1, TransactionType::Login);
+        transaction.add_field(TransactionField::from_encoded_string(FieldType::UserLogin, "admin"));
+        transaction.add_field(TransactionField::from_encoded_string(FieldType::UserPassword, "hunter2"));
+        transaction.add_field(TransactionField::from_string(FieldType::UserName, "bob"));
+        let password = TransactionField::from_encoded_string(FieldType::UserPassword, "hunter2").data;
+
+        let debug = format!("{:?}", transaction);
+        assert!(!debug.contains(&format!("{:?}", password)));
+
+        let dump = transaction.redacted().encode();
+        assert!(!dump.windows(password.len()).any(|w| w == password.as_slice()));
+        assert!(dump.windows(3).any(|w| w == b"bob"));
+        // The real frame is untouched
+        assert!(transaction.encode().windows(password.len()).any(|w| w == password.as_slice()));
+    }
+
     #[test]
     fn field_u16_roundtrip() {
         let field = TransactionField::from_u16(FieldType::UserId, 42);
@@ -317,6 +399,16 @@ mod tests {
         assert_eq!(field.to_u64().unwrap(), 9_876_543_210);
     }
 
+    #[test]
+    fn size_fields_accept_short_and_extended_widths() {
+        let short = TransactionField::from_u32(FieldType::FileSize, 123456);
+        assert_eq!(short.to_size().unwrap(), 123456);
+        let extended = TransactionField::from_u64(FieldType::FileSize, 5_000_000_000);
+        assert_eq!(extended.to_size().unwrap(), 5_000_000_000);
+        let bad = TransactionField::new(FieldType::FileSize, vec![0; 3]);
+        assert!(bad.to_size().is_err());
+    }
+
     #[test]
     fn field_u16_wrong_size() {
         let field = TransactionField::new(FieldType::UserId, vec![0, 0, 0]);
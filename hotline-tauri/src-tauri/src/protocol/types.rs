@@ -1,6 +1,9 @@
 // Hotline protocol types
 use serde::{Deserialize, Serialize};
 
+use super::avatar::{self, AvatarFallback};
+use super::quirks::{ServerCapabilities, ServerQuirks, ServerSoftware};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum BookmarkType {
@@ -9,6 +12,7 @@ pub enum BookmarkType {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Bookmark {
     pub id: String,
     pub name: String,
@@ -19,42 +23,250 @@ pub struct Bookmark {
     pub password: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub icon: Option<u16>,
-    #[serde(default)]
+    #[serde(default, alias = "auto_connect")]
     pub auto_connect: bool,
     #[serde(default)]
     pub tls: bool,
     #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
     pub bookmark_type: Option<BookmarkType>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Disconnect after this many idle minutes without transfers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idle_disconnect_mins: Option<u32>,
+    /// The bookmark group this bookmark is filed under
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    /// Run in order once we're logged in and past the agreement
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub on_connect: Vec<ConnectAction>,
+}
+
+/// Something done on the user's behalf right after connecting
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "camelCase")]
+pub enum ConnectAction {
+    /// Say something in public chat
+    SendChat { text: String },
+    /// Show a folder in the Files tab
+    OpenFolder { path: Vec<String> },
+    /// Load the message board
+    FetchNews,
+}
+
+impl ConnectAction {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ConnectAction::SendChat { .. } => "sendChat",
+            ConnectAction::OpenFolder { .. } => "openFolder",
+            ConnectAction::FetchNews => "fetchNews",
+        }
+    }
+}
+
+impl Bookmark {
+    /// Whether every word of `query` appears in the name, address, a tag or
+    /// the notes, ignoring case. An empty query matches everything.
+    pub fn matches(&self, query: &str) -> bool {
+        let haystacks: Vec<String> = [&self.name, &self.address]
+            .into_iter()
+            .chain(self.tags.iter())
+            .chain(self.notes.iter())
+            .map(|text| text.to_lowercase())
+            .collect();
+        query
+            .to_lowercase()
+            .split_whitespace()
+            .all(|word| haystacks.iter().any(|text| text.contains(word)))
+    }
+
+    /// Trim tags, dropping empty ones and repeats (ignoring case), and drop
+    /// notes and greetings that are only whitespace
+    pub fn tidy(&mut self) {
+        let mut seen = std::collections::HashSet::new();
+        self.tags = std::mem::take(&mut self.tags)
+            .into_iter()
+            .map(|tag| tag.trim().to_string())
+            .filter(|tag| !tag.is_empty() && seen.insert(tag.to_lowercase()))
+            .collect();
+        if self.notes.as_deref().is_some_and(|notes| notes.trim().is_empty()) {
+            self.notes = None;
+        }
+        self.on_connect
+            .retain(|action| !matches!(action, ConnectAction::SendChat { text } if text.trim().is_empty()));
+    }
+}
+
+/// How the client identifies itself at login. `version_number` is the
+/// protocol-level version servers use to pick features (151+ enables the
+/// agreement flow); name and version are shown in newer servers' logs.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ClientIdentity {
+    pub version_number: u16,
+    pub name: String,
+    pub version: String,
+    /// Whether to send the name/version fields at all
+    pub send_identification: bool,
+}
+
+pub const DEFAULT_CLIENT_VERSION_NUMBER: u16 = 255;
+
+impl Default for ClientIdentity {
+    fn default() -> Self {
+        Self {
+            version_number: DEFAULT_CLIENT_VERSION_NUMBER,
+            name: "Hotline Navigator".to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            send_identification: true,
+        }
+    }
+}
+
+/// Declares UserAccess with one bool per Hotline access bit. Bit N counts
+/// from the most significant bit of the 64-bit access field.
+macro_rules! user_access {
+    ($($name:ident = $bit:expr,)*) => {
+        #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+        #[serde(rename_all = "camelCase", default)]
+        pub struct UserAccess {
+            $(pub $name: bool,)*
+        }
+
+        impl UserAccess {
+            pub fn from_bits(bits: u64) -> Self {
+                Self {
+                    $($name: bits & (1u64 << (63 - $bit)) != 0,)*
+                }
+            }
+
+            pub fn to_bits(self) -> u64 {
+                let mut bits = 0u64;
+                $(if self.$name {
+                    bits |= 1u64 << (63 - $bit);
+                })*
+                bits
+            }
+        }
+    };
+}
+
+user_access! {
+    delete_file = 0,
+    upload_file = 1,
+    download_file = 2,
+    rename_file = 3,
+    move_file = 4,
+    create_folder = 5,
+    delete_folder = 6,
+    rename_folder = 7,
+    move_folder = 8,
+    read_chat = 9,
+    send_chat = 10,
+    open_chat = 11,
+    close_chat = 12,
+    show_in_list = 13,
+    create_user = 14,
+    delete_user = 15,
+    open_user = 16,
+    modify_user = 17,
+    change_own_password = 18,
+    send_private_message = 19,
+    news_read_article = 20,
+    news_post_article = 21,
+    disconnect_user = 22,
+    cannot_be_disconnected = 23,
+    get_client_info = 24,
+    upload_anywhere = 25,
+    any_name = 26,
+    no_agreement = 27,
+    set_file_comment = 28,
+    set_folder_comment = 29,
+    view_drop_boxes = 30,
+    make_alias = 31,
+    broadcast = 32,
+    news_delete_article = 33,
+    news_create_category = 34,
+    news_delete_category = 35,
+    news_create_folder = 36,
+    news_delete_folder = 37,
+    upload_folder = 38,
+    download_folder = 39,
+    send_message = 40,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct TrackerServer {
     pub address: String,
     pub port: u16,
     pub users: u16,
     pub name: Option<String>,
     pub description: Option<String>,
+    /// Learned from earlier connections; trackers don't list it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_limit: Option<u16>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct ServerInfo {
     pub name: String,
     pub description: String,
     pub version: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub agreement: Option<String>,
+    #[serde(default)]
+    pub agreement_accepted: bool,
+    #[serde(default)]
+    pub server_software: ServerSoftware,
+    #[serde(default)]
+    pub quirks: ServerQuirks,
+    #[serde(default)]
+    pub capabilities: ServerCapabilities,
+    /// Most users the server allows at once, when it says
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_limit: Option<u16>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Hotline user flag bits
+const USER_FLAG_IDLE: u16 = 0x0001;
+const USER_FLAG_ADMIN: u16 = 0x0002;
+const USER_FLAG_REFUSE_MESSAGES: u16 = 0x0004;
+const USER_FLAG_REFUSE_CHAT: u16 = 0x0008;
+
+/// An online user, with the flag bits decoded
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct User {
-    pub id: u32,
-    pub name: String,
-    pub icon: u16,
+    pub user_id: u16,
+    pub user_name: String,
+    pub icon_id: u16,
     pub flags: u16,
     pub is_admin: bool,
     pub is_idle: bool,
+    pub refuses_messages: bool,
+    pub refuses_chat: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub color: Option<String>,
+    pub avatar: Option<AvatarFallback>,
+}
+
+impl User {
+    pub fn new(user_id: u16, user_name: String, icon_id: u16, flags: u16) -> Self {
+        Self {
+            avatar: avatar::fallback(icon_id, &user_name),
+            user_id,
+            user_name,
+            icon_id,
+            flags,
+            is_admin: flags & USER_FLAG_ADMIN != 0,
+            is_idle: flags & USER_FLAG_IDLE != 0,
+            refuses_messages: flags & USER_FLAG_REFUSE_MESSAGES != 0,
+            refuses_chat: flags & USER_FLAG_REFUSE_CHAT != 0,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -65,26 +277,126 @@ pub enum ConnectionStatus {
     Connected,
     LoggingIn,
     LoggedIn,
+    /// Connection dropped; retrying with backoff
+    Reconnecting,
     Failed,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct NewsCategory {
     #[serde(rename = "type")]
     pub category_type: u16, // 2 = bundle (folder), 3 = category
     pub count: u16,         // Number of items inside
     pub name: String,
     pub path: Vec<String>,  // Full path to this category
+    /// 16-byte GUID as hex, when the server sends one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub guid: Option<String>,
+    /// Serial numbers of the last addition and deletion, when sent
+    #[serde(alias = "add_sn", skip_serializing_if = "Option::is_none")]
+    pub add_sn: Option<u32>,
+    #[serde(alias = "delete_sn", skip_serializing_if = "Option::is_none")]
+    pub delete_sn: Option<u32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct NewsArticle {
     pub id: u32,
+    #[serde(alias = "parent_id")]
     pub parent_id: u32,     // 0 if root article
     pub flags: u32,
     pub title: String,
     pub poster: String,
+    /// When it was posted, in Unix seconds (UTC)
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub date: Option<String>,
+    pub date: Option<i64>,
     pub path: Vec<String>,  // Path to containing category
 }
+
+/// How a posted article's text is marked up
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ArticleFlavor {
+    #[default]
+    Plain,
+    /// Styled text, for servers and clients that render it
+    Styled,
+}
+
+impl ArticleFlavor {
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            ArticleFlavor::Plain => "text/plain",
+            ArticleFlavor::Styled => "text/styled",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bookmark(name: &str, address: &str) -> Bookmark {
+        Bookmark {
+            id: name.to_string(),
+            name: name.to_string(),
+            address: address.to_string(),
+            port: 5500,
+            login: "guest".to_string(),
+            password: None,
+            icon: None,
+            auto_connect: false,
+            tls: false,
+            bookmark_type: None,
+            notes: None,
+            tags: Vec::new(),
+            idle_disconnect_mins: None,
+            group: None,
+            on_connect: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn bookmarks_match_every_word_in_any_field() {
+        let mut b = bookmark("System7 Today", "hotline.system7today.com");
+        b.tags = vec!["Mac".to_string(), "retro".to_string()];
+        b.notes = Some("Great software archive".to_string());
+
+        assert!(b.matches(""));
+        assert!(b.matches("system7"));
+        assert!(b.matches("RETRO archive"));
+        assert!(b.matches("today.com mac"));
+        assert!(!b.matches("retro games"));
+    }
+
+    #[test]
+    fn tidies_tags_and_blank_notes() {
+        let mut b = bookmark("Home", "localhost");
+        b.tags = vec![" mac ".to_string(), "".to_string(), "Mac".to_string(), "files".to_string()];
+        b.notes = Some("  ".to_string());
+        b.tidy();
+        assert_eq!(b.tags, vec!["mac".to_string(), "files".to_string()]);
+        assert_eq!(b.notes, None);
+    }
+
+    #[test]
+    fn user_access_bits_round_trip() {
+        // Bit 0 (delete file) is the most significant bit; 22 is disconnect user
+        let bits = (1u64 << 63) | (1u64 << (63 - 22)) | (1u64 << (63 - 40));
+        let access = UserAccess::from_bits(bits);
+        assert!(access.delete_file && access.disconnect_user && access.send_message);
+        assert!(!access.upload_file && !access.broadcast);
+        assert_eq!(access.to_bits(), bits);
+    }
+
+    #[test]
+    fn decodes_user_flags() {
+        let user = User::new(3, "alice".to_string(), 128, 0x000E);
+        assert!(user.is_admin && user.refuses_messages && user.refuses_chat);
+        assert!(!user.is_idle);
+        let idle = User::new(4, "bob".to_string(), 128, 0x0001);
+        assert!(idle.is_idle && !idle.is_admin && !idle.refuses_messages);
+    }
+}
@@ -0,0 +1,146 @@
+// Automatic away
+//
+// When enabled, a task per connection marks us away after a stretch without
+// outgoing chat or private messages; the client clears it again on our next
+// message. The policy, including the away message and nickname suffix used,
+// is stored in auto_away.json in the app data directory.
+
+use crate::protocol::client::away::AwayStatus;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use super::json_store;
+
+/// Longest away message, in characters
+const MAX_AWAY_MESSAGE: usize = 255;
+/// Longest nickname suffix, in characters
+const MAX_NICKNAME_SUFFIX: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AutoAwayPolicy {
+    pub enabled: bool,
+    pub idle_secs: u64,
+    pub message: Option<String>,
+    pub nickname_suffix: Option<String>,
+}
+
+impl Default for AutoAwayPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            idle_secs: 600,
+            message: None,
+            nickname_suffix: None,
+        }
+    }
+}
+
+impl AutoAwayPolicy {
+    /// Idle time before going away, or None when automatic away is off
+    pub fn idle(&self) -> Option<Duration> {
+        self.enabled.then(|| Duration::from_secs(self.idle_secs))
+    }
+
+    pub fn status(&self) -> AwayStatus {
+        AwayStatus {
+            message: self.message.clone(),
+            nickname_suffix: self.nickname_suffix.clone(),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.idle_secs < 60 {
+            return Err("Idle time before going away must be at least 60 seconds".to_string());
+        }
+        validate_status(&self.status())
+    }
+}
+
+/// Check an away message and nickname suffix against the length limits
+pub fn validate_status(status: &AwayStatus) -> Result<(), String> {
+    if status.message.as_ref().is_some_and(|text| text.chars().count() > MAX_AWAY_MESSAGE) {
+        return Err(format!("Away message must be at most {} characters", MAX_AWAY_MESSAGE));
+    }
+    if status.nickname_suffix.as_ref().is_some_and(|text| text.chars().count() > MAX_NICKNAME_SUFFIX) {
+        return Err(format!("Away nickname suffix must be at most {} characters", MAX_NICKNAME_SUFFIX));
+    }
+    Ok(())
+}
+
+pub struct AutoAway {
+    path: PathBuf,
+    policy: AutoAwayPolicy,
+    tasks: HashMap<String, JoinHandle<()>>,
+}
+
+impl AutoAway {
+    pub fn load(path: PathBuf) -> Self {
+        let policy = json_store::load_json_or_default(&path, "auto-away policy");
+
+        Self {
+            path,
+            policy,
+            tasks: HashMap::new(),
+        }
+    }
+
+    pub fn policy(&self) -> AutoAwayPolicy {
+        self.policy.clone()
+    }
+
+    pub fn set(&mut self, policy: AutoAwayPolicy) -> Result<(), String> {
+        policy.validate()?;
+        json_store::save_json(&self.path, &policy, "auto-away policy")?;
+        self.policy = policy;
+        Ok(())
+    }
+
+    /// Track the idle timer for a connection, replacing any earlier one
+    pub fn start(&mut self, server_id: &str, task: JoinHandle<()>) {
+        if let Some(previous) = self.tasks.insert(server_id.to_string(), task) {
+            previous.abort();
+        }
+    }
+
+    pub fn stop(&mut self, server_id: &str) {
+        if let Some(task) = self.tasks.remove(server_id) {
+            task.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn idle_needs_policy_enabled_and_a_minute() {
+        assert_eq!(AutoAwayPolicy::default().idle(), None);
+        let enabled = AutoAwayPolicy { enabled: true, idle_secs: 300, ..Default::default() };
+        assert_eq!(enabled.idle(), Some(Duration::from_secs(300)));
+        assert!(AutoAwayPolicy { idle_secs: 30, ..Default::default() }.validate().is_err());
+    }
+
+    #[test]
+    fn limits_message_and_suffix_length() {
+        let policy = AutoAwayPolicy {
+            message: Some("Back soon".to_string()),
+            nickname_suffix: Some(" [away]".to_string()),
+            ..Default::default()
+        };
+        assert!(policy.validate().is_ok());
+        assert!(validate_status(&AwayStatus {
+            nickname_suffix: Some(" [away from keyboard]".to_string()),
+            ..Default::default()
+        })
+        .is_err());
+        assert!(validate_status(&AwayStatus {
+            message: Some("x".repeat(256)),
+            ..Default::default()
+        })
+        .is_err());
+    }
+}
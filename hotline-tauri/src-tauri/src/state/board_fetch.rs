@@ -0,0 +1,59 @@
+// Message board fetch size
+//
+// Boards are loaded a page at a time; this caps how much of a board one page
+// carries before the rest is left behind a continuation. Stored in
+// board_fetch.json in the app data directory.
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use super::json_store;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BoardFetchPolicy {
+    /// Most board text per page, in KiB
+    pub max_kib: u64,
+}
+
+impl Default for BoardFetchPolicy {
+    fn default() -> Self {
+        Self { max_kib: 256 }
+    }
+}
+
+impl BoardFetchPolicy {
+    pub fn max_bytes(&self) -> u64 {
+        self.max_kib * 1024
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !(16..=16 * 1024).contains(&self.max_kib) {
+            return Err("Message board page size must be between 16 KiB and 16 MiB".to_string());
+        }
+        Ok(())
+    }
+}
+
+pub struct BoardFetch {
+    path: PathBuf,
+    policy: BoardFetchPolicy,
+}
+
+impl BoardFetch {
+    pub fn load(path: PathBuf) -> Self {
+        let policy = json_store::load_json_or_default(&path, "message board fetch size");
+
+        Self { path, policy }
+    }
+
+    pub fn policy(&self) -> BoardFetchPolicy {
+        self.policy
+    }
+
+    pub fn set(&mut self, policy: BoardFetchPolicy) -> Result<(), String> {
+        policy.validate()?;
+        json_store::save_json(&self.path, &policy, "message board fetch size")?;
+        self.policy = policy;
+        Ok(())
+    }
+}
@@ -0,0 +1,224 @@
+// Bookmark groups
+//
+// Folders for organizing bookmarks, which may be nested. The groups are kept
+// in bookmark_groups.json in the app data directory, in display order, each
+// naming its parent; a bookmark names the group it belongs to. bookmarks.json
+// stays a flat list so older versions can still read it, and its order is
+// the order of bookmarks within each group.
+
+use crate::protocol::types::Bookmark;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use super::conversations::now_secs;
+use super::json_store;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BookmarkGroup {
+    pub id: String,
+    pub name: String,
+    /// The group this one is nested in; None at the top level
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+}
+
+pub struct BookmarkGroups {
+    path: PathBuf,
+    groups: Vec<BookmarkGroup>,
+}
+
+impl BookmarkGroups {
+    pub fn load(path: PathBuf) -> Self {
+        let groups = json_store::load_json_or_default(&path, "bookmark groups");
+
+        Self { path, groups }
+    }
+
+    pub fn list(&self) -> Vec<BookmarkGroup> {
+        self.groups.clone()
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.groups.iter().any(|group| group.id == id)
+    }
+
+    pub fn create(&mut self, name: &str, parent: Option<String>) -> Result<BookmarkGroup, String> {
+        let group = self.insert(name, parent)?;
+        self.save()?;
+        Ok(group)
+    }
+
+    pub fn rename(&mut self, id: &str, name: &str) -> Result<(), String> {
+        let name = group_name(name)?;
+        let group = self.groups.iter_mut().find(|group| group.id == id).ok_or("Group not found")?;
+        group.name = name;
+        self.save()
+    }
+
+    /// Nest group `id` in `parent` at `index` among its new siblings, or last
+    pub fn move_group(&mut self, id: &str, parent: Option<String>, index: Option<usize>) -> Result<(), String> {
+        self.relocate(id, parent, index)?;
+        self.save()
+    }
+
+    /// Remove group `id`, handing its subgroups to its parent. Returns the
+    /// parent so the caller can move the group's bookmarks there too.
+    pub fn remove(&mut self, id: &str) -> Result<Option<String>, String> {
+        let parent = self.take(id)?;
+        self.save()?;
+        Ok(parent)
+    }
+
+    fn insert(&mut self, name: &str, parent: Option<String>) -> Result<BookmarkGroup, String> {
+        let name = group_name(name)?;
+        if let Some(parent) = &parent {
+            if !self.contains(parent) {
+                return Err("Parent group not found".to_string());
+            }
+        }
+        let stamp = now_secs();
+        let id = (1..)
+            .map(|n| format!("group-{}-{}", stamp, n))
+            .find(|id| !self.contains(id))
+            .unwrap_or_default();
+        let group = BookmarkGroup { id, name, parent };
+        self.groups.push(group.clone());
+        Ok(group)
+    }
+
+    fn relocate(&mut self, id: &str, parent: Option<String>, index: Option<usize>) -> Result<(), String> {
+        if !self.contains(id) {
+            return Err("Group not found".to_string());
+        }
+        // Walk up from the new parent; meeting the group means it would
+        // end up inside itself
+        let mut ancestor = parent.clone();
+        while let Some(current) = ancestor {
+            if current == id {
+                return Err("A group can't be moved into itself".to_string());
+            }
+            ancestor = match self.groups.iter().find(|group| group.id == current) {
+                Some(group) => group.parent.clone(),
+                None => return Err("Parent group not found".to_string()),
+            };
+        }
+
+        let at = self.groups.iter().position(|group| group.id == id).unwrap_or_default();
+        let mut group = self.groups.remove(at);
+        group.parent = parent;
+        let position = sibling_position(&self.groups, |other| other.parent == group.parent, index);
+        self.groups.insert(position, group);
+        Ok(())
+    }
+
+    fn take(&mut self, id: &str) -> Result<Option<String>, String> {
+        let at = self.groups.iter().position(|group| group.id == id).ok_or("Group not found")?;
+        let removed = self.groups.remove(at);
+        for group in self.groups.iter_mut().filter(|group| group.parent.as_deref() == Some(id)) {
+            group.parent = removed.parent.clone();
+        }
+        Ok(removed.parent)
+    }
+
+    fn save(&self) -> Result<(), String> {
+        json_store::save_json(&self.path, &self.groups, "bookmark groups")
+    }
+}
+
+fn group_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Group name cannot be empty".to_string());
+    }
+    Ok(name.to_string())
+}
+
+/// Where in `items` to insert so the new item is the `index`th of the items
+/// matching `is_sibling`; after the last sibling (or at the end) otherwise
+fn sibling_position<T>(items: &[T], is_sibling: impl Fn(&T) -> bool, index: Option<usize>) -> usize {
+    let siblings: Vec<usize> = items
+        .iter()
+        .enumerate()
+        .filter(|(_, item)| is_sibling(item))
+        .map(|(position, _)| position)
+        .collect();
+    match index {
+        Some(index) if index < siblings.len() => siblings[index],
+        _ => siblings.last().map_or(items.len(), |last| last + 1),
+    }
+}
+
+/// Put bookmark `id` in `group` at `index` among the group's bookmarks, or last
+pub fn place_bookmark(bookmarks: &mut Vec<Bookmark>, id: &str, group: Option<String>, index: Option<usize>) -> Result<(), String> {
+    let at = bookmarks.iter().position(|bookmark| bookmark.id == id).ok_or("Bookmark not found")?;
+    let mut bookmark = bookmarks.remove(at);
+    bookmark.group = group;
+    let position = sibling_position(bookmarks, |other| other.group == bookmark.group, index);
+    bookmarks.insert(position, bookmark);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn groups() -> BookmarkGroups {
+        BookmarkGroups {
+            path: PathBuf::new(),
+            groups: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn groups_nest_without_cycles_and_hand_children_up_when_removed() {
+        let mut groups = groups();
+        let retro = groups.insert("Retro", None).unwrap();
+        let mac = groups.insert("Mac", Some(retro.id.clone())).unwrap();
+        let classic = groups.insert("Classic", Some(mac.id.clone())).unwrap();
+        assert!(groups.insert("  ", None).is_err());
+
+        assert!(groups.relocate(&retro.id, Some(classic.id.clone()), None).is_err());
+        groups.relocate(&classic.id, None, Some(0)).unwrap();
+        let order: Vec<&str> = groups.groups.iter().map(|group| group.name.as_str()).collect();
+        assert_eq!(order, vec!["Classic", "Retro", "Mac"]);
+
+        assert_eq!(groups.take(&retro.id).unwrap(), None);
+        assert_eq!(groups.groups.iter().find(|group| group.id == mac.id).unwrap().parent, None);
+    }
+
+    #[test]
+    fn bookmarks_are_ordered_within_their_group() {
+        let bookmark = |id: &str, group: Option<&str>| Bookmark {
+            id: id.to_string(),
+            name: id.to_string(),
+            address: format!("{}.example", id),
+            port: 5500,
+            login: "guest".to_string(),
+            password: None,
+            icon: None,
+            auto_connect: false,
+            tls: false,
+            bookmark_type: None,
+            notes: None,
+            tags: Vec::new(),
+            idle_disconnect_mins: None,
+            group: group.map(str::to_string),
+            on_connect: Vec::new(),
+        };
+        let mut bookmarks = vec![
+            bookmark("a", Some("g")),
+            bookmark("b", None),
+            bookmark("c", Some("g")),
+            bookmark("d", None),
+        ];
+
+        place_bookmark(&mut bookmarks, "d", Some("g".to_string()), Some(1)).unwrap();
+        place_bookmark(&mut bookmarks, "a", None, None).unwrap();
+        let order: Vec<(&str, Option<&str>)> = bookmarks
+            .iter()
+            .map(|bookmark| (bookmark.id.as_str(), bookmark.group.as_deref()))
+            .collect();
+        assert_eq!(order, vec![("b", None), ("a", None), ("d", Some("g")), ("c", Some("g"))]);
+        assert!(place_bookmark(&mut bookmarks, "missing", None, None).is_err());
+    }
+}
@@ -0,0 +1,546 @@
+// Chat history
+//
+// Public chat, private messages and server broadcasts are appended, a line at
+// a time, to one JSONL file per server under chat_logs/ in the app data
+// directory, so scrollback survives reconnecting and restarting the app. The
+// most recent lines of each server are kept in memory for paging through with
+// history(); once a file holds twice that many lines it is rewritten to the
+// retained ones. With history encryption on, each line is sealed on its own.
+// Logs from chat_log.json, which held public chat only, are moved over on
+// first load.
+//
+// Every line gets a per-server sequence number, so the order lines arrived in
+// survives reconnects. Some servers replay recent chat to a client that logs
+// back in; for a short window after a reconnect, a chat line that repeats one
+// logged shortly before the connection dropped is treated as a replay and
+// dropped. Each earlier line absorbs at most one replayed copy, so people
+// repeating themselves after the reconnect still come through.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::protocol::sanitize_path::sanitize_component;
+use super::conversations::now_secs;
+use super::history_crypto::{self, HistoryCipher};
+
+/// Maximum number of lines retained per server
+const MAX_LINES_PER_SERVER: usize = 2000;
+
+/// How long after logging back in lines are checked for replays
+const REPLAY_WINDOW_SECS: u64 = 60;
+
+/// How far before the disconnect a line can be and still be replayed
+const REPLAY_HORIZON_SECS: u64 = 15 * 60;
+
+/// Lines returned by history() when no limit is given
+const DEFAULT_HISTORY_LIMIT: usize = 500;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ChatLogKind {
+    #[default]
+    Chat,
+    Private,
+    Broadcast,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatLogEntry {
+    /// Position in this server's history, increasing across reconnects
+    #[serde(default)]
+    pub seq: u64,
+    #[serde(default)]
+    pub kind: ChatLogKind,
+    /// For private messages, always the other person
+    pub user_id: u16,
+    /// Who wrote the line; empty for broadcasts
+    pub user_name: String,
+    pub message: String,
+    pub timestamp: u64,
+    /// A private message we sent
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub outgoing: bool,
+}
+
+/// Where a connection dropped, and which earlier lines a replay has matched
+struct ReconnectBoundary {
+    /// Last line logged before the connection dropped
+    seq: u64,
+    /// When the client logged back in; None while still reconnecting
+    logged_in_at: Option<u64>,
+    matched: HashSet<u64>,
+}
+
+pub struct ChatLog {
+    /// Directory of per-server logs; None when nothing may be written
+    dir: Option<PathBuf>,
+    cipher: Option<Arc<HistoryCipher>>,
+    /// Retained lines, keyed by log file name
+    logs: HashMap<String, Vec<ChatLogEntry>>,
+    /// Lines in each log file, retained or not
+    file_lines: HashMap<String, usize>,
+    boundaries: HashMap<String, ReconnectBoundary>,
+}
+
+/// Log file name for a server
+fn log_key(server_id: &str) -> String {
+    sanitize_component(server_id)
+}
+
+impl ChatLog {
+    /// Load the logs in `dir`, first moving over any from the older
+    /// single-file log at `legacy_path`
+    pub fn load(dir: PathBuf, legacy_path: &Path, cipher: Option<Arc<HistoryCipher>>) -> Self {
+        let mut log = Self {
+            dir: None,
+            cipher,
+            logs: HashMap::new(),
+            file_lines: HashMap::new(),
+            boundaries: HashMap::new(),
+        };
+        if let Err(e) = fs::create_dir_all(&dir) {
+            eprintln!("Failed to create chat log directory: {}", e);
+            return log;
+        }
+
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                eprintln!("Failed to read chat logs: {}", e);
+                return log;
+            }
+        };
+        for path in entries.flatten().map(|entry| entry.path()) {
+            if path.extension().is_none_or(|ext| ext != "jsonl") {
+                continue;
+            }
+            let Some(key) = path.file_stem().and_then(|stem| stem.to_str()).map(str::to_string) else {
+                continue;
+            };
+            let data = match fs::read_to_string(&path) {
+                Ok(data) => data,
+                Err(e) => {
+                    eprintln!("Failed to read chat log {}: {}", path.display(), e);
+                    continue;
+                }
+            };
+
+            let mut lines = Vec::new();
+            let mut count = 0;
+            for line in data.lines().filter(|line| !line.is_empty()) {
+                count += 1;
+                if log.cipher.is_none() && history_crypto::is_sealed_line(line) {
+                    // Writing now would mix this session's lines into a sealed log
+                    eprintln!("Chat logs are encrypted and the key is unavailable; not saving chat this session");
+                    log.logs.clear();
+                    log.file_lines.clear();
+                    return log;
+                }
+                match history_crypto::open_line(line, log.cipher.as_deref())
+                    .and_then(|json| serde_json::from_str::<ChatLogEntry>(&json).map_err(|e| e.to_string()))
+                {
+                    Ok(entry) => lines.push(entry),
+                    Err(e) => eprintln!("Skipping unreadable line in chat log {}: {}", path.display(), e),
+                }
+            }
+            if lines.len() > MAX_LINES_PER_SERVER {
+                lines.drain(..lines.len() - MAX_LINES_PER_SERVER);
+            }
+            log.logs.insert(key.clone(), lines);
+            log.file_lines.insert(key, count);
+        }
+
+        log.dir = Some(dir);
+        if legacy_path.exists() {
+            log.migrate(legacy_path);
+        }
+        log
+    }
+
+    /// Move public chat from the older single-file log into per-server logs
+    fn migrate(&mut self, legacy_path: &Path) {
+        let legacy: HashMap<String, Vec<ChatLogEntry>> = match history_crypto::read_history(legacy_path, self.cipher.as_deref())
+            .and_then(|data| serde_json::from_str(&data).map_err(|e| e.to_string()))
+        {
+            Ok(legacy) => legacy,
+            Err(e) => {
+                // Leave the file for a later session that can read it
+                eprintln!("Failed to migrate chat log: {}", e);
+                return;
+            }
+        };
+
+        for (server_id, entries) in legacy {
+            let key = log_key(&server_id);
+            let lines = self.logs.entry(key.clone()).or_default();
+            let mut merged: Vec<ChatLogEntry> = entries
+                .into_iter()
+                .enumerate()
+                .map(|(i, entry)| ChatLogEntry { seq: i as u64 + 1, ..entry })
+                .collect();
+            let offset = merged.len() as u64;
+            merged.extend(lines.drain(..).map(|entry| ChatLogEntry { seq: entry.seq + offset, ..entry }));
+            *lines = merged;
+            if let Err(e) = self.rewrite(&key) {
+                eprintln!("Failed to migrate chat log for {}: {}", server_id, e);
+                return;
+            }
+        }
+        if let Err(e) = fs::remove_file(legacy_path) {
+            eprintln!("Failed to remove migrated chat log: {}", e);
+        }
+        println!("Moved chat log to per-server history files");
+    }
+
+    /// Switch encryption on (`Some`) or off and rewrite the logs to match
+    pub fn set_cipher(&mut self, cipher: Option<Arc<HistoryCipher>>) -> Result<(), String> {
+        self.cipher = cipher;
+        let keys: Vec<String> = self.logs.keys().cloned().collect();
+        for key in keys {
+            self.rewrite(&key)?;
+        }
+        Ok(())
+    }
+
+    fn log_path(&self, key: &str) -> Option<PathBuf> {
+        self.dir.as_ref().map(|dir| dir.join(format!("{}.jsonl", key)))
+    }
+
+    fn encode(&self, entry: &ChatLogEntry) -> Result<String, String> {
+        let json = serde_json::to_string(entry)
+            .map_err(|e| format!("Failed to serialize chat log: {}", e))?;
+        history_crypto::seal_line(&json, self.cipher.as_deref())
+    }
+
+    /// Append one line to a server's log file, compacting it when it has grown
+    fn write(&mut self, key: &str, entry: &ChatLogEntry) -> Result<(), String> {
+        let Some(path) = self.log_path(key) else {
+            return Ok(());
+        };
+        let line = self.encode(entry)?;
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .and_then(|mut file| writeln!(file, "{}", line))
+            .map_err(|e| format!("Failed to write chat log: {}", e))?;
+
+        let count = self.file_lines.entry(key.to_string()).or_default();
+        *count += 1;
+        if *count > 2 * MAX_LINES_PER_SERVER {
+            self.rewrite(key)?;
+        }
+        Ok(())
+    }
+
+    /// Replace a server's log file with its retained lines
+    fn rewrite(&mut self, key: &str) -> Result<(), String> {
+        let Some(path) = self.log_path(key) else {
+            return Ok(());
+        };
+        let lines = self.logs.get(key).map(Vec::as_slice).unwrap_or_default();
+        let mut contents = String::new();
+        for entry in lines {
+            contents.push_str(&self.encode(entry)?);
+            contents.push('\n');
+        }
+        fs::write(&path, contents).map_err(|e| format!("Failed to write chat log: {}", e))?;
+        self.file_lines.insert(key.to_string(), lines.len());
+        Ok(())
+    }
+
+    /// Note that the connection to `server_id` dropped
+    pub fn mark_disconnected(&mut self, server_id: &str) {
+        let key = log_key(server_id);
+        let seq = self.last_seq(&key);
+        self.boundaries.insert(key, ReconnectBoundary {
+            seq,
+            logged_in_at: None,
+            matched: HashSet::new(),
+        });
+    }
+
+    /// Note that the client logged back in, opening the replay window
+    pub fn mark_logged_in(&mut self, server_id: &str) {
+        if let Some(boundary) = self.boundaries.get_mut(&log_key(server_id)) {
+            boundary.logged_in_at.get_or_insert_with(now_secs);
+        }
+    }
+
+    /// Log a public chat line. Returns its sequence number, or None when it
+    /// was a replay of a line already logged before a reconnect.
+    pub fn append(&mut self, server_id: &str, user_id: u16, user_name: &str, message: &str) -> Result<Option<u64>, String> {
+        let key = log_key(server_id);
+        let Some(seq) = self.append_at(&key, user_id, user_name, message, now_secs()) else {
+            return Ok(None);
+        };
+        self.write_last(&key)?;
+        Ok(Some(seq))
+    }
+
+    /// Log a private message with `user_id`, sent or received
+    pub fn append_private(&mut self, server_id: &str, user_id: u16, user_name: &str, message: &str, outgoing: bool) -> Result<u64, String> {
+        let key = log_key(server_id);
+        let seq = self.push(&key, ChatLogEntry {
+            seq: 0,
+            kind: ChatLogKind::Private,
+            user_id,
+            user_name: user_name.to_string(),
+            message: message.to_string(),
+            timestamp: now_secs(),
+            outgoing,
+        });
+        self.write_last(&key)?;
+        Ok(seq)
+    }
+
+    /// Log a broadcast from the server or an administrator
+    pub fn append_broadcast(&mut self, server_id: &str, message: &str) -> Result<u64, String> {
+        let key = log_key(server_id);
+        let seq = self.push(&key, ChatLogEntry {
+            seq: 0,
+            kind: ChatLogKind::Broadcast,
+            user_id: 0,
+            user_name: String::new(),
+            message: message.to_string(),
+            timestamp: now_secs(),
+            outgoing: false,
+        });
+        self.write_last(&key)?;
+        Ok(seq)
+    }
+
+    fn write_last(&mut self, key: &str) -> Result<(), String> {
+        let Some(entry) = self.logs.get(key).and_then(|lines| lines.last()).cloned() else {
+            return Ok(());
+        };
+        self.write(key, &entry)
+    }
+
+    fn append_at(&mut self, key: &str, user_id: u16, user_name: &str, message: &str, now: u64) -> Option<u64> {
+        if self.is_replay(key, user_name, message, now) {
+            println!("Dropping chat line replayed after reconnect: {}: {}", user_name, message);
+            return None;
+        }
+
+        Some(self.push(key, ChatLogEntry {
+            seq: 0,
+            kind: ChatLogKind::Chat,
+            user_id,
+            user_name: user_name.to_string(),
+            message: message.to_string(),
+            timestamp: now,
+            outgoing: false,
+        }))
+    }
+
+    /// Retain a line under the next sequence number and return that number
+    fn push(&mut self, key: &str, mut entry: ChatLogEntry) -> u64 {
+        entry.seq = self.last_seq(key) + 1;
+        let seq = entry.seq;
+        let lines = self.logs.entry(key.to_string()).or_default();
+        lines.push(entry);
+        if lines.len() > MAX_LINES_PER_SERVER {
+            let excess = lines.len() - MAX_LINES_PER_SERVER;
+            lines.drain(..excess);
+        }
+        seq
+    }
+
+    /// Match a chat line against those logged shortly before the last
+    /// disconnect, while the replay window is open
+    fn is_replay(&mut self, key: &str, user_name: &str, message: &str, now: u64) -> bool {
+        let Some(boundary) = self.boundaries.get_mut(key) else {
+            return false;
+        };
+        let Some(logged_in_at) = boundary.logged_in_at else {
+            return false;
+        };
+        if now.saturating_sub(logged_in_at) > REPLAY_WINDOW_SECS {
+            self.boundaries.remove(key);
+            return false;
+        }
+
+        let lines = self.logs.get(key).map(Vec::as_slice).unwrap_or_default();
+        let earlier = lines.iter().rev().skip_while(|line| line.seq > boundary.seq);
+        let horizon = logged_in_at.saturating_sub(REPLAY_HORIZON_SECS);
+        let original = earlier
+            .take_while(|line| line.timestamp >= horizon)
+            .find(|line| {
+                line.kind == ChatLogKind::Chat
+                    && !boundary.matched.contains(&line.seq)
+                    && line.user_name == user_name
+                    && line.message == message
+            });
+        match original {
+            Some(line) => {
+                boundary.matched.insert(line.seq);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn last_seq(&self, key: &str) -> u64 {
+        self.logs
+            .get(key)
+            .and_then(|lines| lines.last())
+            .map(|line| line.seq)
+            .unwrap_or(0)
+    }
+
+    /// Up to `limit` lines for a server from before sequence number `before`
+    /// (or the newest, without it), oldest first
+    pub fn history(&self, server_id: &str, limit: Option<usize>, before: Option<u64>) -> Vec<ChatLogEntry> {
+        let Some(lines) = self.logs.get(&log_key(server_id)) else {
+            return Vec::new();
+        };
+        let end = match before {
+            Some(before) => lines.partition_point(|line| line.seq < before),
+            None => lines.len(),
+        };
+        let start = end.saturating_sub(limit.unwrap_or(DEFAULT_HISTORY_LIMIT));
+        lines[start..end].to_vec()
+    }
+
+    /// Forget one server's history, or every server's
+    pub fn clear(&mut self, server_id: Option<&str>) -> Result<(), String> {
+        let keys: Vec<String> = match server_id {
+            Some(server_id) => vec![log_key(server_id)],
+            None => self.logs.keys().cloned().collect(),
+        };
+        for key in keys {
+            self.logs.remove(&key);
+            self.file_lines.remove(&key);
+            self.boundaries.remove(&key);
+            if let Some(path) = self.log_path(&key).filter(|path| path.exists()) {
+                fs::remove_file(&path).map_err(|e| format!("Failed to clear chat history: {}", e))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn memory_log() -> ChatLog {
+        ChatLog {
+            dir: None,
+            cipher: None,
+            logs: HashMap::new(),
+            file_lines: HashMap::new(),
+            boundaries: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn keeps_most_recent_lines_per_server() {
+        let mut log = memory_log();
+        for i in 0..(MAX_LINES_PER_SERVER + 5) {
+            log.append("srv", 1, "bob", &format!("line {}", i)).unwrap();
+        }
+        log.append("other", 2, "amy", "hi").unwrap();
+
+        let history = log.history("srv", Some(MAX_LINES_PER_SERVER * 2), None);
+        assert_eq!(history.len(), MAX_LINES_PER_SERVER);
+        assert_eq!(history[0].message, "line 5");
+        assert_eq!(log.history("other", None, None).len(), 1);
+        assert!(log.history("missing", None, None).is_empty());
+    }
+
+    #[test]
+    fn pages_back_through_history() {
+        let mut log = memory_log();
+        for i in 0..10 {
+            log.append("srv", 1, "bob", &format!("line {}", i)).unwrap();
+        }
+        log.append_private("srv", 2, "amy", "psst", false).unwrap();
+        log.append_broadcast("srv", "Server going down").unwrap();
+
+        let newest = log.history("srv", Some(3), None);
+        let seqs: Vec<u64> = newest.iter().map(|line| line.seq).collect();
+        assert_eq!(seqs, vec![10, 11, 12]);
+        assert_eq!(newest[1].kind, ChatLogKind::Private);
+        assert_eq!(newest[2].kind, ChatLogKind::Broadcast);
+
+        let older = log.history("srv", Some(3), Some(10));
+        let seqs: Vec<u64> = older.iter().map(|line| line.seq).collect();
+        assert_eq!(seqs, vec![7, 8, 9]);
+        assert_eq!(log.history("srv", Some(5), Some(3)).len(), 2);
+
+        log.clear(Some("srv")).unwrap();
+        assert!(log.history("srv", None, None).is_empty());
+    }
+
+    #[test]
+    fn appends_to_and_reloads_per_server_files() {
+        let dir = std::env::temp_dir().join(format!("chat-log-test-{}", std::process::id()));
+        let legacy = dir.join("chat_log.json");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&legacy, r#"{"srv":[{"userId":1,"userName":"bob","message":"old","timestamp":5}]}"#).unwrap();
+
+        let cipher = Arc::new(HistoryCipher::from_key(&[9u8; 32]).unwrap());
+        let mut log = ChatLog::load(dir.join("logs"), &legacy, Some(Arc::clone(&cipher)));
+        assert!(!legacy.exists());
+        log.append("srv", 1, "bob", "new").unwrap();
+        log.append_private("srv", 2, "me", "hello", true).unwrap();
+
+        let reloaded = ChatLog::load(dir.join("logs"), &legacy, Some(cipher));
+        let history = reloaded.history("srv", None, None);
+        let messages: Vec<&str> = history.iter().map(|line| line.message.as_str()).collect();
+        assert_eq!(messages, vec!["old", "new", "hello"]);
+        assert_eq!(history[2].seq, 3);
+        assert!(history[2].outgoing);
+
+        // Sealed logs can't be read, or added to, without the key
+        let locked = ChatLog::load(dir.join("logs"), &legacy, None);
+        assert!(locked.dir.is_none() && locked.history("srv", None, None).is_empty());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn drops_lines_replayed_after_a_reconnect() {
+        let mut log = memory_log();
+        assert_eq!(log.append_at("srv", 1, "bob", "hello", 1_000), Some(1));
+        assert_eq!(log.append_at("srv", 2, "amy", "hi bob", 1_010), Some(2));
+
+        log.mark_disconnected("srv");
+        log.boundaries.get_mut("srv").unwrap().logged_in_at = Some(1_100);
+
+        // The server replays both lines, then chat carries on
+        assert_eq!(log.append_at("srv", 1, "bob", "hello", 1_101), None);
+        assert_eq!(log.append_at("srv", 2, "amy", "hi bob", 1_101), None);
+        assert_eq!(log.append_at("srv", 1, "bob", "hello", 1_105), Some(3));
+        assert_eq!(log.append_at("srv", 2, "amy", "welcome back", 1_106), Some(4));
+
+        // Once the window closes, repeats are ordinary lines again
+        assert_eq!(log.append_at("srv", 2, "amy", "hi bob", 1_100 + REPLAY_WINDOW_SECS + 1), Some(5));
+
+        let seqs: Vec<u64> = log.history("srv", None, None).iter().map(|line| line.seq).collect();
+        assert_eq!(seqs, vec![1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn repeats_pass_without_a_reconnect_or_before_logging_in() {
+        let mut log = memory_log();
+        log.append_at("srv", 1, "bob", "lol", 1_000);
+        assert_eq!(log.append_at("srv", 1, "bob", "lol", 1_001), Some(2));
+
+        log.mark_disconnected("srv");
+        assert_eq!(log.append_at("srv", 1, "bob", "lol", 1_002), Some(3));
+
+        // Lines from long before the disconnect aren't replays
+        log.mark_disconnected("srv");
+        log.boundaries.get_mut("srv").unwrap().logged_in_at = Some(1_003 + REPLAY_HORIZON_SECS + 1);
+        assert_eq!(log.append_at("srv", 1, "bob", "lol", 1_003 + REPLAY_HORIZON_SECS + 2), Some(4));
+    }
+}
@@ -0,0 +1,78 @@
+// Actions run on connect
+//
+// Regulars often do the same few things every time they join a server: say
+// hello, open the folder they always browse, read the board. A bookmark can
+// list these as `onConnect` actions. They run once per connect (not after an
+// automatic reconnect), after login and, when the server shows an agreement,
+// only once it's accepted. A chat greeting just goes out; the folder and the
+// board are kept here for the server window, which may open after the actions
+// finish and so asks for them with `take_connect_outcome`.
+
+use crate::protocol::client::board::BoardPage;
+use crate::protocol::types::ConnectAction;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// What the actions left for the server window to show
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectOutcome {
+    /// Folder to show in the Files tab
+    pub folder: Option<Vec<String>>,
+    pub board: Option<BoardPage>,
+}
+
+#[derive(Default)]
+pub struct ConnectActions {
+    /// Actions waiting for the agreement to be accepted
+    held: HashMap<String, Vec<ConnectAction>>,
+    outcomes: HashMap<String, ConnectOutcome>,
+}
+
+impl ConnectActions {
+    pub fn hold(&mut self, server_id: &str, actions: Vec<ConnectAction>) {
+        if actions.is_empty() {
+            self.held.remove(server_id);
+        } else {
+            self.held.insert(server_id.to_string(), actions);
+        }
+    }
+
+    /// Hand over a server's held actions so they can run
+    pub fn release(&mut self, server_id: &str) -> Vec<ConnectAction> {
+        self.held.remove(server_id).unwrap_or_default()
+    }
+
+    pub fn finish(&mut self, server_id: &str, outcome: ConnectOutcome) {
+        self.outcomes.insert(server_id.to_string(), outcome);
+    }
+
+    /// The outcome of the last run, once; None until the actions finish
+    pub fn take_outcome(&mut self, server_id: &str) -> Option<ConnectOutcome> {
+        self.outcomes.remove(server_id)
+    }
+
+    pub fn forget(&mut self, server_id: &str) {
+        self.held.remove(server_id);
+        self.outcomes.remove(server_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn actions_are_released_and_outcomes_taken_once() {
+        let mut actions = ConnectActions::default();
+        actions.hold("a", vec![ConnectAction::FetchNews]);
+        actions.hold("b", Vec::new());
+        assert_eq!(actions.release("a"), vec![ConnectAction::FetchNews]);
+        assert!(actions.release("a").is_empty() && actions.release("b").is_empty());
+
+        let folder = Some(vec!["Uploads".to_string()]);
+        actions.finish("a", ConnectOutcome { folder: folder.clone(), board: None });
+        assert_eq!(actions.take_outcome("a").unwrap().folder, folder);
+        assert!(actions.take_outcome("a").is_none());
+    }
+}
@@ -0,0 +1,324 @@
+// Private message conversation tracking
+//
+// Groups private messages into one conversation per (server, user) pair so the
+// frontend can render PM windows with unread counts instead of re-grouping raw
+// `private-message-*` events itself. Each message keeps the nickname the user
+// had when it was sent or received, so threads stay readable after the user
+// disconnects and their id is handed to someone else. Conversations are
+// persisted to conversations.json in the app data directory.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::history_crypto::{self, HistoryCipher};
+
+/// Maximum number of messages retained per conversation on disk
+const MAX_MESSAGES_PER_CONVERSATION: usize = 500;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PrivateMessageEntry {
+    pub user_id: u16,
+    pub user_name: String,
+    pub message: String,
+    pub outgoing: bool,
+    pub timestamp: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Conversation {
+    pub server_id: String,
+    pub user_id: u16,
+    pub user_name: String,
+    pub unread_count: u32,
+    #[serde(default)]
+    pub messages: Vec<PrivateMessageEntry>,
+}
+
+/// Conversation as returned to the frontend: participant, unread count and
+/// the most recent message, without the full message history.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversationSummary {
+    pub server_id: String,
+    pub user_id: u16,
+    pub user_name: String,
+    pub unread_count: u32,
+    pub last_message: Option<PrivateMessageEntry>,
+}
+
+impl Conversation {
+    pub fn summary(&self) -> ConversationSummary {
+        ConversationSummary {
+            server_id: self.server_id.clone(),
+            user_id: self.user_id,
+            user_name: self.user_name.clone(),
+            unread_count: self.unread_count,
+            last_message: self.messages.last().cloned(),
+        }
+    }
+
+    fn last_timestamp(&self) -> u64 {
+        self.messages.last().map(|m| m.timestamp).unwrap_or(0)
+    }
+}
+
+pub struct ConversationStore {
+    path: Option<PathBuf>,
+    cipher: Option<Arc<HistoryCipher>>,
+    conversations: HashMap<(String, u16), Conversation>,
+}
+
+pub fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+impl ConversationStore {
+    /// Load conversations from disk, starting empty if the file is missing or unreadable
+    pub fn load(path: PathBuf, cipher: Option<Arc<HistoryCipher>>) -> Self {
+        let mut conversations = HashMap::new();
+
+        if cipher.is_none() && history_crypto::is_sealed(&path) {
+            // Writing now would replace the sealed history with this session's messages
+            eprintln!("Conversations are encrypted and the key is unavailable; not saving messages this session");
+            return Self {
+                path: None,
+                cipher,
+                conversations,
+            };
+        }
+
+        if path.exists() {
+            match history_crypto::read_history(&path, cipher.as_deref())
+                .and_then(|data| serde_json::from_str::<Vec<Conversation>>(&data).map_err(|e| e.to_string()))
+            {
+                Ok(list) => {
+                    for conversation in list {
+                        conversations.insert((conversation.server_id.clone(), conversation.user_id), conversation);
+                    }
+                }
+                Err(e) => eprintln!("Failed to load conversations: {}", e),
+            }
+        }
+
+        Self {
+            path: Some(path),
+            cipher,
+            conversations,
+        }
+    }
+
+    #[cfg(test)]
+    fn in_memory() -> Self {
+        Self {
+            path: None,
+            cipher: None,
+            conversations: HashMap::new(),
+        }
+    }
+
+    /// Switch encryption on (`Some`) or off and rewrite the file to match
+    pub fn set_cipher(&mut self, cipher: Option<Arc<HistoryCipher>>) -> Result<(), String> {
+        self.cipher = cipher;
+        self.save()
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        let list: Vec<&Conversation> = self.conversations.values().collect();
+        let json = serde_json::to_string_pretty(&list)
+            .map_err(|e| format!("Failed to serialize conversations: {}", e))?;
+        history_crypto::write_history(path, &json, self.cipher.as_deref())
+            .map_err(|e| format!("Failed to write conversations: {}", e))
+    }
+
+    fn conversation_mut(&mut self, server_id: &str, user_id: u16, user_name: &str) -> &mut Conversation {
+        let conversation = self
+            .conversations
+            .entry((server_id.to_string(), user_id))
+            .or_insert_with(|| Conversation {
+                server_id: server_id.to_string(),
+                user_id,
+                user_name: user_name.to_string(),
+                unread_count: 0,
+                messages: Vec::new(),
+            });
+
+        // Keep the participant name current when the server tells us about it
+        if !user_name.is_empty() {
+            conversation.user_name = user_name.to_string();
+        }
+
+        conversation
+    }
+
+    fn push(conversation: &mut Conversation, entry: PrivateMessageEntry) {
+        conversation.messages.push(entry);
+        if conversation.messages.len() > MAX_MESSAGES_PER_CONVERSATION {
+            let excess = conversation.messages.len() - MAX_MESSAGES_PER_CONVERSATION;
+            conversation.messages.drain(..excess);
+        }
+    }
+
+    /// Record a message received from another user, bumping the unread count
+    pub fn record_incoming(&mut self, server_id: &str, user_id: u16, user_name: &str, message: &str) -> ConversationSummary {
+        let conversation = self.conversation_mut(server_id, user_id, user_name);
+        let entry = PrivateMessageEntry {
+            user_id,
+            user_name: conversation.user_name.clone(),
+            message: message.to_string(),
+            outgoing: false,
+            timestamp: now_secs(),
+        };
+        Self::push(conversation, entry);
+        conversation.unread_count += 1;
+        conversation.summary()
+    }
+
+    /// Record a message we sent to another user. `user_name` is the
+    /// recipient's current nickname, or empty if it isn't known.
+    pub fn record_outgoing(&mut self, server_id: &str, user_id: u16, user_name: &str, sender_name: &str, message: &str) -> ConversationSummary {
+        let conversation = self.conversation_mut(server_id, user_id, user_name);
+        let entry = PrivateMessageEntry {
+            user_id,
+            user_name: sender_name.to_string(),
+            message: message.to_string(),
+            outgoing: true,
+            timestamp: now_secs(),
+        };
+        Self::push(conversation, entry);
+        conversation.summary()
+    }
+
+    /// Clear the unread count for a conversation. Returns false if it doesn't exist.
+    pub fn mark_read(&mut self, server_id: &str, user_id: u16) -> bool {
+        match self.conversations.get_mut(&(server_id.to_string(), user_id)) {
+            Some(conversation) => {
+                conversation.unread_count = 0;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The message a new one to `user_id` answers: their last message, if
+    /// nothing has been sent to them since
+    pub fn reply_quote(&self, server_id: &str, user_id: u16) -> Option<String> {
+        let conversation = self.conversations.get(&(server_id.to_string(), user_id))?;
+        conversation
+            .messages
+            .last()
+            .filter(|m| !m.outgoing)
+            .map(|m| m.message.clone())
+    }
+
+    /// Full message history of one conversation
+    pub fn thread(&self, server_id: &str, user_id: u16) -> Option<Conversation> {
+        self.conversations.get(&(server_id.to_string(), user_id)).cloned()
+    }
+
+    /// List conversations, most recently active first, optionally limited to one server
+    pub fn list(&self, server_id: Option<&str>) -> Vec<ConversationSummary> {
+        let mut conversations: Vec<&Conversation> = self
+            .conversations
+            .values()
+            .filter(|c| server_id.is_none_or(|id| c.server_id == id))
+            .collect();
+        conversations.sort_by(|a, b| b.last_timestamp().cmp(&a.last_timestamp()).then(a.user_id.cmp(&b.user_id)));
+        conversations.into_iter().map(|c| c.summary()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn incoming_messages_increment_unread() {
+        let mut store = ConversationStore::in_memory();
+        store.record_incoming("srv", 7, "alice", "hi");
+        let summary = store.record_incoming("srv", 7, "alice", "are you there?");
+
+        assert_eq!(summary.unread_count, 2);
+        assert_eq!(summary.user_name, "alice");
+        assert_eq!(summary.last_message.unwrap().message, "are you there?");
+    }
+
+    #[test]
+    fn outgoing_messages_do_not_count_as_unread() {
+        let mut store = ConversationStore::in_memory();
+        store.record_incoming("srv", 7, "alice", "hi");
+        let summary = store.record_outgoing("srv", 7, "", "me", "hello");
+
+        assert_eq!(summary.unread_count, 1);
+        assert_eq!(summary.user_name, "alice");
+        assert!(summary.last_message.unwrap().outgoing);
+    }
+
+    #[test]
+    fn thread_keeps_names_from_the_time_of_each_message() {
+        let mut store = ConversationStore::in_memory();
+        store.record_outgoing("srv", 7, "alice", "me", "hi alice");
+        store.record_incoming("srv", 7, "alice", "hey");
+        store.record_incoming("srv", 7, "alice_away", "brb");
+
+        let thread = store.thread("srv", 7).unwrap();
+        assert_eq!(thread.user_name, "alice_away");
+        let names: Vec<&str> = thread.messages.iter().map(|m| m.user_name.as_str()).collect();
+        assert_eq!(names, vec!["me", "alice", "alice_away"]);
+        assert!(store.thread("srv", 8).is_none());
+    }
+
+    #[test]
+    fn quotes_only_unanswered_messages() {
+        let mut store = ConversationStore::in_memory();
+        assert_eq!(store.reply_quote("srv", 7), None);
+        store.record_incoming("srv", 7, "alice", "lunch?");
+        assert_eq!(store.reply_quote("srv", 7).as_deref(), Some("lunch?"));
+        store.record_outgoing("srv", 7, "alice", "me", "sure");
+        assert_eq!(store.reply_quote("srv", 7), None);
+    }
+
+    #[test]
+    fn mark_read_resets_unread_count() {
+        let mut store = ConversationStore::in_memory();
+        store.record_incoming("srv", 7, "alice", "hi");
+
+        assert!(store.mark_read("srv", 7));
+        assert!(!store.mark_read("srv", 8));
+        assert_eq!(store.list(Some("srv"))[0].unread_count, 0);
+    }
+
+    #[test]
+    fn list_filters_by_server() {
+        let mut store = ConversationStore::in_memory();
+        store.record_incoming("a", 1, "alice", "hi");
+        store.record_incoming("b", 1, "bob", "yo");
+
+        assert_eq!(store.list(None).len(), 2);
+        let only_b = store.list(Some("b"));
+        assert_eq!(only_b.len(), 1);
+        assert_eq!(only_b[0].user_name, "bob");
+    }
+
+    #[test]
+    fn history_is_capped() {
+        let mut store = ConversationStore::in_memory();
+        for i in 0..(MAX_MESSAGES_PER_CONVERSATION + 10) {
+            store.record_incoming("srv", 1, "alice", &format!("msg {}", i));
+        }
+        let conversation = store.conversations.get(&("srv".to_string(), 1)).unwrap();
+        assert_eq!(conversation.messages.len(), MAX_MESSAGES_PER_CONVERSATION);
+        assert_eq!(conversation.messages[0].message, "msg 10");
+    }
+}
@@ -0,0 +1,100 @@
+// Failures log
+//
+// The last few hundred things that went wrong, across every server: failed
+// connections and dropped ones, requests the server answered with an error
+// or didn't answer, failed transfers and tracker fetches. Each entry keeps
+// its category apart so a flaky transfer path isn't mistaken for a server
+// refusing requests, and notes whether trying again could help. Kept in
+// memory for the Problems panel and written into diagnostics bundles.
+
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use super::conversations::now_secs;
+use super::diagnostics::redact_text;
+
+const MAX_FAILURES: usize = 300;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FailureCategory {
+    /// Connecting or staying connected
+    Connection,
+    /// The server replied to a request with an error, or not at all
+    Protocol,
+    /// A file or folder transfer
+    Transfer,
+    Tracker,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Failure {
+    pub timestamp: u64,
+    /// What was being done, e.g. "download" or "get file list"
+    pub operation: String,
+    pub server: Option<String>,
+    pub category: FailureCategory,
+    /// Whether the same thing might work if tried again
+    pub retryable: bool,
+    pub error: String,
+}
+
+#[derive(Default)]
+pub struct FailureLog {
+    failures: Mutex<VecDeque<Failure>>,
+}
+
+impl FailureLog {
+    pub fn record(&self, category: FailureCategory, operation: &str, server: Option<&str>, retryable: bool, error: &str) {
+        let mut failures = self.failures.lock().unwrap();
+        failures.push_back(Failure {
+            timestamp: now_secs(),
+            operation: operation.to_string(),
+            server: server.map(str::to_string),
+            category,
+            retryable,
+            error: redact_text(error),
+        });
+        while failures.len() > MAX_FAILURES {
+            failures.pop_front();
+        }
+    }
+
+    /// Newest first, optionally only one server's, at most `limit` of them
+    pub fn recent(&self, server: Option<&str>, limit: Option<usize>) -> Vec<Failure> {
+        self.failures
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .filter(|failure| server.is_none() || failure.server.as_deref() == server)
+            .take(limit.unwrap_or(MAX_FAILURES))
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_newest_failures_per_server() {
+        let log = FailureLog::default();
+        for n in 0..MAX_FAILURES + 5 {
+            log.record(FailureCategory::Transfer, "download", Some("a"), true, &format!("failure {}", n));
+        }
+        log.record(FailureCategory::Protocol, "get file list", Some("b"), false, "password=hunter2 rejected");
+
+        let recent = log.recent(None, Some(2));
+        assert_eq!(recent[0].server.as_deref(), Some("b"));
+        assert_eq!(recent[0].error, "password=[redacted] rejected");
+        assert_eq!(recent[1].error, format!("failure {}", MAX_FAILURES + 4));
+
+        let only_a = log.recent(Some("a"), None);
+        assert_eq!(only_a.len(), MAX_FAILURES - 1);
+        assert!(only_a.iter().all(|failure| failure.category == FailureCategory::Transfer));
+    }
+}
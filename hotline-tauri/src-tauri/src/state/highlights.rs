@@ -0,0 +1,222 @@
+// Chat highlight rules
+//
+// User-defined rules (keywords, regular expressions, messages from specific
+// users) evaluated against incoming chat and private messages. Matching happens
+// here in the event pipeline so the frontend only has to render the result.
+// Rules are persisted to highlight_rules.json in the app data directory.
+
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use super::json_store;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum HighlightKind {
+    Keyword,
+    Regex,
+    FromUser,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HighlightRule {
+    pub id: String,
+    pub kind: HighlightKind,
+    pub pattern: String,
+    #[serde(default)]
+    pub case_sensitive: bool,
+    /// Escalate matches to a notification rather than only tagging the message
+    #[serde(default)]
+    pub notify: bool,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// Result of evaluating a message against the rule set
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct HighlightMatch {
+    pub rule_ids: Vec<String>,
+    pub notify: bool,
+}
+
+enum Matcher {
+    Keyword { needle: String, case_sensitive: bool },
+    Regex(Regex),
+    FromUser { name: String, case_sensitive: bool },
+}
+
+impl Matcher {
+    fn compile(rule: &HighlightRule) -> Result<Self, String> {
+        match rule.kind {
+            HighlightKind::Keyword => Ok(Matcher::Keyword {
+                needle: if rule.case_sensitive { rule.pattern.clone() } else { rule.pattern.to_lowercase() },
+                case_sensitive: rule.case_sensitive,
+            }),
+            HighlightKind::Regex => RegexBuilder::new(&rule.pattern)
+                .case_insensitive(!rule.case_sensitive)
+                .build()
+                .map(Matcher::Regex)
+                .map_err(|e| format!("Invalid regex in highlight rule {}: {}", rule.id, e)),
+            HighlightKind::FromUser => Ok(Matcher::FromUser {
+                name: rule.pattern.trim().to_string(),
+                case_sensitive: rule.case_sensitive,
+            }),
+        }
+    }
+
+    fn is_match(&self, user_name: &str, message: &str) -> bool {
+        match self {
+            Matcher::Keyword { needle, case_sensitive } => {
+                if needle.is_empty() {
+                    false
+                } else if *case_sensitive {
+                    message.contains(needle.as_str())
+                } else {
+                    message.to_lowercase().contains(needle.as_str())
+                }
+            }
+            Matcher::Regex(regex) => regex.is_match(message),
+            Matcher::FromUser { name, case_sensitive } => {
+                if *case_sensitive {
+                    user_name.trim() == name
+                } else {
+                    user_name.trim().eq_ignore_ascii_case(name)
+                }
+            }
+        }
+    }
+}
+
+pub struct HighlightEngine {
+    path: Option<PathBuf>,
+    rules: Vec<HighlightRule>,
+    matchers: Vec<Matcher>,
+}
+
+impl HighlightEngine {
+    /// Load rules from disk. Rules that fail to compile are kept but never match.
+    pub fn load(path: PathBuf) -> Self {
+        let rules = json_store::load_json_or_default::<Vec<HighlightRule>>(&path, "highlight rules");
+
+        let matchers = rules
+            .iter()
+            .map(|rule| {
+                Matcher::compile(rule).unwrap_or_else(|e| {
+                    eprintln!("{}", e);
+                    Matcher::Keyword { needle: String::new(), case_sensitive: true }
+                })
+            })
+            .collect();
+
+        Self {
+            path: Some(path),
+            rules,
+            matchers,
+        }
+    }
+
+    #[cfg(test)]
+    fn with_rules(rules: Vec<HighlightRule>) -> Result<Self, String> {
+        let mut engine = Self {
+            path: None,
+            rules: Vec::new(),
+            matchers: Vec::new(),
+        };
+        engine.set_rules(rules)?;
+        Ok(engine)
+    }
+
+    pub fn rules(&self) -> Vec<HighlightRule> {
+        self.rules.clone()
+    }
+
+    /// Replace the rule set, validating every rule before anything changes
+    pub fn set_rules(&mut self, rules: Vec<HighlightRule>) -> Result<(), String> {
+        let matchers = rules.iter().map(Matcher::compile).collect::<Result<Vec<_>, _>>()?;
+        self.rules = rules;
+        self.matchers = matchers;
+        self.save()
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        json_store::save_json(path, &self.rules, "highlight rules")
+    }
+
+    /// Evaluate a message against all enabled rules
+    pub fn evaluate(&self, user_name: &str, message: &str) -> HighlightMatch {
+        let mut result = HighlightMatch::default();
+
+        for (rule, matcher) in self.rules.iter().zip(self.matchers.iter()) {
+            if rule.enabled && matcher.is_match(user_name, message) {
+                result.rule_ids.push(rule.id.clone());
+                result.notify |= rule.notify;
+            }
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(id: &str, kind: HighlightKind, pattern: &str) -> HighlightRule {
+        HighlightRule {
+            id: id.to_string(),
+            kind,
+            pattern: pattern.to_string(),
+            case_sensitive: false,
+            notify: false,
+            enabled: true,
+        }
+    }
+
+    #[test]
+    fn keyword_matches_case_insensitively() {
+        let engine = HighlightEngine::with_rules(vec![rule("k", HighlightKind::Keyword, "Hotline")]).unwrap();
+        assert_eq!(engine.evaluate("bob", "I love HOTLINE").rule_ids, vec!["k"]);
+        assert!(engine.evaluate("bob", "nothing here").rule_ids.is_empty());
+    }
+
+    #[test]
+    fn regex_and_from_user_rules() {
+        let mut notify_rule = rule("u", HighlightKind::FromUser, "Admin");
+        notify_rule.notify = true;
+        let engine = HighlightEngine::with_rules(vec![
+            rule("r", HighlightKind::Regex, r"\bv\d+\.\d+\b"),
+            notify_rule,
+        ])
+        .unwrap();
+
+        let result = engine.evaluate("admin", "released v1.9 today");
+        assert_eq!(result.rule_ids, vec!["r", "u"]);
+        assert!(result.notify);
+
+        let result = engine.evaluate("guest", "released v1.9 today");
+        assert_eq!(result.rule_ids, vec!["r"]);
+        assert!(!result.notify);
+    }
+
+    #[test]
+    fn disabled_rules_are_skipped() {
+        let mut disabled = rule("k", HighlightKind::Keyword, "hello");
+        disabled.enabled = false;
+        let engine = HighlightEngine::with_rules(vec![disabled]).unwrap();
+        assert!(engine.evaluate("bob", "hello").rule_ids.is_empty());
+    }
+
+    #[test]
+    fn invalid_regex_is_rejected() {
+        assert!(HighlightEngine::with_rules(vec![rule("bad", HighlightKind::Regex, "(unclosed")]).is_err());
+    }
+}
@@ -0,0 +1,230 @@
+// Optional encryption at rest for chat and private message history
+//
+// When switched on, the chat logs and conversations.json are sealed with
+// AES-256-GCM. The key is generated on first use and kept in the OS keychain
+// (macOS Keychain, Windows Credential Manager, the Linux kernel keyring), never
+// next to the files. Sealed files start with a magic header, so plaintext
+// history written before encryption was enabled still loads. Chat logs are
+// appended a line at a time, so each of their lines is sealed on its own and
+// stored as base64; plaintext lines are JSON objects and start with `{`.
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use super::json_store;
+
+const MAGIC: &[u8] = b"HLENC1\0";
+const KEYCHAIN_SERVICE: &str = "com.greg.hotline-navigator";
+const KEYCHAIN_ACCOUNT: &str = "history-key";
+
+pub struct HistoryCipher {
+    key: LessSafeKey,
+}
+
+impl HistoryCipher {
+    pub fn from_key(bytes: &[u8; 32]) -> Result<Self, String> {
+        let key = UnboundKey::new(&AES_256_GCM, bytes).map_err(|_| "Invalid history key".to_string())?;
+        Ok(Self { key: LessSafeKey::new(key) })
+    }
+
+    /// Fetch the history key from the OS keychain, creating it on first use
+    pub fn from_keychain() -> Result<Self, String> {
+        let entry = keyring::Entry::new(KEYCHAIN_SERVICE, KEYCHAIN_ACCOUNT)
+            .map_err(|e| format!("Failed to open keychain: {}", e))?;
+
+        let mut key = [0u8; 32];
+        match entry.get_password() {
+            Ok(encoded) => {
+                let decoded = STANDARD
+                    .decode(encoded)
+                    .map_err(|e| format!("History key in keychain is corrupt: {}", e))?;
+                if decoded.len() != key.len() {
+                    return Err("History key in keychain has the wrong length".to_string());
+                }
+                key.copy_from_slice(&decoded);
+            }
+            Err(keyring::Error::NoEntry) => {
+                SystemRandom::new()
+                    .fill(&mut key)
+                    .map_err(|_| "Failed to generate history key".to_string())?;
+                entry
+                    .set_password(&STANDARD.encode(key))
+                    .map_err(|e| format!("Failed to store history key in keychain: {}", e))?;
+                println!("Created history encryption key in keychain");
+            }
+            Err(e) => return Err(format!("Failed to read history key from keychain: {}", e)),
+        }
+
+        Self::from_key(&key)
+    }
+
+    pub fn seal(&self, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+        let mut nonce = [0u8; NONCE_LEN];
+        SystemRandom::new()
+            .fill(&mut nonce)
+            .map_err(|_| "Failed to generate nonce".to_string())?;
+
+        let mut in_out = plaintext.to_vec();
+        self.key
+            .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::from(MAGIC), &mut in_out)
+            .map_err(|_| "Failed to encrypt history".to_string())?;
+
+        let mut sealed = Vec::with_capacity(MAGIC.len() + NONCE_LEN + in_out.len());
+        sealed.extend_from_slice(MAGIC);
+        sealed.extend_from_slice(&nonce);
+        sealed.extend_from_slice(&in_out);
+        Ok(sealed)
+    }
+
+    pub fn open(&self, sealed: &[u8]) -> Result<Vec<u8>, String> {
+        let body = sealed
+            .strip_prefix(MAGIC)
+            .filter(|body| body.len() >= NONCE_LEN)
+            .ok_or_else(|| "Not an encrypted history file".to_string())?;
+        let (nonce, ciphertext) = body.split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| "Invalid nonce".to_string())?;
+
+        let mut in_out = ciphertext.to_vec();
+        let plaintext = self
+            .key
+            .open_in_place(nonce, Aad::from(MAGIC), &mut in_out)
+            .map_err(|_| "Failed to decrypt history (wrong key or corrupt file)".to_string())?;
+        Ok(plaintext.to_vec())
+    }
+}
+
+/// Whether `path` holds sealed history
+pub fn is_sealed(path: &Path) -> bool {
+    fs::read(path).map(|data| data.starts_with(MAGIC)).unwrap_or(false)
+}
+
+/// Read a history file, decrypting it if it was sealed
+pub fn read_history(path: &Path, cipher: Option<&HistoryCipher>) -> Result<String, String> {
+    let data = fs::read(path).map_err(|e| e.to_string())?;
+    let plaintext = if data.starts_with(MAGIC) {
+        cipher
+            .ok_or_else(|| "History is encrypted but the key is unavailable".to_string())?
+            .open(&data)?
+    } else {
+        data
+    };
+    String::from_utf8(plaintext).map_err(|e| e.to_string())
+}
+
+/// Write a history file, sealing it when a cipher is set
+pub fn write_history(path: &Path, contents: &str, cipher: Option<&HistoryCipher>) -> Result<(), String> {
+    let data = match cipher {
+        Some(cipher) => cipher.seal(contents.as_bytes())?,
+        None => contents.as_bytes().to_vec(),
+    };
+    fs::write(path, data).map_err(|e| e.to_string())
+}
+
+/// Whether one line of a line-per-record history file is sealed
+pub fn is_sealed_line(line: &str) -> bool {
+    !line.starts_with('{')
+}
+
+/// Encode one record for a line-per-record history file, sealing it when a
+/// cipher is set
+pub fn seal_line(record: &str, cipher: Option<&HistoryCipher>) -> Result<String, String> {
+    match cipher {
+        Some(cipher) => Ok(STANDARD.encode(cipher.seal(record.as_bytes())?)),
+        None => Ok(record.to_string()),
+    }
+}
+
+/// Decode a line written by seal_line
+pub fn open_line(line: &str, cipher: Option<&HistoryCipher>) -> Result<String, String> {
+    if !is_sealed_line(line) {
+        return Ok(line.to_string());
+    }
+    let cipher = cipher.ok_or_else(|| "History is encrypted but the key is unavailable".to_string())?;
+    let sealed = STANDARD.decode(line).map_err(|e| format!("Corrupt history line: {}", e))?;
+    String::from_utf8(cipher.open(&sealed)?).map_err(|e| e.to_string())
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct HistoryEncryption {
+    enabled: bool,
+}
+
+/// Whether history encryption is on, persisted to history_encryption.json
+pub struct HistoryEncryptionSettings {
+    path: PathBuf,
+    settings: HistoryEncryption,
+}
+
+impl HistoryEncryptionSettings {
+    pub fn load(path: PathBuf) -> Self {
+        let settings = json_store::load_json_or_default(&path, "history encryption setting");
+
+        Self { path, settings }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.settings.enabled
+    }
+
+    pub fn set(&mut self, enabled: bool) -> Result<(), String> {
+        let settings = HistoryEncryption { enabled };
+        json_store::save_json(&self.path, &settings, "history encryption setting")?;
+        self.settings = settings;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sealed_history_round_trips_and_rejects_other_keys() {
+        let cipher = HistoryCipher::from_key(&[7u8; 32]).unwrap();
+        let sealed = cipher.seal(b"[{\"message\":\"hi\"}]").unwrap();
+        assert!(sealed.starts_with(MAGIC));
+        assert!(!sealed.windows(2).any(|w| w == b"hi"));
+        assert_eq!(cipher.open(&sealed).unwrap(), b"[{\"message\":\"hi\"}]");
+
+        let other = HistoryCipher::from_key(&[8u8; 32]).unwrap();
+        assert!(other.open(&sealed).is_err());
+        assert!(cipher.open(b"plain json").is_err());
+    }
+
+    #[test]
+    fn plaintext_files_still_load_and_sealed_files_need_the_key() {
+        let path = std::env::temp_dir().join(format!("history-crypto-test-{}.json", std::process::id()));
+        let cipher = HistoryCipher::from_key(&[1u8; 32]).unwrap();
+
+        write_history(&path, "{}", None).unwrap();
+        assert!(!is_sealed(&path));
+        assert_eq!(read_history(&path, Some(&cipher)).unwrap(), "{}");
+
+        write_history(&path, "{\"a\":1}", Some(&cipher)).unwrap();
+        assert!(is_sealed(&path));
+        assert_eq!(read_history(&path, Some(&cipher)).unwrap(), "{\"a\":1}");
+        assert!(read_history(&path, None).is_err());
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn lines_seal_individually() {
+        let cipher = HistoryCipher::from_key(&[3u8; 32]).unwrap();
+        let record = "{\"message\":\"hi\"}";
+
+        let plain = seal_line(record, None).unwrap();
+        assert!(!is_sealed_line(&plain));
+        assert_eq!(open_line(&plain, Some(&cipher)).unwrap(), record);
+
+        let sealed = seal_line(record, Some(&cipher)).unwrap();
+        assert!(is_sealed_line(&sealed) && !sealed.contains('\n'));
+        assert_eq!(open_line(&sealed, Some(&cipher)).unwrap(), record);
+        assert!(open_line(&sealed, None).is_err());
+    }
+}
@@ -0,0 +1,59 @@
+// Hosting a server from the app
+//
+// The configuration of the built-in server (name, port, agreement, shared
+// folders, accounts and what guests may do) and the server itself while it
+// runs. Stored in hosting.json in the app data directory; account passwords
+// are kept there as typed, so it's meant for casual servers among friends.
+// A configuration change takes effect the next time hosting starts.
+
+use crate::protocol::server::{HostingStatus, HotlineServer, ServerConfig};
+use std::path::PathBuf;
+use super::json_store;
+
+pub struct Hosting {
+    path: PathBuf,
+    config: ServerConfig,
+    server: Option<HotlineServer>,
+}
+
+impl Hosting {
+    pub fn load(path: PathBuf) -> Self {
+        let config = json_store::load_json_or_default(&path, "hosting settings");
+
+        Self { path, config, server: None }
+    }
+
+    pub fn config(&self) -> &ServerConfig {
+        &self.config
+    }
+
+    pub fn set_config(&mut self, config: ServerConfig) -> Result<(), String> {
+        config.validate()?;
+        self.config = config;
+        json_store::save_json(&self.path, &self.config, "hosting settings")
+    }
+
+    pub async fn start(&mut self) -> Result<HostingStatus, String> {
+        if self.server.is_some() {
+            return Err("Already hosting".to_string());
+        }
+        let server = HotlineServer::start(self.config.clone()).await?;
+        let status = server.status().await;
+        self.server = Some(server);
+        Ok(status)
+    }
+
+    pub fn stop(&mut self) {
+        if let Some(server) = self.server.take() {
+            server.stop();
+        }
+    }
+
+    /// None while not hosting
+    pub async fn status(&self) -> Option<HostingStatus> {
+        match &self.server {
+            Some(server) => Some(server.status().await),
+            None => None,
+        }
+    }
+}
@@ -0,0 +1,76 @@
+// Disconnecting when idle
+//
+// Small community servers often cap how many people can be connected at
+// once. A bookmark can ask to be disconnected after some minutes without
+// outgoing chat or messages and with no transfers running; a warning event
+// goes out a minute beforehand so the user can stay by doing anything.
+
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+/// How long before disconnecting the warning is sent
+pub const WARNING_LEAD: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdleAction {
+    Stay,
+    Warn,
+    Disconnect,
+}
+
+/// What to do about a connection idle for `idle` with a limit of `limit`.
+/// Transfers count as activity, and the warning is only sent once.
+pub fn idle_action(limit: Duration, idle: Duration, transferring: bool, warned: bool) -> IdleAction {
+    if transferring {
+        IdleAction::Stay
+    } else if idle >= limit {
+        IdleAction::Disconnect
+    } else if !warned && idle + WARNING_LEAD >= limit {
+        IdleAction::Warn
+    } else {
+        IdleAction::Stay
+    }
+}
+
+/// The idle timers of connections whose bookmark sets a limit
+#[derive(Default)]
+pub struct IdleDisconnect {
+    tasks: HashMap<String, JoinHandle<()>>,
+}
+
+impl IdleDisconnect {
+    /// Track the idle timer for a connection, replacing any earlier one
+    pub fn start(&mut self, server_id: &str, task: JoinHandle<()>) {
+        if let Some(previous) = self.tasks.insert(server_id.to_string(), task) {
+            previous.abort();
+        }
+    }
+
+    pub fn stop(&mut self, server_id: &str) {
+        if let Some(task) = self.tasks.remove(server_id) {
+            task.abort();
+        }
+    }
+
+    /// Drop a timer without aborting it, for the timer's own task
+    pub fn forget(&mut self, server_id: &str) {
+        self.tasks.remove(server_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn warns_a_minute_ahead_then_disconnects_unless_transferring() {
+        let limit = Duration::from_secs(600);
+        let at = Duration::from_secs;
+        assert_eq!(idle_action(limit, at(500), false, false), IdleAction::Stay);
+        assert_eq!(idle_action(limit, at(545), false, false), IdleAction::Warn);
+        assert_eq!(idle_action(limit, at(560), false, true), IdleAction::Stay);
+        assert_eq!(idle_action(limit, at(600), false, true), IdleAction::Disconnect);
+        assert_eq!(idle_action(limit, at(900), true, true), IdleAction::Stay);
+    }
+}
@@ -0,0 +1,55 @@
+// JSON settings files in the app data directory
+//
+// Every store keeps its settings as one pretty-printed JSON file. A file
+// that is missing or unreadable falls back to the defaults, so a damaged
+// file never keeps the app from starting.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+/// Read `path`, or the default if it is missing or can't be parsed.
+/// `what` names the file's contents in the logged error.
+pub fn load_json_or_default<T: DeserializeOwned + Default>(path: &Path, what: &str) -> T {
+    if !path.exists() {
+        return T::default();
+    }
+    fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|data| serde_json::from_str(&data).map_err(|e| e.to_string()))
+        .unwrap_or_else(|e| {
+            eprintln!("Failed to load {}: {}", what, e);
+            T::default()
+        })
+}
+
+pub fn save_json<T: Serialize + ?Sized>(path: &Path, value: &T, what: &str) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(value)
+        .map_err(|e| format!("Failed to serialize {}: {}", what, e))?;
+    fs::write(path, json).map_err(|e| format!("Failed to write {}: {}", what, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn missing_or_damaged_files_load_as_default() {
+        let path = std::env::temp_dir().join("hotline-json-store-test.json");
+        let _ = fs::remove_file(&path);
+        let loaded: HashMap<String, u16> = load_json_or_default(&path, "test settings");
+        assert!(loaded.is_empty());
+
+        let settings = HashMap::from([("server".to_string(), 50u16)]);
+        save_json(&path, &settings, "test settings").unwrap();
+        let loaded: HashMap<String, u16> = load_json_or_default(&path, "test settings");
+        assert_eq!(loaded, settings);
+
+        fs::write(&path, "{ not json").unwrap();
+        let loaded: HashMap<String, u16> = load_json_or_default(&path, "test settings");
+        assert!(loaded.is_empty());
+        let _ = fs::remove_file(&path);
+    }
+}
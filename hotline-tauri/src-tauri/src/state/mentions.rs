@@ -0,0 +1,83 @@
+// Mentions
+//
+// A chat line mentions us when it has our nickname as `@nickname`, or one of
+// the user's watch words as a whole word, ignoring case either way. It's
+// detected in the event loop so chat payloads arrive marked and the frontend
+// only decides how to show them. Watch words are stored in
+// mention_keywords.json in the app data directory.
+
+use serde::Serialize;
+use std::path::PathBuf;
+use super::json_store;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MentionKind {
+    Nickname,
+    Keyword,
+}
+
+fn is_word_char(c: Option<char>) -> bool {
+    c.is_some_and(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// Whether `needle` occurs in `haystack` with no word character right after
+/// it, and when `whole_word`, none right before it either
+fn contains_word(haystack: &str, needle: &str, whole_word: bool) -> bool {
+    haystack.match_indices(needle).any(|(at, found)| {
+        (!whole_word || !is_word_char(haystack[..at].chars().next_back()))
+            && !is_word_char(haystack[at + found.len()..].chars().next())
+    })
+}
+
+/// How `message` mentions us, if it does
+pub fn detect(message: &str, nickname: &str, keywords: &[String]) -> Option<MentionKind> {
+    let message = message.to_lowercase();
+    let nickname = nickname.trim().to_lowercase();
+    if !nickname.is_empty() && contains_word(&message, &format!("@{}", nickname), false) {
+        return Some(MentionKind::Nickname);
+    }
+    keywords
+        .iter()
+        .map(|keyword| keyword.trim().to_lowercase())
+        .any(|keyword| !keyword.is_empty() && contains_word(&message, &keyword, true))
+        .then_some(MentionKind::Keyword)
+}
+
+pub struct MentionKeywords {
+    path: PathBuf,
+    keywords: Vec<String>,
+}
+
+impl MentionKeywords {
+    pub fn load(path: PathBuf) -> Self {
+        let keywords = json_store::load_json_or_default(&path, "mention keywords");
+
+        Self { path, keywords }
+    }
+
+    pub fn get(&self) -> &[String] {
+        &self.keywords
+    }
+
+    pub fn set(&mut self, keywords: Vec<String>) -> Result<(), String> {
+        self.keywords = keywords;
+        json_store::save_json(&self.path, &self.keywords, "mention keywords")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nickname_needs_the_at_sign_and_keywords_a_whole_word() {
+        let keywords = vec!["Hotline".to_string(), "  ".to_string()];
+        assert_eq!(detect("hey @Alice, you there?", "alice", &keywords), Some(MentionKind::Nickname));
+        assert_eq!(detect("@alice_2 hi", "alice", &keywords), None);
+        assert_eq!(detect("alice said hi", "alice", &keywords), None);
+        assert_eq!(detect("Long live HOTLINE!", "alice", &keywords), Some(MentionKind::Keyword));
+        assert_eq!(detect("hotlines everywhere", "alice", &keywords), None);
+        assert_eq!(detect("anything", "", &[]), None);
+    }
+}
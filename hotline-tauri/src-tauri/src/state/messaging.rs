@@ -0,0 +1,108 @@
+// Private message preferences
+//
+// Whether to refuse private messages and chat invitations, and the automatic
+// response sent to anyone who messages us. They apply to every connection and
+// are stored in messaging_options.json in the app data directory.
+//
+// Servers without instant message options pass automatic responses and
+// refusals on as plain messages, so two away clients would answer each other
+// forever. Like the classic clients, each user gets one automatic reply per
+// REPLY_INTERVAL.
+
+use crate::protocol::client::instant_message::MessagingOptions;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use super::json_store;
+
+/// Longest automatic response, in characters
+const MAX_AUTOMATIC_RESPONSE: usize = 255;
+
+/// How long a user waits for another automatic reply
+const REPLY_INTERVAL: Duration = Duration::from_secs(60);
+
+pub struct MessagingSettings {
+    path: PathBuf,
+    options: MessagingOptions,
+    replies: ReplyThrottle,
+}
+
+impl MessagingSettings {
+    pub fn load(path: PathBuf) -> Self {
+        let options = json_store::load_json_or_default(&path, "messaging options");
+
+        Self { path, options, replies: ReplyThrottle::default() }
+    }
+
+    /// Whether an automatic response or refusal may go to this user now
+    pub fn may_reply(&mut self, server_id: &str, user_id: u16) -> bool {
+        self.replies.allow(server_id, user_id, Instant::now())
+    }
+
+    pub fn options(&self) -> MessagingOptions {
+        self.options.clone()
+    }
+
+    pub fn set(&mut self, mut options: MessagingOptions) -> Result<(), String> {
+        options.automatic_response = options
+            .automatic_response
+            .map(|text| text.trim().to_string())
+            .filter(|text| !text.is_empty());
+        if options.automatic_response.as_ref().is_some_and(|text| text.chars().count() > MAX_AUTOMATIC_RESPONSE) {
+            return Err(format!("Automatic response must be at most {} characters", MAX_AUTOMATIC_RESPONSE));
+        }
+
+        json_store::save_json(&self.path, &options, "messaging options")?;
+        self.options = options;
+        Ok(())
+    }
+}
+
+/// When each user on each server was last answered automatically
+#[derive(Default)]
+struct ReplyThrottle {
+    last_reply: HashMap<(String, u16), Instant>,
+}
+
+impl ReplyThrottle {
+    fn allow(&mut self, server_id: &str, user_id: u16, now: Instant) -> bool {
+        self.last_reply.retain(|_, at| now.duration_since(*at) < REPLY_INTERVAL);
+        let key = (server_id.to_string(), user_id);
+        if self.last_reply.contains_key(&key) {
+            return false;
+        }
+        self.last_reply.insert(key, now);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn two_away_clients_stop_answering_each_other() {
+        let now = Instant::now();
+        let mut alice = ReplyThrottle::default();
+        let mut bob = ReplyThrottle::default();
+
+        // Bob (user 2) messages Alice, and every reply arrives at the other
+        // side as another plain message to answer
+        let mut replies = 0;
+        while replies < 100 {
+            let answered = if replies % 2 == 0 {
+                alice.allow("server", 2, now)
+            } else {
+                bob.allow("server", 1, now)
+            };
+            if !answered {
+                break;
+            }
+            replies += 1;
+        }
+        assert_eq!(replies, 2);
+
+        assert!(alice.allow("other-server", 2, now));
+        assert!(alice.allow("server", 2, now + REPLY_INTERVAL));
+    }
+}
@@ -0,0 +1,204 @@
+// Opt-in metrics
+//
+// Counters for connections, transfers, server events and errors, for people
+// running bots or bridges who want to watch the client. Nothing is recorded
+// until metrics are enabled; `dump_metrics` returns a JSON snapshot.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::conversations::now_secs;
+
+/// Window used for the transfer throughput figure
+const THROUGHPUT_WINDOW: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TransferDirection {
+    Download,
+    Upload,
+}
+
+#[derive(Default)]
+struct Counters {
+    since: u64,
+    started: Option<Instant>,
+    connections_opened: u64,
+    connection_failures: u64,
+    disconnects: u64,
+    bytes_downloaded: u64,
+    bytes_uploaded: u64,
+    transfers_completed: u64,
+    transfers_failed: u64,
+    events: BTreeMap<String, u64>,
+    errors: BTreeMap<String, u64>,
+    recent_transfers: VecDeque<(Instant, u64)>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetricsSnapshot {
+    pub enabled: bool,
+    /// When collection started (Unix seconds)
+    pub since: u64,
+    pub uptime_secs: u64,
+    pub active_connections: usize,
+    pub connections_opened: u64,
+    pub connection_failures: u64,
+    pub disconnects: u64,
+    pub bytes_downloaded: u64,
+    pub bytes_uploaded: u64,
+    pub transfers_completed: u64,
+    pub transfers_failed: u64,
+    /// Bytes per second over the last minute
+    pub throughput_bytes_per_sec: f64,
+    pub events: BTreeMap<String, u64>,
+    /// Server events per second since collection started
+    pub event_rate_per_sec: f64,
+    pub errors: BTreeMap<String, u64>,
+}
+
+#[derive(Default)]
+pub struct Metrics {
+    enabled: AtomicBool,
+    counters: Mutex<Counters>,
+}
+
+impl Metrics {
+    /// Turn collection on or off. Enabling starts from zeroed counters.
+    pub fn set_enabled(&self, enabled: bool) {
+        if enabled && !self.enabled.load(Ordering::SeqCst) {
+            *self.counters.lock().unwrap() = Counters {
+                since: now_secs(),
+                started: Some(Instant::now()),
+                ..Counters::default()
+            };
+        }
+        self.enabled.store(enabled, Ordering::SeqCst);
+    }
+
+    fn record(&self, update: impl FnOnce(&mut Counters)) {
+        if self.enabled.load(Ordering::Relaxed) {
+            update(&mut self.counters.lock().unwrap());
+        }
+    }
+
+    pub fn record_connection(&self, ok: bool) {
+        self.record(|c| {
+            if ok {
+                c.connections_opened += 1;
+            } else {
+                c.connection_failures += 1;
+                *c.errors.entry("connect".to_string()).or_default() += 1;
+            }
+        });
+    }
+
+    pub fn record_disconnect(&self) {
+        self.record(|c| c.disconnects += 1);
+    }
+
+    pub fn record_event(&self, kind: &str) {
+        self.record(|c| *c.events.entry(kind.to_string()).or_default() += 1);
+    }
+
+    pub fn record_error(&self, category: &str) {
+        self.record(|c| *c.errors.entry(category.to_string()).or_default() += 1);
+    }
+
+    pub fn record_transfer(&self, direction: TransferDirection, bytes: u64, ok: bool) {
+        self.record(|c| {
+            match direction {
+                TransferDirection::Download => c.bytes_downloaded += bytes,
+                TransferDirection::Upload => c.bytes_uploaded += bytes,
+            }
+            if ok {
+                c.transfers_completed += 1;
+            } else {
+                c.transfers_failed += 1;
+                *c.errors.entry("transfer".to_string()).or_default() += 1;
+            }
+            c.recent_transfers.push_back((Instant::now(), bytes));
+        });
+    }
+
+    pub fn snapshot(&self, active_connections: usize) -> MetricsSnapshot {
+        let mut c = self.counters.lock().unwrap();
+        let now = Instant::now();
+        while c
+            .recent_transfers
+            .front()
+            .is_some_and(|(at, _)| now.duration_since(*at) > THROUGHPUT_WINDOW)
+        {
+            c.recent_transfers.pop_front();
+        }
+
+        let uptime = c.started.map(|started| now.duration_since(started)).unwrap_or_default();
+        let recent_bytes: u64 = c.recent_transfers.iter().map(|(_, bytes)| bytes).sum();
+        let window = uptime.min(THROUGHPUT_WINDOW).as_secs_f64().max(1.0);
+        let total_events: u64 = c.events.values().sum();
+
+        MetricsSnapshot {
+            enabled: self.enabled.load(Ordering::SeqCst),
+            since: c.since,
+            uptime_secs: uptime.as_secs(),
+            active_connections,
+            connections_opened: c.connections_opened,
+            connection_failures: c.connection_failures,
+            disconnects: c.disconnects,
+            bytes_downloaded: c.bytes_downloaded,
+            bytes_uploaded: c.bytes_uploaded,
+            transfers_completed: c.transfers_completed,
+            transfers_failed: c.transfers_failed,
+            throughput_bytes_per_sec: recent_bytes as f64 / window,
+            events: c.events.clone(),
+            event_rate_per_sec: total_events as f64 / uptime.as_secs_f64().max(1.0),
+            errors: c.errors.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_nothing_until_enabled() {
+        let metrics = Metrics::default();
+        metrics.record_connection(true);
+        metrics.record_event("chatMessage");
+        let snapshot = metrics.snapshot(0);
+        assert!(!snapshot.enabled);
+        assert_eq!(snapshot.connections_opened, 0);
+        assert!(snapshot.events.is_empty());
+    }
+
+    #[test]
+    fn counts_connections_transfers_and_errors() {
+        let metrics = Metrics::default();
+        metrics.set_enabled(true);
+        metrics.record_connection(true);
+        metrics.record_connection(false);
+        metrics.record_event("chatMessage");
+        metrics.record_event("chatMessage");
+        metrics.record_transfer(TransferDirection::Download, 1000, true);
+        metrics.record_transfer(TransferDirection::Upload, 10, false);
+
+        let snapshot = metrics.snapshot(1);
+        assert_eq!(snapshot.active_connections, 1);
+        assert_eq!((snapshot.connections_opened, snapshot.connection_failures), (1, 1));
+        assert_eq!(snapshot.events.get("chatMessage"), Some(&2));
+        assert_eq!((snapshot.bytes_downloaded, snapshot.bytes_uploaded), (1000, 10));
+        assert_eq!((snapshot.transfers_completed, snapshot.transfers_failed), (1, 1));
+        assert_eq!(snapshot.errors.get("connect"), Some(&1));
+        assert_eq!(snapshot.errors.get("transfer"), Some(&1));
+        assert!(snapshot.throughput_bytes_per_sec > 0.0);
+
+        // Re-enabling while already on keeps the counters
+        metrics.set_enabled(true);
+        assert_eq!(metrics.snapshot(0).connections_opened, 1);
+    }
+}
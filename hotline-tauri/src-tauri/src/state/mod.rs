@@ -1,19 +1,170 @@
 // Application state management
 
-use crate::protocol::{types::Bookmark, HotlineClient};
+pub mod auto_away;
+pub mod board_fetch;
+pub mod bookmark_groups;
+pub mod conversations;
+pub mod connect_actions;
+pub mod chat_log;
+pub mod history_crypto;
+pub mod idle_disconnect;
+pub mod json_store;
+pub mod diagnostics;
+pub mod failures;
+pub mod highlights;
+pub mod hosting;
+pub mod messaging;
+pub mod mentions;
+pub mod metrics;
+pub mod news_cache;
+pub mod news_export;
+pub mod news_threads;
+pub mod notifications;
+pub mod offline;
+pub mod operations;
+pub mod partial_downloads;
+pub mod preferences;
+pub mod preview_access;
+pub mod recent_servers;
+pub mod reconnect;
+pub mod request_policies;
+pub mod server_full;
+pub mod server_refresh;
+pub mod server_windows;
+pub mod session_stats;
+pub mod shutdown;
+pub mod tracker_browse;
+pub mod tracker_cache;
+pub mod transfer_history;
+pub mod transfer_limits;
+pub mod upload_staging;
+pub mod user_limits;
+pub mod watchlist;
+
+use crate::protocol::avatar;
+use crate::protocol::cancel::{cancellable, check_cancelled, CancellationToken};
+use crate::protocol::file_access::{FolderCapabilities, ItemCapabilities};
+use crate::protocol::i18n::{tr, MessageId};
+use crate::protocol::sanitize_path;
+use crate::protocol::tracker::TrackerClient;
+use crate::protocol::tracker_registration::{TrackerAddress, TrackerRegistrar, TrackerRegistration};
+use crate::protocol::{types::{ArticleFlavor, Bookmark, ClientIdentity, ConnectAction}, HotlineClient};
 use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Instant;
 use tauri::{AppHandle, Emitter, Manager};
+use tokio::io::AsyncWriteExt;
 use tokio::sync::RwLock;
 
+use auto_away::{AutoAway, AutoAwayPolicy};
+use board_fetch::{BoardFetch, BoardFetchPolicy};
+use connect_actions::{ConnectActions, ConnectOutcome};
+use bookmark_groups::{BookmarkGroup, BookmarkGroups};
+use conversations::{Conversation, ConversationStore, ConversationSummary};
+use highlights::{HighlightEngine, HighlightRule};
+use idle_disconnect::{IdleAction, IdleDisconnect};
+use messaging::MessagingSettings;
+use crate::protocol::client::away::AwayStatus;
+use crate::protocol::client::board::{BoardContinuation, BoardPage};
+use crate::protocol::client::file_batch::{BatchResult, FileRef};
+use crate::protocol::client::instant_message::{InstantMessageKind, MessagingOptions};
+use preferences::{Preferences, PreferencesStore};
+use hosting::Hosting;
+use crate::protocol::server::{HostingStatus, ServerConfig};
+use preview_access::PreviewAllowList;
+use recent_servers::{RecentServer, RecentServers};
+use reconnect::{ReconnectPolicy, ReconnectSettings};
+use server_full::{ServerFullRetry, ServerFullRetryPolicy};
+use server_refresh::{RefreshOutcome, ServerRefresh, ServerRefreshPolicy};
+use tracker_browse::{TrackerBrowse, TrackerFailure};
+use tracker_cache::{TrackerCache, TrackerDiff, TrackerListing, TrackerSearchResult, TrackerSort};
+use request_policies::RequestPolicyStore;
+use transfer_limits::TransferLimitStore;
+use upload_staging::{StagingManifest, UploadStaging};
+use user_limits::UserLimitStore;
+use crate::protocol::client::bandwidth::{TransferLimits, TransferMeter};
+use crate::protocol::client::request_policy::RequestPolicy;
+use crate::protocol::client::mac_forks::{self, ForkMode, MacForks, UploadFile};
+use crate::protocol::client::occupancy;
+use crate::protocol::client::upload_conflict::{ConflictResolution, UploadConflict};
+use watchlist::{WatchList, WatchPreferences};
+use notifications::{NotificationPrefs, NotificationPrefsStore};
+use mentions::MentionKeywords;
+use conversations::now_secs;
+use partial_downloads::{DownloadOptions, DownloadVerification, PartialDownload, PartialDownloadManifest};
+use news_cache::{CachedContent, NewsCache, NewsCacheStats};
+use news_export::NewsExportFormat;
+use news_threads::NewsThread;
+use offline::{CachedView, FileListCache};
+use metrics::{Metrics, MetricsSnapshot, TransferDirection};
+use session_stats::{SessionStats, SessionSummary};
+use shutdown::Shutdown;
+use chat_log::{ChatLog, ChatLogEntry};
+use history_crypto::{HistoryCipher, HistoryEncryptionSettings};
+use diagnostics::{DiagnosticLog, ZipBuilder};
+use failures::{Failure, FailureCategory, FailureLog};
+use transfer_history::{TransferHistory, TransferHistoryFilter, TransferRecord};
+use crate::protocol::client::{account_from_info, Account, FileInfo, LoginRefusal};
+use crate::commands::ConnectError;
+use operations::{emit_operation_progress, OperationJob, OperationRegistry, OperationSummary};
+use crate::protocol::client::private_chat::PrivateChatInfo;
+use crate::protocol::client::raw::{RawField, RawReply};
+use crate::protocol::client::transfer_queue::{download_limit_from_error, DownloadSlots, QueuedTransfer, TransferManager, TransferRequest, TransferState};
+
 pub struct AppState {
-    clients: Arc<RwLock<HashMap<String, HotlineClient>>>,
+    // Shared so long transfers can keep their client without holding the lock
+    clients: Arc<RwLock<HashMap<String, Arc<HotlineClient>>>>,
     bookmarks: Arc<RwLock<Vec<Bookmark>>>,
     bookmarks_path: PathBuf,
+    bookmark_groups: Arc<RwLock<BookmarkGroups>>,
+    client_identity: Arc<RwLock<ClientIdentity>>,
+    client_identity_path: PathBuf,
     app_handle: AppHandle,
     pending_agreements: Arc<RwLock<HashMap<String, String>>>, // server_id -> agreement_text
+    connect_actions: Arc<RwLock<ConnectActions>>,
+    conversations: Arc<RwLock<ConversationStore>>,
+    messaging: Arc<RwLock<MessagingSettings>>,
+    highlights: Arc<RwLock<HighlightEngine>>,
+    watch_list: Arc<RwLock<WatchList>>,
+    preferences: Arc<RwLock<PreferencesStore>>,
+    notification_prefs: Arc<RwLock<NotificationPrefsStore>>,
+    mentions: Arc<RwLock<MentionKeywords>>,
+    partial_downloads: Arc<RwLock<PartialDownloadManifest>>,
+    news_cache: Arc<RwLock<NewsCache>>,
+    file_list_cache: Arc<RwLock<FileListCache>>,
+    chat_log: Arc<RwLock<ChatLog>>,
+    history_encryption: Arc<RwLock<HistoryEncryptionSettings>>,
+    metrics: Arc<Metrics>,
+    session_stats: Arc<SessionStats>,
+    diagnostic_log: Arc<DiagnosticLog>,
+    failures: Arc<FailureLog>,
+    transfer_history: Arc<RwLock<TransferHistory>>,
+    hosting: Arc<RwLock<Hosting>>,
+    reconnect: Arc<RwLock<ReconnectSettings>>,
+    server_refresh: Arc<RwLock<ServerRefresh>>,
+    auto_away: Arc<RwLock<AutoAway>>,
+    idle_disconnect: Arc<RwLock<IdleDisconnect>>,
+    board_fetch: Arc<RwLock<BoardFetch>>,
+    tracker_registrar: Arc<RwLock<TrackerRegistrar>>,
+    tracker_cache: Arc<RwLock<TrackerCache>>,
+    server_full_retry: Arc<RwLock<ServerFullRetry>>,
+    transfer_limits: Arc<RwLock<TransferLimitStore>>,
+    user_limits: Arc<RwLock<UserLimitStore>>,
+    recent_servers: Arc<RwLock<RecentServers>>,
+    request_policies: Arc<RwLock<RequestPolicyStore>>,
+    preview_allow_list: Arc<RwLock<PreviewAllowList>>,
+    transfer_counter: AtomicU64,
+    /// Whether send_raw_transaction is allowed; on in debug builds, and in
+    /// release builds only when started with HOTLINE_RAW_TRANSACTIONS=1.
+    /// Fixed at startup so the webview can't turn it on.
+    raw_transactions: bool,
+    transfers: Arc<std::sync::Mutex<TransferManager>>,
+    upload_staging: Arc<RwLock<UploadStaging>>,
+    operations: Arc<RwLock<OperationRegistry>>,
+    shutdown: Shutdown,
 }
 
 impl AppState {
@@ -24,6 +175,47 @@ impl AppState {
         }
 
         let bookmarks_path = app_data_dir.join("bookmarks.json");
+        let client_identity_path = app_data_dir.join("client_identity.json");
+        let client_identity = Self::load_client_identity(&client_identity_path);
+        let history_encryption = HistoryEncryptionSettings::load(app_data_dir.join("history_encryption.json"));
+        let history_cipher = if history_encryption.enabled() {
+            HistoryCipher::from_keychain()
+                .map(Arc::new)
+                .map_err(|e| eprintln!("History encryption unavailable: {}", e))
+                .ok()
+        } else {
+            None
+        };
+        let conversations = ConversationStore::load(app_data_dir.join("conversations.json"), history_cipher.clone());
+        let highlights = HighlightEngine::load(app_data_dir.join("highlight_rules.json"));
+        let messaging = MessagingSettings::load(app_data_dir.join("messaging_options.json"));
+        let watch_list = WatchList::load(app_data_dir.join("watch_list.json"));
+        let preferences = PreferencesStore::load(app_data_dir.join("preferences.json"));
+        let mut notification_prefs = NotificationPrefsStore::load(app_data_dir.join("notification_prefs.json"));
+        notification_prefs.set_defaults(preferences.get().notifications);
+        let mentions = MentionKeywords::load(app_data_dir.join("mention_keywords.json"));
+        let partial_downloads = PartialDownloadManifest::load(&app_data_dir);
+        let news_cache = NewsCache::load(app_data_dir.join("news_cache.json"));
+        let file_list_cache = FileListCache::load(app_data_dir.join("file_list_cache.json"));
+        let chat_log = ChatLog::load(app_data_dir.join("chat_logs"), &app_data_dir.join("chat_log.json"), history_cipher);
+        let transfer_history = TransferHistory::load(app_data_dir.join("transfer_history.jsonl"));
+        let hosting = Hosting::load(app_data_dir.join("hosting.json"));
+        let reconnect = ReconnectSettings::load(app_data_dir.join("reconnect.json"));
+        let server_refresh = ServerRefresh::load(app_data_dir.join("server_refresh.json"));
+        let auto_away = AutoAway::load(app_data_dir.join("auto_away.json"));
+        let board_fetch = BoardFetch::load(app_data_dir.join("board_fetch.json"));
+        let server_full_retry = ServerFullRetry::load(app_data_dir.join("server_full_retry.json"));
+        let mut transfer_limits = TransferLimitStore::load(app_data_dir.join("transfer_limits.json"));
+        transfer_limits.set_defaults(preferences.get().transfer_limits);
+        let user_limits = UserLimitStore::load(app_data_dir.join("user_limits.json"));
+        let request_policies = RequestPolicyStore::load(app_data_dir.join("request_policies.json"));
+        let recent_servers = RecentServers::load(app_data_dir.join("recent_servers.json"));
+        let preview_allow_list = PreviewAllowList::load(
+            app_data_dir.join("preview_allow_list.json"),
+            app_handle.path().home_dir().ok(),
+            app_data_dir.clone(),
+        );
+        let bookmark_groups = BookmarkGroups::load(app_data_dir.join("bookmark_groups.json"));
 
         // Load existing bookmarks
         let bookmarks = Self::load_bookmarks(&bookmarks_path).unwrap_or_default();
@@ -32,9 +224,143 @@ impl AppState {
             clients: Arc::new(RwLock::new(HashMap::new())),
             bookmarks: Arc::new(RwLock::new(bookmarks)),
             bookmarks_path,
+            bookmark_groups: Arc::new(RwLock::new(bookmark_groups)),
+            client_identity: Arc::new(RwLock::new(client_identity)),
+            client_identity_path,
             app_handle,
             pending_agreements: Arc::new(RwLock::new(HashMap::new())),
+            connect_actions: Arc::new(RwLock::new(ConnectActions::default())),
+            conversations: Arc::new(RwLock::new(conversations)),
+            messaging: Arc::new(RwLock::new(messaging)),
+            highlights: Arc::new(RwLock::new(highlights)),
+            watch_list: Arc::new(RwLock::new(watch_list)),
+            preferences: Arc::new(RwLock::new(preferences)),
+            notification_prefs: Arc::new(RwLock::new(notification_prefs)),
+            mentions: Arc::new(RwLock::new(mentions)),
+            partial_downloads: Arc::new(RwLock::new(partial_downloads)),
+            news_cache: Arc::new(RwLock::new(news_cache)),
+            file_list_cache: Arc::new(RwLock::new(file_list_cache)),
+            chat_log: Arc::new(RwLock::new(chat_log)),
+            history_encryption: Arc::new(RwLock::new(history_encryption)),
+            metrics: Arc::new(Metrics::default()),
+            session_stats: Arc::new(SessionStats::default()),
+            diagnostic_log: Arc::new(DiagnosticLog::default()),
+            failures: Arc::new(FailureLog::default()),
+            transfer_history: Arc::new(RwLock::new(transfer_history)),
+            hosting: Arc::new(RwLock::new(hosting)),
+            reconnect: Arc::new(RwLock::new(reconnect)),
+            server_refresh: Arc::new(RwLock::new(server_refresh)),
+            auto_away: Arc::new(RwLock::new(auto_away)),
+            idle_disconnect: Arc::new(RwLock::new(IdleDisconnect::default())),
+            board_fetch: Arc::new(RwLock::new(board_fetch)),
+            tracker_registrar: Arc::new(RwLock::new(TrackerRegistrar::default())),
+            tracker_cache: Arc::new(RwLock::new(TrackerCache::default())),
+            server_full_retry: Arc::new(RwLock::new(server_full_retry)),
+            transfer_limits: Arc::new(RwLock::new(transfer_limits)),
+            user_limits: Arc::new(RwLock::new(user_limits)),
+            request_policies: Arc::new(RwLock::new(request_policies)),
+            recent_servers: Arc::new(RwLock::new(recent_servers)),
+            preview_allow_list: Arc::new(RwLock::new(preview_allow_list)),
+            transfer_counter: AtomicU64::new(0),
+            raw_transactions: cfg!(debug_assertions) || std::env::var("HOTLINE_RAW_TRANSACTIONS").is_ok_and(|value| value == "1"),
+            transfers: Arc::new(std::sync::Mutex::new(TransferManager::default())),
+            upload_staging: Arc::new(RwLock::new(UploadStaging::default())),
+            operations: Arc::new(RwLock::new(OperationRegistry::default())),
+            shutdown: Shutdown::default(),
+        }
+    }
+
+    fn load_client_identity(path: &PathBuf) -> ClientIdentity {
+        let mut identity: ClientIdentity = json_store::load_json_or_default(path, "client identity");
+        // The version string always follows the running build
+        identity.version = ClientIdentity::default().version;
+        identity
+    }
+
+    pub async fn get_history_encryption(&self) -> bool {
+        self.history_encryption.read().await.enabled()
+    }
+
+    /// Turn encryption of chat and PM history on or off, rewriting the files
+    pub async fn set_history_encryption(&self, enabled: bool) -> Result<(), String> {
+        let cipher = if enabled {
+            Some(Arc::new(HistoryCipher::from_keychain()?))
+        } else {
+            None
+        };
+        self.chat_log.write().await.set_cipher(cipher.clone())?;
+        self.conversations.write().await.set_cipher(cipher)?;
+        self.history_encryption.write().await.set(enabled)
+    }
+
+    pub async fn get_reconnect_policy(&self) -> ReconnectPolicy {
+        self.reconnect.read().await.policy()
+    }
+
+    pub async fn set_reconnect_policy(&self, policy: ReconnectPolicy) -> Result<(), String> {
+        self.reconnect.write().await.set(policy)
+    }
+
+    pub async fn get_server_full_retry_policy(&self) -> ServerFullRetryPolicy {
+        self.server_full_retry.read().await.policy()
+    }
+
+    pub async fn set_server_full_retry_policy(&self, policy: ServerFullRetryPolicy) -> Result<(), String> {
+        self.server_full_retry.write().await.set(policy)
+    }
+
+    pub async fn cancel_server_full_wait(&self, bookmark_id: &str) -> bool {
+        self.server_full_retry.write().await.cancel(bookmark_id)
+    }
+
+    pub async fn get_server_refresh_policy(&self) -> ServerRefreshPolicy {
+        self.server_refresh.read().await.policy()
+    }
+
+    pub async fn set_server_refresh_policy(&self, policy: ServerRefreshPolicy) -> Result<(), String> {
+        self.server_refresh.write().await.set(policy)
+    }
+
+    pub async fn get_auto_away_policy(&self) -> AutoAwayPolicy {
+        self.auto_away.read().await.policy()
+    }
+
+    pub async fn set_auto_away_policy(&self, policy: AutoAwayPolicy) -> Result<(), String> {
+        self.auto_away.write().await.set(policy)
+    }
+
+    pub async fn get_board_fetch_policy(&self) -> BoardFetchPolicy {
+        self.board_fetch.read().await.policy()
+    }
+
+    pub async fn set_board_fetch_policy(&self, policy: BoardFetchPolicy) -> Result<(), String> {
+        self.board_fetch.write().await.set(policy)
+    }
+
+    pub async fn get_client_identity(&self) -> ClientIdentity {
+        self.client_identity.read().await.clone()
+    }
+
+    /// Update the login identification used for new connections. The version
+    /// string is fixed to the app version; only the name, protocol version
+    /// number and whether to send identification are configurable.
+    pub async fn set_client_identity(&self, identity: ClientIdentity) -> Result<ClientIdentity, String> {
+        let name = identity.name.trim();
+        if identity.send_identification && name.is_empty() {
+            return Err("Client name cannot be empty".to_string());
         }
+        if identity.version_number == 0 {
+            return Err("Version number must be greater than zero".to_string());
+        }
+        let identity = ClientIdentity {
+            name: name.to_string(),
+            version: ClientIdentity::default().version,
+            ..identity
+        };
+
+        json_store::save_json(&self.client_identity_path, &identity, "client identity")?;
+        *self.client_identity.write().await = identity.clone();
+        Ok(identity)
     }
 
     fn load_bookmarks(path: &PathBuf) -> Result<Vec<Bookmark>, String> {
@@ -116,6 +442,11 @@ impl AppState {
                     auto_connect: false,
                     tls: false,
                     bookmark_type: Some(BookmarkType::Tracker),
+                    notes: None,
+                    tags: Vec::new(),
+                    idle_disconnect_mins: None,
+                    group: None,
+                    on_connect: Vec::new(),
                 };
                 bookmarks.push(tracker);
             }
@@ -133,6 +464,11 @@ impl AppState {
                     auto_connect: false,
                     tls: *tls,
                     bookmark_type: Some(BookmarkType::Server),
+                    notes: None,
+                    tags: Vec::new(),
+                    idle_disconnect_mins: None,
+                    group: None,
+                    on_connect: Vec::new(),
                 };
                 bookmarks.push(server);
             }
@@ -160,14 +496,186 @@ impl AppState {
         Ok(())
     }
 
-    pub async fn connect_server(&self, bookmark: Bookmark, username: String, user_icon_id: u16, auto_detect_tls: bool) -> Result<crate::commands::ConnectResult, String> {
+    pub async fn connect_server(&self, bookmark: Bookmark, username: String, user_icon_id: u16, auto_detect_tls: bool, takeover: bool, wait_if_full: bool) -> Result<crate::commands::ConnectResult, ConnectError> {
+        let target = format!("{}:{}", bookmark.address, bookmark.port);
+        let mut result = self.open_connection(bookmark.clone(), username.clone(), user_icon_id, auto_detect_tls).await;
+        if takeover && matches!(&result, Err(e) if e.refusal == Some(LoginRefusal::AlreadyLoggedIn)) {
+            self.diagnostic_log.record(&format!("Taking over the other session of {} on {}", bookmark.login, target));
+            self.take_over_session(&bookmark).await?;
+            result = self.open_connection(bookmark.clone(), username.clone(), user_icon_id, auto_detect_tls).await;
+        }
+        if wait_if_full && matches!(&result, Err(e) if e.refusal == Some(LoginRefusal::ServerFull)) {
+            self.diagnostic_log.record(&format!("{} is full, waiting for a slot", target));
+            result = self.wait_for_slot(&bookmark, username, user_icon_id, auto_detect_tls).await;
+        }
+        self.metrics.record_connection(result.is_ok());
+        match &result {
+            Ok(connected) => {
+                self.diagnostic_log.record(&format!("Connected to {} (tls: {})", target, connected.tls));
+                let saved = self.bookmarks.read().await.iter().any(|b| b.id == bookmark.id);
+                if let Err(e) = self.recent_servers.write().await.record(&bookmark, connected.tls, connected.port, saved) {
+                    eprintln!("{}", e);
+                }
+                let user_limit = match self.clients.read().await.get(&connected.server_id) {
+                    Some(client) => client.get_server_info().await.ok().and_then(|info| info.user_limit),
+                    None => None,
+                };
+                if let Err(e) = self.user_limits.write().await.record(&bookmark.address, bookmark.port, user_limit) {
+                    eprintln!("{}", e);
+                }
+            }
+            Err(e) => {
+                self.diagnostic_log.record(&format!("Connection to {} failed: {}", target, e.message));
+                // A full server's refusal often says how many it allows
+                let user_limit = occupancy::user_limit_from_text(&e.message);
+                if let Err(e) = self.user_limits.write().await.record(&bookmark.address, bookmark.port, user_limit) {
+                    eprintln!("{}", e);
+                }
+                // Only another session of the account needs the user to step in
+                let retryable = e.refusal != Some(LoginRefusal::AlreadyLoggedIn);
+                self.failures.record(FailureCategory::Connection, "connect", Some(&bookmark.id), retryable, &e.message);
+            }
+        }
+        result
+    }
+
+    /// Keep logging in to a full server until it lets us in, following the
+    /// server full retry policy. Each attempt is announced with a
+    /// `server-full-retry-{bookmark_id}` event, and a notification is shown
+    /// when a slot opens.
+    async fn wait_for_slot(&self, bookmark: &Bookmark, username: String, user_icon_id: u16, auto_detect_tls: bool) -> Result<crate::commands::ConnectResult, ConnectError> {
+        let (policy, token) = {
+            let mut retry = self.server_full_retry.write().await;
+            (retry.policy(), retry.begin(&bookmark.id))
+        };
+        let deadline = tokio::time::Instant::now() + policy.max_duration();
+        let event = format!("server-full-retry-{}", bookmark.id);
+
+        let mut attempt = 0u32;
+        let result = loop {
+            let delay = policy.delay(server_full::random_jitter());
+            if tokio::time::Instant::now() + delay > deadline {
+                break Err(ConnectError {
+                    message: format!("{} is still full after waiting {} minutes", bookmark.name, policy.max_duration_secs / 60),
+                    refusal: Some(LoginRefusal::ServerFull),
+                });
+            }
+            attempt += 1;
+            let _ = self.app_handle.emit(&event, server_windows::versioned(serde_json::json!({
+                "attempt": attempt,
+                "delaySecs": delay.as_secs(),
+            })));
+            if let Err(e) = cancellable(&token, async {
+                tokio::time::sleep(delay).await;
+                Ok(())
+            })
+            .await
+            {
+                break Err(e.into());
+            }
+            match self.open_connection(bookmark.clone(), username.clone(), user_icon_id, auto_detect_tls).await {
+                Err(e) if e.refusal == Some(LoginRefusal::ServerFull) => continue,
+                other => break other,
+            }
+        };
+        self.server_full_retry.write().await.finish(&bookmark.id, &token);
+
+        if result.is_ok() {
+            notifications::show_os_notification(
+                &self.app_handle,
+                "Server slot open",
+                &format!("A slot opened on {} and you are now connected", bookmark.name),
+            );
+        }
+        result
+    }
+
+    /// Disconnect the session that holds `bookmark`'s account, so the login
+    /// can be retried. That is either our own connection under the same id,
+    /// or another session found through a connection to the same server that
+    /// is allowed to disconnect users.
+    async fn take_over_session(&self, bookmark: &Bookmark) -> Result<(), String> {
+        if self.clients.read().await.contains_key(&bookmark.id) {
+            return self.disconnect_server(&bookmark.id).await;
+        }
+
+        // Clone the candidates out so the clients lock isn't held across the
+        // round-trips below
+        let candidates: Vec<Arc<HotlineClient>> = self
+            .clients
+            .read()
+            .await
+            .values()
+            .filter(|client| client.address() == (bookmark.address.as_str(), bookmark.port))
+            .cloned()
+            .collect();
+        let mut moderator = None;
+        for client in candidates {
+            if client.get_user_access().await.disconnect_user {
+                moderator = Some(client);
+                break;
+            }
+        }
+        let client = moderator.ok_or_else(|| tr(MessageId::TakeoverNotPermitted))?;
+
+        let mut disconnected = 0;
+        for user in client.fetch_user_list().await? {
+            let Ok(info) = client.get_user_info(user.user_id).await else {
+                continue;
+            };
+            if account_from_info(&info.info) == Some(bookmark.login.as_str()) {
+                client.disconnect_user(user.user_id, None).await?;
+                disconnected += 1;
+            }
+        }
+        if disconnected == 0 {
+            return Err(tr(MessageId::TakeoverSessionNotFound));
+        }
+        Self::refresh_roster(&client).await;
+
+        // Give the server a moment to drop the session before logging in again
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        Ok(())
+    }
+
+    /// Connect a new client, keeping why the server refused the login
+    async fn connect_client(client: &HotlineClient) -> Result<(), ConnectError> {
+        match client.connect().await {
+            Ok(()) => Ok(()),
+            Err(message) => Err(ConnectError {
+                message,
+                refusal: client.login_refusal().await,
+            }),
+        }
+    }
+
+    async fn new_client(&self, bookmark: Bookmark, username: String, user_icon_id: u16) -> HotlineClient {
+        let limits = self.transfer_limits.read().await.get(&bookmark.id);
+        let request_policy = self.request_policies.read().await.get(&bookmark.id);
+        let slots = self.transfers.lock().unwrap().host_slots();
+        let client = HotlineClient::new(bookmark);
+        client.set_transfer_limits(limits).await;
+        client.set_transfer_slots(slots).await;
+        client.set_request_policy(request_policy).await;
+        client.set_user_info(username, user_icon_id).await;
+        client.set_identity(self.client_identity.read().await.clone()).await;
+        client.set_messaging_options(self.messaging.read().await.options()).await;
+        client.set_watchdog_timeout(self.reconnect.read().await.policy().watchdog_timeout()).await;
+        client
+    }
+
+    async fn open_connection(&self, bookmark: Bookmark, username: String, user_icon_id: u16, auto_detect_tls: bool) -> Result<crate::commands::ConnectResult, ConnectError> {
         // Don't allow connecting to trackers - they use a different protocol
         if matches!(bookmark.bookmark_type, Some(crate::protocol::types::BookmarkType::Tracker)) {
-            return Err("Cannot connect to tracker. Trackers are used to browse servers, not to connect directly.".to_string());
+            return Err(tr(MessageId::CannotConnectToTracker).into());
         }
 
         let bookmark = bookmark;
         let server_id = bookmark.id.clone();
+        let idle_limit = bookmark
+            .idle_disconnect_mins
+            .filter(|&mins| mins > 0)
+            .map(|mins| std::time::Duration::from_secs(u64::from(mins) * 60));
 
         // Auto-detect TLS: when enabled and the bookmark isn't already TLS, try
         // connecting directly on port+100 (the Mobius TLS convention). If TLS fails
@@ -182,36 +690,34 @@ impl AppState {
             tls_bookmark.tls = true;
             tls_bookmark.port = tls_port;
 
-            let tls_client = HotlineClient::new(tls_bookmark);
-            tls_client.set_user_info(username.clone(), user_icon_id).await;
+            let tls_client = self.new_client(tls_bookmark, username.clone(), user_icon_id).await;
 
             match tokio::time::timeout(
                 std::time::Duration::from_secs(5),
-                tls_client.connect(),
+                Self::connect_client(&tls_client),
             ).await {
                 Ok(Ok(())) => {
                     println!("Auto-detect TLS: connected via TLS on port {}", tls_port);
                     (tls_client, true, tls_port)
                 }
+                // TLS worked but the login was refused; plain would be refused too
+                Ok(Err(e)) if e.refusal.is_some() => return Err(e),
                 Ok(Err(e)) => {
-                    println!("Auto-detect TLS: TLS failed ({}), falling back to plain on port {}", e, bookmark.port);
-                    let client = HotlineClient::new(bookmark.clone());
-                    client.set_user_info(username, user_icon_id).await;
-                    client.connect().await?;
+                    println!("Auto-detect TLS: TLS failed ({}), falling back to plain on port {}", e.message, bookmark.port);
+                    let client = self.new_client(bookmark.clone(), username, user_icon_id).await;
+                    Self::connect_client(&client).await?;
                     (client, false, bookmark.port)
                 }
                 Err(_) => {
                     println!("Auto-detect TLS: timed out, falling back to plain on port {}", bookmark.port);
-                    let client = HotlineClient::new(bookmark.clone());
-                    client.set_user_info(username, user_icon_id).await;
-                    client.connect().await?;
+                    let client = self.new_client(bookmark.clone(), username, user_icon_id).await;
+                    Self::connect_client(&client).await?;
                     (client, false, bookmark.port)
                 }
             }
         } else {
-            let client = HotlineClient::new(bookmark.clone());
-            client.set_user_info(username, user_icon_id).await;
-            client.connect().await?;
+            let client = self.new_client(bookmark.clone(), username, user_icon_id).await;
+            Self::connect_client(&client).await?;
             (client, bookmark.tls, bookmark.port)
         };
 
@@ -219,14 +725,14 @@ impl AppState {
         // (once stored, we can't move it)
         let mut event_rx = {
             let mut rx_guard = client.event_rx.lock().await;
-            rx_guard.take().ok_or("Event receiver already taken")?
+            rx_guard.take().ok_or_else(|| "Event receiver already taken".to_string())?
         };
 
         // Store client in clients map BEFORE starting event loop
         // This ensures it's available when StatusChanged events fire
         {
             let mut clients = self.clients.write().await;
-            clients.insert(server_id.clone(), client);
+            clients.insert(server_id.clone(), Arc::new(client));
         }
 
         // Start event forwarding task
@@ -234,18 +740,75 @@ impl AppState {
         let server_id_clone = server_id.clone();
         let state_clone = Arc::clone(&self.pending_agreements);
         let clients_clone = Arc::clone(&self.clients);
+        let conversations_clone = Arc::clone(&self.conversations);
+        let messaging_clone = Arc::clone(&self.messaging);
+        let highlights_clone = Arc::clone(&self.highlights);
+        let watch_list_clone = Arc::clone(&self.watch_list);
+        let notification_prefs_clone = Arc::clone(&self.notification_prefs);
+        let mentions_clone = Arc::clone(&self.mentions);
+        let preferences_clone = Arc::clone(&self.preferences);
+        let server_name = bookmark.name.clone();
+        let file_list_cache_clone = Arc::clone(&self.file_list_cache);
+        let chat_log_clone = Arc::clone(&self.chat_log);
+        let metrics_clone = Arc::clone(&self.metrics);
+        let reconnect_clone = Arc::clone(&self.reconnect);
+        let diagnostic_log_clone = Arc::clone(&self.diagnostic_log);
+        let failures_clone = Arc::clone(&self.failures);
+        let session_stats_clone = Arc::clone(&self.session_stats);
         tokio::spawn(async move {
             while let Some(event) = event_rx.recv().await {
                 use crate::protocol::client::HotlineEvent;
+                metrics_clone.record_event(event.name());
 
                 match event {
                     HotlineEvent::ChatMessage { user_id, user_name, message } => {
+                        let seq = match chat_log_clone.write().await.append(&server_id_clone, user_id, &user_name, &message) {
+                            Ok(Some(seq)) => Some(seq),
+                            // Already shown before the reconnect
+                            Ok(None) => continue,
+                            Err(e) => {
+                                eprintln!("{}", e);
+                                None
+                            }
+                        };
+                        let highlight = highlights_clone.read().await.evaluate(&user_name, &message);
+                        let own_name = match clients_clone.read().await.get(&server_id_clone) {
+                            Some(client) => client.get_username().await,
+                            None => String::new(),
+                        };
+                        let mention = if user_name == own_name {
+                            None
+                        } else {
+                            mentions::detect(&message, &own_name, mentions_clone.read().await.get())
+                        };
                         let payload = serde_json::json!({
+                            "seq": seq,
                             "userId": user_id,
                             "userName": user_name,
                             "message": message,
+                            "matchedRules": highlight.rule_ids,
+                            "mentioned": mention.is_some(),
                         });
-                        let _ = app_handle.emit(&format!("chat-message-{}", server_id_clone), payload);
+                        server_windows::emit(&app_handle, &server_id_clone, "chat-message", payload.clone());
+                        if highlight.notify {
+                            server_windows::emit(&app_handle, &server_id_clone, "highlight", serde_json::json!({
+                                "source": "chat",
+                                "event": payload,
+                            }));
+                        }
+                        if let Some(kind) = mention {
+                            server_windows::emit(&app_handle, &server_id_clone, "mention", serde_json::json!({
+                                "kind": kind,
+                                "event": payload,
+                            }));
+                            if notification_prefs_clone.read().await.get(&server_id_clone).mention {
+                                notifications::show_os_notification(
+                                    &app_handle,
+                                    &format!("{} mentioned you on {}", user_name, server_name),
+                                    &message,
+                                );
+                            }
+                        }
                     }
                     HotlineEvent::UserJoined { user_id, user_name, icon, flags } => {
                         let payload = serde_json::json!({
@@ -253,34 +816,98 @@ impl AppState {
                             "userName": user_name,
                             "iconId": icon,
                             "flags": flags,
+                            "avatar": avatar::fallback(icon, &user_name),
                         });
-                        let _ = app_handle.emit(&format!("user-joined-{}", server_id_clone), payload);
+                        server_windows::emit(&app_handle, &server_id_clone, "user-joined", payload);
                     }
                     HotlineEvent::UserLeft { user_id } => {
                         let payload = serde_json::json!({
                             "userId": user_id,
                         });
-                        let _ = app_handle.emit(&format!("user-left-{}", server_id_clone), payload);
+                        server_windows::emit(&app_handle, &server_id_clone, "user-left", payload);
                     }
-                    HotlineEvent::UserChanged { user_id, user_name, icon, flags } => {
+                    HotlineEvent::UserChanged { user_id, user_name, icon, flags, is_self, previous_name } => {
+                        let preferences = watch_list_clone.read().await.get(&server_id_clone);
+                        if preferences.came_online(&user_name, previous_name.as_deref()) {
+                            server_windows::emit(&app_handle, &server_id_clone, "user-watch-triggered", serde_json::json!({
+                                "userId": user_id,
+                                "userName": user_name,
+                            }));
+                            if preferences.os_notification {
+                                notifications::show_os_notification(
+                                    &app_handle,
+                                    "User online",
+                                    &format!("{} is now online", user_name),
+                                );
+                            }
+                        }
+
                         let payload = serde_json::json!({
                             "userId": user_id,
                             "userName": user_name,
                             "iconId": icon,
                             "flags": flags,
+                            "avatar": avatar::fallback(icon, &user_name),
+                            "isSelf": is_self,
                         });
-                        let _ = app_handle.emit(&format!("user-changed-{}", server_id_clone), payload);
+                        server_windows::emit(&app_handle, &server_id_clone, "user-changed", payload);
+                    }
+                    HotlineEvent::UserListSynced(diff) => {
+                        println!(
+                            "Roster sync for {}: +{} -{} ~{}",
+                            server_id_clone,
+                            diff.added.len(),
+                            diff.removed.len(),
+                            diff.changed.len()
+                        );
+                        server_windows::emit(&app_handle, &server_id_clone, "roster-sync", diff);
                     }
                     HotlineEvent::ServerMessage(msg) => {
                         println!("Server broadcast message: {}", msg);
+                        if let Err(e) = chat_log_clone.write().await.append_broadcast(&server_id_clone, &msg) {
+                            eprintln!("{}", e);
+                        }
                         let payload = serde_json::json!({
                             "message": msg,
                         });
-                        let _ = app_handle.emit(&format!("broadcast-message-{}", server_id_clone), payload);
+                        server_windows::emit(&app_handle, &server_id_clone, "broadcast-message", payload);
+
+                        // Server notices often follow moderation (kicks, bans), so
+                        // re-check the roster in the background
+                        let clients = Arc::clone(&clients_clone);
+                        let server_id = server_id_clone.clone();
+                        tokio::spawn(async move {
+                            if let Some(client) = clients.read().await.get(&server_id) {
+                                if let Err(e) = client.refresh_user_list().await {
+                                    eprintln!("Roster refresh failed: {}", e);
+                                }
+                            }
+                        });
                     }
                     HotlineEvent::AgreementRequired(agreement) => {
                         println!("State: Received AgreementRequired event, agreement length: {}", agreement.len());
-                        
+
+                        // After a reconnect the same agreement comes back; accept it
+                        // again without prompting unless the text changed
+                        let already_accepted = match clients_clone.read().await.get(&server_id_clone) {
+                            Some(client) => client.has_accepted_agreement(&agreement).await,
+                            None => false,
+                        };
+                        if already_accepted {
+                            println!("State: Re-accepting unchanged agreement for {}", server_id_clone);
+                            let clients = Arc::clone(&clients_clone);
+                            let server_id = server_id_clone.clone();
+                            tokio::spawn(async move {
+                                if let Some(client) = clients.read().await.get(&server_id) {
+                                    let _ = client.record_agreement(&agreement).await;
+                                    if let Err(e) = client.accept_agreement().await {
+                                        eprintln!("Failed to re-accept agreement: {}", e);
+                                    }
+                                }
+                            });
+                            continue;
+                        }
+
                         // Store agreement in pending_agreements
                         {
                             let mut pending = state_clone.write().await;
@@ -288,58 +915,269 @@ impl AppState {
                             println!("State: Stored agreement for server {}", server_id_clone);
                         }
                         
+                        // Keep the text on the client's ServerInfo so it can be re-shown later
+                        let updated = match clients_clone.read().await.get(&server_id_clone) {
+                            Some(client) => client.record_agreement(&agreement).await,
+                            None => false,
+                        };
+                        if updated {
+                            println!("State: Server {} pushed an updated agreement", server_id_clone);
+                            server_windows::emit(&app_handle, &server_id_clone, "agreement-updated", serde_json::json!({
+                                "agreement": agreement,
+                            }));
+                        }
+
                         let payload = serde_json::json!({
                             "agreement": agreement,
                         });
-                        let event_name = format!("agreement-required-{}", server_id_clone);
-                        println!("State: Emitting event: {}", event_name);
-                        match app_handle.emit(&event_name, payload) {
-                            Ok(_) => println!("State: Event emitted successfully"),
-                            Err(e) => println!("State: Failed to emit event: {:?}", e),
-                        }
+                        println!("State: Emitting event: agreement-required-{}", server_id_clone);
+                        server_windows::emit(&app_handle, &server_id_clone, "agreement-required", payload);
                     }
                     HotlineEvent::FileList { files, path } => {
+                        if let Err(e) = file_list_cache_clone.write().await.record(&server_id_clone, &path, &files) {
+                            eprintln!("{}", e);
+                        }
+                        let access = match clients_clone.read().await.get(&server_id_clone) {
+                            Some(client) => client.get_user_access().await,
+                            None => Default::default(),
+                        };
                         let payload = serde_json::json!({
-                            "files": files.iter().map(|f| serde_json::json!({
-                                "name": f.name,
-                                "size": f.size,
-                                "isFolder": f.is_folder,
-                                "fileType": f.file_type,
-                                "creator": f.creator,
-                            })).collect::<Vec<_>>(),
+                            "files": files.iter().map(|f| {
+                                let item = ItemCapabilities::for_item(&access, f.is_folder);
+                                serde_json::json!({
+                                    "name": f.name,
+                                    "size": f.size,
+                                    "isFolder": f.is_folder,
+                                    "fileType": f.file_type,
+                                    "creator": f.creator,
+                                    "kind": f.kind,
+                                    "canDelete": item.can_delete,
+                                    "canRename": item.can_rename,
+                                })
+                            }).collect::<Vec<_>>(),
                             "path": path,
+                            "capabilities": FolderCapabilities::for_path(&access, &path),
                         });
-                        let _ = app_handle.emit(&format!("file-list-{}", server_id_clone), payload);
+                        server_windows::emit(&app_handle, &server_id_clone, "file-list", payload);
                     }
                     HotlineEvent::NewMessageBoardPost(message) => {
                         let payload = serde_json::json!({
                             "message": message,
                         });
-                        let _ = app_handle.emit(&format!("message-board-post-{}", server_id_clone), payload);
+                        server_windows::emit(&app_handle, &server_id_clone, "message-board-post", payload);
+                    }
+                    HotlineEvent::MessageBoardChunk(posts) => {
+                        let payload = serde_json::json!({
+                            "posts": posts,
+                        });
+                        server_windows::emit(&app_handle, &server_id_clone, "message-board-chunk", payload);
+                    }
+                    HotlineEvent::PrivateMessage { user_id, user_name, message, quoting } => {
+                        // Servers that don't enforce our options pass messages on;
+                        // answer them the way the server would have
+                        let options = messaging_clone.read().await.options();
+                        let response = match clients_clone.read().await.get(&server_id_clone) {
+                            Some(client) => client.automatic_response().await,
+                            None => None,
+                        };
+                        if options.refuse_messages {
+                            if messaging_clone.write().await.may_reply(&server_id_clone, user_id) {
+                                let reply = response.unwrap_or_default();
+                                spawn_instant_reply(&clients_clone, &server_id_clone, user_id, InstantMessageKind::RefuseMessage, reply);
+                            }
+                            continue;
+                        }
+                        if let Some(response) = response {
+                            if messaging_clone.write().await.may_reply(&server_id_clone, user_id) {
+                                spawn_instant_reply(&clients_clone, &server_id_clone, user_id, InstantMessageKind::AutomaticResponse, response);
+                            }
+                        }
+
+                        // Some servers leave the name out; take it from the user
+                        // list now, while the sender is still online
+                        let user_name = if user_name.is_empty() {
+                            match clients_clone.read().await.get(&server_id_clone) {
+                                Some(client) => client.user_name(user_id).await.unwrap_or_default(),
+                                None => String::new(),
+                            }
+                        } else {
+                            user_name
+                        };
+                        let conversation = {
+                            let mut conversations = conversations_clone.write().await;
+                            let summary = conversations.record_incoming(&server_id_clone, user_id, &user_name, &message);
+                            if let Err(e) = conversations.save() {
+                                eprintln!("{}", e);
+                            }
+                            summary
+                        };
+                        if let Err(e) = chat_log_clone.write().await.append_private(&server_id_clone, user_id, &user_name, &message, false) {
+                            eprintln!("{}", e);
+                        }
+
+                        let highlight = highlights_clone.read().await.evaluate(&user_name, &message);
+                        let payload = serde_json::json!({
+                            "userId": user_id,
+                            "userName": user_name,
+                            "message": message,
+                            "quoting": quoting,
+                            "matchedRules": highlight.rule_ids,
+                        });
+                        server_windows::emit(&app_handle, &server_id_clone, "private-message", payload.clone());
+                        server_windows::emit(&app_handle, &server_id_clone, "conversation-updated", conversation);
+                        if highlight.notify {
+                            server_windows::emit(&app_handle, &server_id_clone, "highlight", serde_json::json!({
+                                "source": "private",
+                                "event": payload,
+                            }));
+                        }
+                        if notification_prefs_clone.read().await.get(&server_id_clone).private_message {
+                            notifications::show_os_notification(
+                                &app_handle,
+                                &format!("Message from {} on {}", user_name, server_name),
+                                &message,
+                            );
+                        }
+                    }
+                    HotlineEvent::MessageRefused { user_id, user_name, message } => {
+                        server_windows::emit(&app_handle, &server_id_clone, "message-refused", serde_json::json!({
+                            "userId": user_id,
+                            "userName": user_name,
+                            "message": message,
+                        }));
+                    }
+                    HotlineEvent::ChatRefused { user_id, user_name, message } => {
+                        server_windows::emit(&app_handle, &server_id_clone, "chat-refused", serde_json::json!({
+                            "userId": user_id,
+                            "userName": user_name,
+                            "message": message,
+                        }));
+                    }
+                    HotlineEvent::AutomaticResponse { user_id, user_name, message } => {
+                        server_windows::emit(&app_handle, &server_id_clone, "automatic-response", serde_json::json!({
+                            "userId": user_id,
+                            "userName": user_name,
+                            "message": message,
+                        }));
+                    }
+                    HotlineEvent::ChatInvite { chat_id, user_id, user_name } => {
+                        if messaging_clone.read().await.options().refuse_chat {
+                            let clients = Arc::clone(&clients_clone);
+                            let server_id = server_id_clone.clone();
+                            tokio::spawn(async move {
+                                if let Some(client) = clients.read().await.get(&server_id) {
+                                    if let Err(e) = client.reject_private_chat_invite(chat_id).await {
+                                        eprintln!("Failed to reject chat invite: {}", e);
+                                    }
+                                }
+                            });
+                            spawn_instant_reply(&clients_clone, &server_id_clone, user_id, InstantMessageKind::RefuseChat, String::new());
+                            continue;
+                        }
+                        let payload = serde_json::json!({
+                            "chatId": chat_id,
+                            "userId": user_id,
+                            "userName": user_name,
+                        });
+                        server_windows::emit(&app_handle, &server_id_clone, "private-chat-invite", payload);
+                    }
+                    HotlineEvent::ChatUserJoined { chat_id, user_id, user_name, icon, flags } => {
+                        let payload = serde_json::json!({
+                            "chatId": chat_id,
+                            "userId": user_id,
+                            "userName": user_name,
+                            "iconId": icon,
+                            "flags": flags,
+                            "avatar": avatar::fallback(icon, &user_name),
+                        });
+                        server_windows::emit(&app_handle, &server_id_clone, "private-chat-user-joined", payload);
+                    }
+                    HotlineEvent::ChatUserLeft { chat_id, user_id } => {
+                        let payload = serde_json::json!({
+                            "chatId": chat_id,
+                            "userId": user_id,
+                        });
+                        server_windows::emit(&app_handle, &server_id_clone, "private-chat-user-left", payload);
+                    }
+                    HotlineEvent::ChatSubjectChanged { chat_id, subject } => {
+                        let payload = serde_json::json!({
+                            "chatId": chat_id,
+                            "subject": subject,
+                        });
+                        server_windows::emit(&app_handle, &server_id_clone, "private-chat-subject", payload);
                     }
-                    HotlineEvent::PrivateMessage { user_id, message } => {
+                    HotlineEvent::PrivateChatMessage { chat_id, user_id, user_name, message } => {
                         let payload = serde_json::json!({
+                            "chatId": chat_id,
                             "userId": user_id,
+                            "userName": user_name,
                             "message": message,
                         });
-                        let _ = app_handle.emit(&format!("private-message-{}", server_id_clone), payload);
+                        server_windows::emit(&app_handle, &server_id_clone, "private-chat-message", payload);
+                    }
+                    HotlineEvent::AwayChanged { away, automatic } => {
+                        let payload = serde_json::json!({
+                            "away": away,
+                            "automatic": automatic,
+                        });
+                        server_windows::emit(&app_handle, &server_id_clone, "away-changed", payload);
+                    }
+                    HotlineEvent::RequestFailed { action, error, timed_out } => {
+                        failures_clone.record(FailureCategory::Protocol, &action, Some(&server_id_clone), timed_out, &error);
+                    }
+                    HotlineEvent::Lifecycle(notice) => {
+                        diagnostic_log_clone.record(&format!("{} on {}: {}", notice.name(), server_id_clone, notice.detail()));
+                        let verbosity = preferences_clone.read().await.get().diagnostic_verbosity;
+                        if notice.shown_at(verbosity) {
+                            let payload = serde_json::json!({
+                                "notice": notice.name(),
+                                "detail": notice.detail(),
+                                "problem": notice.is_problem(),
+                                "timestamp": now_secs(),
+                            });
+                            server_windows::emit(&app_handle, &server_id_clone, "lifecycle", payload);
+                        }
+                    }
+                    HotlineEvent::ConnectionLost => {
+                        diagnostic_log_clone.record(&format!("Connection to {} lost", server_id_clone));
+                        failures_clone.record(FailureCategory::Connection, "connection", Some(&server_id_clone), true, "Connection lost");
+                        server_windows::emit(&app_handle, &server_id_clone, "session-summary", session_stats_clone.server(&server_id_clone));
+                        chat_log_clone.write().await.mark_disconnected(&server_id_clone);
+                        if notification_prefs_clone.read().await.get(&server_id_clone).disconnect {
+                            notifications::show_os_notification(
+                                &app_handle,
+                                "Disconnected",
+                                &format!("Lost the connection to {}", server_name),
+                            );
+                        }
+                        let policy = reconnect_clone.read().await.policy();
+                        if policy.enabled {
+                            tokio::spawn(reconnect::supervise(
+                                app_handle.clone(),
+                                Arc::clone(&clients_clone),
+                                server_id_clone.clone(),
+                                policy,
+                            ));
+                        } else {
+                            server_windows::emit(&app_handle, &server_id_clone, "status-changed", serde_json::json!({
+                                "status": crate::protocol::types::ConnectionStatus::Disconnected,
+                            }));
+                        }
                     }
                     HotlineEvent::StatusChanged(status) => {
                         let payload = serde_json::json!({
                             "status": status,
                         });
-                        let _ = app_handle.emit(&format!("status-changed-{}", server_id_clone), payload);
+                        server_windows::emit(&app_handle, &server_id_clone, "status-changed", payload);
                         
                         // Emit user access permissions when we're logged in
                         // This ensures we only emit after login is complete and user_access is set
                         if matches!(status, crate::protocol::types::ConnectionStatus::LoggedIn) {
+                            chat_log_clone.write().await.mark_logged_in(&server_id_clone);
                             // Get user access from the client (non-blocking, already logged in)
                             if let Some(client) = clients_clone.read().await.get(&server_id_clone) {
                                 let user_access = client.get_user_access().await;
-                                let access_payload = serde_json::json!({
-                                    "access": user_access,
-                                });
-                                let _ = app_handle.emit(&format!("user-access-{}", server_id_clone), access_payload);
+                                server_windows::emit(&app_handle, &server_id_clone, "user-access", user_access);
                             }
                         }
                     }
@@ -348,25 +1186,161 @@ impl AppState {
             println!("Event forwarding task ended for server {}", server_id_clone);
         });
 
+        let refresh_task = tokio::spawn(Self::run_server_refresh(self.app_handle.clone(), server_id.clone()));
+        self.server_refresh.write().await.start(&server_id, refresh_task);
+        let away_task = tokio::spawn(Self::run_auto_away(self.app_handle.clone(), server_id.clone()));
+        self.auto_away.write().await.start(&server_id, away_task);
+        if let Some(limit) = idle_limit {
+            let idle_task = tokio::spawn(Self::run_idle_disconnect(self.app_handle.clone(), server_id.clone(), limit));
+            self.idle_disconnect.write().await.start(&server_id, idle_task);
+        }
+
+        let users = match self.clients.read().await.get(&server_id) {
+            Some(client) => client.load_user_list().await.unwrap_or_else(|e| {
+                eprintln!("Failed to load the user list of {}: {}", server_id, e);
+                Vec::new()
+            }),
+            None => Vec::new(),
+        };
+
+        // By now an agreement, if the server has one, has arrived with the
+        // user list's reply; the actions then wait for it to be accepted
+        self.connect_actions.write().await.hold(&server_id, bookmark.on_connect.clone());
+        self.run_connect_actions(&server_id).await;
+
         Ok(crate::commands::ConnectResult {
             server_id,
             tls: final_tls,
             port: final_port,
+            users,
         })
     }
 
+    /// The client for `server_id`, for work that runs too long to hold the
+    /// clients lock: a connect or disconnect meanwhile would wait for it
+    async fn shared_client(&self, server_id: &str) -> Result<Arc<HotlineClient>, String> {
+        self.clients
+            .read()
+            .await
+            .get(server_id)
+            .cloned()
+            .ok_or_else(|| tr(MessageId::ServerNotConnected))
+    }
+
     pub async fn disconnect_server(&self, server_id: &str) -> Result<(), String> {
+        // Stop in-flight requests first, so transfers don't outlive the session
+        self.operations.write().await.cancel_server(server_id);
+
         let mut clients = self.clients.write().await;
 
         if let Some(client) = clients.get(server_id) {
             client.disconnect().await?;
             clients.remove(server_id);
+            self.server_refresh.write().await.stop(server_id);
+            self.auto_away.write().await.stop(server_id);
+            self.idle_disconnect.write().await.stop(server_id);
+            self.connect_actions.write().await.forget(server_id);
+            self.metrics.record_disconnect();
+            self.diagnostic_log.record(&format!("Disconnected from {}", server_id));
+            server_windows::emit(&self.app_handle, server_id, "session-summary", self.session_stats.server(server_id));
             Ok(())
         } else {
             Err("Server not found".to_string())
         }
     }
 
+    /// Start shutting down; false when another exit request already did
+    pub fn begin_shutdown(&self) -> bool {
+        self.shutdown.begin()
+    }
+
+    pub fn is_shut_down(&self) -> bool {
+        self.shutdown.is_finished()
+    }
+
+    /// Quit without waiting for the transfers still running
+    pub fn quit_now(&self) {
+        self.shutdown.skip_wait();
+    }
+
+    /// Wind down before the app exits: give running transfers a grace period,
+    /// pause what's left, then disconnect every server so none keeps listing
+    /// us as a user
+    pub async fn shutdown(&self) {
+        let deadline = Instant::now() + shutdown::TRANSFER_GRACE;
+        let mut announced = 0;
+        loop {
+            let running: Vec<String> = self
+                .list_transfers(None)
+                .into_iter()
+                .filter(|item| item.state == TransferState::Running)
+                .map(|item| item.id)
+                .collect();
+            if running.is_empty() {
+                break;
+            }
+            if Instant::now() >= deadline || !self.shutdown.waits_for_transfers() {
+                for id in running {
+                    if let Err(e) = self.pause_transfer(&id).await {
+                        eprintln!("Failed to pause {} while quitting: {}", id, e);
+                    }
+                }
+                break;
+            }
+            if running.len() != announced {
+                announced = running.len();
+                let _ = self.app_handle.emit("shutdown-waiting", server_windows::versioned(serde_json::json!({
+                    "transfers": announced,
+                    "graceSecs": shutdown::TRANSFER_GRACE.as_secs(),
+                })));
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+        }
+
+        let server_ids: Vec<String> = self.clients.read().await.keys().cloned().collect();
+        for server_id in server_ids {
+            if let Err(e) = self.disconnect_server(&server_id).await {
+                eprintln!("Failed to disconnect {} while quitting: {}", server_id, e);
+            }
+        }
+        // Chat logs are written line by line; conversations get a last save
+        if let Err(e) = self.conversations.read().await.save() {
+            eprintln!("Failed to save conversations while quitting: {}", e);
+        }
+        self.shutdown.finish();
+    }
+
+    /// Move a connected server into a window of its own, or focus the one it has
+    pub async fn open_server_window(&self, server_id: &str, title: &str) -> Result<(), String> {
+        if !self.clients.read().await.contains_key(server_id) {
+            return Err(tr(MessageId::ServerNotConnected));
+        }
+        server_windows::open(&self.app_handle, server_id, title)
+    }
+
+    pub fn close_server_window(&self, server_id: &str) -> Result<(), String> {
+        server_windows::close(&self.app_handle, server_id)
+    }
+
+    /// A server window was closed, by the user or close_server_window
+    pub async fn server_window_closed(&self, server_id: &str) {
+        if self.clients.read().await.contains_key(server_id) {
+            if let Err(e) = self.disconnect_server(server_id).await {
+                eprintln!("Failed to disconnect {} after its window closed: {}", server_id, e);
+            }
+        }
+    }
+
+    /// Change our nickname and icon on one server without reconnecting
+    pub async fn update_user_info(&self, server_id: &str, username: &str, icon_id: u16) -> Result<(), String> {
+        if username.trim().is_empty() {
+            return Err("Nickname cannot be empty".to_string());
+        }
+        let clients = self.clients.read().await;
+        let client = clients.get(server_id).ok_or_else(|| tr(MessageId::ServerNotConnected))?;
+        client.send_set_client_user_info(username.trim(), icon_id).await
+    }
+
     pub async fn update_user_info_all_servers(&self, username: &str, icon_id: u16) -> Result<(), String> {
         let clients = self.clients.read().await;
         let mut errors = Vec::new();
@@ -390,134 +1364,466 @@ impl AppState {
         if let Some(client) = clients.get(server_id) {
             client.send_chat(message).await
         } else {
-            Err("Server not connected".to_string())
+            Err(tr(MessageId::ServerNotConnected))
         }
     }
 
-    pub async fn send_private_message(&self, server_id: &str, user_id: u16, message: String) -> Result<(), String> {
+    /// Send a private message. With `quote_reply`, a message answering one
+    /// we received quotes it.
+    pub async fn send_private_message(&self, server_id: &str, user_id: u16, message: String, quote_reply: bool) -> Result<(), String> {
         let clients = self.clients.read().await;
 
         if let Some(client) = clients.get(server_id) {
-            client.send_private_message(user_id, message).await
+            let quoting = if quote_reply {
+                self.conversations.read().await.reply_quote(server_id, user_id)
+            } else {
+                None
+            };
+            client.send_private_message(user_id, message.clone(), quoting.as_deref()).await?;
+
+            let own_name = client.get_username().await;
+            if let Err(e) = self.chat_log.write().await.append_private(server_id, user_id, &own_name, &message, true) {
+                eprintln!("{}", e);
+            }
+            let recipient = client.user_name(user_id).await.unwrap_or_default();
+            let mut conversations = self.conversations.write().await;
+            conversations.record_outgoing(server_id, user_id, &recipient, &own_name, &message);
+            conversations.save()
         } else {
-            Err("Server not connected".to_string())
+            Err(tr(MessageId::ServerNotConnected))
         }
     }
 
-    pub async fn send_broadcast(&self, server_id: &str, message: String) -> Result<(), String> {
+    pub async fn create_private_chat(&self, server_id: &str, user_ids: Vec<u16>) -> Result<u32, String> {
         let clients = self.clients.read().await;
+
         if let Some(client) = clients.get(server_id) {
-            client.send_broadcast(message).await
+            client.create_private_chat(&user_ids).await
         } else {
-            Err("Server not connected".to_string())
+            Err(tr(MessageId::ServerNotConnected))
         }
     }
 
-    pub async fn create_folder(&self, server_id: &str, path: Vec<String>, name: String) -> Result<(), String> {
+    pub async fn invite_to_private_chat(&self, server_id: &str, chat_id: u32, user_id: u16) -> Result<(), String> {
         let clients = self.clients.read().await;
+
         if let Some(client) = clients.get(server_id) {
-            client.create_folder(path, name).await
+            client.invite_to_private_chat(chat_id, user_id).await
         } else {
-            Err("Server not connected".to_string())
+            Err(tr(MessageId::ServerNotConnected))
         }
     }
 
-    pub async fn create_news_category(&self, server_id: &str, path: Vec<String>, name: String) -> Result<(), String> {
+    pub async fn join_private_chat(&self, server_id: &str, chat_id: u32) -> Result<PrivateChatInfo, String> {
         let clients = self.clients.read().await;
+
         if let Some(client) = clients.get(server_id) {
-            client.create_news_category(path, name).await
+            client.join_private_chat(chat_id).await
         } else {
-            Err("Server not connected".to_string())
+            Err(tr(MessageId::ServerNotConnected))
         }
     }
 
-    pub async fn create_news_folder(&self, server_id: &str, path: Vec<String>, name: String) -> Result<(), String> {
+    pub async fn leave_private_chat(&self, server_id: &str, chat_id: u32) -> Result<(), String> {
         let clients = self.clients.read().await;
+
         if let Some(client) = clients.get(server_id) {
-            client.create_news_folder(path, name).await
+            client.leave_private_chat(chat_id).await
         } else {
-            Err("Server not connected".to_string())
+            Err(tr(MessageId::ServerNotConnected))
         }
     }
 
-    pub async fn delete_news_item(&self, server_id: &str, path: Vec<String>) -> Result<(), String> {
+    pub async fn reject_private_chat_invite(&self, server_id: &str, chat_id: u32) -> Result<(), String> {
         let clients = self.clients.read().await;
+
         if let Some(client) = clients.get(server_id) {
-            client.delete_news_item(path).await
+            client.reject_private_chat_invite(chat_id).await
         } else {
-            Err("Server not connected".to_string())
+            Err(tr(MessageId::ServerNotConnected))
         }
     }
 
-    pub async fn delete_news_article(&self, server_id: &str, path: Vec<String>, article_id: u32, recursive: bool) -> Result<(), String> {
+    pub async fn send_private_chat(&self, server_id: &str, chat_id: u32, message: String) -> Result<(), String> {
         let clients = self.clients.read().await;
+
         if let Some(client) = clients.get(server_id) {
-            client.delete_news_article(path, article_id, recursive).await
+            client.send_private_chat(chat_id, message).await
         } else {
-            Err("Server not connected".to_string())
+            Err(tr(MessageId::ServerNotConnected))
         }
     }
 
-    pub async fn get_pending_agreement(&self, server_id: &str) -> Option<String> {
-        let pending = self.pending_agreements.read().await;
-        pending.get(server_id).cloned()
-    }
-
-    pub async fn accept_agreement(&self, server_id: &str) -> Result<(), String> {
+    pub async fn set_private_chat_subject(&self, server_id: &str, chat_id: u32, subject: String) -> Result<(), String> {
         let clients = self.clients.read().await;
 
         if let Some(client) = clients.get(server_id) {
-            // Remove agreement from pending after acceptance
-            {
-                let mut pending = self.pending_agreements.write().await;
-                pending.remove(server_id);
-            }
-            client.accept_agreement().await
+            client.set_private_chat_subject(chat_id, subject).await
         } else {
-            Err("Server not connected".to_string())
+            Err(tr(MessageId::ServerNotConnected))
         }
     }
 
-    pub async fn download_banner(&self, server_id: &str) -> Result<String, String> {
-        let clients = self.clients.read().await;
+    pub async fn get_messaging_options(&self) -> MessagingOptions {
+        self.messaging.read().await.options()
+    }
 
-        if let Some(client) = clients.get(server_id) {
-            // Get reference number and transfer size
-            let (reference_number, transfer_size) = client.download_banner().await?;
-            
-            println!("Banner download info - reference: {}, transferSize: {}", reference_number, transfer_size);
+    /// Store the options and send them to every connected server
+    pub async fn set_messaging_options(&self, options: MessagingOptions) -> Result<(), String> {
+        let mut messaging = self.messaging.write().await;
+        messaging.set(options)?;
+        let options = messaging.options();
+        drop(messaging);
+
+        for client in self.clients.read().await.values() {
+            client.set_messaging_options(options.clone()).await;
+            let username = client.get_username().await;
+            let icon_id = client.get_user_icon_id().await;
+            if let Err(e) = client.send_set_client_user_info(&username, icon_id).await {
+                eprintln!("Failed to update messaging options: {}", e);
+            }
+        }
+        Ok(())
+    }
 
-            // Download banner as raw image data (not FILP format)
-            let file_data = client.download_banner_raw(reference_number, transfer_size).await?;
+    pub async fn get_pm_conversations(&self, server_id: Option<&str>) -> Vec<ConversationSummary> {
+        self.conversations.read().await.list(server_id)
+    }
 
-            println!("Banner download complete, {} bytes received", file_data.len());
+    pub async fn get_pm_thread(&self, server_id: &str, user_id: u16) -> Result<Conversation, String> {
+        self.conversations
+            .read()
+            .await
+            .thread(server_id, user_id)
+            .ok_or_else(|| format!("No conversation with user {} on {}", user_id, server_id))
+    }
 
-            // Save banner to app data directory
-            let banner_path = self.bookmarks_path.parent()
-                .ok_or("Failed to get app data directory".to_string())?
-                .join(format!("banner-{}.png", server_id));
-            
-            std::fs::write(&banner_path, &file_data)
-                .map_err(|e| format!("Failed to save banner: {}", e))?;
+    pub async fn get_highlight_rules(&self) -> Vec<HighlightRule> {
+        self.highlights.read().await.rules()
+    }
 
-            println!("Banner saved to: {:?}", banner_path);
+    pub async fn set_highlight_rules(&self, rules: Vec<HighlightRule>) -> Result<(), String> {
+        self.highlights.write().await.set_rules(rules)
+    }
 
-            // Return path as string
-            banner_path.to_str()
-                .ok_or("Failed to convert banner path to string".to_string())
-                .map(|s| s.to_string())
-        } else {
-            Err("Server not connected".to_string())
-        }
+    pub async fn get_watch_list(&self, server_id: &str) -> WatchPreferences {
+        self.watch_list.read().await.get(server_id)
     }
 
-    pub async fn get_message_board(&self, server_id: &str) -> Result<Vec<String>, String> {
-        let clients = self.clients.read().await;
+    pub async fn set_watch_list(&self, server_id: &str, preferences: WatchPreferences) -> Result<(), String> {
+        self.watch_list.write().await.set(server_id, preferences)
+    }
 
+    pub async fn get_preferences(&self) -> Preferences {
+        self.preferences.read().await.get().clone()
+    }
+
+    /// Store the preferences, hand their defaults to the per-server settings
+    /// and apply changed transfer limits to live connections that use them
+    pub async fn set_preferences(&self, mut preferences: Preferences) -> Result<(), String> {
+        // Refuse download folders as broad as the preview allow list does
+        if let Some(directory) = &preferences.download_directory {
+            let directory = self.preview_allow_list.read().await.check(std::path::Path::new(directory))?;
+            preferences.download_directory = Some(directory.to_string_lossy().into_owned());
+        }
+        self.preferences.write().await.set(preferences.clone())?;
+        self.notification_prefs.write().await.set_defaults(preferences.notifications);
+        self.transfer_limits.write().await.set_defaults(preferences.transfer_limits);
+        for (server_id, client) in self.clients.read().await.iter() {
+            let limits = self.transfer_limits.read().await.get(server_id);
+            client.set_transfer_limits(limits).await;
+        }
+        if let Err(e) = self.app_handle.emit("preferences-changed", server_windows::versioned(&preferences)) {
+            eprintln!("Failed to emit preferences-changed: {}", e);
+        }
+        Ok(())
+    }
+
+    pub async fn get_notification_prefs(&self, server_id: &str) -> NotificationPrefs {
+        self.notification_prefs.read().await.get(server_id)
+    }
+
+    pub async fn set_notification_prefs(&self, server_id: &str, prefs: NotificationPrefs) -> Result<(), String> {
+        self.notification_prefs.write().await.set(server_id, prefs)
+    }
+
+    pub async fn get_mention_keywords(&self) -> Vec<String> {
+        self.mentions.read().await.get().to_vec()
+    }
+
+    pub async fn set_mention_keywords(&self, keywords: Vec<String>) -> Result<(), String> {
+        self.mentions.write().await.set(keywords)
+    }
+
+    pub async fn get_transfer_limits(&self, server_id: &str) -> TransferLimits {
+        self.transfer_limits.read().await.get(server_id)
+    }
+
+    /// Store limits for `server_id` and apply them to its live connection
+    pub async fn set_transfer_limits(&self, server_id: &str, limits: TransferLimits) -> Result<(), String> {
+        self.transfer_limits.write().await.set(server_id, limits)?;
+        if let Some(client) = self.clients.read().await.get(server_id) {
+            client.set_transfer_limits(limits).await;
+        }
+        Ok(())
+    }
+
+    pub async fn get_request_policy(&self, bookmark_id: &str) -> RequestPolicy {
+        self.request_policies.read().await.get(bookmark_id)
+    }
+
+    /// Store the policy for `bookmark_id` and apply it to its live connection
+    pub async fn set_request_policy(&self, bookmark_id: &str, policy: RequestPolicy) -> Result<(), String> {
+        self.request_policies.write().await.set(bookmark_id, policy)?;
+        if let Some(client) = self.clients.read().await.get(bookmark_id) {
+            client.set_request_policy(policy).await;
+        }
+        Ok(())
+    }
+
+    pub async fn mark_conversation_read(&self, server_id: &str, user_id: u16) -> Result<(), String> {
+        let mut conversations = self.conversations.write().await;
+        if !conversations.mark_read(server_id, user_id) {
+            return Err(format!("No conversation with user {} on {}", user_id, server_id));
+        }
+        conversations.save()
+    }
+
+    pub async fn broadcast_message(&self, server_id: &str, message: String) -> Result<(), String> {
+        let clients = self.clients.read().await;
+        if let Some(client) = clients.get(server_id) {
+            client.broadcast_message(message).await
+        } else {
+            Err(tr(MessageId::ServerNotConnected))
+        }
+    }
+
+    pub async fn create_folder(&self, server_id: &str, path: Vec<String>, name: String) -> Result<(), String> {
+        let clients = self.clients.read().await;
         if let Some(client) = clients.get(server_id) {
-            client.get_message_board().await
+            client.create_folder(path, name).await
         } else {
-            Err("Server not connected".to_string())
+            Err(tr(MessageId::ServerNotConnected))
+        }
+    }
+
+    pub async fn create_news_category(&self, server_id: &str, path: Vec<String>, name: String) -> Result<(), String> {
+        let clients = self.clients.read().await;
+        if let Some(client) = clients.get(server_id) {
+            client.create_news_category(path, name).await
+        } else {
+            Err(tr(MessageId::ServerNotConnected))
+        }
+    }
+
+    pub async fn create_news_folder(&self, server_id: &str, path: Vec<String>, name: String) -> Result<(), String> {
+        let clients = self.clients.read().await;
+        if let Some(client) = clients.get(server_id) {
+            client.create_news_folder(path, name).await
+        } else {
+            Err(tr(MessageId::ServerNotConnected))
+        }
+    }
+
+    pub async fn delete_news_item(&self, server_id: &str, path: Vec<String>) -> Result<(), String> {
+        let clients = self.clients.read().await;
+        if let Some(client) = clients.get(server_id) {
+            client.delete_news_item(path.clone()).await?;
+            if let Err(e) = self.news_cache.write().await.forget_articles(server_id, &path, None) {
+                eprintln!("Failed to update news cache: {}", e);
+            }
+            Ok(())
+        } else {
+            Err(tr(MessageId::ServerNotConnected))
+        }
+    }
+
+    pub async fn delete_news_article(&self, server_id: &str, path: Vec<String>, article_id: u32, recursive: bool) -> Result<(), String> {
+        let clients = self.clients.read().await;
+        if let Some(client) = clients.get(server_id) {
+            client.delete_news_article(path.clone(), article_id, recursive).await?;
+            // Which replies went with it isn't known here, so a recursive
+            // delete forgets the whole category's cached bodies
+            let article_id = if recursive { None } else { Some(article_id) };
+            if let Err(e) = self.news_cache.write().await.forget_articles(server_id, &path, article_id) {
+                eprintln!("Failed to update news cache: {}", e);
+            }
+            Ok(())
+        } else {
+            Err(tr(MessageId::ServerNotConnected))
+        }
+    }
+
+    pub async fn get_pending_agreement(&self, server_id: &str) -> Option<String> {
+        let pending = self.pending_agreements.read().await;
+        pending.get(server_id).cloned()
+    }
+
+    /// Agreement text for a server, whether or not it has been accepted yet
+    pub async fn get_agreement(&self, server_id: &str) -> Result<Option<String>, String> {
+        let clients = self.clients.read().await;
+        if let Some(client) = clients.get(server_id) {
+            match client.get_agreement().await {
+                Some(agreement) => Ok(Some(agreement)),
+                None => Ok(self.get_pending_agreement(server_id).await),
+            }
+        } else {
+            Err(tr(MessageId::ServerNotConnected))
+        }
+    }
+
+    pub async fn accept_agreement(&self, server_id: &str) -> Result<(), String> {
+        let clients = self.clients.read().await;
+
+        if let Some(client) = clients.get(server_id) {
+            // Remove agreement from pending after acceptance
+            {
+                let mut pending = self.pending_agreements.write().await;
+                pending.remove(server_id);
+            }
+            client.accept_agreement().await?;
+            drop(clients);
+            self.run_connect_actions(server_id).await;
+            Ok(())
+        } else {
+            Err(tr(MessageId::ServerNotConnected))
+        }
+    }
+
+    /// Start a bookmark's connect actions unless an agreement still needs
+    /// accepting. Results for the server window are announced with a
+    /// `connect-actions-finished` event.
+    async fn run_connect_actions(&self, server_id: &str) {
+        if self.pending_agreements.read().await.contains_key(server_id) {
+            return;
+        }
+        let actions = self.connect_actions.write().await.release(server_id);
+        if actions.is_empty() {
+            return;
+        }
+
+        let app_handle = self.app_handle.clone();
+        let server_id = server_id.to_string();
+        tokio::spawn(async move {
+            let state = app_handle.state::<AppState>();
+            let mut outcome = ConnectOutcome::default();
+            for action in actions {
+                let name = action.name();
+                let result = match action {
+                    ConnectAction::SendChat { text } => state.send_chat(&server_id, text).await,
+                    ConnectAction::OpenFolder { path } => state
+                        .get_file_list(&server_id, path.clone())
+                        .await
+                        .map(|()| outcome.folder = Some(path)),
+                    ConnectAction::FetchNews => state
+                        .get_message_board(&server_id, None)
+                        .await
+                        .map(|page| outcome.board = Some(page)),
+                };
+                if let Err(e) = result {
+                    eprintln!("Connect action {} on {} failed: {}", name, server_id, e);
+                }
+            }
+            state.connect_actions.write().await.finish(&server_id, outcome);
+            server_windows::emit(&app_handle, &server_id, "connect-actions-finished", serde_json::json!({}));
+        });
+    }
+
+    pub async fn take_connect_outcome(&self, server_id: &str) -> Option<ConnectOutcome> {
+        self.connect_actions.write().await.take_outcome(server_id)
+    }
+
+    /// Download the banner, save it to the app data directory and remember it
+    /// as the one the UI shows. Returns where it was saved.
+    pub async fn download_banner(&self, server_id: &str) -> Result<String, String> {
+        let file_data = self.fetch_banner(server_id).await?;
+        self.server_refresh.write().await.record_banner(server_id, &file_data);
+
+        let banner_path = self.banner_path(server_id)?;
+        banner_path.to_str()
+            .ok_or("Failed to convert banner path to string".to_string())
+            .map(|s| s.to_string())
+    }
+
+    fn banner_path(&self, server_id: &str) -> Result<PathBuf, String> {
+        Ok(self.bookmarks_path.parent()
+            .ok_or("Failed to get app data directory".to_string())?
+            .join(sanitize_path::sanitize_component(&format!("banner-{}.png", server_id))))
+    }
+
+    async fn fetch_banner(&self, server_id: &str) -> Result<Vec<u8>, String> {
+        let clients = self.clients.read().await;
+
+        if let Some(client) = clients.get(server_id) {
+            // Get reference number and transfer size
+            let (reference_number, transfer_size) = client.download_banner().await?;
+            
+            println!("Banner download info - reference: {}, transferSize: {}", reference_number, transfer_size);
+
+            // Download banner as raw image data (not FILP format)
+            let file_data = client.download_banner_raw(reference_number, transfer_size).await?;
+
+            println!("Banner download complete, {} bytes received", file_data.len());
+
+            // Save banner to app data directory
+            let banner_path = self.banner_path(server_id)?;
+            std::fs::write(&banner_path, &file_data)
+                .map_err(|e| format!("Failed to save banner: {}", e))?;
+
+            println!("Banner saved to: {:?}", banner_path);
+            Ok(file_data)
+        } else {
+            Err(tr(MessageId::ServerNotConnected))
+        }
+    }
+
+    /// Fetch the first page of the message board, falling back to the cached
+    /// copy when the server is unreachable
+    pub async fn get_message_board(&self, server_id: &str, operation_id: Option<String>) -> Result<BoardPage, String> {
+        let max_bytes = self.board_fetch.read().await.policy().max_bytes();
+        let clients = self.clients.read().await;
+
+        let result = if let Some(client) = clients.get(server_id) {
+            let (operation_id, token) = self.begin_operation(operation_id, "messageBoard", Some(server_id)).await;
+            let result = cancellable(&token, client.get_message_board(max_bytes)).await;
+            self.finish_operation(&operation_id).await;
+            result
+        } else {
+            Err(tr(MessageId::ServerNotConnected))
+        };
+        drop(clients);
+
+        let mut cache = self.news_cache.write().await;
+        match result {
+            Ok(page) => {
+                if let Err(e) = cache.put(server_id, news_cache::BOARD_KEY, CachedContent::Board { posts: page.posts.clone() }) {
+                    eprintln!("Failed to cache message board: {}", e);
+                }
+                Ok(page)
+            }
+            Err(e) => match cache.get(server_id, news_cache::BOARD_KEY) {
+                Some(CachedContent::Board { posts }) => {
+                    println!("Serving cached message board for {} ({})", server_id, e);
+                    Ok(BoardPage {
+                        total_bytes: posts.iter().map(|post| post.len() as u64).sum(),
+                        posts,
+                        continuation: None,
+                    })
+                }
+                _ => Err(e),
+            },
+        }
+    }
+
+    /// Load the next page of the message board fetched last
+    pub async fn get_more_message_board(&self, server_id: &str, continuation: BoardContinuation) -> Result<BoardPage, String> {
+        let max_bytes = self.board_fetch.read().await.policy().max_bytes();
+        let clients = self.clients.read().await;
+
+        if let Some(client) = clients.get(server_id) {
+            client.get_more_message_board(continuation, max_bytes).await
+        } else {
+            Err(tr(MessageId::ServerNotConnected))
         }
     }
 
@@ -527,177 +1833,841 @@ impl AppState {
         if let Some(client) = clients.get(server_id) {
             client.post_message_board(message).await
         } else {
-            Err("Server not connected".to_string())
+            Err(tr(MessageId::ServerNotConnected))
+        }
+    }
+
+    pub async fn get_file_list(&self, server_id: &str, path: Vec<String>) -> Result<(), String> {
+        let clients = self.clients.read().await;
+
+        if let Some(client) = clients.get(server_id) {
+            client.get_file_list(path).await
+        } else {
+            Err(tr(MessageId::ServerNotConnected))
+        }
+    }
+
+    /// Resolve the downloads directory: the folder asked for, else the one
+    /// from the preferences, else the platform default
+    async fn downloads_dir(&self, download_folder: Option<String>) -> Result<PathBuf, String> {
+        let download_folder = match download_folder {
+            Some(folder) => Some(folder),
+            None => self.preferences.read().await.get().download_directory.clone(),
+        };
+        let downloads_dir = match download_folder {
+            Some(folder) => PathBuf::from(folder),
+            None => self.platform_downloads_dir()?,
+        };
+
+        // Ensure downloads directory exists
+        fs::create_dir_all(&downloads_dir)
+            .map_err(|e| format!("Failed to create downloads directory: {}", e))?;
+
+        Ok(downloads_dir)
+    }
+
+    /// The platform's downloads directory, whatever the preferences say
+    fn platform_downloads_dir(&self) -> Result<PathBuf, String> {
+        let downloads_dir = if cfg!(target_os = "ios") {
+            self.app_handle
+                .path()
+                .document_dir()
+                .or_else(|_| {
+                    self.app_handle
+                        .path()
+                        .app_data_dir()
+                        .map(|dir| dir.join("Downloads"))
+                })
+                .map_err(|e| format!("Failed to get documents directory: {}", e))?
+        } else if cfg!(target_os = "android") {
+            self.app_handle
+                .path()
+                .download_dir()
+                .or_else(|_| {
+                    self.app_handle
+                        .path()
+                        .app_data_dir()
+                        .map(|dir| dir.join("Downloads"))
+                })
+                .map_err(|e| format!("Failed to get downloads directory: {}", e))?
+        } else {
+            self.app_handle
+                .path()
+                .download_dir()
+                .or_else(|_| {
+                    self.app_handle
+                        .path()
+                        .home_dir()
+                        .map(|dir| dir.join("Downloads"))
+                })
+                .or_else(|_| {
+                    self.app_handle
+                        .path()
+                        .app_data_dir()
+                        .map(|dir| dir.join("Downloads"))
+                })
+                .map_err(|e| format!("Failed to get downloads directory: {}", e))?
+        };
+
+        Ok(downloads_dir)
+    }
+
+    /// Start a download in the background and return its operation id. Progress and
+    /// the final result are reported through `operation-progress-{id}` and
+    /// `operation-finished-{id}` events.
+    pub async fn download_file(&self, server_id: &str, path: Vec<String>, file_name: String, file_size: u64, options: DownloadOptions, operation_id: Option<String>) -> Result<String, String> {
+        if !self.clients.read().await.contains_key(server_id) {
+            return Err(tr(MessageId::ServerNotConnected));
+        }
+        let entry = self.new_partial_download(self.next_transfer_id(), server_id, path, file_name, file_size, options).await?;
+
+        let (operation_id, token) = self.begin_operation(operation_id, "download", Some(server_id)).await;
+        self.spawn_operation(operation_id.clone(), token, OperationJob::Download { entry, offset: 0 });
+        Ok(operation_id)
+    }
+
+    /// Start downloading a whole folder tree into the downloads folder and return
+    /// its operation id. Aggregate progress is emitted as `folder-download-progress-{server}`.
+    pub async fn download_folder(&self, server_id: &str, path: Vec<String>, folder_name: String, download_folder: Option<String>, operation_id: Option<String>) -> Result<String, String> {
+        if !self.clients.read().await.contains_key(server_id) {
+            return Err(tr(MessageId::ServerNotConnected));
+        }
+        let destination = sanitize_path::local_path(&self.downloads_dir(download_folder).await?, std::slice::from_ref(&folder_name));
+
+        let (operation_id, token) = self.begin_operation(operation_id, "folderDownload", Some(server_id)).await;
+        self.spawn_operation(operation_id.clone(), token, OperationJob::FolderDownload {
+            server_id: server_id.to_string(),
+            path,
+            folder_name,
+            destination,
+        });
+        Ok(operation_id)
+    }
+
+    async fn run_folder_download(&self, server_id: &str, path: Vec<String>, folder_name: String, destination: PathBuf, cancel: &CancellationToken, operation_id: &str) -> Result<String, String> {
+        let client = self.shared_client(server_id).await?;
+
+        let mut record = TransferRecord::new(server_id, TransferDirection::Download, &path, &folder_name);
+        record.local_path = Some(destination.clone());
+        let requested = Instant::now();
+        let download = match cancellable(cancel, client.download_folder(path, folder_name.clone())).await {
+            Ok(download) => download,
+            Err(e) => {
+                self.record_transfer_history(record, requested.elapsed(), Some(&e), cancel).await;
+                return Err(e);
+            }
+        };
+
+        let app_handle = self.app_handle.clone();
+        let server_id_clone = server_id.to_string();
+        let operation_id = operation_id.to_string();
+        let mut meter = TransferMeter::new();
+        let started = Instant::now();
+        let files = client.perform_folder_download(download, &destination, cancel, move |item, progress| {
+            let percent = if progress.total_bytes > 0 {
+                (progress.bytes_received as f64 / progress.total_bytes as f64 * 100.0) as u32
+            } else {
+                0
+            };
+            let speed = meter.update(progress.bytes_received, progress.total_bytes);
+            let payload = serde_json::json!({
+                "operationId": operation_id,
+                "folderName": folder_name,
+                "itemPath": item.path,
+                "bytesReceived": progress.bytes_received,
+                "totalBytes": progress.total_bytes,
+                "itemsCompleted": progress.items_completed,
+                "itemCount": progress.item_count,
+                "progress": percent,
+                "bytesPerSec": speed.bytes_per_sec,
+                "etaSecs": speed.eta_secs,
+            });
+            server_windows::emit(&app_handle, &server_id_clone, "folder-download-progress", payload);
+            emit_operation_progress(&app_handle, &operation_id, progress.bytes_received, progress.total_bytes);
+        }).await;
+        let received = if files.is_ok() { download.total_size } else { 0 };
+        self.metrics.record_transfer(TransferDirection::Download, received, files.is_ok());
+        let file_count = files.as_ref().map(|count| u64::from(*count)).unwrap_or(0);
+        self.session_stats.record(server_id, TransferDirection::Download, file_count, received, started.elapsed(), files.is_ok());
+        record.size = received;
+        self.record_transfer_history(record, requested.elapsed(), files.as_ref().err(), cancel).await;
+        let files = files?;
+
+        println!("Folder saved to {:?} ({} files)", destination, files);
+        Ok(format!("Downloaded to: {}", destination.display()))
+    }
+
+    /// Check a preview path against the platform downloads directory, the app
+    /// cache and the user's extra preview folders. The download directory
+    /// preference isn't a root: the webview can set it.
+    pub async fn resolve_preview_path(&self, path: &str) -> Result<PathBuf, String> {
+        let mut roots = vec![self.platform_downloads_dir()?];
+        if let Ok(cache_dir) = self.app_handle.path().app_cache_dir() {
+            roots.push(cache_dir);
+        }
+        self.preview_allow_list.read().await.resolve(std::path::Path::new(path), &roots)
+    }
+
+    pub async fn get_preview_allow_list(&self) -> Vec<PathBuf> {
+        self.preview_allow_list.read().await.extra()
+    }
+
+    /// Add a folder picked in the native folder picker
+    pub async fn add_preview_folder(&self, directory: &std::path::Path) -> Result<Vec<PathBuf>, String> {
+        let mut allow_list = self.preview_allow_list.write().await;
+        allow_list.add(directory)?;
+        Ok(allow_list.extra())
+    }
+
+    pub async fn remove_preview_folder(&self, directory: &std::path::Path) -> Result<Vec<PathBuf>, String> {
+        let mut allow_list = self.preview_allow_list.write().await;
+        allow_list.remove(directory)?;
+        Ok(allow_list.extra())
+    }
+
+    fn next_transfer_id(&self) -> String {
+        format!("{}-{}", now_secs(), self.transfer_counter.fetch_add(1, Ordering::SeqCst))
+    }
+
+    async fn new_partial_download(&self, id: String, server_id: &str, path: Vec<String>, file_name: String, file_size: u64, options: DownloadOptions) -> Result<PartialDownload, String> {
+        let downloads_dir = self.downloads_dir(options.download_folder).await?;
+
+        let destination = downloads_dir.join(sanitize_path::sanitize_component(&file_name));
+        let partial_path = self.partial_downloads.read().await.part_path(&destination, &id);
+
+        Ok(PartialDownload {
+            id,
+            server_id: server_id.to_string(),
+            path,
+            file_name,
+            partial_path,
+            destination,
+            total_size: file_size,
+            bytes_received: 0,
+            updated_at: 0,
+            fork_mode: options.fork_mode,
+        })
+    }
+
+    /// Continue an interrupted download from the bytes already on disk. Like
+    /// `download_file`, this returns an operation id right away.
+    pub async fn resume_download(&self, transfer_id: &str, operation_id: Option<String>) -> Result<String, String> {
+        let (entry, offset) = self.resume_point(transfer_id).await?;
+        println!("Resuming download {} of {} at byte {}", entry.id, entry.file_name, offset);
+
+        let (operation_id, token) = self.begin_operation(operation_id, "download", Some(&entry.server_id)).await;
+        self.spawn_operation(operation_id.clone(), token, OperationJob::Download { entry, offset });
+        Ok(operation_id)
+    }
+
+    /// Look up a partial download and how many bytes of it are already on disk.
+    /// Resume offsets are 32-bit on the wire, so past 4GB the download picks
+    /// up from the last offset that can be expressed.
+    async fn resume_point(&self, transfer_id: &str) -> Result<(PartialDownload, u32), String> {
+        let entry = self
+            .partial_downloads
+            .read()
+            .await
+            .get(transfer_id)
+            .ok_or_else(|| format!("No partial download with id {}", transfer_id))?;
+
+        let offset = fs::metadata(&entry.partial_path)
+            .map(|m| u32::try_from(m.len()).unwrap_or(u32::MAX))
+            .map_err(|e| format!("Partial download data is missing: {}", e))?;
+
+        Ok((entry, offset))
+    }
+
+    pub async fn get_partial_downloads(&self, server_id: Option<&str>) -> Vec<PartialDownload> {
+        self.partial_downloads.read().await.list(server_id)
+    }
+
+    pub async fn discard_partial_download(&self, transfer_id: &str) -> Result<(), String> {
+        let mut manifest = self.partial_downloads.write().await;
+        if let Some(entry) = manifest.get(transfer_id) {
+            let _ = fs::remove_file(&entry.partial_path);
+        }
+        manifest.remove(transfer_id)
+    }
+
+    /// Hash a downloaded file and compare it with `expected_sha256` when given.
+    /// Only files the preview may read can be checked.
+    pub async fn verify_download(&self, path: &str, expected_sha256: Option<&str>) -> Result<DownloadVerification, String> {
+        let resolved = self.resolve_preview_path(path).await?;
+        let size = fs::metadata(&resolved)
+            .map_err(|e| format!("Failed to read {}: {}", resolved.display(), e))?
+            .len();
+        let sha256 = partial_downloads::sha256_file(&resolved).await?;
+        let matches = expected_sha256.map(|expected| expected.trim().eq_ignore_ascii_case(&sha256));
+        Ok(DownloadVerification { path: resolved, size, sha256, matches })
+    }
+
+    /// Add a finished or failed transfer to the history. Cancelled and paused
+    /// transfers pick up again later, so they aren't recorded.
+    async fn record_transfer_history(&self, mut record: TransferRecord, elapsed: std::time::Duration, error: Option<&String>, cancel: &CancellationToken) {
+        if cancel.is_cancelled() {
+            return;
+        }
+        record.finish(elapsed, error.map(String::as_str));
+        if let Err(e) = self.transfer_history.write().await.record(record) {
+            eprintln!("{}", e);
+        }
+    }
+
+    pub async fn get_transfer_history(&self, filter: &TransferHistoryFilter) -> Vec<TransferRecord> {
+        self.transfer_history.read().await.query(filter)
+    }
+
+    pub async fn clear_transfer_history(&self) -> Result<(), String> {
+        self.transfer_history.write().await.clear()
+    }
+
+    pub async fn get_hosting_config(&self) -> ServerConfig {
+        self.hosting.read().await.config().clone()
+    }
+
+    pub async fn set_hosting_config(&self, config: ServerConfig) -> Result<(), String> {
+        self.hosting.write().await.set_config(config)
+    }
+
+    pub async fn start_hosting(&self) -> Result<HostingStatus, String> {
+        self.hosting.write().await.start().await
+    }
+
+    pub async fn stop_hosting(&self) {
+        self.hosting.write().await.stop();
+    }
+
+    pub async fn get_hosting_status(&self) -> Option<HostingStatus> {
+        self.hosting.read().await.status().await
+    }
+
+    /// Download a file and move it into place, returning the result message and
+    /// the SHA-256 of the saved data
+    async fn run_download(&self, entry: PartialDownload, offset: u32, cancel: &CancellationToken, operation_id: &str) -> Result<(String, String), String> {
+        if offset == 0
+            && entry.fork_mode != ForkMode::MacBinary
+            && self.preferences.read().await.get().skip_existing_downloads
+            && partial_downloads::is_existing_copy(&entry.destination, entry.total_size)
+        {
+            // A resumed download with nothing received yet leaves no partial behind
+            let _ = self.discard_partial_download(&entry.id).await;
+            let sha256 = partial_downloads::sha256_file(&entry.destination).await?;
+            println!("Skipping download of {:?}, already saved at {:?}", entry.file_name, entry.destination);
+            return Ok((format!("Already downloaded to: {}", entry.destination.display()), sha256));
+        }
+
+        let mut record = TransferRecord::new(&entry.server_id, TransferDirection::Download, &entry.path, &entry.file_name);
+        let started = Instant::now();
+        let result = self.transfer_download(entry, offset, cancel, operation_id, &mut record).await;
+        self.record_transfer_history(record, started.elapsed(), result.as_ref().err(), cancel).await;
+        result
+    }
+
+    /// The transfer itself for `run_download`, filling in `record` as it learns
+    /// how much arrived and where it went
+    async fn transfer_download(&self, mut entry: PartialDownload, offset: u32, cancel: &CancellationToken, operation_id: &str, record: &mut TransferRecord) -> Result<(String, String), String> {
+        let client = self.shared_client(&entry.server_id).await?;
+
+        // Get reference number from server and server-reported file size
+        let download = cancellable(
+            cancel,
+            client.download_file(entry.path.clone(), entry.file_name.clone(), offset),
+        )
+        .await?;
+        let reference_number = download.reference_number;
+        let server_file_size = download.file_size;
+
+        println!("Got reference number {}, starting file transfer...", reference_number);
+        let offset = u64::from(offset);
+        if let Some(server_size) = server_file_size {
+            println!("Server reports file size: {} bytes ({:.2} MB)", server_size, server_size as f64 / 1_000_000.0);
+        }
+
+        // Prefer server-reported file size over file list size, but fall back to file list size if server reports 0
+        match server_file_size {
+            Some(server_size) if server_size > 0 && server_size >= offset => entry.total_size = server_size,
+            _ => println!("Server did not report a usable file size, using file list size: {} bytes", entry.total_size),
+        }
+        let remaining = entry.total_size.saturating_sub(offset);
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(offset > 0)
+            .truncate(offset == 0)
+            .open(&entry.partial_path)
+            .await
+            .map_err(|e| format!("Failed to open partial download file: {}", e))?;
+        if offset > 0 {
+            // Drop anything past the resume offset so the server's data lines up
+            file.set_len(offset)
+                .await
+                .map_err(|e| format!("Failed to open partial download file: {}", e))?;
+        }
+
+        // Record the transfer up front so it stays resumable even if this task is aborted
+        entry.bytes_received = offset;
+        self.partial_downloads.write().await.record(entry.clone())?;
+
+        // Perform the file transfer with progress callback
+        let app_handle = self.app_handle.clone();
+        let server_id_clone = entry.server_id.clone();
+        let file_name_clone = entry.file_name.clone();
+        let transfer_id = entry.id.clone();
+        let total_size = entry.total_size;
+        let operation_id = operation_id.to_string();
+        let mut meter = TransferMeter::new();
+        let mut forks = MacForks::default();
+        let keep_forks = entry.fork_mode != ForkMode::Discard;
+        let started = Instant::now();
+        let result = client.perform_file_transfer(
+            reference_number,
+            remaining,
+            &mut file,
+            keep_forks.then_some(&mut forks),
+            cancel,
+            move |bytes_read, _| {
+                let bytes_read = offset + bytes_read;
+                let total_bytes = total_size.max(bytes_read);
+                let progress = (bytes_read as f64 / total_bytes as f64 * 100.0) as u32;
+                let speed = meter.update(bytes_read, total_bytes);
+                let payload = serde_json::json!({
+                    "transferId": transfer_id,
+                    "fileName": file_name_clone,
+                    "bytesRead": bytes_read,
+                    "totalBytes": total_bytes,
+                    "progress": progress,
+                    "bytesPerSec": speed.bytes_per_sec,
+                    "etaSecs": speed.eta_secs,
+                });
+                server_windows::emit(&app_handle, &server_id_clone, "download-progress", payload);
+                emit_operation_progress(&app_handle, &operation_id, bytes_read, total_bytes);
+            }
+        ).await;
+
+        let _ = file.flush().await;
+        drop(file);
+
+        let received = tokio::fs::metadata(&entry.partial_path).await.map(|m| m.len()).unwrap_or(offset);
+        let complete = result.is_ok() && (entry.total_size == 0 || received >= entry.total_size);
+        self.metrics.record_transfer(TransferDirection::Download, received.saturating_sub(offset), complete);
+        self.session_stats.record(&entry.server_id, TransferDirection::Download, 1, received.saturating_sub(offset), started.elapsed(), complete);
+        record.size = received.saturating_sub(offset);
+
+        if !complete {
+            entry.bytes_received = received;
+            self.partial_downloads.write().await.record(entry.clone())?;
+
+            let payload = serde_json::json!({
+                "transferId": entry.id,
+                "fileName": entry.file_name,
+                "bytesReceived": received,
+                "totalBytes": entry.total_size,
+            });
+            server_windows::emit(&self.app_handle, &entry.server_id, "download-interrupted", payload);
+
+            let reason = result.err().unwrap_or_else(|| "connection closed early".to_string());
+            return Err(format!(
+                "Download interrupted after {} of {} bytes ({}). It can be resumed with transfer id {}",
+                received, entry.total_size, reason, entry.id
+            ));
+        }
+
+        if let Err(e) = partial_downloads::verify_size(received, entry.total_size) {
+            // Resuming can't fix data that doesn't match, so start over next time
+            let _ = tokio::fs::remove_file(&entry.partial_path).await;
+            self.partial_downloads.write().await.remove(&entry.id)?;
+            return Err(e);
+        }
+
+        println!("File transfer complete, {} bytes received", received);
+        println!("Saving file to: {:?} (original name: {:?})", entry.destination, entry.file_name);
+
+        let sha256;
+        let saved_to = if entry.fork_mode == ForkMode::MacBinary {
+            // The data fork becomes part of the .bin, assembled beside the
+            // partial file under a .part name of its own; the partial file goes away
+            let name = entry.destination.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+            let target = entry.destination.with_file_name(mac_forks::mac_binary_name(&name));
+            let staged = entry.partial_path.with_extension("bin.part");
+            mac_forks::write_mac_binary(&entry.partial_path, &staged, &entry.file_name, &forks)?;
+            let _ = tokio::fs::remove_file(&entry.partial_path).await;
+            sha256 = partial_downloads::sha256_file(&staged).await?;
+            partial_downloads::move_into_place(&staged, &target).await?;
+            target
+        } else {
+            sha256 = partial_downloads::sha256_file(&entry.partial_path).await?;
+            partial_downloads::move_into_place(&entry.partial_path, &entry.destination).await?;
+            if entry.fork_mode == ForkMode::AppleDouble && !forks.is_empty() {
+                mac_forks::write_apple_double(&entry.destination, &forks)?;
+            }
+            entry.destination.clone()
+        };
+        self.partial_downloads.write().await.remove(&entry.id)?;
+
+        println!("File saved successfully to {:?} (SHA-256 {})", saved_to, sha256);
+        record.local_path = Some(saved_to.clone());
+        record.sha256 = Some(sha256.clone());
+
+        Ok((format!("Downloaded to: {}", saved_to.display()), sha256))
+    }
+
+    pub async fn get_bookmarks(&self) -> Result<Vec<Bookmark>, String> {
+        let bookmarks = self.bookmarks.read().await;
+        Ok(bookmarks.clone())
+    }
+
+    /// Bookmarks whose name, address, tags or notes contain every word of `query`
+    pub async fn search_bookmarks(&self, query: &str) -> Vec<Bookmark> {
+        self.bookmarks
+            .read()
+            .await
+            .iter()
+            .filter(|bookmark| bookmark.matches(query))
+            .cloned()
+            .collect()
+    }
+
+    pub async fn get_recent_servers(&self) -> Vec<RecentServer> {
+        self.recent_servers.read().await.list()
+    }
+
+    pub async fn clear_recent_servers(&self) -> Result<(), String> {
+        self.recent_servers.write().await.clear()
+    }
+
+    pub async fn get_server_info(&self, server_id: &str) -> Result<crate::protocol::types::ServerInfo, String> {
+        let info = {
+            let clients = self.clients.read().await;
+            let client = clients.get(server_id).ok_or_else(|| tr(MessageId::ServerNotConnected))?;
+            client.get_server_info().await?
+        };
+        self.server_refresh.write().await.record_info(server_id, &info);
+        Ok(info)
+    }
+
+    /// Fetch server info and the banner again, emitting server-info-updated
+    /// and banner-updated for whichever changed since the UI last saw them.
+    /// Name and description only come with the login reply, so between
+    /// logins the info picks up what the server pushed (such as a new agreement).
+    pub async fn refresh_server_info(&self, server_id: &str) -> Result<RefreshOutcome, String> {
+        let info = {
+            let clients = self.clients.read().await;
+            let client = clients.get(server_id).ok_or_else(|| tr(MessageId::ServerNotConnected))?;
+            client.get_server_info().await?
+        };
+        let mut outcome = RefreshOutcome::default();
+        if self.server_refresh.write().await.record_info(server_id, &info) {
+            outcome.info_changed = true;
+            server_windows::emit(&self.app_handle, server_id, "server-info-updated", &info);
+        }
+
+        // Servers without a banner refuse the download; that isn't a failed refresh
+        match self.fetch_banner(server_id).await {
+            Ok(data) => {
+                if self.server_refresh.write().await.record_banner(server_id, &data) {
+                    outcome.banner_changed = true;
+                    server_windows::emit(&self.app_handle, server_id, "banner-updated", serde_json::json!({
+                        "bannerUrl": server_refresh::banner_data_url(&data),
+                    }));
+                }
+            }
+            Err(e) => println!("Banner refresh for {} skipped: {}", server_id, e),
+        }
+
+        Ok(outcome)
+    }
+
+    /// Refresh one connection on the policy's timer until it goes away
+    async fn run_server_refresh(app_handle: AppHandle, server_id: String) {
+        // How often to look at the policy again while periodic refresh is off
+        const DISABLED_POLL: std::time::Duration = std::time::Duration::from_secs(60);
+
+        let state = app_handle.state::<AppState>();
+        loop {
+            let interval = state.server_refresh.read().await.policy().interval();
+            tokio::time::sleep(interval.unwrap_or(DISABLED_POLL)).await;
+            if !state.clients.read().await.contains_key(&server_id) {
+                break;
+            }
+            if interval.is_none() {
+                continue;
+            }
+            if let Err(e) = state.refresh_server_info(&server_id).await {
+                println!("Periodic server info refresh for {} failed: {}", server_id, e);
+            }
+        }
+    }
+
+    /// Mark a connection away once it has been idle for the policy's time
+    async fn run_auto_away(app_handle: AppHandle, server_id: String) {
+        // How often to compare the idle time against the policy
+        const POLL: std::time::Duration = std::time::Duration::from_secs(30);
+
+        let state = app_handle.state::<AppState>();
+        loop {
+            tokio::time::sleep(POLL).await;
+            let policy = state.auto_away.read().await.policy();
+            let clients = state.clients.read().await;
+            let Some(client) = clients.get(&server_id) else {
+                break;
+            };
+            let Some(idle) = policy.idle() else {
+                continue;
+            };
+            if client.is_away().await || client.idle_for().await < idle {
+                continue;
+            }
+            if let Err(e) = client.set_away(Some(policy.status()), true).await {
+                println!("Automatic away for {} failed: {}", server_id, e);
+            }
+        }
+    }
+
+    /// Disconnect a connection that has been idle for `limit` with no
+    /// transfers running, after an `idle-disconnect-warning-{id}` event a
+    /// minute before
+    async fn run_idle_disconnect(app_handle: AppHandle, server_id: String, limit: std::time::Duration) {
+        // How often to compare the idle time against the limit
+        const POLL: std::time::Duration = std::time::Duration::from_secs(15);
+
+        let state = app_handle.state::<AppState>();
+        let mut warned = false;
+        loop {
+            tokio::time::sleep(POLL).await;
+            let idle = match state.clients.read().await.get(&server_id) {
+                Some(client) => client.idle_for().await,
+                None => break,
+            };
+            let transferring = state.operations.read().await.has_transfers(&server_id);
+            match idle_disconnect::idle_action(limit, idle, transferring, warned) {
+                IdleAction::Stay => {
+                    // Activity since the warning starts the countdown over
+                    warned &= idle + idle_disconnect::WARNING_LEAD >= limit;
+                }
+                IdleAction::Warn => {
+                    warned = true;
+                    server_windows::emit(&app_handle, &server_id, "idle-disconnect-warning", serde_json::json!({
+                        "secondsLeft": limit.saturating_sub(idle).as_secs(),
+                    }));
+                }
+                IdleAction::Disconnect => {
+                    state.diagnostic_log.record(&format!("Disconnecting {} after {} idle minutes", server_id, limit.as_secs() / 60));
+                    // Stopping the idle timer from inside would abort this task
+                    state.idle_disconnect.write().await.forget(&server_id);
+                    if let Err(e) = state.disconnect_server(&server_id).await {
+                        println!("Idle disconnect of {} failed: {}", server_id, e);
+                    }
+                    server_windows::emit(&app_handle, &server_id, "status-changed", serde_json::json!({
+                        "status": crate::protocol::types::ConnectionStatus::Disconnected,
+                    }));
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Go away with `message` as the automatic response, or come back
+    pub async fn set_away(&self, server_id: &str, away: bool, message: Option<String>) -> Result<(), String> {
+        let clients = self.clients.read().await;
+        let client = clients.get(server_id).ok_or_else(|| tr(MessageId::ServerNotConnected))?;
+        let status = if away {
+            let status = AwayStatus {
+                message: message.map(|text| text.trim().to_string()).filter(|text| !text.is_empty()),
+                nickname_suffix: self.auto_away.read().await.policy().nickname_suffix,
+            };
+            auto_away::validate_status(&status)?;
+            Some(status)
+        } else {
+            None
+        };
+        client.set_away(status, false).await
+    }
+
+    pub async fn get_user_access(&self, server_id: &str) -> Result<crate::protocol::types::UserAccess, String> {
+        let clients = self.clients.read().await;
+        if let Some(client) = clients.get(server_id) {
+            Ok(client.get_user_access().await)
+        } else {
+            Err(tr(MessageId::ServerNotConnected))
+        }
+    }
+
+    pub async fn get_connection_stats(&self, server_id: &str) -> Result<crate::protocol::client::ConnectionStats, String> {
+        let clients = self.clients.read().await;
+        if let Some(client) = clients.get(server_id) {
+            Ok(client.connection_stats().await)
+        } else {
+            Err(tr(MessageId::ServerNotConnected))
+        }
+    }
+
+    pub async fn get_file_info(&self, server_id: &str, path: Vec<String>, file_name: String) -> Result<crate::protocol::client::FileDetails, String> {
+        let clients = self.clients.read().await;
+
+        if let Some(client) = clients.get(server_id) {
+            client.get_file_info(path, file_name).await
+        } else {
+            Err(tr(MessageId::ServerNotConnected))
+        }
+    }
+
+    pub async fn set_file_info(&self, server_id: &str, path: Vec<String>, file_name: String, new_name: Option<String>, comment: Option<String>) -> Result<(), String> {
+        let clients = self.clients.read().await;
+
+        if let Some(client) = clients.get(server_id) {
+            client.set_file_info(path, file_name, new_name, comment).await
+        } else {
+            Err(tr(MessageId::ServerNotConnected))
+        }
+    }
+
+    pub async fn rename_file(&self, server_id: &str, path: Vec<String>, file_name: String, new_name: String) -> Result<(), String> {
+        let clients = self.clients.read().await;
+
+        if let Some(client) = clients.get(server_id) {
+            client.rename_file(path, file_name, new_name).await
+        } else {
+            Err(tr(MessageId::ServerNotConnected))
+        }
+    }
+
+    pub async fn move_file(&self, server_id: &str, path: Vec<String>, file_name: String, new_path: Vec<String>) -> Result<(), String> {
+        let clients = self.clients.read().await;
+
+        if let Some(client) = clients.get(server_id) {
+            client.move_file(path, file_name, new_path).await
+        } else {
+            Err(tr(MessageId::ServerNotConnected))
+        }
+    }
+
+    pub async fn make_file_alias(&self, server_id: &str, path: Vec<String>, file_name: String, alias_path: Vec<String>) -> Result<(), String> {
+        let clients = self.clients.read().await;
+
+        if let Some(client) = clients.get(server_id) {
+            client.make_file_alias(path, file_name, alias_path).await
+        } else {
+            Err(tr(MessageId::ServerNotConnected))
+        }
+    }
+
+    pub async fn delete_file(&self, server_id: &str, path: Vec<String>, file_name: String) -> Result<(), String> {
+        let clients = self.clients.read().await;
+
+        if let Some(client) = clients.get(server_id) {
+            client.delete_file(path, file_name).await
+        } else {
+            Err(tr(MessageId::ServerNotConnected))
+        }
+    }
+
+    pub async fn delete_files(&self, server_id: &str, files: Vec<FileRef>) -> Result<BatchResult, String> {
+        let clients = self.clients.read().await;
+        let client = clients.get(server_id).ok_or_else(|| tr(MessageId::ServerNotConnected))?;
+        let result = client.delete_files(files).await;
+        self.announce_files_changed(server_id, &result);
+        Ok(result)
+    }
+
+    pub async fn move_files(&self, server_id: &str, files: Vec<FileRef>, new_path: Vec<String>) -> Result<BatchResult, String> {
+        let clients = self.clients.read().await;
+        let client = clients.get(server_id).ok_or_else(|| tr(MessageId::ServerNotConnected))?;
+        let result = client.move_files(files, new_path).await;
+        self.announce_files_changed(server_id, &result);
+        Ok(result)
+    }
+
+    /// One `files-changed-{server_id}` event for a whole batch
+    fn announce_files_changed(&self, server_id: &str, result: &BatchResult) {
+        if !result.changed_folders.is_empty() {
+            server_windows::emit(&self.app_handle, server_id, "files-changed", serde_json::json!({
+                "folders": result.changed_folders,
+            }));
+        }
+    }
+
+    pub async fn get_users(&self, server_id: &str) -> Result<Vec<crate::protocol::User>, String> {
+        let clients = self.clients.read().await;
+        if let Some(client) = clients.get(server_id) {
+            Ok(client.get_users().await)
+        } else {
+            Err(tr(MessageId::ServerNotConnected))
+        }
+    }
+
+    pub async fn get_user_info(&self, server_id: &str, user_id: u16) -> Result<crate::protocol::client::UserInfo, String> {
+        let clients = self.clients.read().await;
+
+        if let Some(client) = clients.get(server_id) {
+            client.get_user_info(user_id).await
+        } else {
+            Err(tr(MessageId::ServerNotConnected))
+        }
+    }
+
+    pub async fn disconnect_user(&self, server_id: &str, user_id: u16, options: Option<u16>) -> Result<(), String> {
+        let clients = self.clients.read().await;
+        if let Some(client) = clients.get(server_id) {
+            client.disconnect_user(user_id, options).await?;
+            Self::refresh_roster(client).await;
+            Ok(())
+        } else {
+            Err(tr(MessageId::ServerNotConnected))
         }
     }
 
-    pub async fn get_file_list(&self, server_id: &str, path: Vec<String>) -> Result<(), String> {
-        let clients = self.clients.read().await;
-
-        if let Some(client) = clients.get(server_id) {
-            client.get_file_list(path).await
-        } else {
-            Err("Server not connected".to_string())
+    /// Re-sync the user list after a moderation or account change; failures
+    /// only leave the roster as it was, so they aren't reported to the caller
+    async fn refresh_roster(client: &HotlineClient) {
+        if let Err(e) = client.refresh_user_list().await {
+            eprintln!("Roster refresh failed: {}", e);
         }
     }
 
-    pub async fn download_file(&self, server_id: &str, path: Vec<String>, file_name: String, file_size: u32, download_folder: Option<String>) -> Result<String, String> {
+    pub async fn list_accounts(&self, server_id: &str) -> Result<Vec<Account>, String> {
         let clients = self.clients.read().await;
-
         if let Some(client) = clients.get(server_id) {
-            // Get reference number from server and server-reported file size
-            let (reference_number, server_file_size) = client.download_file(path, file_name.clone()).await?;
-
-            println!("Got reference number {}, starting file transfer...", reference_number);
-            if let Some(server_size) = server_file_size {
-                println!("Server reports file size: {} bytes ({:.2} MB)", server_size, server_size as f64 / 1_000_000.0);
-            }
-
-            // Prefer server-reported file size over file list size, but fall back to file list size if server reports 0
-            let effective_file_size = if let Some(server_size) = server_file_size {
-                if server_size > 0 {
-                    server_size
-                } else {
-                    println!("Server reported file size is 0, using file list size: {} bytes", file_size);
-                    file_size
-                }
-            } else {
-                println!("Server did not report file size, using file list size: {} bytes", file_size);
-                file_size
-            };
-
-            // Perform the file transfer with progress callback
-            let app_handle = self.app_handle.clone();
-            let server_id_clone = server_id.to_string();
-            let file_name_clone = file_name.clone();
-            let file_data = client.perform_file_transfer(
-                reference_number,
-                effective_file_size,
-                move |bytes_read, total_bytes| {
-                    let progress = (bytes_read as f64 / total_bytes as f64 * 100.0) as u32;
-                    let payload = serde_json::json!({
-                        "fileName": file_name_clone,
-                        "bytesRead": bytes_read,
-                        "totalBytes": total_bytes,
-                        "progress": progress,
-                    });
-                    let _ = app_handle.emit(&format!("download-progress-{}", server_id_clone), payload);
-                }
-            ).await?;
-
-            println!("File transfer complete, {} bytes received", file_data.len());
-
-            // Get downloads directory: use user preference if set, otherwise fall back to system default
-            let downloads_dir = if let Some(ref folder) = download_folder {
-                std::path::PathBuf::from(folder)
-            } else if cfg!(target_os = "ios") {
-                self.app_handle
-                    .path()
-                    .document_dir()
-                    .or_else(|_| {
-                        self.app_handle
-                            .path()
-                            .app_data_dir()
-                            .map(|dir| dir.join("Downloads"))
-                    })
-                    .map_err(|e| format!("Failed to get documents directory: {}", e))?
-            } else if cfg!(target_os = "android") {
-                self.app_handle
-                    .path()
-                    .download_dir()
-                    .or_else(|_| {
-                        self.app_handle
-                            .path()
-                            .app_data_dir()
-                            .map(|dir| dir.join("Downloads"))
-                    })
-                    .map_err(|e| format!("Failed to get downloads directory: {}", e))?
-            } else {
-                self.app_handle
-                    .path()
-                    .download_dir()
-                    .or_else(|_| {
-                        self.app_handle
-                            .path()
-                            .home_dir()
-                            .map(|dir| dir.join("Downloads"))
-                    })
-                    .or_else(|_| {
-                        self.app_handle
-                            .path()
-                            .app_data_dir()
-                            .map(|dir| dir.join("Downloads"))
-                    })
-                    .map_err(|e| format!("Failed to get downloads directory: {}", e))?
-            };
-
-            // Ensure downloads directory exists
-            fs::create_dir_all(&downloads_dir)
-                .map_err(|e| format!("Failed to create downloads directory: {}", e))?;
-
-            // Sanitize filename for filesystem (handle unicode and invalid characters)
-            // Replace invalid path characters with underscore
-            let sanitized_name = file_name
-                .chars()
-                .map(|c| {
-                    if c.is_control() || matches!(c, '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*') {
-                        '_'
-                    } else {
-                        c
-                    }
-                })
-                .collect::<String>();
-            
-            // Create full file path
-            let file_path = downloads_dir.join(&sanitized_name);
-
-            println!("Saving file to: {:?} (original name: {:?})", file_path, file_name);
-
-            // Save file to disk
-            fs::write(&file_path, file_data)
-                .map_err(|e| format!("Failed to write file: {}", e))?;
-
-            println!("File saved successfully to {:?}", file_path);
-
-            Ok(format!("Downloaded to: {}", file_path.display()))
+            client.list_accounts().await
         } else {
-            Err("Server not connected".to_string())
+            Err(tr(MessageId::ServerNotConnected))
         }
     }
 
-    pub async fn get_bookmarks(&self) -> Result<Vec<Bookmark>, String> {
-        let bookmarks = self.bookmarks.read().await;
-        Ok(bookmarks.clone())
-    }
-
-    pub async fn get_server_info(&self, server_id: &str) -> Result<crate::protocol::types::ServerInfo, String> {
+    pub async fn get_account(&self, server_id: &str, login: &str) -> Result<Account, String> {
         let clients = self.clients.read().await;
         if let Some(client) = clients.get(server_id) {
-            client.get_server_info().await
+            client.get_account(login).await
         } else {
-            Err("Server not connected".to_string())
+            Err(tr(MessageId::ServerNotConnected))
         }
     }
 
-    pub async fn get_user_access(&self, server_id: &str) -> Result<u64, String> {
+    pub async fn save_account(&self, server_id: &str, account: Account, is_new: bool) -> Result<(), String> {
         let clients = self.clients.read().await;
         if let Some(client) = clients.get(server_id) {
-            Ok(client.get_user_access().await)
+            client.save_account(&account, is_new).await?;
+            Self::refresh_roster(client).await;
+            Ok(())
         } else {
-            Err("Server not connected".to_string())
+            Err(tr(MessageId::ServerNotConnected))
         }
     }
 
-    pub async fn disconnect_user(&self, server_id: &str, user_id: u16, options: Option<u16>) -> Result<(), String> {
+    pub async fn delete_account(&self, server_id: &str, login: &str) -> Result<(), String> {
         let clients = self.clients.read().await;
         if let Some(client) = clients.get(server_id) {
-            client.disconnect_user(user_id, options).await
+            client.delete_account(login).await?;
+            Self::refresh_roster(client).await;
+            Ok(())
         } else {
-            Err("Server not connected".to_string())
+            Err(tr(MessageId::ServerNotConnected))
         }
     }
 
-    pub async fn save_bookmark(&self, bookmark: Bookmark) -> Result<(), String> {
+    pub async fn save_bookmark(&self, mut bookmark: Bookmark) -> Result<(), String> {
+        bookmark.tidy();
         let mut bookmarks = self.bookmarks.write().await;
 
         // Check if bookmark already exists, update it
@@ -742,6 +2712,46 @@ impl AppState {
         Ok(())
     }
 
+    pub async fn get_bookmark_groups(&self) -> Vec<BookmarkGroup> {
+        self.bookmark_groups.read().await.list()
+    }
+
+    pub async fn create_bookmark_group(&self, name: &str, parent: Option<String>) -> Result<BookmarkGroup, String> {
+        self.bookmark_groups.write().await.create(name, parent)
+    }
+
+    pub async fn rename_bookmark_group(&self, id: &str, name: &str) -> Result<(), String> {
+        self.bookmark_groups.write().await.rename(id, name)
+    }
+
+    /// Nest a group in another (or at the top level), at `index` among its siblings
+    pub async fn move_bookmark_group(&self, id: &str, parent: Option<String>, index: Option<usize>) -> Result<(), String> {
+        self.bookmark_groups.write().await.move_group(id, parent, index)
+    }
+
+    /// Delete a group; its bookmarks and subgroups move up to its parent
+    pub async fn delete_bookmark_group(&self, id: &str) -> Result<(), String> {
+        let parent = self.bookmark_groups.write().await.remove(id)?;
+        let mut bookmarks = self.bookmarks.write().await;
+        for bookmark in bookmarks.iter_mut().filter(|bookmark| bookmark.group.as_deref() == Some(id)) {
+            bookmark.group = parent.clone();
+        }
+        self.save_bookmarks_to_disk(&bookmarks)
+    }
+
+    /// File a bookmark under `group` (None for the top level), at `index`
+    /// among the group's bookmarks or last
+    pub async fn move_bookmark_to_group(&self, bookmark_id: &str, group: Option<String>, index: Option<usize>) -> Result<(), String> {
+        if let Some(group) = &group {
+            if !self.bookmark_groups.read().await.contains(group) {
+                return Err("Group not found".to_string());
+            }
+        }
+        let mut bookmarks = self.bookmarks.write().await;
+        bookmark_groups::place_bookmark(&mut bookmarks, bookmark_id, group, index)?;
+        self.save_bookmarks_to_disk(&bookmarks)
+    }
+
     pub async fn add_default_bookmarks(&self) -> Result<Vec<Bookmark>, String> {
         use crate::protocol::constants::{DEFAULT_SERVER_PORT, DEFAULT_TLS_PORT, DEFAULT_TRACKER_PORT};
         use crate::protocol::types::BookmarkType;
@@ -784,6 +2794,11 @@ impl AppState {
                     auto_connect: false,
                     tls: false,
                     bookmark_type: Some(BookmarkType::Tracker),
+                    notes: None,
+                    tags: Vec::new(),
+                    idle_disconnect_mins: None,
+                    group: None,
+                    on_connect: Vec::new(),
                 };
                 bookmarks.push(tracker);
                 added_count += 1;
@@ -809,6 +2824,11 @@ impl AppState {
                     auto_connect: false,
                     tls: *tls,
                     bookmark_type: Some(BookmarkType::Server),
+                    notes: None,
+                    tags: Vec::new(),
+                    idle_disconnect_mins: None,
+                    group: None,
+                    on_connect: Vec::new(),
                 };
                 bookmarks.push(server);
                 added_count += 1;
@@ -824,80 +2844,801 @@ impl AppState {
         Ok(result)
     }
 
-    pub async fn get_news_categories(&self, server_id: &str, path: Vec<String>) -> Result<Vec<crate::protocol::types::NewsCategory>, String> {
+    pub async fn get_news_categories(&self, server_id: &str, path: Vec<String>, operation_id: Option<String>) -> Result<Vec<crate::protocol::types::NewsCategory>, String> {
+        let clients = self.clients.read().await;
+
+        if let Some(client) = clients.get(server_id) {
+            let (operation_id, token) = self.begin_operation(operation_id, "newsCategories", Some(server_id)).await;
+            let result = cancellable(&token, client.get_news_categories(path)).await;
+            self.finish_operation(&operation_id).await;
+            result
+        } else {
+            Err(tr(MessageId::ServerNotConnected))
+        }
+    }
+
+    pub async fn get_news_articles(&self, server_id: &str, path: Vec<String>, operation_id: Option<String>) -> Result<Vec<crate::protocol::types::NewsArticle>, String> {
         let clients = self.clients.read().await;
 
         if let Some(client) = clients.get(server_id) {
-            client.get_news_categories(path).await
+            let (operation_id, token) = self.begin_operation(operation_id, "newsArticles", Some(server_id)).await;
+            let result = cancellable(&token, client.get_news_articles(path)).await;
+            self.finish_operation(&operation_id).await;
+            result
         } else {
-            Err("Server not connected".to_string())
+            Err(tr(MessageId::ServerNotConnected))
         }
     }
 
-    pub async fn get_news_articles(&self, server_id: &str, path: Vec<String>) -> Result<Vec<crate::protocol::types::NewsArticle>, String> {
+    /// A category's articles as reply trees
+    pub async fn get_news_thread_tree(&self, server_id: &str, path: Vec<String>, operation_id: Option<String>) -> Result<Vec<NewsThread>, String> {
+        let articles = self.get_news_articles(server_id, path, operation_id).await?;
+        Ok(news_threads::build(articles))
+    }
+
+    /// Article bodies are served from the news cache when available, so
+    /// previously read articles open without a round trip (or a connection)
+    pub async fn get_news_article_data(&self, server_id: &str, article_id: u32, path: Vec<String>, operation_id: Option<String>) -> Result<String, String> {
+        let key = news_cache::article_key(&path, article_id);
+        if let Some(CachedContent::Article { body, .. }) = self.news_cache.write().await.get(server_id, &key) {
+            return Ok(body);
+        }
+
         let clients = self.clients.read().await;
 
         if let Some(client) = clients.get(server_id) {
-            client.get_news_articles(path).await
+            let (operation_id, token) = self.begin_operation(operation_id, "newsArticle", Some(server_id)).await;
+            let result = cancellable(&token, client.get_news_article_data(article_id, path.clone())).await;
+            self.finish_operation(&operation_id).await;
+
+            if let Ok(body) = &result {
+                let content = CachedContent::Article {
+                    path,
+                    article_id,
+                    body: body.clone(),
+                };
+                if let Err(e) = self.news_cache.write().await.put(server_id, &key, content) {
+                    eprintln!("Failed to cache news article: {}", e);
+                }
+            }
+            result
         } else {
-            Err("Server not connected".to_string())
+            Err(tr(MessageId::ServerNotConnected))
+        }
+    }
+
+    pub async fn get_cached_file_list(&self, server_id: &str, path: &[String]) -> Result<CachedView<Vec<FileInfo>>, String> {
+        self.file_list_cache
+            .read()
+            .await
+            .get(server_id, path)
+            .ok_or_else(|| "This folder has not been cached".to_string())
+    }
+
+    pub async fn get_cached_news(&self, server_id: &str) -> Vec<CachedView<CachedContent>> {
+        self.news_cache.read().await.list(server_id)
+    }
+
+    pub async fn get_chat_history(&self, server_id: &str, limit: Option<usize>, before: Option<u64>) -> Vec<ChatLogEntry> {
+        self.chat_log.read().await.history(server_id, limit, before)
+    }
+
+    pub async fn clear_chat_history(&self, server_id: Option<&str>) -> Result<(), String> {
+        self.chat_log.write().await.clear(server_id)
+    }
+
+    /// Save a chat transcript to the downloads folder and return its path.
+    /// The UI writes the text, so timestamps follow its time zone and locale.
+    pub async fn export_chat_transcript(&self, server_name: &str, transcript: &str, destination: Option<String>) -> Result<String, String> {
+        let dir = self.downloads_dir(destination).await?;
+        let file_name = sanitize_path::sanitize_component(&format!("{} chat {}.txt", server_name, now_secs()));
+        let path = dir.join(file_name);
+        fs::write(&path, transcript).map_err(|e| format!("Failed to write transcript: {}", e))?;
+        Ok(path.to_string_lossy().to_string())
+    }
+
+    /// Save a news category with its threads to the downloads folder and
+    /// return the file's path
+    pub async fn export_news(&self, server_id: &str, path: Vec<String>, format: NewsExportFormat, destination: Option<String>, operation_id: Option<String>) -> Result<String, String> {
+        let host = match self.clients.read().await.get(server_id) {
+            Some(client) => client.address().0.to_string(),
+            None => return Err(tr(MessageId::ServerNotConnected)),
+        };
+        let articles = self.get_news_articles(server_id, path.clone(), None).await?;
+
+        let (operation_id, token) = self.begin_operation(operation_id, "newsExport", Some(server_id)).await;
+        let mut bodies = HashMap::with_capacity(articles.len());
+        let mut result = Ok(());
+        for article in &articles {
+            if let Err(e) = check_cancelled(&token) {
+                result = Err(e);
+                break;
+            }
+            match self.get_news_article_data(server_id, article.id, article.path.clone(), None).await {
+                Ok(body) => {
+                    bodies.insert(article.id, body);
+                }
+                Err(e) => {
+                    result = Err(format!("Failed to fetch '{}': {}", article.title, e));
+                    break;
+                }
+            }
         }
+        self.finish_operation(&operation_id).await;
+        result?;
+
+        let threads = news_threads::build(articles);
+        let contents = match format {
+            NewsExportFormat::Markdown => news_export::to_markdown(&path, &threads, &bodies),
+            NewsExportFormat::Mbox => news_export::to_mbox(&path, &host, &threads, &bodies),
+        };
+        let dir = self.downloads_dir(destination).await?;
+        let title = if path.is_empty() { "news".to_string() } else { path.join(" - ") };
+        let file_name = sanitize_path::sanitize_component(&format!("{} {} {}.{}", host, title, now_secs(), format.extension()));
+        let file_path = dir.join(file_name);
+        fs::write(&file_path, contents).map_err(|e| format!("Failed to write news export: {}", e))?;
+        Ok(file_path.to_string_lossy().to_string())
+    }
+
+    pub async fn clear_news_cache(&self, server_id: Option<&str>) -> Result<(), String> {
+        self.news_cache.write().await.clear(server_id)
+    }
+
+    pub async fn get_news_cache_stats(&self, server_id: Option<&str>) -> Vec<NewsCacheStats> {
+        self.news_cache.read().await.stats(server_id)
     }
 
-    pub async fn get_news_article_data(&self, server_id: &str, article_id: u32, path: Vec<String>) -> Result<String, String> {
+    pub async fn post_news_article(&self, server_id: &str, title: String, text: String, path: Vec<String>, parent_id: u32, flavor: ArticleFlavor) -> Result<Option<u32>, String> {
         let clients = self.clients.read().await;
 
         if let Some(client) = clients.get(server_id) {
-            client.get_news_article_data(article_id, path).await
+            client.post_news_article(title, text, path, parent_id, flavor).await
         } else {
-            Err("Server not connected".to_string())
+            Err(tr(MessageId::ServerNotConnected))
         }
     }
 
-    pub async fn post_news_article(&self, server_id: &str, title: String, text: String, path: Vec<String>, parent_id: u32) -> Result<(), String> {
+    pub async fn reply_to_news_article(&self, server_id: &str, path: Vec<String>, parent_id: u32, title: Option<String>, text: String, flavor: ArticleFlavor) -> Result<Option<u32>, String> {
         let clients = self.clients.read().await;
 
         if let Some(client) = clients.get(server_id) {
-            client.post_news_article(title, text, path, parent_id).await
+            client.reply_to_news_article(path, parent_id, title, text, flavor).await
         } else {
-            Err("Server not connected".to_string())
+            Err(tr(MessageId::ServerNotConnected))
         }
     }
 
+    /// Start an upload in the background and return its operation id
+    /// Check whether an upload's name is already taken in `path`
+    pub async fn check_upload_conflict(&self, server_id: &str, path: Vec<String>, file_name: &str, file_size: u64) -> Result<Option<UploadConflict>, String> {
+        let clients = self.clients.read().await;
+        let client = clients.get(server_id).ok_or_else(|| tr(MessageId::ServerNotConnected))?;
+        Ok(client.check_upload_conflict(&path, file_name, file_size).await)
+    }
+
+    /// Start an upload. `conflict` says how to get past an existing file
+    /// with the same name, as reported by `check_upload_conflict`.
     pub async fn upload_file(
         &self,
         server_id: &str,
         path: Vec<String>,
-        file_name: String,
-        file_data: Vec<u8>,
+        mut upload: UploadFile,
+        conflict: Option<ConflictResolution>,
+        operation_id: Option<String>,
+    ) -> Result<String, String> {
+        let resume = {
+            let clients = self.clients.read().await;
+            let client = clients.get(server_id).ok_or_else(|| tr(MessageId::ServerNotConnected))?;
+            match conflict {
+                None => false,
+                Some(ConflictResolution::Rename) => {
+                    upload.file_name = client
+                        .free_upload_name(&path, &upload.file_name)
+                        .await
+                        .ok_or_else(|| format!("No free name for {} in this folder", upload.file_name))?;
+                    false
+                }
+                Some(ConflictResolution::Overwrite) => {
+                    client.delete_file(path.clone(), upload.file_name.clone()).await?;
+                    false
+                }
+                Some(ConflictResolution::Resume) => true,
+            }
+        };
+
+        let (operation_id, token) = self.begin_operation(operation_id, "upload", Some(server_id)).await;
+        self.spawn_operation(operation_id.clone(), token, OperationJob::Upload {
+            server_id: server_id.to_string(),
+            path,
+            upload,
+            resume,
+        });
+        Ok(operation_id)
+    }
+
+    /// Send an upload. With `resume`, the server is asked to continue a partial
+    /// copy left by an earlier attempt.
+    async fn run_upload(
+        &self,
+        server_id: &str,
+        path: Vec<String>,
+        upload: UploadFile,
+        resume: bool,
+        cancel: &CancellationToken,
+        operation_id: &str,
     ) -> Result<(), String> {
-        let clients = self.clients.read().await;
+        let client = self.shared_client(server_id).await?;
+        let app_handle = self.app_handle.clone();
+        let server_id_clone = server_id.to_string();
+        let file_name_clone = upload.file_name.clone();
+        let operation_id = operation_id.to_string();
+        let total_bytes = upload.data.len() as u64;
+        let mut record = TransferRecord::new(server_id, TransferDirection::Upload, &path, &upload.file_name);
+        record.sha256 = Some(partial_downloads::sha256_bytes(&upload.data));
+        let mut meter = TransferMeter::new();
+        let started = Instant::now();
+
+        let result = client.upload_file(
+            path,
+            upload,
+            resume,
+            cancel,
+            move |bytes_sent, total_bytes| {
+                let progress = (bytes_sent as f64 / total_bytes as f64 * 100.0) as u32;
+                let speed = meter.update(bytes_sent, total_bytes);
+                let payload = serde_json::json!({
+                    "fileName": file_name_clone,
+                    "bytesSent": bytes_sent,
+                    "totalBytes": total_bytes,
+                    "progress": progress,
+                    "bytesPerSec": speed.bytes_per_sec,
+                    "etaSecs": speed.eta_secs,
+                });
+                server_windows::emit(&app_handle, &server_id_clone, "upload-progress", payload);
+                emit_operation_progress(&app_handle, &operation_id, bytes_sent, total_bytes);
+            }
+        ).await;
+
+        let sent = if result.is_ok() { total_bytes } else { 0 };
+        self.metrics.record_transfer(TransferDirection::Upload, sent, result.is_ok());
+        self.session_stats.record(server_id, TransferDirection::Upload, 1, sent, started.elapsed(), result.is_ok());
+        record.size = sent;
+        self.record_transfer_history(record, started.elapsed(), result.as_ref().err(), cancel).await;
+        result
+    }
 
-        if let Some(client) = clients.get(server_id) {
-            let app_handle = self.app_handle.clone();
-            let server_id_clone = server_id.to_string();
-            let file_name_clone = file_name.clone();
-            let total_bytes = file_data.len() as u32;
+    pub fn queue_download(&self, server_id: &str, path: Vec<String>, file_name: String, file_size: u64, options: DownloadOptions) -> String {
+        self.enqueue_transfer(server_id, TransferRequest::Download {
+            path,
+            file_name,
+            file_size,
+            download_folder: options.download_folder,
+            fork_mode: options.fork_mode,
+        })
+    }
+
+    pub fn queue_upload(&self, server_id: &str, path: Vec<String>, upload: UploadFile) -> String {
+        self.enqueue_transfer(server_id, TransferRequest::Upload { path, upload })
+    }
+
+    /// Check dropped paths and hold the resulting manifest until the user
+    /// confirms it with `commit_staged_uploads` or drops it
+    pub async fn stage_uploads(&self, paths: Vec<String>) -> StagingManifest {
+        let manifest = StagingManifest::stage(format!("staging-{}", self.next_transfer_id()), &paths);
+        self.upload_staging.write().await.insert(manifest.clone());
+        manifest
+    }
+
+    /// Queue every valid upload of a staged set into `path` on the server.
+    /// Nothing is queued if any of them can no longer be read.
+    pub async fn commit_staged_uploads(&self, staging_id: &str, server_id: &str, path: Vec<String>) -> Result<Vec<String>, String> {
+        let manifest = self
+            .upload_staging
+            .write()
+            .await
+            .take(staging_id)
+            .ok_or_else(|| format!("No staged uploads with id {}", staging_id))?;
+
+        let uploads = manifest
+            .uploads
+            .iter()
+            .filter(|upload| upload.error.is_none())
+            .map(|upload| upload.load())
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(uploads
+            .into_iter()
+            .map(|upload| self.queue_upload(server_id, path.clone(), upload))
+            .collect())
+    }
+
+    pub async fn discard_staged_uploads(&self, staging_id: &str) {
+        self.upload_staging.write().await.take(staging_id);
+    }
+
+    fn enqueue_transfer(&self, server_id: &str, request: TransferRequest) -> String {
+        let id = self.next_transfer_id();
+        let item = QueuedTransfer::new(id.clone(), server_id.to_string(), request);
+        self.transfers.lock().unwrap().queue(server_id).enqueue(item);
+        self.pump_transfers(server_id);
+        id
+    }
 
-            client.upload_file(
-                path,
-                file_name,
-                file_data,
-                move |bytes_sent, total_bytes| {
-                    let progress = (bytes_sent as f64 / total_bytes as f64 * 100.0) as u32;
-                    let payload = serde_json::json!({
-                        "fileName": file_name_clone,
-                        "bytesSent": bytes_sent,
-                        "totalBytes": total_bytes,
-                        "progress": progress,
+    pub fn list_transfers(&self, server_id: Option<&str>) -> Vec<QueuedTransfer> {
+        self.transfers.lock().unwrap().list(server_id)
+    }
+
+    /// Pause a transfer. A running download keeps its partial data and continues
+    /// from there when resumed; a running upload continues from what the server
+    /// already received, on servers that keep partial uploads.
+    pub async fn pause_transfer(&self, transfer_id: &str) -> Result<(), String> {
+        let server_id = {
+            let mut manager = self.transfers.lock().unwrap();
+            let server_id = manager
+                .server_for(transfer_id)
+                .ok_or_else(|| format!("No queued transfer with id {}", transfer_id))?;
+            if manager.queue(&server_id).pause(transfer_id)? {
+                manager.abort(transfer_id);
+            }
+            server_id
+        };
+        self.finish_operation(transfer_id).await;
+        self.pump_transfers(&server_id);
+        Ok(())
+    }
+
+    pub fn resume_transfer(&self, transfer_id: &str) -> Result<(), String> {
+        let server_id = {
+            let mut manager = self.transfers.lock().unwrap();
+            let server_id = manager
+                .server_for(transfer_id)
+                .ok_or_else(|| format!("No queued transfer with id {}", transfer_id))?;
+            manager.queue(&server_id).resume(transfer_id)?;
+            server_id
+        };
+        self.pump_transfers(&server_id);
+        Ok(())
+    }
+
+    /// Cancel a transfer and throw away any partial download data
+    pub async fn cancel_transfer(&self, transfer_id: &str) -> Result<(), String> {
+        let server_id = {
+            let mut manager = self.transfers.lock().unwrap();
+            let server_id = manager
+                .server_for(transfer_id)
+                .ok_or_else(|| format!("No queued transfer with id {}", transfer_id))?;
+            if manager.queue(&server_id).cancel(transfer_id)? {
+                manager.abort(transfer_id);
+            }
+            server_id
+        };
+        self.finish_operation(transfer_id).await;
+        self.discard_partial_download(transfer_id).await?;
+        self.pump_transfers(&server_id);
+        Ok(())
+    }
+
+    pub fn reorder_transfers(&self, server_id: &str, transfer_ids: Vec<String>) -> Result<(), String> {
+        self.transfers.lock().unwrap().queue(server_id).reorder(&transfer_ids)?;
+        self.emit_transfer_queue(server_id);
+        Ok(())
+    }
+
+    pub fn get_download_slots(&self, server_id: &str) -> DownloadSlots {
+        self.transfers.lock().unwrap().queue(server_id).download_slots()
+    }
+
+    pub fn set_max_concurrent_transfers(&self, server_id: &str, max_concurrent: usize) {
+        self.transfers.lock().unwrap().queue(server_id).set_max_concurrent(max_concurrent);
+        self.pump_transfers(server_id);
+    }
+
+    /// Cap transfer connections open to any one host, across all servers
+    pub fn set_max_transfers_per_host(&self, limit: usize) {
+        self.transfers.lock().unwrap().set_max_per_host(limit);
+    }
+
+    pub fn clear_finished_transfers(&self, server_id: &str) {
+        self.transfers.lock().unwrap().queue(server_id).clear_finished();
+        self.emit_transfer_queue(server_id);
+    }
+
+    /// Start queued transfers for a server while it has free slots
+    fn pump_transfers(&self, server_id: &str) {
+        // Nothing new starts once the app is quitting
+        if !self.shutdown.is_started() {
+            let mut manager = self.transfers.lock().unwrap();
+            for item in manager.queue(server_id).start_next() {
+                let transfer_id = item.id.clone();
+                let task = tokio::spawn(Self::run_queued_transfer(self.app_handle.clone(), item));
+                manager.set_running(&transfer_id, task.abort_handle());
+            }
+        }
+        self.emit_transfer_queue(server_id);
+    }
+
+    async fn run_queued_transfer(app_handle: AppHandle, item: QueuedTransfer) {
+        let state = app_handle.state::<AppState>();
+        let QueuedTransfer { id, server_id, request, started, file_name, .. } = item;
+        let is_download = matches!(request, TransferRequest::Download { .. });
+
+        let result = match request {
+            TransferRequest::Download { path, file_name, file_size, download_folder, fork_mode } => {
+                // A transfer that was paused mid-way has a manifest entry to pick up from
+                let has_partial = state.partial_downloads.read().await.get(&id).is_some();
+                let start = if has_partial {
+                    state.resume_point(&id).await
+                } else {
+                    state
+                        .new_partial_download(id.clone(), &server_id, path, file_name, file_size, DownloadOptions { download_folder, fork_mode })
+                        .await
+                        .map(|entry| (entry, 0))
+                };
+                match start {
+                    Ok((entry, offset)) => {
+                        let (operation_id, token) = state.begin_operation(Some(id.clone()), "download", Some(&server_id)).await;
+                        let result = state.run_download(entry, offset, &token, &operation_id).await.map(|_| ());
+                        state.finish_operation(&operation_id).await;
+                        result
+                    }
+                    Err(e) => Err(e),
+                }
+            }
+            TransferRequest::Upload { path, upload } => {
+                // An upload that ran before may have left part of the file on the server
+                let (operation_id, token) = state.begin_operation(Some(id.clone()), "upload", Some(&server_id)).await;
+                let result = state.run_upload(&server_id, path, upload, started, &token, &operation_id).await;
+                state.finish_operation(&operation_id).await;
+                result
+            }
+        };
+
+        // A download refused over the server's cap waits for one of ours to
+        // finish, unless none of ours is what fills the cap
+        let mut requeue = false;
+        if let Err(e) = &result {
+            if is_download {
+                let others = state.transfers.lock().unwrap().queue(&server_id).running_downloads().saturating_sub(1);
+                if let Some(limit) = download_limit_from_error(e, others) {
+                    requeue = others >= limit;
+                    println!("{} allows {} downloads at once", server_id, limit);
+                    state.transfers.lock().unwrap().queue(&server_id).set_download_limit(limit);
+                    if let Some(client) = state.clients.read().await.get(&server_id) {
+                        client.set_download_limit(limit).await;
+                    }
+                }
+            }
+            if !requeue {
+                println!("Queued transfer {} failed: {}", id, e);
+                // Paused or cancelled transfers were stopped on purpose
+                let stopped = !state
+                    .transfers
+                    .lock()
+                    .unwrap()
+                    .queue(&server_id)
+                    .get(&id)
+                    .is_some_and(|item| item.state == TransferState::Running);
+                if !stopped {
+                    let kind = if is_download { "download" } else { "upload" };
+                    state.failures.record(FailureCategory::Transfer, kind, Some(&server_id), true, e);
+                }
+            }
+        }
+        if !requeue {
+            let prefs = state.notification_prefs.read().await.get(&server_id);
+            match &result {
+                Ok(()) if prefs.transfer_complete => {
+                    notifications::show_os_notification(&app_handle, "Transfer complete", &file_name);
+                }
+                Err(e) if prefs.transfer_failed => {
+                    notifications::show_os_notification(&app_handle, &format!("{} failed", file_name), e);
+                }
+                _ => {}
+            }
+        }
+
+        {
+            let mut manager = state.transfers.lock().unwrap();
+            manager.clear_running(&id);
+            if requeue {
+                manager.queue(&server_id).requeue(&id);
+            } else {
+                manager.queue(&server_id).finish(&id, result);
+            }
+        }
+        state.pump_transfers(&server_id);
+    }
+
+    pub async fn fetch_tracker_servers(&self, address: &str, port: Option<u16>, operation_id: Option<String>) -> Result<Vec<crate::protocol::types::TrackerServer>, String> {
+        let (operation_id, token) = self.begin_operation(operation_id, "trackerFetch", None).await;
+        let mut result = TrackerClient::fetch_servers(address, port, &token).await;
+        self.finish_operation(&operation_id).await;
+        if let Ok(servers) = &mut result {
+            self.user_limits.read().await.annotate(servers);
+        }
+        match &result {
+            Ok(servers) => {
+                self.tracker_cache.write().await.record(address, port, servers.clone());
+            }
+            Err(e) => {
+                self.metrics.record_error("tracker");
+                self.diagnostic_log.record(&format!("Tracker {} failed: {}", address, e));
+                if !token.is_cancelled() {
+                    self.failures.record(FailureCategory::Tracker, "tracker fetch", Some(address), true, e);
+                }
+            }
+        }
+        result
+    }
+
+    /// The last listing fetched from a tracker this session, if any
+    pub async fn get_cached_tracker_servers(&self, address: &str, port: Option<u16>) -> Option<TrackerListing> {
+        let mut listing = self.tracker_cache.read().await.get(address, port)?;
+        self.user_limits.read().await.annotate(&mut listing.servers);
+        Some(listing)
+    }
+
+    /// Search every tracker listing fetched this session
+    pub async fn search_tracker_servers(&self, query: &str, min_users: u16, sort: TrackerSort) -> Vec<TrackerSearchResult> {
+        self.tracker_cache.read().await.search(query, min_users, sort)
+    }
+
+    /// Fetch a tracker again and report what changed since the last listing
+    pub async fn refresh_tracker(&self, address: &str, port: Option<u16>, operation_id: Option<String>) -> Result<TrackerDiff, String> {
+        let (operation_id, token) = self.begin_operation(operation_id, "trackerFetch", None).await;
+        let result = TrackerClient::fetch_servers(address, port, &token).await;
+        self.finish_operation(&operation_id).await;
+        match result {
+            Ok(mut servers) => {
+                self.user_limits.read().await.annotate(&mut servers);
+                Ok(self.tracker_cache.write().await.record(address, port, servers))
+            }
+            Err(e) => {
+                self.metrics.record_error("tracker");
+                self.diagnostic_log.record(&format!("Tracker {} failed: {}", address, e));
+                if !token.is_cancelled() {
+                    self.failures.record(FailureCategory::Tracker, "tracker fetch", Some(address), true, &e);
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Query every tracker bookmark at once and merge their listings
+    pub async fn fetch_all_trackers(&self, operation_id: Option<String>) -> TrackerBrowse {
+        use crate::protocol::types::BookmarkType;
+
+        let trackers: Vec<Bookmark> = self
+            .bookmarks
+            .read()
+            .await
+            .iter()
+            .filter(|b| matches!(b.bookmark_type, Some(BookmarkType::Tracker)))
+            .cloned()
+            .collect();
+
+        let (operation_id, token) = self.begin_operation(operation_id, "trackerFetch", None).await;
+        let fetches = tracker_browse::fetch_all(trackers, &token).await;
+        self.finish_operation(&operation_id).await;
+
+        let mut browse = TrackerBrowse::default();
+        for fetch in fetches {
+            match fetch.result {
+                Ok(mut servers) => {
+                    self.user_limits.read().await.annotate(&mut servers);
+                    self.tracker_cache.write().await.record(&fetch.tracker.address, fetch.port, servers.clone());
+                    browse.add(&fetch.tracker.id, servers);
+                }
+                Err(e) => {
+                    self.metrics.record_error("tracker");
+                    self.diagnostic_log.record(&format!("Tracker {} failed: {}", fetch.tracker.address, e));
+                    if !token.is_cancelled() {
+                        self.failures.record(FailureCategory::Tracker, "tracker fetch", Some(&fetch.tracker.address), true, &e);
+                    }
+                    browse.failures.push(TrackerFailure {
+                        tracker_id: fetch.tracker.id,
+                        error: e,
                     });
-                    let _ = app_handle.emit(&format!("upload-progress-{}", server_id_clone), payload);
                 }
-            ).await?;
+            }
+        }
+        browse
+    }
+
+    /// List our server on `trackers`, replacing any registration already
+    /// running. Call again with the new details when the user count changes.
+    pub async fn start_tracker_registration(&self, registration: TrackerRegistration, trackers: Vec<TrackerAddress>) -> Result<(), String> {
+        self.tracker_registrar.write().await.start(registration, trackers)
+    }
+
+    pub async fn stop_tracker_registration(&self) {
+        self.tracker_registrar.write().await.stop();
+    }
+
+    async fn begin_operation(&self, operation_id: Option<String>, kind: &str, server_id: Option<&str>) -> (String, CancellationToken) {
+        self.operations.write().await.begin(operation_id, kind, server_id)
+    }
+
+    async fn finish_operation(&self, operation_id: &str) {
+        self.operations.write().await.finish(operation_id);
+    }
+
+    fn spawn_operation(&self, operation_id: String, token: CancellationToken, job: OperationJob) {
+        tokio::spawn(Self::run_operation(self.app_handle.clone(), operation_id, token, job));
+    }
+
+    /// Run a background operation to completion and report the outcome
+    async fn run_operation(app_handle: AppHandle, operation_id: String, token: CancellationToken, job: OperationJob) {
+        let state = app_handle.state::<AppState>();
+
+        let (kind, server_id) = match &job {
+            OperationJob::Download { entry, .. } => ("download", entry.server_id.clone()),
+            OperationJob::FolderDownload { server_id, .. } => ("folder download", server_id.clone()),
+            OperationJob::Upload { server_id, .. } => ("upload", server_id.clone()),
+        };
+        let result = match job {
+            OperationJob::Download { entry, offset } => state
+                .run_download(entry, offset, &token, &operation_id)
+                .await
+                .map(|(result, sha256)| (result, Some(sha256))),
+            OperationJob::FolderDownload { server_id, path, folder_name, destination } => state
+                .run_folder_download(&server_id, path, folder_name, destination, &token, &operation_id)
+                .await
+                .map(|result| (result, None)),
+            OperationJob::Upload { server_id, path, upload, resume } => state
+                .run_upload(&server_id, path, upload, resume, &token, &operation_id)
+                .await
+                .map(|_| ("Upload complete".to_string(), None)),
+        };
+        state.finish_operation(&operation_id).await;
+
+        if let Err(e) = &result {
+            println!("Operation {} failed: {}", operation_id, e);
+            state.diagnostic_log.record(&format!("Operation {} failed: {}", operation_id, e));
+            // Paused and partial transfers pick up where they stopped
+            if !token.is_cancelled() {
+                state.failures.record(FailureCategory::Transfer, kind, Some(&server_id), true, e);
+            }
+        }
 
+        let payload = match result {
+            Ok((result, sha256)) => serde_json::json!({ "operationId": operation_id, "result": result, "sha256": sha256 }),
+            Err(error) => serde_json::json!({ "operationId": operation_id, "error": error }),
+        };
+        let _ = app_handle.emit(&format!("operation-finished-{}", operation_id), server_windows::versioned(payload));
+    }
+
+    pub fn set_metrics_enabled(&self, enabled: bool) {
+        self.metrics.set_enabled(enabled);
+    }
+
+    /// Send a hand-built transaction, for experimenting with server features
+    /// this client doesn't know about
+    pub async fn send_raw_transaction(&self, server_id: &str, transaction_type: u16, fields: Vec<RawField>) -> Result<RawReply, String> {
+        if !self.raw_transactions {
+            return Err("Raw transactions are disabled; start the app with HOTLINE_RAW_TRANSACTIONS=1 to allow them".to_string());
+        }
+        let client = self.shared_client(server_id).await?;
+        self.diagnostic_log.record(&format!("Raw transaction {} with {} fields to {}", transaction_type, fields.len(), server_id));
+        client.send_raw_transaction(transaction_type, &fields).await
+    }
+
+    /// Transfer totals for this run of the app, overall and per server
+    pub fn get_session_summary(&self) -> SessionSummary {
+        self.session_stats.summary()
+    }
+
+    pub async fn dump_metrics(&self) -> MetricsSnapshot {
+        let active_connections = self.clients.read().await.len();
+        self.metrics.snapshot(active_connections)
+    }
+
+    pub fn get_recent_failures(&self, server_id: Option<&str>, limit: Option<usize>) -> Vec<Failure> {
+        self.failures.recent(server_id, limit)
+    }
+
+    /// Write a redacted diagnostics zip and return its path. `settings` is the
+    /// frontend's preferences blob; PM content is reduced to per-conversation counts.
+    pub async fn generate_diagnostics(&self, settings: Option<serde_json::Value>, destination: Option<String>) -> Result<String, String> {
+        let package = self.app_handle.package_info();
+        let system = serde_json::json!({
+            "appName": package.name,
+            "appVersion": package.version.to_string(),
+            "os": std::env::consts::OS,
+            "osFamily": std::env::consts::FAMILY,
+            "arch": std::env::consts::ARCH,
+            "generatedAt": now_secs(),
+        });
+
+        let connected: Vec<String> = self.clients.read().await.keys().cloned().collect();
+        let connections = serde_json::json!({
+            "connectedServers": connected,
+            "metrics": self.dump_metrics().await,
+            "operations": self.list_operations().await,
+            "transfers": self.list_transfers(None),
+        });
+
+        let mut bookmarks = serde_json::to_value(&*self.bookmarks.read().await)
+            .map_err(|e| format!("Failed to serialize bookmarks: {}", e))?;
+        diagnostics::redact_json(&mut bookmarks);
+        let mut settings = settings.unwrap_or(serde_json::Value::Null);
+        diagnostics::redact_json(&mut settings);
+
+        let conversations: Vec<serde_json::Value> = self
+            .conversations
+            .read()
+            .await
+            .list(None)
+            .into_iter()
+            .map(|c| serde_json::json!({ "serverId": c.server_id, "unreadCount": c.unread_count }))
+            .collect();
+
+        let mut zip = ZipBuilder::default();
+        for (name, value) in [
+            ("system.json", &system),
+            ("connections.json", &connections),
+            ("bookmarks.json", &bookmarks),
+            ("settings.json", &settings),
+        ] {
+            let json = serde_json::to_vec_pretty(value)
+                .map_err(|e| format!("Failed to serialize {}: {}", name, e))?;
+            zip.add(name, &json);
+        }
+        let conversations = serde_json::to_vec_pretty(&conversations)
+            .map_err(|e| format!("Failed to serialize conversations: {}", e))?;
+        zip.add("conversations.json", &conversations);
+        let failures = serde_json::to_vec_pretty(&self.failures.recent(None, None))
+            .map_err(|e| format!("Failed to serialize failures: {}", e))?;
+        zip.add("failures.json", &failures);
+        zip.add("log.txt", self.diagnostic_log.lines().join("\n").as_bytes());
+
+        let dir = self.downloads_dir(destination).await?;
+        fs::create_dir_all(&dir).map_err(|e| format!("Failed to create directory: {}", e))?;
+        let path = dir.join(format!("hotline-diagnostics-{}.zip", now_secs()));
+        fs::write(&path, zip.finish()).map_err(|e| format!("Failed to write diagnostics: {}", e))?;
+        Ok(path.to_string_lossy().to_string())
+    }
+
+    pub async fn list_operations(&self) -> Vec<OperationSummary> {
+        self.operations.read().await.list()
+    }
+
+    /// Cancel a running operation. Queued transfers are cancelled through the
+    /// transfer queue so they don't get restarted.
+    pub async fn cancel_operation(&self, operation_id: &str) -> Result<(), String> {
+        let queued = self.transfers.lock().unwrap().server_for(operation_id).is_some();
+        if queued {
+            return self.cancel_transfer(operation_id).await;
+        }
+
+        if self.operations.write().await.cancel(operation_id) {
             Ok(())
         } else {
-            Err("Server not connected".to_string())
+            Err(format!("No running operation with id {}", operation_id))
         }
     }
+
+    fn emit_transfer_queue(&self, server_id: &str) {
+        let transfers = self.list_transfers(Some(server_id));
+        server_windows::emit(&self.app_handle, server_id, "transfer-queue-updated", transfers);
+        server_windows::emit(&self.app_handle, server_id, "download-slots-updated", self.get_download_slots(server_id));
+    }
+}
+
+/// Answer a user from the event loop without holding it up
+fn spawn_instant_reply(
+    clients: &Arc<RwLock<HashMap<String, Arc<HotlineClient>>>>,
+    server_id: &str,
+    user_id: u16,
+    kind: InstantMessageKind,
+    message: String,
+) {
+    let clients = Arc::clone(clients);
+    let server_id = server_id.to_string();
+    tokio::spawn(async move {
+        if let Some(client) = clients.read().await.get(&server_id) {
+            if let Err(e) = client.send_instant_message(user_id, kind, &message, None).await {
+                eprintln!("Failed to answer user {}: {}", user_id, e);
+            }
+        }
+    });
 }
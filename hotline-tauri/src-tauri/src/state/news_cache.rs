@@ -0,0 +1,298 @@
+// News and message board cache
+//
+// Article bodies and message boards are kept on disk per server so items that
+// were read before open instantly and stay readable while disconnected. The
+// cache is bounded by total content size; when it grows past the limit the
+// least recently used entries are evicted first.
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use super::conversations::now_secs;
+use super::offline::CachedView;
+use super::json_store;
+
+pub const DEFAULT_NEWS_CACHE_MAX_BYTES: usize = 4 * 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum CachedContent {
+    Article {
+        path: Vec<String>,
+        #[serde(alias = "article_id")]
+        article_id: u32,
+        body: String,
+    },
+    Board { posts: Vec<String> },
+}
+
+impl CachedContent {
+    fn size(&self) -> usize {
+        match self {
+            CachedContent::Article { body, .. } => body.len(),
+            CachedContent::Board { posts } => posts.iter().map(String::len).sum(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CacheEntry {
+    server_id: String,
+    key: String,
+    content: CachedContent,
+    cached_at: u64,
+    /// Monotonic use counter; the lowest value is evicted first
+    last_used: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewsCacheStats {
+    pub server_id: String,
+    pub articles: usize,
+    pub boards: usize,
+    pub bytes: usize,
+    pub oldest: Option<u64>,
+    pub newest: Option<u64>,
+}
+
+pub struct NewsCache {
+    path: PathBuf,
+    max_bytes: usize,
+    entries: Vec<CacheEntry>,
+    use_counter: u64,
+}
+
+pub const BOARD_KEY: &str = "board";
+
+pub fn article_key(path: &[String], article_id: u32) -> String {
+    format!("article:{}:{}", path.join("/"), article_id)
+}
+
+impl NewsCache {
+    pub fn load(path: PathBuf) -> Self {
+        let entries: Vec<CacheEntry> = json_store::load_json_or_default(&path, "news cache");
+        let use_counter = entries.iter().map(|entry| entry.last_used).max().unwrap_or(0);
+
+        Self {
+            path,
+            max_bytes: DEFAULT_NEWS_CACHE_MAX_BYTES,
+            entries,
+            use_counter,
+        }
+    }
+
+    fn save(&self) -> Result<(), String> {
+        json_store::save_json(&self.path, &self.entries, "news cache")
+    }
+
+    fn next_use(&mut self) -> u64 {
+        self.use_counter += 1;
+        self.use_counter
+    }
+
+    /// Look up a cached item, marking it as recently used
+    pub fn get(&mut self, server_id: &str, key: &str) -> Option<CachedContent> {
+        let used = self.next_use();
+        let entry = self
+            .entries
+            .iter_mut()
+            .find(|entry| entry.server_id == server_id && entry.key == key)?;
+        entry.last_used = used;
+        Some(entry.content.clone())
+    }
+
+    pub fn put(&mut self, server_id: &str, key: &str, content: CachedContent) -> Result<(), String> {
+        let used = self.next_use();
+        self.entries.retain(|entry| !(entry.server_id == server_id && entry.key == key));
+        self.entries.push(CacheEntry {
+            server_id: server_id.to_string(),
+            key: key.to_string(),
+            content,
+            cached_at: now_secs(),
+            last_used: used,
+        });
+        self.evict();
+        self.save()
+    }
+
+    fn total_bytes(&self) -> usize {
+        self.entries.iter().map(|entry| entry.content.size()).sum()
+    }
+
+    fn evict(&mut self) {
+        while self.entries.len() > 1 && self.total_bytes() > self.max_bytes {
+            if let Some(pos) = self
+                .entries
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(pos, _)| pos)
+            {
+                self.entries.remove(pos);
+            }
+        }
+    }
+
+    /// Everything cached for a server, most recently fetched first
+    pub fn list(&self, server_id: &str) -> Vec<CachedView<CachedContent>> {
+        let mut items: Vec<&CacheEntry> = self
+            .entries
+            .iter()
+            .filter(|entry| entry.server_id == server_id)
+            .collect();
+        items.sort_by_key(|entry| std::cmp::Reverse(entry.cached_at));
+        items
+            .into_iter()
+            .map(|entry| CachedView::new(entry.cached_at, entry.content.clone()))
+            .collect()
+    }
+
+    /// Drop cached items for one server, or everything when `server_id` is None
+    pub fn clear(&mut self, server_id: Option<&str>) -> Result<(), String> {
+        match server_id {
+            Some(server_id) => self.entries.retain(|entry| entry.server_id != server_id),
+            None => self.entries.clear(),
+        }
+        self.save()
+    }
+
+    /// Drop cached bodies of articles the server no longer has: one
+    /// article, or with `article_id` None everything at or below `path`
+    pub fn forget_articles(&mut self, server_id: &str, path: &[String], article_id: Option<u32>) -> Result<(), String> {
+        let before = self.entries.len();
+        self.entries.retain(|entry| {
+            let CachedContent::Article { path: article_path, article_id: id, .. } = &entry.content else {
+                return true;
+            };
+            let gone = match article_id {
+                Some(article_id) => article_path.as_slice() == path && *id == article_id,
+                None => article_path.starts_with(path),
+            };
+            entry.server_id != server_id || !gone
+        });
+        if self.entries.len() == before {
+            return Ok(());
+        }
+        self.save()
+    }
+
+    /// Per-server statistics, optionally limited to one server
+    pub fn stats(&self, server_id: Option<&str>) -> Vec<NewsCacheStats> {
+        let mut stats: Vec<NewsCacheStats> = Vec::new();
+        for entry in self
+            .entries
+            .iter()
+            .filter(|entry| server_id.is_none_or(|id| entry.server_id == id))
+        {
+            let pos = match stats.iter().position(|s| s.server_id == entry.server_id) {
+                Some(pos) => pos,
+                None => {
+                    stats.push(NewsCacheStats {
+                        server_id: entry.server_id.clone(),
+                        articles: 0,
+                        boards: 0,
+                        bytes: 0,
+                        oldest: None,
+                        newest: None,
+                    });
+                    stats.len() - 1
+                }
+            };
+            let s = &mut stats[pos];
+            match entry.content {
+                CachedContent::Article { .. } => s.articles += 1,
+                CachedContent::Board { .. } => s.boards += 1,
+            }
+            s.bytes += entry.content.size();
+            s.oldest = Some(s.oldest.map_or(entry.cached_at, |t| t.min(entry.cached_at)));
+            s.newest = Some(s.newest.map_or(entry.cached_at, |t| t.max(entry.cached_at)));
+        }
+        stats.sort_by(|a, b| a.server_id.cmp(&b.server_id));
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn cache(max_bytes: usize) -> NewsCache {
+        let path = std::env::temp_dir().join(format!("news-cache-test-{}-{}.json", std::process::id(), max_bytes));
+        let mut cache = NewsCache::load(path);
+        cache.max_bytes = max_bytes;
+        cache
+    }
+
+    fn article(body: &str) -> CachedContent {
+        CachedContent::Article {
+            path: vec!["General".to_string()],
+            article_id: 1,
+            body: body.to_string(),
+        }
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let mut cache = cache(10);
+        cache.put("srv", "a", article("aaaa")).unwrap();
+        cache.put("srv", "b", article("bbbb")).unwrap();
+        // Touch "a" so "b" becomes the eviction candidate
+        assert!(cache.get("srv", "a").is_some());
+        cache.put("srv", "c", article("cccc")).unwrap();
+
+        assert!(cache.get("srv", "a").is_some());
+        assert!(cache.get("srv", "b").is_none());
+        assert!(cache.get("srv", "c").is_some());
+        let _ = fs::remove_file(&cache.path);
+    }
+
+    #[test]
+    fn deleted_articles_are_forgotten() {
+        let mut cache = cache(2048);
+        let in_path = |path: &[&str], article_id: u32| CachedContent::Article {
+            path: path.iter().map(|part| part.to_string()).collect(),
+            article_id,
+            body: "body".to_string(),
+        };
+        cache.put("srv", "1", in_path(&["Bundle", "General"], 1)).unwrap();
+        cache.put("srv", "2", in_path(&["Bundle", "General"], 2)).unwrap();
+        cache.put("srv", "3", in_path(&["Bundle", "Other"], 3)).unwrap();
+        cache.put("srv", "4", in_path(&["Elsewhere"], 4)).unwrap();
+        cache.put("other", "1", in_path(&["Bundle", "General"], 1)).unwrap();
+
+        let general = vec!["Bundle".to_string(), "General".to_string()];
+        cache.forget_articles("srv", &general, Some(1)).unwrap();
+        assert!(cache.get("srv", "1").is_none());
+        assert!(cache.get("srv", "2").is_some());
+
+        // Deleting the bundle takes everything inside it
+        cache.forget_articles("srv", &general[..1], None).unwrap();
+        assert_eq!(cache.list("srv").len(), 1);
+        assert!(cache.get("other", "1").is_some());
+        let _ = fs::remove_file(&cache.path);
+    }
+
+    #[test]
+    fn stats_and_clear_are_per_server() {
+        let mut cache = cache(1024);
+        cache.put("one", "a", article("hello")).unwrap();
+        cache
+            .put("one", BOARD_KEY, CachedContent::Board { posts: vec!["hi".to_string()] })
+            .unwrap();
+        cache.put("two", "a", article("x")).unwrap();
+
+        let stats = cache.stats(None);
+        assert_eq!(stats.len(), 2);
+        assert_eq!((stats[0].articles, stats[0].boards, stats[0].bytes), (1, 1, 7));
+
+        assert_eq!(cache.list("one").len(), 2);
+
+        cache.clear(Some("one")).unwrap();
+        assert!(cache.stats(Some("one")).is_empty());
+        assert_eq!(cache.stats(None).len(), 1);
+        let _ = fs::remove_file(&cache.path);
+    }
+}
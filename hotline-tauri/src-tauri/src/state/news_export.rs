@@ -0,0 +1,181 @@
+// Exporting news threads
+//
+// Archives a news category's threads as Markdown or mbox. Markdown nests
+// replies under their parent with deeper headings; mbox links each reply to
+// its parent with In-Reply-To so mail clients thread them again. Titles,
+// posters and dates are kept; dates are written in UTC.
+
+use super::news_threads::{walk, NewsThread};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum NewsExportFormat {
+    Markdown,
+    Mbox,
+}
+
+impl NewsExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            NewsExportFormat::Markdown => "md",
+            NewsExportFormat::Mbox => "mbox",
+        }
+    }
+}
+
+const WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+/// A UTC date and time broken into its parts
+struct UtcTime {
+    year: i64,
+    month: usize,
+    day: i64,
+    weekday: usize,
+    hour: i64,
+    minute: i64,
+    second: i64,
+}
+
+impl UtcTime {
+    fn from_unix(unix: i64) -> Self {
+        let days = unix.div_euclid(86_400);
+        let seconds = unix.rem_euclid(86_400);
+
+        // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let day_of_era = z.rem_euclid(146_097);
+        let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+        let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+        Self {
+            year,
+            month: month as usize,
+            day,
+            weekday: days.rem_euclid(7) as usize,
+            hour: seconds / 3600,
+            minute: seconds % 3600 / 60,
+            second: seconds % 60,
+        }
+    }
+
+    /// 2026-10-17 14:05 UTC
+    fn readable(&self) -> String {
+        format!("{}-{:02}-{:02} {:02}:{:02} UTC", self.year, self.month, self.day, self.hour, self.minute)
+    }
+
+    /// Sat, 17 Oct 2026 14:05:09 +0000
+    fn rfc2822(&self) -> String {
+        format!(
+            "{}, {} {} {} {:02}:{:02}:{:02} +0000",
+            WEEKDAYS[self.weekday], self.day, MONTHS[self.month - 1], self.year, self.hour, self.minute, self.second
+        )
+    }
+
+    /// Sat Oct 17 14:05:09 2026, for the mbox separator line
+    fn asctime(&self) -> String {
+        format!(
+            "{} {} {:2} {:02}:{:02}:{:02} {}",
+            WEEKDAYS[self.weekday], MONTHS[self.month - 1], self.day, self.hour, self.minute, self.second, self.year
+        )
+    }
+}
+
+/// `bodies` holds each article's text by id
+pub fn to_markdown(category: &[String], threads: &[NewsThread], bodies: &HashMap<u32, String>) -> String {
+    let mut out = format!("# {}\n", if category.is_empty() { "News".to_string() } else { category.join(" / ") });
+    for thread in walk(threads) {
+        let article = &thread.article;
+        // Markdown stops at six levels; deeper replies share the last one
+        let heading = "#".repeat((thread.depth + 2).min(6));
+        out.push_str(&format!("\n{} {}\n\n", heading, article.title));
+        let date = article.date.map(|date| format!(" · {}", UtcTime::from_unix(date).readable())).unwrap_or_default();
+        out.push_str(&format!("*{}{}*\n\n", article.poster, date));
+        out.push_str(bodies.get(&article.id).map(String::as_str).unwrap_or_default().trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+pub fn to_mbox(category: &[String], host: &str, threads: &[NewsThread], bodies: &HashMap<u32, String>) -> String {
+    let message_id = |id: u32| format!("<{}.{}@{}>", id, category.join(".").replace([' ', '<', '>', '@'], "_"), host);
+    let mut out = String::new();
+    for thread in walk(threads) {
+        let article = &thread.article;
+        let time = UtcTime::from_unix(article.date.unwrap_or(0));
+        let sender = article.poster.replace(|c: char| c.is_whitespace() || c == '@', "_");
+        out.push_str(&format!("From {}@{} {}\n", if sender.is_empty() { "unknown" } else { &sender }, host, time.asctime()));
+        out.push_str(&format!("From: {}\n", article.poster));
+        out.push_str(&format!("Subject: {}\n", article.title));
+        if article.date.is_some() {
+            out.push_str(&format!("Date: {}\n", time.rfc2822()));
+        }
+        out.push_str(&format!("Message-ID: {}\n", message_id(article.id)));
+        if thread.depth > 0 {
+            out.push_str(&format!("In-Reply-To: {}\n", message_id(article.parent_id)));
+        }
+        out.push_str("Content-Type: text/plain; charset=utf-8\n\n");
+        for line in bodies.get(&article.id).map(String::as_str).unwrap_or_default().trim_end().lines() {
+            // mboxrd quoting, so body lines can't be read as the next message
+            if line.trim_start_matches('>').starts_with("From ") {
+                out.push('>');
+            }
+            out.push_str(line);
+            out.push('\n');
+        }
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::types::NewsArticle;
+    use crate::state::news_threads::build;
+
+    fn article(id: u32, parent_id: u32, title: &str) -> NewsArticle {
+        NewsArticle {
+            id,
+            parent_id,
+            flags: 0,
+            title: title.to_string(),
+            poster: "alice".to_string(),
+            date: Some(1_792_245_909),
+            path: vec!["General".to_string()],
+        }
+    }
+
+    #[test]
+    fn replies_follow_their_parents() {
+        let threads = build(vec![
+            article(1, 0, "Welcome"),
+            article(2, 0, "Second"),
+            article(3, 1, "Re: Welcome"),
+        ]);
+        let bodies = HashMap::from([
+            (1, "Hello".to_string()),
+            (2, "Another thread".to_string()),
+            (3, "From the desk of bob".to_string()),
+        ]);
+
+        let markdown = to_markdown(&["General".to_string()], &threads, &bodies);
+        let welcome = markdown.find("## Welcome").unwrap();
+        let reply = markdown.find("### Re: Welcome").unwrap();
+        assert!(welcome < reply && reply < markdown.find("## Second").unwrap());
+        assert!(markdown.contains("*alice · 2026-10-17 14:05 UTC*"));
+
+        let mbox = to_mbox(&["General".to_string()], "example.com", &threads, &bodies);
+        assert!(mbox.starts_with("From alice@example.com Sat Oct 17 14:05:09 2026\n"));
+        assert!(mbox.contains("Date: Sat, 17 Oct 2026 14:05:09 +0000\n"));
+        assert!(mbox.contains("In-Reply-To: <1.General@example.com>\n"));
+        assert!(mbox.contains("\n>From the desk of bob\n"));
+    }
+}
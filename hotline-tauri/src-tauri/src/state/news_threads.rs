@@ -0,0 +1,105 @@
+// News threads
+//
+// Servers list a category's articles flat, each naming its parent. Here they
+// are put back into reply trees so the frontend and the exporter don't have
+// to: threads keep the server's order, replies hang under their parent, and
+// each node knows its depth and how many replies sit below it. A reply whose
+// parent isn't in the list, or that is caught in a parent cycle, starts a
+// thread of its own rather than disappearing.
+
+use crate::protocol::types::NewsArticle;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewsThread {
+    pub article: NewsArticle,
+    /// 0 for the article that starts a thread
+    pub depth: usize,
+    /// Direct replies
+    pub child_count: usize,
+    /// Replies at any depth below this article
+    pub descendant_count: usize,
+    pub replies: Vec<NewsThread>,
+}
+
+impl NewsThread {
+    fn grow(article: NewsArticle, depth: usize, children: &mut HashMap<u32, Vec<NewsArticle>>) -> Self {
+        let replies: Vec<_> = children
+            .remove(&article.id)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|reply| Self::grow(reply, depth + 1, children))
+            .collect();
+        Self {
+            article,
+            depth,
+            child_count: replies.len(),
+            descendant_count: replies.iter().map(|reply| 1 + reply.descendant_count).sum(),
+            replies,
+        }
+    }
+}
+
+pub fn build(articles: Vec<NewsArticle>) -> Vec<NewsThread> {
+    let ids: HashSet<u32> = articles.iter().map(|article| article.id).collect();
+    let mut children: HashMap<u32, Vec<NewsArticle>> = HashMap::new();
+    let mut roots = Vec::new();
+    for article in articles {
+        let parent = article.parent_id;
+        if parent != 0 && parent != article.id && ids.contains(&parent) {
+            children.entry(parent).or_default().push(article);
+        } else {
+            roots.push(article);
+        }
+    }
+
+    let mut threads: Vec<_> = roots.into_iter().map(|article| NewsThread::grow(article, 0, &mut children)).collect();
+    // Whatever is left only hangs off itself
+    while let Some(parent) = children.keys().min().copied() {
+        for article in children.remove(&parent).unwrap_or_default() {
+            threads.push(NewsThread::grow(article, 0, &mut children));
+        }
+    }
+    threads
+}
+
+/// Every article in reading order: each thread, then its replies depth first
+pub fn walk(threads: &[NewsThread]) -> Vec<&NewsThread> {
+    let mut ordered = Vec::new();
+    let mut stack: Vec<_> = threads.iter().rev().collect();
+    while let Some(thread) = stack.pop() {
+        ordered.push(thread);
+        stack.extend(thread.replies.iter().rev());
+    }
+    ordered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn article(id: u32, parent_id: u32) -> NewsArticle {
+        NewsArticle {
+            id,
+            parent_id,
+            flags: 0,
+            title: format!("Article {}", id),
+            poster: "alice".to_string(),
+            date: None,
+            path: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn replies_nest_under_their_parents() {
+        // 5 replies to an article that isn't listed; 6 and 7 reply to each other
+        let threads = build(vec![article(1, 0), article(2, 1), article(3, 2), article(4, 1), article(5, 99), article(6, 7), article(7, 6)]);
+
+        let order: Vec<_> = walk(&threads).iter().map(|thread| (thread.article.id, thread.depth)).collect();
+        assert_eq!(order, vec![(1, 0), (2, 1), (3, 2), (4, 1), (5, 0), (7, 0), (6, 1)]);
+        assert_eq!((threads[0].child_count, threads[0].descendant_count), (2, 3));
+        assert_eq!(threads[0].replies[0].descendant_count, 1);
+    }
+}
@@ -0,0 +1,88 @@
+// Desktop notifications
+//
+// OS notifications for things worth hearing about while the app is in the
+// background: private messages, mentions in chat, finished or failed
+// transfers and lost connections. Which of these raise one is chosen per
+// server; servers without preferences of their own follow the app
+// preferences, which have all of them on unless changed. Stored in
+// notification_prefs.json in the app data directory.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tauri::AppHandle;
+use tauri_plugin_notification::NotificationExt;
+use super::json_store;
+
+fn on() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationPrefs {
+    #[serde(default = "on")]
+    pub private_message: bool,
+    /// Someone else's chat line mentions us (see `mentions`)
+    #[serde(default = "on")]
+    pub mention: bool,
+    #[serde(default = "on")]
+    pub transfer_complete: bool,
+    #[serde(default = "on")]
+    pub transfer_failed: bool,
+    /// The connection dropped without us disconnecting
+    #[serde(default = "on")]
+    pub disconnect: bool,
+}
+
+impl Default for NotificationPrefs {
+    fn default() -> Self {
+        Self {
+            private_message: true,
+            mention: true,
+            transfer_complete: true,
+            transfer_failed: true,
+            disconnect: true,
+        }
+    }
+}
+
+pub struct NotificationPrefsStore {
+    path: PathBuf,
+    servers: HashMap<String, NotificationPrefs>,
+    /// For servers without their own
+    defaults: NotificationPrefs,
+}
+
+impl NotificationPrefsStore {
+    pub fn load(path: PathBuf) -> Self {
+        let servers = json_store::load_json_or_default(&path, "notification preferences");
+
+        Self { path, servers, defaults: NotificationPrefs::default() }
+    }
+
+    pub fn set_defaults(&mut self, defaults: NotificationPrefs) {
+        self.defaults = defaults;
+    }
+
+    pub fn get(&self, server_id: &str) -> NotificationPrefs {
+        self.servers.get(server_id).copied().unwrap_or(self.defaults)
+    }
+
+    pub fn set(&mut self, server_id: &str, prefs: NotificationPrefs) -> Result<(), String> {
+        if prefs == self.defaults {
+            self.servers.remove(server_id);
+        } else {
+            self.servers.insert(server_id.to_string(), prefs);
+        }
+
+        json_store::save_json(&self.path, &self.servers, "notification preferences")
+    }
+}
+
+/// Show a native notification. Failures (e.g. permission denied) are logged, not surfaced.
+pub fn show_os_notification(app_handle: &AppHandle, title: &str, body: &str) {
+    if let Err(e) = app_handle.notification().builder().title(title).body(body).show() {
+        eprintln!("Failed to show notification: {}", e);
+    }
+}
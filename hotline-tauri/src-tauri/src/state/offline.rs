@@ -0,0 +1,126 @@
+// Offline browsing
+//
+// File listings received from a server are remembered here (persisted to
+// file_list_cache.json) so they can be browsed read-only after disconnecting.
+// Everything served from a cache is wrapped in `CachedView` so the frontend
+// can label it with the time it was captured.
+
+use crate::protocol::client::FileInfo;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use super::conversations::now_secs;
+use super::json_store;
+
+/// Maximum number of folder listings kept across all servers
+const MAX_CACHED_LISTINGS: usize = 500;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CachedView<T> {
+    /// Always true; lets the frontend tell cached data from live replies
+    pub cached: bool,
+    pub cached_at: u64,
+    pub data: T,
+}
+
+impl<T> CachedView<T> {
+    pub fn new(cached_at: u64, data: T) -> Self {
+        Self {
+            cached: true,
+            cached_at,
+            data,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CachedListing {
+    server_id: String,
+    path: Vec<String>,
+    files: Vec<FileInfo>,
+    cached_at: u64,
+}
+
+pub struct FileListCache {
+    path: Option<PathBuf>,
+    listings: Vec<CachedListing>,
+}
+
+impl FileListCache {
+    pub fn load(path: PathBuf) -> Self {
+        let listings = json_store::load_json_or_default(&path, "file list cache");
+
+        Self {
+            path: Some(path),
+            listings,
+        }
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        json_store::save_json(path, &self.listings, "file list cache")
+    }
+
+    /// Remember a listing, replacing any older copy of the same folder
+    pub fn record(&mut self, server_id: &str, path: &[String], files: &[FileInfo]) -> Result<(), String> {
+        self.listings
+            .retain(|listing| !(listing.server_id == server_id && listing.path == path));
+        self.listings.push(CachedListing {
+            server_id: server_id.to_string(),
+            path: path.to_vec(),
+            files: files.to_vec(),
+            cached_at: now_secs(),
+        });
+        if self.listings.len() > MAX_CACHED_LISTINGS {
+            let excess = self.listings.len() - MAX_CACHED_LISTINGS;
+            self.listings.drain(..excess);
+        }
+        self.save()
+    }
+
+    pub fn get(&self, server_id: &str, path: &[String]) -> Option<CachedView<Vec<FileInfo>>> {
+        self.listings
+            .iter()
+            .find(|listing| listing.server_id == server_id && listing.path == path)
+            .map(|listing| CachedView::new(listing.cached_at, listing.files.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::client::file_kind::FileKind;
+
+    fn file(name: &str) -> FileInfo {
+        FileInfo {
+            name: name.to_string(),
+            size: 1,
+            is_folder: false,
+            file_type: "TEXT".to_string(),
+            creator: "ttxt".to_string(),
+            kind: FileKind::Text,
+        }
+    }
+
+    #[test]
+    fn newer_listing_replaces_older_copy() {
+        let mut cache = FileListCache {
+            path: None,
+            listings: Vec::new(),
+        };
+        let path = vec!["Uploads".to_string()];
+        cache.record("srv", &path, &[file("a.txt")]).unwrap();
+        cache.record("srv", &path, &[file("b.txt")]).unwrap();
+
+        let view = cache.get("srv", &path).unwrap();
+        assert!(view.cached);
+        assert_eq!(view.data.len(), 1);
+        assert_eq!(view.data[0].name, "b.txt");
+        assert!(cache.get("srv", &[]).is_none());
+        assert!(cache.get("other", &path).is_none());
+    }
+}
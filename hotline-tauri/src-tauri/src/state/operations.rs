@@ -0,0 +1,176 @@
+// Registry of cancellable operations
+//
+// Long-running commands register an operation id with a CancellationToken
+// for as long as they run. The frontend may pick the id itself (so it can
+// cancel a request it is still awaiting) or let one be generated.
+// cancel_operation looks the id up here and fires the token.
+//
+// Transfers (including folder downloads) run in the background: their commands return the operation id
+// immediately and report through `operation-progress-{id}` and
+// `operation-finished-{id}` events.
+
+use crate::protocol::cancel::CancellationToken;
+use crate::protocol::client::mac_forks::UploadFile;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tauri::{AppHandle, Emitter};
+
+use super::conversations::now_secs;
+use super::partial_downloads::PartialDownload;
+use super::server_windows::versioned;
+
+/// Work carried out by a background operation
+pub(crate) enum OperationJob {
+    Download {
+        entry: PartialDownload,
+        offset: u32,
+    },
+    FolderDownload {
+        server_id: String,
+        path: Vec<String>,
+        folder_name: String,
+        destination: PathBuf,
+    },
+    Upload {
+        server_id: String,
+        path: Vec<String>,
+        upload: UploadFile,
+        resume: bool,
+    },
+}
+
+pub(crate) fn emit_operation_progress(app_handle: &AppHandle, operation_id: &str, bytes: u64, total_bytes: u64) {
+    let progress = if total_bytes > 0 {
+        (bytes as f64 / total_bytes as f64 * 100.0) as u32
+    } else {
+        0
+    };
+    let payload = serde_json::json!({
+        "operationId": operation_id,
+        "bytes": bytes,
+        "totalBytes": total_bytes,
+        "progress": progress,
+    });
+    let _ = app_handle.emit(&format!("operation-progress-{}", operation_id), versioned(payload));
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OperationSummary {
+    pub id: String,
+    pub kind: String,
+    pub server_id: Option<String>,
+    pub started_at: u64,
+}
+
+struct Operation {
+    summary: OperationSummary,
+    token: CancellationToken,
+}
+
+#[derive(Default)]
+pub struct OperationRegistry {
+    operations: HashMap<String, Operation>,
+    next_id: u64,
+}
+
+impl OperationRegistry {
+    /// Register a new operation. A caller-supplied id that is already in use is
+    /// replaced by a generated one.
+    pub fn begin(&mut self, id: Option<String>, kind: &str, server_id: Option<&str>) -> (String, CancellationToken) {
+        let id = match id {
+            Some(id) if !self.operations.contains_key(&id) => id,
+            _ => {
+                self.next_id += 1;
+                format!("op-{}-{}", now_secs(), self.next_id)
+            }
+        };
+
+        let token = CancellationToken::new();
+        self.operations.insert(
+            id.clone(),
+            Operation {
+                summary: OperationSummary {
+                    id: id.clone(),
+                    kind: kind.to_string(),
+                    server_id: server_id.map(str::to_string),
+                    started_at: now_secs(),
+                },
+                token: token.clone(),
+            },
+        );
+        (id, token)
+    }
+
+    pub fn finish(&mut self, id: &str) {
+        self.operations.remove(id);
+    }
+
+    /// Cancel a running operation. Returns false if no such operation is running.
+    pub fn cancel(&mut self, id: &str) -> bool {
+        match self.operations.remove(id) {
+            Some(operation) => {
+                operation.token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Cancel every operation for a server (used when disconnecting)
+    pub fn cancel_server(&mut self, server_id: &str) {
+        self.operations.retain(|_, operation| {
+            if operation.summary.server_id.as_deref() == Some(server_id) {
+                operation.token.cancel();
+                false
+            } else {
+                true
+            }
+        });
+    }
+
+    /// Whether a download or upload for `server_id` is running
+    pub fn has_transfers(&self, server_id: &str) -> bool {
+        self.operations.values().any(|operation| {
+            operation.summary.server_id.as_deref() == Some(server_id)
+                && matches!(operation.summary.kind.as_str(), "download" | "upload" | "folderDownload")
+        })
+    }
+
+    pub fn list(&self) -> Vec<OperationSummary> {
+        let mut operations: Vec<OperationSummary> =
+            self.operations.values().map(|operation| operation.summary.clone()).collect();
+        operations.sort_by_key(|operation| operation.started_at);
+        operations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cancel_fires_token_and_forgets_operation() {
+        let mut registry = OperationRegistry::default();
+        let (id, token) = registry.begin(Some("fetch-1".to_string()), "trackerFetch", None);
+        assert_eq!(id, "fetch-1");
+
+        assert!(registry.cancel("fetch-1"));
+        assert!(token.is_cancelled());
+        assert!(!registry.cancel("fetch-1"));
+    }
+
+    #[test]
+    fn duplicate_ids_are_replaced_and_server_cancel_is_scoped() {
+        let mut registry = OperationRegistry::default();
+        let (first, first_token) = registry.begin(Some("op".to_string()), "download", Some("a"));
+        let (second, second_token) = registry.begin(Some("op".to_string()), "download", Some("b"));
+        assert_ne!(first, second);
+
+        registry.cancel_server("a");
+        assert!(first_token.is_cancelled());
+        assert!(!second_token.is_cancelled());
+        assert_eq!(registry.list().len(), 1);
+    }
+}
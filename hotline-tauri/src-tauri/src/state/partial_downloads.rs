@@ -0,0 +1,278 @@
+// Partial download manifest
+//
+// Downloads are written to `name.part` beside their destination while they
+// run. If a transfer is interrupted the partial file is kept and recorded
+// here so resume_download can ask the server to continue from the bytes we
+// already have. Entries are removed once the file is complete.
+//
+// A finished file is checked against the size the server announced and
+// renamed to its final name, which being in the same directory is atomic:
+// an interrupted download never looks like a complete one. Entries from
+// before downloads were written in place still have their data under the app
+// data directory; when that is another filesystem the data is first copied
+// next to the destination under a hidden temporary name.
+//
+// Every finished file is hashed with SHA-256 before it is moved into place,
+// and the digest goes out with the completion event so it can be compared
+// against one the server's admin published. With the skip-existing preference
+// on, a file already in the downloads folder under the same name and size is
+// taken as downloaded and not fetched again.
+
+use ring::digest::{self, Context, SHA256};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncReadExt;
+
+use super::conversations::now_secs;
+use crate::protocol::client::mac_forks::ForkMode;
+use super::json_store;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PartialDownload {
+    pub id: String,
+    pub server_id: String,
+    pub path: Vec<String>,
+    pub file_name: String,
+    /// Where the incomplete data lives
+    pub partial_path: PathBuf,
+    /// Where the finished file should end up
+    pub destination: PathBuf,
+    pub total_size: u64,
+    pub bytes_received: u64,
+    pub updated_at: u64,
+    /// What to do with INFO and resource forks once the download completes
+    #[serde(default)]
+    pub fork_mode: ForkMode,
+}
+
+/// Where a file download goes and what happens to its Mac forks
+#[derive(Debug, Clone, Default)]
+pub struct DownloadOptions {
+    pub download_folder: Option<String>,
+    pub fork_mode: ForkMode,
+}
+
+pub struct PartialDownloadManifest {
+    path: PathBuf,
+    entries: Vec<PartialDownload>,
+}
+
+impl PartialDownloadManifest {
+    pub fn load(app_data_dir: &Path) -> Self {
+        let path = app_data_dir.join("partial_downloads.json");
+
+        let entries: Vec<PartialDownload> = json_store::load_json_or_default(&path, "partial download manifest");
+
+        // Drop entries whose partial file has gone missing
+        let entries = entries.into_iter().filter(|entry| entry.partial_path.exists()).collect();
+
+        Self { path, entries }
+    }
+
+    fn save(&self) -> Result<(), String> {
+        json_store::save_json(&self.path, &self.entries, "partial download manifest")
+    }
+
+    pub fn get(&self, id: &str) -> Option<PartialDownload> {
+        self.entries.iter().find(|entry| entry.id == id).cloned()
+    }
+
+    /// Partial downloads for one server (or all servers), most recent first
+    pub fn list(&self, server_id: Option<&str>) -> Vec<PartialDownload> {
+        let mut entries: Vec<PartialDownload> = self
+            .entries
+            .iter()
+            .filter(|entry| server_id.is_none_or(|id| entry.server_id == id))
+            .cloned()
+            .collect();
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.updated_at));
+        entries
+    }
+
+    /// Insert or update an entry
+    pub fn record(&mut self, mut entry: PartialDownload) -> Result<(), String> {
+        entry.updated_at = now_secs();
+        match self.entries.iter_mut().find(|existing| existing.id == entry.id) {
+            Some(existing) => *existing = entry,
+            None => self.entries.push(entry),
+        }
+        self.save()
+    }
+
+    pub fn remove(&mut self, id: &str) -> Result<(), String> {
+        self.entries.retain(|entry| entry.id != id);
+        self.save()
+    }
+
+    /// Where to write a new download of `destination` while it runs; the
+    /// transfer id goes into the name when another download already has it
+    pub fn part_path(&self, destination: &Path, id: &str) -> PathBuf {
+        part_path(destination, id, |path| self.entries.iter().any(|entry| entry.partial_path == path))
+    }
+}
+
+/// Where to write a download of `destination` while it runs: `name.part`, or
+/// `name.<id>.part` when that file exists or `in_use` claims it
+pub fn part_path(destination: &Path, id: &str, in_use: impl Fn(&Path) -> bool) -> PathBuf {
+    let path = with_part_suffix(destination, None);
+    if path.exists() || in_use(&path) {
+        with_part_suffix(destination, Some(id))
+    } else {
+        path
+    }
+}
+
+fn with_part_suffix(destination: &Path, id: Option<&str>) -> PathBuf {
+    let name = destination
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    match id {
+        Some(id) => destination.with_file_name(format!("{}.{}.part", name, id)),
+        None => destination.with_file_name(format!("{}.part", name)),
+    }
+}
+
+/// Check a finished download against the size the server announced (0 when
+/// it announced none)
+pub fn verify_size(received: u64, expected: u64) -> Result<(), String> {
+    if expected == 0 || received == expected {
+        Ok(())
+    } else {
+        Err(format!("Download is {} bytes but the server announced {}", received, expected))
+    }
+}
+
+/// Whether `destination` already holds a file of `size` bytes, so a download
+/// of it can be skipped. An unknown size (0) never counts as a match.
+pub fn is_existing_copy(destination: &Path, size: u64) -> bool {
+    size > 0 && fs::metadata(destination).is_ok_and(|meta| meta.is_file() && meta.len() == size)
+}
+
+/// Lowercase hex SHA-256 of a file's contents
+pub async fn sha256_file(path: &Path) -> Result<String, String> {
+    let mut file = tokio::fs::File::open(path)
+        .await
+        .map_err(|e| format!("Failed to open {} for hashing: {}", path.display(), e))?;
+    let mut context = Context::new(&SHA256);
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let read = file
+            .read(&mut buffer)
+            .await
+            .map_err(|e| format!("Failed to read {} for hashing: {}", path.display(), e))?;
+        if read == 0 {
+            break;
+        }
+        context.update(&buffer[..read]);
+    }
+    Ok(hex(context.finish().as_ref()))
+}
+
+/// Lowercase hex SHA-256 of data already in memory, such as an upload
+pub fn sha256_bytes(data: &[u8]) -> String {
+    hex(digest::digest(&SHA256, data).as_ref())
+}
+
+fn hex(digest: &[u8]) -> String {
+    digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// The result of checking a file already on disk
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadVerification {
+    pub path: PathBuf,
+    pub size: u64,
+    pub sha256: String,
+    /// Whether the digest equals the expected one, when one was given
+    pub matches: Option<bool>,
+}
+
+/// Temporary name used while a finished download is copied next to `destination`
+fn staging_path(destination: &Path) -> PathBuf {
+    let name = destination
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    destination.with_file_name(format!(".{}.partial", name))
+}
+
+/// Move completed download data from `from` to `destination`
+pub async fn move_into_place(from: &Path, destination: &Path) -> Result<(), String> {
+    if tokio::fs::rename(from, destination).await.is_ok() {
+        return Ok(());
+    }
+
+    // Different filesystems: copy alongside the destination, then rename
+    let staging = staging_path(destination);
+    if let Err(e) = tokio::fs::copy(from, &staging).await {
+        let _ = tokio::fs::remove_file(&staging).await;
+        return Err(format!("Failed to write file: {}", e));
+    }
+    tokio::fs::rename(&staging, destination)
+        .await
+        .map_err(|e| format!("Failed to write file: {}", e))?;
+    let _ = tokio::fs::remove_file(from).await;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn staging_file_sits_next_to_destination() {
+        let destination = Path::new("/downloads/Read Me.txt");
+        assert_eq!(staging_path(destination), Path::new("/downloads/.Read Me.txt.partial"));
+    }
+
+    #[test]
+    fn part_file_sits_beside_destination_unless_taken() {
+        let base = std::env::temp_dir().join(format!("part-path-test-{}", std::process::id()));
+        std::fs::create_dir_all(&base).unwrap();
+        let manifest = PartialDownloadManifest { path: base.join("manifest.json"), entries: Vec::new() };
+        let destination = base.join("Read Me.txt");
+        assert_eq!(manifest.part_path(&destination, "7"), base.join("Read Me.txt.part"));
+
+        std::fs::write(base.join("Read Me.txt.part"), b"other").unwrap();
+        assert_eq!(manifest.part_path(&destination, "7"), base.join("Read Me.txt.7.part"));
+        assert!(verify_size(10, 10).is_ok() && verify_size(10, 0).is_ok());
+        assert!(verify_size(12, 10).is_err());
+
+        let _ = std::fs::remove_dir_all(&base);
+    }
+
+    #[tokio::test]
+    async fn hashes_files_and_spots_existing_copies() {
+        let path = std::env::temp_dir().join(format!("hash-test-{}.txt", std::process::id()));
+        std::fs::write(&path, b"abc").unwrap();
+        assert_eq!(
+            sha256_file(&path).await.unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert!(is_existing_copy(&path, 3));
+        assert!(!is_existing_copy(&path, 4) && !is_existing_copy(&path, 0));
+
+        let _ = std::fs::remove_file(&path);
+        assert!(sha256_file(&path).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn moves_data_into_place() {
+        let base = std::env::temp_dir().join(format!("partial-downloads-test-{}", std::process::id()));
+        std::fs::create_dir_all(&base).unwrap();
+        let from = base.join("1.partial");
+        let destination = base.join("file.bin");
+        std::fs::write(&from, b"data").unwrap();
+
+        move_into_place(&from, &destination).await.unwrap();
+        assert_eq!(std::fs::read(&destination).unwrap(), b"data");
+        assert!(!from.exists());
+        assert!(move_into_place(&from, &destination).await.is_err());
+
+        let _ = std::fs::remove_dir_all(&base);
+    }
+}
@@ -0,0 +1,116 @@
+// Application preferences
+//
+// Settings that apply everywhere unless something more specific overrides
+// them: the nickname and icon to log in with, where downloads go, transfer
+// limits and notifications for servers without their own, whether files
+// already in the downloads folder are fetched again, the theme the
+// windows should start in, the time zone and locale every timestamp is
+// shown in, and how much background task chatter reaches the frontend. Stored in preferences.json in the app data directory; every
+// change is announced with a `preferences-changed` event.
+
+use crate::protocol::client::bandwidth::TransferLimits;
+use crate::protocol::client::lifecycle::DiagnosticVerbosity;
+use crate::protocol::constants::{DEFAULT_NICKNAME, DEFAULT_USER_ICON};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use super::notifications::NotificationPrefs;
+use super::json_store;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Theme {
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Preferences {
+    pub nickname: String,
+    pub icon: u16,
+    /// None for the platform's downloads folder
+    pub download_directory: Option<String>,
+    pub transfer_limits: TransferLimits,
+    /// Don't download a file when one with the same name and size is
+    /// already in the downloads folder
+    pub skip_existing_downloads: bool,
+    pub notifications: NotificationPrefs,
+    pub theme: Theme,
+    /// IANA name such as "Europe/Berlin"; None for the system's. Chat logs,
+    /// message history and news keep UTC and are converted only for display
+    /// and transcripts.
+    pub time_zone: Option<String>,
+    /// BCP 47 tag such as "en-GB"; None for the system's
+    pub locale: Option<String>,
+    /// Which background task notices are emitted as `lifecycle` events
+    pub diagnostic_verbosity: DiagnosticVerbosity,
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Self {
+            nickname: DEFAULT_NICKNAME.to_string(),
+            icon: DEFAULT_USER_ICON,
+            download_directory: None,
+            transfer_limits: TransferLimits::default(),
+            skip_existing_downloads: false,
+            notifications: NotificationPrefs::default(),
+            theme: Theme::default(),
+            time_zone: None,
+            locale: None,
+            diagnostic_verbosity: DiagnosticVerbosity::default(),
+        }
+    }
+}
+
+impl Preferences {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.nickname.trim().is_empty() {
+            return Err("Nickname cannot be empty".to_string());
+        }
+        self.transfer_limits.validate()
+    }
+}
+
+pub struct PreferencesStore {
+    path: PathBuf,
+    preferences: Preferences,
+}
+
+impl PreferencesStore {
+    pub fn load(path: PathBuf) -> Self {
+        let preferences = json_store::load_json_or_default(&path, "preferences");
+
+        Self { path, preferences }
+    }
+
+    pub fn get(&self) -> &Preferences {
+        &self.preferences
+    }
+
+    pub fn set(&mut self, preferences: Preferences) -> Result<(), String> {
+        preferences.validate()?;
+        self.preferences = preferences;
+        json_store::save_json(&self.path, &self.preferences, "preferences")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_fields_fall_back_to_defaults() {
+        let preferences: Preferences = serde_json::from_str(r#"{"nickname": "alice", "theme": "dark"}"#).unwrap();
+        assert_eq!(preferences.nickname, "alice");
+        assert_eq!(preferences.theme, Theme::Dark);
+        assert_eq!(preferences.icon, DEFAULT_USER_ICON);
+        assert_eq!(preferences.notifications, NotificationPrefs::default());
+
+        let unnamed = Preferences { nickname: " ".to_string(), ..preferences };
+        assert!(unnamed.validate().is_err());
+    }
+}
@@ -0,0 +1,159 @@
+// Directories read_preview_file may read from
+//
+// The preview command takes a path from the webview, so it must not become a
+// way to read arbitrary files. Paths are canonicalized (resolving symlinks and
+// `..`) and must land inside the downloads directory, the app cache, or one of
+// the extra folders the user added. Extra folders live in
+// preview_allow_list.json in the app data directory.
+//
+// A compromised webview must not be able to widen the list, so folders are
+// only added through a native folder picker, never from a path the webview
+// sends. Folders that would open up too much are refused: a filesystem root,
+// the home directory or anything above it, and anything overlapping the app
+// data directory, where bookmarks and passwords are kept. The download
+// directory preference is set from the webview too, so it gets the same
+// checks before downloads are written there.
+
+use crate::protocol::sanitize_path::check_no_traversal;
+use std::fs;
+use std::path::{Path, PathBuf};
+use super::json_store;
+
+pub struct PreviewAllowList {
+    path: PathBuf,
+    extra: Vec<PathBuf>,
+    home: Option<PathBuf>,
+    app_data: PathBuf,
+}
+
+impl PreviewAllowList {
+    pub fn load(path: PathBuf, home: Option<PathBuf>, app_data: PathBuf) -> Self {
+        let extra: Vec<PathBuf> = json_store::load_json_or_default(&path, "preview allow list");
+
+        // Lists written before folders were checked may hold ones now refused
+        let extra = extra
+            .into_iter()
+            .filter(|dir| check_folder(dir, home.as_deref(), &app_data).is_ok())
+            .collect();
+        Self { path, extra, home, app_data }
+    }
+
+    pub fn extra(&self) -> Vec<PathBuf> {
+        self.extra.clone()
+    }
+
+    /// Canonical form of `directory`, or why it is too broad to use
+    pub fn check(&self, directory: &Path) -> Result<PathBuf, String> {
+        check_folder(directory, self.home.as_deref(), &self.app_data)
+    }
+
+    /// Add a folder the user picked in the native folder picker
+    pub fn add(&mut self, directory: &Path) -> Result<(), String> {
+        let directory = self.check(directory)?;
+        if self.extra.contains(&directory) {
+            return Ok(());
+        }
+        let mut extra = self.extra.clone();
+        extra.push(directory);
+        self.save(extra)
+    }
+
+    /// Removing only narrows the list, so the webview may ask for it
+    pub fn remove(&mut self, directory: &Path) -> Result<(), String> {
+        let extra = self.extra.iter().filter(|dir| dir.as_path() != directory).cloned().collect();
+        self.save(extra)
+    }
+
+    fn save(&mut self, extra: Vec<PathBuf>) -> Result<(), String> {
+        json_store::save_json(&self.path, &extra, "preview allow list")?;
+        self.extra = extra;
+        Ok(())
+    }
+
+    /// Resolve `file` and make sure it sits under `builtin_roots` or one of the
+    /// extra folders. Returns the canonical path to read.
+    pub fn resolve(&self, file: &Path, builtin_roots: &[PathBuf]) -> Result<PathBuf, String> {
+        let roots: Vec<PathBuf> = builtin_roots.iter().chain(self.extra.iter()).cloned().collect();
+        resolve_within(file, &roots)
+    }
+}
+
+/// Canonical form of a folder that may be added, or why it may not
+fn check_folder(directory: &Path, home: Option<&Path>, app_data: &Path) -> Result<PathBuf, String> {
+    if !directory.is_absolute() {
+        return Err(format!("Folders must be absolute paths: {}", directory.display()));
+    }
+    let canonical = fs::canonicalize(directory).map_err(|e| format!("Failed to read folder: {}", e))?;
+    let too_broad = canonical.parent().is_none()
+        || home.and_then(|home| fs::canonicalize(home).ok()).is_some_and(|home| home.starts_with(&canonical));
+    if too_broad {
+        return Err(format!("{} is too broad; pick a folder inside it", directory.display()));
+    }
+    let app_data = fs::canonicalize(app_data).unwrap_or_else(|_| app_data.to_path_buf());
+    if canonical.starts_with(&app_data) || app_data.starts_with(&canonical) {
+        return Err(format!("{} overlaps the app's own data", directory.display()));
+    }
+    Ok(canonical)
+}
+
+fn resolve_within(file: &Path, roots: &[PathBuf]) -> Result<PathBuf, String> {
+    if !file.is_absolute() {
+        return Err(format!("Preview path must be absolute: {}", file.display()));
+    }
+    check_no_traversal(file)?;
+
+    let canonical = fs::canonicalize(file).map_err(|e| format!("Failed to read file: {}", e))?;
+    let allowed = roots
+        .iter()
+        .filter_map(|root| fs::canonicalize(root).ok())
+        .any(|root| canonical.starts_with(root));
+    if !allowed {
+        return Err(format!("Preview is only available for downloaded files: {}", file.display()));
+    }
+    Ok(canonical)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_files_under_allowed_roots_resolve() {
+        let base = std::env::temp_dir().join(format!("preview-access-test-{}", std::process::id()));
+        let allowed = base.join("downloads");
+        let outside = base.join("elsewhere");
+        fs::create_dir_all(&allowed).unwrap();
+        fs::create_dir_all(&outside).unwrap();
+        fs::write(allowed.join("a.txt"), b"a").unwrap();
+        fs::write(outside.join("b.txt"), b"b").unwrap();
+
+        let roots = vec![allowed.clone()];
+        assert!(resolve_within(&allowed.join("a.txt"), &roots).is_ok());
+        assert!(resolve_within(&outside.join("b.txt"), &roots).is_err());
+        assert!(resolve_within(&allowed.join("../elsewhere/b.txt"), &roots).is_err());
+        assert!(resolve_within(Path::new("a.txt"), &roots).is_err());
+        assert!(resolve_within(&allowed.join("missing.txt"), &roots).is_err());
+
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn broad_folders_cannot_be_added() {
+        let base = fs::canonicalize(std::env::temp_dir())
+            .unwrap()
+            .join(format!("preview-folders-test-{}", std::process::id()));
+        let home = base.join("home");
+        let app_data = home.join("app-data");
+        let pictures = home.join("Pictures");
+        fs::create_dir_all(&app_data).unwrap();
+        fs::create_dir_all(&pictures).unwrap();
+
+        assert!(check_folder(Path::new("/"), Some(&home), &app_data).is_err());
+        assert!(check_folder(&home, Some(&home), &app_data).is_err());
+        assert!(check_folder(&base, Some(&home), &app_data).is_err());
+        assert!(check_folder(&app_data, Some(&home), &app_data).is_err());
+        assert_eq!(check_folder(&pictures, Some(&home), &app_data), Ok(pictures.clone()));
+
+        let _ = fs::remove_dir_all(&base);
+    }
+}
@@ -0,0 +1,120 @@
+// Recently connected servers
+//
+// Every successful connection is remembered, whether it came from a bookmark,
+// a tracker listing or an address typed into the connect dialog, so the UI can
+// offer a "Recent" list that doesn't depend on bookmarks. The list is most
+// recent first, keyed by address and port, capped at MAX_RECENT_SERVERS and
+// stored in recent_servers.json in the app data directory. Passwords are never
+// stored here.
+
+use crate::protocol::types::Bookmark;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use super::conversations::now_secs;
+use super::json_store;
+
+const MAX_RECENT_SERVERS: usize = 20;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecentServer {
+    pub name: String,
+    pub address: String,
+    pub port: u16,
+    pub login: String,
+    pub tls: bool,
+    /// The bookmark the connection was opened from, if it was saved as one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bookmark_id: Option<String>,
+    pub connected_at: u64,
+}
+
+pub struct RecentServers {
+    path: PathBuf,
+    servers: Vec<RecentServer>,
+}
+
+impl RecentServers {
+    pub fn load(path: PathBuf) -> Self {
+        let servers = json_store::load_json_or_default(&path, "recent servers");
+
+        Self { path, servers }
+    }
+
+    pub fn list(&self) -> Vec<RecentServer> {
+        self.servers.clone()
+    }
+
+    /// Move the server `bookmark` connected to to the front of the list.
+    /// `saved` says whether the bookmark is one of the user's bookmarks.
+    pub fn record(&mut self, bookmark: &Bookmark, tls: bool, port: u16, saved: bool) -> Result<(), String> {
+        self.push(RecentServer {
+            name: bookmark.name.clone(),
+            address: bookmark.address.clone(),
+            port,
+            login: bookmark.login.clone(),
+            tls,
+            bookmark_id: saved.then(|| bookmark.id.clone()),
+            connected_at: now_secs(),
+        });
+        self.save()
+    }
+
+    pub fn clear(&mut self) -> Result<(), String> {
+        self.servers.clear();
+        self.save()
+    }
+
+    fn push(&mut self, server: RecentServer) {
+        self.servers
+            .retain(|s| !(s.address.eq_ignore_ascii_case(&server.address) && s.port == server.port));
+        self.servers.insert(0, server);
+        self.servers.truncate(MAX_RECENT_SERVERS);
+    }
+
+    fn save(&self) -> Result<(), String> {
+        json_store::save_json(&self.path, &self.servers, "recent servers")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server(address: &str, port: u16) -> RecentServer {
+        RecentServer {
+            name: address.to_string(),
+            address: address.to_string(),
+            port,
+            login: "guest".to_string(),
+            tls: false,
+            bookmark_id: None,
+            connected_at: 0,
+        }
+    }
+
+    #[test]
+    fn most_recent_first_without_repeats_and_capped() {
+        let mut recent = RecentServers {
+            path: PathBuf::new(),
+            servers: Vec::new(),
+        };
+        recent.push(server("a.example", 5500));
+        recent.push(server("b.example", 5500));
+        recent.push(server("A.example", 5500));
+        recent.push(server("a.example", 5600));
+
+        let order: Vec<(String, u16)> = recent.list().into_iter().map(|s| (s.address, s.port)).collect();
+        assert_eq!(order, vec![
+            ("a.example".to_string(), 5600),
+            ("A.example".to_string(), 5500),
+            ("b.example".to_string(), 5500),
+        ]);
+
+        for i in 0..(MAX_RECENT_SERVERS as u16 + 5) {
+            recent.push(server("c.example", 6000 + i));
+        }
+        assert_eq!(recent.list().len(), MAX_RECENT_SERVERS);
+        assert_eq!(recent.list()[0].port, 6000 + MAX_RECENT_SERVERS as u16 + 4);
+    }
+}
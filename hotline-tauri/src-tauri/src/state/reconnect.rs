@@ -0,0 +1,184 @@
+// Automatic reconnection after a dropped connection
+//
+// When the receive loop loses the socket, the event loop retries the same
+// client with exponential backoff instead of reporting Disconnected straight
+// away. The policy is stored in reconnect.json in the app data directory.
+
+use crate::protocol::types::ConnectionStatus;
+use crate::protocol::HotlineClient;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::AppHandle;
+use tokio::sync::RwLock;
+
+use super::server_windows;
+use super::json_store;
+
+/// Upper bound on one attempt (TCP connect, handshake and login)
+const ATTEMPT_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ReconnectPolicy {
+    pub enabled: bool,
+    /// Wait before the first attempt; doubles after each failure
+    pub initial_delay_secs: u64,
+    pub max_delay_secs: u64,
+    /// Attempts before giving up and reporting Disconnected
+    pub max_retries: u32,
+    /// Treat a connection that has been silent this long as lost; 0 turns the
+    /// watchdog off
+    pub watchdog_timeout_secs: u64,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            initial_delay_secs: 2,
+            max_delay_secs: 60,
+            max_retries: 8,
+            watchdog_timeout_secs: 420,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Delay before `attempt` (1-based)
+    pub fn delay_for_attempt(&self, attempt: u32) -> Duration {
+        let factor = 1u64 << attempt.saturating_sub(1).min(20);
+        let secs = self.initial_delay_secs.saturating_mul(factor).min(self.max_delay_secs);
+        Duration::from_secs(secs)
+    }
+
+    pub fn watchdog_timeout(&self) -> Option<Duration> {
+        (self.watchdog_timeout_secs > 0).then(|| Duration::from_secs(self.watchdog_timeout_secs))
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.initial_delay_secs == 0 {
+            return Err("Initial reconnect delay must be at least one second".to_string());
+        }
+        if self.max_delay_secs < self.initial_delay_secs {
+            return Err("Maximum reconnect delay cannot be shorter than the initial delay".to_string());
+        }
+        if self.enabled && self.max_retries == 0 {
+            return Err("Reconnect attempts must be greater than zero".to_string());
+        }
+        if self.watchdog_timeout_secs > 0 && self.watchdog_timeout_secs < 60 {
+            return Err("Watchdog timeout must be at least 60 seconds".to_string());
+        }
+        Ok(())
+    }
+}
+
+pub struct ReconnectSettings {
+    path: PathBuf,
+    policy: ReconnectPolicy,
+}
+
+impl ReconnectSettings {
+    pub fn load(path: PathBuf) -> Self {
+        let policy = json_store::load_json_or_default(&path, "reconnect policy");
+
+        Self { path, policy }
+    }
+
+    pub fn policy(&self) -> ReconnectPolicy {
+        self.policy.clone()
+    }
+
+    pub fn set(&mut self, policy: ReconnectPolicy) -> Result<(), String> {
+        policy.validate()?;
+        json_store::save_json(&self.path, &policy, "reconnect policy")?;
+        self.policy = policy;
+        Ok(())
+    }
+}
+
+fn emit_status(app_handle: &AppHandle, server_id: &str, status: ConnectionStatus) {
+    server_windows::emit(app_handle, server_id, "status-changed", serde_json::json!({
+        "status": status,
+    }));
+}
+
+/// Retry `server_id` until it logs in again, the policy runs out, or the user
+/// disconnects (which removes the client from `clients`).
+pub async fn supervise(
+    app_handle: AppHandle,
+    clients: Arc<RwLock<HashMap<String, Arc<HotlineClient>>>>,
+    server_id: String,
+    policy: ReconnectPolicy,
+) {
+    for attempt in 1..=policy.max_retries {
+        let delay = policy.delay_for_attempt(attempt);
+        println!("Reconnect {}: attempt {}/{} in {:?}", server_id, attempt, policy.max_retries, delay);
+        emit_status(&app_handle, &server_id, ConnectionStatus::Reconnecting);
+        server_windows::emit(&app_handle, &server_id, "reconnecting", serde_json::json!({
+            "attempt": attempt,
+            "maxRetries": policy.max_retries,
+            "delaySecs": delay.as_secs(),
+        }));
+        tokio::time::sleep(delay).await;
+
+        let clients = clients.read().await;
+        let Some(client) = clients.get(&server_id) else {
+            println!("Reconnect {}: server was disconnected, giving up", server_id);
+            return;
+        };
+
+        let result = tokio::time::timeout(ATTEMPT_TIMEOUT, client.reconnect())
+            .await
+            .unwrap_or_else(|_| Err("Reconnect attempt timed out".to_string()));
+        match result {
+            Ok(()) => {
+                println!("Reconnect {}: connected after {} attempt(s)", server_id, attempt);
+                server_windows::emit(&app_handle, &server_id, "reconnected", serde_json::json!({
+                    "attempt": attempt,
+                }));
+                // Anyone who left while we were away only shows up in a full re-sync
+                if let Err(e) = client.refresh_user_list().await {
+                    eprintln!("Roster refresh failed: {}", e);
+                }
+                return;
+            }
+            Err(e) => eprintln!("Reconnect {}: attempt {} failed: {}", server_id, attempt, e),
+        }
+    }
+
+    println!("Reconnect {}: giving up after {} attempts", server_id, policy.max_retries);
+    if let Some(client) = clients.read().await.get(&server_id) {
+        let _ = client.disconnect().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        let policy = ReconnectPolicy {
+            initial_delay_secs: 2,
+            max_delay_secs: 30,
+            ..Default::default()
+        };
+        let delays: Vec<u64> = (1..=6).map(|n| policy.delay_for_attempt(n).as_secs()).collect();
+        assert_eq!(delays, vec![2, 4, 8, 16, 30, 30]);
+        assert_eq!(policy.delay_for_attempt(200).as_secs(), 30);
+    }
+
+    #[test]
+    fn rejects_inconsistent_policies() {
+        assert!(ReconnectPolicy::default().validate().is_ok());
+        assert!(ReconnectPolicy { initial_delay_secs: 0, ..Default::default() }.validate().is_err());
+        assert!(ReconnectPolicy { max_delay_secs: 1, ..Default::default() }.validate().is_err());
+        assert!(ReconnectPolicy { max_retries: 0, ..Default::default() }.validate().is_err());
+        assert!(ReconnectPolicy { enabled: false, max_retries: 0, ..Default::default() }.validate().is_ok());
+        assert!(ReconnectPolicy { watchdog_timeout_secs: 10, ..Default::default() }.validate().is_err());
+        assert!(ReconnectPolicy { watchdog_timeout_secs: 0, ..Default::default() }.validate().is_ok());
+    }
+}
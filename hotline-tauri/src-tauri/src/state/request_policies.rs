@@ -0,0 +1,37 @@
+// Per-bookmark request timeouts and retries
+//
+// Stored in request_policies.json in the app data directory and applied to
+// the client whenever a connection to that bookmark is opened.
+
+use crate::protocol::client::request_policy::RequestPolicy;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use super::json_store;
+
+pub struct RequestPolicyStore {
+    path: PathBuf,
+    bookmarks: HashMap<String, RequestPolicy>,
+}
+
+impl RequestPolicyStore {
+    pub fn load(path: PathBuf) -> Self {
+        let bookmarks = json_store::load_json_or_default(&path, "request policies");
+
+        Self { path, bookmarks }
+    }
+
+    pub fn get(&self, bookmark_id: &str) -> RequestPolicy {
+        self.bookmarks.get(bookmark_id).copied().unwrap_or_default()
+    }
+
+    pub fn set(&mut self, bookmark_id: &str, policy: RequestPolicy) -> Result<(), String> {
+        policy.validate()?;
+        if policy == RequestPolicy::default() {
+            self.bookmarks.remove(bookmark_id);
+        } else {
+            self.bookmarks.insert(bookmark_id.to_string(), policy);
+        }
+
+        json_store::save_json(&self.path, &self.bookmarks, "request policies")
+    }
+}
@@ -0,0 +1,173 @@
+// Waiting for a slot on a full server
+//
+// Servers with a user limit refuse logins once it is reached. When the user
+// asks to wait, connect_server tries again every `interval_secs` plus a random
+// share of `jitter_secs`, so clients turned away at the same moment don't all
+// come back together, until a login succeeds, `max_duration_secs` have passed
+// or the wait is cancelled. The policy is stored in server_full_retry.json in
+// the app data directory.
+
+use crate::protocol::cancel::CancellationToken;
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+use super::json_store;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ServerFullRetryPolicy {
+    pub interval_secs: u64,
+    /// Up to this many seconds are added to each interval at random
+    pub jitter_secs: u64,
+    /// Give up after waiting this long in total
+    pub max_duration_secs: u64,
+}
+
+impl Default for ServerFullRetryPolicy {
+    fn default() -> Self {
+        Self {
+            interval_secs: 30,
+            jitter_secs: 15,
+            max_duration_secs: 1800,
+        }
+    }
+}
+
+impl ServerFullRetryPolicy {
+    /// Wait before the next attempt, with `random` picking the jitter
+    pub fn delay(&self, random: u32) -> Duration {
+        let jitter = u64::from(random) % (self.jitter_secs + 1);
+        Duration::from_secs(self.interval_secs + jitter)
+    }
+
+    pub fn max_duration(&self) -> Duration {
+        Duration::from_secs(self.max_duration_secs)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.interval_secs < 5 {
+            return Err("Retry interval must be at least 5 seconds".to_string());
+        }
+        if self.jitter_secs > self.interval_secs {
+            return Err("Retry jitter cannot be longer than the interval".to_string());
+        }
+        if self.max_duration_secs < self.interval_secs {
+            return Err("Maximum wait cannot be shorter than the retry interval".to_string());
+        }
+        if self.max_duration_secs > 86_400 {
+            return Err("Maximum wait must be at most 24 hours".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Random input for `ServerFullRetryPolicy::delay`
+pub fn random_jitter() -> u32 {
+    let mut bytes = [0u8; 4];
+    if SystemRandom::new().fill(&mut bytes).is_err() {
+        return 0;
+    }
+    u32::from_le_bytes(bytes)
+}
+
+pub struct ServerFullRetry {
+    path: PathBuf,
+    policy: ServerFullRetryPolicy,
+    /// Waits in progress, by bookmark id
+    waiting: HashMap<String, CancellationToken>,
+}
+
+impl ServerFullRetry {
+    pub fn load(path: PathBuf) -> Self {
+        let policy = json_store::load_json_or_default(&path, "server full retry policy");
+
+        Self {
+            path,
+            policy,
+            waiting: HashMap::new(),
+        }
+    }
+
+    pub fn policy(&self) -> ServerFullRetryPolicy {
+        self.policy.clone()
+    }
+
+    pub fn set(&mut self, policy: ServerFullRetryPolicy) -> Result<(), String> {
+        policy.validate()?;
+        json_store::save_json(&self.path, &policy, "server full retry policy")?;
+        self.policy = policy;
+        Ok(())
+    }
+
+    /// Start waiting for `bookmark_id`, cancelling any earlier wait for it
+    pub fn begin(&mut self, bookmark_id: &str) -> CancellationToken {
+        let token = CancellationToken::new();
+        if let Some(previous) = self.waiting.insert(bookmark_id.to_string(), token.clone()) {
+            previous.cancel();
+        }
+        token
+    }
+
+    /// Forget the wait behind `token` once it has ended. A cancelled token has
+    /// already been removed or replaced by a newer wait.
+    pub fn finish(&mut self, bookmark_id: &str, token: &CancellationToken) {
+        if !token.is_cancelled() {
+            self.waiting.remove(bookmark_id);
+        }
+    }
+
+    /// Stop waiting for `bookmark_id`. Returns false if it wasn't waiting.
+    pub fn cancel(&mut self, bookmark_id: &str) -> bool {
+        match self.waiting.remove(bookmark_id) {
+            Some(token) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delay_adds_bounded_jitter() {
+        let policy = ServerFullRetryPolicy { interval_secs: 30, jitter_secs: 10, max_duration_secs: 600 };
+        assert_eq!(policy.delay(0), Duration::from_secs(30));
+        assert_eq!(policy.delay(10), Duration::from_secs(40));
+        assert_eq!(policy.delay(11), Duration::from_secs(30));
+        assert!(policy.delay(random_jitter()) <= Duration::from_secs(40));
+
+        let steady = ServerFullRetryPolicy { jitter_secs: 0, ..policy };
+        assert_eq!(steady.delay(u32::MAX), Duration::from_secs(30));
+    }
+
+    #[test]
+    fn rejects_out_of_range_values() {
+        assert!(ServerFullRetryPolicy::default().validate().is_ok());
+        assert!(ServerFullRetryPolicy { interval_secs: 1, ..Default::default() }.validate().is_err());
+        assert!(ServerFullRetryPolicy { jitter_secs: 60, ..Default::default() }.validate().is_err());
+        assert!(ServerFullRetryPolicy { max_duration_secs: 10, ..Default::default() }.validate().is_err());
+    }
+
+    #[test]
+    fn a_new_wait_cancels_the_previous_one() {
+        let mut retry = ServerFullRetry::load(std::env::temp_dir().join("hotline-server-full-test.json"));
+        let first = retry.begin("b1");
+        let second = retry.begin("b1");
+        assert!(first.is_cancelled());
+        assert!(!second.is_cancelled());
+        assert!(retry.cancel("b1"));
+        assert!(second.is_cancelled());
+        assert!(!retry.cancel("b1"));
+
+        let third = retry.begin("b1");
+        retry.finish("b1", &first);
+        assert!(!third.is_cancelled());
+        assert!(retry.cancel("b1"));
+    }
+}
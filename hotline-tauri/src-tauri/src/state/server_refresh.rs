@@ -0,0 +1,191 @@
+// Periodic refresh of server info and the banner
+//
+// Both are fetched once after login, but some servers swap their banner or
+// agreement during long sessions. When the policy is enabled, a task per
+// connection fetches them again on a timer; refresh_server_info does the same
+// on demand. Updates are only emitted when the content differs from what was
+// last handed to the UI. The policy is stored in server_refresh.json in the
+// app data directory.
+
+use crate::protocol::types::ServerInfo;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use super::json_store;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ServerRefreshPolicy {
+    pub enabled: bool,
+    pub interval_secs: u64,
+}
+
+impl Default for ServerRefreshPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_secs: 900,
+        }
+    }
+}
+
+impl ServerRefreshPolicy {
+    /// Time between refreshes, or None when periodic refresh is off
+    pub fn interval(&self) -> Option<Duration> {
+        self.enabled.then(|| Duration::from_secs(self.interval_secs))
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.interval_secs < 60 {
+            return Err("Server info refresh interval must be at least 60 seconds".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Which parts changed in one refresh
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RefreshOutcome {
+    pub info_changed: bool,
+    pub banner_changed: bool,
+}
+
+/// What the UI last saw from one server
+#[derive(Default)]
+struct Snapshot {
+    info: Option<serde_json::Value>,
+    banner: Option<u64>,
+}
+
+pub struct ServerRefresh {
+    path: PathBuf,
+    policy: ServerRefreshPolicy,
+    snapshots: HashMap<String, Snapshot>,
+    tasks: HashMap<String, JoinHandle<()>>,
+}
+
+impl ServerRefresh {
+    pub fn load(path: PathBuf) -> Self {
+        let policy = json_store::load_json_or_default(&path, "server refresh policy");
+
+        Self {
+            path,
+            policy,
+            snapshots: HashMap::new(),
+            tasks: HashMap::new(),
+        }
+    }
+
+    pub fn policy(&self) -> ServerRefreshPolicy {
+        self.policy.clone()
+    }
+
+    pub fn set(&mut self, policy: ServerRefreshPolicy) -> Result<(), String> {
+        policy.validate()?;
+        json_store::save_json(&self.path, &policy, "server refresh policy")?;
+        self.policy = policy;
+        Ok(())
+    }
+
+    /// Remember the info shown for `server_id`. Returns true when it differs
+    /// from the previous copy.
+    pub fn record_info(&mut self, server_id: &str, info: &ServerInfo) -> bool {
+        let value = serde_json::to_value(info).ok();
+        let snapshot = self.snapshots.entry(server_id.to_string()).or_default();
+        let changed = snapshot.info != value;
+        snapshot.info = value;
+        changed
+    }
+
+    /// Remember the banner shown for `server_id`. Returns true when it differs
+    /// from the previous copy.
+    pub fn record_banner(&mut self, server_id: &str, data: &[u8]) -> bool {
+        let mut hasher = DefaultHasher::new();
+        data.hash(&mut hasher);
+        let hash = Some(hasher.finish());
+        let snapshot = self.snapshots.entry(server_id.to_string()).or_default();
+        let changed = snapshot.banner != hash;
+        snapshot.banner = hash;
+        changed
+    }
+
+    /// Track the timer task for a connection, replacing any earlier one
+    pub fn start(&mut self, server_id: &str, task: JoinHandle<()>) {
+        if let Some(previous) = self.tasks.insert(server_id.to_string(), task) {
+            previous.abort();
+        }
+    }
+
+    /// Stop the timer and forget what was shown, after a disconnect
+    pub fn stop(&mut self, server_id: &str) {
+        if let Some(task) = self.tasks.remove(server_id) {
+            task.abort();
+        }
+        self.snapshots.remove(server_id);
+    }
+}
+
+/// Banner image as a data URL, with the type sniffed from its signature
+pub fn banner_data_url(data: &[u8]) -> String {
+    let mime_type = if data.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        "image/jpeg"
+    } else if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+        "image/gif"
+    } else {
+        // PNG, and the default for anything unrecognized
+        "image/png"
+    };
+    format!("data:{};base64,{}", mime_type, STANDARD.encode(data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(name: &str) -> ServerInfo {
+        serde_json::from_value(serde_json::json!({
+            "name": name,
+            "description": "",
+            "version": "190",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn reports_changes_only_when_content_differs() {
+        let mut refresh = ServerRefresh::load(std::env::temp_dir().join("hotline-server-refresh-test.json"));
+        assert!(refresh.record_info("s1", &info("Home")));
+        assert!(!refresh.record_info("s1", &info("Home")));
+        assert!(refresh.record_info("s1", &info("Home II")));
+
+        assert!(refresh.record_banner("s1", b"one"));
+        assert!(!refresh.record_banner("s1", b"one"));
+        assert!(refresh.record_banner("s1", b"two"));
+
+        refresh.stop("s1");
+        assert!(refresh.record_banner("s1", b"two"));
+    }
+
+    #[test]
+    fn interval_needs_policy_enabled_and_a_minute() {
+        let policy = ServerRefreshPolicy::default();
+        assert_eq!(policy.interval(), None);
+        let enabled = ServerRefreshPolicy { enabled: true, interval_secs: 300 };
+        assert_eq!(enabled.interval(), Some(Duration::from_secs(300)));
+        assert!(ServerRefreshPolicy { enabled: true, interval_secs: 10 }.validate().is_err());
+    }
+
+    #[test]
+    fn sniffs_banner_types() {
+        assert!(banner_data_url(&[0xFF, 0xD8, 0xFF, 0xE1]).starts_with("data:image/jpeg;base64,"));
+        assert!(banner_data_url(b"GIF89a...").starts_with("data:image/gif;base64,"));
+        assert!(banner_data_url(&[0x89, b'P', b'N', b'G']).starts_with("data:image/png;base64,"));
+    }
+}
@@ -0,0 +1,122 @@
+// Server windows
+//
+// A connected server can be moved out of the tabs into a window of its own.
+// The window's label is derived from the server id, so events about the
+// server go only to that window while it's open and to every window
+// otherwise, under the same `{event}-{server_id}` names either way. Closing
+// the window disconnects its server.
+//
+// Event payloads use camelCase keys throughout, and object payloads carry a
+// `schemaVersion` so the frontend can tell which layout it's reading.
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, EventTarget, Manager, WebviewUrl, WebviewWindowBuilder};
+
+const LABEL_PREFIX: &str = "server-";
+
+/// Bumped when event payload keys change. Version 2 switched the last
+/// snake_case keys (`parent_id`, `auto_connect`, `is_text`, ...) to camelCase;
+/// the old spellings are still accepted wherever payloads are read back.
+pub const PAYLOAD_SCHEMA_VERSION: u32 = 2;
+
+/// The payload as JSON, with `schemaVersion` added when it's an object
+pub fn versioned<S: Serialize>(payload: S) -> serde_json::Value {
+    let mut value = serde_json::to_value(payload).unwrap_or_default();
+    if let serde_json::Value::Object(map) = &mut value {
+        map.insert("schemaVersion".to_string(), PAYLOAD_SCHEMA_VERSION.into());
+    }
+    value
+}
+
+/// Window labels only allow a few characters, so the id goes in as hex
+pub fn window_label(server_id: &str) -> String {
+    let hex: String = server_id.bytes().map(|b| format!("{:02x}", b)).collect();
+    format!("{}{}", LABEL_PREFIX, hex)
+}
+
+/// The server a window belongs to, if it's a server window
+pub fn server_id_for_label(label: &str) -> Option<String> {
+    let hex = label.strip_prefix(LABEL_PREFIX)?;
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    let bytes = (0..hex.len())
+        .step_by(2)
+        .map(|at| u8::from_str_radix(hex.get(at..at + 2)?, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    String::from_utf8(bytes).ok()
+}
+
+/// Emit `{event}-{server_id}` to the server's window, or everywhere when it
+/// has none
+pub fn emit<S: Serialize + Clone>(app_handle: &AppHandle, server_id: &str, event: &str, payload: S) {
+    let name = format!("{}-{}", event, server_id);
+    let payload = versioned(payload);
+    let label = window_label(server_id);
+    let result = if app_handle.get_webview_window(&label).is_some() {
+        app_handle.emit_to(EventTarget::webview_window(label), &name, payload)
+    } else {
+        app_handle.emit(&name, payload)
+    };
+    if let Err(e) = result {
+        eprintln!("Failed to emit {}: {}", name, e);
+    }
+}
+
+fn query_escape(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'_' | b'.' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Open (or focus) the window for `server_id`
+pub fn open(app_handle: &AppHandle, server_id: &str, title: &str) -> Result<(), String> {
+    let label = window_label(server_id);
+    if let Some(window) = app_handle.get_webview_window(&label) {
+        return window.set_focus().map_err(|e| format!("Failed to focus window: {}", e));
+    }
+
+    let url = format!("index.html?serverWindow={}&name={}", query_escape(server_id), query_escape(title));
+    WebviewWindowBuilder::new(app_handle, &label, WebviewUrl::App(url.into()))
+        .title(title)
+        .inner_size(1000.0, 700.0)
+        .build()
+        .map(|_| ())
+        .map_err(|e| format!("Failed to open window: {}", e))
+}
+
+/// Close the window for `server_id`; closing it disconnects the server
+pub fn close(app_handle: &AppHandle, server_id: &str) -> Result<(), String> {
+    match app_handle.get_webview_window(&window_label(server_id)) {
+        Some(window) => window.close().map_err(|e| format!("Failed to close window: {}", e)),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels_round_trip_any_server_id() {
+        for id in ["default-server-system7", "hotline.example.com:5500", "ünïcode id"] {
+            let label = window_label(id);
+            assert!(label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'));
+            assert_eq!(server_id_for_label(&label).as_deref(), Some(id));
+        }
+        assert_eq!(server_id_for_label("main"), None);
+        assert_eq!(server_id_for_label("server-abc"), None);
+    }
+
+    #[test]
+    fn object_payloads_carry_the_schema_version() {
+        let payload = versioned(serde_json::json!({ "folders": [["Uploads"]] }));
+        assert_eq!(payload["schemaVersion"], PAYLOAD_SCHEMA_VERSION);
+        assert_eq!(payload["folders"][0][0], "Uploads");
+        assert_eq!(versioned(vec![1, 2]), serde_json::json!([1, 2]));
+    }
+}
@@ -0,0 +1,133 @@
+// Session transfer totals
+//
+// Unlike the opt-in metrics, these are always kept: files and bytes moved
+// each way, time spent transferring and failures, for this run of the app
+// and for each server. People moving large archives use them to follow a
+// job; a server's totals are also emitted as a summary when it disconnects.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use super::conversations::now_secs;
+use super::metrics::TransferDirection;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferTotals {
+    pub files_downloaded: u64,
+    pub files_uploaded: u64,
+    /// Includes what failed transfers moved before they stopped
+    pub bytes_downloaded: u64,
+    pub bytes_uploaded: u64,
+    pub failures: u64,
+    /// Time spent transferring, in seconds
+    pub transfer_secs: f64,
+    /// Bytes moved per second of transfer time
+    pub average_bytes_per_sec: f64,
+}
+
+impl TransferTotals {
+    fn add(&mut self, direction: TransferDirection, files: u64, bytes: u64, elapsed: Duration, ok: bool) {
+        match direction {
+            TransferDirection::Download => {
+                self.bytes_downloaded += bytes;
+                if ok {
+                    self.files_downloaded += files;
+                }
+            }
+            TransferDirection::Upload => {
+                self.bytes_uploaded += bytes;
+                if ok {
+                    self.files_uploaded += files;
+                }
+            }
+        }
+        if !ok {
+            self.failures += 1;
+        }
+        self.transfer_secs += elapsed.as_secs_f64();
+        if self.transfer_secs > 0.0 {
+            self.average_bytes_per_sec = (self.bytes_downloaded + self.bytes_uploaded) as f64 / self.transfer_secs;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionSummary {
+    /// When the app started (Unix seconds)
+    pub since: u64,
+    pub totals: TransferTotals,
+    /// Totals by server id
+    pub servers: BTreeMap<String, TransferTotals>,
+}
+
+#[derive(Default)]
+struct Totals {
+    overall: TransferTotals,
+    servers: BTreeMap<String, TransferTotals>,
+}
+
+pub struct SessionStats {
+    since: u64,
+    totals: Mutex<Totals>,
+}
+
+impl Default for SessionStats {
+    fn default() -> Self {
+        Self {
+            since: now_secs(),
+            totals: Mutex::new(Totals::default()),
+        }
+    }
+}
+
+impl SessionStats {
+    /// Count one finished transfer; a folder download counts each file in it
+    pub fn record(&self, server_id: &str, direction: TransferDirection, files: u64, bytes: u64, elapsed: Duration, ok: bool) {
+        let mut totals = self.totals.lock().unwrap();
+        totals.overall.add(direction, files, bytes, elapsed, ok);
+        totals.servers.entry(server_id.to_string()).or_default().add(direction, files, bytes, elapsed, ok);
+    }
+
+    pub fn summary(&self) -> SessionSummary {
+        let totals = self.totals.lock().unwrap();
+        SessionSummary {
+            since: self.since,
+            totals: totals.overall.clone(),
+            servers: totals.servers.clone(),
+        }
+    }
+
+    /// Totals for one server, zero when nothing was transferred
+    pub fn server(&self, server_id: &str) -> TransferTotals {
+        self.totals.lock().unwrap().servers.get(server_id).cloned().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn totals_add_up_overall_and_per_server() {
+        let stats = SessionStats::default();
+        stats.record("a", TransferDirection::Download, 3, 3000, Duration::from_secs(2), true);
+        stats.record("a", TransferDirection::Upload, 1, 500, Duration::from_secs(1), false);
+        stats.record("b", TransferDirection::Upload, 1, 1500, Duration::from_secs(1), true);
+
+        let summary = stats.summary();
+        assert_eq!(summary.totals.files_downloaded, 3);
+        assert_eq!(summary.totals.files_uploaded, 1);
+        assert_eq!(summary.totals.bytes_uploaded, 2000);
+        assert_eq!(summary.totals.failures, 1);
+        assert_eq!(summary.totals.average_bytes_per_sec, 1250.0);
+
+        let a = stats.server("a");
+        assert_eq!((a.files_uploaded, a.bytes_uploaded, a.failures), (0, 500, 1));
+        assert_eq!(a.average_bytes_per_sec, 3500.0 / 3.0);
+        assert_eq!(stats.server("c"), TransferTotals::default());
+    }
+}
@@ -0,0 +1,69 @@
+// Shutting down
+//
+// Exiting used to drop every socket mid-stream, and servers kept the user
+// listed until their own timeout noticed. The exit is now held until running
+// transfers get a short grace period to finish (whatever is left is paused,
+// so downloads resume next launch) and every server was disconnected. The
+// user can cut the wait short from the window that shows it.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// How long running transfers may hold up quitting
+pub const TRANSFER_GRACE: Duration = Duration::from_secs(30);
+
+#[derive(Default)]
+pub struct Shutdown {
+    started: AtomicBool,
+    finished: AtomicBool,
+    skip_wait: AtomicBool,
+}
+
+impl Shutdown {
+    /// True for the first caller only, who then runs the shutdown
+    pub fn begin(&self) -> bool {
+        !self.started.swap(true, Ordering::SeqCst)
+    }
+
+    /// Once started, queued transfers stay queued
+    pub fn is_started(&self) -> bool {
+        self.started.load(Ordering::SeqCst)
+    }
+
+    pub fn finish(&self) {
+        self.finished.store(true, Ordering::SeqCst);
+    }
+
+    /// The app may exit now
+    pub fn is_finished(&self) -> bool {
+        self.finished.load(Ordering::SeqCst)
+    }
+
+    /// Stop waiting for transfers; the user chose to quit now
+    pub fn skip_wait(&self) {
+        self.skip_wait.store(true, Ordering::SeqCst);
+    }
+
+    pub fn waits_for_transfers(&self) -> bool {
+        !self.skip_wait.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_first_exit_request_runs_the_shutdown() {
+        let shutdown = Shutdown::default();
+        assert!(!shutdown.is_started());
+        assert!(shutdown.begin());
+        assert!(!shutdown.begin());
+        assert!(shutdown.is_started() && !shutdown.is_finished());
+
+        shutdown.skip_wait();
+        assert!(!shutdown.waits_for_transfers());
+        shutdown.finish();
+        assert!(shutdown.is_finished());
+    }
+}
@@ -0,0 +1,131 @@
+// Browsing every tracker at once
+//
+// Queries all tracker bookmarks concurrently, each with its own timeout so
+// one dead tracker can't hold up the rest, then merges the listings into one
+// list. Servers listed by several trackers appear once, tagged with every
+// tracker that lists them.
+
+use crate::protocol::cancel::CancellationToken;
+use crate::protocol::tracker::TrackerClient;
+use crate::protocol::types::{Bookmark, TrackerServer};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::task::JoinSet;
+
+/// How long one tracker gets before it's reported as failed
+const TRACKER_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BrowsedServer {
+    #[serde(flatten)]
+    pub server: TrackerServer,
+    /// Bookmark IDs of the trackers listing this server
+    pub trackers: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrackerFailure {
+    pub tracker_id: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrackerBrowse {
+    pub servers: Vec<BrowsedServer>,
+    pub failures: Vec<TrackerFailure>,
+    /// Index into `servers` by lowercased address and port
+    #[serde(skip)]
+    seen: HashMap<(String, u16), usize>,
+}
+
+/// One tracker's answer, in the order the trackers were given
+pub struct TrackerFetch {
+    pub tracker: Bookmark,
+    pub port: Option<u16>,
+    pub result: Result<Vec<TrackerServer>, String>,
+}
+
+/// Fetch every tracker concurrently
+pub async fn fetch_all(trackers: Vec<Bookmark>, cancel: &CancellationToken) -> Vec<TrackerFetch> {
+    let mut fetches = JoinSet::new();
+    for (index, tracker) in trackers.into_iter().enumerate() {
+        let cancel = cancel.clone();
+        fetches.spawn(async move {
+            // Port 0 means the bookmark leaves it to the default
+            let port = Some(tracker.port).filter(|port| *port != 0);
+            let result = tokio::time::timeout(TRACKER_TIMEOUT, TrackerClient::fetch_servers(&tracker.address, port, &cancel))
+                .await
+                .unwrap_or_else(|_| Err(format!("Tracker {} did not answer in time", tracker.address)));
+            (index, TrackerFetch { tracker, port, result })
+        });
+    }
+
+    let mut results = Vec::with_capacity(fetches.len());
+    while let Some(joined) = fetches.join_next().await {
+        match joined {
+            Ok(fetch) => results.push(fetch),
+            Err(e) => eprintln!("Tracker fetch task failed: {}", e),
+        }
+    }
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, fetch)| fetch).collect()
+}
+
+impl TrackerBrowse {
+    /// Add one tracker's listing. A server already listed keeps its first
+    /// name and description but takes the highest user count reported.
+    pub fn add(&mut self, tracker_id: &str, servers: Vec<TrackerServer>) {
+        for server in servers {
+            let key = (server.address.to_ascii_lowercase(), server.port);
+            match self.seen.get(&key) {
+                Some(&index) => {
+                    let existing = &mut self.servers[index];
+                    existing.server.users = existing.server.users.max(server.users);
+                    if !existing.trackers.iter().any(|id| id == tracker_id) {
+                        existing.trackers.push(tracker_id.to_string());
+                    }
+                }
+                None => {
+                    self.seen.insert(key, self.servers.len());
+                    self.servers.push(BrowsedServer {
+                        server,
+                        trackers: vec![tracker_id.to_string()],
+                    });
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server(address: &str, port: u16, users: u16) -> TrackerServer {
+        TrackerServer {
+            address: address.to_string(),
+            port,
+            users,
+            name: Some(format!("{} server", address)),
+            description: None,
+            user_limit: None,
+        }
+    }
+
+    #[test]
+    fn merges_duplicates_and_tags_every_source() {
+        let mut browse = TrackerBrowse::default();
+        browse.add("t1", vec![server("a.example", 5500, 3), server("b.example", 5500, 1)]);
+        browse.add("t2", vec![server("A.example", 5500, 7), server("a.example", 5600, 0)]);
+
+        assert_eq!(browse.servers.len(), 3);
+        assert_eq!(browse.servers[0].trackers, ["t1", "t2"]);
+        assert_eq!(browse.servers[0].server.users, 7);
+        assert_eq!(browse.servers[0].server.name.as_deref(), Some("a.example server"));
+        assert_eq!(browse.servers[2].trackers, ["t2"]);
+    }
+}
@@ -0,0 +1,224 @@
+// Tracker listings
+//
+// The last listing fetched from each tracker, kept for the session so
+// expanding a tracker can show it at once while a fresh copy downloads.
+// Listings older than TRACKER_TTL_SECS are marked stale; refreshing reports
+// which servers appeared, disappeared or changed their user count. Searches
+// run here too, so the webview only receives the servers that match.
+
+use crate::protocol::types::TrackerServer;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::conversations::now_secs;
+
+/// Age after which a listing should be refreshed before it's trusted
+const TRACKER_TTL_SECS: u64 = 300;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrackerListing {
+    pub servers: Vec<TrackerServer>,
+    pub fetched_at: u64,
+    pub stale: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserCountChange {
+    pub address: String,
+    pub port: u16,
+    pub name: Option<String>,
+    pub before: u16,
+    pub after: u16,
+}
+
+/// What a refresh changed, along with the new listing
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrackerDiff {
+    pub servers: Vec<TrackerServer>,
+    pub fetched_at: u64,
+    pub added: Vec<TrackerServer>,
+    pub removed: Vec<TrackerServer>,
+    pub changed: Vec<UserCountChange>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TrackerSort {
+    /// Busiest first
+    #[default]
+    Users,
+    Name,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrackerSearchResult {
+    #[serde(flatten)]
+    pub server: TrackerServer,
+    /// The tracker that listed this server
+    pub tracker_address: String,
+    pub tracker_port: Option<u16>,
+}
+
+struct CachedListing {
+    servers: Vec<TrackerServer>,
+    fetched_at: u64,
+}
+
+#[derive(Default)]
+pub struct TrackerCache {
+    listings: HashMap<(String, Option<u16>), CachedListing>,
+}
+
+impl TrackerCache {
+    pub fn get(&self, address: &str, port: Option<u16>) -> Option<TrackerListing> {
+        let listing = self.listings.get(&(address.to_string(), port))?;
+        Some(TrackerListing {
+            servers: listing.servers.clone(),
+            fetched_at: listing.fetched_at,
+            stale: now_secs().saturating_sub(listing.fetched_at) >= TRACKER_TTL_SECS,
+        })
+    }
+
+    /// Store a fresh listing and report how it differs from the previous one.
+    /// The first listing from a tracker counts every server as added.
+    pub fn record(&mut self, address: &str, port: Option<u16>, servers: Vec<TrackerServer>) -> TrackerDiff {
+        let fetched_at = now_secs();
+        let previous = self.listings.insert(
+            (address.to_string(), port),
+            CachedListing {
+                servers: servers.clone(),
+                fetched_at,
+            },
+        );
+        let (added, removed, changed) = diff(previous.map(|p| p.servers).as_deref().unwrap_or_default(), &servers);
+        TrackerDiff {
+            servers,
+            fetched_at,
+            added,
+            removed,
+            changed,
+        }
+    }
+
+    /// Cached servers from every tracker whose name or description contains
+    /// `query` (ignoring case) and that have at least `min_users` users. A
+    /// server listed by two trackers is returned once for each.
+    pub fn search(&self, query: &str, min_users: u16, sort: TrackerSort) -> Vec<TrackerSearchResult> {
+        let query = query.trim().to_lowercase();
+        let contains = |text: &Option<String>| text.as_deref().is_some_and(|text| text.to_lowercase().contains(&query));
+
+        let mut results: Vec<TrackerSearchResult> = self
+            .listings
+            .iter()
+            .flat_map(|((address, port), listing)| {
+                listing.servers.iter().map(move |server| TrackerSearchResult {
+                    server: server.clone(),
+                    tracker_address: address.clone(),
+                    tracker_port: *port,
+                })
+            })
+            .filter(|result| result.server.users >= min_users)
+            .filter(|result| query.is_empty() || contains(&result.server.name) || contains(&result.server.description))
+            .collect();
+
+        let name = |result: &TrackerSearchResult| result.server.name.as_deref().unwrap_or(&result.server.address).to_lowercase();
+        match sort {
+            TrackerSort::Users => results.sort_by(|a, b| b.server.users.cmp(&a.server.users).then_with(|| name(a).cmp(&name(b)))),
+            TrackerSort::Name => results.sort_by(|a, b| name(a).cmp(&name(b)).then_with(|| b.server.users.cmp(&a.server.users))),
+        }
+        results
+    }
+}
+
+/// Servers are the same listing entry when address and port match
+fn diff(old: &[TrackerServer], new: &[TrackerServer]) -> (Vec<TrackerServer>, Vec<TrackerServer>, Vec<UserCountChange>) {
+    let key = |server: &TrackerServer| (server.address.clone(), server.port);
+    let old_by_key: HashMap<_, _> = old.iter().map(|server| (key(server), server)).collect();
+    let new_by_key: HashMap<_, _> = new.iter().map(|server| (key(server), server)).collect();
+
+    let mut added = Vec::new();
+    let mut changed = Vec::new();
+    for server in new {
+        match old_by_key.get(&key(server)) {
+            None => added.push(server.clone()),
+            Some(before) if before.users != server.users => changed.push(UserCountChange {
+                address: server.address.clone(),
+                port: server.port,
+                name: server.name.clone(),
+                before: before.users,
+                after: server.users,
+            }),
+            Some(_) => {}
+        }
+    }
+    let removed = old
+        .iter()
+        .filter(|server| !new_by_key.contains_key(&key(server)))
+        .cloned()
+        .collect();
+
+    (added, removed, changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server(address: &str, users: u16) -> TrackerServer {
+        TrackerServer {
+            address: address.to_string(),
+            port: 5500,
+            users,
+            name: Some(address.to_string()),
+            description: None,
+            user_limit: None,
+        }
+    }
+
+    #[test]
+    fn refresh_reports_added_removed_and_user_count_changes() {
+        let mut cache = TrackerCache::default();
+        let first = cache.record("tracker", None, vec![server("a", 1), server("b", 2)]);
+        assert_eq!(first.added.len(), 2);
+
+        let second = cache.record("tracker", None, vec![server("b", 5), server("c", 0)]);
+        assert_eq!(second.added.iter().map(|s| s.address.as_str()).collect::<Vec<_>>(), ["c"]);
+        assert_eq!(second.removed.iter().map(|s| s.address.as_str()).collect::<Vec<_>>(), ["a"]);
+        assert_eq!(second.changed.len(), 1);
+        assert_eq!((second.changed[0].before, second.changed[0].after), (2, 5));
+    }
+
+    #[test]
+    fn search_filters_by_text_and_users_then_sorts() {
+        let mut cache = TrackerCache::default();
+        let mut retro = server("retro", 4);
+        retro.description = Some("Classic Mac FILES".to_string());
+        cache.record("one", None, vec![server("Mac Attack", 2), retro, server("macdrop", 9)]);
+        cache.record("two", Some(5498), vec![server("elsewhere", 20)]);
+
+        let by_users = cache.search(" mac ", 0, TrackerSort::Users);
+        let names: Vec<_> = by_users.iter().map(|r| r.server.address.as_str()).collect();
+        assert_eq!(names, ["macdrop", "retro", "Mac Attack"]);
+
+        let by_name = cache.search("MAC", 3, TrackerSort::Name);
+        let names: Vec<_> = by_name.iter().map(|r| r.server.address.as_str()).collect();
+        assert_eq!(names, ["macdrop", "retro"]);
+        assert_eq!(by_name[0].tracker_address, "one");
+
+        assert_eq!(cache.search("", 10, TrackerSort::Users).len(), 1);
+    }
+
+    #[test]
+    fn listings_are_kept_per_tracker_and_port() {
+        let mut cache = TrackerCache::default();
+        cache.record("tracker", None, vec![server("a", 1)]);
+        assert!(cache.get("tracker", Some(5499)).is_none());
+        let listing = cache.get("tracker", None).unwrap();
+        assert_eq!(listing.servers.len(), 1);
+        assert!(!listing.stale);
+    }
+}
@@ -0,0 +1,212 @@
+// Transfer history
+//
+// Every upload and download that finished or failed, across sessions:
+// server, remote path, size, how long it took and how fast it went, where it
+// was saved and its SHA-256 when known. Transfers that were paused or
+// cancelled are left out. Appended a line at a time to
+// transfer_history.jsonl in the app data directory; the newest records are
+// kept and the file is rewritten once it holds twice as many lines.
+
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use super::conversations::now_secs;
+use super::metrics::TransferDirection;
+
+const MAX_RECORDS: usize = 5000;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferRecord {
+    /// When the transfer finished
+    pub timestamp: u64,
+    pub server_id: String,
+    pub direction: TransferDirection,
+    /// Folder on the server
+    pub path: Vec<String>,
+    pub file_name: String,
+    /// Bytes moved, including what a failed transfer moved before it stopped
+    pub size: u64,
+    pub duration_secs: f64,
+    pub average_bytes_per_sec: f64,
+    /// Where a download was saved
+    pub local_path: Option<PathBuf>,
+    pub sha256: Option<String>,
+    /// None when the transfer succeeded
+    pub error: Option<String>,
+}
+
+impl TransferRecord {
+    pub fn new(server_id: &str, direction: TransferDirection, path: &[String], file_name: &str) -> Self {
+        Self {
+            timestamp: 0,
+            server_id: server_id.to_string(),
+            direction,
+            path: path.to_vec(),
+            file_name: file_name.to_string(),
+            size: 0,
+            duration_secs: 0.0,
+            average_bytes_per_sec: 0.0,
+            local_path: None,
+            sha256: None,
+            error: None,
+        }
+    }
+
+    /// Stamp the record with how the transfer ended
+    pub fn finish(&mut self, elapsed: Duration, error: Option<&str>) {
+        self.timestamp = now_secs();
+        self.duration_secs = elapsed.as_secs_f64();
+        self.average_bytes_per_sec = if self.duration_secs > 0.0 { self.size as f64 / self.duration_secs } else { 0.0 };
+        self.error = error.map(str::to_string);
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TransferHistoryFilter {
+    pub server_id: Option<String>,
+    pub direction: Option<TransferDirection>,
+    /// Only transfers that succeeded (true) or failed (false)
+    pub succeeded: Option<bool>,
+    /// Only transfers that finished at or after this time
+    pub since: Option<u64>,
+    /// Matched against the file name and remote path, ignoring case
+    pub query: Option<String>,
+    pub limit: Option<usize>,
+}
+
+impl TransferHistoryFilter {
+    fn matches(&self, record: &TransferRecord) -> bool {
+        let query = self.query.as_deref().map(str::to_lowercase).unwrap_or_default();
+        self.server_id.as_ref().is_none_or(|id| *id == record.server_id)
+            && self.direction.is_none_or(|direction| direction == record.direction)
+            && self.succeeded.is_none_or(|succeeded| succeeded == record.error.is_none())
+            && self.since.is_none_or(|since| record.timestamp >= since)
+            && (query.is_empty()
+                || record.file_name.to_lowercase().contains(&query)
+                || record.path.iter().any(|segment| segment.to_lowercase().contains(&query)))
+    }
+}
+
+pub struct TransferHistory {
+    path: PathBuf,
+    records: Vec<TransferRecord>,
+    /// Lines in the file, retained or not
+    file_lines: usize,
+}
+
+impl TransferHistory {
+    pub fn load(path: PathBuf) -> Self {
+        let data = if path.exists() {
+            fs::read_to_string(&path).unwrap_or_else(|e| {
+                eprintln!("Failed to load transfer history: {}", e);
+                String::new()
+            })
+        } else {
+            String::new()
+        };
+
+        let mut records = Vec::new();
+        let mut file_lines = 0;
+        for line in data.lines().filter(|line| !line.is_empty()) {
+            file_lines += 1;
+            match serde_json::from_str(line) {
+                Ok(record) => records.push(record),
+                Err(e) => eprintln!("Skipping unreadable line in transfer history: {}", e),
+            }
+        }
+        if records.len() > MAX_RECORDS {
+            records.drain(..records.len() - MAX_RECORDS);
+        }
+
+        Self { path, records, file_lines }
+    }
+
+    pub fn record(&mut self, record: TransferRecord) -> Result<(), String> {
+        let line = serde_json::to_string(&record)
+            .map_err(|e| format!("Failed to serialize transfer history: {}", e))?;
+        self.records.push(record);
+        if self.records.len() > MAX_RECORDS {
+            self.records.remove(0);
+        }
+
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut file| writeln!(file, "{}", line))
+            .map_err(|e| format!("Failed to write transfer history: {}", e))?;
+        self.file_lines += 1;
+        if self.file_lines > 2 * MAX_RECORDS {
+            self.rewrite()?;
+        }
+        Ok(())
+    }
+
+    /// Newest first
+    pub fn query(&self, filter: &TransferHistoryFilter) -> Vec<TransferRecord> {
+        self.records
+            .iter()
+            .rev()
+            .filter(|record| filter.matches(record))
+            .take(filter.limit.unwrap_or(MAX_RECORDS))
+            .cloned()
+            .collect()
+    }
+
+    pub fn clear(&mut self) -> Result<(), String> {
+        self.records.clear();
+        self.rewrite()
+    }
+
+    /// Replace the file with the retained records
+    fn rewrite(&mut self) -> Result<(), String> {
+        let mut contents = String::new();
+        for record in &self.records {
+            let line = serde_json::to_string(record)
+                .map_err(|e| format!("Failed to serialize transfer history: {}", e))?;
+            contents.push_str(&line);
+            contents.push('\n');
+        }
+        fs::write(&self.path, contents).map_err(|e| format!("Failed to write transfer history: {}", e))?;
+        self.file_lines = self.records.len();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_survive_reload_and_filter() {
+        let path = std::env::temp_dir().join(format!("transfer-history-test-{}.jsonl", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let mut history = TransferHistory::load(path.clone());
+        let mut download = TransferRecord::new("a", TransferDirection::Download, &["Uploads".to_string()], "Read Me.txt");
+        download.size = 1000;
+        download.finish(Duration::from_secs(4), None);
+        let mut upload = TransferRecord::new("b", TransferDirection::Upload, &[], "photo.jpg");
+        upload.finish(Duration::from_secs(1), Some("Connection reset"));
+        history.record(download.clone()).unwrap();
+        history.record(upload.clone()).unwrap();
+        assert_eq!(download.average_bytes_per_sec, 250.0);
+
+        let history = TransferHistory::load(path.clone());
+        assert_eq!(history.query(&TransferHistoryFilter::default()), vec![upload.clone(), download.clone()]);
+        let failed = TransferHistoryFilter { succeeded: Some(false), ..Default::default() };
+        assert_eq!(history.query(&failed), vec![upload]);
+        let search = TransferHistoryFilter { query: Some("uploads".to_string()), direction: Some(TransferDirection::Download), ..Default::default() };
+        assert_eq!(history.query(&search), vec![download]);
+
+        let mut history = history;
+        history.clear().unwrap();
+        assert!(TransferHistory::load(path.clone()).query(&TransferHistoryFilter::default()).is_empty());
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,44 @@
+// Per-server transfer rate limits
+//
+// Stored in transfer_limits.json in the app data directory and applied to the
+// client whenever a connection to that server is opened. Servers without
+// limits of their own get the ones from the app preferences.
+
+use crate::protocol::client::bandwidth::TransferLimits;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use super::json_store;
+
+pub struct TransferLimitStore {
+    path: PathBuf,
+    servers: HashMap<String, TransferLimits>,
+    /// For servers without their own
+    defaults: TransferLimits,
+}
+
+impl TransferLimitStore {
+    pub fn load(path: PathBuf) -> Self {
+        let servers = json_store::load_json_or_default(&path, "transfer limits");
+
+        Self { path, servers, defaults: TransferLimits::default() }
+    }
+
+    pub fn set_defaults(&mut self, defaults: TransferLimits) {
+        self.defaults = defaults;
+    }
+
+    pub fn get(&self, server_id: &str) -> TransferLimits {
+        self.servers.get(server_id).copied().unwrap_or(self.defaults)
+    }
+
+    pub fn set(&mut self, server_id: &str, limits: TransferLimits) -> Result<(), String> {
+        limits.validate()?;
+        if limits == self.defaults {
+            self.servers.remove(server_id);
+        } else {
+            self.servers.insert(server_id.to_string(), limits);
+        }
+
+        json_store::save_json(&self.path, &self.servers, "transfer limits")
+    }
+}
@@ -0,0 +1,213 @@
+// Staging dropped files for upload
+//
+// Paths dropped onto a window are checked before anything joins the transfer
+// queue: each must be a readable regular file that fits in a Hotline
+// transfer. The manifest says what every upload will carry, including a
+// resource fork found in a MacBinary wrapper or a `._name` AppleDouble
+// companion, so the user can confirm first. Only headers are read while
+// staging; file contents are loaded when the set is committed.
+
+use crate::protocol::client::mac_forks::{apple_double_name, mac_binary_layout, parse_apple_double, UploadFile};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Transfer handshakes carry a 32-bit size
+const MAX_UPLOAD_BYTES: u64 = u32::MAX as u64;
+
+/// Where an upload's INFO and resource forks come from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ForkSource {
+    /// Data fork only
+    None,
+    MacBinary,
+    AppleDouble,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StagedUpload {
+    pub path: String,
+    /// Name the file will have on the server
+    pub file_name: String,
+    pub data_bytes: u64,
+    pub resource_bytes: u64,
+    pub fork_source: ForkSource,
+    /// Why the file can't be uploaded; it's left out when the set is committed
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StagingManifest {
+    pub staging_id: String,
+    pub uploads: Vec<StagedUpload>,
+    /// Data and resource bytes of every upload that passed validation
+    pub total_bytes: u64,
+}
+
+impl StagingManifest {
+    /// Check `paths` and describe the uploads they would make. A dropped
+    /// `._name` companion is folded into its data file when both are dropped.
+    pub fn stage(staging_id: String, paths: &[String]) -> Self {
+        let dropped: HashSet<PathBuf> = paths.iter().map(PathBuf::from).collect();
+        let uploads: Vec<StagedUpload> = paths
+            .iter()
+            .map(Path::new)
+            .filter(|path| !is_dropped_companion(path, &dropped))
+            .map(stage_path)
+            .collect();
+        let total_bytes = uploads
+            .iter()
+            .filter(|upload| upload.error.is_none())
+            .map(|upload| upload.data_bytes + upload.resource_bytes)
+            .sum();
+
+        Self {
+            staging_id,
+            uploads,
+            total_bytes,
+        }
+    }
+}
+
+fn file_name_of(path: &Path) -> String {
+    path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default()
+}
+
+fn companion_path(path: &Path) -> PathBuf {
+    path.with_file_name(apple_double_name(&file_name_of(path)))
+}
+
+fn is_dropped_companion(path: &Path, dropped: &HashSet<PathBuf>) -> bool {
+    file_name_of(path)
+        .strip_prefix("._")
+        .is_some_and(|data_name| dropped.contains(&path.with_file_name(data_name)))
+}
+
+fn stage_path(path: &Path) -> StagedUpload {
+    let mut upload = StagedUpload {
+        path: path.to_string_lossy().to_string(),
+        file_name: file_name_of(path),
+        data_bytes: 0,
+        resource_bytes: 0,
+        fork_source: ForkSource::None,
+        error: None,
+    };
+    if let Err(e) = inspect(path, &mut upload) {
+        upload.error = Some(e);
+    }
+    upload
+}
+
+fn inspect(path: &Path, upload: &mut StagedUpload) -> Result<(), String> {
+    let metadata = fs::metadata(path).map_err(|e| format!("Can't read {}: {}", upload.file_name, e))?;
+    if metadata.is_dir() {
+        return Err("Folders can't be uploaded from a drop".to_string());
+    }
+    if !metadata.is_file() {
+        return Err("Not a regular file".to_string());
+    }
+    upload.data_bytes = metadata.len();
+
+    let mut header = Vec::with_capacity(128);
+    File::open(path)
+        .and_then(|file| file.take(128).read_to_end(&mut header))
+        .map_err(|e| format!("Can't read {}: {}", upload.file_name, e))?;
+
+    if let Some(layout) = mac_binary_layout(&header, metadata.len()) {
+        upload.file_name = layout.name;
+        upload.data_bytes = layout.data_len;
+        upload.resource_bytes = layout.resource_len;
+        upload.fork_source = ForkSource::MacBinary;
+    } else if let Ok(companion) = fs::read(companion_path(path)) {
+        let (_, resource) = parse_apple_double(&companion).map_err(|e| format!("Bad AppleDouble companion: {}", e))?;
+        upload.resource_bytes = resource.len() as u64;
+        upload.fork_source = ForkSource::AppleDouble;
+    }
+
+    if upload.data_bytes + upload.resource_bytes > MAX_UPLOAD_BYTES {
+        return Err(format!("Too large to upload ({} bytes, limit {})", upload.data_bytes + upload.resource_bytes, MAX_UPLOAD_BYTES));
+    }
+    Ok(())
+}
+
+impl StagedUpload {
+    /// Read the file (and companion) staged earlier into an upload
+    pub fn load(&self) -> Result<UploadFile, String> {
+        let path = Path::new(&self.path);
+        let data = fs::read(path).map_err(|e| format!("Failed to read {}: {}", self.path, e))?;
+        match self.fork_source {
+            ForkSource::None => Ok(UploadFile::new(self.file_name.clone(), data)),
+            ForkSource::MacBinary => UploadFile::from_local(file_name_of(path), data, true, None),
+            ForkSource::AppleDouble => {
+                let companion = fs::read(companion_path(path))
+                    .map_err(|e| format!("Failed to read AppleDouble companion of {}: {}", self.path, e))?;
+                UploadFile::from_local(self.file_name.clone(), data, false, Some(&companion))
+            }
+        }
+    }
+}
+
+/// Manifests waiting for the user to confirm or discard them
+#[derive(Default)]
+pub struct UploadStaging {
+    staged: HashMap<String, StagingManifest>,
+}
+
+impl UploadStaging {
+    pub fn insert(&mut self, manifest: StagingManifest) {
+        self.staged.insert(manifest.staging_id.clone(), manifest);
+    }
+
+    pub fn take(&mut self, staging_id: &str) -> Option<StagingManifest> {
+        self.staged.remove(staging_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::client::mac_forks::{apple_double, mac_binary_header, mac_binary_padding, MacMetadata};
+
+    #[test]
+    fn stages_plain_mac_binary_and_apple_double_files() {
+        let dir = std::env::temp_dir().join(format!("upload-staging-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let meta = MacMetadata::default();
+
+        let plain = dir.join("notes.txt");
+        fs::write(&plain, b"hello").unwrap();
+
+        let mut bin = mac_binary_header("Game", &meta, 3, 2).to_vec();
+        bin.extend_from_slice(b"abc");
+        bin.extend(vec![0; mac_binary_padding(3)]);
+        bin.extend_from_slice(b"rs");
+        let bin_path = dir.join("Game.bin");
+        fs::write(&bin_path, &bin).unwrap();
+
+        let forked = dir.join("Icon");
+        fs::write(&forked, b"data").unwrap();
+        fs::write(dir.join("._Icon"), apple_double(&meta, b"resource")).unwrap();
+
+        let paths: Vec<String> = [&plain, &bin_path, &forked, &dir.join("._Icon"), &dir]
+            .iter()
+            .map(|p| p.to_string_lossy().to_string())
+            .collect();
+        let manifest = StagingManifest::stage("s1".to_string(), &paths);
+        fs::remove_dir_all(&dir).unwrap();
+
+        // The companion rides along with its data file; the folder is refused
+        assert_eq!(manifest.uploads.len(), 4);
+        assert_eq!(manifest.uploads[0].fork_source, ForkSource::None);
+        assert_eq!((manifest.uploads[1].file_name.as_str(), manifest.uploads[1].data_bytes), ("Game", 3));
+        assert_eq!(manifest.uploads[1].fork_source, ForkSource::MacBinary);
+        assert_eq!(manifest.uploads[2].fork_source, ForkSource::AppleDouble);
+        assert_eq!(manifest.uploads[2].resource_bytes, 8);
+        assert!(manifest.uploads[3].error.is_some());
+        assert_eq!(manifest.total_bytes, 5 + 3 + 2 + 4 + 8);
+    }
+}
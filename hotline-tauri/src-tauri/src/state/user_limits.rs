@@ -0,0 +1,81 @@
+// User limits learned from servers
+//
+// A server's user limit is only known once we've connected to it, or been
+// turned away by it. What was learned is kept by address and port in
+// user_limits.json in the app data directory, so tracker listings can show
+// occupancy (14/50) for servers before connecting to them.
+
+use crate::protocol::types::TrackerServer;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use super::json_store;
+
+fn key(address: &str, port: u16) -> String {
+    format!("{}:{}", address.to_lowercase(), port)
+}
+
+pub struct UserLimitStore {
+    path: PathBuf,
+    limits: HashMap<String, u16>,
+}
+
+impl UserLimitStore {
+    pub fn load(path: PathBuf) -> Self {
+        let limits = json_store::load_json_or_default(&path, "user limits");
+
+        Self { path, limits }
+    }
+
+    pub fn get(&self, address: &str, port: u16) -> Option<u16> {
+        self.limits.get(&key(address, port)).copied()
+    }
+
+    /// Remember the limit a server stated. Servers that state none keep the
+    /// last one learned, since most only state it when they refuse a login.
+    pub fn record(&mut self, address: &str, port: u16, limit: Option<u16>) -> Result<(), String> {
+        let Some(limit) = limit else {
+            return Ok(());
+        };
+        if self.limits.insert(key(address, port), limit) == Some(limit) {
+            return Ok(());
+        }
+
+        json_store::save_json(&self.path, &self.limits, "user limits")
+    }
+
+    /// Fill in the limits known for tracker-listed servers
+    pub fn annotate(&self, servers: &mut [TrackerServer]) {
+        for server in servers {
+            server.user_limit = self.get(&server.address, server.port);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn limits_are_kept_until_a_new_one_is_stated() {
+        let path = std::env::temp_dir().join("hotline-user-limits-test.json");
+        let _ = fs::remove_file(&path);
+        let mut store = UserLimitStore::load(path.clone());
+        store.record("Hotline.Example.com", 5500, Some(50)).unwrap();
+        store.record("hotline.example.com", 5500, None).unwrap();
+        assert_eq!(store.get("hotline.example.com", 5500), Some(50));
+        assert_eq!(store.get("hotline.example.com", 5600), None);
+
+        let mut servers = vec![TrackerServer {
+            address: "hotline.example.com".to_string(),
+            port: 5500,
+            users: 14,
+            name: None,
+            description: None,
+            user_limit: None,
+        }];
+        UserLimitStore::load(path.clone()).annotate(&mut servers);
+        assert_eq!(servers[0].user_limit, Some(50));
+        let _ = fs::remove_file(&path);
+    }
+}
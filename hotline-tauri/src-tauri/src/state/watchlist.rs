@@ -0,0 +1,88 @@
+// Roster watch list
+//
+// Per-server list of nicknames the user wants to hear about when they come
+// online. Stored in watch_list.json in the app data directory.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use super::json_store;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchPreferences {
+    #[serde(default)]
+    pub nicknames: Vec<String>,
+    /// Also raise an OS notification, not just the in-app event
+    #[serde(default)]
+    pub os_notification: bool,
+}
+
+impl WatchPreferences {
+    pub fn is_watched(&self, user_name: &str) -> bool {
+        let user_name = user_name.trim();
+        !user_name.is_empty()
+            && self
+                .nicknames
+                .iter()
+                .any(|nick| nick.trim().eq_ignore_ascii_case(user_name))
+    }
+
+    /// Whether a user change means a watched nickname just came online:
+    /// a user who wasn't online (`previous_name` is None), or one switching
+    /// to a watched nickname
+    pub fn came_online(&self, user_name: &str, previous_name: Option<&str>) -> bool {
+        self.is_watched(user_name) && previous_name.is_none_or(|name| !self.is_watched(name))
+    }
+}
+
+pub struct WatchList {
+    path: PathBuf,
+    servers: HashMap<String, WatchPreferences>,
+}
+
+impl WatchList {
+    pub fn load(path: PathBuf) -> Self {
+        let servers = json_store::load_json_or_default(&path, "watch list");
+
+        Self { path, servers }
+    }
+
+    pub fn get(&self, server_id: &str) -> WatchPreferences {
+        self.servers.get(server_id).cloned().unwrap_or_default()
+    }
+
+    pub fn set(&mut self, server_id: &str, preferences: WatchPreferences) -> Result<(), String> {
+        if preferences.nicknames.is_empty() {
+            self.servers.remove(server_id);
+        } else {
+            self.servers.insert(server_id.to_string(), preferences);
+        }
+
+        json_store::save_json(&self.path, &self.servers, "watch list")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prefs(nicks: &[&str]) -> WatchPreferences {
+        WatchPreferences {
+            nicknames: nicks.iter().map(|n| n.to_string()).collect(),
+            os_notification: false,
+        }
+    }
+
+    #[test]
+    fn triggers_when_a_watched_nickname_comes_online() {
+        let preferences = prefs(&["Alice"]);
+
+        assert!(preferences.came_online("alice", None));
+        // Icon or status change for the same user shouldn't re-trigger
+        assert!(!preferences.came_online("alice", Some("alice")));
+        assert!(!preferences.came_online("bob", None));
+        // Renaming to a watched nickname counts as coming online
+        assert!(preferences.came_online("alice", Some("unnamed")));
+    }
+}
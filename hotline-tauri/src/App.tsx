@@ -1,18 +1,46 @@
 import { useEffect } from 'react';
 import { invoke } from '@tauri-apps/api/core';
 import { useAppStore } from './stores/appStore';
+import { usePreferencesStore } from './stores/preferencesStore';
 import TrackerWindow from './components/tracker/TrackerWindow';
 import ServerWindow from './components/server/ServerWindow';
 import TabBar from './components/tabs/TabBar';
 import { useDarkMode } from './hooks/useDarkMode';
 import { useKeyboardShortcuts } from './hooks/useKeyboardShortcuts';
 import NotificationContainer from './components/notifications/NotificationContainer';
+import ShutdownBanner from './components/common/ShutdownBanner';
+import type { AppPreferences } from './types';
 
 function App() {
   // Initialize dark mode management
   useDarkMode();
   
   const { tabs, activeTabId, serverInfo, removeTab, addTab, setActiveTab } = useAppStore();
+  const { watchWords, username, userIconId, downloadFolder, skipExistingDownloads, darkMode, timeZone, locale, diagnosticVerbosity } = usePreferencesStore();
+
+  // Mentions are detected in the backend, which needs the watch words
+  useEffect(() => {
+    invoke('set_mention_keywords', { keywords: watchWords }).catch(console.error);
+  }, [watchWords]);
+
+  // The backend falls back on its copy of these when a request leaves them out
+  useEffect(() => {
+    invoke<AppPreferences>('get_preferences')
+      .then((preferences) => invoke('set_preferences', {
+        preferences: {
+          ...preferences,
+          nickname: username,
+          icon: userIconId,
+          downloadDirectory: downloadFolder,
+          skipExistingDownloads,
+          theme: darkMode,
+          timeZone,
+          locale,
+          diagnosticVerbosity,
+        },
+      }))
+      .catch(console.error);
+  }, [username, userIconId, downloadFolder, skipExistingDownloads, darkMode, timeZone, locale, diagnosticVerbosity]);
   
   // Ensure we always have at least one tab
   useEffect(() => {
@@ -123,6 +151,7 @@ function App() {
       
       {/* Notification toasts */}
       <NotificationContainer />
+      <ShutdownBanner />
     </div>
   );
 }
@@ -3,6 +3,9 @@ import Linkify from '../common/Linkify';
 interface BoardTabProps {
   boardPosts: string[];
   loadingBoard: boolean;
+  hasMoreBoard: boolean;
+  loadingMoreBoard: boolean;
+  onLoadMoreBoard: () => void;
   boardMessage: string;
   postingBoard: boolean;
   onBoardMessageChange: (value: string) => void;
@@ -12,6 +15,9 @@ interface BoardTabProps {
 export default function BoardTab({
   boardPosts,
   loadingBoard,
+  hasMoreBoard,
+  loadingMoreBoard,
+  onLoadMoreBoard,
   boardMessage,
   postingBoard,
   onBoardMessageChange,
@@ -21,7 +27,7 @@ export default function BoardTab({
     <div className="flex-1 flex flex-col min-h-0">
       {/* Posts list */}
       <div className="flex-1 overflow-y-auto p-4">
-        {loadingBoard ? (
+        {loadingBoard && boardPosts.length === 0 ? (
           <div className="text-sm text-gray-500 dark:text-gray-400 text-center py-8">
             Loading message board...
           </div>
@@ -46,6 +52,18 @@ export default function BoardTab({
               </div>
               );
             })}
+            {hasMoreBoard && (
+              <div className="flex justify-center">
+                <button
+                  type="button"
+                  onClick={onLoadMoreBoard}
+                  disabled={loadingMoreBoard}
+                  className="px-4 py-2 text-sm bg-gray-100 hover:bg-gray-200 dark:bg-gray-800 dark:hover:bg-gray-700 text-gray-700 dark:text-gray-300 rounded-md border border-gray-200 dark:border-gray-700 disabled:opacity-50"
+                >
+                  {loadingMoreBoard ? 'Loading...' : 'Load more posts'}
+                </button>
+              </div>
+            )}
           </div>
         )}
       </div>
@@ -0,0 +1,40 @@
+import { useEffect, useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+
+interface ShutdownWaiting {
+  transfers: number;
+  graceSecs: number;
+}
+
+// Shown while quitting is held up by running transfers, with a way to quit
+// right away (the transfers are paused and resume next launch)
+export default function ShutdownBanner() {
+  const [waiting, setWaiting] = useState<ShutdownWaiting | null>(null);
+
+  useEffect(() => {
+    const unlisten = listen<ShutdownWaiting>('shutdown-waiting', (event) => {
+      setWaiting(event.payload);
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  if (!waiting) return null;
+
+  return (
+    <div className="fixed bottom-4 left-1/2 -translate-x-1/2 z-50 flex items-center gap-3 px-4 py-2 rounded-lg shadow-lg bg-gray-800 text-white text-sm">
+      <span>
+        Waiting for {waiting.transfers} {waiting.transfers === 1 ? 'transfer' : 'transfers'} to finish before quitting
+        (up to {waiting.graceSecs}s)…
+      </span>
+      <button
+        onClick={() => invoke('quit_now').catch(console.error)}
+        className="px-2 py-1 rounded bg-red-600 hover:bg-red-700 text-white text-xs font-medium"
+      >
+        Quit Now
+      </button>
+    </div>
+  );
+}
@@ -1,5 +1,7 @@
 import { useState, useEffect } from 'react';
+import { invoke } from '@tauri-apps/api/core';
 import { useAppStore } from '../../stores/appStore';
+import { formatTimestamp } from '../../utils/time';
 
 interface FileInfoDialogProps {
   serverId: string;
@@ -9,9 +11,21 @@ interface FileInfoDialogProps {
   creator?: string;
   isFolder: boolean;
   path: string[];
+  onChanged?: () => void;
   onClose: () => void;
 }
 
+interface FileDetails {
+  name: string;
+  typeName: string;
+  creatorName: string;
+  typeCode: string;
+  comment: string;
+  size: number | null;
+  created: number | null;
+  modified: number | null;
+}
+
 function formatBytes(bytes: number): string {
   if (bytes === 0) return '0 B';
   const k = 1024;
@@ -21,14 +35,52 @@ function formatBytes(bytes: number): string {
   return `${(bytes / Math.pow(k, idx)).toFixed(1)} ${sizes[idx]}`;
 }
 
-export default function FileInfoDialog({ serverId, fileName, fileSize, fileType, creator, isFolder, path, onClose }: FileInfoDialogProps) {
+export default function FileInfoDialog({ serverId, fileName, fileSize, fileType, creator, isFolder, path, onChanged, onClose }: FileInfoDialogProps) {
   const [visible, setVisible] = useState(false);
   const serverInfo = useAppStore((s) => s.serverInfo.get(serverId));
+  const [details, setDetails] = useState<FileDetails | null>(null);
+  const [editName, setEditName] = useState(fileName);
+  const [editComment, setEditComment] = useState('');
+  const [saving, setSaving] = useState(false);
+  const [saveError, setSaveError] = useState<string | null>(null);
 
   useEffect(() => {
     requestAnimationFrame(() => setVisible(true));
   }, []);
 
+  useEffect(() => {
+    invoke<FileDetails>('get_file_info', { serverId, path, fileName })
+      .then((info) => {
+        setDetails(info);
+        setEditComment(info.comment);
+      })
+      .catch((error) => console.error('Failed to get file info:', error));
+  }, [serverId, fileName, path]);
+
+  const trimmedName = editName.trim();
+  const nameChanged = trimmedName !== '' && trimmedName !== fileName;
+  const commentChanged = details !== null && editComment !== details.comment;
+
+  const handleSave = async () => {
+    setSaving(true);
+    setSaveError(null);
+    try {
+      await invoke('set_file_info', {
+        serverId,
+        path,
+        fileName,
+        newName: nameChanged ? trimmedName : null,
+        comment: commentChanged ? editComment : null,
+      });
+      onChanged?.();
+      handleClose();
+    } catch (error) {
+      setSaveError(String(error));
+    } finally {
+      setSaving(false);
+    }
+  };
+
   const handleClose = () => {
     setVisible(false);
     setTimeout(onClose, 300);
@@ -51,16 +103,27 @@ export default function FileInfoDialog({ serverId, fileName, fileSize, fileType,
   };
 
   const rows: { label: string; value: string }[] = [
-    { label: 'Name', value: fileName },
-    { label: 'Kind', value: isFolder ? 'Folder' : (fileType || 'File') },
+    { label: 'Kind', value: isFolder ? 'Folder' : (details?.typeName || fileType || 'File') },
   ];
 
+  if (details?.typeCode) {
+    rows.push({ label: 'Type', value: details.typeCode });
+  }
+
   if (!isFolder) {
     rows.push({ label: 'Size', value: `${formatBytes(fileSize)} (${fileSize.toLocaleString()} bytes)` });
   }
 
-  if (creator) {
-    rows.push({ label: 'Creator', value: creator });
+  if (creator || details?.creatorName) {
+    rows.push({ label: 'Creator', value: details?.creatorName || creator || '' });
+  }
+
+  if (details?.created) {
+    rows.push({ label: 'Created', value: formatTimestamp(details.created) });
+  }
+
+  if (details?.modified) {
+    rows.push({ label: 'Modified', value: formatTimestamp(details.modified) });
   }
 
   if (path.length > 0) {
@@ -110,6 +173,17 @@ export default function FileInfoDialog({ serverId, fileName, fileSize, fileType,
         </div>
 
         <div className="px-6 py-4 space-y-3">
+          <div className="flex items-center gap-3">
+            <span className="text-xs font-medium text-gray-500 dark:text-gray-400 w-14 flex-shrink-0 text-right">
+              Name
+            </span>
+            <input
+              type="text"
+              value={editName}
+              onChange={(e) => setEditName(e.target.value)}
+              className="flex-1 px-2 py-1 text-sm border border-gray-300 dark:border-gray-600 rounded-md bg-white dark:bg-gray-800 text-gray-900 dark:text-white focus:outline-none focus:ring-2 focus:ring-blue-500"
+            />
+          </div>
           {rows.map((row) => (
             <div key={row.label} className="flex items-start gap-3">
               <span className="text-xs font-medium text-gray-500 dark:text-gray-400 w-14 flex-shrink-0 pt-0.5 text-right">
@@ -120,6 +194,21 @@ export default function FileInfoDialog({ serverId, fileName, fileSize, fileType,
               </span>
             </div>
           ))}
+          <div className="flex items-start gap-3">
+            <span className="text-xs font-medium text-gray-500 dark:text-gray-400 w-14 flex-shrink-0 pt-1 text-right">
+              Comment
+            </span>
+            <textarea
+              value={editComment}
+              onChange={(e) => setEditComment(e.target.value)}
+              disabled={details === null}
+              rows={3}
+              className="flex-1 px-2 py-1 text-sm border border-gray-300 dark:border-gray-600 rounded-md bg-white dark:bg-gray-800 text-gray-900 dark:text-white focus:outline-none focus:ring-2 focus:ring-blue-500 resize-none disabled:opacity-50"
+            />
+          </div>
+          {saveError && (
+            <p className="text-xs text-red-600 dark:text-red-400">{saveError}</p>
+          )}
         </div>
 
         <div className="flex gap-2 px-6 py-4 bg-gray-50 dark:bg-gray-900/50 rounded-b-lg">
@@ -131,12 +220,22 @@ export default function FileInfoDialog({ serverId, fileName, fileSize, fileType,
               Copy URL
             </button>
           )}
-          <button
-            onClick={handleClose}
-            className="flex-1 px-4 py-2 border border-gray-300 dark:border-gray-600 text-gray-700 dark:text-gray-300 rounded-md text-sm font-medium hover:bg-gray-100 dark:hover:bg-gray-700 transition-colors"
-          >
-            Done
-          </button>
+          {(nameChanged || commentChanged) ? (
+            <button
+              onClick={handleSave}
+              disabled={saving}
+              className="flex-1 px-4 py-2 bg-blue-600 hover:bg-blue-700 disabled:bg-blue-400 text-white rounded-md text-sm font-medium transition-colors"
+            >
+              {saving ? 'Saving...' : 'Save'}
+            </button>
+          ) : (
+            <button
+              onClick={handleClose}
+              className="flex-1 px-4 py-2 border border-gray-300 dark:border-gray-600 text-gray-700 dark:text-gray-300 rounded-md text-sm font-medium hover:bg-gray-100 dark:hover:bg-gray-700 transition-colors"
+            >
+              Done
+            </button>
+          )}
         </div>
       </div>
     </div>
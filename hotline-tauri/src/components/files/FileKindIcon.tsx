@@ -0,0 +1,68 @@
+// Icon for the kind the backend resolved from a file's type/creator codes or extension
+export type FileKind =
+  | 'folder'
+  | 'archive'
+  | 'image'
+  | 'audio'
+  | 'video'
+  | 'text'
+  | 'document'
+  | 'application'
+  | 'file';
+
+const FILE_PATH =
+  'M4 4a2 2 0 012-2h4.586A2 2 0 0112 2.586L15.414 6A2 2 0 0116 7.414V16a2 2 0 01-2 2H6a2 2 0 01-2-2V4z';
+
+const ICONS: Record<FileKind, { className: string; path: string }> = {
+  folder: {
+    className: 'text-yellow-500',
+    path: 'M2 6a2 2 0 012-2h4l2 2h6a2 2 0 012 2v6a2 2 0 01-2 2H4a2 2 0 01-2-2V6z',
+  },
+  archive: {
+    className: 'text-amber-700 dark:text-amber-500',
+    path: 'M4 3a2 2 0 100 4h12a2 2 0 100-4H4zM3 8h14v7a2 2 0 01-2 2H5a2 2 0 01-2-2V8zm5 3a1 1 0 011-1h2a1 1 0 110 2H9a1 1 0 01-1-1z',
+  },
+  image: {
+    className: 'text-green-500',
+    path: 'M4 3a2 2 0 00-2 2v10a2 2 0 002 2h12a2 2 0 002-2V5a2 2 0 00-2-2H4zm12 12H4l4-8 3 6 2-4 3 6z',
+  },
+  audio: {
+    className: 'text-pink-500',
+    path: 'M18 3a1 1 0 00-1.196-.98l-10 2A1 1 0 006 5v9.114A4.369 4.369 0 005 14c-1.657 0-3 .895-3 2s1.343 2 3 2 3-.895 3-2V7.82l8-1.6v5.894A4.37 4.37 0 0015 12c-1.657 0-3 .895-3 2s1.343 2 3 2 3-.895 3-2V3z',
+  },
+  video: {
+    className: 'text-purple-500',
+    path: 'M4 3a2 2 0 00-2 2v10a2 2 0 002 2h12a2 2 0 002-2V5a2 2 0 00-2-2H4zm3 2h6v4H7V5zm8 8v2h1v-2h-1zm-2-2H7v4h6v-4zm2 0h1V9h-1v2zm1-4V5h-1v2h1zM5 5v2H4V5h1zm0 4H4v2h1V9zm-1 4h1v2H4v-2z',
+  },
+  text: {
+    className: 'text-gray-500 dark:text-gray-400',
+    path: 'M4 4a2 2 0 012-2h4.586A2 2 0 0112 2.586L15.414 6A2 2 0 0116 7.414V16a2 2 0 01-2 2H6a2 2 0 01-2-2V4zm2 6a1 1 0 011-1h6a1 1 0 110 2H7a1 1 0 01-1-1zm1 3a1 1 0 100 2h6a1 1 0 100-2H7z',
+  },
+  document: {
+    className: 'text-blue-500',
+    path: FILE_PATH,
+  },
+  application: {
+    className: 'text-indigo-500',
+    path: 'M2 5a2 2 0 012-2h12a2 2 0 012 2v10a2 2 0 01-2 2H4a2 2 0 01-2-2V5zm3.293 1.293a1 1 0 011.414 0l3 3a1 1 0 010 1.414l-3 3a1 1 0 01-1.414-1.414L7.586 10 5.293 7.707a1 1 0 010-1.414zM11 12a1 1 0 100 2h3a1 1 0 100-2h-3z',
+  },
+  file: {
+    className: 'text-gray-400 dark:text-gray-500',
+    path: FILE_PATH,
+  },
+};
+
+interface FileKindIconProps {
+  kind?: FileKind;
+  isFolder: boolean;
+}
+
+export default function FileKindIcon({ kind, isFolder }: FileKindIconProps) {
+  // Listings cached before kinds were resolved only know folder or not
+  const icon = ICONS[kind ?? (isFolder ? 'folder' : 'file')] ?? ICONS.file;
+  return (
+    <svg className={`w-5 h-5 ${icon.className}`} fill="currentColor" viewBox="0 0 20 20">
+      <path fillRule="evenodd" d={icon.path} clipRule="evenodd" />
+    </svg>
+  );
+}
@@ -4,6 +4,8 @@ import { useKeyboardShortcuts } from '../../hooks/useKeyboardShortcuts';
 import { useContextMenu, ContextMenuRenderer, type ContextMenuItem } from '../common/ContextMenu';
 import { useIsMobile } from '../../hooks/useIsMobile';
 import FileInfoDialog from './FileInfoDialog';
+import FileKindIcon, { type FileKind } from './FileKindIcon';
+import { runOperation } from '../../utils/operations';
 
 interface FileItem {
   name: string;
@@ -11,6 +13,7 @@ interface FileItem {
   isFolder: boolean;
   fileType?: string;
   creator?: string;
+  kind?: FileKind;
 }
 
 interface FilesTabProps {
@@ -21,6 +24,7 @@ interface FilesTabProps {
   uploadProgress?: Map<string, number>;
   onPathChange: (path: string[]) => void;
   onDownloadFile: (fileName: string, fileSize: number) => Promise<void>;
+  onDownloadFolder?: (folderName: string, path?: string[]) => Promise<void>;
   onUploadFile?: (file: File) => Promise<void>;
   onRefresh?: () => void;
   getAllCachedFiles?: () => Array<{ file: FileItem; path: string[] }>;
@@ -30,6 +34,10 @@ interface FilesTabProps {
   onCancelNavigation?: () => void;
   canCreateFolder?: boolean;
   onCreateFolder?: (name: string) => Promise<void>;
+  canRename?: (isFolder: boolean) => boolean;
+  canMove?: (isFolder: boolean) => boolean;
+  canDelete?: (isFolder: boolean) => boolean;
+  canMakeAlias?: boolean;
 }
 
 export default function FilesTab({
@@ -39,6 +47,7 @@ export default function FilesTab({
   downloadProgress,
   onPathChange,
   onDownloadFile,
+  onDownloadFolder,
   onUploadFile,
   onRefresh,
   getAllCachedFiles,
@@ -48,6 +57,10 @@ export default function FilesTab({
   onCancelNavigation,
   canCreateFolder = false,
   onCreateFolder,
+  canRename = () => false,
+  canMove = () => false,
+  canDelete = () => false,
+  canMakeAlias = false,
 }: FilesTabProps) {
   const isMobile = useIsMobile();
   const [searchQuery, setSearchQuery] = useState('');
@@ -56,6 +69,45 @@ export default function FilesTab({
   const [searchResults, setSearchResults] = useState<Array<{ file: FileItem; path: string[] }>>([]);
   const fileInputRef = useRef<HTMLInputElement>(null);
   const { contextMenu, showContextMenu, hideContextMenu } = useContextMenu();
+
+  const manageFile = async (command: string, args: Record<string, unknown>) => {
+    try {
+      await invoke(command, { serverId, ...args });
+      onRefresh?.();
+    } catch (error) {
+      alert(`File operation failed: ${error}`);
+    }
+  };
+
+  const handleRename = (file: FileItem, path: string[]) => {
+    const newName = prompt(`Rename "${file.name}" to:`, file.name)?.trim();
+    if (newName && newName !== file.name) {
+      manageFile('rename_file', { path, fileName: file.name, newName });
+    }
+  };
+
+  const handleMove = (file: FileItem, path: string[]) => {
+    const target = prompt(`Move "${file.name}" to folder (e.g. /Uploads/Old):`, '/' + path.join('/'));
+    if (target === null) return;
+    const newPath = target.split('/').map((part) => part.trim()).filter(Boolean);
+    if (newPath.join('/') !== path.join('/')) {
+      manageFile('move_file', { path, fileName: file.name, newPath });
+    }
+  };
+
+  const handleMakeAlias = (file: FileItem, path: string[]) => {
+    const target = prompt(`Create an alias of "${file.name}" in folder (e.g. /Shared):`, '/');
+    if (target === null) return;
+    const aliasPath = target.split('/').map((part) => part.trim()).filter(Boolean);
+    manageFile('make_file_alias', { path, fileName: file.name, aliasPath });
+  };
+
+  const handleDelete = (file: FileItem, path: string[]) => {
+    const what = file.isFolder ? 'folder and everything in it' : 'file';
+    if (confirm(`Delete the ${what} "${file.name}"? This cannot be undone.`)) {
+      manageFile('delete_file', { path, fileName: file.name });
+    }
+  };
   const [fileInfoTarget, setFileInfoTarget] = useState<{ file: FileItem; path: string[] } | null>(null);
   const previewableExtensions = [
     // Images
@@ -184,20 +236,20 @@ export default function FilesTab({
 
     try {
       // Download to a temp file for preview
-      const previewPath = await invoke<string>('download_file', {
+      const previewPath = await runOperation('download_file', {
         serverId,
         path,
         fileName: file.name,
         fileSize: file.size,
       });
 
-      // extract actual path from returned string "Downloaded to: <path>"
-      const actualPath = previewPath.replace(/^Downloaded to:\s*/, '').trim();
+      // extract actual path from returned string "Downloaded to: <path>" or "Already downloaded to: <path>"
+      const actualPath = previewPath.replace(/^(Already d|D)ownloaded to:\s*/, '').trim();
       const kind = previewType(file);
       let result: { src: string | null; text?: string };
       if (kind === 'text') {
         try {
-          const preview = await invoke<{ mime: string; data: string; is_text: boolean }>('read_preview_file', { path: actualPath });
+          const preview = await invoke<{ mime: string; data: string; isText: boolean }>('read_preview_file', { path: actualPath });
           result = { src: null, text: preview.data };
         } catch (err) {
           setPreviewState((prev) => ({
@@ -211,7 +263,7 @@ export default function FilesTab({
         }
       } else {
         try {
-          const preview = await invoke<{ mime: string; data: string; is_text: boolean }>('read_preview_file', { path: actualPath });
+          const preview = await invoke<{ mime: string; data: string; isText: boolean }>('read_preview_file', { path: actualPath });
           const bytes = Uint8Array.from(atob(preview.data), (c) => c.charCodeAt(0));
           const blob = new Blob([bytes], { type: preview.mime });
           const objectUrl = URL.createObjectURL(blob);
@@ -514,11 +566,13 @@ export default function FilesTab({
                         label: 'Download',
                         icon: '⬇️',
                         action: () => {
-                          if (!file.isFolder) {
+                          if (file.isFolder) {
+                            onDownloadFolder?.(file.name, 'path' in item ? (item as { path: string[] }).path : currentPath);
+                          } else {
                             onDownloadFile(file.name, file.size);
                           }
                         },
-                        disabled: file.isFolder,
+                        disabled: file.isFolder && !onDownloadFolder,
                       },
                       { divider: true, label: '', action: () => {} },
                       {
@@ -537,19 +591,28 @@ export default function FilesTab({
                         disabled: !canPreview(file),
                       },
                     ];
+                    const itemPath = 'path' in item ? (item as { path: string[] }).path : currentPath;
+                    const canAlias = canMakeAlias && !file.isFolder;
+                    if (canRename(file.isFolder) || canMove(file.isFolder) || canAlias || canDelete(file.isFolder)) {
+                      items.push({ divider: true, label: '', action: () => {} });
+                    }
+                    if (canRename(file.isFolder)) {
+                      items.push({ label: 'Rename...', icon: '✏️', action: () => handleRename(file, itemPath) });
+                    }
+                    if (canMove(file.isFolder)) {
+                      items.push({ label: 'Move...', icon: '📂', action: () => handleMove(file, itemPath) });
+                    }
+                    if (canAlias) {
+                      items.push({ label: 'Make Alias...', icon: '🔗', action: () => handleMakeAlias(file, itemPath) });
+                    }
+                    if (canDelete(file.isFolder)) {
+                      items.push({ label: 'Delete', icon: '🗑️', action: () => handleDelete(file, itemPath) });
+                    }
                     showContextMenu(e, items);
                   }}
                 >
                   <div className="w-6 h-6 flex items-center justify-center">
-                    {file.isFolder ? (
-                      <svg className="w-5 h-5 text-yellow-500" fill="currentColor" viewBox="0 0 20 20">
-                        <path d="M2 6a2 2 0 012-2h4l2 2h6a2 2 0 012 2v6a2 2 0 01-2 2H4a2 2 0 01-2-2V6z" />
-                      </svg>
-                    ) : (
-                      <svg className="w-5 h-5 text-gray-400 dark:text-gray-500" fill="currentColor" viewBox="0 0 20 20">
-                        <path fillRule="evenodd" d="M4 4a2 2 0 012-2h4.586A2 2 0 0112 2.586L15.414 6A2 2 0 0116 7.414V16a2 2 0 01-2 2H6a2 2 0 01-2-2V4z" clipRule="evenodd" />
-                      </svg>
-                    )}
+                    <FileKindIcon kind={file.kind} isFolder={file.isFolder} />
                   </div>
                   <div className="flex-1 min-w-0">
                     <div className="text-sm font-medium text-gray-900 dark:text-white break-words">
@@ -623,6 +686,7 @@ export default function FilesTab({
           creator={fileInfoTarget.file.creator}
           isFolder={fileInfoTarget.file.isFolder}
           path={fileInfoTarget.path}
+          onChanged={onRefresh}
           onClose={() => setFileInfoTarget(null)}
         />
       )}
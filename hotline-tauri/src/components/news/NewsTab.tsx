@@ -1,5 +1,6 @@
 import { useState } from 'react';
 import Linkify from '../common/Linkify';
+import { formatTimestamp } from '../../utils/time';
 
 interface NewsCategory {
   type: number;
@@ -10,14 +11,19 @@ interface NewsCategory {
 
 interface NewsArticle {
   id: number;
-  parent_id: number;
+  parentId: number;
   flags: number;
   title: string;
   poster: string;
-  date?: string;
+  date?: number; // Unix seconds
   path: string[];
+  depth?: number;
+  replyCount?: number;
 }
 
+/** Deepest reply level that still gets its own indent */
+const MAX_INDENT_DEPTH = 6;
+
 interface NewsTabProps {
   newsPath: string[];
   newsCategories: NewsCategory[];
@@ -46,7 +52,8 @@ interface NewsTabProps {
   onCreateCategory?: (name: string) => Promise<void>;
   onCreateFolder?: (name: string) => Promise<void>;
   onDeleteItem?: (path: string[]) => Promise<void>;
-  onDeleteArticle?: (articleId: number, path: string[]) => Promise<void>;
+  onDeleteArticle?: (articleId: number, path: string[], recursive: boolean) => Promise<void>;
+  onExport?: (format: 'markdown' | 'mbox') => void;
 }
 
 export default function NewsTab({
@@ -78,6 +85,7 @@ export default function NewsTab({
   onCreateFolder,
   onDeleteItem,
   onDeleteArticle,
+  onExport,
 }: NewsTabProps) {
   // On mobile, show article detail view if an article is selected or composer is open
   const showMobileDetail = selectedArticle || showComposer;
@@ -85,6 +93,17 @@ export default function NewsTab({
   const [newItemMode, setNewItemMode] = useState<'category' | 'folder' | null>(null);
   const [newItemName, setNewItemName] = useState('');
 
+  // Replies go with the article; otherwise the server would leave them orphaned
+  const confirmDeleteArticle = async (article: NewsArticle) => {
+    const replies = article.replyCount ?? 0;
+    const what = replies > 0
+      ? `article "${article.title}" and its ${replies} ${replies === 1 ? 'reply' : 'replies'}`
+      : `article "${article.title}"`;
+    if (onDeleteArticle && confirm(`Delete ${what}? This cannot be undone.`)) {
+      await onDeleteArticle(article.id, article.path, replies > 0);
+    }
+  };
+
   return (
     <div className="flex-1 flex md:flex-row flex-col h-full overflow-hidden">
       {/* Left panel: Categories and Articles — hidden on mobile when viewing article */}
@@ -112,10 +131,17 @@ export default function NewsTab({
             </span>
             );
           })}
+          {onExport && newsArticles.length > 0 && (
+            <span className="ml-auto flex items-center gap-2 text-xs text-gray-500 dark:text-gray-400">
+              Export
+              <button onClick={() => onExport('markdown')} className="text-blue-600 dark:text-blue-400 hover:underline">Markdown</button>
+              <button onClick={() => onExport('mbox')} className="text-blue-600 dark:text-blue-400 hover:underline">mbox</button>
+            </span>
+          )}
           {newsPath.length > 0 && (
             <button
               onClick={onNewsBack}
-              className="ml-auto text-sm text-blue-600 dark:text-blue-400 hover:underline"
+              className={`${onExport && newsArticles.length > 0 ? '' : 'ml-auto '}text-sm text-blue-600 dark:text-blue-400 hover:underline`}
             >
               ← Back
             </button>
@@ -175,7 +201,8 @@ export default function NewsTab({
               {newsCategories.map((category, index) => {
                 // Create unique key from category path and name
                 const uniqueKey = `category-${category.path.join('/')}-${category.name}-${index}`;
-                const canDelete = (canDeleteCategory || canDeleteFolder) && onDeleteItem;
+                // Type 2 is a bundle (folder), 3 a category
+                const canDelete = (category.type === 2 ? canDeleteFolder : canDeleteCategory) && onDeleteItem;
                 return (
                 <div key={uniqueKey} className="flex items-center hover:bg-gray-50 dark:hover:bg-gray-800 transition-colors group">
                   <button
@@ -195,7 +222,8 @@ export default function NewsTab({
                     <button
                       onClick={async (e) => {
                         e.stopPropagation();
-                        if (confirm(`Delete "${category.name}"? This cannot be undone.`)) {
+                        const what = category.type === 2 ? 'bundle and everything in it' : 'category and its articles';
+                        if (confirm(`Delete the ${what} "${category.name}"? This cannot be undone.`)) {
                           await onDeleteItem!(category.path);
                         }
                       }}
@@ -248,18 +276,24 @@ export default function NewsTab({
                     className={`flex-1 px-4 py-3 text-left ${
                       selectedArticle?.id === article.id ? 'bg-blue-50 dark:bg-blue-900/20' : ''
                     }`}
+                    style={article.depth ? { paddingLeft: `${1 + Math.min(article.depth, MAX_INDENT_DEPTH)}rem` } : undefined}
                   >
                     <div className="text-sm font-medium text-gray-900 dark:text-gray-100">
                       {article.title}
                     </div>
                     <div className="text-xs text-gray-600 dark:text-gray-400 mt-1">
                       by {article.poster}
-                      {article.date && (
+                      {article.date !== undefined && (
                         <span className="ml-2 text-gray-500 dark:text-gray-500">
-                          • {article.date}
+                          • {formatTimestamp(article.date)}
                         </span>
                       )}
-                      {article.parent_id > 0 && (
+                      {article.replyCount ? (
+                        <span className="ml-2 text-gray-500 dark:text-gray-500">
+                          • {article.replyCount} {article.replyCount === 1 ? 'reply' : 'replies'}
+                        </span>
+                      ) : null}
+                      {article.parentId > 0 && (
                         <span className="ml-2 text-blue-600 dark:text-blue-400">↳ Reply</span>
                       )}
                     </div>
@@ -268,9 +302,7 @@ export default function NewsTab({
                     <button
                       onClick={async (e) => {
                         e.stopPropagation();
-                        if (confirm(`Delete article "${article.title}"? This cannot be undone.`)) {
-                          await onDeleteArticle(article.id, article.path);
-                        }
+                        await confirmDeleteArticle(article);
                       }}
                       className="opacity-0 group-hover:opacity-100 px-2 py-1 mr-2 text-red-500 hover:text-red-700 transition-opacity"
                       title="Delete article"
@@ -318,7 +350,7 @@ export default function NewsTab({
               type="text"
               value={composerTitle}
               onChange={(e) => onComposerTitleChange(e.target.value)}
-              placeholder="Article title..."
+              placeholder={selectedArticle ? `Re: ${selectedArticle.title}` : 'Article title...'}
               className="px-3 py-2 border border-gray-300 dark:border-gray-600 rounded-md bg-white dark:bg-gray-800 text-gray-900 dark:text-white placeholder-gray-400 dark:placeholder-gray-500 focus:outline-none focus:ring-2 focus:ring-blue-500 mb-4"
             />
             <textarea
@@ -330,7 +362,7 @@ export default function NewsTab({
             />
             <button
               type="submit"
-              disabled={!composerTitle.trim() || !composerBody.trim() || postingNews}
+              disabled={(!composerTitle.trim() && !selectedArticle) || !composerBody.trim() || postingNews}
               className="px-4 py-2 bg-blue-600 hover:bg-blue-700 disabled:bg-blue-400 text-white rounded-md font-medium disabled:cursor-not-allowed"
             >
               {postingNews ? 'Posting...' : 'Post'}
@@ -355,9 +387,9 @@ export default function NewsTab({
                   </h2>
                   <div className="text-sm text-gray-600 dark:text-gray-400 mt-1">
                     by {selectedArticle.poster}
-                    {selectedArticle.date && (
+                    {selectedArticle.date !== undefined && (
                       <span className="ml-2 text-gray-500 dark:text-gray-500">
-                        • {selectedArticle.date}
+                        • {formatTimestamp(selectedArticle.date)}
                       </span>
                     )}
                   </div>
@@ -365,9 +397,7 @@ export default function NewsTab({
                 {canDeleteArticle && onDeleteArticle && (
                   <button
                     onClick={async () => {
-                      if (confirm(`Delete article "${selectedArticle.title}"? This cannot be undone.`)) {
-                        await onDeleteArticle(selectedArticle.id, selectedArticle.path);
-                      }
+                      await confirmDeleteArticle(selectedArticle);
                     }}
                     className="flex-shrink-0 p-1 text-red-500 hover:text-red-700"
                     title="Delete article"
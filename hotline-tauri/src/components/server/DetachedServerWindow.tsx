@@ -0,0 +1,32 @@
+import { invoke } from '@tauri-apps/api/core';
+import ServerWindow from './ServerWindow';
+import NotificationContainer from '../notifications/NotificationContainer';
+import ShutdownBanner from '../common/ShutdownBanner';
+import { useDarkMode } from '../../hooks/useDarkMode';
+
+interface DetachedServerWindowProps {
+  serverId: string;
+  serverName: string;
+}
+
+// A server moved out of the tabs into its own window. Closing the window
+// disconnects the server (handled by the backend).
+export default function DetachedServerWindow({ serverId, serverName }: DetachedServerWindowProps) {
+  useDarkMode();
+
+  return (
+    <div className="h-screen flex flex-col bg-white dark:bg-gray-900">
+      <div className="flex-1 overflow-hidden">
+        <ServerWindow
+          serverId={serverId}
+          serverName={serverName}
+          onClose={() => {
+            invoke('close_server_window', { serverId }).catch(console.error);
+          }}
+        />
+      </div>
+      <NotificationContainer />
+      <ShutdownBanner />
+    </div>
+  );
+}
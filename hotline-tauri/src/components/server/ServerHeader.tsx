@@ -1,5 +1,6 @@
 import type { ServerInfo, ConnectionStatus } from '../../types';
 import type { User } from '../server/serverTypes';
+import { formatOccupancy, isFull } from '../../utils/occupancy';
 
 interface ServerHeaderProps {
   serverName: string;
@@ -10,6 +11,8 @@ interface ServerHeaderProps {
   onDisconnect: () => void;
   onShowTransfers?: () => void;
   onShowNotificationLog?: () => void;
+  onShowAccounts?: () => void;
+  onRefreshInfo?: () => void;
 }
 
 export default function ServerHeader({
@@ -21,6 +24,8 @@ export default function ServerHeader({
   onDisconnect,
   onShowTransfers,
   onShowNotificationLog,
+  onShowAccounts,
+  onRefreshInfo,
 }: ServerHeaderProps) {
   return (
     <div className="bg-gray-100 dark:bg-gray-800 border-b border-gray-200 dark:border-gray-700 px-3 py-2 md:px-4 md:py-3">
@@ -40,7 +45,7 @@ export default function ServerHeader({
           <div className="flex items-center gap-1.5 md:gap-2">
             <div className={`w-2 h-2 rounded-full ${
               connectionStatus === 'logged-in' ? 'bg-green-500' :
-              connectionStatus === 'connecting' || connectionStatus === 'logging-in' ? 'bg-yellow-500 animate-pulse' :
+              connectionStatus === 'connecting' || connectionStatus === 'logging-in' || connectionStatus === 'reconnecting' ? 'bg-yellow-500 animate-pulse' :
               connectionStatus === 'connected' ? 'bg-blue-500' :
               connectionStatus === 'failed' ? 'bg-red-500' :
               'bg-gray-400'
@@ -56,6 +61,7 @@ export default function ServerHeader({
               {connectionStatus === 'logged-in' ? 'Logged in' :
                connectionStatus === 'logging-in' ? 'Logging in...' :
                connectionStatus === 'connecting' ? 'Connecting...' :
+               connectionStatus === 'reconnecting' ? 'Reconnecting...' :
                connectionStatus === 'connected' ? 'Connected' :
                connectionStatus === 'failed' ? 'Failed' :
                'Disconnected'}
@@ -63,10 +69,24 @@ export default function ServerHeader({
           </div>
           {serverInfo && (
             <div className="hidden md:flex items-center gap-2 text-sm text-gray-600 dark:text-gray-400">
-              <span className="font-medium">{users.length}</span>
+              <span
+                className={`font-medium ${isFull(users.length, serverInfo.userLimit) ? 'text-red-600 dark:text-red-400' : ''}`}
+                title={serverInfo.userLimit ? `${serverInfo.userLimit} users allowed` : undefined}
+              >
+                {formatOccupancy(users.length, serverInfo.userLimit)}
+              </span>
               <span>user{users.length !== 1 ? 's' : ''}</span>
             </div>
           )}
+          {onRefreshInfo && (
+            <button
+              onClick={onRefreshInfo}
+              className="px-2 py-1 md:px-3 text-sm text-gray-600 hover:text-gray-700 dark:text-gray-400 dark:hover:text-gray-300 rounded hover:bg-gray-100 dark:hover:bg-gray-700"
+              title="Refresh Server Info and Banner"
+            >
+              🔄
+            </button>
+          )}
           {onShowTransfers && (
             <button
               onClick={onShowTransfers}
@@ -76,6 +96,15 @@ export default function ServerHeader({
               📥<span className="hidden md:inline"> Transfers</span>
             </button>
           )}
+          {onShowAccounts && (
+            <button
+              onClick={onShowAccounts}
+              className="px-2 py-1 md:px-3 text-sm text-gray-600 hover:text-gray-700 dark:text-gray-400 dark:hover:text-gray-300 rounded hover:bg-gray-100 dark:hover:bg-gray-700"
+              title="Accounts"
+            >
+              👥<span className="hidden md:inline"> Accounts</span>
+            </button>
+          )}
           {onShowNotificationLog && (
             <button
               onClick={onShowNotificationLog}
@@ -1,5 +1,6 @@
 import UserList from '../users/UserList';
 import type { ViewTab, User } from '../server/serverTypes';
+import type { ServerCapabilities } from '../../types';
 
 interface ServerSidebarProps {
   activeTab: ViewTab;
@@ -9,6 +10,7 @@ interface ServerSidebarProps {
   onUserRightClick?: (user: User, event: React.MouseEvent) => void;
   onOpenMessageDialog: (user: User) => void;
   unreadCounts: Map<number, number>;
+  capabilities?: ServerCapabilities;
 }
 
 export default function ServerSidebar({
@@ -19,6 +21,7 @@ export default function ServerSidebar({
   onUserRightClick,
   onOpenMessageDialog,
   unreadCounts,
+  capabilities,
 }: ServerSidebarProps) {
   return (
     <div className="hidden md:flex w-[200px] bg-gray-50 dark:bg-gray-800 border-r border-gray-200 dark:border-gray-700 flex-col">
@@ -46,12 +49,13 @@ export default function ServerSidebar({
         </button>
         <button
           onClick={() => onTabChange('board')}
-          className={`flex items-center gap-2 px-2 py-2 rounded transition-colors ${
+          disabled={capabilities?.messageBoard === false}
+          className={`flex items-center gap-2 px-2 py-2 rounded transition-colors disabled:opacity-40 disabled:cursor-not-allowed ${
             activeTab === 'board'
               ? 'bg-blue-100 dark:bg-blue-900/30 text-blue-700 dark:text-blue-300'
               : 'text-gray-600 dark:text-gray-400 hover:bg-gray-200 dark:hover:bg-gray-700 hover:text-gray-800 dark:hover:text-gray-200'
           }`}
-          title="Message Board"
+          title={capabilities?.messageBoard === false ? 'Message Board (disabled on this server)' : 'Message Board'}
         >
           <img 
             src="/icons/section-board.png" 
@@ -66,12 +70,13 @@ export default function ServerSidebar({
         </button>
         <button
           onClick={() => onTabChange('news')}
-          className={`flex items-center gap-2 px-2 py-2 rounded transition-colors ${
+          disabled={capabilities?.news === false}
+          className={`flex items-center gap-2 px-2 py-2 rounded transition-colors disabled:opacity-40 disabled:cursor-not-allowed ${
             activeTab === 'news'
               ? 'bg-blue-100 dark:bg-blue-900/30 text-blue-700 dark:text-blue-300'
               : 'text-gray-600 dark:text-gray-400 hover:bg-gray-200 dark:hover:bg-gray-700 hover:text-gray-800 dark:hover:text-gray-200'
           }`}
-          title="News"
+          title={capabilities?.news === false ? 'News (disabled on this server)' : 'News'}
         >
           <img 
             src="/icons/section-news.png" 
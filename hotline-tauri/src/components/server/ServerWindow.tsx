@@ -3,6 +3,7 @@ import { invoke } from '@tauri-apps/api/core';
 import { listen } from '@tauri-apps/api/event';
 import MessageDialog from '../chat/MessageDialog';
 import UserInfoDialog from '../users/UserInfoDialog';
+import AccountsDialog from '../users/AccountsDialog';
 import { useContextMenu, ContextMenuRenderer, type ContextMenuItem } from '../common/ContextMenu';
 import ChatTab from '../chat/ChatTab';
 import BoardTab from '../board/BoardTab';
@@ -15,14 +16,16 @@ import MobileTabBar from './MobileTabBar';
 import TransferList from '../transfers/TransferList';
 import NotificationLog from '../notifications/NotificationLog';
 import Linkify from '../common/Linkify';
-import { ServerInfo, ConnectionStatus } from '../../types';
+import { ServerInfo, ConnectionStatus, UserAccess } from '../../types';
 import { useAppStore } from '../../stores/appStore';
 import { usePreferencesStore } from '../../stores/preferencesStore';
+import { showNotification } from '../../stores/notificationStore';
 import { useKeyboardShortcuts } from '../../hooks/useKeyboardShortcuts';
 import { useServerEvents } from './hooks/useServerEvents';
 import { useServerHandlers } from './hooks/useServerHandlers';
 import { parseUserFlags } from './serverUtils';
-import type { ChatMessage, User, PrivateMessage, FileItem, NewsCategory, NewsArticle, ViewTab } from './serverTypes';
+import { flattenThreads } from '../../utils/newsThreads';
+import type { ChatMessage, User, PrivateMessage, FileItem, FolderCapabilities, NewsCategory, NewsArticle, NewsThread, ViewTab, BoardContinuation, BoardPage, ConnectOutcome } from './serverTypes';
 
 interface ServerWindowProps {
   serverId: string;
@@ -33,7 +36,7 @@ interface ServerWindowProps {
 export default function ServerWindow({ serverId, serverName, onClose }: ServerWindowProps) {
   const { setFileCache, getFileCache, clearFileCache, clearFileCachePath, addTransfer, updateTransfer, updateTabTitle, serverInfo: serverInfoMap, tabs } = useAppStore();
   const isTls = serverInfoMap.get(serverId)?.tls ?? false;
-  const { enablePrivateMessaging, downloadFolder, showServerBanner } = usePreferencesStore();
+  const { enablePrivateMessaging, downloadFolder, forkMode, showServerBanner } = usePreferencesStore();
   const [showTransferList, setShowTransferList] = useState(false);
   const [showNotificationLog, setShowNotificationLog] = useState(false);
   const { contextMenu, showContextMenu, hideContextMenu } = useContextMenu();
@@ -42,16 +45,19 @@ export default function ServerWindow({ serverId, serverName, onClose }: ServerWi
   const [sending, setSending] = useState(false);
   const [messages, setMessages] = useState<ChatMessage[]>([]);
   const [users, setUsers] = useState<User[]>([]);
-  const [userAccess, setUserAccess] = useState<number>(0); // User access permissions (bitmask)
+  const [userAccess, setUserAccess] = useState<UserAccess | null>(null); // Decoded access permissions
   const [files, setFiles] = useState<FileItem[]>([]);
   const currentPathRef = useRef<string[]>([]);
   const [currentPath, setCurrentPath] = useState<string[]>([]);
+  const [folderCapabilities, setFolderCapabilities] = useState<Map<string, FolderCapabilities>>(new Map());
   const [downloadProgress, setDownloadProgress] = useState<Map<string, number>>(new Map());
   const [uploadProgress, setUploadProgress] = useState<Map<string, number>>(new Map());
   const [boardPosts, setBoardPosts] = useState<string[]>([]);
   const [boardMessage, setBoardMessage] = useState('');
   const [postingBoard, setPostingBoard] = useState(false);
   const [loadingBoard, setLoadingBoard] = useState(false);
+  const [boardContinuation, setBoardContinuation] = useState<BoardContinuation | null>(null);
+  const [loadingMoreBoard, setLoadingMoreBoard] = useState(false);
   const [newsCategories, setNewsCategories] = useState<NewsCategory[]>([]);
   const [newsArticles, setNewsArticles] = useState<NewsArticle[]>([]);
   const [newsPath, setNewsPath] = useState<string[]>([]);
@@ -64,6 +70,7 @@ export default function ServerWindow({ serverId, serverName, onClose }: ServerWi
   const [postingNews, setPostingNews] = useState(false);
   const [messageDialogUser, setMessageDialogUser] = useState<User | null>(null);
   const [userInfoDialogUser, setUserInfoDialogUser] = useState<User | null>(null);
+  const [showAccounts, setShowAccounts] = useState(false);
   const [unreadCounts, setUnreadCounts] = useState<Map<number, number>>(new Map());
   const [privateMessageHistory, setPrivateMessageHistory] = useState<Map<number, PrivateMessage[]>>(new Map());
   const [agreementText, setAgreementText] = useState<string | null>(null);
@@ -131,10 +138,10 @@ export default function ServerWindow({ serverId, serverName, onClose }: ServerWi
   useEffect(() => {
     let isActive = true;
     
-    const unlistenPromise = listen<{ access: number }>(`user-access-${serverId}`, (event) => {
+    const unlistenPromise = listen<UserAccess>(`user-access-${serverId}`, (event) => {
       if (!isActive) return;
-      setUserAccess(event.payload.access);
-      console.log('User access permissions received:', '0x' + event.payload.access.toString(16));
+      setUserAccess(event.payload);
+      console.log('User access permissions received:', event.payload);
     });
 
     return () => {
@@ -143,16 +150,36 @@ export default function ServerWindow({ serverId, serverName, onClose }: ServerWi
     };
   }, [serverId]);
 
-  // Helper function to check if user has specific permission
-  const hasPermission = (bitIndex: number): boolean => {
-    // Hotline access bits are indexed from 63 down to 0
-    // bitIndex 22 = canDisconnectUsers (bit 63-22 = 41)
-    const bit = 63 - bitIndex;
-    // Convert to BigInt for 64-bit operations, then back to boolean
-    const accessBigInt = BigInt(userAccess);
-    const bitMask = BigInt(1) << BigInt(bit);
-    return (accessBigInt & bitMask) !== BigInt(0);
-  };
+  // Pick up what the bookmark's connect actions opened. They may finish
+  // before this window is listening, so check on mount as well.
+  useEffect(() => {
+    let isActive = true;
+
+    const takeOutcome = () => {
+      invoke<ConnectOutcome | null>('take_connect_outcome', { serverId })
+        .then((outcome) => {
+          if (!isActive || !outcome) return;
+          if (outcome.folder) {
+            setPendingFilePath(outcome.folder);
+          }
+          if (outcome.board) {
+            setBoardPosts(outcome.board.posts);
+            setBoardContinuation(outcome.board.continuation);
+          }
+        })
+        .catch((error) => console.error('Failed to get connect action results:', error));
+    };
+    takeOutcome();
+    const unlistenPromise = listen(`connect-actions-finished-${serverId}`, takeOutcome);
+
+    return () => {
+      isActive = false;
+      unlistenPromise.then((unlisten) => unlisten()).catch(() => {});
+    };
+  }, [serverId]);
+
+  // Check a decoded permission; nothing is allowed until access arrives
+  const hasPermission = (permission: keyof UserAccess): boolean => userAccess?.[permission] ?? false;
   
   // Update connection status based on users - if we have users, we're logged in
   // Use a ref to track if we've already updated to avoid infinite loops
@@ -242,6 +269,8 @@ export default function ServerWindow({ serverId, serverName, onClose }: ServerWi
     setDownloadProgress,
     setUploadProgress,
     setAgreementText,
+    setServerInfo,
+    setBannerUrl,
     setConnectionStatus,
     setFileCache,
     currentPathRef,
@@ -250,6 +279,9 @@ export default function ServerWindow({ serverId, serverName, onClose }: ServerWi
     addTransfer,
     updateTransfer,
     onFileListReceived: handleFileListReceived,
+    onFolderCapabilities: (path, capabilities) => {
+      setFolderCapabilities((prev) => new Map(prev).set(JSON.stringify(path), capabilities));
+    },
   });
 
   // Keyboard shortcuts
@@ -301,6 +333,25 @@ export default function ServerWindow({ serverId, serverName, onClose }: ServerWi
     currentPathRef.current = currentPath;
   }, [currentPath]);
 
+  // A batch delete or move changed these folders; refetch the one on screen
+  useEffect(() => {
+    const unlistenPromise = listen<{ folders: string[][] }>(`files-changed-${serverId}`, (event) => {
+      const current = currentPathRef.current;
+      for (const folder of event.payload.folders) {
+        clearFileCachePath(serverId, folder);
+        if (folder.length === current.length && folder.every((part, i) => part === current[i])) {
+          invoke('get_file_list', { serverId, path: current }).catch((error) => {
+            console.error('Failed to refresh file list:', error);
+          });
+        }
+      }
+    });
+
+    return () => {
+      unlistenPromise.then((unlisten) => unlisten()).catch(() => {});
+    };
+  }, [serverId, clearFileCachePath]);
+
   useEffect(() => {
     if (activeTab === 'files') {
       // Set loading state immediately to prevent race conditions
@@ -357,14 +408,12 @@ export default function ServerWindow({ serverId, serverName, onClose }: ServerWi
   useEffect(() => {
     if (activeTab === 'board' && !loadingBoard && boardPosts.length === 0) {
       setLoadingBoard(true);
-      invoke<string[]>('get_message_board', {
+      invoke<BoardPage>('get_message_board', {
         serverId,
-      }).then((posts) => {
-        console.log('Received board posts:', posts);
-        posts.forEach((post, i) => {
-          console.log(`Post ${i}:`, JSON.stringify(post));
-        });
-        setBoardPosts(posts);
+      }).then((page) => {
+        console.log(`Received ${page.posts.length} board posts (${page.totalBytes} bytes on the board)`);
+        setBoardPosts(page.posts);
+        setBoardContinuation(page.continuation);
         setLoadingBoard(false);
       }).catch((error) => {
         console.error('Failed to get message board:', error);
@@ -373,6 +422,28 @@ export default function ServerWindow({ serverId, serverName, onClose }: ServerWi
     }
   }, [activeTab, serverId, loadingBoard, boardPosts.length]);
 
+  // Load the next page of a board too big for one fetch. Chunk events append
+  // posts as they're decoded; the page result then replaces them.
+  const handleLoadMoreBoard = async () => {
+    if (!boardContinuation || loadingMoreBoard) return;
+    const loaded = boardPosts;
+    setLoadingMoreBoard(true);
+    try {
+      const page = await invoke<BoardPage>('get_more_message_board', {
+        serverId,
+        continuation: boardContinuation,
+      });
+      setBoardPosts([...loaded, ...page.posts]);
+      setBoardContinuation(page.continuation);
+    } catch (error) {
+      console.error('Failed to load more of the message board:', error);
+      setBoardPosts(loaded);
+      showNotification.error(String(error), 'Message Board', undefined, serverName);
+    } finally {
+      setLoadingMoreBoard(false);
+    }
+  };
+
 
   // Download banner after connection (only once)
   useEffect(() => {
@@ -442,6 +513,15 @@ export default function ServerWindow({ serverId, serverName, onClose }: ServerWi
   }, [serverId, users.length, serverInfo, serverName, updateTabTitle]);
 
 
+  const handleRefreshServerInfo = async () => {
+    try {
+      // Changes arrive as server-info-updated and banner-updated events
+      await invoke('refresh_server_info', { serverId });
+    } catch (error) {
+      showNotification.error(String(error), 'Server Info');
+    }
+  };
+
   const handleUserClick = (user: User) => {
     // Open message dialog (chat) directly
     handleOpenMessageDialog(user);
@@ -470,8 +550,7 @@ export default function ServerWindow({ serverId, serverName, onClose }: ServerWi
     ];
 
     // Add Disconnect option if user has permission
-    // canDisconnectUsers is bit 22 (accessIndexToBit(22) = bit 41)
-    if (hasPermission(22)) {
+    if (hasPermission('disconnectUser')) {
       items.push({ divider: true, label: '', action: () => {} });
       items.push({
         label: 'Disconnect',
@@ -514,6 +593,7 @@ export default function ServerWindow({ serverId, serverName, onClose }: ServerWi
     handleSendMessage,
     handlePostBoard,
     handleDownloadFile,
+    handleDownloadFolder,
     handleUploadFile,
     handleSendPrivateMessage,
     handleAcceptAgreement,
@@ -528,11 +608,13 @@ export default function ServerWindow({ serverId, serverName, onClose }: ServerWi
     serverName,
     currentPath,
     downloadFolder,
+    forkMode,
     setMessage,
     setSending,
     setBoardMessage,
     setPostingBoard,
     setBoardPosts,
+    setBoardContinuation,
     setDownloadProgress,
     setUploadProgress,
     setPrivateMessageHistory,
@@ -548,17 +630,26 @@ export default function ServerWindow({ serverId, serverName, onClose }: ServerWi
   });
 
   // Permission-gated feature flags
-  const canBroadcast = hasPermission(32);
-  const canCreateFolder = hasPermission(5);
-  const canCreateNewsCategories = hasPermission(34);
-  const canDeleteNewsCategories = hasPermission(35);
-  const canCreateNewsFolders = hasPermission(36);
-  const canDeleteNewsFolders = hasPermission(37);
-  const canDeleteNewsArticles = hasPermission(33);
+  const canBroadcast = hasPermission('broadcast');
+  // Uploading and creating folders also depend on the folder; file listings
+  // carry the backend's answer for their path
+  const currentCapabilities = folderCapabilities.get(JSON.stringify(currentPath));
+  const canCreateFolder = currentCapabilities?.canCreateFolder ?? hasPermission('createFolder');
+  const canUpload = currentCapabilities?.canUpload ?? hasPermission('uploadFile');
+  // File and folder management use separate access bits
+  const canRenameFile = (isFolder: boolean) => hasPermission(isFolder ? 'renameFolder' : 'renameFile');
+  const canMoveFile = (isFolder: boolean) => hasPermission(isFolder ? 'moveFolder' : 'moveFile');
+  const canDeleteFile = (isFolder: boolean) => hasPermission(isFolder ? 'deleteFolder' : 'deleteFile');
+  const canMakeAlias = hasPermission('makeAlias');
+  const canCreateNewsCategories = hasPermission('newsCreateCategory');
+  const canDeleteNewsCategories = hasPermission('newsDeleteCategory');
+  const canCreateNewsFolders = hasPermission('newsCreateFolder');
+  const canDeleteNewsFolders = hasPermission('newsDeleteFolder');
+  const canDeleteNewsArticles = hasPermission('newsDeleteArticle');
 
   const handleSendBroadcast = async (msg: string) => {
     try {
-      await invoke('send_broadcast', { serverId, message: msg });
+      await invoke('broadcast_message', { serverId, message: msg });
     } catch (error) {
       console.error('Failed to send broadcast:', error);
     }
@@ -566,7 +657,7 @@ export default function ServerWindow({ serverId, serverName, onClose }: ServerWi
 
   const handleCreateFolder = async (name: string) => {
     try {
-      await invoke('create_folder', { serverId, path: currentPath, name });
+      await invoke('create_remote_folder', { serverId, path: currentPath, name });
       // Refresh file list
       clearFileCachePath(serverId, currentPath);
       invoke('get_file_list', { serverId, path: currentPath }).catch(console.error);
@@ -607,8 +698,8 @@ export default function ServerWindow({ serverId, serverName, onClose }: ServerWi
         const categories = await invoke<NewsCategory[]>('get_news_categories', { serverId, path: newsPath });
         setNewsCategories(categories);
       } else {
-        const articles = await invoke<NewsArticle[]>('get_news_articles', { serverId, path: newsPath });
-        setNewsArticles(articles);
+        const threads = await invoke<NewsThread[]>('get_news_thread_tree', { serverId, path: newsPath });
+        setNewsArticles(flattenThreads(threads));
       }
     } catch (error) {
       console.error('Failed to delete news item:', error);
@@ -616,11 +707,11 @@ export default function ServerWindow({ serverId, serverName, onClose }: ServerWi
     }
   };
 
-  const handleDeleteNewsArticle = async (articleId: number, articlePath: string[]) => {
+  const handleDeleteNewsArticle = async (articleId: number, articlePath: string[], recursive: boolean) => {
     try {
-      await invoke('delete_news_article', { serverId, path: articlePath, articleId, recursive: false });
-      const articles = await invoke<NewsArticle[]>('get_news_articles', { serverId, path: newsPath });
-      setNewsArticles(articles);
+      await invoke('delete_news_article', { serverId, path: articlePath, articleId, recursive });
+      const threads = await invoke<NewsThread[]>('get_news_thread_tree', { serverId, path: newsPath });
+      setNewsArticles(flattenThreads(threads));
       setSelectedArticle(null);
       setArticleContent('');
     } catch (error) {
@@ -629,6 +720,16 @@ export default function ServerWindow({ serverId, serverName, onClose }: ServerWi
     }
   };
 
+  const handleExportNews = async (format: 'markdown' | 'mbox') => {
+    try {
+      const savedPath = await invoke<string>('export_news', { serverId, path: newsPath, format });
+      alert(`News saved to ${savedPath}`);
+    } catch (error) {
+      console.error('Failed to export news:', error);
+      alert(`Failed to export news: ${error}`);
+    }
+  };
+
   // Wrapper functions for handlers that need additional state
   const handleSendMessageWrapper = (e: React.FormEvent) => {
     e.preventDefault();
@@ -655,7 +756,11 @@ export default function ServerWindow({ serverId, serverName, onClose }: ServerWi
   };
 
   const handlePostNewsWrapper = async (e: React.FormEvent) => {
-    await handlePostNews(e, newsPath, composerTitle, composerBody, postingNews, selectedArticle?.id ?? 0);
+    const posted = await handlePostNews(e, newsPath, composerTitle, composerBody, postingNews, selectedArticle?.id ?? 0);
+    // Jump to what was just posted
+    if (posted) {
+      await handleSelectArticleWrapper(posted);
+    }
   };
 
   // Load news when News tab is activated or path changes
@@ -680,12 +785,12 @@ export default function ServerWindow({ serverId, serverName, onClose }: ServerWi
             setNewsArticles([]);
           } else {
             // Inside a category - load articles only
-            const articles = await invoke<NewsArticle[]>('get_news_articles', {
+            const threads = await invoke<NewsThread[]>('get_news_thread_tree', {
               serverId,
               path: newsPath,
             });
             setNewsCategories([]);
-            setNewsArticles(articles);
+            setNewsArticles(flattenThreads(threads));
           }
         } catch (error) {
           console.error('Failed to load news:', error);
@@ -714,6 +819,8 @@ export default function ServerWindow({ serverId, serverName, onClose }: ServerWi
         onDisconnect={handleDisconnect}
         onShowTransfers={() => setShowTransferList(true)}
         onShowNotificationLog={() => setShowNotificationLog(true)}
+        onShowAccounts={hasPermission('openUser') ? () => setShowAccounts(true) : undefined}
+        onRefreshInfo={connectionStatus === 'logged-in' ? handleRefreshServerInfo : undefined}
       />
 
       {/* Mobile section tabs (above content on mobile) */}
@@ -791,6 +898,7 @@ export default function ServerWindow({ serverId, serverName, onClose }: ServerWi
           onUserRightClick={handleUserRightClick}
           onOpenMessageDialog={handleOpenMessageDialog}
           unreadCounts={unreadCounts}
+          capabilities={serverInfo?.capabilities}
         />
 
         {/* Main area with content */}
@@ -820,6 +928,9 @@ export default function ServerWindow({ serverId, serverName, onClose }: ServerWi
             <BoardTab
               boardPosts={boardPosts}
               loadingBoard={loadingBoard}
+              hasMoreBoard={boardContinuation !== null}
+              loadingMoreBoard={loadingMoreBoard}
+              onLoadMoreBoard={handleLoadMoreBoard}
               boardMessage={boardMessage}
               postingBoard={postingBoard}
               onBoardMessageChange={setBoardMessage}
@@ -858,6 +969,7 @@ export default function ServerWindow({ serverId, serverName, onClose }: ServerWi
               onCreateFolder={handleCreateNewsFolder}
               onDeleteItem={handleDeleteNewsItem}
               onDeleteArticle={handleDeleteNewsArticle}
+              onExport={handleExportNews}
             />
           )}
 
@@ -884,8 +996,13 @@ export default function ServerWindow({ serverId, serverName, onClose }: ServerWi
               onCancelNavigation={handleCancelNavigation}
               canCreateFolder={canCreateFolder}
               onCreateFolder={handleCreateFolder}
+              canRename={canRenameFile}
+              canMove={canMoveFile}
+              canDelete={canDeleteFile}
+              canMakeAlias={canMakeAlias}
               onDownloadFile={handleDownloadFile}
-              onUploadFile={handleUploadFile}
+              onDownloadFolder={handleDownloadFolder}
+              onUploadFile={canUpload ? handleUploadFile : undefined}
               onRefresh={() => {
                 // Clear cache for current path and fetch fresh data
                 clearFileCachePath(serverId, currentPath);
@@ -923,12 +1040,24 @@ export default function ServerWindow({ serverId, serverName, onClose }: ServerWi
       {userInfoDialogUser && (
         <UserInfoDialog
           user={userInfoDialogUser}
+          serverId={serverId}
           onClose={() => setUserInfoDialogUser(null)}
           onSendMessage={handleOpenMessageDialog}
           enablePrivateMessaging={enablePrivateMessaging}
         />
       )}
 
+      {/* Account administration */}
+      {showAccounts && (
+        <AccountsDialog
+          serverId={serverId}
+          canCreate={hasPermission('createUser')}
+          canModify={hasPermission('modifyUser')}
+          canDelete={hasPermission('deleteUser')}
+          onClose={() => setShowAccounts(false)}
+        />
+      )}
+
       {/* Message Dialog */}
       {messageDialogUser && (
         <MessageDialog
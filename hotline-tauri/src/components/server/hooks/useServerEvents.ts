@@ -1,12 +1,12 @@
 import { useEffect, useRef } from 'react';
+import { invoke } from '@tauri-apps/api/core';
 import { listen } from '@tauri-apps/api/event';
-import type { ConnectionStatus } from '../../../types';
-import type { ChatMessage, FileItem, User } from '../serverTypes';
+import type { AvatarFallback, ConnectionStatus, RosterUser, ServerInfo } from '../../../types';
+import type { ChatMessage, FileItem, FolderCapabilities, User } from '../serverTypes';
 import { useSound } from '../../../hooks/useSound';
 import { useAppStore } from '../../../stores/appStore';
 import { usePreferencesStore } from '../../../stores/preferencesStore';
 import { showNotification, useNotificationStore } from '../../../stores/notificationStore';
-import { containsMention, containsWatchWord } from '../../../utils/mentions';
 
 interface UseServerEventsProps {
   serverId: string;
@@ -20,6 +20,8 @@ interface UseServerEventsProps {
   setDownloadProgress: React.Dispatch<React.SetStateAction<Map<string, number>>>;
   setUploadProgress: React.Dispatch<React.SetStateAction<Map<string, number>>>;
   setAgreementText: React.Dispatch<React.SetStateAction<string | null>>;
+  setServerInfo: React.Dispatch<React.SetStateAction<ServerInfo | null>>;
+  setBannerUrl: React.Dispatch<React.SetStateAction<string | null>>;
   setConnectionStatus: React.Dispatch<React.SetStateAction<ConnectionStatus>>;
   setFileCache: (serverId: string, path: string[], files: FileItem[]) => void;
   currentPathRef: React.MutableRefObject<string[]>;
@@ -28,6 +30,7 @@ interface UseServerEventsProps {
   addTransfer: (transfer: any) => void;
   updateTransfer: (id: string, updates: Partial<any>) => void;
   onFileListReceived?: (path: string[]) => void;
+  onFolderCapabilities?: (path: string[], capabilities: FolderCapabilities) => void;
 }
 
 export function useServerEvents({
@@ -42,6 +45,8 @@ export function useServerEvents({
   setDownloadProgress,
   setUploadProgress,
   setAgreementText,
+  setServerInfo,
+  setBannerUrl,
   setConnectionStatus,
   setFileCache,
   currentPathRef,
@@ -50,11 +55,12 @@ export function useServerEvents({
   addTransfer,
   updateTransfer,
   onFileListReceived,
+  onFolderCapabilities,
 }: UseServerEventsProps) {
   const sounds = useSound();
   const soundsRef = useRef(sounds);
   const usersRef = useRef<User[]>([]);
-  const { updateTabUnread } = useAppStore();
+  const { updateTabUnread, updateTabTitle } = useAppStore();
   const { username } = usePreferencesStore();
   
   // Helper to check if this server's tab is active
@@ -93,11 +99,8 @@ export function useServerEvents({
       // Look up sender's admin status from current users
       const sender = usersRef.current.find(u => u.userId === event.payload.userId);
 
-      // Check if message contains a mention of the current user or a watch word
-      const isMention = !isMuted && (
-        containsMention(event.payload.message, username) ||
-        containsWatchWord(event.payload.message, prefs.watchWords)
-      );
+      // The backend marks messages mentioning us or a watch word
+      const isMention = !isMuted && event.payload.mentioned === true;
 
       const messageData = {
         ...event.payload,
@@ -111,30 +114,6 @@ export function useServerEvents({
 
       if (isMuted) return;
 
-      // Always log mentions/watch words to history; show toast only when tab is not active (and popup enabled)
-      if (isMention) {
-        const isWatchWord = !containsMention(event.payload.message, username) &&
-          containsWatchWord(event.payload.message, prefs.watchWords);
-        const notifMessage = isWatchWord
-          ? `Watch word matched in chat`
-          : `@${username} mentioned in chat`;
-        const notifTitle = `From ${event.payload.userName}`;
-        if (isTabActive() || !prefs.mentionPopup) {
-          useNotificationStore.getState().addToHistory({
-            type: 'info',
-            message: notifMessage,
-            title: notifTitle,
-            serverName,
-          });
-        } else {
-          showNotification.info(
-            notifMessage,
-            notifTitle,
-            undefined,
-            serverName
-          );
-        }
-      }
       if (!isTabActive()) {
         incrementUnread();
       }
@@ -144,7 +123,45 @@ export function useServerEvents({
       isActive = false;
       unlistenPromise.then((unlisten) => unlisten()).catch(() => {});
     };
-  }, [serverId, setMessages, username]);
+  }, [serverId, setMessages]);
+
+  // Mentions and watch words: always logged to history, toast only when the tab is not active (and popup enabled)
+  useEffect(() => {
+    let isActive = true;
+
+    const unlistenPromise = listen<{ kind: 'nickname' | 'keyword'; event: ChatMessage }>(`mention-${serverId}`, (event) => {
+      if (!isActive) return;
+
+      const prefs = usePreferencesStore.getState();
+      const { kind, event: chat } = event.payload;
+      if (prefs.mutedUsers.some((u) => u.toLowerCase() === chat.userName.toLowerCase())) return;
+
+      const notifMessage = kind === 'keyword'
+        ? `Watch word matched in chat`
+        : `@${username} mentioned in chat`;
+      const notifTitle = `From ${chat.userName}`;
+      if (isTabActive() || !prefs.mentionPopup) {
+        useNotificationStore.getState().addToHistory({
+          type: 'info',
+          message: notifMessage,
+          title: notifTitle,
+          serverName,
+        });
+      } else {
+        showNotification.info(
+          notifMessage,
+          notifTitle,
+          undefined,
+          serverName
+        );
+      }
+    });
+
+    return () => {
+      isActive = false;
+      unlistenPromise.then((unlisten) => unlisten()).catch(() => {});
+    };
+  }, [serverId, serverName, username]);
 
   // Listen for broadcast messages
   useEffect(() => {
@@ -174,8 +191,11 @@ export function useServerEvents({
 
   // Listen for file list events
   useEffect(() => {
-    const unlisten = listen<{ files: FileItem[]; path: string[] }>(`file-list-${serverId}`, (event) => {
-      const { files, path } = event.payload;
+    const unlisten = listen<{ files: FileItem[]; path: string[]; capabilities?: FolderCapabilities }>(`file-list-${serverId}`, (event) => {
+      const { files, path, capabilities } = event.payload;
+      if (capabilities && onFolderCapabilities) {
+        onFolderCapabilities(path, capabilities);
+      }
       
       // Only update UI if this is for the current path
       if (path.length === currentPathRef.current.length && path.every((v, i) => v === currentPathRef.current[i])) {
@@ -193,7 +213,7 @@ export function useServerEvents({
     return () => {
       unlisten.then((fn) => fn()).catch(() => {});
     };
-  }, [serverId, setFiles, setFileCache, currentPathRef, onFileListReceived]);
+  }, [serverId, setFiles, setFileCache, currentPathRef, onFileListReceived, onFolderCapabilities]);
 
   // Listen for new message board posts
   useEffect(() => {
@@ -206,15 +226,45 @@ export function useServerEvents({
     };
   }, [serverId, setBoardPosts]);
 
+  // Posts of a board page arrive in chunks while it's being decoded
+  useEffect(() => {
+    const unlisten = listen<{ posts: string[] }>(`message-board-chunk-${serverId}`, (event) => {
+      setBoardPosts((prev) => [...prev, ...event.payload.posts]);
+    });
+
+    return () => {
+      unlisten.then((fn) => fn()).catch(() => {});
+    };
+  }, [serverId, setBoardPosts]);
+
   // Sync usersRef when users are initially loaded or updated
   // We'll update the ref in the user event handlers, but also need to handle initial load
   // The ref will be updated in the join/leave/change handlers below
 
+  // Start from the user list kept by the backend, which every window shares.
+  // It holds the list loaded at login (the same one the connect result
+  // carries), so only changes after it arrive as events.
+  useEffect(() => {
+    let isActive = true;
+    invoke<User[]>('get_users', { serverId })
+      .then((users) => {
+        if (!isActive || users.length === 0) return;
+        usersRef.current = users;
+        setUsers(users);
+      })
+      .catch(() => {
+        // Not connected yet; a late user list reply fills it in
+      });
+    return () => {
+      isActive = false;
+    };
+  }, [serverId, setUsers]);
+
   // Listen for user events
   useEffect(() => {
     let isActive = true;
     
-    const unlistenJoinPromise = listen<{ userId: number; userName: string; iconId: number; flags: number }>(
+    const unlistenJoinPromise = listen<{ userId: number; userName: string; iconId: number; flags: number; avatar?: AvatarFallback }>(
       `user-joined-${serverId}`,
       (event) => {
         if (!isActive) return;
@@ -231,7 +281,7 @@ export function useServerEvents({
         const { isAdmin, isIdle } = parseUserFlags(event.payload.flags);
         
         // Add user to list
-        // Note: This handler is primarily for initial user list load from GetUserNameList reply
+        // Note: This handler only sees user list replies that arrived too late for the connect result
         // For actual new user joins, we rely on the user-changed handler which receives NotifyUserChange
         setUsers((prev) => {
           // Double-check user doesn't exist (race condition protection)
@@ -244,6 +294,7 @@ export function useServerEvents({
             userName: event.payload.userName,
             iconId: event.payload.iconId,
             flags: event.payload.flags,
+            avatar: event.payload.avatar,
             isAdmin,
             isIdle,
           }];
@@ -292,7 +343,7 @@ export function useServerEvents({
       }
     );
 
-    const unlistenChangePromise = listen<{ userId: number; userName: string; iconId: number; flags: number }>(
+    const unlistenChangePromise = listen<{ userId: number; userName: string; iconId: number; flags: number; avatar?: AvatarFallback; isSelf: boolean }>(
       `user-changed-${serverId}`,
       (event) => {
         if (!isActive) return;
@@ -313,6 +364,8 @@ export function useServerEvents({
                     userName: event.payload.userName,
                     iconId: event.payload.iconId,
                     flags: event.payload.flags,
+                    avatar: event.payload.avatar,
+                    isSelf: event.payload.isSelf || u.isSelf,
                     ...parseUserFlags(event.payload.flags),
                   }
                 : u
@@ -327,6 +380,8 @@ export function useServerEvents({
               userName: event.payload.userName,
               iconId: event.payload.iconId,
               flags: event.payload.flags,
+              avatar: event.payload.avatar,
+              isSelf: event.payload.isSelf,
               isAdmin,
               isIdle,
             }];
@@ -352,11 +407,38 @@ export function useServerEvents({
       }
     );
 
+    // Full user list re-sync (after kicks, account edits, server notices).
+    // Applied silently: no join/leave messages or sounds for stale entries.
+    const unlistenSyncPromise = listen<{ added: RosterUser[]; removed: number[]; changed: RosterUser[] }>(
+      `roster-sync-${serverId}`,
+      (event) => {
+        if (!isActive) return;
+
+        const { added, removed, changed } = event.payload;
+        const toUser = (u: RosterUser) => ({ ...u, ...parseUserFlags(u.flags) });
+        const replacements = new Map(changed.map((u) => [u.userId, toUser(u)]));
+
+        setUsers((prev) => {
+          const updated = prev
+            .filter((u) => !removed.includes(u.userId))
+            .map((u) => replacements.get(u.userId) ?? u);
+          for (const user of added) {
+            if (!updated.some((u) => u.userId === user.userId)) {
+              updated.push(toUser(user));
+            }
+          }
+          usersRef.current = updated;
+          return updated;
+        });
+      }
+    );
+
     return () => {
       isActive = false;
       unlistenJoinPromise.then((fn) => fn()).catch(() => {});
       unlistenLeavePromise.then((fn) => fn()).catch(() => {});
       unlistenChangePromise.then((fn) => fn()).catch(() => {});
+      unlistenSyncPromise.then((fn) => fn()).catch(() => {});
     };
   }, [serverId, setUsers, setMessages, parseUserFlags]);
 
@@ -437,12 +519,100 @@ export function useServerEvents({
     };
   }, [serverId, setPrivateMessageHistory, setUnreadCounts, enablePrivateMessaging, setUsers]);
 
+  // Refusals and automatic responses to our private messages and invitations
+  useEffect(() => {
+    type Reply = { userId: number; userName: string; message: string };
+
+    const unlisteners = [
+      listen<Reply>(`message-refused-${serverId}`, (event) => {
+        showNotification.warning(
+          event.payload.message || 'This user is not accepting private messages',
+          `${event.payload.userName || `User ${event.payload.userId}`} refused your message`,
+          undefined,
+          serverName
+        );
+      }),
+      listen<Reply>(`chat-refused-${serverId}`, (event) => {
+        showNotification.warning(
+          event.payload.message || 'This user is not accepting chat invitations',
+          `${event.payload.userName || `User ${event.payload.userId}`} refused your invitation`,
+          undefined,
+          serverName
+        );
+      }),
+      listen<Reply>(`automatic-response-${serverId}`, (event) => {
+        const { userId, message } = event.payload;
+        setPrivateMessageHistory((prev) => {
+          const newHistory = new Map(prev);
+          const userMessages = newHistory.get(userId) || [];
+          newHistory.set(userId, [
+            ...userMessages,
+            {
+              text: `[Automatic response] ${message}`,
+              isOutgoing: false,
+              timestamp: new Date(),
+            },
+          ]);
+          return newHistory;
+        });
+      }),
+    ];
+
+    return () => {
+      unlisteners.forEach((promise) => promise.then((unlisten) => unlisten()).catch(() => {}));
+    };
+  }, [serverId, serverName, setPrivateMessageHistory]);
+
+  // Away status set by the idle timer, /away or /back
+  useEffect(() => {
+    const unlisten = listen<{ away: boolean; automatic: boolean }>(`away-changed-${serverId}`, (event) => {
+      const { away, automatic } = event.payload;
+      const message = away
+        ? automatic ? 'Marked away after being idle' : 'You are now away'
+        : 'You are back';
+      showNotification.info(message, 'Away', undefined, serverName);
+    });
+
+    return () => {
+      unlisten.then((fn) => fn()).catch(() => {});
+    };
+  }, [serverId, serverName]);
+
+  // Background task notices, when turned on under About > Diagnostics
+  useEffect(() => {
+    const unlisten = listen<{ notice: string; detail: string; problem: boolean; timestamp: number }>(`lifecycle-${serverId}`, (event) => {
+      const { notice, detail, problem } = event.payload;
+      const log = problem ? console.warn : console.debug;
+      log(`[${serverName}] ${notice}${detail ? `: ${detail}` : ''}`);
+    });
+
+    return () => {
+      unlisten.then((fn) => fn()).catch(() => {});
+    };
+  }, [serverId, serverName]);
+
+  // Bookmarks with an idle limit warn a minute before disconnecting
+  useEffect(() => {
+    const unlisten = listen<{ secondsLeft: number }>(`idle-disconnect-warning-${serverId}`, (event) => {
+      showNotification.warning(
+        `Disconnecting in ${event.payload.secondsLeft} seconds for being idle. Send a message to stay connected.`,
+        'Idle',
+        undefined,
+        serverName
+      );
+    });
+
+    return () => {
+      unlisten.then((fn) => fn()).catch(() => {});
+    };
+  }, [serverId, serverName]);
+
   // Listen for download progress events
   useEffect(() => {
-    const unlisten = listen<{ fileName: string; bytesRead: number; totalBytes: number; progress: number }>(
+    const unlisten = listen<{ fileName: string; bytesRead: number; totalBytes: number; progress: number; bytesPerSec: number; etaSecs: number | null }>(
       `download-progress-${serverId}`,
       (event) => {
-        const { fileName, bytesRead, totalBytes, progress } = event.payload;
+        const { fileName, bytesRead, totalBytes, progress, bytesPerSec, etaSecs } = event.payload;
         setDownloadProgress((prev) => new Map(prev).set(fileName, progress));
         
         // Track transfer
@@ -457,7 +627,8 @@ export function useServerEvents({
             fileName,
             fileSize: totalBytes || 0,
             transferred: bytesRead || 0,
-            speed: 0,
+            speed: bytesPerSec || 0,
+            etaSecs,
             status: 'active',
             startTime: new Date(),
           });
@@ -465,6 +636,8 @@ export function useServerEvents({
           updateTransfer(transferId, {
             transferred: bytesRead || 0,
             fileSize: totalBytes || existingTransfer.fileSize || 0,
+            speed: bytesPerSec || 0,
+            etaSecs,
           });
         }
       }
@@ -477,10 +650,10 @@ export function useServerEvents({
 
   // Listen for upload progress events
   useEffect(() => {
-    const unlisten = listen<{ fileName: string; bytesSent: number; totalBytes: number; progress: number }>(
+    const unlisten = listen<{ fileName: string; bytesSent: number; totalBytes: number; progress: number; bytesPerSec: number; etaSecs: number | null }>(
       `upload-progress-${serverId}`,
       (event) => {
-        const { fileName, bytesSent, totalBytes, progress } = event.payload;
+        const { fileName, bytesSent, totalBytes, progress, bytesPerSec, etaSecs } = event.payload;
         setUploadProgress((prev) => new Map(prev).set(fileName, progress));
         
         // Track transfer
@@ -495,7 +668,8 @@ export function useServerEvents({
             fileName,
             fileSize: totalBytes || 0,
             transferred: bytesSent || 0,
-            speed: 0,
+            speed: bytesPerSec || 0,
+            etaSecs,
             status: 'active',
             startTime: new Date(),
           });
@@ -503,6 +677,8 @@ export function useServerEvents({
           updateTransfer(transferId, {
             transferred: bytesSent || 0,
             fileSize: totalBytes || existingTransfer.fileSize || 0,
+            speed: bytesPerSec || 0,
+            etaSecs,
           });
         }
       }
@@ -663,5 +839,20 @@ export function useServerEvents({
       unlisten.then((fn) => fn()).catch(() => {});
     };
   }, [serverId, setAgreementText]);
-}
 
+  // Listen for server info and banner changes picked up by a refresh
+  useEffect(() => {
+    const unlistenInfo = listen<ServerInfo>(`server-info-updated-${serverId}`, (event) => {
+      setServerInfo(event.payload);
+      updateTabTitle(`server-${serverId}`, event.payload.name || serverName);
+    });
+    const unlistenBanner = listen<{ bannerUrl: string }>(`banner-updated-${serverId}`, (event) => {
+      setBannerUrl(event.payload.bannerUrl);
+    });
+
+    return () => {
+      unlistenInfo.then((fn) => fn()).catch(() => {});
+      unlistenBanner.then((fn) => fn()).catch(() => {});
+    };
+  }, [serverId, serverName, setServerInfo, setBannerUrl, updateTabTitle]);
+}
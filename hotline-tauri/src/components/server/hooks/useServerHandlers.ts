@@ -1,19 +1,39 @@
 import { invoke } from '@tauri-apps/api/core';
 import { openPath } from '@tauri-apps/plugin-opener';
-import type { NewsArticle } from '../serverTypes';
+import type { BoardContinuation, BoardPage, ConflictResolution, NewsArticle, NewsThread, RawField, RawReply, UploadConflict } from '../serverTypes';
 import { useSound } from '../../../hooks/useSound';
 import { showNotification, useNotificationStore } from '../../../stores/notificationStore';
+import { runOperation } from '../../../utils/operations';
+import { flattenThreads } from '../../../utils/newsThreads';
+import { usePreferencesStore, type ForkMode } from '../../../stores/preferencesStore';
+
+// "/raw" field arguments: id=0x… is hex, id=number is a 16- or 32-bit
+// integer and anything else is text (quotes optional)
+function parseRawField(arg: string): RawField | null {
+  const match = arg.match(/^(\d+)=(.*)$/);
+  if (!match) return null;
+  const id = parseInt(match[1]);
+  const value = match[2];
+  if (/^0x[0-9a-f]*$/i.test(value)) return { id, value: { kind: 'hex', value: value.slice(2) } };
+  if (/^\d+$/.test(value)) {
+    const number = parseInt(value);
+    return { id, value: number > 0xffff ? { kind: 'u32', value: number } : { kind: 'u16', value: number } };
+  }
+  return { id, value: { kind: 'text', value: value.replace(/^"(.*)"$/, '$1') } };
+}
 
 interface UseServerHandlersProps {
   serverId: string;
   serverName: string;
   currentPath: string[];
   downloadFolder?: string | null;
+  forkMode?: ForkMode;
   setMessage: React.Dispatch<React.SetStateAction<string>>;
   setSending: React.Dispatch<React.SetStateAction<boolean>>;
   setBoardMessage: React.Dispatch<React.SetStateAction<string>>;
   setPostingBoard: React.Dispatch<React.SetStateAction<boolean>>;
   setBoardPosts: React.Dispatch<React.SetStateAction<string[]>>;
+  setBoardContinuation: React.Dispatch<React.SetStateAction<BoardContinuation | null>>;
   setDownloadProgress: React.Dispatch<React.SetStateAction<Map<string, number>>>;
   setUploadProgress: React.Dispatch<React.SetStateAction<Map<string, number>>>;
   setPrivateMessageHistory: React.Dispatch<React.SetStateAction<Map<number, any[]>>>;
@@ -33,11 +53,13 @@ export function useServerHandlers({
   serverName,
   currentPath,
   downloadFolder,
+  forkMode,
   setMessage,
   setSending,
   setBoardMessage,
   setPostingBoard,
   setBoardPosts,
+  setBoardContinuation,
   setDownloadProgress,
   setUploadProgress,
   setPrivateMessageHistory,
@@ -58,6 +80,66 @@ export function useServerHandlers({
     if (!message.trim() || sending) return;
 
     const messageText = message.trim();
+
+    // "/away [message]" and "/back" toggle away instead of chatting
+    const awayCommand = messageText.match(/^\/(away|back)\b\s*(.*)$/i);
+    if (awayCommand) {
+      const away = awayCommand[1].toLowerCase() === 'away';
+      try {
+        await invoke('set_away', { serverId, away, message: awayCommand[2] || null });
+        setMessage('');
+      } catch (error) {
+        showNotification.error(String(error), 'Away', undefined, serverName);
+      }
+      return;
+    }
+
+    // "/raw type [id=value ...]" sends a raw transaction and logs the reply's
+    // fields; only debug builds, or ones started with HOTLINE_RAW_TRANSACTIONS=1
+    const rawCommand = messageText.match(/^\/raw\s+(.+)$/i);
+    if (rawCommand) {
+      const [first, ...args] = rawCommand[1].match(/(?:[^\s"]+|"[^"]*")+/g) || [];
+      try {
+        const fields = args.map(parseRawField);
+        if (!/^\d+$/.test(first) || fields.some((field) => field === null)) {
+          throw new Error('Usage: /raw <type> [<field id>=<value> ...]');
+        }
+        const reply = await invoke<RawReply>('send_raw_transaction', {
+          serverId,
+          transactionType: parseInt(first),
+          fields,
+        });
+        console.table(reply.fields);
+        showNotification.info(
+          reply.fields.map((field) => `${field.name ?? field.id}: ${field.text ?? field.number ?? field.hex}`).join('\n')
+            || 'No fields',
+          `Reply to ${first}${reply.errorCode ? ` (error ${reply.errorCode})` : ''}`,
+          undefined,
+          serverName
+        );
+        setMessage('');
+      } catch (error) {
+        showNotification.error(String(error), 'Raw', undefined, serverName);
+      }
+      return;
+    }
+
+    // "/nick name" changes our nickname on this server only
+    const nickCommand = messageText.match(/^\/nick\s+(.+)$/i);
+    if (nickCommand) {
+      try {
+        await invoke('update_user_info', {
+          serverId,
+          username: nickCommand[1].trim(),
+          iconId: usePreferencesStore.getState().userIconId,
+        });
+        setMessage('');
+      } catch (error) {
+        showNotification.error(String(error), 'Nickname', undefined, serverName);
+      }
+      return;
+    }
+
     setSending(true);
     try {
       await invoke('send_chat_message', {
@@ -93,10 +175,13 @@ export function useServerHandlers({
         message: messageText,
       });
 
-      const posts = await invoke<string[]>('get_message_board', {
+      // Chunk events refill the list while the board reloads
+      setBoardPosts([]);
+      const page = await invoke<BoardPage>('get_message_board', {
         serverId,
       });
-      setBoardPosts(posts);
+      setBoardPosts(page.posts);
+      setBoardContinuation(page.continuation);
 
       setBoardMessage('');
     } catch (error) {
@@ -116,12 +201,13 @@ export function useServerHandlers({
     try {
       setDownloadProgress((prev) => new Map(prev).set(fileName, 0));
 
-      const result = await invoke<string>('download_file', {
+      const result = await runOperation('download_file', {
         serverId,
         path: currentPath,
         fileName,
         fileSize,
         downloadFolder: downloadFolder ?? null,
+        forkMode: forkMode ?? 'discard',
       });
 
       setDownloadProgress((prev) => {
@@ -130,8 +216,8 @@ export function useServerHandlers({
         return next;
       });
 
-      // Extract file path from result string "Downloaded to: <path>"
-      const filePath = result.replace(/^Downloaded to:\s*/, '').trim();
+      // Extract file path from result string "Downloaded to: <path>" or "Already downloaded to: <path>"
+      const filePath = result.replace(/^(Already d|D)ownloaded to:\s*/, '').trim();
 
       const isIOS = typeof window !== 'undefined' && (
         /iPad|iPhone|iPod/.test(navigator.userAgent) ||
@@ -182,19 +268,88 @@ export function useServerHandlers({
     }
   };
 
+  const handleDownloadFolder = async (folderName: string, path: string[] = currentPath) => {
+    const clearProgress = () =>
+      setDownloadProgress((prev) => {
+        const next = new Map(prev);
+        next.delete(folderName);
+        return next;
+      });
+
+    try {
+      setDownloadProgress((prev) => new Map(prev).set(folderName, 0));
+
+      const result = await runOperation(
+        'download_folder',
+        {
+          serverId,
+          path,
+          folderName,
+          downloadFolder: downloadFolder ?? null,
+        },
+        {
+          onProgress: ({ progress }) =>
+            setDownloadProgress((prev) => new Map(prev).set(folderName, progress)),
+        }
+      );
+
+      clearProgress();
+      showNotification.success(result, 'Download Complete', undefined, serverName);
+      sounds.playFileTransferCompleteSound();
+    } catch (error) {
+      console.error('Folder download failed:', error);
+      sounds.playErrorSound();
+      clearProgress();
+      showNotification.error(
+        `Download failed: ${error}`,
+        'Download Error',
+        undefined,
+        serverName
+      );
+    }
+  };
+
+  // Ask what to do about a name clash, offering only what the server allows.
+  // Null means the user cancelled.
+  const chooseConflictResolution = (conflict: UploadConflict): ConflictResolution | null => {
+    const name = conflict.fileName;
+    if (conflict.canResume && confirm(`"${name}" is already here but smaller. Resume uploading it?`)) {
+      return 'resume';
+    }
+    if (conflict.canOverwrite && confirm(`Replace the existing "${name}"? This cannot be undone.`)) {
+      return 'overwrite';
+    }
+    if (conflict.suggestedName && confirm(`Upload as "${conflict.suggestedName}" instead?`)) {
+      return 'rename';
+    }
+    return null;
+  };
+
   const handleUploadFile = async (file: File) => {
     try {
       const fileName = file.name;
+      const conflict = await invoke<UploadConflict | null>('check_upload_conflict', {
+        serverId,
+        path: currentPath,
+        fileName,
+        fileSize: file.size,
+      });
+      const resolution = conflict ? chooseConflictResolution(conflict) : null;
+      if (conflict && !resolution) return;
+
       setUploadProgress((prev) => new Map(prev).set(fileName, 0));
 
       const arrayBuffer = await file.arrayBuffer();
       const fileData = Array.from(new Uint8Array(arrayBuffer));
 
-      await invoke('upload_file', {
+      await runOperation('upload_file', {
         serverId,
         path: currentPath,
         fileName,
         fileData,
+        // Unpacked into INFO and resource forks when it really is MacBinary
+        macBinary: fileName.toLowerCase().endsWith('.bin'),
+        conflict: resolution,
       });
 
       setUploadProgress((prev) => {
@@ -298,29 +453,40 @@ export function useServerHandlers({
     composerBody: string,
     postingNews: boolean,
     parentId: number = 0,
-  ) => {
+  ): Promise<NewsArticle | null> => {
     e.preventDefault();
-    if (!composerTitle.trim() || !composerBody.trim() || postingNews) return;
+    // Replies may leave the title out and get "Re: " plus the parent's
+    if ((!composerTitle.trim() && !parentId) || !composerBody.trim() || postingNews) return null;
 
     setPostingNews(true);
     try {
-      await invoke('post_news_article', {
-        serverId,
-        path: newsPath,
-        title: composerTitle.trim(),
-        text: composerBody.trim(),
-        parentId,
-      });
+      const postedId = parentId
+        ? await invoke<number | null>('reply_to_news_article', {
+            serverId,
+            path: newsPath,
+            parentId,
+            title: composerTitle.trim() || null,
+            text: composerBody.trim(),
+          })
+        : await invoke<number | null>('post_news_article', {
+            serverId,
+            path: newsPath,
+            title: composerTitle.trim(),
+            text: composerBody.trim(),
+            parentId,
+          });
 
       setComposerTitle('');
       setComposerBody('');
       setShowComposer(false);
 
-      const articles = await invoke<NewsArticle[]>('get_news_articles', {
+      const threads = await invoke<NewsThread[]>('get_news_thread_tree', {
         serverId,
         path: newsPath,
       });
+      const articles = flattenThreads(threads);
       setNewsArticles(articles);
+      return articles.find((article) => article.id === postedId) ?? null;
     } catch (error) {
       console.error('Failed to post news:', error);
       const errorMsg = String(error);
@@ -339,6 +505,7 @@ export function useServerHandlers({
           serverName
         );
       }
+      return null;
     } finally {
       setPostingNews(false);
     }
@@ -381,6 +548,7 @@ export function useServerHandlers({
     handleSendMessage,
     handlePostBoard,
     handleDownloadFile,
+    handleDownloadFolder,
     handleUploadFile,
     handleSendPrivateMessage,
     handleAcceptAgreement,
@@ -1,11 +1,15 @@
 // Type definitions for ServerWindow and related components
 
+import type { AvatarFallback } from '../../types';
+import type { FileKind } from '../files/FileKindIcon';
+
 export interface ChatMessage {
   userId: number;
   userName: string;
   message: string;
   timestamp: Date;
   isMention?: boolean; // Indicates if this message mentions the current user
+  mentioned?: boolean; // Set by the backend when the message mentions us or a watch word
   isAdmin?: boolean; // Indicates if the sender is an admin
 }
 
@@ -16,6 +20,10 @@ export interface User {
   flags: number;
   isAdmin: boolean;
   isIdle: boolean;
+  refusesMessages?: boolean;
+  refusesChat?: boolean;
+  avatar?: AvatarFallback;
+  isSelf?: boolean; // our own entry
 }
 
 export interface PrivateMessage {
@@ -30,6 +38,18 @@ export interface FileItem {
   isFolder: boolean;
   fileType?: string;
   creator?: string;
+  kind?: FileKind;
+  canDelete?: boolean;
+  canRename?: boolean;
+}
+
+// What the account may do inside a listed folder, computed by the backend
+// from the access bits and upload/drop box folders on the path
+export interface FolderCapabilities {
+  canUpload: boolean;
+  canCreateFolder: boolean;
+  canDelete: boolean;
+  canRename: boolean;
 }
 
 export interface NewsCategory {
@@ -37,16 +57,83 @@ export interface NewsCategory {
   count: number;
   name: string;
   path: string[];
+  guid?: string;
+  addSn?: number;
+  deleteSn?: number;
 }
 
 export interface NewsArticle {
   id: number;
-  parent_id: number;
+  parentId: number;
   flags: number;
   title: string;
   poster: string;
-  date?: string;
+  date?: number; // Unix seconds
   path: string[];
+  depth?: number; // Set when listed from a thread tree
+  replyCount?: number;
+}
+
+export interface NewsThread {
+  article: NewsArticle;
+  depth: number;
+  childCount: number;
+  descendantCount: number;
+  replies: NewsThread[];
+}
+
+export interface BoardContinuation {
+  fetch: number;
+  offset: number;
+}
+
+// One page of the message board; `continuation` is set when more posts remain
+export interface BoardPage {
+  posts: string[];
+  totalBytes: number;
+  continuation: BoardContinuation | null;
+}
+
+// What a bookmark's connect actions left for the window to show
+export interface ConnectOutcome {
+  folder: string[] | null;
+  board: BoardPage | null;
+}
+
+// An upload whose name is already taken in the destination folder
+export interface UploadConflict {
+  fileName: string;
+  existingSize: number | null;
+  suggestedName: string | null;
+  canOverwrite: boolean;
+  canResume: boolean;
+}
+
+export type ConflictResolution = 'rename' | 'overwrite' | 'resume';
+
+// Field values for send_raw_transaction
+export type RawValue =
+  | { kind: 'text'; value: string }
+  | { kind: 'u16'; value: number }
+  | { kind: 'u32'; value: number }
+  | { kind: 'hex'; value: string };
+
+export interface RawField {
+  id: number;
+  value: RawValue;
+}
+
+export interface RawReply {
+  transactionType: number;
+  errorCode: number;
+  fields: {
+    id: number;
+    name: string | null;
+    size: number;
+    hex: string;
+    text: string | null;
+    number: number | null;
+  }[];
 }
 
 export type ViewTab = 'chat' | 'board' | 'news' | 'files';
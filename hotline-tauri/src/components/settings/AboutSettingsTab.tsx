@@ -1,9 +1,15 @@
 import { useState, useEffect } from 'react';
 import { getVersion } from '@tauri-apps/api/app';
+import { invoke } from '@tauri-apps/api/core';
 import { openUrl } from '@tauri-apps/plugin-opener';
+import ProblemsPanel from './ProblemsPanel';
+import { usePreferencesStore } from '../../stores/preferencesStore';
+import type { DiagnosticVerbosity } from '../../types';
 
 export default function AboutSettingsTab() {
   const [version, setVersion] = useState<string>('0.1.1');
+  const [diagnosticsStatus, setDiagnosticsStatus] = useState<string | null>(null);
+  const { diagnosticVerbosity, setDiagnosticVerbosity } = usePreferencesStore();
 
   useEffect(() => {
     getVersion().then(setVersion).catch(() => {
@@ -11,6 +17,25 @@ export default function AboutSettingsTab() {
     });
   }, []);
 
+  const handleGenerateDiagnostics = async () => {
+    const consent = window.confirm(
+      'Create a diagnostics bundle for a bug report?\n\n' +
+      'It includes the app version, your OS, connection stats, bookmarks, settings, a short activity log and recent failures. ' +
+      'Passwords are redacted and private message content is never included.'
+    );
+    if (!consent) return;
+
+    setDiagnosticsStatus('Generating...');
+    try {
+      const stored = localStorage.getItem('hotline-preferences');
+      const settings = stored ? JSON.parse(stored).state ?? null : null;
+      const path = await invoke<string>('generate_diagnostics', { consent, settings });
+      setDiagnosticsStatus(`Saved to ${path}`);
+    } catch (error) {
+      setDiagnosticsStatus(`Failed: ${error}`);
+    }
+  };
+
   return (
     <div className="p-6 space-y-6">
       {/* Logo/Icon */}
@@ -106,6 +131,41 @@ export default function AboutSettingsTab() {
         </div>
       </div>
 
+      {/* Diagnostics */}
+      <div className="border-t border-gray-200 dark:border-gray-700 pt-4">
+        <h4 className="text-sm font-semibold text-gray-700 dark:text-gray-300 mb-2">
+          Diagnostics
+        </h4>
+        <p className="text-xs text-gray-600 dark:text-gray-400 mb-2">
+          Save a redacted zip with version, platform and connection details to attach to bug reports.
+        </p>
+        <button
+          onClick={handleGenerateDiagnostics}
+          className="px-3 py-1.5 text-sm border border-gray-300 dark:border-gray-600 text-gray-700 dark:text-gray-300 rounded-md hover:bg-gray-100 dark:hover:bg-gray-700 transition-colors"
+        >
+          Generate Diagnostics...
+        </button>
+        {diagnosticsStatus && (
+          <p className="text-xs text-gray-500 dark:text-gray-400 mt-2 break-all">{diagnosticsStatus}</p>
+        )}
+        <label className="block text-xs text-gray-600 dark:text-gray-400 mt-3 mb-1">
+          Connection task notices in the developer console
+        </label>
+        <select
+          value={diagnosticVerbosity}
+          onChange={(e) => setDiagnosticVerbosity(e.target.value as DiagnosticVerbosity)}
+          className="px-2 py-1 border border-gray-300 dark:border-gray-600 rounded-md bg-white dark:bg-gray-700 text-sm text-gray-900 dark:text-white"
+        >
+          <option value="off">Off</option>
+          <option value="problems">Stalls and unexpected stops</option>
+          <option value="all">Everything</option>
+        </select>
+      </div>
+
+      <div className="border-t border-gray-200 dark:border-gray-700 pt-4">
+        <ProblemsPanel />
+      </div>
+
       {/* Copyright */}
       <div className="text-center text-xs text-gray-500 dark:text-gray-500 pt-2 border-t border-gray-200 dark:border-gray-700">
         <p>© 2026 Greg Gant</p>
@@ -1,19 +1,161 @@
 import { useState, useEffect } from 'react';
 import { invoke } from '@tauri-apps/api/core';
-import { usePreferencesStore } from '../../stores/preferencesStore';
+import { usePreferencesStore, type ForkMode } from '../../stores/preferencesStore';
 import { useAppStore } from '../../stores/appStore';
 import { showNotification } from '../../stores/notificationStore';
 import { useIsMobile } from '../../hooks/useIsMobile';
 import type { Bookmark } from '../../types';
 
+interface ClientIdentity {
+  versionNumber: number;
+  name: string;
+  version: string;
+  sendIdentification: boolean;
+}
+
+interface ReconnectPolicy {
+  enabled: boolean;
+  initialDelaySecs: number;
+  maxDelaySecs: number;
+  maxRetries: number;
+  watchdogTimeoutSecs: number;
+}
+
+interface ServerRefreshPolicy {
+  enabled: boolean;
+  intervalSecs: number;
+}
+
+interface AutoAwayPolicy {
+  enabled: boolean;
+  idleSecs: number;
+  message: string | null;
+  nicknameSuffix: string | null;
+}
+
+interface BoardFetchPolicy {
+  maxKib: number;
+}
+
 export default function GeneralSettingsTab() {
-  const { username, setUsername, enablePrivateMessaging, setEnablePrivateMessaging, darkMode, setDarkMode, downloadFolder, setDownloadFolder, showServerBanner, setShowServerBanner, clickableLinks, setClickableLinks, useRemoteIcons, setUseRemoteIcons, showRemoteBanners, setShowRemoteBanners, autoDetectTls, setAutoDetectTls, mentionPopup, setMentionPopup, mutedUsers, addMutedUser, removeMutedUser, watchWords, addWatchWord, removeWatchWord } = usePreferencesStore();
+  const { username, setUsername, enablePrivateMessaging, setEnablePrivateMessaging, darkMode, setDarkMode, downloadFolder, setDownloadFolder, forkMode, setForkMode, skipExistingDownloads, setSkipExistingDownloads, showServerBanner, setShowServerBanner, clickableLinks, setClickableLinks, useRemoteIcons, setUseRemoteIcons, showRemoteBanners, setShowRemoteBanners, autoDetectTls, setAutoDetectTls, timeZone, setTimeZone, locale, setLocale, mentionPopup, setMentionPopup, mutedUsers, addMutedUser, removeMutedUser, watchWords, addWatchWord, removeWatchWord } = usePreferencesStore();
   const { setBookmarks } = useAppStore();
   const isMobile = useIsMobile();
   const [localUsername, setLocalUsername] = useState(username);
   const [isAddingDefaults, setIsAddingDefaults] = useState(false);
   const [muteInput, setMuteInput] = useState('');
   const [watchInput, setWatchInput] = useState('');
+  const [cacheStats, setCacheStats] = useState<{ entries: number; bytes: number }>({ entries: 0, bytes: 0 });
+  const [identity, setIdentity] = useState<ClientIdentity | null>(null);
+  const [reconnectPolicy, setReconnectPolicy] = useState<ReconnectPolicy | null>(null);
+  const [refreshPolicy, setRefreshPolicy] = useState<ServerRefreshPolicy | null>(null);
+  const [autoAwayPolicy, setAutoAwayPolicy] = useState<AutoAwayPolicy | null>(null);
+  const [boardFetchPolicy, setBoardFetchPolicy] = useState<BoardFetchPolicy | null>(null);
+  const [encryptHistory, setEncryptHistory] = useState<boolean | null>(null);
+
+  const refreshCacheStats = async () => {
+    try {
+      const stats = await invoke<Array<{ articles: number; boards: number; bytes: number }>>('get_cache_stats');
+      setCacheStats({
+        entries: stats.reduce((sum, s) => sum + s.articles + s.boards, 0),
+        bytes: stats.reduce((sum, s) => sum + s.bytes, 0),
+      });
+    } catch (error) {
+      console.error('Failed to load cache stats:', error);
+    }
+  };
+
+  useEffect(() => {
+    refreshCacheStats();
+    invoke<ClientIdentity>('get_client_identity')
+      .then(setIdentity)
+      .catch((error) => console.error('Failed to load client identity:', error));
+    invoke<ReconnectPolicy>('get_reconnect_policy')
+      .then(setReconnectPolicy)
+      .catch((error) => console.error('Failed to load reconnect policy:', error));
+    invoke<ServerRefreshPolicy>('get_server_refresh_policy')
+      .then(setRefreshPolicy)
+      .catch((error) => console.error('Failed to load server refresh policy:', error));
+    invoke<AutoAwayPolicy>('get_auto_away_policy')
+      .then(setAutoAwayPolicy)
+      .catch((error) => console.error('Failed to load auto-away policy:', error));
+    invoke<BoardFetchPolicy>('get_board_fetch_policy')
+      .then(setBoardFetchPolicy)
+      .catch((error) => console.error('Failed to load message board fetch size:', error));
+    invoke<boolean>('get_history_encryption')
+      .then(setEncryptHistory)
+      .catch((error) => console.error('Failed to load history encryption setting:', error));
+  }, []);
+
+  const saveIdentity = async (update: Partial<ClientIdentity>) => {
+    if (!identity) return;
+    try {
+      setIdentity(await invoke<ClientIdentity>('set_client_identity', { identity: { ...identity, ...update } }));
+    } catch (error) {
+      showNotification.error(String(error), 'Client Identification');
+    }
+  };
+
+  const saveReconnectPolicy = async (update: Partial<ReconnectPolicy>) => {
+    if (!reconnectPolicy) return;
+    const policy = { ...reconnectPolicy, ...update };
+    try {
+      await invoke('set_reconnect_policy', { policy });
+      setReconnectPolicy(policy);
+    } catch (error) {
+      showNotification.error(String(error), 'Reconnect');
+    }
+  };
+
+  const saveRefreshPolicy = async (update: Partial<ServerRefreshPolicy>) => {
+    if (!refreshPolicy) return;
+    const policy = { ...refreshPolicy, ...update };
+    try {
+      await invoke('set_server_refresh_policy', { policy });
+      setRefreshPolicy(policy);
+    } catch (error) {
+      showNotification.error(String(error), 'Server Info Refresh');
+    }
+  };
+
+  const saveAutoAwayPolicy = async (update: Partial<AutoAwayPolicy>) => {
+    if (!autoAwayPolicy) return;
+    const policy = { ...autoAwayPolicy, ...update };
+    try {
+      await invoke('set_auto_away_policy', { policy });
+      setAutoAwayPolicy(policy);
+    } catch (error) {
+      showNotification.error(String(error), 'Auto-Away');
+    }
+  };
+
+  const saveBoardFetchPolicy = async (policy: BoardFetchPolicy) => {
+    try {
+      await invoke('set_board_fetch_policy', { policy });
+      setBoardFetchPolicy(policy);
+    } catch (error) {
+      showNotification.error(String(error), 'Message Board');
+    }
+  };
+
+  const saveEncryptHistory = async (enabled: boolean) => {
+    try {
+      await invoke('set_history_encryption', { enabled });
+      setEncryptHistory(enabled);
+    } catch (error) {
+      showNotification.error(String(error), 'History Encryption');
+    }
+  };
+
+  const handleClearCache = async () => {
+    try {
+      await invoke('clear_cache');
+      await refreshCacheStats();
+      showNotification.success('Cached news and message boards cleared', 'Cache Cleared');
+    } catch (error) {
+      showNotification.error(`Failed to clear cache: ${error}`, 'Error');
+    }
+  };
 
   useEffect(() => {
     setLocalUsername(username);
@@ -195,6 +337,31 @@ export default function GeneralSettingsTab() {
         </div>
       </div>
 
+      <div>
+        <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-1">
+          Time Zone and Locale
+        </label>
+        <p className="text-xs text-gray-500 dark:text-gray-400 mb-2">
+          Used for every timestamp in chat history, news and exported transcripts. Leave empty to follow the system.
+        </p>
+        <div className="flex gap-2">
+          <input
+            type="text"
+            defaultValue={timeZone ?? ''}
+            onBlur={(e) => setTimeZone(e.target.value.trim() || null)}
+            placeholder={Intl.DateTimeFormat().resolvedOptions().timeZone}
+            className="flex-1 px-3 py-2 border border-gray-300 dark:border-gray-600 rounded-md bg-white dark:bg-gray-800 text-gray-900 dark:text-white placeholder-gray-400 dark:placeholder-gray-500 focus:outline-none focus:ring-2 focus:ring-blue-500"
+          />
+          <input
+            type="text"
+            defaultValue={locale ?? ''}
+            onBlur={(e) => setLocale(e.target.value.trim() || null)}
+            placeholder={Intl.DateTimeFormat().resolvedOptions().locale}
+            className="w-32 px-3 py-2 border border-gray-300 dark:border-gray-600 rounded-md bg-white dark:bg-gray-800 text-gray-900 dark:text-white placeholder-gray-400 dark:placeholder-gray-500 focus:outline-none focus:ring-2 focus:ring-blue-500"
+          />
+        </div>
+      </div>
+
       <div>
         <div className="flex items-center justify-between">
           <div className="flex-1">
@@ -380,6 +547,30 @@ export default function GeneralSettingsTab() {
               </button>
             )}
           </div>
+          <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mt-4 mb-2">
+            Resource Forks
+          </label>
+          <select
+            value={forkMode}
+            onChange={(e) => setForkMode(e.target.value as ForkMode)}
+            className="px-3 py-2 border border-gray-300 dark:border-gray-600 rounded-md bg-white dark:bg-gray-700 text-sm text-gray-900 dark:text-white"
+          >
+            <option value="discard">Discard (data fork only)</option>
+            <option value="appleDouble">Keep as AppleDouble (._ file)</option>
+            <option value="macBinary">Save as MacBinary (.bin)</option>
+          </select>
+          <p className="text-xs text-gray-500 dark:text-gray-400 mt-1">
+            Classic Mac files carry Finder info and a resource fork alongside their data.
+          </p>
+          <label className="flex items-center gap-2 text-sm text-gray-700 dark:text-gray-300 mt-4">
+            <input
+              type="checkbox"
+              checked={skipExistingDownloads}
+              onChange={(e) => setSkipExistingDownloads(e.target.checked)}
+              className="w-4 h-4 text-blue-600 border-gray-300 rounded focus:ring-blue-500"
+            />
+            Skip files already in the download folder with the same name and size
+          </label>
         </div>
       )}
 
@@ -398,6 +589,277 @@ export default function GeneralSettingsTab() {
           {isAddingDefaults ? 'Adding...' : 'Re-add Default Servers & Trackers'}
         </button>
       </div>
+
+      {identity && (
+        <div className="border-t border-gray-200 dark:border-gray-700 pt-6">
+          <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-2">
+            Client Identification
+          </label>
+          <p className="text-xs text-gray-500 dark:text-gray-400 mb-3">
+            Sent at login; newer servers show it in their logs. Applies to new connections.
+          </p>
+          <label className="flex items-center gap-2 text-sm text-gray-700 dark:text-gray-300 mb-3">
+            <input
+              type="checkbox"
+              checked={identity.sendIdentification}
+              onChange={(e) => saveIdentity({ sendIdentification: e.target.checked })}
+              className="w-4 h-4 text-blue-600 border-gray-300 rounded focus:ring-blue-500"
+            />
+            Send client name and version ({identity.name} {identity.version})
+          </label>
+          <div className="flex gap-3">
+            <input
+              type="text"
+              defaultValue={identity.name}
+              onBlur={(e) => e.target.value !== identity.name && saveIdentity({ name: e.target.value })}
+              placeholder="Client name"
+              className="flex-1 px-3 py-2 border border-gray-300 dark:border-gray-600 rounded-md bg-white dark:bg-gray-800 text-gray-900 dark:text-white text-sm focus:outline-none focus:ring-2 focus:ring-blue-500"
+            />
+            <input
+              type="number"
+              min={1}
+              max={65535}
+              defaultValue={identity.versionNumber}
+              onBlur={(e) => {
+                const value = Number(e.target.value);
+                if (value >= 1 && value <= 65535 && value !== identity.versionNumber) {
+                  saveIdentity({ versionNumber: value });
+                }
+              }}
+              title="Protocol version number"
+              className="w-24 px-3 py-2 border border-gray-300 dark:border-gray-600 rounded-md bg-white dark:bg-gray-800 text-gray-900 dark:text-white text-sm focus:outline-none focus:ring-2 focus:ring-blue-500"
+            />
+          </div>
+        </div>
+      )}
+
+      {reconnectPolicy && (
+        <div className="border-t border-gray-200 dark:border-gray-700 pt-6">
+          <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-2">
+            Reconnect
+          </label>
+          <label className="flex items-center gap-2 text-sm text-gray-700 dark:text-gray-300 mb-3">
+            <input
+              type="checkbox"
+              checked={reconnectPolicy.enabled}
+              onChange={(e) => saveReconnectPolicy({ enabled: e.target.checked })}
+              className="w-4 h-4 text-blue-600 border-gray-300 rounded focus:ring-blue-500"
+            />
+            Reconnect automatically when a connection drops
+          </label>
+          <div className="flex gap-3 text-sm text-gray-700 dark:text-gray-300">
+            {([
+              ['maxRetries', 'Attempts', 1, 100],
+              ['initialDelaySecs', 'First delay (s)', 1, 3600],
+              ['maxDelaySecs', 'Max delay (s)', 1, 3600],
+            ] as [keyof ReconnectPolicy, string, number, number][]).map(([key, label, min, max]) => (
+              <label key={key} className="flex flex-col gap-1">
+                <span className="text-xs text-gray-500 dark:text-gray-400">{label}</span>
+                <input
+                  type="number"
+                  min={min}
+                  max={max}
+                  disabled={!reconnectPolicy.enabled}
+                  defaultValue={reconnectPolicy[key] as number}
+                  onBlur={(e) => {
+                    const value = Number(e.target.value);
+                    if (value >= min && value <= max && value !== reconnectPolicy[key]) {
+                      saveReconnectPolicy({ [key]: value });
+                    }
+                  }}
+                  className="w-28 px-3 py-2 border border-gray-300 dark:border-gray-600 rounded-md bg-white dark:bg-gray-800 text-gray-900 dark:text-white text-sm focus:outline-none focus:ring-2 focus:ring-blue-500 disabled:opacity-50"
+                />
+              </label>
+            ))}
+          </div>
+          <label className="flex flex-col gap-1 mt-3 text-sm text-gray-700 dark:text-gray-300">
+            <span className="text-xs text-gray-500 dark:text-gray-400">
+              Treat a connection as lost after this many silent seconds (0 = never)
+            </span>
+            <input
+              type="number"
+              min={0}
+              max={3600}
+              defaultValue={reconnectPolicy.watchdogTimeoutSecs}
+              onBlur={(e) => {
+                const value = Number(e.target.value);
+                if ((value === 0 || (value >= 60 && value <= 3600)) && value !== reconnectPolicy.watchdogTimeoutSecs) {
+                  saveReconnectPolicy({ watchdogTimeoutSecs: value });
+                }
+              }}
+              className="w-28 px-3 py-2 border border-gray-300 dark:border-gray-600 rounded-md bg-white dark:bg-gray-800 text-gray-900 dark:text-white text-sm focus:outline-none focus:ring-2 focus:ring-blue-500"
+            />
+          </label>
+        </div>
+      )}
+
+      {refreshPolicy && (
+        <div className="border-t border-gray-200 dark:border-gray-700 pt-6">
+          <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-2">
+            Server Info Refresh
+          </label>
+          <label className="flex items-center gap-2 text-sm text-gray-700 dark:text-gray-300 mb-3">
+            <input
+              type="checkbox"
+              checked={refreshPolicy.enabled}
+              onChange={(e) => saveRefreshPolicy({ enabled: e.target.checked })}
+              className="w-4 h-4 text-blue-600 border-gray-300 rounded focus:ring-blue-500"
+            />
+            Check connected servers for a new banner or server info periodically
+          </label>
+          <label className="flex flex-col gap-1 text-sm text-gray-700 dark:text-gray-300">
+            <span className="text-xs text-gray-500 dark:text-gray-400">Interval (minutes)</span>
+            <input
+              type="number"
+              min={1}
+              max={1440}
+              disabled={!refreshPolicy.enabled}
+              defaultValue={Math.round(refreshPolicy.intervalSecs / 60)}
+              onBlur={(e) => {
+                const value = Number(e.target.value);
+                if (value >= 1 && value <= 1440 && value * 60 !== refreshPolicy.intervalSecs) {
+                  saveRefreshPolicy({ intervalSecs: value * 60 });
+                }
+              }}
+              className="w-28 px-3 py-2 border border-gray-300 dark:border-gray-600 rounded-md bg-white dark:bg-gray-800 text-gray-900 dark:text-white text-sm focus:outline-none focus:ring-2 focus:ring-blue-500 disabled:opacity-50"
+            />
+          </label>
+        </div>
+      )}
+
+      {autoAwayPolicy && (
+        <div className="border-t border-gray-200 dark:border-gray-700 pt-6">
+          <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-2">
+            Auto-Away
+          </label>
+          <label className="flex items-center gap-2 text-sm text-gray-700 dark:text-gray-300 mb-3">
+            <input
+              type="checkbox"
+              checked={autoAwayPolicy.enabled}
+              onChange={(e) => saveAutoAwayPolicy({ enabled: e.target.checked })}
+              className="w-4 h-4 text-blue-600 border-gray-300 rounded focus:ring-blue-500"
+            />
+            Mark me away after a while without chatting
+          </label>
+          <div className="flex flex-wrap gap-4">
+            <label className="flex flex-col gap-1 text-sm text-gray-700 dark:text-gray-300">
+              <span className="text-xs text-gray-500 dark:text-gray-400">Idle time (minutes)</span>
+              <input
+                type="number"
+                min={1}
+                max={1440}
+                disabled={!autoAwayPolicy.enabled}
+                defaultValue={Math.round(autoAwayPolicy.idleSecs / 60)}
+                onBlur={(e) => {
+                  const value = Number(e.target.value);
+                  if (value >= 1 && value <= 1440 && value * 60 !== autoAwayPolicy.idleSecs) {
+                    saveAutoAwayPolicy({ idleSecs: value * 60 });
+                  }
+                }}
+                className="w-28 px-3 py-2 border border-gray-300 dark:border-gray-600 rounded-md bg-white dark:bg-gray-800 text-gray-900 dark:text-white text-sm focus:outline-none focus:ring-2 focus:ring-blue-500 disabled:opacity-50"
+              />
+            </label>
+            <label className="flex flex-col gap-1 text-sm text-gray-700 dark:text-gray-300">
+              <span className="text-xs text-gray-500 dark:text-gray-400">Nickname suffix</span>
+              <input
+                type="text"
+                maxLength={16}
+                placeholder=" [away]"
+                defaultValue={autoAwayPolicy.nicknameSuffix ?? ''}
+                onBlur={(e) => {
+                  const value = e.target.value || null;
+                  if (value !== autoAwayPolicy.nicknameSuffix) {
+                    saveAutoAwayPolicy({ nicknameSuffix: value });
+                  }
+                }}
+                className="w-36 px-3 py-2 border border-gray-300 dark:border-gray-600 rounded-md bg-white dark:bg-gray-800 text-gray-900 dark:text-white text-sm focus:outline-none focus:ring-2 focus:ring-blue-500"
+              />
+            </label>
+          </div>
+          <label className="flex flex-col gap-1 text-sm text-gray-700 dark:text-gray-300 mt-3">
+            <span className="text-xs text-gray-500 dark:text-gray-400">Away message, sent to users who message you</span>
+            <input
+              type="text"
+              maxLength={255}
+              defaultValue={autoAwayPolicy.message ?? ''}
+              onBlur={(e) => {
+                const value = e.target.value.trim() || null;
+                if (value !== autoAwayPolicy.message) {
+                  saveAutoAwayPolicy({ message: value });
+                }
+              }}
+              className="w-full px-3 py-2 border border-gray-300 dark:border-gray-600 rounded-md bg-white dark:bg-gray-800 text-gray-900 dark:text-white text-sm focus:outline-none focus:ring-2 focus:ring-blue-500"
+            />
+          </label>
+          <p className="text-xs text-gray-500 dark:text-gray-400 mt-2">
+            Type /away [message] or /back in chat to set it yourself.
+          </p>
+        </div>
+      )}
+
+      {boardFetchPolicy && (
+        <div className="border-t border-gray-200 dark:border-gray-700 pt-6">
+          <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-2">
+            Message Board
+          </label>
+          <label className="flex flex-col gap-1 text-sm text-gray-700 dark:text-gray-300">
+            <span className="text-xs text-gray-500 dark:text-gray-400">Load at most this much at a time (KiB)</span>
+            <input
+              type="number"
+              min={16}
+              max={16384}
+              defaultValue={boardFetchPolicy.maxKib}
+              onBlur={(e) => {
+                const value = Number(e.target.value);
+                if (value >= 16 && value <= 16384 && value !== boardFetchPolicy.maxKib) {
+                  saveBoardFetchPolicy({ maxKib: value });
+                }
+              }}
+              className="w-28 px-3 py-2 border border-gray-300 dark:border-gray-600 rounded-md bg-white dark:bg-gray-800 text-gray-900 dark:text-white text-sm focus:outline-none focus:ring-2 focus:ring-blue-500"
+            />
+          </label>
+          <p className="text-xs text-gray-500 dark:text-gray-400 mt-2">
+            Larger boards show a button to load the rest.
+          </p>
+        </div>
+      )}
+
+      {encryptHistory !== null && (
+        <div className="border-t border-gray-200 dark:border-gray-700 pt-6">
+          <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-2">
+            History
+          </label>
+          <label className="flex items-center gap-2 text-sm text-gray-700 dark:text-gray-300">
+            <input
+              type="checkbox"
+              checked={encryptHistory}
+              onChange={(e) => saveEncryptHistory(e.target.checked)}
+              className="w-4 h-4 text-blue-600 border-gray-300 rounded focus:ring-blue-500"
+            />
+            Encrypt chat and private message history on disk
+          </label>
+          <p className="text-xs text-gray-500 dark:text-gray-400 mt-1">
+            The key is kept in your system keychain.
+          </p>
+        </div>
+      )}
+
+      <div className="border-t border-gray-200 dark:border-gray-700 pt-6">
+        <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-2">
+          News Cache
+        </label>
+        <p className="text-xs text-gray-500 dark:text-gray-400 mb-3">
+          News articles and message boards you have read are kept for offline reading.
+          {' '}{cacheStats.entries} item{cacheStats.entries === 1 ? '' : 's'}, {(cacheStats.bytes / 1024).toFixed(1)} KB.
+        </p>
+        <button
+          onClick={handleClearCache}
+          disabled={cacheStats.entries === 0}
+          className="px-4 py-2 border border-gray-300 dark:border-gray-600 text-gray-700 dark:text-gray-300 rounded-md font-medium hover:bg-gray-100 dark:hover:bg-gray-700 disabled:opacity-50 disabled:cursor-not-allowed transition-colors"
+        >
+          Clear Cache
+        </button>
+      </div>
     </div>
   );
 }
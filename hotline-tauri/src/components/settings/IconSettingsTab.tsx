@@ -1,4 +1,5 @@
 import { useState, useEffect, useRef } from 'react';
+import { invoke } from '@tauri-apps/api/core';
 import { usePreferencesStore } from '../../stores/preferencesStore';
 import UserIcon from '../users/UserIcon';
 
@@ -119,6 +120,14 @@ export default function IconSettingsTab() {
   const [hoveredIconId, setHoveredIconId] = useState<number | null>(null);
   const selectedIconRef = useRef<HTMLDivElement>(null);
 
+  const selectIcon = (iconId: number) => {
+    setUserIconId(iconId);
+    // Show the new icon on connected servers right away
+    invoke('update_user_info', { username: usePreferencesStore.getState().username, iconId }).catch(() => {
+      // Silently ignore - no servers connected or update failed on some
+    });
+  };
+
   useEffect(() => {
     // Scroll to selected icon when component mounts
     if (selectedIconRef.current) {
@@ -143,7 +152,7 @@ export default function IconSettingsTab() {
               <div
                 key={iconId}
                 ref={isSelected ? selectedIconRef : null}
-                onClick={() => setUserIconId(iconId)}
+                onClick={() => selectIcon(iconId)}
                 onMouseEnter={() => setHoveredIconId(iconId)}
                 onMouseLeave={() => setHoveredIconId(null)}
                 className={`
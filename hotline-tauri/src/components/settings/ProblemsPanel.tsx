@@ -0,0 +1,67 @@
+import { useCallback, useEffect, useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { formatTimestamp } from '../../utils/time';
+
+interface Failure {
+  timestamp: number;
+  operation: string;
+  server: string | null;
+  category: 'connection' | 'protocol' | 'transfer' | 'tracker';
+  retryable: boolean;
+  error: string;
+}
+
+const CATEGORY_LABELS: Record<Failure['category'], string> = {
+  connection: 'Connection',
+  protocol: 'Server error',
+  transfer: 'Transfer',
+  tracker: 'Tracker',
+};
+
+export default function ProblemsPanel() {
+  const [failures, setFailures] = useState<Failure[]>([]);
+
+  const refresh = useCallback(() => {
+    invoke<Failure[]>('get_recent_failures', { limit: 50 })
+      .then(setFailures)
+      .catch((error) => console.error('Failed to load recent failures:', error));
+  }, []);
+
+  useEffect(() => {
+    refresh();
+  }, [refresh]);
+
+  return (
+    <div>
+      <div className="flex items-center justify-between mb-2">
+        <h4 className="text-sm font-semibold text-gray-700 dark:text-gray-300">
+          Problems
+        </h4>
+        <button
+          onClick={refresh}
+          className="text-xs text-blue-600 dark:text-blue-400 hover:underline"
+        >
+          Refresh
+        </button>
+      </div>
+      {failures.length === 0 ? (
+        <p className="text-xs text-gray-500 dark:text-gray-400">Nothing has gone wrong this session.</p>
+      ) : (
+        <ul className="max-h-48 overflow-y-auto text-xs divide-y divide-gray-200 dark:divide-gray-700 border border-gray-200 dark:border-gray-700 rounded-md">
+          {failures.map((failure, index) => (
+            <li key={`${failure.timestamp}-${index}`} className="px-2 py-1.5">
+              <div className="flex items-center gap-2 text-gray-500 dark:text-gray-400">
+                <span>{formatTimestamp(failure.timestamp, 'time')}</span>
+                <span className="font-medium text-gray-700 dark:text-gray-300">{CATEGORY_LABELS[failure.category]}</span>
+                <span>{failure.operation}</span>
+                {failure.server && <span className="truncate">{failure.server}</span>}
+                {failure.retryable && <span className="ml-auto text-gray-400">may retry</span>}
+              </div>
+              <p className="text-gray-700 dark:text-gray-300 break-words">{failure.error}</p>
+            </li>
+          ))}
+        </ul>
+      )}
+    </div>
+  );
+}
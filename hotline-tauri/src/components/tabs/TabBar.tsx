@@ -26,6 +26,21 @@ export default function TabBar() {
     removeTab(tabId);
   };
 
+  const handleDetachTab = async (e: React.MouseEvent, tabId: string) => {
+    e.stopPropagation();
+
+    const tab = tabs.find(t => t.id === tabId);
+    if (tab?.type !== 'server' || !tab.serverId) return;
+
+    // The connection stays up and moves to its own window
+    try {
+      await invoke('open_server_window', { serverId: tab.serverId, title: tab.title });
+      removeTab(tabId);
+    } catch (error) {
+      console.error('Failed to open server window:', error);
+    }
+  };
+
   // Detect mobile (iOS/iPadOS/Android) for safe area padding
   const isMobile = typeof window !== 'undefined' && (
     /iPad|iPhone|iPod|Android/.test(navigator.userAgent) ||
@@ -76,6 +91,18 @@ export default function TabBar() {
               </span>
             )}
             
+            {/* Open in own window - desktop only */}
+            {tab.type === 'server' && !isMobile && (
+              <button
+                onClick={(e) => handleDetachTab(e, tab.id)}
+                className="flex-shrink-0 ml-1 w-4 h-4 rounded hover:bg-gray-200 dark:hover:bg-gray-600 flex items-center justify-center text-gray-500 hover:text-gray-700 dark:text-gray-400 dark:hover:text-gray-200"
+                aria-label="Open in new window"
+                title="Open in new window"
+              >
+                ⧉
+              </button>
+            )}
+
             {/* Close button - only show for server tabs, not tracker tabs */}
             {tab.type === 'server' && (
               <button
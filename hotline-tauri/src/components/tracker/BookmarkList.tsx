@@ -1,11 +1,14 @@
-import { useState } from 'react';
+import { useEffect, useState } from 'react';
 import { invoke } from '@tauri-apps/api/core';
 import { useAppStore } from '../../stores/appStore';
 import { usePreferencesStore } from '../../stores/preferencesStore';
 import { useIsMobile } from '../../hooks/useIsMobile';
+import { connectToServer, connectErrorMessage } from '../../utils/connect';
 import type { Bookmark, ServerBookmark } from '../../types';
 import EditBookmarkDialog from './EditBookmarkDialog';
 import BookmarkInfoDialog from './BookmarkInfoDialog';
+import OfflineViewDialog from './OfflineViewDialog';
+import { formatOccupancy, isFull } from '../../utils/occupancy';
 import { useContextMenu, ContextMenuRenderer, type ContextMenuItem } from '../common/ContextMenu';
 import {
   DndContext,
@@ -25,9 +28,48 @@ import {
 } from '@dnd-kit/sortable';
 import { CSS } from '@dnd-kit/utilities';
 
+interface TrackerServer {
+  address: string;
+  port: number;
+  users: number;
+  name?: string | null;
+  description?: string | null;
+  userLimit?: number | null;
+}
+
+interface TrackerListing {
+  servers: TrackerServer[];
+  fetchedAt: number;
+  stale: boolean;
+}
+
+interface TrackerDiff {
+  servers: TrackerServer[];
+  fetchedAt: number;
+  added: TrackerServer[];
+  removed: TrackerServer[];
+  changed: { address: string; port: number; name?: string | null; before: number; after: number }[];
+}
+
+interface TrackerBrowse {
+  servers: (TrackerServer & { trackers: string[] })[];
+  failures: { trackerId: string; error: string }[];
+}
+
+interface TrackerSearchResult extends TrackerServer {
+  trackerAddress: string;
+  trackerPort?: number | null;
+}
+
+// Identifies a server as listed by one tracker
+const trackerServerKey = (trackerAddress: string, trackerPort: number | null | undefined, address: string, port: number) =>
+  `${trackerAddress}:${trackerPort || ''}/${address}:${port}`;
+
 interface BookmarkListProps {
   bookmarks: Bookmark[];
   searchQuery?: string;
+  // Bumped by the toolbar's Refresh button to reload every tracker
+  refreshAllTrackers?: number;
 }
 
 interface SortableItemProps {
@@ -64,17 +106,22 @@ function SortableItem({ id, children }: SortableItemProps) {
   );
 }
 
-export default function BookmarkList({ bookmarks, searchQuery = '' }: BookmarkListProps) {
+export default function BookmarkList({ bookmarks, searchQuery = '', refreshAllTrackers = 0 }: BookmarkListProps) {
   const { removeBookmark, addActiveServer, addTab, setBookmarks, tabs, serverInfo, setActiveTab } = useAppStore();
   const { username, userIconId, autoDetectTls } = usePreferencesStore();
   const [editingBookmark, setEditingBookmark] = useState<Bookmark | null>(null);
   const [addingBookmark, setAddingBookmark] = useState<Bookmark | null>(null);
   const [deletingId, setDeletingId] = useState<string | null>(null);
   const [infoBookmark, setInfoBookmark] = useState<Bookmark | null>(null);
+  const [offlineBookmark, setOfflineBookmark] = useState<Bookmark | null>(null);
   const [connectingId, setConnectingId] = useState<string | null>(null);
   const [connectionErrors, setConnectionErrors] = useState<Map<string, string>>(new Map());
   const [expandedTrackers, setExpandedTrackers] = useState<Set<string>>(new Set());
   const [trackerServers, setTrackerServers] = useState<Map<string, ServerBookmark[]>>(new Map());
+  // IDs of bookmarks matching the search by name, address, tags or notes
+  const [matchingIds, setMatchingIds] = useState<Set<string> | null>(null);
+  // Tracker servers matching the search, keyed by trackerServerKey
+  const [matchingServers, setMatchingServers] = useState<Set<string> | null>(null);
   const [loadingTrackers, setLoadingTrackers] = useState<Set<string>>(new Set());
   const [mobileActionServer, setMobileActionServer] = useState<{ trackerId: string; server: ServerBookmark } | null>(null);
   const isMobile = useIsMobile();
@@ -131,7 +178,7 @@ export default function BookmarkList({ bookmarks, searchQuery = '' }: BookmarkLi
     });
 
     try {
-      const result = await invoke<{ serverId: string; tls: boolean; port: number }>('connect_to_server', {
+      const result = await connectToServer({
         bookmark,
         username,
         userIconId,
@@ -160,7 +207,7 @@ export default function BookmarkList({ bookmarks, searchQuery = '' }: BookmarkLi
       console.error('Failed to connect:', error);
       
       // Format error message for display
-      const errorMessage = String(error);
+      const errorMessage = connectErrorMessage(error);
       let userFriendlyMessage = 'Failed to connect to server.';
       
       if (errorMessage.includes('Cannot connect to tracker') || errorMessage.includes('tracker')) {
@@ -192,41 +239,48 @@ export default function BookmarkList({ bookmarks, searchQuery = '' }: BookmarkLi
     }
   };
 
-  const fetchTrackerServers = async (trackerId: string) => {
+  const showTrackerServers = (trackerId: string, servers: TrackerServer[]) => {
+    // Convert TrackerServer to ServerBookmark format
+    const serverBookmarks: ServerBookmark[] = servers.map((server, index) => ({
+      id: `${trackerId}-${index}`,
+      name: server.name || server.address,
+      description: server.description || '',
+      address: server.address,
+      port: server.port,
+      users: server.users,
+      userLimit: server.userLimit,
+    }));
+
+    setTrackerServers((prev) => {
+      const next = new Map(prev);
+      next.set(trackerId, serverBookmarks);
+      return next;
+    });
+  };
+
+  const fetchTrackerServers = async (trackerId: string, force = false) => {
     const tracker = bookmarks.find(b => b.id === trackerId);
     if (!tracker || tracker.type !== 'tracker') return;
-    
+    const target = { address: tracker.address, port: tracker.port || undefined };
+
+    // Show the last listing right away; only go back to the tracker when it's
+    // stale or the user asked for a refresh
+    const cached = await invoke<TrackerListing | null>('get_cached_tracker_servers', target);
+    if (cached) {
+      showTrackerServers(trackerId, cached.servers);
+      if (!cached.stale && !force) return;
+    }
+
     setLoadingTrackers((prev) => new Set(prev).add(trackerId));
     
     try {
-      interface TrackerServer {
-        address: string;
-        port: number;
-        users: number;
-        name?: string | null;
-        description?: string | null;
+      const diff = await invoke<TrackerDiff>('refresh_tracker', target);
+      if (cached) {
+        console.log(
+          `Tracker ${tracker.name}: ${diff.added.length} added, ${diff.removed.length} removed, ${diff.changed.length} changed`,
+        );
       }
-      
-      const servers = await invoke<TrackerServer[]>('fetch_tracker_servers', {
-        address: tracker.address,
-        port: tracker.port || undefined,
-      });
-      
-      // Convert TrackerServer to ServerBookmark format
-      const serverBookmarks: ServerBookmark[] = servers.map((server, index) => ({
-        id: `${trackerId}-${index}`,
-        name: server.name || server.address,
-        description: server.description || '',
-        address: server.address,
-        port: server.port,
-        users: server.users,
-      }));
-      
-      setTrackerServers((prev) => {
-        const next = new Map(prev);
-        next.set(trackerId, serverBookmarks);
-        return next;
-      });
+      showTrackerServers(trackerId, diff.servers);
       
       // Clear any previous errors
       setConnectionErrors((prev) => {
@@ -275,11 +329,11 @@ export default function BookmarkList({ bookmarks, searchQuery = '' }: BookmarkLi
   const handleRefreshTracker = async (trackerId: string) => {
     // Refresh: re-fetch servers if tracker is expanded
     if (expandedTrackers.has(trackerId)) {
-      await fetchTrackerServers(trackerId);
+      await fetchTrackerServers(trackerId, true);
     } else {
       // If not expanded, expand it (which will fetch)
       setExpandedTrackers((prev) => new Set(prev).add(trackerId));
-      await fetchTrackerServers(trackerId);
+      await fetchTrackerServers(trackerId, true);
     }
   };
 
@@ -300,6 +354,9 @@ export default function BookmarkList({ bookmarks, searchQuery = '' }: BookmarkLi
     
     console.log('Connecting to server from tracker:', bookmark.name, bookmark.address, bookmark.port);
     await handleConnect(bookmark);
+    // Connecting (or being turned away) may have taught the backend the
+    // server's user limit; show it from the cached listing
+    void fetchTrackerServers(trackerId);
   };
 
   const handleAddTrackerServerToBookmarks = (_trackerId: string, server: ServerBookmark) => {
@@ -321,6 +378,38 @@ export default function BookmarkList({ bookmarks, searchQuery = '' }: BookmarkLi
     });
   };
 
+  useEffect(() => {
+    if (!refreshAllTrackers) return;
+    const trackerIds = bookmarks.filter((b) => b.type === 'tracker').map((b) => b.id);
+    if (trackerIds.length === 0) return;
+
+    let cancelled = false;
+    setExpandedTrackers(new Set(trackerIds));
+    setLoadingTrackers(new Set(trackerIds));
+    invoke<TrackerBrowse>('fetch_all_trackers')
+      .then((browse) => {
+        if (cancelled) return;
+        for (const trackerId of trackerIds) {
+          showTrackerServers(trackerId, browse.servers.filter((server) => server.trackers.includes(trackerId)));
+        }
+        setConnectionErrors((prev) => {
+          const next = new Map(prev);
+          trackerIds.forEach((id) => next.delete(id));
+          browse.failures.forEach((failure) => next.set(failure.trackerId, `Failed to fetch servers: ${failure.error}`));
+          return next;
+        });
+      })
+      .catch((error) => console.error('Failed to fetch trackers:', error))
+      .finally(() => {
+        if (!cancelled) setLoadingTrackers(new Set());
+      });
+    return () => {
+      cancelled = true;
+    };
+    // Only the Refresh button should trigger this, not bookmark edits
+    // eslint-disable-next-line react-hooks/exhaustive-deps
+  }, [refreshAllTrackers]);
+
   // Filter helper functions
   const matchesSearch = (text: string): boolean => {
     if (!searchQuery.trim()) return true;
@@ -328,18 +417,58 @@ export default function BookmarkList({ bookmarks, searchQuery = '' }: BookmarkLi
     return text.toLowerCase().includes(query);
   };
 
+  useEffect(() => {
+    if (!searchQuery.trim()) {
+      setMatchingIds(null);
+      return;
+    }
+    let cancelled = false;
+    invoke<Bookmark[]>('search_bookmarks', { query: searchQuery })
+      .then((matches) => {
+        if (!cancelled) setMatchingIds(new Set(matches.map((b) => b.id)));
+      })
+      .catch((error) => console.error('Failed to search bookmarks:', error));
+    return () => {
+      cancelled = true;
+    };
+  }, [searchQuery, bookmarks]);
+
+  useEffect(() => {
+    if (!searchQuery.trim()) {
+      setMatchingServers(null);
+      return;
+    }
+    let cancelled = false;
+    invoke<TrackerSearchResult[]>('search_tracker_servers', { query: searchQuery })
+      .then((matches) => {
+        if (cancelled) return;
+        setMatchingServers(
+          new Set(matches.map((m) => trackerServerKey(m.trackerAddress, m.trackerPort, m.address, m.port))),
+        );
+      })
+      .catch((error) => console.error('Failed to search tracker servers:', error));
+    return () => {
+      cancelled = true;
+    };
+  }, [searchQuery, trackerServers]);
+
   const shouldShowBookmark = (bookmark: Bookmark): boolean => {
     if (!searchQuery.trim()) return true;
-    // For regular bookmarks, filter by name
+    // For regular bookmarks, filter by name, address, tags and notes
     if (bookmark.type !== 'tracker') {
-      return matchesSearch(bookmark.name);
+      return matchingIds ? matchingIds.has(bookmark.id) : matchesSearch(bookmark.name);
     }
     // Trackers are always shown (they won't be filtered)
     return true;
   };
 
-  const getFilteredTrackerServers = (servers: ServerBookmark[]): ServerBookmark[] => {
+  const getFilteredTrackerServers = (tracker: Bookmark, servers: ServerBookmark[]): ServerBookmark[] => {
     if (!searchQuery.trim()) return servers;
+    if (matchingServers) {
+      return servers.filter((server) =>
+        matchingServers.has(trackerServerKey(tracker.address, tracker.port, server.address, server.port)),
+      );
+    }
     return servers.filter(server => 
       matchesSearch(server.name) || 
       (server.description && matchesSearch(server.description))
@@ -390,7 +519,7 @@ export default function BookmarkList({ bookmarks, searchQuery = '' }: BookmarkLi
           const isTracker = bookmark.type === 'tracker';
           const isExpanded = expandedTrackers.has(bookmark.id);
           const allServers = trackerServers.get(bookmark.id) || [];
-          const servers = getFilteredTrackerServers(allServers);
+          const servers = getFilteredTrackerServers(bookmark, allServers);
           const isLoading = loadingTrackers.has(bookmark.id);
           const isEven = index % 2 === 0;
           
@@ -645,8 +774,10 @@ export default function BookmarkList({ bookmarks, searchQuery = '' }: BookmarkLi
                         {/* User count with animated dot */}
                         {server.users > 0 && (
                           <div className="flex items-center gap-1 text-xs text-gray-500 dark:text-gray-400 flex-shrink-0">
-                            <span>{server.users}</span>
-                            <div className="w-1.5 h-1.5 bg-green-500 rounded-full animate-pulse"></div>
+                            <span title={server.userLimit ? `${server.userLimit} users allowed` : undefined}>
+                              {formatOccupancy(server.users, server.userLimit)}
+                            </span>
+                            <div className={`w-1.5 h-1.5 rounded-full animate-pulse ${isFull(server.users, server.userLimit) ? 'bg-red-500' : 'bg-green-500'}`}></div>
                           </div>
                         )}
 
@@ -743,6 +874,11 @@ export default function BookmarkList({ bookmarks, searchQuery = '' }: BookmarkLi
                         icon: 'ℹ️',
                         action: () => setInfoBookmark(bookmark),
                       },
+                      {
+                        label: 'Browse Offline...',
+                        icon: '🗄️',
+                        action: () => setOfflineBookmark(bookmark),
+                      },
                       {
                         label: 'Edit Bookmark...',
                         icon: '✏️',
@@ -899,6 +1035,14 @@ export default function BookmarkList({ bookmarks, searchQuery = '' }: BookmarkLi
         />
       )}
 
+      {/* Offline view */}
+      {offlineBookmark && (
+        <OfflineViewDialog
+          bookmark={offlineBookmark}
+          onClose={() => setOfflineBookmark(null)}
+        />
+      )}
+
       {/* Edit dialog */}
       {editingBookmark && (
         <EditBookmarkDialog
@@ -951,7 +1095,7 @@ export default function BookmarkList({ bookmarks, searchQuery = '' }: BookmarkLi
               )}
               <p className="text-xs text-gray-400 dark:text-gray-500">
                 {mobileActionServer.server.address}:{mobileActionServer.server.port}
-                {mobileActionServer.server.users > 0 && ` · ${mobileActionServer.server.users} user${mobileActionServer.server.users !== 1 ? 's' : ''}`}
+                {mobileActionServer.server.users > 0 && ` · ${formatOccupancy(mobileActionServer.server.users, mobileActionServer.server.userLimit)} user${mobileActionServer.server.users !== 1 ? 's' : ''}`}
               </p>
             </div>
             <div className="px-4 pt-2 space-y-1">
@@ -2,6 +2,7 @@ import { useState, useEffect, useRef, useCallback } from 'react';
 import { invoke } from '@tauri-apps/api/core';
 import { useAppStore } from '../../stores/appStore';
 import { usePreferencesStore } from '../../stores/preferencesStore';
+import { connectToServer, connectErrorMessage } from '../../utils/connect';
 import type { Bookmark } from '../../types';
 
 interface ConnectDialogProps {
@@ -48,53 +49,38 @@ export default function ConnectDialog({ onClose }: ConnectDialogProps) {
     setTimeout(onClose, 300);
   };
 
-  const parseHotlineUrl = (raw: string): { address: string; port: number; tls: boolean; filePath?: string[] } | null => {
-    const trimmed = raw.trim();
-    const match = trimmed.match(/^hotline:\/\/([^/:]+)(?::(\d+))?(\/.*)?$/i);
-    if (!match) return null;
-    const address = match[1];
-    const port = match[2] ? parseInt(match[2]) : 5500;
-    const tls = port === 5600;
-    // Parse file path: /files/folder1/folder2 → ['folder1', 'folder2']
-    let filePath: string[] | undefined;
-    if (match[3]) {
-      const pathStr = match[3].replace(/^\/files\/?/i, '').replace(/\/$/, '');
-      if (pathStr) {
-        filePath = pathStr.split('/').map(s => decodeURIComponent(s));
-      }
-    }
-    return { address, port, tls, filePath };
-  };
-
   const handleSubmit = async (e: React.FormEvent) => {
     e.preventDefault();
     setError(null);
 
-    let address = formData.address;
-    let port = parseInt(formData.port);
-    let tls = formData.tls;
-    let bookmarkType: 'server' | 'tracker' = formData.type === 'tracker' ? 'tracker' : 'server';
+    const bookmarkType: 'server' | 'tracker' = formData.type === 'tracker' ? 'tracker' : 'server';
+    let bookmark: Bookmark;
     let filePath: string[] | undefined;
 
     if (formData.type === 'url') {
-      const parsed = parseHotlineUrl(formData.url);
-      if (!parsed) return;
-      address = parsed.address;
-      port = parsed.port;
-      tls = parsed.tls;
-      filePath = parsed.filePath;
+      // Quick connect addresses are parsed by the backend, credentials and all
+      try {
+        const parsed = await invoke<{ bookmark: Bookmark; path: string[] }>('parse_server_address', { address: formData.url });
+        bookmark = parsed.bookmark;
+        filePath = parsed.path;
+      } catch (err) {
+        setError(String(err));
+        return;
+      }
+    } else {
+      const port = parseInt(formData.port);
+      bookmark = {
+        id: crypto.randomUUID(),
+        name: formData.name || `${formData.address}:${port}`,
+        address: formData.address,
+        port,
+        login: formData.login,
+        password: formData.password || undefined,
+        tls: formData.tls,
+        type: bookmarkType,
+      };
     }
-
-    const bookmark: Bookmark = {
-      id: crypto.randomUUID(),
-      name: formData.type === 'url' ? address : (formData.name || `${address}:${port}`),
-      address,
-      port,
-      login: formData.login,
-      password: formData.password || undefined,
-      tls,
-      type: bookmarkType,
-    };
+    const { address, tls } = bookmark;
 
     try {
       // Save bookmark if requested (not for URL type)
@@ -125,7 +111,7 @@ export default function ConnectDialog({ onClose }: ConnectDialogProps) {
 
       // Connect to the server
       setConnecting(true);
-      const result = await invoke<{ serverId: string; tls: boolean; port: number }>('connect_to_server', {
+      const result = await connectToServer({
         bookmark,
         username,
         userIconId,
@@ -152,7 +138,7 @@ export default function ConnectDialog({ onClose }: ConnectDialogProps) {
       handleClose();
     } catch (err) {
       console.error('Failed to connect:', err);
-      const errorMessage = String(err);
+      const errorMessage = connectErrorMessage(err);
       if (errorMessage.includes('nodename nor servname provided') || errorMessage.includes('not known')) {
         setError('Unable to resolve server address.');
       } else if (errorMessage.includes('Connection refused')) {
@@ -210,14 +196,14 @@ export default function ConnectDialog({ onClose }: ConnectDialogProps) {
             >
               <option value="server">Server</option>
               <option value="tracker">Tracker</option>
-              <option value="url">Hotline URL</option>
+              <option value="url">Address or Hotline URL</option>
             </select>
           </div>
           {formData.type === 'url' ? (
             <>
               <div>
                 <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-1">
-                  Hotline URL *
+                  Address *
                 </label>
                 <input
                   type="text"
@@ -225,35 +211,11 @@ export default function ConnectDialog({ onClose }: ConnectDialogProps) {
                   value={formData.url}
                   onChange={(e) => setFormData({ ...formData, url: e.target.value })}
                   className="w-full px-3 py-2 border border-gray-300 dark:border-gray-600 rounded-md bg-white dark:bg-gray-700 text-gray-900 dark:text-white focus:outline-none focus:ring-2 focus:ring-blue-500"
-                  placeholder="hotline://server.example.com:5500"
-                />
-                {formData.url && !parseHotlineUrl(formData.url) && (
-                  <p className="mt-1 text-xs text-red-500">Invalid URL. Use format: hotline://address or hotline://address:port</p>
-                )}
-              </div>
-
-              <div>
-                <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-1">
-                  Login
-                </label>
-                <input
-                  type="text"
-                  value={formData.login}
-                  onChange={(e) => setFormData({ ...formData, login: e.target.value })}
-                  className="w-full px-3 py-2 border border-gray-300 dark:border-gray-600 rounded-md bg-white dark:bg-gray-700 text-gray-900 dark:text-white focus:outline-none focus:ring-2 focus:ring-blue-500"
-                />
-              </div>
-
-              <div>
-                <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-1">
-                  Password
-                </label>
-                <input
-                  type="password"
-                  value={formData.password}
-                  onChange={(e) => setFormData({ ...formData, password: e.target.value })}
-                  className="w-full px-3 py-2 border border-gray-300 dark:border-gray-600 rounded-md bg-white dark:bg-gray-700 text-gray-900 dark:text-white focus:outline-none focus:ring-2 focus:ring-blue-500"
+                  placeholder="user:password@server.example.com:5500"
                 />
+                <p className="mt-1 text-xs text-gray-500 dark:text-gray-400">
+                  A Hotline URL or an address with optional login, password and port. Logs in as guest when no login is given.
+                </p>
               </div>
             </>
           ) : (
@@ -1,7 +1,8 @@
 import { useState, useEffect } from 'react';
 import { invoke } from '@tauri-apps/api/core';
 import { useAppStore } from '../../stores/appStore';
-import type { Bookmark } from '../../types';
+import type { Bookmark, ConnectAction } from '../../types';
+import { formatConnectActions, parseConnectActions } from '../../utils/connectActions';
 
 interface EditBookmarkDialogProps {
   bookmark: Bookmark;
@@ -20,7 +21,12 @@ export default function EditBookmarkDialog({ bookmark, onClose, mode = 'edit', o
     login: bookmark.login || 'guest',
     password: bookmark.password || '',
     tls: bookmark.tls || false,
+    tags: (bookmark.tags || []).join(', '),
+    notes: bookmark.notes || '',
+    idleDisconnectMins: bookmark.idleDisconnectMins?.toString() || '',
+    onConnect: formatConnectActions(bookmark.onConnect || []),
   });
+  const [error, setError] = useState<string | null>(null);
 
   useEffect(() => {
     requestAnimationFrame(() => setVisible(true));
@@ -34,6 +40,14 @@ export default function EditBookmarkDialog({ bookmark, onClose, mode = 'edit', o
   const handleSubmit = async (e: React.FormEvent) => {
     e.preventDefault();
 
+    let onConnect: ConnectAction[];
+    try {
+      onConnect = parseConnectActions(formData.onConnect);
+    } catch (err) {
+      setError(err instanceof Error ? err.message : String(err));
+      return;
+    }
+
     const finalBookmark: Bookmark = {
       ...bookmark,
       name: formData.name || `${formData.address}:${formData.port}`,
@@ -43,6 +57,10 @@ export default function EditBookmarkDialog({ bookmark, onClose, mode = 'edit', o
       password: formData.password || undefined,
       tls: formData.tls,
       type: bookmark.type,
+      tags: formData.tags.split(',').map((tag) => tag.trim()).filter(Boolean),
+      notes: formData.notes.trim() || undefined,
+      idleDisconnectMins: parseInt(formData.idleDisconnectMins) > 0 ? parseInt(formData.idleDisconnectMins) : undefined,
+      onConnect: onConnect.length > 0 ? onConnect : undefined,
     };
 
     try {
@@ -166,6 +184,72 @@ export default function EditBookmarkDialog({ bookmark, onClose, mode = 'edit', o
             />
           </div>
 
+          {bookmark.type !== 'tracker' && (
+            <div>
+              <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-1">
+                Disconnect When Idle (minutes)
+              </label>
+              <input
+                type="number"
+                min="1"
+                value={formData.idleDisconnectMins}
+                onChange={(e) => setFormData({ ...formData, idleDisconnectMins: e.target.value })}
+                className="w-full px-3 py-2 border border-gray-300 dark:border-gray-600 rounded-md bg-white dark:bg-gray-700 text-gray-900 dark:text-white focus:outline-none focus:ring-2 focus:ring-blue-500"
+                placeholder="Never"
+              />
+            </div>
+          )}
+
+          <div>
+            <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-1">
+              Tags
+            </label>
+            <input
+              type="text"
+              value={formData.tags}
+              onChange={(e) => setFormData({ ...formData, tags: e.target.value })}
+              className="w-full px-3 py-2 border border-gray-300 dark:border-gray-600 rounded-md bg-white dark:bg-gray-700 text-gray-900 dark:text-white focus:outline-none focus:ring-2 focus:ring-blue-500"
+              placeholder="mac, files, retro"
+            />
+          </div>
+
+          <div>
+            <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-1">
+              Notes
+            </label>
+            <textarea
+              value={formData.notes}
+              onChange={(e) => setFormData({ ...formData, notes: e.target.value })}
+              rows={3}
+              className="w-full px-3 py-2 border border-gray-300 dark:border-gray-600 rounded-md bg-white dark:bg-gray-700 text-gray-900 dark:text-white focus:outline-none focus:ring-2 focus:ring-blue-500"
+            />
+          </div>
+
+          {bookmark.type !== 'tracker' && (
+            <div>
+              <label className="block text-sm font-medium text-gray-700 dark:text-gray-300 mb-1">
+                On Connect
+              </label>
+              <textarea
+                value={formData.onConnect}
+                onChange={(e) => {
+                  setFormData({ ...formData, onConnect: e.target.value });
+                  setError(null);
+                }}
+                rows={3}
+                className="w-full px-3 py-2 border border-gray-300 dark:border-gray-600 rounded-md bg-white dark:bg-gray-700 text-gray-900 dark:text-white font-mono text-sm focus:outline-none focus:ring-2 focus:ring-blue-500"
+                placeholder={'say Hello everyone!\nopen Uploads/New\nnews'}
+              />
+              <p className="mt-1 text-xs text-gray-500 dark:text-gray-400">
+                One per line: say a greeting, open a folder, or load the news
+              </p>
+            </div>
+          )}
+
+          {error && (
+            <p className="text-sm text-red-500 dark:text-red-400">{error}</p>
+          )}
+
           <div className="flex gap-3 pt-4">
             <button
               type="button"
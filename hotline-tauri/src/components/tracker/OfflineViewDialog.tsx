@@ -0,0 +1,236 @@
+import { useState, useEffect } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import type { Bookmark } from '../../types';
+import { formatTimestamp } from '../../utils/time';
+
+interface OfflineViewDialogProps {
+  bookmark: Bookmark;
+  onClose: () => void;
+}
+
+interface CachedView<T> {
+  cached: boolean;
+  cachedAt: number;
+  data: T;
+}
+
+interface CachedFile {
+  name: string;
+  size: number;
+  isFolder: boolean;
+}
+
+type CachedNewsItem =
+  | { kind: 'article'; path: string[]; articleId: number; body: string }
+  | { kind: 'board'; posts: string[] };
+
+interface ChatLogEntry {
+  seq: number;
+  kind: 'chat' | 'private' | 'broadcast';
+  userId: number;
+  userName: string;
+  message: string;
+  timestamp: number;
+  outgoing?: boolean;
+}
+
+type OfflineTab = 'chat' | 'files' | 'news';
+
+export default function OfflineViewDialog({ bookmark, onClose }: OfflineViewDialogProps) {
+  const [visible, setVisible] = useState(false);
+  const [tab, setTab] = useState<OfflineTab>('chat');
+  const [chat, setChat] = useState<ChatLogEntry[]>([]);
+  const [news, setNews] = useState<CachedView<CachedNewsItem>[]>([]);
+  const [filePath, setFilePath] = useState<string[]>([]);
+  const [listing, setListing] = useState<CachedView<CachedFile[]> | null>(null);
+  const [listingError, setListingError] = useState<string | null>(null);
+  const [exportStatus, setExportStatus] = useState<string | null>(null);
+
+  useEffect(() => {
+    requestAnimationFrame(() => setVisible(true));
+    invoke<ChatLogEntry[]>('get_chat_history', { serverId: bookmark.id })
+      .then(setChat)
+      .catch((error) => console.error('Failed to load chat history:', error));
+    invoke<CachedView<CachedNewsItem>[]>('get_cached_news', { serverId: bookmark.id })
+      .then(setNews)
+      .catch((error) => console.error('Failed to load cached news:', error));
+  }, [bookmark.id]);
+
+  useEffect(() => {
+    invoke<CachedView<CachedFile[]>>('get_cached_file_list', { serverId: bookmark.id, path: filePath })
+      .then((view) => {
+        setListing(view);
+        setListingError(null);
+      })
+      .catch((error) => {
+        setListing(null);
+        setListingError(String(error));
+      });
+  }, [bookmark.id, filePath]);
+
+  const handleExport = () => {
+    const transcript = chat
+      .map((entry) => {
+        const who = entry.kind === 'broadcast'
+          ? 'Broadcast'
+          : entry.kind === 'private'
+            ? `${entry.userName} (private${entry.outgoing ? ', sent' : ''})`
+            : entry.userName;
+        return `[${formatTimestamp(entry.timestamp)}] ${who}: ${entry.message}`;
+      })
+      .join('\n');
+    invoke<string>('export_chat_transcript', { serverName: bookmark.name || bookmark.address, transcript })
+      .then((path) => setExportStatus(`Saved to ${path}`))
+      .catch((error) => setExportStatus(`Failed: ${error}`));
+  };
+
+  const handleClose = () => {
+    setVisible(false);
+    setTimeout(onClose, 300);
+  };
+
+  const tabClass = (name: OfflineTab) =>
+    `px-3 py-1.5 text-sm rounded-md transition-colors ${
+      tab === name
+        ? 'bg-blue-600 text-white'
+        : 'text-gray-700 dark:text-gray-300 hover:bg-gray-200 dark:hover:bg-gray-700'
+    }`;
+
+  return (
+    <div
+      onClick={handleClose}
+      className={`fixed inset-0 flex items-center justify-center z-50 transition-all duration-300 ease-in-out ${
+        visible ? 'bg-black/60 backdrop-blur-sm' : 'bg-black/0 backdrop-blur-none'
+      }`}
+    >
+      <div
+        onClick={(e) => e.stopPropagation()}
+        className={`bg-white dark:bg-gray-900 rounded-lg shadow-xl w-full max-w-2xl mx-4 flex flex-col max-h-[80vh] transition-all duration-300 ease-in-out ${
+          visible ? 'opacity-100 scale-100 translate-y-0' : 'opacity-0 scale-95 translate-y-2'
+        }`}
+      >
+        <div className="bg-gray-100 dark:bg-gray-800 border-b border-gray-200 dark:border-gray-700 px-6 py-4 flex items-center justify-between rounded-t-lg">
+          <div className="min-w-0">
+            <h2 className="text-lg font-semibold text-gray-900 dark:text-white truncate">
+              {bookmark.name || bookmark.address}
+            </h2>
+            <p className="text-xs text-gray-500 dark:text-gray-400">Offline — showing cached content (read-only)</p>
+          </div>
+          <button
+            onClick={handleClose}
+            className="text-gray-500 hover:text-gray-700 dark:text-gray-400 dark:hover:text-gray-200"
+          >
+            ✕
+          </button>
+        </div>
+
+        <div className="flex gap-2 px-6 py-3 border-b border-gray-200 dark:border-gray-700">
+          <button className={tabClass('chat')} onClick={() => setTab('chat')}>Chat</button>
+          <button className={tabClass('files')} onClick={() => setTab('files')}>Files</button>
+          <button className={tabClass('news')} onClick={() => setTab('news')}>News</button>
+        </div>
+
+        <div className="flex-1 overflow-y-auto px-6 py-4 text-sm text-gray-900 dark:text-white">
+          {tab === 'chat' && (
+            chat.length === 0 ? (
+              <p className="text-gray-500 dark:text-gray-400">No chat history saved for this server.</p>
+            ) : (
+              <div className="space-y-1 font-mono">
+                {chat.map((entry) => (
+                  <div key={entry.seq} className={entry.kind === 'broadcast' ? 'text-red-600 dark:text-red-400' : entry.kind === 'private' ? 'text-purple-700 dark:text-purple-300' : ''}>
+                    <span className="text-xs text-gray-400 mr-2">{formatTimestamp(entry.timestamp)}</span>
+                    {entry.kind === 'broadcast' ? (
+                      <span className="font-semibold">Broadcast:</span>
+                    ) : (
+                      <span className="font-semibold">
+                        {entry.userName}
+                        {entry.kind === 'private' && (entry.outgoing ? ' (private, sent)' : ' (private)')}:
+                      </span>
+                    )}{' '}
+                    {entry.message}
+                  </div>
+                ))}
+                <div className="flex gap-4 mt-3">
+                  <button
+                    onClick={handleExport}
+                    className="text-xs text-gray-500 hover:text-blue-600 dark:text-gray-400 dark:hover:text-blue-400"
+                  >
+                    Export transcript
+                  </button>
+                  <button
+                    onClick={() => {
+                      invoke('clear_chat_history', { serverId: bookmark.id })
+                        .then(() => setChat([]))
+                        .catch((error) => console.error('Failed to clear chat history:', error));
+                    }}
+                    className="text-xs text-gray-500 hover:text-red-600 dark:text-gray-400 dark:hover:text-red-400"
+                  >
+                    Clear chat history
+                  </button>
+                </div>
+                {exportStatus && (
+                  <p className="text-xs text-gray-500 dark:text-gray-400 break-all">{exportStatus}</p>
+                )}
+              </div>
+            )
+          )}
+
+          {tab === 'files' && (
+            <div className="space-y-2">
+              <div className="flex items-center gap-2 text-xs text-gray-500 dark:text-gray-400">
+                {filePath.length > 0 && (
+                  <button
+                    onClick={() => setFilePath(filePath.slice(0, -1))}
+                    className="px-2 py-0.5 border border-gray-300 dark:border-gray-600 rounded hover:bg-gray-100 dark:hover:bg-gray-700"
+                  >
+                    ↑ Up
+                  </button>
+                )}
+                <span className="truncate">/{filePath.join('/')}</span>
+                {listing && <span className="ml-auto">Cached {formatTimestamp(listing.cachedAt)}</span>}
+              </div>
+              {listingError && <p className="text-gray-500 dark:text-gray-400">{listingError}</p>}
+              {listing?.data.map((file) => (
+                <div
+                  key={file.name}
+                  onClick={() => file.isFolder && setFilePath([...filePath, file.name])}
+                  className={`flex items-center gap-2 px-2 py-1 rounded ${
+                    file.isFolder ? 'cursor-pointer hover:bg-gray-100 dark:hover:bg-gray-800' : ''
+                  }`}
+                >
+                  <span>{file.isFolder ? '📁' : '📄'}</span>
+                  <span className="flex-1 truncate">{file.name}</span>
+                  {!file.isFolder && <span className="text-xs text-gray-400">{file.size} bytes</span>}
+                </div>
+              ))}
+            </div>
+          )}
+
+          {tab === 'news' && (
+            news.length === 0 ? (
+              <p className="text-gray-500 dark:text-gray-400">No news or message board posts cached for this server.</p>
+            ) : (
+              <div className="space-y-4">
+                {news.map((item, i) => (
+                  <div key={i} className="border border-gray-200 dark:border-gray-700 rounded-md p-3">
+                    <div className="flex items-center justify-between text-xs text-gray-500 dark:text-gray-400 mb-2">
+                      <span>
+                        {item.data.kind === 'board'
+                          ? 'Message Board'
+                          : `${item.data.path.join(' / ')} #${item.data.articleId}`}
+                      </span>
+                      <span>Cached {formatTimestamp(item.cachedAt)}</span>
+                    </div>
+                    <div className="whitespace-pre-wrap">
+                      {item.data.kind === 'board' ? item.data.posts.join('\n\n') : item.data.body}
+                    </div>
+                  </div>
+                ))}
+              </div>
+            )
+          )}
+        </div>
+      </div>
+    </div>
+  );
+}
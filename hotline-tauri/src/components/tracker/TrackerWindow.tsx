@@ -13,6 +13,7 @@ export default function TrackerWindow() {
   const [showSettings, setShowSettings] = useState(false);
   const [showNotificationLog, setShowNotificationLog] = useState(false);
   const [searchQuery, setSearchQuery] = useState('');
+  const [refreshAllTrackers, setRefreshAllTrackers] = useState(0);
   const { bookmarks, setBookmarks } = useAppStore();
 
   // Load bookmarks from disk on mount - replace entire array to avoid duplicates
@@ -55,10 +56,7 @@ export default function TrackerWindow() {
           </div>
           <div className="flex items-center gap-2">
           <button
-            onClick={() => {
-              // Refresh all expanded trackers - this will be handled by BookmarkList
-              // We could emit an event or use a ref, but for now this is a placeholder
-            }}
+            onClick={() => setRefreshAllTrackers((count) => count + 1)}
             className="px-3 py-1.5 text-sm text-gray-700 dark:text-gray-300 hover:bg-gray-200 dark:hover:bg-gray-700 rounded transition-colors flex items-center gap-1.5"
             title="Refresh Trackers"
           >
@@ -132,7 +130,7 @@ export default function TrackerWindow() {
             <p className="text-sm">Click "Connect" to add a server</p>
           </div>
         ) : (
-          <BookmarkList bookmarks={bookmarks} searchQuery={searchQuery} />
+          <BookmarkList bookmarks={bookmarks} searchQuery={searchQuery} refreshAllTrackers={refreshAllTrackers} />
         )}
       </div>
 
@@ -1,5 +1,23 @@
+import { useEffect, useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import { useAppStore } from '../../stores/appStore';
-import type { Transfer } from '../../types';
+import type { DownloadSlots, Transfer } from '../../types';
+
+interface TransferTotals {
+  filesDownloaded: number;
+  filesUploaded: number;
+  bytesDownloaded: number;
+  bytesUploaded: number;
+  failures: number;
+  averageBytesPerSec: number;
+}
+
+interface SessionSummary {
+  since: number;
+  totals: TransferTotals;
+  servers: Record<string, TransferTotals>;
+}
 
 interface TransferListProps {
   serverId?: string;
@@ -21,8 +39,39 @@ function formatSpeed(bytesPerSecond: number | undefined | null): string {
   return `${formatBytes(bytesPerSecond)}/s`;
 }
 
+function formatEta(seconds: number | undefined | null): string | null {
+  if (seconds === undefined || seconds === null || !isFinite(seconds)) return null;
+  if (seconds < 60) return `${seconds}s left`;
+  const minutes = Math.floor(seconds / 60);
+  if (minutes < 60) return `${minutes}m ${seconds % 60}s left`;
+  return `${Math.floor(minutes / 60)}h ${minutes % 60}m left`;
+}
+
 export default function TransferList({ serverId, serverName, onClose }: TransferListProps) {
   const { transfers, removeTransfer, clearCompletedTransfers, serverInfo } = useAppStore();
+  const [sessionTotals, setSessionTotals] = useState<TransferTotals | null>(null);
+
+  useEffect(() => {
+    invoke<SessionSummary>('get_session_summary')
+      .then((summary) => setSessionTotals(serverId ? summary.servers[serverId] ?? null : summary.totals))
+      .catch((error) => console.error('Failed to load session summary:', error));
+  }, [serverId, transfers]);
+
+  // The server's cap on simultaneous downloads, once it has refused one over it
+  const [downloadSlots, setDownloadSlots] = useState<DownloadSlots | null>(null);
+
+  useEffect(() => {
+    if (!serverId) return;
+    invoke<DownloadSlots>('get_download_slots', { serverId })
+      .then(setDownloadSlots)
+      .catch((error) => console.error('Failed to load download slots:', error));
+    const unlisten = listen<DownloadSlots>(`download-slots-updated-${serverId}`, (event) => {
+      setDownloadSlots(event.payload);
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, [serverId]);
 
   // Filter transfers by server if serverId is provided
   const filteredTransfers = serverId
@@ -75,6 +124,21 @@ export default function TransferList({ serverId, serverName, onClose }: Transfer
           </div>
         </div>
 
+        {/* This session's totals */}
+        {sessionTotals && (
+          <div className="px-6 py-2 border-b border-gray-200 dark:border-gray-700 text-xs text-gray-600 dark:text-gray-400">
+            This session: {sessionTotals.filesDownloaded} downloaded ({formatBytes(sessionTotals.bytesDownloaded)}),{' '}
+            {sessionTotals.filesUploaded} uploaded ({formatBytes(sessionTotals.bytesUploaded)}),{' '}
+            {sessionTotals.failures} failed, average {formatSpeed(sessionTotals.averageBytesPerSec)}
+          </div>
+        )}
+
+        {downloadSlots?.limit != null && (
+          <div className="px-6 py-2 border-b border-gray-200 dark:border-gray-700 text-xs text-gray-600 dark:text-gray-400">
+            {downloadSlots.used} of {downloadSlots.limit} download slots used
+          </div>
+        )}
+
         {/* Content */}
         <div className="flex-1 overflow-auto p-6">
           {filteredTransfers.length === 0 ? (
@@ -140,6 +204,7 @@ export default function TransferList({ serverId, serverName, onClose }: Transfer
                           {transfer.speed && transfer.speed > 0 && (
                             <p className="text-xs text-gray-500 dark:text-gray-400">
                               {formatSpeed(transfer.speed)}
+                              {formatEta(transfer.etaSecs) && ` · ${formatEta(transfer.etaSecs)}`}
                             </p>
                           )}
                         </div>
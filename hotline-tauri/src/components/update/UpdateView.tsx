@@ -4,14 +4,14 @@ import { getVersion } from '@tauri-apps/api/app';
 import { openUrl } from '@tauri-apps/plugin-opener';
 
 interface UpdateRelease {
-  tag_name: string;
-  display_version: string;
-  version_number: number;
-  build_number: number;
+  tagName: string;
+  displayVersion: string;
+  versionNumber: number;
+  buildNumber: number;
   notes: string;
-  download_url: string;
-  asset_name: string;
-  published_at: string;
+  downloadUrl: string;
+  assetName: string;
+  publishedAt: string;
 }
 
 interface UpdateViewProps {
@@ -51,7 +51,7 @@ export default function UpdateView({ onClose }: UpdateViewProps) {
         };
         
         const currentParts = parseVersion(currentVersion);
-        const releaseParts = parseVersion(release.display_version);
+        const releaseParts = parseVersion(release.displayVersion);
         
         // Compare version parts
         let isNewer = false;
@@ -87,7 +87,7 @@ export default function UpdateView({ onClose }: UpdateViewProps) {
     setIsDownloading(true);
     try {
       // Open the download URL in the default browser
-      await openUrl(update.download_url);
+      await openUrl(update.downloadUrl);
     } catch (err) {
       setError(err instanceof Error ? err.message : 'Failed to open download link');
     } finally {
@@ -176,7 +176,7 @@ export default function UpdateView({ onClose }: UpdateViewProps) {
                 />
                 <div>
                   <h3 className="text-xl font-semibold text-gray-900 dark:text-white">
-                    Hotline Navigator {update.display_version}
+                    Hotline Navigator {update.displayVersion}
                   </h3>
                   <p className="text-sm text-gray-500 dark:text-gray-400">
                     A new version is available! 🎉
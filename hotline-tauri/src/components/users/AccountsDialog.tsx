@@ -0,0 +1,301 @@
+import { useEffect, useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import type { UserAccess } from '../../types';
+
+interface Account {
+  login: string;
+  name: string;
+  password?: string;
+  access: UserAccess;
+}
+
+interface AccountsDialogProps {
+  serverId: string;
+  canCreate: boolean;
+  canModify: boolean;
+  canDelete: boolean;
+  onClose: () => void;
+}
+
+// Privileges grouped the way the classic Hotline account editor shows them
+const ACCESS_GROUPS: { title: string; items: [keyof UserAccess, string][] }[] = [
+  {
+    title: 'Files',
+    items: [
+      ['downloadFile', 'Download files'],
+      ['downloadFolder', 'Download folders'],
+      ['uploadFile', 'Upload files'],
+      ['uploadFolder', 'Upload folders'],
+      ['uploadAnywhere', 'Upload anywhere'],
+      ['deleteFile', 'Delete files'],
+      ['renameFile', 'Rename files'],
+      ['moveFile', 'Move files'],
+      ['setFileComment', 'Comment files'],
+      ['createFolder', 'Create folders'],
+      ['deleteFolder', 'Delete folders'],
+      ['renameFolder', 'Rename folders'],
+      ['moveFolder', 'Move folders'],
+      ['setFolderComment', 'Comment folders'],
+      ['viewDropBoxes', 'View drop boxes'],
+      ['makeAlias', 'Make aliases'],
+    ],
+  },
+  {
+    title: 'Users',
+    items: [
+      ['createUser', 'Create accounts'],
+      ['deleteUser', 'Delete accounts'],
+      ['openUser', 'Read accounts'],
+      ['modifyUser', 'Modify accounts'],
+      ['changeOwnPassword', 'Change own password'],
+      ['getClientInfo', 'Get user info'],
+      ['disconnectUser', 'Disconnect users'],
+      ['cannotBeDisconnected', 'Cannot be disconnected'],
+    ],
+  },
+  {
+    title: 'Messaging',
+    items: [
+      ['readChat', 'Read chat'],
+      ['sendChat', 'Send chat'],
+      ['openChat', 'Initiate private chat'],
+      ['closeChat', 'Close chat'],
+      ['sendMessage', 'Send messages'],
+      ['sendPrivateMessage', 'Send private messages'],
+      ['broadcast', 'Broadcast'],
+    ],
+  },
+  {
+    title: 'News',
+    items: [
+      ['newsReadArticle', 'Read articles'],
+      ['newsPostArticle', 'Post articles'],
+      ['newsDeleteArticle', 'Delete articles'],
+      ['newsCreateCategory', 'Create categories'],
+      ['newsDeleteCategory', 'Delete categories'],
+      ['newsCreateFolder', 'Create news bundles'],
+      ['newsDeleteFolder', 'Delete news bundles'],
+    ],
+  },
+  {
+    title: 'Miscellaneous',
+    items: [
+      ['showInList', 'Show in user list'],
+      ['anyName', 'Use any name'],
+      ['noAgreement', "Don't show agreement"],
+    ],
+  },
+];
+
+const emptyAccess = (): UserAccess =>
+  Object.fromEntries(ACCESS_GROUPS.flatMap((g) => g.items.map(([key]) => [key, false]))) as unknown as UserAccess;
+
+export default function AccountsDialog({ serverId, canCreate, canModify, canDelete, onClose }: AccountsDialogProps) {
+  const [accounts, setAccounts] = useState<Account[]>([]);
+  const [selected, setSelected] = useState<Account | null>(null);
+  const [isNew, setIsNew] = useState(false);
+  const [password, setPassword] = useState('');
+  const [busy, setBusy] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  const loadAccounts = async () => {
+    try {
+      setAccounts(await invoke<Account[]>('list_accounts', { serverId }));
+      setError(null);
+    } catch (err) {
+      setError(String(err));
+    }
+  };
+
+  useEffect(() => {
+    loadAccounts();
+  }, [serverId]);
+
+  const selectAccount = async (login: string) => {
+    try {
+      const account = await invoke<Account>('get_account', { serverId, login });
+      setSelected(account);
+      setIsNew(false);
+      setPassword('');
+      setError(null);
+    } catch (err) {
+      setError(String(err));
+    }
+  };
+
+  const startNew = () => {
+    setSelected({ login: '', name: '', access: emptyAccess() });
+    setIsNew(true);
+    setPassword('');
+  };
+
+  const handleSave = async () => {
+    if (!selected) return;
+    setBusy(true);
+    try {
+      // Leaving the password blank on an existing account keeps it unchanged
+      const account: Account = { ...selected, password: password || (isNew ? '' : undefined) };
+      await invoke('save_account', { serverId, account, isNew });
+      setIsNew(false);
+      await loadAccounts();
+    } catch (err) {
+      setError(String(err));
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  const handleDelete = async () => {
+    if (!selected || isNew) return;
+    if (!confirm(`Delete account "${selected.login}"?`)) return;
+    setBusy(true);
+    try {
+      await invoke('delete_account', { serverId, login: selected.login });
+      setSelected(null);
+      await loadAccounts();
+    } catch (err) {
+      setError(String(err));
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  const editable = isNew ? canCreate : canModify;
+
+  return (
+    <div className="fixed inset-0 bg-black bg-opacity-50 flex items-center justify-center z-50">
+      <div className="bg-white dark:bg-gray-900 rounded-lg shadow-xl w-[760px] max-h-[85vh] flex flex-col">
+        <div className="bg-gray-100 dark:bg-gray-800 border-b border-gray-200 dark:border-gray-700 px-4 py-3 flex items-center justify-between rounded-t-lg">
+          <h2 className="text-lg font-semibold text-gray-900 dark:text-white">Accounts</h2>
+          <button
+            onClick={onClose}
+            className="text-gray-500 hover:text-gray-700 dark:text-gray-400 dark:hover:text-gray-200"
+          >
+            ✕
+          </button>
+        </div>
+
+        {error && (
+          <div className="px-4 py-2 text-sm text-red-600 dark:text-red-400 border-b border-gray-200 dark:border-gray-700">
+            {error}
+          </div>
+        )}
+
+        <div className="flex flex-1 min-h-0">
+          {/* Account list */}
+          <div className="w-56 border-r border-gray-200 dark:border-gray-700 flex flex-col">
+            <div className="flex-1 overflow-y-auto">
+              {accounts.map((account) => (
+                <button
+                  key={account.login}
+                  onClick={() => selectAccount(account.login)}
+                  className={`w-full text-left px-3 py-2 text-sm ${
+                    !isNew && selected?.login === account.login
+                      ? 'bg-blue-100 dark:bg-blue-900/40'
+                      : 'hover:bg-gray-100 dark:hover:bg-gray-800'
+                  } text-gray-900 dark:text-white`}
+                >
+                  <div className="font-medium truncate">{account.login}</div>
+                  {account.name && (
+                    <div className="text-xs text-gray-500 dark:text-gray-400 truncate">{account.name}</div>
+                  )}
+                </button>
+              ))}
+            </div>
+            {canCreate && (
+              <button
+                onClick={startNew}
+                className="m-2 px-3 py-1.5 text-sm bg-blue-600 text-white rounded hover:bg-blue-700"
+              >
+                New Account
+              </button>
+            )}
+          </div>
+
+          {/* Editor */}
+          <div className="flex-1 overflow-y-auto p-4">
+            {selected ? (
+              <div className="space-y-4">
+                <div className="grid grid-cols-3 gap-3">
+                  <label className="text-sm text-gray-700 dark:text-gray-300">
+                    Login
+                    <input
+                      value={selected.login}
+                      disabled={!isNew}
+                      onChange={(e) => setSelected({ ...selected, login: e.target.value })}
+                      className="mt-1 w-full px-2 py-1 border border-gray-300 dark:border-gray-600 rounded bg-white dark:bg-gray-800 disabled:opacity-60"
+                    />
+                  </label>
+                  <label className="text-sm text-gray-700 dark:text-gray-300">
+                    Name
+                    <input
+                      value={selected.name}
+                      disabled={!editable}
+                      onChange={(e) => setSelected({ ...selected, name: e.target.value })}
+                      className="mt-1 w-full px-2 py-1 border border-gray-300 dark:border-gray-600 rounded bg-white dark:bg-gray-800 disabled:opacity-60"
+                    />
+                  </label>
+                  <label className="text-sm text-gray-700 dark:text-gray-300">
+                    Password
+                    <input
+                      type="password"
+                      value={password}
+                      disabled={!editable}
+                      placeholder={isNew ? '' : 'Unchanged'}
+                      onChange={(e) => setPassword(e.target.value)}
+                      className="mt-1 w-full px-2 py-1 border border-gray-300 dark:border-gray-600 rounded bg-white dark:bg-gray-800 disabled:opacity-60"
+                    />
+                  </label>
+                </div>
+
+                <div className="grid grid-cols-2 gap-4">
+                  {ACCESS_GROUPS.map((group) => (
+                    <fieldset key={group.title} className="border border-gray-200 dark:border-gray-700 rounded p-2">
+                      <legend className="text-xs font-semibold text-gray-500 dark:text-gray-400 uppercase px-1">
+                        {group.title}
+                      </legend>
+                      {group.items.map(([key, label]) => (
+                        <label key={key} className="flex items-center gap-2 text-sm text-gray-800 dark:text-gray-200">
+                          <input
+                            type="checkbox"
+                            checked={selected.access[key]}
+                            disabled={!editable}
+                            onChange={(e) =>
+                              setSelected({ ...selected, access: { ...selected.access, [key]: e.target.checked } })
+                            }
+                          />
+                          {label}
+                        </label>
+                      ))}
+                    </fieldset>
+                  ))}
+                </div>
+
+                <div className="flex justify-end gap-2">
+                  {!isNew && canDelete && (
+                    <button
+                      onClick={handleDelete}
+                      disabled={busy}
+                      className="px-3 py-1.5 text-sm text-red-600 border border-red-300 rounded hover:bg-red-50 dark:hover:bg-red-900/30 disabled:opacity-50"
+                    >
+                      Delete
+                    </button>
+                  )}
+                  <button
+                    onClick={handleSave}
+                    disabled={busy || !editable || !selected.login.trim()}
+                    className="px-3 py-1.5 text-sm bg-blue-600 text-white rounded hover:bg-blue-700 disabled:opacity-50"
+                  >
+                    {isNew ? 'Create' : 'Save'}
+                  </button>
+                </div>
+              </div>
+            ) : (
+              <div className="text-sm text-gray-500 dark:text-gray-400">Select an account to edit it.</div>
+            )}
+          </div>
+        </div>
+      </div>
+    </div>
+  );
+}
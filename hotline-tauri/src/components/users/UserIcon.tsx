@@ -1,14 +1,18 @@
 import { useState } from 'react';
 import { usePreferencesStore } from '../../stores/preferencesStore';
+import type { AvatarFallback } from '../../types';
 
 interface UserIconProps {
   iconId: number;
   size?: number;
   className?: string;
+  // Sent with users whose icon isn't bundled; shown instead of the icon id
+  avatar?: AvatarFallback;
 }
 
-export default function UserIcon({ iconId, size = 16, className = '' }: UserIconProps) {
-  const [localError, setLocalError] = useState(false);
+export default function UserIcon({ iconId, size = 16, className = '', avatar }: UserIconProps) {
+  // The backend already knows when there is no local artwork
+  const [localError, setLocalError] = useState(Boolean(avatar));
   const [remoteError, setRemoteError] = useState(false);
   const useRemoteIcons = usePreferencesStore((s) => s.useRemoteIcons);
 
@@ -18,6 +22,23 @@ export default function UserIcon({ iconId, size = 16, className = '' }: UserIcon
   const showRemote = localError && useRemoteIcons && !remoteError;
   const showFallback = localError && (!useRemoteIcons || remoteError);
 
+  if (showFallback && avatar) {
+    return (
+      <div
+        className={`inline-flex items-center justify-center rounded font-semibold text-white ${className}`}
+        style={{
+          width: size,
+          height: size,
+          backgroundColor: avatar.color,
+          fontSize: `${Math.max(7, size * (avatar.initials.length > 1 ? 0.4 : 0.55))}px`,
+        }}
+        title={`Icon ${iconId}`}
+      >
+        {avatar.initials}
+      </div>
+    );
+  }
+
   if (showFallback) {
     return (
       <div
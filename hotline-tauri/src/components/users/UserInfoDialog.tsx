@@ -1,4 +1,7 @@
+import { useEffect, useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
 import UserIcon from './UserIcon';
+import type { AvatarFallback } from '../../types';
 
 interface User {
   userId: number;
@@ -7,16 +10,45 @@ interface User {
   flags: number;
   isAdmin: boolean;
   isIdle: boolean;
+  avatar?: AvatarFallback;
+}
+
+interface ServerUserInfo {
+  userId: number;
+  userName: string;
+  info: string;
 }
 
 interface UserInfoDialogProps {
   user: User;
+  serverId?: string;
   onClose: () => void;
   onSendMessage?: (user: User) => void;
   enablePrivateMessaging?: boolean;
 }
 
-export default function UserInfoDialog({ user, onClose, onSendMessage, enablePrivateMessaging = true }: UserInfoDialogProps) {
+export default function UserInfoDialog({ user, serverId, onClose, onSendMessage, enablePrivateMessaging = true }: UserInfoDialogProps) {
+  const [serverInfo, setServerInfo] = useState<string | null>(null);
+  const [serverInfoError, setServerInfoError] = useState<string | null>(null);
+
+  // Ask the server for its info text on this user (address, login time, transfers)
+  useEffect(() => {
+    if (!serverId) return;
+    let cancelled = false;
+    setServerInfo(null);
+    setServerInfoError(null);
+    invoke<ServerUserInfo>('get_user_info', { serverId, userId: user.userId })
+      .then((result) => {
+        if (!cancelled) setServerInfo(result.info);
+      })
+      .catch((error) => {
+        if (!cancelled) setServerInfoError(String(error));
+      });
+    return () => {
+      cancelled = true;
+    };
+  }, [serverId, user.userId]);
+
   return (
     <div className="fixed inset-0 bg-black bg-opacity-50 flex items-center justify-center z-50">
       <div className="bg-white dark:bg-gray-900 rounded-lg shadow-xl w-[400px] flex flex-col">
@@ -37,7 +69,7 @@ export default function UserInfoDialog({ user, onClose, onSendMessage, enablePri
         <div className="p-6 space-y-4">
           {/* User Icon */}
           <div className="flex justify-center">
-            <UserIcon iconId={user.iconId} size={64} className="rounded-lg" />
+            <UserIcon iconId={user.iconId} size={64} className="rounded-lg" avatar={user.avatar} />
           </div>
 
           {/* User Details */}
@@ -101,6 +133,27 @@ export default function UserInfoDialog({ user, onClose, onSendMessage, enablePri
                 0x{user.flags.toString(16).padStart(4, '0').toUpperCase()}
               </div>
             </div>
+
+            {serverId && (
+              <div>
+                <label className="text-xs font-semibold text-gray-500 dark:text-gray-400 uppercase">
+                  Server Info
+                </label>
+                {serverInfo !== null ? (
+                  <pre className="mt-1 max-h-48 overflow-y-auto whitespace-pre-wrap text-gray-900 dark:text-white font-mono text-xs">
+                    {serverInfo || 'No information provided'}
+                  </pre>
+                ) : serverInfoError ? (
+                  <div className="mt-1 text-gray-500 dark:text-gray-400 text-sm">
+                    {serverInfoError}
+                  </div>
+                ) : (
+                  <div className="mt-1 text-gray-500 dark:text-gray-400 text-sm">
+                    Loading…
+                  </div>
+                )}
+              </div>
+            )}
           </div>
         </div>
 
@@ -1,4 +1,5 @@
 import UserIcon, { UserBanner } from './UserIcon';
+import type { AvatarFallback } from '../../types';
 
 interface User {
   userId: number;
@@ -7,6 +8,7 @@ interface User {
   flags: number;
   isAdmin: boolean;
   isIdle: boolean;
+  avatar?: AvatarFallback;
 }
 
 interface UserListProps {
@@ -39,7 +41,7 @@ export default function UserList({ users, unreadCounts, onUserClick, onUserRight
             title={`Click to message${user.isAdmin ? ' (Admin)' : ''}${user.isIdle ? ' (Idle)' : ''} | Right-click for menu`}
           >
             <UserBanner iconId={user.iconId} />
-            <UserIcon iconId={user.iconId} size={16} />
+            <UserIcon iconId={user.iconId} size={16} avatar={user.avatar} />
             <span className={`truncate flex-1 ${user.isIdle ? 'italic' : ''}`}>
               {user.userName}
             </span>
@@ -1,10 +1,19 @@
 import React from "react";
 import ReactDOM from "react-dom/client";
 import App from "./App";
+import DetachedServerWindow from "./components/server/DetachedServerWindow";
 import "./App.css";
 
+// Server windows load the same page with the server named in the query
+const params = new URLSearchParams(window.location.search);
+const serverWindowId = params.get("serverWindow");
+
 ReactDOM.createRoot(document.getElementById("root") as HTMLElement).render(
   <React.StrictMode>
-    <App />
+    {serverWindowId ? (
+      <DetachedServerWindow serverId={serverWindowId} serverName={params.get("name") ?? serverWindowId} />
+    ) : (
+      <App />
+    )}
   </React.StrictMode>,
 );
@@ -1,5 +1,6 @@
 import { create } from 'zustand';
 import { Bookmark, TrackerBookmark, Transfer } from '../types';
+import type { FileKind } from '../components/files/FileKindIcon';
 
 interface ServerInfo {
   id: string;
@@ -15,6 +16,7 @@ interface FileItem {
   isFolder: boolean;
   fileType?: string;
   creator?: string;
+  kind?: FileKind;
 }
 
 // File cache key is the path joined with '/'
@@ -1,7 +1,9 @@
 import { create } from 'zustand';
 import { persist, createJSONStorage } from 'zustand/middleware';
+import type { DiagnosticVerbosity } from '../types';
 
 export type DarkModePreference = 'system' | 'light' | 'dark';
+export type ForkMode = 'discard' | 'appleDouble' | 'macBinary';
 
 interface PreferencesState {
   username: string;
@@ -12,6 +14,10 @@ interface PreferencesState {
   // Download preferences
   downloadFolder: string | null;
   setDownloadFolder: (folder: string | null) => void;
+  forkMode: ForkMode;
+  setForkMode: (mode: ForkMode) => void;
+  skipExistingDownloads: boolean;
+  setSkipExistingDownloads: (enabled: boolean) => void;
 
   // Banner preferences
   showServerBanner: boolean;
@@ -41,6 +47,16 @@ interface PreferencesState {
   autoDetectTls: boolean;
   setAutoDetectTls: (enabled: boolean) => void;
 
+  // Time preferences (null follows the system)
+  timeZone: string | null;
+  setTimeZone: (timeZone: string | null) => void;
+  locale: string | null;
+  setLocale: (locale: string | null) => void;
+
+  // Background task notices shown in the developer console
+  diagnosticVerbosity: DiagnosticVerbosity;
+  setDiagnosticVerbosity: (verbosity: DiagnosticVerbosity) => void;
+
   // Sound preferences
   playSounds: boolean;
   playChatSound: boolean;
@@ -81,6 +97,10 @@ export const usePreferencesStore = create<PreferencesState>()(
       // Download preferences
       downloadFolder: null,
       setDownloadFolder: (downloadFolder) => set({ downloadFolder }),
+      forkMode: 'discard',
+      setForkMode: (forkMode) => set({ forkMode }),
+      skipExistingDownloads: false,
+      setSkipExistingDownloads: (skipExistingDownloads) => set({ skipExistingDownloads }),
 
       // Banner preferences
       showServerBanner: true,
@@ -118,6 +138,15 @@ export const usePreferencesStore = create<PreferencesState>()(
       autoDetectTls: false,
       setAutoDetectTls: (autoDetectTls) => set({ autoDetectTls }),
 
+      // Time preferences
+      timeZone: null,
+      setTimeZone: (timeZone) => set({ timeZone }),
+      locale: null,
+      setLocale: (locale) => set({ locale }),
+
+      diagnosticVerbosity: 'off',
+      setDiagnosticVerbosity: (diagnosticVerbosity) => set({ diagnosticVerbosity }),
+
       // Sound preferences (all enabled by default)
       playSounds: true,
       playChatSound: true,
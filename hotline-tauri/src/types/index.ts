@@ -13,6 +13,22 @@ export interface Bookmark {
   autoConnect?: boolean;
   tls?: boolean;
   type?: BookmarkType; // 'server' by default, 'tracker' for tracker servers
+  notes?: string;
+  tags?: string[];
+  idleDisconnectMins?: number; // disconnect after this many idle minutes
+  group?: string; // id of the BookmarkGroup it's filed under
+  onConnect?: ConnectAction[]; // run in order after login and the agreement
+}
+
+export type ConnectAction =
+  | { action: 'sendChat'; text: string }
+  | { action: 'openFolder'; path: string[] }
+  | { action: 'fetchNews' };
+
+export interface BookmarkGroup {
+  id: string;
+  name: string;
+  parent?: string; // nested in this group; top level when absent
 }
 
 export interface TrackerBookmark {
@@ -31,6 +47,23 @@ export interface ServerBookmark {
   address: string;
   port: number;
   users: number;
+  // Learned from earlier connections, when the server stated one
+  userLimit?: number | null;
+}
+
+// Placeholder for user icons without bundled artwork, computed by the backend
+export interface AvatarFallback {
+  color: string;
+  initials: string;
+}
+
+// One entry of a server's user list as the backend sends it
+export interface RosterUser {
+  userId: number;
+  userName: string;
+  iconId: number;
+  flags: number;
+  avatar?: AvatarFallback;
 }
 
 export interface User {
@@ -90,6 +123,7 @@ export interface Transfer {
   fileSize: number;
   transferred: number;
   speed: number;
+  etaSecs?: number | null;
   status: 'active' | 'completed' | 'failed' | 'cancelled';
   error?: string;
   startTime?: Date;
@@ -125,6 +159,87 @@ export interface ServerInfo {
   description: string;
   version: string;
   agreement?: string;
+  agreementAccepted?: boolean;
+  serverSoftware?: ServerSoftware;
+  quirks?: ServerQuirks;
+  capabilities?: ServerCapabilities;
+  userLimit?: number;
+}
+
+export type ServerSoftware =
+  | 'unknown'
+  | 'hotline12'
+  | 'hotline18'
+  | 'hotline19'
+  | 'mobius'
+  | 'synHl'
+  | 'hlServerClone';
+
+export interface ServerQuirks {
+  agreedRequiresUserInfo: boolean;
+  userListAfterAgreement: boolean;
+  threadedNews: boolean;
+  keepaliveIntervalSecs: number;
+  instantMessageOptions: boolean;
+  acknowledgesInstantMessages: boolean;
+}
+
+export interface ServerCapabilities {
+  messageBoard: boolean;
+  news: boolean;
+  // Downloads one user may run at once, once the server has refused one over it
+  maxDownloads?: number | null;
+}
+
+// Download slots in use on a server, against its cap when one is known
+export interface DownloadSlots {
+  used: number;
+  limit: number | null;
+}
+
+// Decoded Hotline access bits, as emitted on user-access-{serverId}
+export interface UserAccess {
+  deleteFile: boolean;
+  uploadFile: boolean;
+  downloadFile: boolean;
+  renameFile: boolean;
+  moveFile: boolean;
+  createFolder: boolean;
+  deleteFolder: boolean;
+  renameFolder: boolean;
+  moveFolder: boolean;
+  readChat: boolean;
+  sendChat: boolean;
+  openChat: boolean;
+  closeChat: boolean;
+  showInList: boolean;
+  createUser: boolean;
+  deleteUser: boolean;
+  openUser: boolean;
+  modifyUser: boolean;
+  changeOwnPassword: boolean;
+  sendPrivateMessage: boolean;
+  newsReadArticle: boolean;
+  newsPostArticle: boolean;
+  disconnectUser: boolean;
+  cannotBeDisconnected: boolean;
+  getClientInfo: boolean;
+  uploadAnywhere: boolean;
+  anyName: boolean;
+  noAgreement: boolean;
+  setFileComment: boolean;
+  setFolderComment: boolean;
+  viewDropBoxes: boolean;
+  makeAlias: boolean;
+  broadcast: boolean;
+  newsDeleteArticle: boolean;
+  newsCreateCategory: boolean;
+  newsDeleteCategory: boolean;
+  newsCreateFolder: boolean;
+  newsDeleteFolder: boolean;
+  uploadFolder: boolean;
+  downloadFolder: boolean;
+  sendMessage: boolean;
 }
 
 export interface Permissions {
@@ -152,4 +267,30 @@ export type ConnectionStatus =
   | 'connected'
   | 'logging-in'
   | 'logged-in'
+  | 'reconnecting'
   | 'failed';
+
+// App-wide preferences kept by the backend (preferences.json)
+export type DiagnosticVerbosity = 'off' | 'problems' | 'all';
+
+export interface AppPreferences {
+  nickname: string;
+  icon: number;
+  downloadDirectory: string | null;
+  transferLimits: {
+    downloadBytesPerSec: number | null;
+    uploadBytesPerSec: number | null;
+  };
+  skipExistingDownloads: boolean;
+  notifications: {
+    privateMessage: boolean;
+    mention: boolean;
+    transferComplete: boolean;
+    transferFailed: boolean;
+    disconnect: boolean;
+  };
+  theme: 'system' | 'light' | 'dark';
+  timeZone: string | null;
+  locale: string | null;
+  diagnosticVerbosity: DiagnosticVerbosity;
+}
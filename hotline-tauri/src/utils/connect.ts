@@ -0,0 +1,80 @@
+// Connecting to servers
+//
+// connect_to_server rejects with { message, refusal }. `refusal` names login
+// refusals the user can resolve; for 'alreadyLoggedIn' the connect can be
+// retried with `takeover`, which disconnects the other session first, and for
+// 'serverFull' with `waitIfFull`, which keeps retrying until a slot opens.
+
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import { showNotification } from '../stores/notificationStore';
+import type { Bookmark, RosterUser } from '../types';
+
+export interface ConnectResult {
+  serverId: string;
+  tls: boolean;
+  port: number;
+  // Who was online at login; user events only report changes after this
+  users: RosterUser[];
+}
+
+export type LoginRefusal = 'alreadyLoggedIn' | 'serverFull';
+
+interface ConnectError {
+  message: string;
+  refusal: LoginRefusal | null;
+}
+
+function isConnectError(error: unknown): error is ConnectError {
+  return typeof error === 'object' && error !== null && 'message' in error;
+}
+
+export function connectErrorMessage(error: unknown): string {
+  return isConnectError(error) ? error.message : String(error);
+}
+
+// Connect, offering to take over the account's other session when the server
+// says it is already logged in, or to wait for a slot when it is full
+export async function connectToServer(args: {
+  bookmark: Bookmark;
+  username: string;
+  userIconId: number;
+  autoDetectTls: boolean;
+}): Promise<ConnectResult> {
+  try {
+    return await invoke<ConnectResult>('connect_to_server', args);
+  } catch (error) {
+    if (
+      isConnectError(error) &&
+      error.refusal === 'alreadyLoggedIn' &&
+      confirm(`${error.message}. Disconnect the other session and log in here?`)
+    ) {
+      return await invoke<ConnectResult>('connect_to_server', { ...args, takeover: true });
+    }
+    if (
+      isConnectError(error) &&
+      error.refusal === 'serverFull' &&
+      confirm(`${error.message}. Keep trying until a slot opens?`)
+    ) {
+      return await waitForSlot(args);
+    }
+    throw error;
+  }
+}
+
+async function waitForSlot(args: { bookmark: Bookmark }): Promise<ConnectResult> {
+  const unlisten = await listen<{ attempt: number; delaySecs: number }>(
+    `server-full-retry-${args.bookmark.id}`,
+    (event) => {
+      showNotification.info(
+        `Server is full, trying again in ${event.payload.delaySecs}s (attempt ${event.payload.attempt})`,
+        args.bookmark.name,
+      );
+    },
+  );
+  try {
+    return await invoke<ConnectResult>('connect_to_server', { ...args, waitIfFull: true });
+  } finally {
+    unlisten();
+  }
+}
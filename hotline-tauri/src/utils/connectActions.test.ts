@@ -0,0 +1,22 @@
+import { describe, it, expect } from 'vitest';
+import { formatConnectActions, parseConnectActions } from './connectActions';
+
+describe('parseConnectActions', () => {
+  it('reads one action per line', () => {
+    expect(parseConnectActions('say Hi all!\n\nOPEN /Uploads/New Stuff/\nnews')).toEqual([
+      { action: 'sendChat', text: 'Hi all!' },
+      { action: 'openFolder', path: ['Uploads', 'New Stuff'] },
+      { action: 'fetchNews' },
+    ]);
+  });
+
+  it('rejects lines it does not understand', () => {
+    expect(() => parseConnectActions('wave')).toThrow('"wave"');
+    expect(() => parseConnectActions('say')).toThrow();
+  });
+
+  it('round-trips through the editor text', () => {
+    const text = 'say hello\nopen Files/Mac\nnews';
+    expect(formatConnectActions(parseConnectActions(text))).toBe(text);
+  });
+});
@@ -0,0 +1,47 @@
+// Bookmark connect actions as text
+//
+// The bookmark editor shows a bookmark's connect actions one per line:
+// `say <text>` for a chat greeting, `open <folder/path>` for the Files tab
+// and `news` to load the message board.
+
+import type { ConnectAction } from '../types';
+
+export function formatConnectActions(actions: ConnectAction[]): string {
+  return actions
+    .map((action) => {
+      switch (action.action) {
+        case 'sendChat':
+          return `say ${action.text}`;
+        case 'openFolder':
+          return `open ${action.path.join('/')}`;
+        case 'fetchNews':
+          return 'news';
+      }
+    })
+    .join('\n');
+}
+
+/** Parse the editor's text, throwing on the first line that isn't an action */
+export function parseConnectActions(text: string): ConnectAction[] {
+  return text
+    .split('\n')
+    .map((line) => line.trim())
+    .filter(Boolean)
+    .map((line): ConnectAction => {
+      const [, verb = '', rest = ''] = line.match(/^(\S+)\s*(.*)$/) ?? [];
+      switch (verb.toLowerCase()) {
+        case 'say':
+          if (!rest) break;
+          return { action: 'sendChat', text: rest };
+        case 'open': {
+          const path = rest.split('/').map((segment) => segment.trim()).filter(Boolean);
+          if (path.length === 0) break;
+          return { action: 'openFolder', path };
+        }
+        case 'news':
+          if (rest) break;
+          return { action: 'fetchNews' };
+      }
+      throw new Error(`Not a connect action: "${line}"`);
+    });
+}
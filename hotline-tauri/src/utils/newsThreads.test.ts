@@ -0,0 +1,22 @@
+import { describe, it, expect } from 'vitest';
+import { flattenThreads } from './newsThreads';
+import type { NewsArticle, NewsThread } from '../components/server/serverTypes';
+
+function thread(id: number, depth: number, replies: NewsThread[] = []): NewsThread {
+  const article: NewsArticle = { id, parentId: 0, flags: 0, title: `Article ${id}`, poster: 'alice', path: ['General'] };
+  const descendantCount = replies.reduce((count, reply) => count + 1 + reply.descendantCount, 0);
+  return { article, depth, childCount: replies.length, descendantCount, replies };
+}
+
+describe('flattenThreads', () => {
+  it('lists replies after their parent with their depth', () => {
+    const threads = [thread(1, 0, [thread(2, 1, [thread(3, 2)]), thread(4, 1)]), thread(5, 0)];
+    expect(flattenThreads(threads).map((a) => [a.id, a.depth, a.replyCount])).toEqual([
+      [1, 0, 3],
+      [2, 1, 1],
+      [3, 2, 0],
+      [4, 1, 0],
+      [5, 0, 0],
+    ]);
+  });
+});
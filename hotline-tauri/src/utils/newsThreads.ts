@@ -0,0 +1,14 @@
+// News threads for the article list
+//
+// The backend returns a category's articles as reply trees. The list shows
+// them flat, in reading order, with each article's depth for indenting and
+// how many replies sit below it.
+
+import type { NewsArticle, NewsThread } from '../components/server/serverTypes';
+
+export function flattenThreads(threads: NewsThread[]): NewsArticle[] {
+  return threads.flatMap((thread) => [
+    { ...thread.article, depth: thread.depth, replyCount: thread.descendantCount },
+    ...flattenThreads(thread.replies),
+  ]);
+}
@@ -0,0 +1,16 @@
+import { describe, it, expect } from 'vitest';
+import { formatOccupancy, isFull } from './occupancy';
+
+describe('formatOccupancy', () => {
+  it('shows the limit only when it is known', () => {
+    expect(formatOccupancy(14, 50)).toBe('14/50');
+    expect(formatOccupancy(14)).toBe('14');
+    expect(formatOccupancy(14, null)).toBe('14');
+  });
+
+  it('treats a server at its limit as full', () => {
+    expect(isFull(50, 50)).toBe(true);
+    expect(isFull(49, 50)).toBe(false);
+    expect(isFull(50)).toBe(false);
+  });
+});
@@ -0,0 +1,14 @@
+// Showing how full a server is
+//
+// The backend learns a server's user limit from what the server says about it
+// and passes it along as `userLimit`. Counts read "14/50" when the limit is
+// known and just "14" when it isn't.
+
+export function formatOccupancy(users: number, userLimit?: number | null): string {
+  return userLimit ? `${users}/${userLimit}` : `${users}`;
+}
+
+/** True once a server with a known limit has no free slots */
+export function isFull(users: number, userLimit?: number | null): boolean {
+  return !!userLimit && users >= userLimit;
+}
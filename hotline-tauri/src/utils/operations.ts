@@ -0,0 +1,66 @@
+// Background operations
+//
+// Transfer commands (download_file, upload_file, resume_download) return an
+// operation id right away and report through `operation-progress-{id}` and
+// `operation-finished-{id}` events. runOperation wraps that in a promise that
+// settles when the operation finishes.
+
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+
+export interface OperationProgress {
+  operationId: string;
+  bytes: number;
+  totalBytes: number;
+  progress: number;
+}
+
+interface OperationFinished {
+  operationId: string;
+  result?: string;
+  error?: string;
+}
+
+let operationCounter = 0;
+
+export function newOperationId(prefix = 'op'): string {
+  operationCounter += 1;
+  return `${prefix}-${Date.now()}-${operationCounter}`;
+}
+
+// Start an operation and wait for it to finish. The id is chosen up front so
+// the listeners are in place before the backend can emit anything, and so the
+// caller can pass it to cancel_operation while the promise is pending.
+export async function runOperation(
+  command: string,
+  args: Record<string, unknown>,
+  options: { operationId?: string; onProgress?: (progress: OperationProgress) => void } = {}
+): Promise<string> {
+  const operationId = options.operationId ?? newOperationId(command);
+
+  let settle: (payload: OperationFinished) => void = () => {};
+  const finished = new Promise<OperationFinished>((resolve) => {
+    settle = resolve;
+  });
+
+  const unlistenFinished = await listen<OperationFinished>(`operation-finished-${operationId}`, (event) => {
+    settle(event.payload);
+  });
+  const unlistenProgress = options.onProgress
+    ? await listen<OperationProgress>(`operation-progress-${operationId}`, (event) => {
+        options.onProgress?.(event.payload);
+      })
+    : null;
+
+  try {
+    await invoke<string>(command, { ...args, operationId });
+    const payload = await finished;
+    if (payload.error !== undefined) {
+      throw payload.error;
+    }
+    return payload.result ?? '';
+  } finally {
+    unlistenFinished();
+    unlistenProgress?.();
+  }
+}
@@ -0,0 +1,17 @@
+import { describe, it, expect } from 'vitest';
+import { formatTimestampIn } from './time';
+
+describe('formatTimestampIn', () => {
+  it('uses the chosen time zone', () => {
+    // 2024-01-01 00:30 UTC is still New Year's Eve in New York
+    const secs = Date.UTC(2024, 0, 1, 0, 30) / 1000;
+    expect(formatTimestampIn(secs, 'dateTime', 'UTC', 'en-US')).toContain('Jan 1, 2024');
+    expect(formatTimestampIn(secs, 'dateTime', 'America/New_York', 'en-US')).toContain('Dec 31, 2023');
+  });
+
+  it('falls back to the system settings for unknown zones', () => {
+    expect(formatTimestampIn(0, 'time', 'Not/AZone', null)).toBe(
+      new Intl.DateTimeFormat(undefined, { timeStyle: 'medium' }).format(new Date(0))
+    );
+  });
+});
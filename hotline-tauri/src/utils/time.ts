@@ -0,0 +1,39 @@
+// Formatting timestamps
+//
+// Chat logs, message history, caches and news dates all arrive from the
+// backend as Unix seconds in UTC. Everything shown or exported goes through
+// here, so it follows the time zone and locale chosen in the preferences
+// (the system's when none is chosen) instead of whatever the machine uses.
+
+import { usePreferencesStore } from '../stores/preferencesStore';
+
+export type TimestampStyle = 'dateTime' | 'time';
+
+const STYLES: Record<TimestampStyle, Intl.DateTimeFormatOptions> = {
+  dateTime: { dateStyle: 'medium', timeStyle: 'medium' },
+  time: { timeStyle: 'medium' },
+};
+
+/**
+ * Format Unix seconds in the given time zone and locale, falling back to the
+ * system's for any the runtime doesn't know
+ */
+export function formatTimestampIn(
+  secs: number,
+  style: TimestampStyle,
+  timeZone: string | null,
+  locale: string | null
+): string {
+  const date = new Date(secs * 1000);
+  try {
+    return new Intl.DateTimeFormat(locale ?? undefined, { ...STYLES[style], timeZone: timeZone ?? undefined }).format(date);
+  } catch {
+    return new Intl.DateTimeFormat(undefined, STYLES[style]).format(date);
+  }
+}
+
+/** Format Unix seconds with the time zone and locale from the preferences */
+export function formatTimestamp(secs: number, style: TimestampStyle = 'dateTime'): string {
+  const { timeZone, locale } = usePreferencesStore.getState();
+  return formatTimestampIn(secs, style, timeZone, locale);
+}